@@ -76,7 +76,7 @@ if not functions -q __cossh_completion_bin
                 case '-P' '--profile' '--pass-entry'
                     set expect_value 1
                 case '--profile=*' '--pass-entry=*'
-                case 'ssh' 'rdp' 'vault'
+                case 'ssh' 'rdp' 'vault' 'inventory'
                     echo "$token"
                     return 0
                 case '-*'
@@ -126,7 +126,7 @@ if not functions -q __cossh_completion_bin
 
             if test $seen_vault -eq 1
                 switch "$token"
-                    case init add remove list unlock lock status set-master-password
+                    case init add add-key remove rename list unlock lock status get exec set-master-password audit doctor rekey rotate policy recovery-key recover keyfile ssh-agent
                         return 1
                     case '*'
                         return 0
@@ -195,6 +195,26 @@ if not functions -q __cossh_completion_bin
 
         return 1
     end
+
+    function __cossh_subcommand_action --argument-names subcommand
+        set -l tokens (commandline -opc)
+        set -l index (contains -i -- $subcommand $tokens); or return 1
+        set -l action_index (math $index + 1)
+        test $action_index -le (count $tokens); or return 1
+        echo $tokens[$action_index]
+    end
+
+    function __cossh_need_inventory_action
+        __cossh_seen_subcommand inventory; or return 1
+        set -l action (__cossh_subcommand_action inventory)
+        test -z "$action"
+    end
+
+    function __cossh_inventory_action --argument-names action
+        __cossh_seen_subcommand inventory; or return 1
+        set -l current (__cossh_subcommand_action inventory)
+        test "$current" = "$action"
+    end
 end
 
 set -l __cossh_no_subcommand "__cossh_use_subcommand"
@@ -213,7 +233,7 @@ complete -c cossh -n "$__cossh_no_subcommand" -s P -l profile -x -a "(__cossh_pr
 complete -c cossh -n "$__cossh_no_subcommand" -s t -l test -d "Ignore config logging settings; only use CLI -d/-l logging flags"
 complete -c cossh -n "$__cossh_no_subcommand" -l pass-entry -r -xa "(__cossh_vault_entries)" -d "Override the direct-launch password vault entry"
 complete -c cossh -n "$__cossh_no_subcommand" -l migrate -d "Migrate ~/.ssh/config host entries into ~/.color-ssh/cossh-inventory.yaml"
-complete -c cossh -n "$__cossh_no_subcommand" -a "ssh rdp vault"
+complete -c cossh -n "$__cossh_no_subcommand" -a "ssh rdp vault inventory"
 
 # `cossh ssh` host completions.
 complete -c cossh -n "__cossh_seen_subcommand ssh; and __cossh_current_token_not_option" -a "(__cossh_ssh_hosts)" -d "SSH inventory host"
@@ -225,6 +245,57 @@ complete -c cossh -n "__cossh_seen_subcommand rdp" -s p -l port -r -d "Override
 complete -c cossh -n "__cossh_seen_subcommand rdp; and __cossh_current_token_not_option" -a "(__cossh_rdp_hosts)" -d "RDP inventory host"
 
 # `cossh vault` action and argument completions.
-complete -c cossh -n "__cossh_need_vault_action" -a "init add remove list unlock lock status set-master-password"
+complete -c cossh -n "__cossh_need_vault_action" -a "init add add-key remove rename list unlock lock status get exec set-master-password audit doctor rekey rotate policy recovery-key recover keyfile ssh-agent"
 complete -c cossh -n "__cossh_vault_action remove" -a "(__cossh_vault_entries)" -d "Vault entry"
+complete -c cossh -n "__cossh_vault_action policy" -a "(__cossh_vault_entries)" -d "Vault entry"
+complete -c cossh -n "__cossh_vault_action rename" -a "(__cossh_vault_entries)" -d "Vault entry"
+complete -c cossh -n "__cossh_vault_action rename" -l update-inventory -d "Rewrite vault_pass/vault_key references in inventory files"
+complete -c cossh -n "__cossh_vault_action get; or __cossh_vault_action exec" -a "(__cossh_vault_entries)" -d "Vault entry"
+complete -c cossh -n "__cossh_vault_action get; or __cossh_vault_action exec" -s f -l field -x -a "password user otp" -d "Value to read"
+complete -c cossh -n "__cossh_vault_action get; or __cossh_vault_action exec" -l host -x -a "(__cossh_inventory_hosts all)" -d "Inventory host the value is for"
+complete -c cossh -n "__cossh_vault_action exec" -s e -l env -x -d "Environment variable that receives the value"
+complete -c cossh -n "__cossh_vault_action exec" -l stdin -d "Write the value to the command's stdin"
+complete -c cossh -n "__cossh_vault_action list; or __cossh_vault_action status" -l json -d "Print machine-readable JSON"
 complete -c cossh -n "__cossh_vault_action add" -f -d "Vault entry name"
+
+# `cossh inventory` action and argument completions.
+complete -c cossh -n "__cossh_need_inventory_action" -a "import" -d "Import hosts from another inventory format or SSH client"
+complete -c cossh -n "__cossh_inventory_action import" -l from -x -a "ansible putty mobaxterm remmina securecrt termius" -d "Format of the file to import"
+complete -c cossh -n "__cossh_inventory_action import" -s o -l output -r -F -d "Inventory file to write"
+complete -c cossh -n "__cossh_inventory_action import" -l folder -x -d "Folder for imported sessions"
+complete -c cossh -n "__cossh_inventory_action import" -l on-conflict -x -a "skip rename replace" -d "What to do with existing names"
+complete -c cossh -n "__cossh_inventory_action import" -l dry-run -d "Show the import without writing"
+complete -c cossh -n "__cossh_inventory_action import; and __cossh_current_token_not_option" -F -d "File or directory to import"
+complete -c cossh -n "__cossh_need_inventory_action" -a "export" -d "Export the inventory for ssh, scp, rsync, Ansible and other tools"
+complete -c cossh -n "__cossh_inventory_action export" -s f -l format -x -a "ssh-config ansible csv json" -d "Format to write"
+complete -c cossh -n "__cossh_inventory_action export" -s o -l output -r -F -d "File to write instead of standard output"
+complete -c cossh -n "__cossh_inventory_action export" -l split-includes -d "Write included inventory files to <output>.d/"
+complete -c cossh -n "__cossh_need_inventory_action" -a "sync" -d "Sync hosts both ways between ~/.ssh/config and the inventory"
+complete -c cossh -n "__cossh_inventory_action sync" -l ssh-config -r -F -d "OpenSSH config to sync instead of ~/.ssh/config"
+complete -c cossh -n "__cossh_inventory_action sync" -l prefer -x -a "ssh-config inventory" -d "Side that wins fields changed on both sides"
+complete -c cossh -n "__cossh_inventory_action sync" -l dry-run -d "Show the differences without writing either side"
+complete -c cossh -n "__cossh_need_inventory_action" -a "effective" -d "Show the SSH settings in effect for a host, like ssh -G"
+complete -c cossh -n "__cossh_inventory_action effective" -l ssh-config -r -F -d "OpenSSH config to read instead of ~/.ssh/config"
+complete -c cossh -n "__cossh_inventory_action effective" -l no-exec -d "Do not run Match exec commands"
+complete -c cossh -n "__cossh_inventory_action effective; and __cossh_current_token_not_option" -f -a "(__cossh_ssh_hosts)" -d "SSH inventory host"
+complete -c cossh -n "__cossh_need_inventory_action" -a "list" -d "List inventory hosts"
+complete -c cossh -n "__cossh_inventory_action list" -l folder -x -d "Only list hosts in this folder"
+complete -c cossh -n "__cossh_inventory_action list" -l protocol -x -a "ssh rdp" -d "Only list hosts using this protocol"
+complete -c cossh -n "__cossh_inventory_action list" -s f -l format -x -a "table json" -d "Output format"
+complete -c cossh -n "__cossh_need_inventory_action" -a "show" -d "Show the resolved settings of an inventory host"
+complete -c cossh -n "__cossh_inventory_action show" -l json -d "Print machine-readable JSON"
+complete -c cossh -n "__cossh_inventory_action show; and __cossh_current_token_not_option" -f -a "(__cossh_inventory_hosts all)" -d "Inventory host"
+complete -c cossh -n "__cossh_need_inventory_action" -a "add" -d "Add a host to the inventory"
+complete -c cossh -n "__cossh_inventory_action add" -l folder -x -d "Folder for the host"
+complete -c cossh -n "__cossh_need_inventory_action" -a "set" -d "Change fields of an inventory host"
+complete -c cossh -n "__cossh_inventory_action set; and __cossh_current_token_not_option" -f -a "(__cossh_inventory_hosts all)" -d "Inventory host"
+complete -c cossh -n "__cossh_inventory_action add; or __cossh_inventory_action set" -f -a "name= host= user= port= protocol= description= profile= template= vault_pass= vault_key= hidden= identity_file= proxy_jump= local_forward= remote_forward= rdp_domain= rdp_args=" -d "Host field"
+complete -c cossh -n "__cossh_need_inventory_action" -a "rm" -d "Remove hosts from the inventory"
+complete -c cossh -n "__cossh_inventory_action rm" -f -a "(__cossh_inventory_hosts all)" -d "Inventory host"
+complete -c cossh -n "__cossh_need_inventory_action" -a "mv" -d "Move an inventory host to another folder"
+complete -c cossh -n "__cossh_inventory_action mv" -f -a "(__cossh_inventory_hosts all)" -d "Inventory host"
+complete -c cossh -n "__cossh_need_inventory_action" -a "mkdir" -d "Create an inventory folder and any missing parents"
+complete -c cossh -n "__cossh_inventory_action mkdir" -f
+complete -c cossh -n "__cossh_need_inventory_action" -a "check" -d "Check the inventory for problems"
+complete -c cossh -n "__cossh_inventory_action check" -l strict -d "Also exit non-zero on warnings"
+complete -c cossh -n "__cossh_inventory_action check" -f
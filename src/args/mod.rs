@@ -7,22 +7,367 @@ mod ssh;
 mod validation;
 
 pub use ssh::{extract_destination_host, is_non_interactive_ssh_invocation};
-pub(crate) use validation::{parse_profile_name, parse_vault_entry_name, validate_profile_name, validate_vault_entry_name};
+pub(crate) use validation::{
+    parse_env_var_name, parse_field_assignment, parse_profile_name, parse_secret_reference, parse_vault_entry_name, parse_vault_entry_user,
+    validate_profile_name, validate_secret_reference, validate_vault_entry_name,
+};
 
 use clap::{Arg, Command, error::ErrorKind};
 use std::ffi::OsString;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// Supported `cossh vault` subcommands.
 pub enum VaultCommand {
     Init,
-    AddPass(String),
+    AddPass(VaultAddPassArgs),
+    AddKey(VaultAddKeyArgs),
     RemovePass(String),
-    List,
+    Rename(VaultRenameArgs),
+    /// List entries; `true` prints JSON.
+    List(bool),
     Unlock,
     Lock,
-    Status,
+    /// Show agent status; `true` prints JSON.
+    Status(bool),
+    Get(VaultGetArgs),
+    Exec(VaultExecArgs),
     SetMasterPassword,
+    /// Show the audit log, optionally limited to the newest `n` records.
+    Audit(Option<usize>),
+    /// Check vault integrity; `true` applies safe repairs without asking.
+    Doctor(bool),
+    Rekey(VaultRekeyArgs),
+    Rotate(String, VaultRotateSource),
+    Policy(VaultPolicyArgs),
+    /// Replace the recovery key.
+    RecoveryKey,
+    /// Reset the master password with the recovery key.
+    Recover,
+    Keyfile(VaultKeyfileAction),
+    SshAgent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// What `cossh vault keyfile` changes.
+pub enum VaultKeyfileAction {
+    /// Show the configured keyfile.
+    Show,
+    /// Require `path` in addition to the master password, creating it first when `generate` is set.
+    Set {
+        path: PathBuf,
+        generate: bool,
+    },
+    Remove,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Where `cossh vault rotate` takes the replacement value from.
+pub enum VaultRotateSource {
+    Generate(PasswordGeneratorArgs),
+    Prompt,
+    /// Restore the newest previous value.
+    Rollback,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Parsed password generator flags shared by `vault add` and `vault rotate`.
+pub struct PasswordGeneratorArgs {
+    pub length: Option<usize>,
+    pub no_lowercase: bool,
+    pub no_uppercase: bool,
+    pub no_digits: bool,
+    pub no_symbols: bool,
+    /// Limit symbols to characters network device CLIs accept unescaped.
+    pub device_safe: bool,
+    pub exclude_ambiguous: bool,
+    pub pronounceable: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Parsed arguments for `cossh vault add`.
+pub struct VaultAddPassArgs {
+    pub name: String,
+    /// Generate the secret instead of prompting for it.
+    pub generator: Option<PasswordGeneratorArgs>,
+    /// Login user stored with the entry for `vault get --field user`.
+    pub user: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Parsed arguments for `cossh vault add-key`.
+pub struct VaultAddKeyArgs {
+    pub name: String,
+    /// OpenSSH private key file to import.
+    pub path: PathBuf,
+    /// Overwrite and remove the key file after a successful import.
+    pub shred: bool,
+    /// Ask through `SSH_ASKPASS` before each signature.
+    pub confirm: bool,
+    /// Stop serving the key this many seconds after unlock.
+    pub lifetime_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Parsed arguments for `cossh vault rename`.
+pub struct VaultRenameArgs {
+    pub name: String,
+    pub new_name: String,
+    /// Also rewrite matching `vault_pass`/`vault_key` values in the inventory files.
+    pub update_inventory: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Value `cossh vault get` and `cossh vault exec` read from an entry.
+pub enum VaultSecretField {
+    #[default]
+    Password,
+    /// Login user stored on the entry with `cossh vault add --user`.
+    User,
+    /// Current TOTP code derived from the seed stored in the entry.
+    Otp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Parsed arguments for `cossh vault get`.
+pub struct VaultGetArgs {
+    pub name: String,
+    pub field: VaultSecretField,
+    /// Inventory host alias the value is requested for.
+    pub host: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Parsed arguments for `cossh vault exec`.
+pub struct VaultExecArgs {
+    pub name: String,
+    pub field: VaultSecretField,
+    pub host: Option<String>,
+    /// Environment variable that receives the value in the child.
+    pub env: String,
+    /// Write the value to the child's stdin instead of its environment.
+    pub stdin: bool,
+    /// Program and arguments to run.
+    pub command: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Parsed arguments for `cossh vault policy`.
+///
+/// Without any change flags the current policy is shown.
+pub struct VaultPolicyArgs {
+    pub name: String,
+    /// `Some(true)` for `--confirm`, `Some(false)` for `--no-confirm`.
+    pub confirm: Option<bool>,
+    /// Replacement host patterns from repeated `--allow-host`.
+    pub allowed_hosts: Vec<String>,
+    /// Remove every host restriction.
+    pub any_host: bool,
+    pub max_uses: Option<u32>,
+    /// Remove the usage limit.
+    pub unlimited_uses: bool,
+    /// Reset the policy before applying other flags.
+    pub clear: bool,
+}
+
+impl VaultPolicyArgs {
+    /// Returns `true` when the invocation only displays the policy.
+    pub fn is_query(&self) -> bool {
+        self.confirm.is_none() && self.allowed_hosts.is_empty() && !self.any_host && self.max_uses.is_none() && !self.unlimited_uses && !self.clear
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Supported `cossh inventory` subcommands.
+pub enum InventoryCommand {
+    Import(InventoryImportArgs),
+    Export(InventoryExportArgs),
+    Sync(InventorySyncArgs),
+    Effective(InventoryEffectiveArgs),
+    List(InventoryListArgs),
+    Show(InventoryShowArgs),
+    Add(InventoryAddArgs),
+    Set(InventorySetArgs),
+    Remove(InventoryRemoveArgs),
+    Move(InventoryMoveArgs),
+    Mkdir(InventoryMkdirArgs),
+    Check(InventoryCheckArgs),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// File formats `cossh inventory import` reads.
+pub enum InventoryImportFormat {
+    /// Ansible INI or YAML inventory.
+    Ansible,
+    /// PuTTY sessions exported from the registry.
+    Putty,
+    /// MobaXterm `.mxtsessions` export.
+    MobaXterm,
+    /// Remmina `.remmina` profiles.
+    Remmina,
+    /// SecureCRT XML export or session directory.
+    SecureCrt,
+    /// Termius CSV or JSON export.
+    Termius,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How `cossh inventory import` treats session names that already exist.
+pub enum InventoryImportConflict {
+    #[default]
+    Skip,
+    Rename,
+    Replace,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Parsed arguments for `cossh inventory import`.
+pub struct InventoryImportArgs {
+    pub format: InventoryImportFormat,
+    /// File or directory to import.
+    pub path: PathBuf,
+    /// Inventory file to write instead of the default inventory.
+    pub output: Option<PathBuf>,
+    /// `/`-separated folder for imported sessions; the client name when unset.
+    pub folder: Option<String>,
+    pub on_conflict: InventoryImportConflict,
+    /// Print the import plan without writing the inventory.
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Formats `cossh inventory export` writes.
+pub enum InventoryExportFormat {
+    SshConfig,
+    Ansible,
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Parsed arguments for `cossh inventory export`.
+pub struct InventoryExportArgs {
+    pub format: InventoryExportFormat,
+    /// File to write; standard output when unset.
+    pub output: Option<PathBuf>,
+    /// Write hosts from included inventory files to `<output>.d/` and `Include` them.
+    pub split_includes: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Side whose value `cossh inventory sync` keeps when a field changed on both.
+pub enum InventorySyncPreference {
+    SshConfig,
+    Inventory,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Parsed arguments for `cossh inventory sync`.
+pub struct InventorySyncArgs {
+    /// OpenSSH config to sync instead of `~/.ssh/config`.
+    pub ssh_config: Option<PathBuf>,
+    /// Settle conflicts without prompting.
+    pub prefer: Option<InventorySyncPreference>,
+    /// Print the differences without writing either side.
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Parsed arguments for `cossh inventory effective`.
+pub struct InventoryEffectiveArgs {
+    /// Inventory host name, or `[user@]host` for a destination only in the SSH config.
+    pub target: String,
+    /// OpenSSH config to read instead of `~/.ssh/config` and the system-wide config.
+    pub ssh_config: Option<PathBuf>,
+    /// Treat `Match exec` blocks as not matching instead of running their commands.
+    pub no_exec: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Output formats of `cossh inventory list`.
+pub enum InventoryListFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Parsed arguments for `cossh inventory list`.
+pub struct InventoryListArgs {
+    /// `/`-separated folder whose hosts, including subfolders, are listed.
+    pub folder: Option<String>,
+    pub protocol: Option<String>,
+    pub format: InventoryListFormat,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Parsed arguments for `cossh inventory show`.
+pub struct InventoryShowArgs {
+    pub name: String,
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Parsed arguments for `cossh inventory add`.
+pub struct InventoryAddArgs {
+    pub name: String,
+    /// `key=value` host fields.
+    pub fields: Vec<(String, String)>,
+    /// `/`-separated folder for the host; the root when unset.
+    pub folder: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Parsed arguments for `cossh inventory set`.
+pub struct InventorySetArgs {
+    pub name: String,
+    /// `key=value` host fields; an empty value removes the field.
+    pub fields: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Parsed arguments for `cossh inventory rm`.
+pub struct InventoryRemoveArgs {
+    pub names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Parsed arguments for `cossh inventory mv`.
+pub struct InventoryMoveArgs {
+    pub name: String,
+    /// `/`-separated target folder; `/` is the root.
+    pub folder: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Parsed arguments for `cossh inventory mkdir`.
+pub struct InventoryMkdirArgs {
+    /// `/`-separated folder to create, with missing parents.
+    pub folder: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Parsed arguments for `cossh inventory check`.
+pub struct InventoryCheckArgs {
+    /// Inventory file to check instead of the default inventory.
+    pub path: Option<PathBuf>,
+    /// Exit non-zero on warnings as well as errors.
+    pub strict: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Parsed arguments for `cossh vault rekey`.
+pub struct VaultRekeyArgs {
+    /// Target unlock time in milliseconds for KDF calibration.
+    pub calibrate_ms: Option<u64>,
+    /// Explicit Argon2id memory cost in KiB.
+    pub memory_kib: Option<u32>,
+    /// Explicit Argon2id iteration count.
+    pub time_cost: Option<u32>,
+    /// Explicit Argon2id lane count.
+    pub parallelism: Option<u32>,
+    /// Generate a new data encryption key and re-encrypt all entries.
+    pub rotate_data_key: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -61,6 +406,7 @@ pub enum ProtocolCommand {
 pub enum MainCommand {
     Protocol(ProtocolCommand),
     Vault(VaultCommand),
+    Inventory(InventoryCommand),
     MigrateInventory,
     CompletionHosts(CompletionProtocol),
     AgentServe,
@@ -132,10 +478,10 @@ fn build_cli_command() -> Command {
         .arg(
             Arg::new("pass_entry")
                 .long("pass-entry")
-                .help("Override the optional password vault entry used for a direct protocol launch")
+                .help("Override the optional password vault entry or provider reference (e.g. pass:network/core-sw) used for a direct protocol launch")
                 .num_args(1)
                 .value_name("name")
-                .value_parser(clap::builder::ValueParser::new(parse_vault_entry_name)),
+                .value_parser(clap::builder::ValueParser::new(parse_secret_reference)),
         )
         .arg(
             Arg::new("migrate")
@@ -185,12 +531,65 @@ fn build_cli_command() -> Command {
                 .arg_required_else_help(true)
                 .subcommand(Command::new("init").about("Initialize the password vault"))
                 .subcommand(
-                    Command::new("add").about("Create or replace a password vault entry interactively").arg(
-                        Arg::new("name")
-                            .help("Password entry name")
-                            .required(true)
-                            .value_parser(clap::builder::ValueParser::new(parse_vault_entry_name)),
-                    ),
+                    Command::new("add")
+                        .about("Create or replace a password vault entry interactively")
+                        .arg(
+                            Arg::new("name")
+                                .help("Password entry name")
+                                .required(true)
+                                .value_parser(clap::builder::ValueParser::new(parse_vault_entry_name)),
+                        )
+                        .arg(
+                            Arg::new("generate")
+                                .long("generate")
+                                .short('g')
+                                .help("Generate the password instead of prompting for it")
+                                .action(clap::ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("user")
+                                .long("user")
+                                .short('u')
+                                .value_name("name")
+                                .help("Login user stored with the entry; kept when an entry is replaced without it")
+                                .value_parser(clap::builder::ValueParser::new(parse_vault_entry_user)),
+                        )
+                        .args(password_generator_args().into_iter().map(|arg| arg.requires("generate"))),
+                )
+                .subcommand(
+                    Command::new("add-key")
+                        .about("Store an SSH private key file in the vault for the vault SSH agent")
+                        .arg(
+                            Arg::new("name")
+                                .help("Key entry name")
+                                .required(true)
+                                .value_parser(clap::builder::ValueParser::new(parse_vault_entry_name)),
+                        )
+                        .arg(
+                            Arg::new("path")
+                                .help("OpenSSH private key file")
+                                .required(true)
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("shred")
+                                .long("shred")
+                                .help("Overwrite and delete the key file after it is stored")
+                                .action(clap::ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("confirm")
+                                .long("confirm")
+                                .help("Ask for confirmation before every use of the key")
+                                .action(clap::ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("lifetime")
+                                .long("lifetime")
+                                .value_name("seconds")
+                                .help("Only serve the key for <seconds> after it is added")
+                                .value_parser(clap::value_parser!(u64).range(1..)),
+                        ),
                 )
                 .subcommand(
                     Command::new("remove").about("Remove a password vault entry").arg(
@@ -200,11 +599,437 @@ fn build_cli_command() -> Command {
                             .value_parser(clap::builder::ValueParser::new(parse_vault_entry_name)),
                     ),
                 )
-                .subcommand(Command::new("list").about("List password vault entries"))
+                .subcommand(
+                    Command::new("rename")
+                        .about("Rename a password vault entry, re-encrypting it under the new name")
+                        .arg(
+                            Arg::new("name")
+                                .help("Current entry name")
+                                .required(true)
+                                .value_parser(clap::builder::ValueParser::new(parse_vault_entry_name)),
+                        )
+                        .arg(
+                            Arg::new("new_name")
+                                .help("New entry name")
+                                .required(true)
+                                .value_parser(clap::builder::ValueParser::new(parse_vault_entry_name)),
+                        )
+                        .arg(
+                            Arg::new("update-inventory")
+                                .long("update-inventory")
+                                .help("Rewrite `vault_pass`/`vault_key` references in every included inventory file")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(Command::new("list").about("List password vault entries").arg(json_output_arg()))
                 .subcommand(Command::new("unlock").about("Unlock the shared password vault"))
                 .subcommand(Command::new("lock").about("Lock the shared password vault"))
-                .subcommand(Command::new("status").about("Show shared password vault status"))
-                .subcommand(Command::new("set-master-password").about("Create or rotate the password vault master password")),
+                .subcommand(Command::new("status").about("Show shared password vault status").arg(json_output_arg()))
+                .subcommand(
+                    Command::new("get")
+                        .about("Print one value of a password vault entry from the unlocked vault")
+                        .args(vault_secret_args()),
+                )
+                .subcommand(
+                    Command::new("exec")
+                        .about("Run a command with one value of a password vault entry in its environment or stdin")
+                        .args(vault_secret_args())
+                        .arg(
+                            Arg::new("env")
+                                .long("env")
+                                .short('e')
+                                .value_name("VAR")
+                                .default_value(DEFAULT_VAULT_EXEC_ENV)
+                                .help("Environment variable that receives the value")
+                                .value_parser(clap::builder::ValueParser::new(parse_env_var_name)),
+                        )
+                        .arg(
+                            Arg::new("stdin")
+                                .long("stdin")
+                                .help("Write the value to the command's stdin instead of its environment")
+                                .action(clap::ArgAction::SetTrue)
+                                .conflicts_with("env"),
+                        )
+                        .arg(Arg::new("command").help("Command to run, after `--`").required(true).num_args(1..).last(true)),
+                )
+                .subcommand(Command::new("set-master-password").about("Create or rotate the password vault master password"))
+                .subcommand(
+                    Command::new("audit").about("Show and verify the password vault audit log").arg(
+                        Arg::new("tail")
+                            .long("tail")
+                            .short('n')
+                            .value_name("count")
+                            .help("Only show the newest <count> records")
+                            .value_parser(clap::value_parser!(usize)),
+                    ),
+                )
+                .subcommand(
+                    Command::new("doctor").about("Check password vault integrity and offer safe repairs").arg(
+                        Arg::new("repair")
+                            .long("repair")
+                            .help("Apply safe repairs without prompting")
+                            .action(clap::ArgAction::SetTrue),
+                    ),
+                )
+                .subcommand(
+                    Command::new("rekey")
+                        .about("Re-wrap the vault key with new Argon2id parameters")
+                        .arg(
+                            Arg::new("calibrate")
+                                .long("calibrate")
+                                .value_name("ms")
+                                .num_args(0..=1)
+                                .default_missing_value("500")
+                                .help("Raise the time cost until unlocking takes about <ms> milliseconds (default 500)")
+                                .value_parser(clap::value_parser!(u64).range(1..=60_000)),
+                        )
+                        .arg(
+                            Arg::new("memory-kib")
+                                .long("memory-kib")
+                                .value_name("KiB")
+                                .help("Argon2id memory cost in KiB")
+                                .value_parser(clap::value_parser!(u32)),
+                        )
+                        .arg(
+                            Arg::new("time-cost")
+                                .long("time-cost")
+                                .value_name("passes")
+                                .help("Argon2id time cost (iterations)")
+                                .value_parser(clap::value_parser!(u32)),
+                        )
+                        .arg(
+                            Arg::new("parallelism")
+                                .long("parallelism")
+                                .value_name("lanes")
+                                .help("Argon2id parallelism")
+                                .value_parser(clap::value_parser!(u32)),
+                        )
+                        .arg(
+                            Arg::new("rotate-key")
+                                .long("rotate-key")
+                                .help("Also generate a new data encryption key and re-encrypt every entry")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("rotate")
+                        .about("Replace a password vault entry with a generated password, keeping the old value as history")
+                        .arg(
+                            Arg::new("name")
+                                .help("Password entry name")
+                                .required(true)
+                                .value_parser(clap::builder::ValueParser::new(parse_vault_entry_name)),
+                        )
+                        .arg(
+                            Arg::new("prompt")
+                                .long("prompt")
+                                .help("Prompt for the new password instead of generating it")
+                                .action(clap::ArgAction::SetTrue)
+                                .conflicts_with_all(PASSWORD_GENERATOR_ARG_IDS),
+                        )
+                        .arg(
+                            Arg::new("rollback")
+                                .long("rollback")
+                                .help("Restore the previous password from the entry history")
+                                .action(clap::ArgAction::SetTrue)
+                                .conflicts_with("prompt")
+                                .conflicts_with_all(PASSWORD_GENERATOR_ARG_IDS),
+                        )
+                        .args(password_generator_args()),
+                )
+                .subcommand(
+                    Command::new("policy")
+                        .about("Show or change when the agent may release a password vault entry")
+                        .arg(
+                            Arg::new("name")
+                                .help("Password entry name")
+                                .required(true)
+                                .value_parser(clap::builder::ValueParser::new(parse_vault_entry_name)),
+                        )
+                        .arg(
+                            Arg::new("confirm")
+                                .long("confirm")
+                                .help("Have the agent ask through SSH_ASKPASS before every release; releases are refused without a helper")
+                                .action(clap::ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("no-confirm")
+                                .long("no-confirm")
+                                .help("Release without asking for confirmation")
+                                .action(clap::ArgAction::SetTrue)
+                                .conflicts_with("confirm"),
+                        )
+                        .arg(
+                            Arg::new("allow-host")
+                                .long("allow-host")
+                                .value_name("pattern")
+                                .help("Only release to host aliases or destinations matching <pattern> (`*` and `?` wildcards; repeatable)")
+                                .action(clap::ArgAction::Append),
+                        )
+                        .arg(
+                            Arg::new("any-host")
+                                .long("any-host")
+                                .help("Remove host restrictions")
+                                .action(clap::ArgAction::SetTrue)
+                                .conflicts_with("allow-host"),
+                        )
+                        .arg(
+                            Arg::new("max-uses")
+                                .long("max-uses")
+                                .value_name("count")
+                                .help("Release the entry at most <count> times per unlock session")
+                                .value_parser(clap::value_parser!(u32).range(1..)),
+                        )
+                        .arg(
+                            Arg::new("unlimited-uses")
+                                .long("unlimited-uses")
+                                .help("Remove the usage limit")
+                                .action(clap::ArgAction::SetTrue)
+                                .conflicts_with("max-uses"),
+                        )
+                        .arg(
+                            Arg::new("clear")
+                                .long("clear")
+                                .help("Reset the policy before applying other flags")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(Command::new("recovery-key").about("Replace the password vault recovery key"))
+                .subcommand(Command::new("recover").about("Reset the vault master password with the recovery key"))
+                .subcommand(
+                    Command::new("keyfile")
+                        .about("Show, require or remove a keyfile combined with the vault master password")
+                        .arg(
+                            Arg::new("path")
+                                .help("Keyfile to require when unlocking")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("generate")
+                                .long("generate")
+                                .help("Create a new random keyfile at <path> first")
+                                .action(clap::ArgAction::SetTrue)
+                                .requires("path"),
+                        )
+                        .arg(
+                            Arg::new("remove")
+                                .long("remove")
+                                .help("Stop requiring a keyfile")
+                                .action(clap::ArgAction::SetTrue)
+                                .conflicts_with_all(["path", "generate"]),
+                        ),
+                )
+                .subcommand(Command::new("ssh-agent").about("Print the SSH_AUTH_SOCK export for the vault SSH agent socket")),
+        )
+        .subcommand(
+            Command::new("inventory")
+                .about("Manage, check, import, export, sync and inspect hosts of the YAML inventory")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("import")
+                        .about("Import hosts from another inventory format or SSH client")
+                        .arg(
+                            Arg::new("from")
+                                .long("from")
+                                .value_name("format")
+                                .help("Format of the file to import")
+                                .required(true)
+                                .value_parser(["ansible", "putty", "mobaxterm", "remmina", "securecrt", "termius"]),
+                        )
+                        .arg(
+                            Arg::new("path")
+                                .help("File or directory to import")
+                                .required(true)
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("output")
+                                .long("output")
+                                .short('o')
+                                .value_name("path")
+                                .help("Inventory file to write instead of ~/.color-ssh/cossh-inventory.yaml (Ansible: instead of a new ansible-<name>.yaml include)")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("folder")
+                                .long("folder")
+                                .value_name("path")
+                                .help("Folder for imported sessions, such as Clients/PuTTY; defaults to the client name, '/' is the root"),
+                        )
+                        .arg(
+                            Arg::new("on-conflict")
+                                .long("on-conflict")
+                                .value_name("action")
+                                .help("What to do with sessions whose name already exists")
+                                .default_value("skip")
+                                .value_parser(["skip", "rename", "replace"]),
+                        )
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .help("Show what would be imported without writing the inventory")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("export")
+                        .about("Export the inventory for ssh, scp, rsync, Ansible and other tools")
+                        .arg(
+                            Arg::new("format")
+                                .long("format")
+                                .short('f')
+                                .value_name("format")
+                                .help("Format to write")
+                                .required(true)
+                                .value_parser(["ssh-config", "ansible", "csv", "json"]),
+                        )
+                        .arg(
+                            Arg::new("output")
+                                .long("output")
+                                .short('o')
+                                .value_name("path")
+                                .help("File to write instead of standard output; an existing file is backed up")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("split-includes")
+                                .long("split-includes")
+                                .help("Write hosts from included inventory files to <output>.d/ and Include them (ssh-config only)")
+                                .requires("output")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("sync")
+                        .about("Sync hosts both ways between ~/.ssh/config and the inventory")
+                        .arg(
+                            Arg::new("ssh-config")
+                                .long("ssh-config")
+                                .value_name("path")
+                                .help("OpenSSH config to sync instead of ~/.ssh/config")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("prefer")
+                                .long("prefer")
+                                .value_name("side")
+                                .help("Keep this side's value for fields changed on both sides instead of prompting")
+                                .value_parser(["ssh-config", "inventory"]),
+                        )
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .help("Show the differences without writing either side")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("effective")
+                        .about("Show the SSH settings in effect for a host, like `ssh -G`")
+                        .arg(Arg::new("target").help("Inventory host name or [user@]host").required(true))
+                        .arg(
+                            Arg::new("ssh-config")
+                                .long("ssh-config")
+                                .value_name("path")
+                                .help("OpenSSH config to read instead of ~/.ssh/config and /etc/ssh/ssh_config")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("no-exec")
+                                .long("no-exec")
+                                .help("Do not run `Match exec` commands; treat those blocks as not matching")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("list")
+                        .about("List inventory hosts")
+                        .arg(
+                            Arg::new("folder")
+                                .long("folder")
+                                .value_name("path")
+                                .help("Only list hosts in this folder and its subfolders, such as Prod/Web"),
+                        )
+                        .arg(
+                            Arg::new("protocol")
+                                .long("protocol")
+                                .value_name("protocol")
+                                .help("Only list hosts using this protocol, such as ssh or rdp"),
+                        )
+                        .arg(
+                            Arg::new("format")
+                                .long("format")
+                                .short('f')
+                                .value_name("format")
+                                .help("Output format")
+                                .default_value("table")
+                                .value_parser(["table", "json"]),
+                        ),
+                )
+                .subcommand(
+                    Command::new("show")
+                        .about("Show the resolved settings of an inventory host")
+                        .arg(Arg::new("name").help("Inventory host name").required(true))
+                        .arg(json_output_arg()),
+                )
+                .subcommand(
+                    Command::new("add")
+                        .about("Add a host to the inventory")
+                        .arg(Arg::new("name").help("Inventory host name").required(true))
+                        .arg(inventory_field_arg().help("Host fields such as host=10.0.0.5 user=deploy port=2222"))
+                        .arg(
+                            Arg::new("folder")
+                                .long("folder")
+                                .value_name("path")
+                                .help("Folder for the host, such as Prod/Web; missing folders are created"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("set")
+                        .about("Change fields of an inventory host")
+                        .arg(Arg::new("name").help("Inventory host name").required(true))
+                        .arg(
+                            inventory_field_arg()
+                                .required(true)
+                                .help("Fields to change such as user=deploy; key= removes the field and name= renames the host"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("rm").about("Remove hosts from the inventory").arg(
+                        Arg::new("name")
+                            .help("Inventory host names")
+                            .required(true)
+                            .num_args(1..)
+                            .action(clap::ArgAction::Append),
+                    ),
+                )
+                .subcommand(
+                    Command::new("mv")
+                        .about("Move an inventory host to another folder")
+                        .arg(Arg::new("name").help("Inventory host name").required(true))
+                        .arg(Arg::new("folder").help("Target folder such as Prod/Web; '/' is the root").required(true)),
+                )
+                .subcommand(
+                    Command::new("mkdir")
+                        .about("Create an inventory folder and any missing parents")
+                        .arg(Arg::new("folder").help("Folder to create, such as Prod/Web").required(true)),
+                )
+                .subcommand(
+                    Command::new("check")
+                        .about("Check the inventory for problems and exit non-zero if any are errors")
+                        .arg(
+                            Arg::new("path")
+                                .help("Inventory file to check instead of ~/.color-ssh/cossh-inventory.yaml")
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("strict")
+                                .long("strict")
+                                .help("Also exit non-zero when only warnings are found")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                ),
         )
         .subcommand(
             Command::new("agent")
@@ -239,10 +1064,108 @@ cossh -l ssh user@host -p 2222                            # Both modes with SSH
 cossh ssh user@host -G                                    # Non-interactive command
 cossh rdp desktop01                                       # Launch a configured RDP host
 cossh --migrate                                           # Import ~/.ssh/config into the YAML inventory
+cossh inventory import --from ansible hosts.ini          # Import an Ansible inventory
+cossh inventory import --from putty putty.reg --dry-run  # Preview importing PuTTY sessions
+cossh inventory export --format ssh-config -o ~/.ssh/cossh.conf  # Export hosts for ssh/scp/rsync
+cossh inventory sync --dry-run                            # Compare ~/.ssh/config with the inventory
+cossh inventory effective web01                           # Show the SSH settings used for a host
+cossh inventory add web02 host=10.0.0.12 --folder Prod/Web  # Add a host from a script
+cossh inventory check                                     # Lint the inventory, e.g. in CI
 ",
         )
 }
 
+const PASSWORD_GENERATOR_ARG_IDS: [&str; 8] = [
+    "length",
+    "no-lowercase",
+    "no-uppercase",
+    "no-digits",
+    "no-symbols",
+    "device-safe",
+    "exclude-ambiguous",
+    "pronounceable",
+];
+
+fn password_generator_args() -> Vec<Arg> {
+    let flag = |id: &'static str, help: &'static str| Arg::new(id).long(id).help(help).action(clap::ArgAction::SetTrue);
+    vec![
+        Arg::new("length")
+            .long("length")
+            .short('L')
+            .value_name("chars")
+            .help("Generated password length (default 20)")
+            .value_parser(clap::value_parser!(u16).range(4..=256)),
+        flag("no-lowercase", "Leave lowercase letters out of the generated password"),
+        flag("no-uppercase", "Leave uppercase letters out of the generated password"),
+        flag("no-digits", "Leave digits out of the generated password"),
+        flag("no-symbols", "Leave symbols out of the generated password").conflicts_with("device-safe"),
+        flag("device-safe", "Only use symbols that network device CLIs accept unescaped (-_.+=@%)"),
+        flag("exclude-ambiguous", "Leave out easily confused characters such as 0/O and 1/l"),
+        flag("pronounceable", "Generate a pronounceable password from consonant-vowel syllables"),
+    ]
+}
+
+/// Environment variable `cossh vault exec` uses when `--env` is not given.
+pub const DEFAULT_VAULT_EXEC_ENV: &str = "COSSH_SECRET";
+
+/// Repeated `key=value` host fields of `inventory add` and `inventory set`.
+fn inventory_field_arg() -> Arg {
+    Arg::new("fields")
+        .value_name("key=value")
+        .num_args(1..)
+        .action(clap::ArgAction::Append)
+        .value_parser(clap::builder::ValueParser::new(parse_field_assignment))
+}
+
+fn json_output_arg() -> Arg {
+    Arg::new("json")
+        .long("json")
+        .help("Print machine-readable JSON")
+        .action(clap::ArgAction::SetTrue)
+}
+
+/// Entry, field and host arguments shared by `vault get` and `vault exec`.
+fn vault_secret_args() -> Vec<Arg> {
+    vec![
+        Arg::new("name")
+            .help("Password entry name")
+            .required(true)
+            .value_parser(clap::builder::ValueParser::new(parse_vault_entry_name)),
+        Arg::new("field")
+            .long("field")
+            .short('f')
+            .value_name("field")
+            .default_value("password")
+            .help("Value to read: the password, the inventory login user, or a TOTP code from a stored seed")
+            .value_parser(["password", "user", "otp"]),
+        Arg::new("host")
+            .long("host")
+            .value_name("alias")
+            .help("Inventory host the value is for; checked against the entry policy"),
+    ]
+}
+
+fn parse_vault_secret_field(value: &str) -> VaultSecretField {
+    match value {
+        "user" => VaultSecretField::User,
+        "otp" => VaultSecretField::Otp,
+        _ => VaultSecretField::Password,
+    }
+}
+
+fn parse_password_generator_args(matches: &clap::ArgMatches) -> PasswordGeneratorArgs {
+    PasswordGeneratorArgs {
+        length: matches.get_one::<u16>("length").map(|length| usize::from(*length)),
+        no_lowercase: matches.get_flag("no-lowercase"),
+        no_uppercase: matches.get_flag("no-uppercase"),
+        no_digits: matches.get_flag("no-digits"),
+        no_symbols: matches.get_flag("no-symbols"),
+        device_safe: matches.get_flag("device-safe"),
+        exclude_ambiguous: matches.get_flag("exclude-ambiguous"),
+        pronounceable: matches.get_flag("pronounceable"),
+    }
+}
+
 fn parse_completion_protocol(value: &str) -> CompletionProtocol {
     match value.to_ascii_lowercase().as_str() {
         "ssh" => CompletionProtocol::Ssh,
@@ -279,17 +1202,195 @@ fn parse_ssh_command(ssh_matches: &clap::ArgMatches) -> Option<SshCommandArgs> {
 fn parse_vault_command(vault_matches: &clap::ArgMatches) -> Option<VaultCommand> {
     match vault_matches.subcommand() {
         Some(("init", _)) => Some(VaultCommand::Init),
-        Some(("add", add_pass_matches)) => add_pass_matches.get_one::<String>("name").cloned().map(VaultCommand::AddPass),
+        Some(("add", add_pass_matches)) => Some(VaultCommand::AddPass(VaultAddPassArgs {
+            name: add_pass_matches.get_one::<String>("name").cloned()?,
+            generator: add_pass_matches.get_flag("generate").then(|| parse_password_generator_args(add_pass_matches)),
+            user: add_pass_matches.get_one::<String>("user").cloned(),
+        })),
+        Some(("add-key", add_key_matches)) => Some(VaultCommand::AddKey(VaultAddKeyArgs {
+            name: add_key_matches.get_one::<String>("name").cloned()?,
+            path: add_key_matches.get_one::<PathBuf>("path").cloned()?,
+            shred: add_key_matches.get_flag("shred"),
+            confirm: add_key_matches.get_flag("confirm"),
+            lifetime_seconds: add_key_matches.get_one::<u64>("lifetime").copied(),
+        })),
         Some(("remove", remove_pass_matches)) => remove_pass_matches.get_one::<String>("name").cloned().map(VaultCommand::RemovePass),
-        Some(("list", _)) => Some(VaultCommand::List),
+        Some(("rename", rename_matches)) => Some(VaultCommand::Rename(VaultRenameArgs {
+            name: rename_matches.get_one::<String>("name").cloned()?,
+            new_name: rename_matches.get_one::<String>("new_name").cloned()?,
+            update_inventory: rename_matches.get_flag("update-inventory"),
+        })),
+        Some(("list", list_matches)) => Some(VaultCommand::List(list_matches.get_flag("json"))),
         Some(("unlock", _)) => Some(VaultCommand::Unlock),
         Some(("lock", _)) => Some(VaultCommand::Lock),
-        Some(("status", _)) => Some(VaultCommand::Status),
+        Some(("status", status_matches)) => Some(VaultCommand::Status(status_matches.get_flag("json"))),
+        Some(("get", get_matches)) => Some(VaultCommand::Get(VaultGetArgs {
+            name: get_matches.get_one::<String>("name").cloned()?,
+            field: get_matches
+                .get_one::<String>("field")
+                .map_or_else(VaultSecretField::default, |field| parse_vault_secret_field(field)),
+            host: get_matches.get_one::<String>("host").cloned(),
+        })),
+        Some(("exec", exec_matches)) => Some(VaultCommand::Exec(VaultExecArgs {
+            name: exec_matches.get_one::<String>("name").cloned()?,
+            field: exec_matches
+                .get_one::<String>("field")
+                .map_or_else(VaultSecretField::default, |field| parse_vault_secret_field(field)),
+            host: exec_matches.get_one::<String>("host").cloned(),
+            env: exec_matches.get_one::<String>("env").cloned()?,
+            stdin: exec_matches.get_flag("stdin"),
+            command: exec_matches.get_many::<String>("command")?.cloned().collect(),
+        })),
         Some(("set-master-password", _)) => Some(VaultCommand::SetMasterPassword),
+        Some(("audit", audit_matches)) => Some(VaultCommand::Audit(audit_matches.get_one::<usize>("tail").copied())),
+        Some(("doctor", doctor_matches)) => Some(VaultCommand::Doctor(doctor_matches.get_flag("repair"))),
+        Some(("rekey", rekey_matches)) => Some(VaultCommand::Rekey(VaultRekeyArgs {
+            calibrate_ms: rekey_matches.get_one::<u64>("calibrate").copied(),
+            memory_kib: rekey_matches.get_one::<u32>("memory-kib").copied(),
+            time_cost: rekey_matches.get_one::<u32>("time-cost").copied(),
+            parallelism: rekey_matches.get_one::<u32>("parallelism").copied(),
+            rotate_data_key: rekey_matches.get_flag("rotate-key"),
+        })),
+        Some(("rotate", rotate_matches)) => {
+            let source = if rotate_matches.get_flag("rollback") {
+                VaultRotateSource::Rollback
+            } else if rotate_matches.get_flag("prompt") {
+                VaultRotateSource::Prompt
+            } else {
+                VaultRotateSource::Generate(parse_password_generator_args(rotate_matches))
+            };
+            rotate_matches.get_one::<String>("name").cloned().map(|name| VaultCommand::Rotate(name, source))
+        }
+        Some(("policy", policy_matches)) => Some(VaultCommand::Policy(VaultPolicyArgs {
+            name: policy_matches.get_one::<String>("name").cloned()?,
+            confirm: if policy_matches.get_flag("confirm") {
+                Some(true)
+            } else if policy_matches.get_flag("no-confirm") {
+                Some(false)
+            } else {
+                None
+            },
+            allowed_hosts: policy_matches
+                .get_many::<String>("allow-host")
+                .map(|patterns| patterns.cloned().collect())
+                .unwrap_or_default(),
+            any_host: policy_matches.get_flag("any-host"),
+            max_uses: policy_matches.get_one::<u32>("max-uses").copied(),
+            unlimited_uses: policy_matches.get_flag("unlimited-uses"),
+            clear: policy_matches.get_flag("clear"),
+        })),
+        Some(("recovery-key", _)) => Some(VaultCommand::RecoveryKey),
+        Some(("recover", _)) => Some(VaultCommand::Recover),
+        Some(("keyfile", keyfile_matches)) => {
+            let action = if keyfile_matches.get_flag("remove") {
+                VaultKeyfileAction::Remove
+            } else if let Some(path) = keyfile_matches.get_one::<PathBuf>("path") {
+                VaultKeyfileAction::Set {
+                    path: path.clone(),
+                    generate: keyfile_matches.get_flag("generate"),
+                }
+            } else {
+                VaultKeyfileAction::Show
+            };
+            Some(VaultCommand::Keyfile(action))
+        }
+        Some(("ssh-agent", _)) => Some(VaultCommand::SshAgent),
+        _ => None,
+    }
+}
+
+fn parse_inventory_command(inventory_matches: &clap::ArgMatches) -> Option<InventoryCommand> {
+    match inventory_matches.subcommand() {
+        Some(("import", import_matches)) => Some(InventoryCommand::Import(InventoryImportArgs {
+            format: match import_matches.get_one::<String>("from")?.as_str() {
+                "ansible" => InventoryImportFormat::Ansible,
+                "putty" => InventoryImportFormat::Putty,
+                "mobaxterm" => InventoryImportFormat::MobaXterm,
+                "remmina" => InventoryImportFormat::Remmina,
+                "securecrt" => InventoryImportFormat::SecureCrt,
+                "termius" => InventoryImportFormat::Termius,
+                _ => return None,
+            },
+            path: import_matches.get_one::<PathBuf>("path").cloned()?,
+            output: import_matches.get_one::<PathBuf>("output").cloned(),
+            folder: import_matches.get_one::<String>("folder").cloned(),
+            on_conflict: match import_matches.get_one::<String>("on-conflict").map(String::as_str) {
+                Some("rename") => InventoryImportConflict::Rename,
+                Some("replace") => InventoryImportConflict::Replace,
+                _ => InventoryImportConflict::Skip,
+            },
+            dry_run: import_matches.get_flag("dry-run"),
+        })),
+        Some(("export", export_matches)) => Some(InventoryCommand::Export(InventoryExportArgs {
+            format: match export_matches.get_one::<String>("format")?.as_str() {
+                "ssh-config" => InventoryExportFormat::SshConfig,
+                "ansible" => InventoryExportFormat::Ansible,
+                "csv" => InventoryExportFormat::Csv,
+                "json" => InventoryExportFormat::Json,
+                _ => return None,
+            },
+            output: export_matches.get_one::<PathBuf>("output").cloned(),
+            split_includes: export_matches.get_flag("split-includes"),
+        })),
+        Some(("sync", sync_matches)) => Some(InventoryCommand::Sync(InventorySyncArgs {
+            ssh_config: sync_matches.get_one::<PathBuf>("ssh-config").cloned(),
+            prefer: sync_matches.get_one::<String>("prefer").map(|side| match side.as_str() {
+                "ssh-config" => InventorySyncPreference::SshConfig,
+                _ => InventorySyncPreference::Inventory,
+            }),
+            dry_run: sync_matches.get_flag("dry-run"),
+        })),
+        Some(("effective", effective_matches)) => Some(InventoryCommand::Effective(InventoryEffectiveArgs {
+            target: effective_matches.get_one::<String>("target")?.trim().to_string(),
+            ssh_config: effective_matches.get_one::<PathBuf>("ssh-config").cloned(),
+            no_exec: effective_matches.get_flag("no-exec"),
+        })),
+        Some(("list", list_matches)) => Some(InventoryCommand::List(InventoryListArgs {
+            folder: list_matches.get_one::<String>("folder").cloned(),
+            protocol: list_matches.get_one::<String>("protocol").cloned(),
+            format: match list_matches.get_one::<String>("format").map(String::as_str) {
+                Some("json") => InventoryListFormat::Json,
+                _ => InventoryListFormat::Table,
+            },
+        })),
+        Some(("show", show_matches)) => Some(InventoryCommand::Show(InventoryShowArgs {
+            name: show_matches.get_one::<String>("name")?.trim().to_string(),
+            json: show_matches.get_flag("json"),
+        })),
+        Some(("add", add_matches)) => Some(InventoryCommand::Add(InventoryAddArgs {
+            name: add_matches.get_one::<String>("name")?.trim().to_string(),
+            fields: inventory_field_values(add_matches),
+            folder: add_matches.get_one::<String>("folder").cloned(),
+        })),
+        Some(("set", set_matches)) => Some(InventoryCommand::Set(InventorySetArgs {
+            name: set_matches.get_one::<String>("name")?.trim().to_string(),
+            fields: inventory_field_values(set_matches),
+        })),
+        Some(("rm", remove_matches)) => Some(InventoryCommand::Remove(InventoryRemoveArgs {
+            names: remove_matches.get_many::<String>("name")?.map(|name| name.trim().to_string()).collect(),
+        })),
+        Some(("mv", move_matches)) => Some(InventoryCommand::Move(InventoryMoveArgs {
+            name: move_matches.get_one::<String>("name")?.trim().to_string(),
+            folder: move_matches.get_one::<String>("folder")?.clone(),
+        })),
+        Some(("mkdir", mkdir_matches)) => Some(InventoryCommand::Mkdir(InventoryMkdirArgs {
+            folder: mkdir_matches.get_one::<String>("folder")?.clone(),
+        })),
+        Some(("check", check_matches)) => Some(InventoryCommand::Check(InventoryCheckArgs {
+            path: check_matches.get_one::<PathBuf>("path").cloned(),
+            strict: check_matches.get_flag("strict"),
+        })),
         _ => None,
     }
 }
 
+fn inventory_field_values(matches: &clap::ArgMatches) -> Vec<(String, String)> {
+    matches
+        .get_many::<(String, String)>("fields")
+        .map(|fields| fields.cloned().collect())
+        .unwrap_or_default()
+}
+
 fn parse_rdp_command(rdp_matches: &clap::ArgMatches) -> Option<RdpCommandArgs> {
     let target = rdp_matches.get_one::<String>("target")?.trim().to_string();
     if target.is_empty() {
@@ -317,6 +1418,7 @@ fn parse_main_command(matches: &clap::ArgMatches) -> Option<MainCommand> {
         ("ssh", ssh_matches) => parse_ssh_command(ssh_matches).map(ProtocolCommand::Ssh).map(MainCommand::Protocol),
         ("rdp", rdp_matches) => parse_rdp_command(rdp_matches).map(ProtocolCommand::Rdp).map(MainCommand::Protocol),
         ("vault", vault_matches) => parse_vault_command(vault_matches).map(MainCommand::Vault),
+        ("inventory", inventory_matches) => parse_inventory_command(inventory_matches).map(MainCommand::Inventory),
         ("agent", agent_matches) if agent_matches.get_flag("serve") => Some(MainCommand::AgentServe),
         ("__complete", completion_matches) => parse_completion_command(completion_matches),
         _ => None,
@@ -336,6 +1438,25 @@ fn validate_main_args(cmd: &Command, matches: &clap::ArgMatches, parsed: &MainAr
             .error(ErrorKind::ArgumentConflict, "`--migrate` cannot be combined with interactive mode"));
     }
 
+    if let Some(MainCommand::Inventory(InventoryCommand::Import(import))) = &parsed.command
+        && import.format == InventoryImportFormat::Ansible
+        && (import.folder.is_some() || import.dry_run || import.on_conflict != InventoryImportConflict::Skip)
+    {
+        return Err(cmd.clone().error(
+            ErrorKind::ArgumentConflict,
+            "`--folder`, `--on-conflict` and `--dry-run` only apply to SSH client imports, not `--from ansible`",
+        ));
+    }
+
+    if let Some(MainCommand::Inventory(InventoryCommand::Export(export))) = &parsed.command
+        && export.split_includes
+        && export.format != InventoryExportFormat::SshConfig
+    {
+        return Err(cmd
+            .clone()
+            .error(ErrorKind::ArgumentConflict, "`--split-includes` only applies to `--format ssh-config`"));
+    }
+
     let protocol_command_selected = matches!(parsed.command, Some(MainCommand::Protocol(_)));
     if !protocol_command_selected {
         if parsed.ssh_logging {
@@ -2,6 +2,11 @@
 
 const INVALID_PROFILE_NAME_MESSAGE: &str = "invalid profile name: use only letters, numbers, '_' or '-'";
 const INVALID_VAULT_ENTRY_NAME_MESSAGE: &str = "invalid pass entry name: use only letters, numbers, '.', '_' or '-'";
+const INVALID_VAULT_ENTRY_USER_MESSAGE: &str = "invalid login user: use a non-empty name without whitespace or control characters";
+const INVALID_ENV_VAR_NAME_MESSAGE: &str = "invalid environment variable name: use letters, numbers or '_', not starting with a number";
+const INVALID_FIELD_ASSIGNMENT_MESSAGE: &str = "invalid field assignment: use key=value, or key= to remove the field";
+const INVALID_SECRET_REFERENCE_MESSAGE: &str =
+    "invalid pass entry: use a vault entry name (letters, numbers, '.', '_' or '-') or '<provider>:<locator>' with provider pass, op, bw, secret-tool or cmd";
 
 pub(crate) fn validate_profile_name(name: &str) -> bool {
     !name.is_empty() && name.chars().all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-'))
@@ -26,3 +31,38 @@ pub(crate) fn parse_vault_entry_name(value: &str) -> Result<String, String> {
     }
     Ok(trimmed.to_string())
 }
+
+pub(crate) fn parse_vault_entry_user(value: &str) -> Result<String, String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() || trimmed.chars().any(|ch| ch.is_whitespace() || ch.is_control()) {
+        return Err(INVALID_VAULT_ENTRY_USER_MESSAGE.to_string());
+    }
+    Ok(trimmed.to_string())
+}
+
+pub(crate) fn parse_env_var_name(value: &str) -> Result<String, String> {
+    let valid = value.chars().next().is_some_and(|first| !first.is_ascii_digit()) && value.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+    if !valid {
+        return Err(INVALID_ENV_VAR_NAME_MESSAGE.to_string());
+    }
+    Ok(value.to_string())
+}
+
+pub(crate) fn validate_secret_reference(value: &str) -> bool {
+    crate::auth::provider::SecretReference::parse(value).is_some()
+}
+
+pub(crate) fn parse_secret_reference(value: &str) -> Result<String, String> {
+    let trimmed = value.trim();
+    if !validate_secret_reference(trimmed) {
+        return Err(INVALID_SECRET_REFERENCE_MESSAGE.to_string());
+    }
+    Ok(trimmed.to_string())
+}
+
+pub(crate) fn parse_field_assignment(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => Ok((key.trim().to_string(), value.to_string())),
+        _ => Err(INVALID_FIELD_ASSIGNMENT_MESSAGE.to_string()),
+    }
+}
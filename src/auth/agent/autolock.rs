@@ -0,0 +1,83 @@
+//! Lock triggers besides the idle and session timeouts.
+//!
+//! The server thread polls these while the vault is unlocked: a `SIGUSR1`
+//! flag set by the signal handler, a resume detector, and the liveness of
+//! attached session managers. Failed askpass authorizations are counted by
+//! the request handler instead.
+
+use nix::sys::signal::kill;
+use nix::unistd::Pid;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often the server thread checks the polled triggers while unlocked.
+pub(super) const LOCK_TRIGGER_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Wall-clock time that may pass beyond monotonic time before a suspend is assumed.
+const RESUME_CLOCK_JUMP: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Why the agent locked the vault before its timeouts elapsed.
+pub(crate) enum LockTrigger {
+    Signal,
+    Resume,
+    TuiExit,
+    FailedAuthorizations,
+}
+
+impl LockTrigger {
+    /// Detail recorded with the `locked` audit event.
+    pub(crate) fn audit_detail(self) -> &'static str {
+        match self {
+            Self::Signal => "signal",
+            Self::Resume => "resume from suspend",
+            Self::TuiExit => "last session manager exited",
+            Self::FailedAuthorizations => "failed authorizations",
+        }
+    }
+}
+
+/// Detects suspend by comparing wall-clock and monotonic time between polls.
+///
+/// The monotonic clock stops while the machine sleeps and the wall clock
+/// does not, so after a resume the wall clock is ahead by the sleep time.
+#[derive(Debug, Clone)]
+pub(crate) struct ResumeDetector {
+    monotonic: Instant,
+    wall: SystemTime,
+}
+
+impl ResumeDetector {
+    pub(crate) fn new() -> Self {
+        Self::starting_at(Instant::now(), SystemTime::now())
+    }
+
+    pub(crate) fn starting_at(monotonic: Instant, wall: SystemTime) -> Self {
+        Self { monotonic, wall }
+    }
+
+    /// Returns `true` when the machine was suspended since the last call.
+    pub(crate) fn observe(&mut self) -> bool {
+        self.observe_at(Instant::now(), SystemTime::now())
+    }
+
+    pub(crate) fn observe_at(&mut self, monotonic: Instant, wall: SystemTime) -> bool {
+        let monotonic_elapsed = monotonic.saturating_duration_since(self.monotonic);
+        // A wall clock set backwards is not a resume.
+        let wall_elapsed = wall.duration_since(self.wall).unwrap_or_default();
+        self.monotonic = monotonic;
+        self.wall = wall;
+        wall_elapsed.saturating_sub(monotonic_elapsed) >= RESUME_CLOCK_JUMP
+    }
+}
+
+/// Whether a process with this pid still exists.
+pub(crate) fn process_is_running(pid: u32) -> bool {
+    let Ok(pid) = i32::try_from(pid) else {
+        return false;
+    };
+    // Signal 0 only checks that the process exists and may be signalled.
+    kill(Pid::from_raw(pid), None).is_ok()
+}
+
+#[cfg(test)]
+#[path = "../../test/auth/agent/autolock.rs"]
+mod tests;
@@ -1,5 +1,5 @@
 use super::error::{AgentError, map_remote_error};
-use crate::auth::ipc::{self, AgentRequestPayload, AgentResponse, UnlockPolicy, VaultStatus};
+use crate::auth::ipc::{self, AgentEntryChange, AgentRequestPayload, AgentResponse, UnlockPolicy, VaultStatus};
 use crate::auth::secret::{SensitiveString, sensitive_string};
 use crate::auth::vault::VaultPaths;
 use crate::log_debug;
@@ -75,16 +75,63 @@ impl AgentClient {
         }
     }
 
-    /// Authorize one short-lived askpass token for the named vault entry.
-    pub fn authorize_askpass(&self, name: &str) -> Result<SensitiveString, AgentError> {
+    /// Authorize one short-lived askpass token for the named vault entry or
+    /// external provider reference.
+    ///
+    /// `host` is the alias recorded in the audit log. `host` and
+    /// `destination` are both checked against the entry policy.
+    pub fn authorize_askpass(&self, name: &str, host: Option<&str>, destination: Option<&str>) -> Result<SensitiveString, AgentError> {
         log_debug!("Requesting internal askpass authorization for '{}'", name);
-        match self.request(AgentRequestPayload::AuthorizeAskpass { name: name.to_string() }, true)? {
+        match self.request(
+            AgentRequestPayload::AuthorizeAskpass {
+                name: name.to_string(),
+                host: host.map(str::to_string),
+                destination: destination.map(str::to_string),
+            },
+            true,
+        )? {
             AgentResponse::AskpassAuthorized { token, .. } => Ok(token),
             AgentResponse::Error { code, message, .. } => Err(map_remote_error(&code, message)),
             response => Err(AgentError::Protocol(format!("unexpected askpass authorization response: {response:?}"))),
         }
     }
 
+    /// Change one entry through the unlocked agent, without the master
+    /// password. Fails with [`AgentError::Locked`] while the vault is locked.
+    pub fn change_entry(&self, change: AgentEntryChange) -> Result<VaultStatus, AgentError> {
+        log_debug!("Requesting password vault entry change");
+        match self.request(AgentRequestPayload::ChangeEntry { change }, false) {
+            Ok(AgentResponse::Success { status, .. }) => Ok(status),
+            Ok(AgentResponse::Error { code, message, .. }) => Err(map_remote_error(&code, message)),
+            Ok(response) => Err(AgentError::Protocol(format!("unexpected entry change response: {response:?}"))),
+            Err(AgentError::Io(_)) => Err(AgentError::Locked),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Register this process as a running session manager.
+    ///
+    /// The agent watches registered processes for the `tui_exit` lock
+    /// trigger. Nothing is registered while the vault is locked.
+    pub fn attach_tui(&self) -> Result<(), AgentError> {
+        self.tui_request(AgentRequestPayload::AttachTui)
+    }
+
+    /// Deregister this process, locking the vault if it was the last session
+    /// manager and the `tui_exit` trigger is enabled.
+    pub fn detach_tui(&self) -> Result<(), AgentError> {
+        self.tui_request(AgentRequestPayload::DetachTui)
+    }
+
+    fn tui_request(&self, payload: AgentRequestPayload) -> Result<(), AgentError> {
+        match self.request(payload, false) {
+            Ok(AgentResponse::Status { .. }) | Err(AgentError::Io(_)) => Ok(()),
+            Ok(AgentResponse::Error { code, message, .. }) => Err(map_remote_error(&code, message)),
+            Ok(response) => Err(AgentError::Protocol(format!("unexpected session manager response: {response:?}"))),
+            Err(err) => Err(err),
+        }
+    }
+
     /// Resolve a secret by askpass token.
     pub fn get_secret(&self, token: &str) -> Result<SensitiveString, AgentError> {
         log_debug!("Requesting password vault secret using askpass token");
@@ -15,6 +15,14 @@ pub enum AgentError {
     InvalidOrExpiredAskpassToken,
     UnauthorizedClient,
     VaultNotInitialized,
+    /// The entry policy requires an interactive confirmation first.
+    ConfirmationRequired,
+    /// The user declined the agent's release confirmation.
+    ConfirmationDeclined,
+    /// No confirmation helper is available to the agent.
+    ConfirmationUnavailable,
+    HostNotAllowed,
+    UsageLimitReached,
     Protocol(String),
 }
 
@@ -29,6 +37,14 @@ impl fmt::Display for AgentError {
             Self::InvalidOrExpiredAskpassToken => write!(f, "invalid or expired askpass token"),
             Self::UnauthorizedClient => write!(f, "password vault agent refused secret access from an untrusted client process"),
             Self::VaultNotInitialized => write!(f, "password vault is not initialized"),
+            Self::ConfirmationRequired => write!(f, "password vault entry requires confirmation before use"),
+            Self::ConfirmationDeclined => write!(f, "password vault entry release was declined"),
+            Self::ConfirmationUnavailable => write!(
+                f,
+                "password vault entry requires confirmation, but the agent has no SSH_ASKPASS helper to ask with"
+            ),
+            Self::HostNotAllowed => write!(f, "password vault entry policy does not allow this host"),
+            Self::UsageLimitReached => write!(f, "password vault entry reached its usage limit for this unlock session"),
             Self::Protocol(message) => write!(f, "{message}"),
         }
     }
@@ -56,7 +72,12 @@ pub(crate) fn map_remote_error(code: &str, message: String) -> AgentError {
         "invalid_or_expired_askpass_token" => AgentError::InvalidOrExpiredAskpassToken,
         "unauthorized_client" => AgentError::UnauthorizedClient,
         "vault_not_initialized" => AgentError::VaultNotInitialized,
-        "invalid_entry_name" | "vault_error" | "askpass_token_error" => AgentError::Protocol(message),
+        "confirmation_required" => AgentError::ConfirmationRequired,
+        "confirmation_declined" => AgentError::ConfirmationDeclined,
+        "confirmation_unavailable" => AgentError::ConfirmationUnavailable,
+        "host_not_allowed" => AgentError::HostNotAllowed,
+        "usage_limit_reached" => AgentError::UsageLimitReached,
+        "invalid_entry_name" | "vault_error" | "askpass_token_error" | "secret_provider_error" | "audit_failed" => AgentError::Protocol(message),
         _ => AgentError::Protocol(message),
     }
 }
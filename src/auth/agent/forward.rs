@@ -0,0 +1,324 @@
+//! Vault secret forwarding for `cossh` running on a remote host.
+//!
+//! Every launch of a `vault_forward` host binds its own
+//! `run/vault-forward-<random>.sock` in the launching process and points an
+//! SSH `RemoteForward` at it from `/tmp/cossh-vault-forward-<random>.sock` on
+//! the remote host, whose path is passed on with `SendEnv`. A
+//! remote askpass helper sends one request naming the entry it needs; the
+//! launching process relays it to the agent together with the host alias it
+//! launched, so the remote side cannot choose which host the entry policy is
+//! checked against. The agent asks locally through `SSH_ASKPASS` before every
+//! release, whatever the entry policy says, and audits releases as
+//! `remote_entry_read`.
+//!
+//! Only local vault entries can be requested. Host patterns and usage limits
+//! of the entry policy apply to the launching alias as they do for local launches.
+
+use super::error::{AgentError, map_remote_error};
+use super::runtime::{AgentRuntime, lock_runtime};
+use super::server::entry_release_policy;
+use super::ssh::askpass_confirmation;
+use crate::args::validate_vault_entry_name;
+use crate::auth::audit::{self, AuditEvent, AuditEventKind, AuditProcess};
+use crate::auth::ipc::{self, AgentRequestPayload, AgentResponse};
+use crate::auth::secret::{ExposeSecret, SensitiveString, serde_sensitive_string};
+use crate::auth::vault::{self, VaultPaths};
+use crate::log_debug;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How long a forwarded client may take to send its request.
+const FORWARD_CLIENT_READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a remote helper waits for the local user to answer the confirmation.
+const FORWARD_RESPONSE_TIMEOUT: Duration = Duration::from_secs(120);
+const REMOTE_FORWARD_AUDIT_DETAIL: &str = "remote forward";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+/// Secret request sent by a remote `cossh` over the forwarded socket.
+pub(crate) struct ForwardedSecretRequest {
+    pub(crate) name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+/// Reply to a forwarded secret request; carries no local vault status.
+pub(crate) enum ForwardedSecretResponse {
+    Secret {
+        #[serde(with = "serde_sensitive_string")]
+        secret: SensitiveString,
+    },
+    Error {
+        code: String,
+        message: String,
+    },
+}
+
+impl ForwardedSecretResponse {
+    fn error(code: &str, message: impl Into<String>) -> Self {
+        Self::Error {
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Forwarding socket opened for one `vault_forward` launch; removed when dropped.
+pub struct VaultForward {
+    socket_path: PathBuf,
+    stopping: Arc<AtomicBool>,
+}
+
+impl VaultForward {
+    /// Local end of the launch's `RemoteForward`.
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+}
+
+impl Drop for VaultForward {
+    fn drop(&mut self) {
+        self.stopping.store(true, Ordering::Release);
+        // Wake the relay thread blocked in `accept` so it exits.
+        let _ = UnixStream::connect(&self.socket_path);
+        let _ = ipc::cleanup_vault_forward_socket(&self.socket_path);
+    }
+}
+
+/// Host alias a forwarding socket was opened for.
+struct ForwardBinding {
+    paths: VaultPaths,
+    host: String,
+    destination: Option<String>,
+}
+
+/// Bind a forwarding socket whose requests are only released for `host`.
+///
+/// Requests are relayed to the agent from this process, which is what ties
+/// them to the launching alias. The socket lives until the returned guard drops.
+pub fn open_vault_forward(paths: &VaultPaths, host: &str, destination: Option<&str>) -> io::Result<VaultForward> {
+    let (socket_path, listener) = ipc::bind_vault_forward_listener(paths)?;
+    let stopping = Arc::new(AtomicBool::new(false));
+    let binding = Arc::new(ForwardBinding {
+        paths: paths.clone(),
+        host: host.to_string(),
+        destination: destination.map(str::to_string),
+    });
+    let relay_stopping = Arc::clone(&stopping);
+    let spawned = thread::Builder::new()
+        .name("cossh-vault-forward-relay".to_string())
+        .spawn(move || relay_forwarded_clients(&binding, &listener, &relay_stopping));
+    if let Err(err) = spawned {
+        let _ = ipc::cleanup_vault_forward_socket(&socket_path);
+        return Err(err);
+    }
+    log_debug!("Opened vault forwarding socket for host '{}'", host);
+    Ok(VaultForward { socket_path, stopping })
+}
+
+fn relay_forwarded_clients(binding: &Arc<ForwardBinding>, listener: &UnixListener, stopping: &AtomicBool) {
+    loop {
+        let stream = listener.accept();
+        if stopping.load(Ordering::Acquire) {
+            return;
+        }
+        match stream {
+            Ok((stream, _)) => {
+                let binding = Arc::clone(binding);
+                let spawned = thread::Builder::new().name("cossh-vault-forward-client".to_string()).spawn(move || {
+                    if let Err(err) = relay_forwarded_connection(&binding, stream) {
+                        log_debug!("Vault forwarding client connection ended with error: {}", err);
+                    }
+                });
+                if let Err(err) = spawned {
+                    log_debug!("Failed to spawn vault forwarding worker: {}", err);
+                }
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => {
+                log_debug!("Vault forwarding socket stopped accepting clients: {}", err);
+                return;
+            }
+        }
+    }
+}
+
+/// Pass one remote request to the agent, naming the launching alias as the host.
+fn relay_forwarded_connection(binding: &ForwardBinding, mut stream: UnixStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(FORWARD_CLIENT_READ_TIMEOUT))?;
+    let request: ForwardedSecretRequest = match ipc::read_json_line(&mut stream) {
+        Ok(request) => request,
+        Err(err) => {
+            let response = ForwardedSecretResponse::error("invalid_request", format!("failed to read request: {err}"));
+            return ipc::write_json_line(&mut stream, &response);
+        }
+    };
+
+    let payload = AgentRequestPayload::ForwardedSecret {
+        name: request.name,
+        host: binding.host.clone(),
+        destination: binding.destination.clone(),
+    };
+    let response = match ipc::send_request(&binding.paths, &payload) {
+        Ok(AgentResponse::Secret { secret, .. }) => ForwardedSecretResponse::Secret { secret },
+        Ok(AgentResponse::Error { code, message, .. }) => ForwardedSecretResponse::Error { code, message },
+        Ok(_) => ForwardedSecretResponse::error("invalid_response", "unexpected response from the password vault agent"),
+        Err(err) => {
+            log_debug!("Password vault agent unavailable for forwarded request: {}", err);
+            ForwardedSecretResponse::error("locked", "password vault is locked on the forwarding machine")
+        }
+    };
+    ipc::write_json_line(&mut stream, &response)
+}
+
+/// Answer a forwarded request relayed by the `cossh` process that launched `host`.
+pub(crate) fn serve_forwarded_request(
+    paths: &VaultPaths,
+    runtime: &Mutex<AgentRuntime>,
+    name: &str,
+    host: &str,
+    destination: Option<&str>,
+    peer_pid: Option<u32>,
+) -> AgentResponse {
+    let response = handle_forwarded_request(paths, runtime, name, host, destination, peer_pid, askpass_confirmation);
+    let status = lock_runtime(runtime).status(paths);
+    match response {
+        ForwardedSecretResponse::Secret { secret } => AgentResponse::Secret {
+            status,
+            name: name.to_string(),
+            secret,
+        },
+        ForwardedSecretResponse::Error { code, message } => AgentResponse::Error { status, code, message },
+    }
+}
+
+/// Release one entry to a remote session on `host` after `confirm` approves
+/// the prompt; `confirm` returns `None` when no prompt can be shown.
+pub(crate) fn handle_forwarded_request(
+    paths: &VaultPaths,
+    runtime: &Mutex<AgentRuntime>,
+    name: &str,
+    host: &str,
+    destination: Option<&str>,
+    peer_pid: Option<u32>,
+    confirm: impl FnOnce(&str) -> Option<bool>,
+) -> ForwardedSecretResponse {
+    log_debug!("Handling forwarded vault secret request for entry '{}' (host '{}')", name, host);
+    if !validate_vault_entry_name(name) {
+        return ForwardedSecretResponse::error("invalid_entry_name", "remote hosts can only request local password vault entries");
+    }
+
+    // Check everything that does not need the user before asking them.
+    let Some(unlocked) = lock_runtime(runtime).unlocked_vault(paths) else {
+        return ForwardedSecretResponse::error("locked", "password vault is locked on the forwarding machine");
+    };
+    match vault::entry_exists_with_paths(paths, name) {
+        Ok(true) => {}
+        Ok(false) => return ForwardedSecretResponse::error("entry_not_found", "password vault entry was not found"),
+        Err(err) => return ForwardedSecretResponse::error("vault_error", err.to_string()),
+    }
+    let entry_policy = match entry_release_policy(&unlocked, name) {
+        Ok(entry_policy) => entry_policy,
+        Err(err) => return ForwardedSecretResponse::error("vault_error", err.to_string()),
+    };
+    if !entry_policy.allows_host(Some(host), destination) {
+        record_forward_event(paths, peer_pid, AuditEventKind::EntryDenied, name, host, "remote forward: host_not_allowed");
+        return ForwardedSecretResponse::error("host_not_allowed", AgentError::HostNotAllowed.to_string());
+    }
+
+    match confirm(&format!("Release password vault entry '{name}' to remote host '{host}'?")) {
+        Some(true) => {}
+        Some(false) => {
+            log_debug!("Forwarded release of entry '{}' was declined locally", name);
+            record_forward_event(paths, peer_pid, AuditEventKind::EntryDenied, name, host, "remote forward: declined");
+            return ForwardedSecretResponse::error("confirmation_declined", "release was not confirmed on the forwarding machine");
+        }
+        None => {
+            record_forward_event(
+                paths,
+                peer_pid,
+                AuditEventKind::EntryDenied,
+                name,
+                host,
+                "remote forward: confirmation_unavailable",
+            );
+            return ForwardedSecretResponse::error(
+                "confirmation_unavailable",
+                "the forwarding machine has no SSH_ASKPASS helper to confirm the release",
+            );
+        }
+    }
+
+    let mut runtime = lock_runtime(runtime);
+    // The vault may have been locked while the user was confirming.
+    let Some(unlocked) = runtime.unlocked_vault(paths) else {
+        return ForwardedSecretResponse::error("locked", "password vault is locked on the forwarding machine");
+    };
+    // The prompt above is the confirmation the entry policy asks for.
+    let granted = runtime
+        .issue_askpass_token(name, Some(host), destination, &entry_policy, true)
+        .map(|token| runtime.take_askpass_entry(token.expose_secret()));
+    match granted {
+        Ok(Some(_)) => {}
+        Ok(None) => return ForwardedSecretResponse::error("askpass_token_error", "failed to record forwarded release"),
+        Err(err) => {
+            let code = match err {
+                AgentError::HostNotAllowed => "host_not_allowed",
+                AgentError::UsageLimitReached => "usage_limit_reached",
+                _ => "askpass_token_error",
+            };
+            record_forward_event(paths, peer_pid, AuditEventKind::EntryDenied, name, host, &format!("remote forward: {code}"));
+            return ForwardedSecretResponse::error(code, err.to_string());
+        }
+    }
+
+    match unlocked.get_secret(name) {
+        Ok(secret) => {
+            runtime.touch();
+            let event = forward_audit_event(peer_pid, AuditEventKind::RemoteEntryRead, name, host, REMOTE_FORWARD_AUDIT_DETAIL);
+            if let Err(message) = audit::record_release_event(paths, event) {
+                return ForwardedSecretResponse::error("audit_failed", message);
+            }
+            log_debug!("Released password vault entry '{}' over vault forwarding", name);
+            ForwardedSecretResponse::Secret { secret }
+        }
+        Err(err) => ForwardedSecretResponse::error("vault_error", err.to_string()),
+    }
+}
+
+/// Ask the forwarding machine's agent for one entry through `socket_path`.
+///
+/// Blocks until the local user answers the confirmation prompt there.
+pub fn request_forwarded_secret(socket_path: &Path, name: &str) -> Result<SensitiveString, AgentError> {
+    log_debug!("Requesting password vault entry '{}' over vault forwarding", name);
+    let mut stream = UnixStream::connect(socket_path)?;
+    stream.set_read_timeout(Some(FORWARD_RESPONSE_TIMEOUT))?;
+    let request = ForwardedSecretRequest { name: name.to_string() };
+    ipc::write_json_line(&mut stream, &request)?;
+    match ipc::read_json_line(&mut stream)? {
+        ForwardedSecretResponse::Secret { secret } => Ok(secret),
+        ForwardedSecretResponse::Error { code, message } => Err(map_remote_error(&code, message)),
+    }
+}
+
+fn record_forward_event(paths: &VaultPaths, peer_pid: Option<u32>, kind: AuditEventKind, name: &str, host: &str, detail: &str) {
+    audit::record_event(paths, forward_audit_event(peer_pid, kind, name, host, detail));
+}
+
+fn forward_audit_event(peer_pid: Option<u32>, kind: AuditEventKind, name: &str, host: &str, detail: &str) -> AuditEvent {
+    AuditEvent::new(kind)
+        .with_entry(name)
+        .with_host(Some(host))
+        .with_detail(detail)
+        .with_process(peer_pid.map(AuditProcess::from_pid))
+}
+
+#[cfg(test)]
+#[path = "../../test/auth/agent/forward.rs"]
+mod tests;
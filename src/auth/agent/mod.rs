@@ -3,16 +3,23 @@
 //! `AgentClient` is used by command execution paths to query and unlock the
 //! vault through a shared local IPC endpoint.
 
+mod autolock;
 mod client;
 mod error;
+mod forward;
 mod runtime;
 mod server;
+mod ssh;
 
 /// Shared request/response payload types and status metadata.
-pub use crate::auth::ipc::{AgentRequest, UnlockPolicy as AgentUnlockPolicy, VaultStatus as AgentVaultStatus};
+pub use crate::auth::ipc::{AgentEntryChange, AgentRequest, UnlockPolicy as AgentUnlockPolicy, VaultStatus as AgentVaultStatus};
 /// Client for querying and controlling the local unlock agent.
 pub use client::{AgentClient, AgentEntryStatus};
 /// Error type returned by agent client/server operations.
 pub use error::AgentError;
+/// Per-launch vault forwarding sockets and the client a remote `cossh` uses on them.
+pub use forward::{VaultForward, open_vault_forward, request_forwarded_secret};
+/// Desktop helper the agent asks confirmations through.
+pub(crate) use ssh::askpass_helper;
 /// Starts the unlock-agent server process loop.
 pub use server::run_server;
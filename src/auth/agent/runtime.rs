@@ -1,18 +1,19 @@
 //! In-memory unlock session state for the vault agent.
 
 use super::error::AgentError;
-use crate::auth::ipc::{UnlockPolicy, VaultStatus};
+use crate::auth::ipc::{LockTriggers, UnlockPolicy, VaultStatus};
+use crate::auth::provider::is_external_secret_reference;
 use crate::auth::secret::{ExposeSecret, SensitiveString};
-use crate::auth::vault::{UnlockedVault, VaultPaths};
+use crate::auth::vault::{EntryPolicy, SshKeyConstraints, UnlockedVault, VaultPaths};
 use crate::log_debug;
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
 use getrandom::fill as random_fill;
+use std::collections::HashMap;
 use std::fmt;
+use std::sync::{Mutex, MutexGuard, PoisonError};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use zeroize::Zeroize;
 
-pub(crate) const AGENT_IDLE_SHUTDOWN_POLL_INTERVAL_MIN: Duration = Duration::from_millis(5);
-pub(crate) const AGENT_IDLE_SHUTDOWN_POLL_INTERVAL_MAX: Duration = Duration::from_millis(250);
 const ASKPASS_TOKEN_TTL: Duration = Duration::from_secs(60);
 const ASKPASS_TOKEN_BYTES: usize = 32;
 
@@ -20,9 +21,70 @@ const ASKPASS_TOKEN_BYTES: usize = 32;
 pub(super) struct AskpassLease {
     token: SensitiveString,
     entry_name: String,
+    host: Option<String>,
+    resolved_secret: Option<SensitiveString>,
     expires_at: Instant,
 }
 
+/// Entry bound to a consumed askpass token.
+#[derive(Debug, Clone)]
+pub(crate) struct AskpassGrant {
+    pub(crate) entry_name: String,
+    pub(crate) host: Option<String>,
+    /// External secret resolved at authorization while no unlock session
+    /// could cache it.
+    pub(crate) resolved_secret: Option<SensitiveString>,
+}
+
+impl AskpassLease {
+    fn into_grant(self) -> AskpassGrant {
+        AskpassGrant {
+            entry_name: self.entry_name,
+            host: self.host,
+            resolved_secret: self.resolved_secret,
+        }
+    }
+}
+
+/// Secret resolved through an external provider, kept until the idle or
+/// session timeout of the policy it was cached under elapses.
+#[derive(Debug)]
+pub(super) struct CachedExternalSecret {
+    reference: String,
+    secret: SensitiveString,
+    cached_at: Instant,
+    last_used_at: Instant,
+    policy: UnlockPolicy,
+}
+
+impl CachedExternalSecret {
+    fn is_expired(&self) -> bool {
+        self.time_until_expiry().is_zero()
+    }
+
+    fn time_until_expiry(&self) -> Duration {
+        let idle_remaining = Duration::from_secs(self.policy.idle_timeout_seconds).saturating_sub(self.last_used_at.elapsed());
+        let absolute_remaining = Duration::from_secs(self.policy.session_timeout_seconds).saturating_sub(self.cached_at.elapsed());
+        idle_remaining.min(absolute_remaining)
+    }
+}
+
+/// Key added with `ssh-add`, held in memory until the vault locks or its
+/// lifetime passes. It is never written to the vault.
+#[derive(Clone)]
+pub(super) struct SessionSshKey {
+    pub(super) name: String,
+    pub(super) private_key: ssh_key::PrivateKey,
+    pub(super) constraints: SshKeyConstraints,
+}
+
+/// Public key blob of a vault key entry, valid while the entry keeps `nonce`.
+#[derive(Debug, Clone)]
+pub(super) struct CachedSshPublicKey {
+    pub(super) nonce: String,
+    pub(super) blob: Vec<u8>,
+}
+
 pub(crate) struct AgentRuntime {
     pub(super) data_key: Option<[u8; 32]>,
     pub(super) unlocked_at: Option<Instant>,
@@ -30,6 +92,17 @@ pub(crate) struct AgentRuntime {
     pub(super) absolute_timeout_at: Option<SystemTime>,
     pub(super) policy: Option<UnlockPolicy>,
     pub(super) askpass_leases: Vec<AskpassLease>,
+    pub(super) external_secrets: Vec<CachedExternalSecret>,
+    /// Releases per entry during the current unlock session.
+    pub(super) entry_uses: HashMap<String, u32>,
+    /// Session managers attached while unlocked, for the `tui_exit` trigger.
+    pub(super) tui_pids: Vec<u32>,
+    /// Askpass authorizations refused in a row.
+    pub(super) failed_authorizations: u32,
+    /// Keys added with `ssh-add` during the current unlock session.
+    pub(super) session_ssh_keys: Vec<SessionSshKey>,
+    /// Public keys of vault key entries, so listing never decrypts them.
+    pub(super) ssh_public_keys: HashMap<String, CachedSshPublicKey>,
 }
 
 impl fmt::Debug for AgentRuntime {
@@ -41,6 +114,12 @@ impl fmt::Debug for AgentRuntime {
             .field("absolute_timeout_at", &self.absolute_timeout_at)
             .field("policy", &self.policy)
             .field("askpass_lease_count", &self.askpass_leases.len())
+            .field("external_secret_count", &self.external_secrets.len())
+            .field("entry_uses", &self.entry_uses)
+            .field("tui_pids", &self.tui_pids)
+            .field("failed_authorizations", &self.failed_authorizations)
+            .field("session_ssh_key_count", &self.session_ssh_keys.len())
+            .field("ssh_public_key_count", &self.ssh_public_keys.len())
             .finish()
     }
 }
@@ -55,11 +134,18 @@ impl AgentRuntime {
             absolute_timeout_at: None,
             policy: None,
             askpass_leases: Vec::new(),
+            external_secrets: Vec::new(),
+            entry_uses: HashMap::new(),
+            tui_pids: Vec::new(),
+            failed_authorizations: 0,
+            session_ssh_keys: Vec::new(),
+            ssh_public_keys: HashMap::new(),
         }
     }
 
     /// Returns `true` when session expiration caused a lock transition.
     pub(crate) fn expire_if_needed(&mut self) -> bool {
+        self.prune_expired_external_secrets();
         let Some(policy) = &self.policy else {
             return false;
         };
@@ -87,6 +173,24 @@ impl AgentRuntime {
         false
     }
 
+    /// Time left until the session times out or a cached external secret
+    /// expires, or `None` while locked.
+    pub(crate) fn time_until_expiry(&self) -> Option<Duration> {
+        let session_remaining = match (&self.policy, self.unlocked_at, self.last_activity_at) {
+            (Some(policy), Some(unlocked_at), Some(last_activity_at)) => {
+                let idle_remaining = Duration::from_secs(policy.idle_timeout_seconds).saturating_sub(last_activity_at.elapsed());
+                let absolute_remaining = Duration::from_secs(policy.session_timeout_seconds).saturating_sub(unlocked_at.elapsed());
+                Some(idle_remaining.min(absolute_remaining))
+            }
+            _ => None,
+        };
+        let cache_remaining = self.external_secrets.iter().map(CachedExternalSecret::time_until_expiry).min();
+        match (session_remaining, cache_remaining) {
+            (Some(session), Some(cache)) => Some(session.min(cache)),
+            (session, cache) => session.or(cache),
+        }
+    }
+
     /// Build current vault status snapshot.
     pub(crate) fn status(&self, paths: &VaultPaths) -> VaultStatus {
         let vault_exists = paths.metadata_path().is_file();
@@ -145,6 +249,14 @@ impl AgentRuntime {
         }
         let lease_count = self.askpass_leases.len();
         self.askpass_leases.clear();
+        let cached_external_count = self.external_secrets.len();
+        self.external_secrets.clear();
+        self.entry_uses.clear();
+        self.tui_pids.clear();
+        self.failed_authorizations = 0;
+        let session_key_count = self.session_ssh_keys.len();
+        self.session_ssh_keys.clear();
+        self.ssh_public_keys.clear();
         self.unlocked_at = None;
         self.last_activity_at = None;
         self.absolute_timeout_at = None;
@@ -155,35 +267,198 @@ impl AgentRuntime {
         if lease_count > 0 {
             log_debug!("Cleared {} outstanding askpass token(s)", lease_count);
         }
+        if cached_external_count > 0 {
+            log_debug!("Cleared {} cached external secret(s)", cached_external_count);
+        }
+        if session_key_count > 0 {
+            log_debug!("Dropped {} SSH key(s) added for the session", session_key_count);
+        }
         was_unlocked
     }
 
+    /// Whether data-key material is loaded.
+    pub(crate) fn is_unlocked(&self) -> bool {
+        self.data_key.is_some()
+    }
+
+    /// Lock triggers of the current unlock session, or `None` while locked.
+    pub(crate) fn lock_triggers(&self) -> Option<&LockTriggers> {
+        self.policy.as_ref().filter(|_| self.is_unlocked()).map(|policy| &policy.lock_triggers)
+    }
+
+    /// Attach a session manager process; ignored while locked.
+    pub(crate) fn attach_tui(&mut self, pid: u32) {
+        if self.is_unlocked() && !self.tui_pids.contains(&pid) {
+            log_debug!("Session manager {} attached to the password vault agent", pid);
+            self.tui_pids.push(pid);
+        }
+    }
+
+    /// Detach a session manager. Returns `true` when it was the last one attached.
+    pub(crate) fn detach_tui(&mut self, pid: u32) -> bool {
+        let attached = self.tui_pids.len();
+        self.tui_pids.retain(|attached_pid| *attached_pid != pid);
+        attached > 0 && self.tui_pids.is_empty()
+    }
+
+    /// Forget session managers that `is_running` reports gone. Returns `true`
+    /// when the last attached one is gone.
+    pub(crate) fn prune_exited_tuis(&mut self, is_running: impl Fn(u32) -> bool) -> bool {
+        let attached = self.tui_pids.len();
+        self.tui_pids.retain(|pid| is_running(*pid));
+        attached > 0 && self.tui_pids.is_empty()
+    }
+
+    /// Count one refused askpass authorization. Returns `true` once the
+    /// session's `max_failed_authorizations` is reached.
+    pub(crate) fn record_failed_authorization(&mut self) -> bool {
+        let Some(max_failed) = self.lock_triggers().map(|triggers| triggers.max_failed_authorizations) else {
+            return false;
+        };
+        self.failed_authorizations = self.failed_authorizations.saturating_add(1);
+        log_debug!("Password vault agent refused {} askpass authorization(s) in a row", self.failed_authorizations);
+        max_failed > 0 && self.failed_authorizations >= max_failed
+    }
+
+    /// Reset the refused-authorization count after a successful release.
+    pub(crate) fn reset_failed_authorizations(&mut self) {
+        self.failed_authorizations = 0;
+    }
+
     /// Build an unlocked vault handle from in-memory key material.
     pub(crate) fn unlocked_vault(&self, paths: &VaultPaths) -> Option<UnlockedVault> {
         self.data_key.map(|data_key| UnlockedVault::from_data_key(paths.clone(), data_key))
     }
 
+    /// Refusal `policy` gives a release of `entry_name` before any
+    /// confirmation is asked: host patterns are matched against the alias and
+    /// destination, then the per-session usage limit is checked.
+    pub(crate) fn release_refusal(&self, entry_name: &str, host: Option<&str>, destination: Option<&str>, policy: &EntryPolicy) -> Option<AgentError> {
+        if !policy.allows_host(host, destination) {
+            log_debug!("Entry policy for '{}' refused host {:?} / destination {:?}", entry_name, host, destination);
+            return Some(AgentError::HostNotAllowed);
+        }
+        let uses = self.entry_uses.get(entry_name).copied().unwrap_or(0);
+        if policy.max_uses.is_some_and(|max_uses| uses >= max_uses) {
+            log_debug!("Entry '{}' reached its usage limit for this unlock session", entry_name);
+            return Some(AgentError::UsageLimitReached);
+        }
+        None
+    }
+
     /// Issue a short-lived, single-use askpass token for one entry name.
-    pub(crate) fn issue_askpass_token(&mut self, entry_name: &str) -> Result<SensitiveString, AgentError> {
+    ///
+    /// `policy` is enforced before the token is issued: see
+    /// [`Self::release_refusal`]; a confirm-on-use entry also needs
+    /// `confirmed`, which only the agent's own prompt may set. Every issued
+    /// token counts towards the per-session usage limit.
+    pub(crate) fn issue_askpass_token(
+        &mut self,
+        entry_name: &str,
+        host: Option<&str>,
+        destination: Option<&str>,
+        policy: &EntryPolicy,
+        confirmed: bool,
+    ) -> Result<SensitiveString, AgentError> {
         self.prune_expired_askpass_leases();
+        if let Some(err) = self.release_refusal(entry_name, host, destination, policy) {
+            return Err(err);
+        }
+        if policy.confirm && !confirmed {
+            log_debug!("Entry '{}' requires confirmation before release", entry_name);
+            return Err(AgentError::ConfirmationRequired);
+        }
         let token = random_secret(ASKPASS_TOKEN_BYTES, "failed to generate askpass token")?;
+        if policy.max_uses.is_some() {
+            *self.entry_uses.entry(entry_name.to_string()).or_insert(0) += 1;
+        }
 
         self.askpass_leases.push(AskpassLease {
             token: token.clone(),
             entry_name: entry_name.to_string(),
+            host: host.map(str::to_string),
+            resolved_secret: None,
             expires_at: Instant::now() + ASKPASS_TOKEN_TTL,
         });
         log_debug!("Issued askpass token for entry '{}'", entry_name);
         Ok(token)
     }
 
-    /// Consume token and return bound entry name.
-    pub(crate) fn take_askpass_entry(&mut self, token: &str) -> Option<String> {
+    /// Consume token and return the bound entry.
+    pub(crate) fn take_askpass_entry(&mut self, token: &str) -> Option<AskpassGrant> {
         self.prune_expired_askpass_leases();
         let index = self.askpass_leases.iter().position(|lease| lease.token.expose_secret() == token)?;
         let lease = self.askpass_leases.swap_remove(index);
         log_debug!("Consumed askpass token for entry '{}'", lease.entry_name);
-        Some(lease.entry_name)
+        Some(lease.into_grant())
+    }
+
+    /// Issue an askpass token for an external provider reference.
+    ///
+    /// `resolved_secret` is handed to the token's single lookup, so a secret
+    /// resolved while nothing may be cached is not resolved twice.
+    pub(crate) fn issue_external_askpass_token(
+        &mut self,
+        reference: &str,
+        host: Option<&str>,
+        resolved_secret: Option<SensitiveString>,
+    ) -> Result<SensitiveString, AgentError> {
+        let token = self.issue_askpass_token(reference, host, None, &EntryPolicy::default(), false)?;
+        if let Some(lease) = self.askpass_leases.last_mut() {
+            lease.resolved_secret = resolved_secret;
+        }
+        Ok(token)
+    }
+
+    /// Policy external secrets are cached under: the active unlock session's.
+    /// Nothing is cached while the vault is locked, so clients never choose
+    /// their own cache timeouts.
+    pub(crate) fn external_cache_policy(&self) -> Option<UnlockPolicy> {
+        self.policy.clone().filter(|_| self.is_unlocked())
+    }
+
+    /// Return a cached external secret and refresh its idle timer.
+    pub(crate) fn cached_external_secret(&mut self, reference: &str) -> Option<SensitiveString> {
+        self.prune_expired_external_secrets();
+        let cached = self.external_secrets.iter_mut().find(|cached| cached.reference == reference)?;
+        cached.last_used_at = Instant::now();
+        log_debug!("Using cached external secret for '{}'", reference);
+        Some(cached.secret.clone())
+    }
+
+    /// Cache an external secret under `policy`, replacing any older value.
+    pub(crate) fn cache_external_secret(&mut self, reference: &str, secret: SensitiveString, policy: UnlockPolicy) {
+        self.external_secrets.retain(|cached| cached.reference != reference);
+        let now = Instant::now();
+        self.external_secrets.push(CachedExternalSecret {
+            reference: reference.to_string(),
+            secret,
+            cached_at: now,
+            last_used_at: now,
+            policy,
+        });
+        log_debug!("Cached external secret for '{}'", reference);
+    }
+
+    fn prune_expired_external_secrets(&mut self) {
+        let before = self.external_secrets.len();
+        self.external_secrets.retain(|cached| !cached.is_expired());
+        let removed = before.saturating_sub(self.external_secrets.len());
+        if removed > 0 {
+            log_debug!("Expired {} cached external secret(s)", removed);
+        }
+    }
+
+    /// Consume token only when it is bound to an external provider reference.
+    pub(crate) fn take_external_askpass_entry(&mut self, token: &str) -> Option<AskpassGrant> {
+        self.prune_expired_askpass_leases();
+        let index = self
+            .askpass_leases
+            .iter()
+            .position(|lease| lease.token.expose_secret() == token && is_external_secret_reference(&lease.entry_name))?;
+        let lease = self.askpass_leases.swap_remove(index);
+        log_debug!("Consumed askpass token for external reference '{}'", lease.entry_name);
+        Some(lease.into_grant())
     }
 
     fn prune_expired_askpass_leases(&mut self) {
@@ -205,9 +480,9 @@ fn random_secret(byte_len: usize, context: &'static str) -> Result<SensitiveStri
     Ok(secret)
 }
 
-/// Exponential backoff helper for idle-loop polling.
-pub(crate) fn next_idle_shutdown_poll_interval(current: Duration) -> Duration {
-    current.saturating_mul(2).min(AGENT_IDLE_SHUTDOWN_POLL_INTERVAL_MAX)
+/// Lock shared runtime state, recovering it if a worker panicked while holding it.
+pub(crate) fn lock_runtime(runtime: &Mutex<AgentRuntime>) -> MutexGuard<'_, AgentRuntime> {
+    runtime.lock().unwrap_or_else(PoisonError::into_inner)
 }
 
 #[cfg(test)]
@@ -1,14 +1,47 @@
 //! Unlock-agent server loop and request handling.
+//!
+//! Acceptor threads block on the agent endpoint and the SSH agent socket and
+//! hand each client to its own worker thread. Workers share one
+//! `AgentRuntime` behind a mutex that is only held to read or update session
+//! state; the unlock KDF and external providers run without it, so a stalled
+//! client or a slow provider cannot hold up other tabs. The calling thread
+//! sleeps until the next session or external-cache deadline or a worker event, waking
+//! at least once a second while unlocked to check the polled lock triggers.
 
+use super::autolock::{self, LockTrigger, ResumeDetector};
 use super::error::AgentError;
-use super::runtime::{AGENT_IDLE_SHUTDOWN_POLL_INTERVAL_MIN, AgentRuntime, next_idle_shutdown_poll_interval};
-use crate::auth::ipc::{self, AgentRequestPayload, AgentResponse, VaultStatus, VaultStatusEventKind};
-use crate::auth::secret::ExposeSecret;
-use crate::auth::vault::{self, VaultError, VaultPaths};
+use super::forward;
+use super::runtime::{AgentRuntime, AskpassGrant, lock_runtime};
+use super::ssh;
+use crate::auth::audit::{self, AuditEvent, AuditEventKind, AuditProcess};
+use crate::auth::ipc::{self, AgentEntryChange, AgentRequestPayload, AgentResponse, UnlockPolicy, VaultStatus, VaultStatusEventKind};
+use crate::auth::provider::{self, ExternalSecretRef, SecretReference, is_external_secret_reference};
+use crate::auth::secret::{ExposeSecret, SensitiveString};
+use crate::auth::vault::{self, EntryPolicy, UnlockedVault, VaultError, VaultPaths};
 use crate::log_debug;
-use interprocess::local_socket::traits::Listener as _;
+use interprocess::local_socket::{Listener as LocalSocketListener, Stream as LocalSocketStream, prelude::*};
 use std::io;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Duration;
+
+/// How long a connected client may take to send its request.
+const AGENT_CLIENT_READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// Error codes that count towards the `max_failed_authorizations` lock trigger.
+/// Entry policy refusals are expected answers, not failed authorizations.
+const FAILED_AUTHORIZATION_CODES: [&str; 2] = ["unauthorized_client", "invalid_or_expired_askpass_token"];
+/// Forwarded-request refusals that count towards the same trigger: the remote
+/// side asked for something the local user or the entry policy turned down.
+const FAILED_FORWARD_CODES: [&str; 5] = [
+    "unauthorized_client",
+    "confirmation_declined",
+    "entry_not_found",
+    "host_not_allowed",
+    "usage_limit_reached",
+];
 
 struct EndpointGuard {
     paths: VaultPaths,
@@ -18,6 +51,35 @@ impl Drop for EndpointGuard {
     fn drop(&mut self) {
         log_debug!("Cleaning up password vault agent endpoint");
         let _ = ipc::cleanup_endpoint(&self.paths);
+        let _ = ipc::cleanup_ssh_agent_socket(&self.paths);
+    }
+}
+
+/// Notifications from acceptor and worker threads to the server thread.
+enum ServerEvent {
+    /// A request changed runtime state; recompute the expiry deadline.
+    RuntimeChanged,
+    /// A worker observed the session expire.
+    Expired,
+    /// An explicit lock request asked the agent to exit.
+    Shutdown,
+    /// The agent endpoint can no longer accept clients.
+    Failed(io::Error),
+}
+
+/// State shared by every server thread.
+struct AgentServer {
+    paths: VaultPaths,
+    runtime: Mutex<AgentRuntime>,
+    events: mpsc::Sender<ServerEvent>,
+    stopping: AtomicBool,
+    /// Set from the `SIGUSR1` handler.
+    lock_signal: Arc<AtomicBool>,
+}
+
+impl AgentServer {
+    fn notify(&self, event: ServerEvent) {
+        let _ = self.events.send(event);
     }
 }
 
@@ -33,166 +95,648 @@ pub fn run_server() -> Result<(), AgentError> {
     };
     log_debug!("Password vault agent server started");
     let _endpoint_guard = EndpointGuard { paths: paths.clone() };
-    let mut runtime = AgentRuntime::new();
-    let mut idle_poll_interval = AGENT_IDLE_SHUTDOWN_POLL_INTERVAL_MIN;
+    let ssh_agent_listener = match ipc::bind_ssh_agent_listener(&paths) {
+        Ok(listener) => Some(listener),
+        Err(err) => {
+            log_debug!("SSH agent socket unavailable: {}", err);
+            None
+        }
+    };
+    serve(paths, listener, ssh_agent_listener)
+}
 
-    loop {
-        if runtime.expire_if_needed() {
-            broadcast_vault_status_event(&paths, VaultStatusEventKind::Locked, runtime.status(&paths));
-            log_debug!("Password vault agent exiting after session expiry");
-            return Ok(());
+/// Serve clients on bound listeners until the session expires or a lock request arrives.
+pub(crate) fn serve(paths: VaultPaths, listener: LocalSocketListener, ssh_agent_listener: Option<UnixListener>) -> Result<(), AgentError> {
+    let (events, event_rx) = mpsc::channel();
+    let server = Arc::new(AgentServer {
+        paths,
+        runtime: Mutex::new(AgentRuntime::new()),
+        events,
+        stopping: AtomicBool::new(false),
+        lock_signal: Arc::new(AtomicBool::new(false)),
+    });
+    let signal_id = match signal_hook::flag::register(signal_hook::consts::SIGUSR1, Arc::clone(&server.lock_signal)) {
+        Ok(signal_id) => Some(signal_id),
+        Err(err) => {
+            log_debug!("Failed to install SIGUSR1 lock handler: {}", err);
+            None
         }
+    };
+
+    let ipc_server = Arc::clone(&server);
+    thread::Builder::new()
+        .name("cossh-agent-accept".to_string())
+        .spawn(move || accept_agent_clients(&ipc_server, &listener))?;
+    if let Some(ssh_agent_listener) = ssh_agent_listener {
+        let ssh_server = Arc::clone(&server);
+        thread::Builder::new()
+            .name("cossh-ssh-agent-accept".to_string())
+            .spawn(move || accept_ssh_agent_clients(&ssh_server, &ssh_agent_listener))?;
+    }
+
+    let result = wait_for_server_exit(&server, &event_rx);
+    stop_acceptors(&server);
+    if let Some(signal_id) = signal_id {
+        signal_hook::low_level::unregister(signal_id);
+    }
+    result
+}
 
-        let mut stream = match listener.accept() {
-            Ok(connection) => {
-                idle_poll_interval = AGENT_IDLE_SHUTDOWN_POLL_INTERVAL_MIN;
-                connection
+fn wait_for_server_exit(server: &AgentServer, event_rx: &mpsc::Receiver<ServerEvent>) -> Result<(), AgentError> {
+    let mut resume_detector = ResumeDetector::new();
+    let mut watching_triggers = false;
+    loop {
+        let deadline = {
+            let mut runtime = lock_runtime(&server.runtime);
+            if runtime.expire_if_needed() {
+                drop(runtime);
+                finish_session_expiry(server);
+                log_debug!("Password vault agent exiting after session expiry");
+                return Ok(());
             }
-            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
-                thread::sleep(idle_poll_interval);
-                idle_poll_interval = next_idle_shutdown_poll_interval(idle_poll_interval);
-                continue;
+            if !runtime.is_unlocked() {
+                watching_triggers = false;
+            } else if !watching_triggers {
+                // Time spent locked must not look like a suspend.
+                resume_detector = ResumeDetector::new();
+                watching_triggers = true;
+            } else if let Some(trigger) = polled_lock_trigger(server, &mut runtime, &mut resume_detector) {
+                lock_for_trigger(&server.paths, &mut runtime, trigger);
+                log_debug!("Password vault agent exiting after lock trigger {:?}", trigger);
+                return Ok(());
             }
-            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
-            Err(err) => return Err(AgentError::Io(err)),
+            // External secrets are only cached while unlocked, so nothing
+            // expires while the vault is locked.
+            runtime.time_until_expiry().map(|remaining| remaining.min(autolock::LOCK_TRIGGER_POLL_INTERVAL))
+        };
+
+        // Sleep until the session deadline, or until a worker reports a change.
+        let event = match deadline {
+            Some(deadline) => event_rx.recv_timeout(deadline),
+            None => event_rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
         };
+        match event {
+            Ok(ServerEvent::RuntimeChanged) | Err(RecvTimeoutError::Timeout) => {}
+            Ok(ServerEvent::Expired) => {
+                finish_session_expiry(server);
+                log_debug!("Password vault agent exiting after session expiry during request handling");
+                return Ok(());
+            }
+            Ok(ServerEvent::Shutdown) | Err(RecvTimeoutError::Disconnected) => {
+                log_debug!("Password vault agent exiting after explicit lock request");
+                return Ok(());
+            }
+            Ok(ServerEvent::Failed(err)) => return Err(AgentError::Io(err)),
+        }
+    }
+}
+
+/// Check the lock triggers that need polling rather than a request.
+fn polled_lock_trigger(server: &AgentServer, runtime: &mut AgentRuntime, resume_detector: &mut ResumeDetector) -> Option<LockTrigger> {
+    let triggers = runtime.lock_triggers()?.clone();
+    let signalled = server.lock_signal.swap(false, Ordering::AcqRel);
+    let resumed = resume_detector.observe();
+    if triggers.signal && signalled {
+        return Some(LockTrigger::Signal);
+    }
+    if triggers.resume && resumed {
+        return Some(LockTrigger::Resume);
+    }
+    if triggers.tui_exit && runtime.prune_exited_tuis(autolock::process_is_running) {
+        return Some(LockTrigger::TuiExit);
+    }
+    None
+}
+
+/// Lock for `trigger`, auditing and broadcasting the transition.
+pub(crate) fn lock_for_trigger(paths: &VaultPaths, runtime: &mut AgentRuntime, trigger: LockTrigger) {
+    if runtime.lock() {
+        log_debug!("Password vault locked by trigger {:?}", trigger);
+        audit::record_event(paths, AuditEvent::new(AuditEventKind::Locked).with_detail(trigger.audit_detail()));
+        broadcast_vault_status_event(paths, VaultStatusEventKind::Locked, runtime.status(paths));
+    }
+}
+
+fn finish_session_expiry(server: &AgentServer) {
+    record_session_expiry(&server.paths);
+    let status = lock_runtime(&server.runtime).status(&server.paths);
+    broadcast_vault_status_event(&server.paths, VaultStatusEventKind::Locked, status);
+}
+
+/// Wake blocked acceptor threads so they observe `stopping` and exit.
+fn stop_acceptors(server: &AgentServer) {
+    server.stopping.store(true, Ordering::Release);
+    let _ = ipc::connect(&server.paths);
+    let _ = UnixStream::connect(ipc::ssh_agent_socket_path(&server.paths));
+}
 
-        let request = match ipc::read_request(&mut stream) {
-            Ok(request) => request,
+fn accept_agent_clients(server: &Arc<AgentServer>, listener: &LocalSocketListener) {
+    loop {
+        let stream = listener.accept();
+        if server.stopping.load(Ordering::Acquire) {
+            return;
+        }
+        match stream {
+            Ok(stream) => spawn_worker(server, "cossh-agent-client", move |server| handle_agent_client(server, stream)),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
             Err(err) => {
-                let response = AgentResponse::Error {
-                    status: runtime.status(&paths),
-                    code: "invalid_request".to_string(),
-                    message: format!("failed to read request: {err}"),
-                };
-                let _ = ipc::write_response(&mut stream, &response);
-                continue;
+                server.notify(ServerEvent::Failed(err));
+                return;
             }
-        };
+        }
+    }
+}
+
+fn accept_ssh_agent_clients(server: &Arc<AgentServer>, listener: &UnixListener) {
+    loop {
+        let stream = listener.accept();
+        if server.stopping.load(Ordering::Acquire) {
+            return;
+        }
+        match stream {
+            Ok((stream, _)) => spawn_worker(server, "cossh-ssh-agent-client", move |server| handle_ssh_agent_client(server, stream)),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => {
+                // The unlock agent keeps working without the SSH agent socket.
+                log_debug!("SSH agent socket stopped accepting clients: {}", err);
+                return;
+            }
+        }
+    }
+}
 
-        if runtime.expire_if_needed() {
-            broadcast_vault_status_event(&paths, VaultStatusEventKind::Locked, runtime.status(&paths));
+fn spawn_worker(server: &Arc<AgentServer>, name: &str, work: impl FnOnce(&AgentServer) + Send + 'static) {
+    let server = Arc::clone(server);
+    if let Err(err) = thread::Builder::new().name(name.to_string()).spawn(move || work(&server)) {
+        log_debug!("Failed to spawn password vault agent worker: {}", err);
+    }
+}
+
+fn handle_agent_client(server: &AgentServer, mut stream: LocalSocketStream) {
+    let paths = &server.paths;
+    if let Err(err) = stream.set_recv_timeout(Some(AGENT_CLIENT_READ_TIMEOUT)) {
+        log_debug!("Failed to set password vault agent client timeout: {}", err);
+    }
+    let request = match ipc::read_request(&mut stream) {
+        Ok(request) => request,
+        Err(err) => {
             let response = AgentResponse::Error {
-                status: runtime.status(&paths),
-                code: "locked".to_string(),
-                message: "password vault session expired".to_string(),
+                status: lock_runtime(&server.runtime).status(paths),
+                code: "invalid_request".to_string(),
+                message: format!("failed to read request: {err}"),
             };
             let _ = ipc::write_response(&mut stream, &response);
-            log_debug!("Password vault agent exiting after session expiry during request handling");
-            return Ok(());
+            return;
         }
+    };
 
-        let should_shutdown = matches!(&request.payload, AgentRequestPayload::Lock);
-        log_debug!("Handling password vault agent request '{}'", request.payload.debug_name());
-        let peer_trust = ipc::agent_peer_trust(&stream);
-        let response = handle_request(&paths, &mut runtime, request, peer_trust);
-        let _ = ipc::write_response(&mut stream, &response);
-        if should_shutdown {
-            log_debug!("Password vault agent exiting after explicit lock request");
-            return Ok(());
+    let mut should_shutdown = matches!(&request.payload, AgentRequestPayload::Lock);
+    if matches!(&request.payload, AgentRequestPayload::Unlock { .. }) {
+        // A signal received while locked must not lock the new session.
+        server.lock_signal.store(false, Ordering::Release);
+    }
+    log_debug!("Handling password vault agent request '{}'", request.payload.debug_name());
+    let peer = ipc::agent_peer(&stream);
+    let (was_unlocked, expired) = {
+        let mut runtime = lock_runtime(&server.runtime);
+        (runtime.is_unlocked(), runtime.expire_if_needed())
+    };
+    let response = if expired {
+        AgentResponse::Error {
+            status: lock_runtime(&server.runtime).status(paths),
+            code: "locked".to_string(),
+            message: "password vault session expired".to_string(),
+        }
+    } else {
+        let response = handle_request(paths, &server.runtime, request, peer);
+        // A request-driven lock trigger fired; exit like an explicit lock.
+        should_shutdown |= was_unlocked && !lock_runtime(&server.runtime).is_unlocked();
+        response
+    };
+    let _ = ipc::write_response(&mut stream, &response);
+
+    server.notify(if expired {
+        ServerEvent::Expired
+    } else if should_shutdown {
+        ServerEvent::Shutdown
+    } else {
+        ServerEvent::RuntimeChanged
+    });
+}
+
+fn handle_ssh_agent_client(server: &AgentServer, stream: UnixStream) {
+    match ssh::serve_ssh_agent_connection(&server.paths, &server.runtime, stream) {
+        Ok(true) => server.notify(ServerEvent::Expired),
+        Ok(false) => server.notify(ServerEvent::RuntimeChanged),
+        Err(err) => log_debug!("SSH agent client connection ended with error: {}", err),
+    }
+}
+
+pub(crate) fn handle_request(paths: &VaultPaths, runtime: &Mutex<AgentRuntime>, request: ipc::AgentRequest, peer: ipc::AgentPeer) -> AgentResponse {
+    let failed_codes: &[&str] = match &request.payload {
+        AgentRequestPayload::AuthorizeAskpass { .. } | AgentRequestPayload::GetSecret { .. } => &FAILED_AUTHORIZATION_CODES,
+        AgentRequestPayload::ForwardedSecret { .. } => &FAILED_FORWARD_CODES,
+        _ => &[],
+    };
+    let response = handle_request_payload(paths, runtime, request, peer);
+    if !failed_codes.is_empty() {
+        apply_failed_authorization_trigger(paths, &mut lock_runtime(runtime), &response, failed_codes);
+    }
+    response
+}
+
+/// Count refused askpass authorizations and lock once the policy limit is reached.
+fn apply_failed_authorization_trigger(paths: &VaultPaths, runtime: &mut AgentRuntime, response: &AgentResponse, failed_codes: &[&str]) {
+    match response {
+        AgentResponse::AskpassAuthorized { .. } | AgentResponse::Secret { .. } => runtime.reset_failed_authorizations(),
+        AgentResponse::Error { code, .. } if failed_codes.contains(&code.as_str()) && runtime.record_failed_authorization() => {
+            lock_for_trigger(paths, runtime, LockTrigger::FailedAuthorizations);
         }
+        _ => {}
     }
 }
 
-pub(crate) fn handle_request(paths: &VaultPaths, runtime: &mut AgentRuntime, request: ipc::AgentRequest, peer_trust: ipc::AgentPeerTrust) -> AgentResponse {
+/// Dispatch one request. Unlocks and external providers run without the
+/// runtime lock; every other request holds it only while it is answered.
+fn handle_request_payload(paths: &VaultPaths, runtime: &Mutex<AgentRuntime>, request: ipc::AgentRequest, peer: ipc::AgentPeer) -> AgentResponse {
     match request.payload {
-        AgentRequestPayload::Status => AgentResponse::Status { status: runtime.status(paths) },
-        AgentRequestPayload::Lock => {
-            if runtime.lock() {
-                broadcast_vault_status_event(paths, VaultStatusEventKind::Locked, runtime.status(paths));
+        AgentRequestPayload::Status => AgentResponse::Status {
+            status: lock_runtime(runtime).status(paths),
+        },
+        AgentRequestPayload::Lock => lock_session(paths, &mut lock_runtime(runtime), peer),
+        AgentRequestPayload::AttachTui => update_attached_tui(paths, &mut lock_runtime(runtime), peer, true),
+        AgentRequestPayload::DetachTui => update_attached_tui(paths, &mut lock_runtime(runtime), peer, false),
+        AgentRequestPayload::Unlock { master_password, policy } => handle_unlock(paths, runtime, master_password.expose_secret(), policy, peer),
+        AgentRequestPayload::AuthorizeAskpass { name, host, destination } => {
+            if !peer.trust.is_trusted() {
+                return unauthorized_client_response(&lock_runtime(runtime), paths);
+            }
+            if is_external_secret_reference(&name) {
+                return authorize_external_askpass(paths, runtime, &name, host.as_deref());
+            }
+            authorize_vault_askpass(paths, runtime, &name, host.as_deref(), destination.as_deref(), peer, ssh::askpass_confirmation)
+        }
+        AgentRequestPayload::EntryStatus { name } => entry_status(paths, &lock_runtime(runtime), name),
+        AgentRequestPayload::GetSecret { token } => {
+            if !peer.trust.is_trusted() {
+                return unauthorized_client_response(&lock_runtime(runtime), paths);
+            }
+            // External references do not depend on the vault lock state.
+            let external_grant = lock_runtime(runtime).take_external_askpass_entry(token.expose_secret());
+            if let Some(grant) = external_grant {
+                return serve_external_secret(paths, runtime, grant, peer);
+            }
+            serve_vault_secret(paths, &mut lock_runtime(runtime), token.expose_secret(), peer)
+        }
+        AgentRequestPayload::ForwardedSecret { name, host, destination } => {
+            // The alias is only trusted because a local `cossh` relays it.
+            if !peer.trust.is_trusted() {
+                return unauthorized_client_response(&lock_runtime(runtime), paths);
+            }
+            forward::serve_forwarded_request(paths, runtime, &name, &host, destination.as_deref(), peer.pid)
+        }
+        AgentRequestPayload::ChangeEntry { change } => {
+            if !peer.trust.is_trusted() {
+                return unauthorized_client_response(&lock_runtime(runtime), paths);
             }
+            change_entry(paths, &mut lock_runtime(runtime), change)
+        }
+    }
+}
+
+fn lock_session(paths: &VaultPaths, runtime: &mut AgentRuntime, peer: ipc::AgentPeer) -> AgentResponse {
+    if runtime.lock() {
+        record_peer_event(paths, peer, AuditEvent::new(AuditEventKind::Locked).with_detail("explicit"));
+        broadcast_vault_status_event(paths, VaultStatusEventKind::Locked, runtime.status(paths));
+    }
+    AgentResponse::Success {
+        status: runtime.status(paths),
+        message: "password vault locked".to_string(),
+    }
+}
+
+fn update_attached_tui(paths: &VaultPaths, runtime: &mut AgentRuntime, peer: ipc::AgentPeer, attach: bool) -> AgentResponse {
+    let (true, Some(pid)) = (peer.trust.is_trusted(), peer.pid) else {
+        return unauthorized_client_response(runtime, paths);
+    };
+    if attach {
+        runtime.attach_tui(pid);
+    } else if runtime.detach_tui(pid) && runtime.lock_triggers().is_some_and(|triggers| triggers.tui_exit) {
+        lock_for_trigger(paths, runtime, LockTrigger::TuiExit);
+    }
+    AgentResponse::Status { status: runtime.status(paths) }
+}
+
+/// Derive the data key without the runtime lock, then install it.
+fn handle_unlock(paths: &VaultPaths, runtime: &Mutex<AgentRuntime>, master_password: &str, policy: UnlockPolicy, peer: ipc::AgentPeer) -> AgentResponse {
+    let unlocked = vault::unlock_with_password_and_paths(paths, master_password);
+    let mut runtime = lock_runtime(runtime);
+    match unlocked {
+        Ok(unlocked) => {
+            runtime.unlock(unlocked.data_key_copy(), policy);
+            record_peer_event(paths, peer, AuditEvent::new(AuditEventKind::Unlocked).with_detail("agent"));
+            log_debug!("Password vault agent accepted unlock request");
+            let status = runtime.status(paths);
+            broadcast_vault_status_event(paths, VaultStatusEventKind::Unlocked, status.clone());
             AgentResponse::Success {
-                status: runtime.status(paths),
-                message: "password vault locked".to_string(),
+                status,
+                message: "password vault unlocked".to_string(),
+            }
+        }
+        Err(err) => {
+            if matches!(err, VaultError::InvalidMasterPassword) {
+                record_peer_event(paths, peer, AuditEvent::new(AuditEventKind::UnlockFailed).with_detail("agent"));
             }
+            agent_error_response(&runtime, paths, err)
+        }
+    }
+}
+
+/// Authorize one vault entry release. A confirm-on-use entry is confirmed
+/// here through `confirm`, never on the word of the client; `confirm`
+/// returns `None` when no prompt can be shown.
+fn authorize_vault_askpass(
+    paths: &VaultPaths,
+    runtime: &Mutex<AgentRuntime>,
+    name: &str,
+    host: Option<&str>,
+    destination: Option<&str>,
+    peer: ipc::AgentPeer,
+    confirm: impl FnOnce(&str) -> Option<bool>,
+) -> AgentResponse {
+    let entry_policy = {
+        let runtime = lock_runtime(runtime);
+        let Some(unlocked) = runtime.unlocked_vault(paths) else {
+            return locked_response(&runtime, paths);
+        };
+        match vault::entry_exists_with_paths(paths, name) {
+            Ok(true) => {}
+            Ok(false) => return entry_not_found_response(&runtime, paths),
+            Err(err) => return agent_error_response(&runtime, paths, err),
         }
-        AgentRequestPayload::Unlock { master_password, policy } => match vault::unlock_with_password_and_paths(paths, master_password.expose_secret()) {
-            Ok(unlocked) => {
-                runtime.unlock(unlocked.data_key_copy(), policy);
-                log_debug!("Password vault agent accepted unlock request");
-                let status = runtime.status(paths);
-                broadcast_vault_status_event(paths, VaultStatusEventKind::Unlocked, status.clone());
-                AgentResponse::Success {
-                    status,
-                    message: "password vault unlocked".to_string(),
-                }
+        match entry_release_policy(&unlocked, name) {
+            Ok(entry_policy) => entry_policy,
+            Err(err) => return agent_error_response(&runtime, paths, err),
+        }
+    };
+
+    let confirmed = entry_policy.confirm && {
+        // Refuse what the policy would refuse anyway before asking the user.
+        let refusal = lock_runtime(runtime).release_refusal(name, host, destination, &entry_policy);
+        if let Some(err) = refusal {
+            return entry_policy_error_response(&lock_runtime(runtime), paths, peer, name, host, err);
+        }
+        let prompt = match host {
+            Some(host) => format!("Release password vault entry '{name}' for host '{host}'?"),
+            None => format!("Release password vault entry '{name}'?"),
+        };
+        let err = match confirm(&prompt) {
+            Some(true) => None,
+            Some(false) => Some(AgentError::ConfirmationDeclined),
+            None => Some(AgentError::ConfirmationUnavailable),
+        };
+        if let Some(err) = err {
+            log_debug!("Release of entry '{}' was not confirmed: {}", name, err);
+            return entry_policy_error_response(&lock_runtime(runtime), paths, peer, name, host, err);
+        }
+        true
+    };
+
+    let mut runtime = lock_runtime(runtime);
+    // The vault may have been locked while the user was confirming.
+    if runtime.unlocked_vault(paths).is_none() {
+        return locked_response(&runtime, paths);
+    }
+    match runtime.issue_askpass_token(name, host, destination, &entry_policy, confirmed) {
+        Ok(token) => {
+            runtime.touch();
+            AgentResponse::AskpassAuthorized {
+                status: runtime.status(paths),
+                token,
             }
-            Err(err) => agent_error_response(runtime, paths, err),
+        }
+        Err(err) => entry_policy_error_response(&runtime, paths, peer, name, host, err),
+    }
+}
+
+fn entry_status(paths: &VaultPaths, runtime: &AgentRuntime, name: String) -> AgentResponse {
+    match vault::entry_exists_with_paths(paths, &name) {
+        Ok(exists) => AgentResponse::EntryStatus {
+            status: runtime.status(paths),
+            name,
+            exists,
         },
-        AgentRequestPayload::AuthorizeAskpass { name } => {
-            if !peer_trust.is_trusted() {
-                return unauthorized_client_response(runtime, paths);
-            }
-            let Some(_unlocked) = runtime.unlocked_vault(paths) else {
-                return AgentResponse::Error {
-                    status: runtime.status(paths),
-                    code: "locked".to_string(),
-                    message: "password vault is locked".to_string(),
-                };
-            };
-            match vault::entry_exists_with_paths(paths, &name) {
-                Ok(true) => match runtime.issue_askpass_token(&name) {
-                    Ok(token) => {
-                        runtime.touch();
-                        AgentResponse::AskpassAuthorized {
-                            status: runtime.status(paths),
-                            token,
-                        }
-                    }
-                    Err(err) => AgentResponse::Error {
-                        status: runtime.status(paths),
-                        code: "askpass_token_error".to_string(),
-                        message: err.to_string(),
-                    },
-                },
-                Ok(false) => AgentResponse::Error {
-                    status: runtime.status(paths),
-                    code: "entry_not_found".to_string(),
-                    message: "password vault entry was not found".to_string(),
-                },
-                Err(err) => agent_error_response(runtime, paths, err),
-            }
-        }
-        AgentRequestPayload::EntryStatus { name } => match vault::entry_exists_with_paths(paths, &name) {
-            Ok(exists) => AgentResponse::EntryStatus {
+        Err(err) => agent_error_response(runtime, paths, err),
+    }
+}
+
+fn change_entry(paths: &VaultPaths, runtime: &mut AgentRuntime, change: AgentEntryChange) -> AgentResponse {
+    let Some(unlocked) = runtime.unlocked_vault(paths) else {
+        return locked_response(runtime, paths);
+    };
+    let result = match &change {
+        AgentEntryChange::Store { name, secret } => unlocked.store_secret(name, secret.expose_secret()),
+        AgentEntryChange::Replace { name, secret } => unlocked.rotate_secret(name, secret.expose_secret()),
+        AgentEntryChange::Rename { name, new_name } => unlocked.rename_entry(name, new_name),
+        AgentEntryChange::Remove { name } => unlocked.remove_entry(name),
+    };
+    match result {
+        Ok(()) => {
+            runtime.touch();
+            AgentResponse::Success {
+                status: runtime.status(paths),
+                message: "password vault entry changed".to_string(),
+            }
+        }
+        Err(err) => agent_error_response(runtime, paths, err),
+    }
+}
+
+fn serve_vault_secret(paths: &VaultPaths, runtime: &mut AgentRuntime, token: &str, peer: ipc::AgentPeer) -> AgentResponse {
+    let Some(unlocked) = runtime.unlocked_vault(paths) else {
+        return locked_response(runtime, paths);
+    };
+    // Tokens are single-use and are consumed on lookup.
+    let Some(AskpassGrant { entry_name: name, host, .. }) = runtime.take_askpass_entry(token) else {
+        return AgentResponse::Error {
+            status: runtime.status(paths),
+            code: "invalid_or_expired_askpass_token".to_string(),
+            message: "invalid or expired askpass token".to_string(),
+        };
+    };
+    match unlocked.get_secret(&name) {
+        Ok(secret) => {
+            runtime.touch();
+            let event = AuditEvent::new(AuditEventKind::EntryRead).with_entry(&name).with_host(host.as_deref());
+            if let Err(message) = record_peer_release(paths, peer, event) {
+                return audit_failed_response(runtime, paths, message);
+            }
+            log_debug!("Password vault agent served secret for entry '{}'", name);
+            AgentResponse::Secret {
                 status: runtime.status(paths),
                 name,
-                exists,
-            },
-            Err(err) => agent_error_response(runtime, paths, err),
+                secret,
+            }
+        }
+        Err(err) => agent_error_response(runtime, paths, err),
+    }
+}
+
+/// Return a cached external secret, or run the provider without holding the
+/// runtime lock and cache what it returns while the vault is unlocked.
+fn resolve_external_secret_cached(
+    runtime: &Mutex<AgentRuntime>,
+    name: &str,
+    reference: &ExternalSecretRef,
+) -> Result<SensitiveString, provider::SecretProviderError> {
+    if let Some(secret) = lock_runtime(runtime).cached_external_secret(name) {
+        return Ok(secret);
+    }
+
+    let secret = provider::resolve_external_secret(reference)?;
+    let mut runtime = lock_runtime(runtime);
+    if let Some(policy) = runtime.external_cache_policy() {
+        runtime.cache_external_secret(name, secret.clone(), policy);
+    }
+    Ok(secret)
+}
+
+fn authorize_external_askpass(paths: &VaultPaths, runtime: &Mutex<AgentRuntime>, name: &str, host: Option<&str>) -> AgentResponse {
+    let Some(SecretReference::External(reference)) = SecretReference::parse(name) else {
+        return invalid_external_reference_response(&lock_runtime(runtime), paths, name);
+    };
+    // Resolve up front so provider failures surface at launch time rather
+    // than inside the askpass helper.
+    let secret = match resolve_external_secret_cached(runtime, name, &reference) {
+        Ok(secret) => secret,
+        Err(err) => {
+            log_debug!("External secret provider failed for '{}': {}", name, err);
+            return secret_provider_error_response(&lock_runtime(runtime), paths, err);
+        }
+    };
+
+    let mut runtime = lock_runtime(runtime);
+    // Without an unlock session nothing was cached, so the token carries the secret.
+    let resolved_secret = runtime.external_cache_policy().is_none().then_some(secret);
+    match runtime.issue_external_askpass_token(name, host, resolved_secret) {
+        Ok(token) => AgentResponse::AskpassAuthorized {
+            status: runtime.status(paths),
+            token,
         },
-        AgentRequestPayload::GetSecret { token } => {
-            if !peer_trust.is_trusted() {
-                return unauthorized_client_response(runtime, paths);
-            }
-            let Some(unlocked) = runtime.unlocked_vault(paths) else {
-                return AgentResponse::Error {
-                    status: runtime.status(paths),
-                    code: "locked".to_string(),
-                    message: "password vault is locked".to_string(),
-                };
-            };
-            // Tokens are single-use and are consumed on lookup.
-            let Some(name) = runtime.take_askpass_entry(token.expose_secret()) else {
-                return AgentResponse::Error {
-                    status: runtime.status(paths),
-                    code: "invalid_or_expired_askpass_token".to_string(),
-                    message: "invalid or expired askpass token".to_string(),
-                };
-            };
-            match unlocked.get_secret(&name) {
-                Ok(secret) => {
-                    runtime.touch();
-                    log_debug!("Password vault agent served secret for entry '{}'", name);
-                    AgentResponse::Secret {
-                        status: runtime.status(paths),
-                        name,
-                        secret,
-                    }
-                }
-                Err(err) => agent_error_response(runtime, paths, err),
+        Err(err) => AgentResponse::Error {
+            status: runtime.status(paths),
+            code: "askpass_token_error".to_string(),
+            message: err.to_string(),
+        },
+    }
+}
+
+fn serve_external_secret(paths: &VaultPaths, runtime: &Mutex<AgentRuntime>, grant: AskpassGrant, peer: ipc::AgentPeer) -> AgentResponse {
+    let AskpassGrant {
+        entry_name: name,
+        host,
+        resolved_secret,
+    } = grant;
+    let Some(SecretReference::External(reference)) = SecretReference::parse(&name) else {
+        return invalid_external_reference_response(&lock_runtime(runtime), paths, &name);
+    };
+
+    // The cache may have expired between authorization and lookup.
+    let resolved = match resolved_secret {
+        Some(secret) => Ok(secret),
+        None => resolve_external_secret_cached(runtime, &name, &reference),
+    };
+    match resolved {
+        Ok(secret) => {
+            let event = AuditEvent::new(AuditEventKind::EntryRead)
+                .with_entry(&name)
+                .with_host(host.as_deref())
+                .with_detail("external provider");
+            let runtime = lock_runtime(runtime);
+            if let Err(message) = record_peer_release(paths, peer, event) {
+                return audit_failed_response(&runtime, paths, message);
+            }
+            log_debug!("Password vault agent served external secret for '{}'", name);
+            AgentResponse::Secret {
+                status: runtime.status(paths),
+                name,
+                secret,
             }
         }
+        Err(err) => secret_provider_error_response(&lock_runtime(runtime), paths, err),
+    }
+}
+
+/// Release policy of a vault entry; key passphrase entries are never restricted.
+pub(super) fn entry_release_policy(unlocked: &UnlockedVault, name: &str) -> Result<EntryPolicy, VaultError> {
+    match unlocked.entry_policy(name) {
+        Err(VaultError::WrongEntryKind(_)) => Ok(EntryPolicy::default()),
+        result => result,
+    }
+}
+
+fn entry_policy_error_response(
+    runtime: &AgentRuntime,
+    paths: &VaultPaths,
+    peer: ipc::AgentPeer,
+    name: &str,
+    host: Option<&str>,
+    err: AgentError,
+) -> AgentResponse {
+    let code = match err {
+        AgentError::ConfirmationRequired => "confirmation_required",
+        AgentError::ConfirmationDeclined => "confirmation_declined",
+        AgentError::ConfirmationUnavailable => "confirmation_unavailable",
+        AgentError::HostNotAllowed => "host_not_allowed",
+        AgentError::UsageLimitReached => "usage_limit_reached",
+        _ => "askpass_token_error",
+    };
+    if matches!(
+        err,
+        AgentError::HostNotAllowed | AgentError::UsageLimitReached | AgentError::ConfirmationDeclined | AgentError::ConfirmationUnavailable
+    ) {
+        record_peer_event(
+            paths,
+            peer,
+            AuditEvent::new(AuditEventKind::EntryDenied).with_entry(name).with_host(host).with_detail(code),
+        );
+    }
+    AgentResponse::Error {
+        status: runtime.status(paths),
+        code: code.to_string(),
+        message: err.to_string(),
+    }
+}
+
+fn invalid_external_reference_response(runtime: &AgentRuntime, paths: &VaultPaths, name: &str) -> AgentResponse {
+    AgentResponse::Error {
+        status: runtime.status(paths),
+        code: "secret_provider_error".to_string(),
+        message: format!("invalid secret reference '{name}'"),
+    }
+}
+
+fn secret_provider_error_response(runtime: &AgentRuntime, paths: &VaultPaths, err: provider::SecretProviderError) -> AgentResponse {
+    AgentResponse::Error {
+        status: runtime.status(paths),
+        code: "secret_provider_error".to_string(),
+        message: err.to_string(),
+    }
+}
+
+fn locked_response(runtime: &AgentRuntime, paths: &VaultPaths) -> AgentResponse {
+    AgentResponse::Error {
+        status: runtime.status(paths),
+        code: "locked".to_string(),
+        message: "password vault is locked".to_string(),
+    }
+}
+
+fn entry_not_found_response(runtime: &AgentRuntime, paths: &VaultPaths) -> AgentResponse {
+    AgentResponse::Error {
+        status: runtime.status(paths),
+        code: "entry_not_found".to_string(),
+        message: "password vault entry was not found".to_string(),
     }
 }
 
@@ -204,13 +748,33 @@ fn unauthorized_client_response(runtime: &AgentRuntime, paths: &VaultPaths) -> A
     }
 }
 
+fn record_peer_event(paths: &VaultPaths, peer: ipc::AgentPeer, event: AuditEvent) {
+    audit::record_event(paths, event.with_process(peer.pid.map(AuditProcess::from_pid)));
+}
+
+fn record_peer_release(paths: &VaultPaths, peer: ipc::AgentPeer, event: AuditEvent) -> Result<(), String> {
+    audit::record_release_event(paths, event.with_process(peer.pid.map(AuditProcess::from_pid)))
+}
+
+fn audit_failed_response(runtime: &AgentRuntime, paths: &VaultPaths, message: String) -> AgentResponse {
+    AgentResponse::Error {
+        status: runtime.status(paths),
+        code: "audit_failed".to_string(),
+        message,
+    }
+}
+
+fn record_session_expiry(paths: &VaultPaths) {
+    audit::record_event(paths, AuditEvent::new(AuditEventKind::Locked).with_detail("session expired"));
+}
+
 fn broadcast_vault_status_event(paths: &VaultPaths, kind: VaultStatusEventKind, status: VaultStatus) {
     if let Err(err) = ipc::broadcast_vault_status_event(paths, kind, status) {
         log_debug!("Failed to broadcast password vault status event: {}", err);
     }
 }
 
-fn agent_error_response(runtime: &mut AgentRuntime, paths: &VaultPaths, err: VaultError) -> AgentResponse {
+fn agent_error_response(runtime: &AgentRuntime, paths: &VaultPaths, err: VaultError) -> AgentResponse {
     let (code, message) = match err {
         VaultError::EntryNotFound => ("entry_not_found", err.to_string()),
         VaultError::VaultNotInitialized => ("vault_not_initialized", err.to_string()),
@@ -0,0 +1,422 @@
+//! OpenSSH agent protocol served from vault SSH key entries.
+//!
+//! The unlock agent listens on `run/ssh-agent.sock` next to its own endpoint.
+//! Keys are only listed or used while the vault is unlocked, so they relock
+//! with the same idle and session timeouts as passwords. Public keys of vault
+//! key entries are cached for the unlock session; a private key is decrypted
+//! only to sign. Keys added with `ssh-add` stay in agent memory until the
+//! vault locks or their `-t` lifetime passes, and are never written to the
+//! vault; `-c` asks for confirmation on every use.
+//!
+//! The shared runtime is only locked to snapshot the session and to record
+//! key use, so confirmation prompts and signing never block other clients.
+
+use super::runtime::{AgentRuntime, CachedSshPublicKey, SessionSshKey, lock_runtime};
+use crate::auth::audit::{self, AuditEvent, AuditEventKind, AuditProcess};
+use crate::auth::secret::ExposeSecret;
+use crate::auth::vault::{self, SshKeyConstraints, UnlockedVault, VaultPaths};
+use crate::log_debug;
+use rsa::signature::{SignatureEncoding, Signer};
+use ssh_encoding::{Decode, Encode, Reader};
+use ssh_key::private::KeypairData;
+use ssh_key::sha2::Sha256;
+use ssh_key::{Algorithm, HashAlg, PrivateKey, Signature};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENT_SUCCESS: u8 = 6;
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE: u8 = 14;
+const SSH_AGENTC_ADD_IDENTITY: u8 = 17;
+const SSH_AGENTC_ADD_ID_CONSTRAINED: u8 = 25;
+const SSH_AGENT_CONSTRAIN_LIFETIME: u8 = 1;
+const SSH_AGENT_CONSTRAIN_CONFIRM: u8 = 2;
+const SSH_AGENT_RSA_SHA2_256: u32 = 2;
+const SSH_AGENT_RSA_SHA2_512: u32 = 4;
+/// Upper bound on one agent message, matching OpenSSH's own limit.
+const SSH_AGENT_MAX_MESSAGE_LEN: usize = 256 * 1024;
+/// How long a client may stay silent between requests before it is dropped.
+const SSH_AGENT_CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One key that may currently be served.
+struct LiveKey {
+    name: String,
+    blob: Vec<u8>,
+    constraints: SshKeyConstraints,
+    source: KeySource,
+}
+
+/// Where the private half of a [`LiveKey`] comes from.
+enum KeySource {
+    /// A vault key entry, decrypted only when it signs.
+    Vault,
+    /// A key added with `ssh-add` for this session.
+    Session(Box<PrivateKey>),
+}
+
+/// Unlocked session state captured for one request.
+struct SessionSnapshot {
+    unlocked: UnlockedVault,
+    unlocked_at: Option<Instant>,
+    session_keys: Vec<SessionSshKey>,
+    public_keys: HashMap<String, CachedSshPublicKey>,
+}
+
+impl SessionSnapshot {
+    /// Snapshot the session, dropping `ssh-add` keys past their lifetime.
+    fn capture(paths: &VaultPaths, runtime: &Mutex<AgentRuntime>) -> Option<Self> {
+        let mut runtime = lock_runtime(runtime);
+        let unlocked = runtime.unlocked_vault(paths)?;
+        let unlocked_at = runtime.unlocked_at;
+        runtime.session_ssh_keys.retain(|key| {
+            let expired = lifetime_elapsed(unlocked_at, key.constraints);
+            if expired {
+                log_debug!("SSH agent dropped session key '{}' at the end of its lifetime", key.name);
+            }
+            !expired
+        });
+        Some(Self {
+            unlocked,
+            unlocked_at,
+            session_keys: runtime.session_ssh_keys.clone(),
+            public_keys: runtime.ssh_public_keys.clone(),
+        })
+    }
+}
+
+/// Serve agent requests from one client until it disconnects.
+///
+/// Returns `true` when the vault session expired while the client was
+/// connected, so the caller can run its normal expiry handling.
+pub(crate) fn serve_ssh_agent_connection(paths: &VaultPaths, runtime: &Mutex<AgentRuntime>, mut stream: UnixStream) -> io::Result<bool> {
+    stream.set_read_timeout(Some(SSH_AGENT_CLIENT_TIMEOUT))?;
+    let peer_pid = crate::auth::ipc::ssh_agent_peer_pid(&stream);
+
+    while let Some(message) = read_message(&mut stream)? {
+        if lock_runtime(runtime).expire_if_needed() {
+            write_message(&mut stream, &[SSH_AGENT_FAILURE])?;
+            return Ok(true);
+        }
+        let response = handle_ssh_agent_message(paths, runtime, &message, peer_pid);
+        write_message(&mut stream, &response)?;
+    }
+    Ok(false)
+}
+
+/// Handle one framed agent request and return the response body.
+pub(crate) fn handle_ssh_agent_message(paths: &VaultPaths, runtime: &Mutex<AgentRuntime>, message: &[u8], peer_pid: Option<u32>) -> Vec<u8> {
+    let Some((&kind, mut body)) = message.split_first() else {
+        return vec![SSH_AGENT_FAILURE];
+    };
+
+    let result = match kind {
+        SSH_AGENTC_REQUEST_IDENTITIES => identities_answer(paths, runtime),
+        SSH_AGENTC_SIGN_REQUEST => sign_response(paths, runtime, &mut body, peer_pid),
+        SSH_AGENTC_ADD_IDENTITY | SSH_AGENTC_ADD_ID_CONSTRAINED => add_identity(paths, runtime, &mut body, kind == SSH_AGENTC_ADD_ID_CONSTRAINED),
+        // Removal, agent locking and extensions are handled by `cossh vault`.
+        other => Err(format!("unsupported SSH agent request type {other}")),
+    };
+    result.unwrap_or_else(|reason| {
+        log_debug!("SSH agent request failed: {}", reason);
+        vec![SSH_AGENT_FAILURE]
+    })
+}
+
+fn identities_answer(paths: &VaultPaths, runtime: &Mutex<AgentRuntime>) -> Result<Vec<u8>, String> {
+    let keys = SessionSnapshot::capture(paths, runtime).map_or_else(|| Ok(Vec::new()), |session| live_keys(paths, runtime, &session))?;
+    let mut response = vec![SSH_AGENT_IDENTITIES_ANSWER];
+    encode(&mut response, &(keys.len() as u32))?;
+    for key in &keys {
+        encode(&mut response, &key.blob)?;
+        encode(&mut response, &key.name)?;
+    }
+    log_debug!("SSH agent listed {} identity(s)", keys.len());
+    Ok(response)
+}
+
+fn sign_response(paths: &VaultPaths, runtime: &Mutex<AgentRuntime>, body: &mut &[u8], peer_pid: Option<u32>) -> Result<Vec<u8>, String> {
+    let blob = Vec::<u8>::decode(body).map_err(|err| err.to_string())?;
+    let data = Vec::<u8>::decode(body).map_err(|err| err.to_string())?;
+    let flags = u32::decode(body).map_err(|err| err.to_string())?;
+
+    let Some(session) = SessionSnapshot::capture(paths, runtime) else {
+        return Err("password vault is locked".to_string());
+    };
+    let keys = live_keys(paths, runtime, &session)?;
+    let key = keys
+        .into_iter()
+        .find(|key| key.blob == blob)
+        .ok_or_else(|| "no unlocked vault key matches the requested identity".to_string())?;
+    let private_key = match key.source {
+        KeySource::Session(private_key) => *private_key,
+        KeySource::Vault => {
+            let private_key = load_vault_key(&session.unlocked, &key.name)?;
+            // The entry may have been replaced since its public key was cached.
+            if public_key_blob(&private_key)? != blob {
+                return Err(format!("vault key entry '{}' changed while signing", key.name));
+            }
+            private_key
+        }
+    };
+    if key.constraints.confirm && !confirm_key_use(&key.name) {
+        return Err(format!("use of key '{}' was not confirmed", key.name));
+    }
+
+    let signature = sign(&private_key, &data, flags)?;
+    {
+        // The vault may have been locked while the user was confirming.
+        let mut runtime = lock_runtime(runtime);
+        if runtime.unlocked_vault(paths).is_none() {
+            return Err("password vault was locked before the signature was released".to_string());
+        }
+        runtime.touch();
+    }
+    audit::record_release_event(
+        paths,
+        AuditEvent::new(AuditEventKind::EntryRead)
+            .with_entry(&key.name)
+            .with_process(peer_pid.map(AuditProcess::from_pid))
+            .with_detail("ssh agent signature"),
+    )?;
+    log_debug!("SSH agent signed a request with key '{}'", key.name);
+
+    let mut encoded = Vec::new();
+    signature.encode(&mut encoded).map_err(|err| err.to_string())?;
+    let mut response = vec![SSH_AGENT_SIGN_RESPONSE];
+    encode(&mut response, &encoded)?;
+    Ok(response)
+}
+
+fn add_identity(paths: &VaultPaths, runtime: &Mutex<AgentRuntime>, body: &mut &[u8], constrained: bool) -> Result<Vec<u8>, String> {
+    let keypair = KeypairData::decode(body).map_err(|err| format!("invalid key: {err}"))?;
+    let comment = String::decode(body).map_err(|err| err.to_string())?;
+    let mut constraints = SshKeyConstraints {
+        added_at: Some(SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())),
+        ..SshKeyConstraints::default()
+    };
+    while constrained && !body.is_finished() {
+        match u8::decode(body).map_err(|err| err.to_string())? {
+            SSH_AGENT_CONSTRAIN_LIFETIME => constraints.lifetime_seconds = Some(u64::from(u32::decode(body).map_err(|err| err.to_string())?)),
+            SSH_AGENT_CONSTRAIN_CONFIRM => constraints.confirm = true,
+            other => return Err(format!("unsupported key constraint {other}")),
+        }
+    }
+
+    let private_key = PrivateKey::new(keypair, comment).map_err(|err| err.to_string())?;
+    let name = entry_name_for_key(&private_key);
+    let mut runtime = lock_runtime(runtime);
+    if runtime.unlocked_vault(paths).is_none() {
+        return Err("password vault is locked".to_string());
+    }
+    // Re-adding a key replaces its constraints, as in OpenSSH.
+    runtime
+        .session_ssh_keys
+        .retain(|key| key.private_key.public_key().key_data() != private_key.public_key().key_data());
+    runtime.session_ssh_keys.push(SessionSshKey {
+        name: name.clone(),
+        private_key,
+        constraints,
+    });
+    log_debug!("SSH agent holds added key '{}' until the vault locks", name);
+    Ok(vec![SSH_AGENT_SUCCESS])
+}
+
+/// Derive a vault entry name from a key comment, such as `deploy@ci` -> `deploy_ci`.
+pub(crate) fn entry_name_for_key(private_key: &PrivateKey) -> String {
+    let sanitized: String = private_key
+        .comment()
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-') {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let sanitized = sanitized.trim_start_matches('.');
+    if sanitized.is_empty() {
+        return format!("ssh-key-{}", fingerprint_digest(private_key));
+    }
+    sanitized.to_string()
+}
+
+/// Twelve characters of the key's SHA-256 fingerprint, usable in entry names.
+fn fingerprint_digest(private_key: &PrivateKey) -> String {
+    let fingerprint = private_key.fingerprint(HashAlg::Sha256).to_string();
+    fingerprint.chars().filter(char::is_ascii_alphanumeric).skip(6).take(12).collect()
+}
+
+/// Keys that may currently be served: vault key entries first, then keys
+/// added for the session that no vault entry already serves.
+///
+/// Vault public keys come from the session cache while the entry's nonce is
+/// unchanged; the refreshed cache is stored back while the vault stays unlocked.
+fn live_keys(paths: &VaultPaths, runtime: &Mutex<AgentRuntime>, session: &SessionSnapshot) -> Result<Vec<LiveKey>, String> {
+    let mut keys: Vec<LiveKey> = Vec::new();
+    let mut public_keys = HashMap::new();
+    for entry in vault::list_ssh_key_entries_with_paths(paths).map_err(|err| err.to_string())? {
+        let name = entry.name;
+        let constraints = entry.ssh_key.unwrap_or_default();
+        let cached = match session.public_keys.get(&name).filter(|cached| cached.nonce == entry.nonce) {
+            Some(cached) => cached.clone(),
+            None => match load_vault_key(&session.unlocked, &name).and_then(|private_key| public_key_blob(&private_key)) {
+                Ok(blob) => CachedSshPublicKey { nonce: entry.nonce, blob },
+                Err(err) => {
+                    log_debug!("Skipping unreadable SSH key entry '{}': {}", name, err);
+                    continue;
+                }
+            },
+        };
+        public_keys.insert(name.clone(), cached.clone());
+        if lifetime_elapsed(session.unlocked_at, constraints) {
+            log_debug!("SSH agent key '{}' is past its lifetime", name);
+            continue;
+        }
+        keys.push(LiveKey {
+            name,
+            blob: cached.blob,
+            constraints,
+            source: KeySource::Vault,
+        });
+    }
+
+    {
+        let mut runtime = lock_runtime(runtime);
+        if runtime.is_unlocked() {
+            runtime.ssh_public_keys = public_keys;
+        }
+    }
+
+    for key in &session.session_keys {
+        let blob = public_key_blob(&key.private_key)?;
+        if keys.iter().any(|live| live.blob == blob) {
+            continue;
+        }
+        keys.push(LiveKey {
+            name: key.name.clone(),
+            blob,
+            constraints: key.constraints,
+            source: KeySource::Session(Box::new(key.private_key.clone())),
+        });
+    }
+    Ok(keys)
+}
+
+fn load_vault_key(unlocked: &UnlockedVault, name: &str) -> Result<PrivateKey, String> {
+    let (pem, _) = unlocked.get_ssh_key(name).map_err(|err| err.to_string())?;
+    PrivateKey::from_openssh(pem.expose_secret()).map_err(|err| err.to_string())
+}
+
+fn public_key_blob(private_key: &PrivateKey) -> Result<Vec<u8>, String> {
+    private_key.public_key().to_bytes().map_err(|err| err.to_string())
+}
+
+/// Key lifetimes count from when the key was added, as in OpenSSH.
+///
+/// Keys stored before the add time was recorded count from the vault unlock.
+fn lifetime_elapsed(unlocked_at: Option<Instant>, constraints: SshKeyConstraints) -> bool {
+    let Some(lifetime) = constraints.lifetime_seconds else {
+        return false;
+    };
+    match constraints.added_at {
+        Some(added_at) => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+            now.saturating_sub(added_at) >= lifetime
+        }
+        None => unlocked_at.is_some_and(|unlocked_at| unlocked_at.elapsed() >= Duration::from_secs(lifetime)),
+    }
+}
+
+fn sign(private_key: &PrivateKey, data: &[u8], flags: u32) -> Result<Signature, String> {
+    let KeypairData::Rsa(keypair) = private_key.key_data() else {
+        return private_key.key_data().try_sign(data).map_err(|err| err.to_string());
+    };
+
+    if flags & SSH_AGENT_RSA_SHA2_512 != 0 {
+        keypair.try_sign(data).map_err(|err| err.to_string())
+    } else if flags & SSH_AGENT_RSA_SHA2_256 != 0 {
+        let signing_key = rsa::pkcs1v15::SigningKey::<Sha256>::try_from(keypair).map_err(|err| err.to_string())?;
+        let signature = signing_key.try_sign(data).map_err(|err| err.to_string())?;
+        Signature::new(Algorithm::Rsa { hash: Some(HashAlg::Sha256) }, signature.to_vec()).map_err(|err| err.to_string())
+    } else {
+        Err("SHA-1 RSA signatures are not supported".to_string())
+    }
+}
+
+/// Ask through `SSH_ASKPASS` before a confirm-on-use key signs.
+fn confirm_key_use(name: &str) -> bool {
+    askpass_confirm(&format!("Allow use of the vault SSH key '{name}'?"))
+}
+
+/// Ask a yes/no question through the desktop `SSH_ASKPASS` helper. Without
+/// a helper the request is refused.
+fn askpass_confirm(prompt: &str) -> bool {
+    askpass_confirmation(prompt).unwrap_or(false)
+}
+
+/// Desktop helper used for confirmations: `SSH_ASKPASS`, else `ssh-askpass` on `PATH`.
+pub(crate) fn askpass_helper() -> Option<OsString> {
+    std::env::var_os("SSH_ASKPASS")
+        .filter(|program| !program.is_empty())
+        .or_else(|| which::which("ssh-askpass").ok().map(|path| path.into_os_string()))
+}
+
+/// Ask a yes/no question through [`askpass_helper`], or `None` without one.
+///
+/// Follows the OpenSSH agent convention: the helper is run with
+/// `SSH_ASKPASS_PROMPT=confirm` and a zero exit status means "allow".
+pub(super) fn askpass_confirmation(prompt: &str) -> Option<bool> {
+    let Some(program) = askpass_helper() else {
+        log_debug!("No SSH_ASKPASS helper available to confirm: {}", prompt);
+        return None;
+    };
+
+    let allowed = Command::new(program)
+        .arg(prompt)
+        .env("SSH_ASKPASS_PROMPT", "confirm")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    Some(allowed)
+}
+
+fn encode(out: &mut Vec<u8>, value: &impl Encode) -> Result<(), String> {
+    value.encode(out).map_err(|err| err.to_string())
+}
+
+fn read_message(stream: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match stream.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    if len == 0 || len > SSH_AGENT_MAX_MESSAGE_LEN {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("invalid SSH agent message length {len}")));
+    }
+    let mut message = vec![0u8; len];
+    stream.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+fn write_message(stream: &mut impl Write, body: &[u8]) -> io::Result<()> {
+    stream.write_all(&(body.len() as u32).to_be_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+#[cfg(test)]
+#[path = "../../test/auth/agent/ssh.rs"]
+mod tests;
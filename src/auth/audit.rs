@@ -0,0 +1,460 @@
+//! Hash-chained vault audit log.
+//!
+//! Every vault lifecycle event is appended as one JSON line to
+//! `~/.color-ssh/vault/audit.log`. Each record stores the SHA-256 hash of the
+//! previous record, so editing or deleting a line breaks the chain from that
+//! point on and is reported by `cossh vault audit`. The sequence number and
+//! hash of the newest record are also kept in `audit.head`, which catches
+//! records cut off the end of the log. A head exactly one record behind is
+//! what a crash between the two writes leaves, so it is accepted and caught
+//! up by the next append.
+//!
+//! The chain is not keyed: it detects truncation and accidental damage, not
+//! someone with write access to the vault directory, who can rebuild a
+//! consistent log and head from scratch.
+
+use crate::auth::vault::{PRIVATE_DIRECTORY_MODE, PRIVATE_FILE_MODE, VaultPaths};
+use crate::log_warn;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+
+const AUDIT_LOG_FILENAME: &str = "audit.log";
+const AUDIT_HEAD_FILENAME: &str = "audit.head";
+const AUDIT_GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+const AUDIT_TAIL_CHUNK_LEN: u64 = 4096;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Kind of vault event recorded in the audit log.
+pub enum AuditEventKind {
+    VaultInitialized,
+    Unlocked,
+    UnlockFailed,
+    Locked,
+    EntryRead,
+    /// An entry was released to `cossh` on a remote host through vault forwarding.
+    RemoteEntryRead,
+    EntryStored,
+    EntryRemoved,
+    /// An entry policy refused to release an entry.
+    EntryDenied,
+    MasterPasswordRotated,
+    VaultRekeyed,
+    /// A keyfile or recovery key was added, replaced or removed.
+    UnlockMethodChanged,
+}
+
+impl AuditEventKind {
+    /// Stable label used in log records and CLI output.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::VaultInitialized => "vault_initialized",
+            Self::Unlocked => "unlocked",
+            Self::UnlockFailed => "unlock_failed",
+            Self::Locked => "locked",
+            Self::EntryRead => "entry_read",
+            Self::RemoteEntryRead => "remote_entry_read",
+            Self::EntryStored => "entry_stored",
+            Self::EntryRemoved => "entry_removed",
+            Self::EntryDenied => "entry_denied",
+            Self::MasterPasswordRotated => "master_password_rotated",
+            Self::VaultRekeyed => "vault_rekeyed",
+            Self::UnlockMethodChanged => "unlock_method_changed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+/// Process responsible for an audited event.
+pub struct AuditProcess {
+    pub pid: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exe: Option<String>,
+}
+
+impl AuditProcess {
+    /// Describe the current process.
+    pub fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            exe: std::env::current_exe().ok().map(|path| path.display().to_string()),
+        }
+    }
+
+    /// Describe another local process by pid.
+    pub fn from_pid(pid: u32) -> Self {
+        Self {
+            pid,
+            exe: process_executable(pid),
+        }
+    }
+}
+
+impl fmt::Display for AuditProcess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.exe {
+            Some(exe) => write!(f, "{} ({exe})", self.pid),
+            None => write!(f, "{}", self.pid),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Event data supplied by callers before chaining.
+pub struct AuditEvent {
+    pub kind: AuditEventKind,
+    pub entry: Option<String>,
+    pub host: Option<String>,
+    pub process: Option<AuditProcess>,
+    pub detail: Option<String>,
+}
+
+impl AuditEvent {
+    /// Build an event with no optional context.
+    pub fn new(kind: AuditEventKind) -> Self {
+        Self {
+            kind,
+            entry: None,
+            host: None,
+            process: None,
+            detail: None,
+        }
+    }
+
+    /// Attach the vault entry name or secret reference.
+    pub fn with_entry(mut self, entry: impl Into<String>) -> Self {
+        self.entry = Some(entry.into());
+        self
+    }
+
+    /// Attach the host alias the secret was requested for.
+    pub fn with_host(mut self, host: Option<&str>) -> Self {
+        self.host = host.map(str::to_string);
+        self
+    }
+
+    /// Attach the requesting process.
+    pub fn with_process(mut self, process: Option<AuditProcess>) -> Self {
+        self.process = process;
+        self
+    }
+
+    /// Attach a short free-form detail such as a lock reason.
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+/// One chained audit log line.
+pub struct AuditRecord {
+    pub seq: u64,
+    pub timestamp: String,
+    pub event: AuditEventKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process: Option<AuditProcess>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditRecord {
+    fn digest(&self) -> Result<String, AuditError> {
+        let unsigned = Self {
+            hash: String::new(),
+            ..self.clone()
+        };
+        let serialized = serde_json::to_vec(&unsigned).map_err(|err| AuditError::InvalidRecord(format!("failed to serialize audit record: {err}")))?;
+        Ok(hex_digest(&serialized))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+/// Sequence number and hash of the newest record, stored next to the log.
+struct AuditHead {
+    seq: u64,
+    hash: String,
+}
+
+impl AuditHead {
+    fn of(record: &AuditRecord) -> Self {
+        Self {
+            seq: record.seq,
+            hash: record.hash.clone(),
+        }
+    }
+
+    /// Whether `record` directly follows this head, as when an append
+    /// crashed after syncing its record but before updating the head.
+    fn is_one_behind(&self, record: &AuditRecord) -> bool {
+        record.seq == self.seq + 1 && record.prev_hash == self.hash
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// First point where the audit chain stopped verifying.
+pub struct AuditChainBreak {
+    /// 1-based line number in `audit.log`.
+    pub line: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Result of reading and verifying the audit log.
+pub struct AuditVerification {
+    /// Records that verified before any break.
+    pub records: Vec<AuditRecord>,
+    pub chain_break: Option<AuditChainBreak>,
+}
+
+impl AuditVerification {
+    /// Returns `true` when every record verified.
+    pub fn is_intact(&self) -> bool {
+        self.chain_break.is_none()
+    }
+}
+
+#[derive(Debug)]
+/// Errors returned by audit log operations.
+pub enum AuditError {
+    InvalidRecord(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidRecord(message) => write!(f, "invalid audit log: {message}"),
+            Self::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+impl From<io::Error> for AuditError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Path to the audit log inside the vault directory.
+pub fn audit_log_path(paths: &VaultPaths) -> std::path::PathBuf {
+    paths.vault_dir().join(AUDIT_LOG_FILENAME)
+}
+
+/// Path to the chain head anchor inside the vault directory.
+pub fn audit_head_path(paths: &VaultPaths) -> std::path::PathBuf {
+    paths.vault_dir().join(AUDIT_HEAD_FILENAME)
+}
+
+/// Append `event` to the audit log, chaining it to the previous record.
+pub fn append_event(paths: &VaultPaths, event: AuditEvent) -> Result<AuditRecord, AuditError> {
+    let path = audit_log_path(paths);
+    fs::create_dir_all(paths.vault_dir())?;
+    crate::platform::set_private_directory_permissions(&paths.vault_dir(), PRIVATE_DIRECTORY_MODE)?;
+
+    let mut file = OpenOptions::new().read(true).append(true).create(true).open(&path)?;
+    crate::platform::set_private_file_permissions(&path, PRIVATE_FILE_MODE)?;
+    // The agent and CLI processes may append concurrently.
+    file.lock()?;
+
+    // Chain from the anchored head so records removed from the end of the
+    // log stay detectable after the next append. A head one record behind the
+    // log is left by a crash between the two writes and is caught up here.
+    let previous = match (read_head(paths)?, read_last_record(&mut file)) {
+        (None, tail) => tail?.as_ref().map(AuditHead::of),
+        (Some(head), Ok(Some(tail))) if head.is_one_behind(&tail) => Some(AuditHead::of(&tail)),
+        (Some(head), _) => Some(head),
+    };
+    let mut record = AuditRecord {
+        seq: previous.as_ref().map_or(1, |head| head.seq + 1),
+        timestamp: Utc::now().to_rfc3339(),
+        event: event.kind,
+        entry: event.entry,
+        host: event.host,
+        process: event.process,
+        detail: event.detail,
+        prev_hash: previous.map_or_else(|| AUDIT_GENESIS_HASH.to_string(), |head| head.hash),
+        hash: String::new(),
+    };
+    record.hash = record.digest()?;
+
+    let mut line = serde_json::to_vec(&record).map_err(|err| AuditError::InvalidRecord(format!("failed to serialize audit record: {err}")))?;
+    line.push(b'\n');
+    file.write_all(&line)?;
+    file.sync_data()?;
+    write_head(paths, &AuditHead::of(&record))?;
+    Ok(record)
+}
+
+/// Append `event`, logging instead of failing when the audit log is not writable.
+///
+/// Events that release a secret use [`record_release_event`] instead.
+pub(crate) fn record_event(paths: &VaultPaths, event: AuditEvent) {
+    let kind = event.kind;
+    if let Err(err) = append_event(paths, event) {
+        log_warn!("Failed to append '{}' to the vault audit log: {}", kind.label(), err);
+    }
+}
+
+/// Append a secret release event; callers must not release the secret on error.
+pub(crate) fn record_release_event(paths: &VaultPaths, event: AuditEvent) -> Result<(), String> {
+    let kind = event.kind;
+    append_event(paths, event).map(|_| ()).map_err(|err| {
+        log_warn!("Refusing secret release: failed to append '{}' to the vault audit log: {}", kind.label(), err);
+        format!("secret was not released because the vault audit log could not be written: {err}")
+    })
+}
+
+/// Read every record and verify sequence numbers and the hash chain.
+pub fn verify_audit_log(paths: &VaultPaths) -> Result<AuditVerification, AuditError> {
+    let path = audit_log_path(paths);
+    let head = read_head(paths)?;
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Ok(AuditVerification {
+                records: Vec::new(),
+                chain_break: head.map(|head| AuditChainBreak {
+                    line: 1,
+                    reason: format!("audit log is missing but its head records {} entries", head.seq),
+                }),
+            });
+        }
+        Err(err) => return Err(AuditError::Io(err)),
+    };
+
+    let mut records: Vec<AuditRecord> = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        let line_number = index + 1;
+        let chain_break = |reason: String| AuditChainBreak { line: line_number, reason };
+
+        let record = match serde_json::from_str::<AuditRecord>(&line) {
+            Ok(record) => record,
+            Err(err) => {
+                return Ok(AuditVerification {
+                    records,
+                    chain_break: Some(chain_break(format!("record could not be parsed: {err}"))),
+                });
+            }
+        };
+
+        let (expected_seq, expected_prev_hash) = records
+            .last()
+            .map_or((1, AUDIT_GENESIS_HASH), |previous| (previous.seq + 1, previous.hash.as_str()));
+        let reason = if record.seq != expected_seq {
+            Some(format!("expected sequence {expected_seq}, found {}", record.seq))
+        } else if record.prev_hash != expected_prev_hash {
+            Some("previous-record hash does not match".to_string())
+        } else if record.digest()? != record.hash {
+            Some("record hash does not match its contents".to_string())
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Ok(AuditVerification {
+                records,
+                chain_break: Some(chain_break(reason)),
+            });
+        }
+
+        records.push(record);
+    }
+
+    let reason = match (records.last(), &head) {
+        (None, None) => None,
+        (Some(_), None) => Some("audit head is missing".to_string()),
+        (None, Some(head)) => Some(format!("audit log is empty but its head records {} entries", head.seq)),
+        (Some(last), Some(head)) if head.is_one_behind(last) => None,
+        (Some(last), Some(head)) if last.seq != head.seq => Some(format!(
+            "log ends at sequence {} but its head records sequence {}; records were removed from the end",
+            last.seq, head.seq
+        )),
+        (Some(last), Some(head)) if last.hash != head.hash => Some("last record does not match the audit head".to_string()),
+        _ => None,
+    };
+    let chain_break = reason.map(|reason| AuditChainBreak {
+        line: records.len() + 1,
+        reason,
+    });
+    Ok(AuditVerification { records, chain_break })
+}
+
+fn read_head(paths: &VaultPaths) -> Result<Option<AuditHead>, AuditError> {
+    match fs::read(audit_head_path(paths)) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|err| AuditError::InvalidRecord(format!("audit head could not be parsed: {err}"))),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(AuditError::Io(err)),
+    }
+}
+
+fn write_head(paths: &VaultPaths, head: &AuditHead) -> Result<(), AuditError> {
+    let path = audit_head_path(paths);
+    let tmp_path = paths.vault_dir().join(format!(".{AUDIT_HEAD_FILENAME}.tmp-{}", std::process::id()));
+    let serialized = serde_json::to_vec(head).map_err(|err| AuditError::InvalidRecord(format!("failed to serialize audit head: {err}")))?;
+    fs::write(&tmp_path, serialized)?;
+    crate::platform::set_private_file_permissions(&tmp_path, PRIVATE_FILE_MODE)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn read_last_record(file: &mut File) -> Result<Option<AuditRecord>, AuditError> {
+    let mut offset = file.metadata()?.len();
+    let mut tail = Vec::new();
+    while offset > 0 {
+        let chunk_len = offset.min(AUDIT_TAIL_CHUNK_LEN);
+        offset -= chunk_len;
+        let mut chunk = vec![0u8; chunk_len as usize];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&tail);
+        tail = chunk;
+
+        let trimmed = tail.strip_suffix(b"\n").unwrap_or(&tail);
+        if let Some(newline) = trimmed.iter().rposition(|byte| *byte == b'\n') {
+            return parse_record_line(&trimmed[newline + 1..]).map(Some);
+        }
+    }
+
+    let trimmed = tail.strip_suffix(b"\n").unwrap_or(&tail);
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    parse_record_line(trimmed).map(Some)
+}
+
+fn parse_record_line(line: &[u8]) -> Result<AuditRecord, AuditError> {
+    serde_json::from_slice(line).map_err(|err| AuditError::InvalidRecord(format!("last audit record could not be parsed: {err}")))
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(target_os = "linux")]
+fn process_executable(pid: u32) -> Option<String> {
+    fs::read_link(format!("/proc/{pid}/exe")).ok().map(|path| path.display().to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_executable(_pid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+#[path = "../test/auth/audit.rs"]
+mod tests;
@@ -4,6 +4,8 @@ use super::prompt::{
     prompt_entry_secret, prompt_existing_master_password, prompt_existing_master_password_with_label, prompt_new_master_password,
     prompt_new_master_password_with_label,
 };
+use crate::auth::audit::{self, AuditEvent, AuditEventKind, AuditProcess, AuditRecord};
+use crate::auth::generator::{DEFAULT_PASSWORD_LENGTH, GeneratorOptions, SymbolSet, generate_password};
 use crate::auth::secret::{ExposeSecret, SensitiveString};
 use crate::{args, config, log_debug};
 use chrono::{Local, TimeZone};
@@ -31,19 +33,32 @@ fn unlock_policy_from_config() -> super::ipc::UnlockPolicy {
         auth_settings.idle_timeout_seconds,
         auth_settings.session_timeout_seconds
     );
-    super::ipc::UnlockPolicy::new(auth_settings.idle_timeout_seconds, auth_settings.session_timeout_seconds)
+    super::ipc::UnlockPolicy::from_auth_settings(&auth_settings)
 }
 
 fn vault_command_name(vault_command: &args::VaultCommand) -> &'static str {
     match vault_command {
         args::VaultCommand::Init => "init",
         args::VaultCommand::AddPass(_) => "add",
+        args::VaultCommand::AddKey(_) => "add-key",
         args::VaultCommand::RemovePass(_) => "remove",
-        args::VaultCommand::List => "list",
+        args::VaultCommand::Rename(_) => "rename",
+        args::VaultCommand::List(_) => "list",
         args::VaultCommand::Unlock => "unlock",
         args::VaultCommand::Lock => "lock",
-        args::VaultCommand::Status => "status",
+        args::VaultCommand::Status(_) => "status",
+        args::VaultCommand::Get(_) => "get",
+        args::VaultCommand::Exec(_) => "exec",
         args::VaultCommand::SetMasterPassword => "set-master-password",
+        args::VaultCommand::Audit(_) => "audit",
+        args::VaultCommand::Doctor(_) => "doctor",
+        args::VaultCommand::Rekey(_) => "rekey",
+        args::VaultCommand::Rotate(..) => "rotate",
+        args::VaultCommand::Policy(_) => "policy",
+        args::VaultCommand::RecoveryKey => "recovery-key",
+        args::VaultCommand::Recover => "recover",
+        args::VaultCommand::Keyfile(_) => "keyfile",
+        args::VaultCommand::SshAgent => "ssh-agent",
     }
 }
 
@@ -56,11 +71,19 @@ fn initialize_vault_if_needed() -> Result<Option<SensitiveString>, String> {
     log_debug!("Password vault not initialized; starting first-run setup");
     println!("Password vault is not initialized. Starting first-run setup.");
     let password = prompt_new_master_password()?;
-    super::vault::initialize_vault(password.expose_secret()).map_err(|err| err.to_string())?;
+    let recovery_key = super::vault::initialize_vault(password.expose_secret()).map_err(|err| err.to_string())?;
     println!("Password vault initialized.");
+    print_recovery_key(&recovery_key);
     Ok(Some(password))
 }
 
+fn print_recovery_key(recovery_key: &SensitiveString) {
+    println!();
+    println!("Recovery key: {}", recovery_key.expose_secret());
+    println!("Store it offline. It resets the master password with `cossh vault recover` and is not shown again.");
+    println!();
+}
+
 fn require_initialized_vault() -> Result<(), String> {
     match super::vault::vault_exists().map_err(|err| err.to_string())? {
         true => Ok(()),
@@ -77,7 +100,16 @@ fn resolve_master_password(initial_password: Option<SensitiveString>) -> Result<
 
 fn unlock_vault_for_cli(initial_password: Option<SensitiveString>) -> Result<super::vault::UnlockedVault, String> {
     let master_password = resolve_master_password(initial_password)?;
-    super::vault::unlock_with_password(master_password.expose_secret()).map_err(|err| err.to_string())
+    let result = super::vault::unlock_with_password(master_password.expose_secret());
+    let audit_kind = match &result {
+        Ok(_) => Some(AuditEventKind::Unlocked),
+        Err(super::vault::VaultError::InvalidMasterPassword) => Some(AuditEventKind::UnlockFailed),
+        Err(_) => None,
+    };
+    if let (Some(kind), Ok(paths)) = (audit_kind, super::vault::VaultPaths::resolve_default()) {
+        audit::record_event(&paths, AuditEvent::new(kind).with_process(Some(AuditProcess::current())).with_detail("cli"));
+    }
+    result.map_err(|err| err.to_string())
 }
 
 fn command_failure(message: &str, err: impl std::fmt::Display) -> ExitCode {
@@ -97,8 +129,38 @@ fn run_vault_init_cli() -> ExitCode {
     }
 }
 
-fn run_add_pass_cli(pass_name: &str) -> ExitCode {
+fn generator_options(generator_args: &args::PasswordGeneratorArgs) -> GeneratorOptions {
+    let symbols = if generator_args.no_symbols {
+        SymbolSet::None
+    } else if generator_args.device_safe {
+        SymbolSet::DeviceSafe
+    } else {
+        SymbolSet::Full
+    };
+    GeneratorOptions {
+        length: generator_args.length.unwrap_or(DEFAULT_PASSWORD_LENGTH),
+        lowercase: !generator_args.no_lowercase,
+        uppercase: !generator_args.no_uppercase,
+        digits: !generator_args.no_digits,
+        symbols,
+        exclude_ambiguous: generator_args.exclude_ambiguous,
+        pronounceable: generator_args.pronounceable,
+    }
+}
+
+fn run_add_pass_cli(add_pass_args: &args::VaultAddPassArgs) -> ExitCode {
+    let pass_name = add_pass_args.name.as_str();
     log_debug!("Running `cossh vault add` for entry '{}'", pass_name);
+    let generated = match add_pass_args
+        .generator
+        .as_ref()
+        .map(|generator_args| generate_password(&generator_options(generator_args)))
+        .transpose()
+    {
+        Ok(generated) => generated,
+        Err(err) => return command_failure("Failed to generate password", err),
+    };
+
     let initial_password = match initialize_vault_if_needed() {
         Ok(password) => password,
         Err(err) => return command_failure("Failed to initialize password vault", err),
@@ -109,15 +171,18 @@ fn run_add_pass_cli(pass_name: &str) -> ExitCode {
         Err(err) => return command_failure("Failed to unlock password vault", err),
     };
 
-    let secret = match prompt_entry_secret() {
+    let secret = match generated.as_ref().map_or_else(prompt_entry_secret, |secret| Ok(secret.clone())) {
         Ok(secret) => secret,
         Err(err) => return command_failure("Failed to capture SSH password", err),
     };
 
-    match unlocked.store_secret(pass_name, secret.expose_secret()) {
+    match unlocked.store_secret_for_user(pass_name, secret.expose_secret(), add_pass_args.user.as_deref()) {
         Ok(()) => {
             log_debug!("Saved password vault entry '{}'", pass_name);
             println!("Saved password vault entry: {}", pass_name);
+            if generated.is_some() {
+                println!("Generated password: {}", secret.expose_secret());
+            }
             println!("Set `vault_pass: {}` on the target host in ~/.color-ssh/cossh-inventory.yaml", pass_name);
             ExitCode::SUCCESS
         }
@@ -125,6 +190,157 @@ fn run_add_pass_cli(pass_name: &str) -> ExitCode {
     }
 }
 
+fn run_add_key_cli(add_key_args: &args::VaultAddKeyArgs) -> ExitCode {
+    log_debug!("Running `cossh vault add-key` for entry '{}'", add_key_args.name);
+    let initial_password = match initialize_vault_if_needed() {
+        Ok(password) => password,
+        Err(err) => return command_failure("Failed to initialize password vault", err),
+    };
+
+    let unlocked = match unlock_vault_for_cli(initial_password) {
+        Ok(unlocked) => unlocked,
+        Err(err) => return command_failure("Failed to unlock password vault", err),
+    };
+
+    let constraints = super::vault::SshKeyConstraints {
+        confirm: add_key_args.confirm,
+        lifetime_seconds: add_key_args.lifetime_seconds,
+        ..super::vault::SshKeyConstraints::default()
+    };
+    let imported = match super::keyimport::import_private_key(&unlocked, &add_key_args.name, &add_key_args.path, constraints, || {
+        super::prompt_hidden_secret(&format!("Enter passphrase for {}: ", add_key_args.path.display()))
+    }) {
+        Ok(imported) => imported,
+        Err(err) => return command_failure("Failed to store SSH key", err),
+    };
+    log_debug!("Saved SSH key vault entry '{}'", add_key_args.name);
+    println!("Saved SSH key vault entry: {}", add_key_args.name);
+    println!("{} {} {}", imported.algorithm, imported.fingerprint, imported.comment);
+    if imported.was_encrypted {
+        println!("The key passphrase was removed; the vault master password now protects the key.");
+    }
+
+    if add_key_args.shred {
+        if let Err(err) = super::keyimport::shred_file(&add_key_args.path) {
+            return command_failure("Stored the key but failed to shred the original file", err);
+        }
+        println!("Shredded {}", add_key_args.path.display());
+    }
+    println!("Set `vault_key: {}` on the target host in ~/.color-ssh/cossh-inventory.yaml", add_key_args.name);
+    ExitCode::SUCCESS
+}
+
+fn run_rotate_pass_cli(pass_name: &str, source: &args::VaultRotateSource) -> ExitCode {
+    log_debug!("Running `cossh vault rotate` for entry '{}'", pass_name);
+    let generated = match source {
+        args::VaultRotateSource::Generate(generator_args) => match generate_password(&generator_options(generator_args)) {
+            Ok(secret) => Some(secret),
+            Err(err) => return command_failure("Failed to generate password", err),
+        },
+        args::VaultRotateSource::Prompt | args::VaultRotateSource::Rollback => None,
+    };
+    if let Err(err) = require_initialized_vault() {
+        eprintln!("{err}");
+        return ExitCode::from(1);
+    }
+
+    let unlocked = match unlock_vault_for_cli(None) {
+        Ok(unlocked) => unlocked,
+        Err(err) => return command_failure("Failed to unlock password vault", err),
+    };
+
+    if *source == args::VaultRotateSource::Rollback {
+        return match unlocked.rollback_secret(pass_name) {
+            Ok(()) => {
+                log_debug!("Rolled back password vault entry '{}'", pass_name);
+                println!("Restored the previous password for vault entry: {}", pass_name);
+                ExitCode::SUCCESS
+            }
+            Err(err) => command_failure("Failed to roll back password vault entry", err),
+        };
+    }
+
+    let secret = match generated.as_ref().map_or_else(prompt_entry_secret, |secret| Ok(secret.clone())) {
+        Ok(secret) => secret,
+        Err(err) => return command_failure("Failed to capture SSH password", err),
+    };
+
+    match unlocked.rotate_secret(pass_name, secret.expose_secret()) {
+        Ok(()) => {
+            log_debug!("Rotated password vault entry '{}'", pass_name);
+            println!("Rotated password vault entry: {}", pass_name);
+            if generated.is_some() {
+                println!("New password: {}", secret.expose_secret());
+            }
+            println!("Previous password kept; restore it with `cossh vault rotate {} --rollback`", pass_name);
+            ExitCode::SUCCESS
+        }
+        Err(err) => command_failure("Failed to rotate password vault entry", err),
+    }
+}
+
+fn print_entry_policy(pass_name: &str, policy: &super::vault::EntryPolicy) {
+    println!("Policy for password vault entry: {}", pass_name);
+    println!("  Confirm before release: {}", if policy.confirm { "yes" } else { "no" });
+    if policy.allowed_hosts.is_empty() {
+        println!("  Allowed hosts: any");
+    } else {
+        println!("  Allowed hosts: {}", policy.allowed_hosts.join(", "));
+    }
+    match policy.max_uses {
+        Some(max_uses) => println!("  Uses per unlock: {}", max_uses),
+        None => println!("  Uses per unlock: unlimited"),
+    }
+}
+
+fn run_entry_policy_cli(policy_args: &args::VaultPolicyArgs) -> ExitCode {
+    log_debug!("Running `cossh vault policy` for entry '{}'", policy_args.name);
+    if let Err(err) = require_initialized_vault() {
+        eprintln!("{err}");
+        return ExitCode::from(1);
+    }
+
+    let unlocked = match unlock_vault_for_cli(None) {
+        Ok(unlocked) => unlocked,
+        Err(err) => return command_failure("Failed to unlock password vault", err),
+    };
+
+    let mut policy = match unlocked.entry_policy(&policy_args.name) {
+        Ok(policy) => policy,
+        Err(err) => return command_failure("Failed to read password vault entry policy", err),
+    };
+    if policy_args.is_query() {
+        print_entry_policy(&policy_args.name, &policy);
+        return ExitCode::SUCCESS;
+    }
+
+    if policy_args.clear {
+        policy = super::vault::EntryPolicy::default();
+    }
+    if let Some(confirm) = policy_args.confirm {
+        policy.confirm = confirm;
+    }
+    if policy_args.any_host {
+        policy.allowed_hosts.clear();
+    } else if !policy_args.allowed_hosts.is_empty() {
+        policy.allowed_hosts = policy_args.allowed_hosts.clone();
+    }
+    if policy_args.unlimited_uses {
+        policy.max_uses = None;
+    } else if let Some(max_uses) = policy_args.max_uses {
+        policy.max_uses = Some(max_uses);
+    }
+
+    match unlocked.set_entry_policy(&policy_args.name, policy.clone()) {
+        Ok(()) => {
+            log_debug!("Updated policy for password vault entry '{}'", policy_args.name);
+            print_entry_policy(&policy_args.name, &policy);
+            ExitCode::SUCCESS
+        }
+        Err(err) => command_failure("Failed to update password vault entry policy", err),
+    }
+}
+
 fn run_remove_pass_cli(pass_name: &str) -> ExitCode {
     log_debug!("Running `cossh vault remove` for entry '{}'", pass_name);
     if let Err(err) = require_initialized_vault() {
@@ -147,9 +363,81 @@ fn run_remove_pass_cli(pass_name: &str) -> ExitCode {
     }
 }
 
-fn run_list_passes_cli() -> ExitCode {
-    log_debug!("Running `cossh vault list`");
-    let entries = match super::vault::list_entries() {
+fn run_rename_pass_cli(rename_args: &args::VaultRenameArgs) -> ExitCode {
+    log_debug!(
+        "Running `cossh vault rename` for entry '{}' -> '{}' (update_inventory={})",
+        rename_args.name,
+        rename_args.new_name,
+        rename_args.update_inventory
+    );
+    if rename_args.name == rename_args.new_name {
+        eprintln!("The new entry name must differ from the current one.");
+        return ExitCode::from(2);
+    }
+    if let Err(err) = require_initialized_vault() {
+        eprintln!("{err}");
+        return ExitCode::from(1);
+    }
+
+    let unlocked = match unlock_vault_for_cli(None) {
+        Ok(unlocked) => unlocked,
+        Err(err) => return command_failure("Failed to unlock password vault", err),
+    };
+
+    if let Err(err) = unlocked.rename_entry(&rename_args.name, &rename_args.new_name) {
+        return command_failure("Failed to rename password vault entry", err);
+    }
+    log_debug!("Renamed password vault entry '{}' to '{}'", rename_args.name, rename_args.new_name);
+    println!("Renamed password vault entry: {} -> {}", rename_args.name, rename_args.new_name);
+
+    let tree = match crate::inventory::load_inventory_tree() {
+        Ok(tree) => tree,
+        Err(err) => return command_failure("Failed to load inventory to check vault references", err),
+    };
+
+    if !rename_args.update_inventory {
+        let stale_hosts: Vec<&str> = tree
+            .hosts
+            .iter()
+            .filter(|host| {
+                [host.vault_pass.as_deref(), host.ssh.vault_key.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .any(|value| value.trim() == rename_args.name)
+            })
+            .map(|host| host.name.as_str())
+            .collect();
+        if !stale_hosts.is_empty() {
+            eprintln!(
+                "Warning: {} inventory host(s) still reference '{}': {}",
+                stale_hosts.len(),
+                rename_args.name,
+                stale_hosts.join(", ")
+            );
+            eprintln!("Re-run with `--update-inventory` or edit those hosts to use '{}'.", rename_args.new_name);
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    match crate::inventory::rename_vault_references_in_inventory(&tree, &rename_args.name, &rename_args.new_name) {
+        Ok(touched) if touched.is_empty() => {
+            println!("No inventory entries referenced '{}'", rename_args.name);
+            ExitCode::SUCCESS
+        }
+        Ok(touched) => {
+            for (path, changed) in touched {
+                log_debug!("Rewrote {} vault reference(s) in {}", changed, path.display());
+                println!("Updated {changed} vault reference(s) in {}", path.display());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => command_failure("Failed to update inventory vault references", err),
+    }
+}
+
+fn run_list_passes_cli(json: bool) -> ExitCode {
+    log_debug!("Running `cossh vault list` (json={})", json);
+    let entries = match super::vault::list_entry_summaries() {
         Ok(entries) => entries,
         Err(super::vault::VaultError::VaultNotInitialized) => {
             eprintln!("Password vault is not initialized. Run `cossh vault init` first.");
@@ -159,17 +447,30 @@ fn run_list_passes_cli() -> ExitCode {
     };
 
     log_debug!("Password vault contains {} entry(s)", entries.len());
+    if json {
+        return print_json(&entries);
+    }
     if entries.is_empty() {
         println!("No password vault entries found");
         return ExitCode::SUCCESS;
     }
 
     for entry in entries {
-        println!("{entry}");
+        println!("{}", entry.name);
     }
     ExitCode::SUCCESS
 }
 
+fn print_json(value: &impl serde::Serialize) -> ExitCode {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => {
+            println!("{json}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => command_failure("Failed to serialize JSON output", err),
+    }
+}
+
 fn run_unlock_cli() -> ExitCode {
     log_debug!("Running `cossh vault unlock`");
     let initial_password = match initialize_vault_if_needed() {
@@ -231,8 +532,8 @@ fn run_lock_cli() -> ExitCode {
     }
 }
 
-fn run_vault_status_cli() -> ExitCode {
-    log_debug!("Running `cossh vault status`");
+fn run_vault_status_cli(json: bool) -> ExitCode {
+    log_debug!("Running `cossh vault status` (json={})", json);
     let client = match super::agent::AgentClient::new() {
         Ok(client) => client,
         Err(err) => return command_failure("Failed to access password vault agent", err),
@@ -247,6 +548,9 @@ fn run_vault_status_cli() -> ExitCode {
                 status.unlock_expires_in_seconds,
                 status.absolute_timeout_seconds
             );
+            if json {
+                return print_json(&status);
+            }
             println!("Vault Exist: {}", status.vault_exists);
             println!("Unlocked: {}", status.unlocked);
             if let Some(expires) = status.unlock_expires_in_seconds {
@@ -261,6 +565,134 @@ fn run_vault_status_cli() -> ExitCode {
     }
 }
 
+/// Read the requested value of an entry without prompting for the master password.
+fn read_entry_field_cli(name: &str, field: args::VaultSecretField, host_alias: Option<&str>) -> Result<SensitiveString, String> {
+    if field == args::VaultSecretField::User {
+        // The user is stored unencrypted on the entry, so this works while locked.
+        return match super::vault::entry_user(name) {
+            Ok(Some(user)) => Ok(SensitiveString::from_owned_string(user)),
+            Ok(None) => Err(format!(
+                "vault entry '{name}' has no login user; store one with `cossh vault add {name} --user <name>`"
+            )),
+            Err(err) => Err(err.to_string()),
+        };
+    }
+
+    let hosts = match host_alias {
+        Some(_) => {
+            crate::inventory::load_inventory_tree()
+                .map_err(|err| format!("failed to load inventory: {err}"))?
+                .hosts
+        }
+        None => Vec::new(),
+    };
+    let host = match host_alias {
+        Some(alias) => Some(
+            hosts
+                .iter()
+                .find(|host| host.name == alias)
+                .ok_or_else(|| format!("inventory host '{alias}' was not found"))?,
+        ),
+        None => None,
+    };
+
+    let secret = crate::process::read_vault_entry_from_unlocked_agent(name, host).map_err(|err| err.to_string())?;
+    if field == args::VaultSecretField::Otp {
+        return super::otp::current_totp_code(secret.expose_secret()).map_err(|err| err.to_string());
+    }
+    Ok(secret)
+}
+
+fn run_vault_get_cli(get_args: &args::VaultGetArgs) -> ExitCode {
+    log_debug!("Running `cossh vault get` for entry '{}' (field={:?})", get_args.name, get_args.field);
+    let value = match read_entry_field_cli(&get_args.name, get_args.field, get_args.host.as_deref()) {
+        Ok(value) => value,
+        Err(err) => return command_failure("Failed to read password vault entry", err),
+    };
+
+    let result = {
+        use std::io::Write;
+
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+        stdout
+            .write_all(value.expose_secret().as_bytes())
+            .and_then(|_| stdout.write_all(b"\n"))
+            .and_then(|_| stdout.flush())
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => command_failure("Failed to write password vault entry", err),
+    }
+}
+
+fn run_vault_exec_cli(exec_args: &args::VaultExecArgs) -> ExitCode {
+    use std::io::Write;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{Command, Stdio};
+
+    log_debug!(
+        "Running `cossh vault exec` for entry '{}' (field={:?}, stdin={})",
+        exec_args.name,
+        exec_args.field,
+        exec_args.stdin
+    );
+    let value = match read_entry_field_cli(&exec_args.name, exec_args.field, exec_args.host.as_deref()) {
+        Ok(value) => value,
+        Err(err) => return command_failure("Failed to read password vault entry", err),
+    };
+    let Some((program, program_args)) = exec_args.command.split_first() else {
+        eprintln!("No command given to run.");
+        return ExitCode::from(2);
+    };
+
+    // The value only reaches the child; this process's environment is untouched.
+    let mut command = Command::new(program);
+    command.args(program_args);
+    if exec_args.stdin {
+        command.stdin(Stdio::piped());
+    } else {
+        command.env(&exec_args.env, value.expose_secret());
+    }
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(err) => return command_failure(&format!("Failed to run '{program}'"), err),
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        let written = stdin.write_all(value.expose_secret().as_bytes()).and_then(|_| stdin.write_all(b"\n"));
+        // A child that exits without reading its stdin is not an error here.
+        if let Err(err) = written
+            && err.kind() != std::io::ErrorKind::BrokenPipe
+        {
+            log_debug!("Failed to write password vault entry to child stdin: {}", err);
+        }
+    }
+    drop(value);
+
+    match child.wait() {
+        Ok(status) => {
+            let code = status.code().or_else(|| status.signal().map(|signal| 128 + signal)).unwrap_or(1);
+            log_debug!("`cossh vault exec` child exited with {}", code);
+            ExitCode::from(u8::try_from(code).unwrap_or(1))
+        }
+        Err(err) => command_failure(&format!("Failed to wait for '{program}'"), err),
+    }
+}
+
+fn run_vault_ssh_agent_cli() -> ExitCode {
+    log_debug!("Running `cossh vault ssh-agent`");
+    let paths = match super::vault::VaultPaths::resolve_default() {
+        Ok(paths) => paths,
+        Err(err) => return command_failure("Failed to resolve password vault paths", err),
+    };
+    let socket_path = super::ipc::ssh_agent_socket_path(&paths);
+    if !socket_path.exists() {
+        eprintln!("The vault SSH agent socket is not running; run `cossh vault unlock` first.");
+    }
+    println!("SSH_AUTH_SOCK={}; export SSH_AUTH_SOCK;", socket_path.display());
+    ExitCode::SUCCESS
+}
+
 fn run_set_master_password_cli() -> ExitCode {
     log_debug!("Running `cossh vault set-master-password`");
     let initial_password = match initialize_vault_if_needed() {
@@ -294,30 +726,312 @@ fn run_set_master_password_cli() -> ExitCode {
     }
 }
 
-pub(crate) fn run_internal_askpass() -> ExitCode {
-    log_debug!("Handling internal askpass invocation");
-    let prompt = super::transport::internal_askpass_prompt();
-    let prompt_decision = super::transport::classify_internal_askpass_prompt(prompt.as_deref());
-    log_debug!("Internal askpass prompt decision: {:?}", prompt_decision);
-    if prompt_decision != super::transport::AskpassPromptDecision::Allow {
-        eprintln!("Password auto-login is unavailable for this SSH prompt.");
+fn run_recovery_key_cli() -> ExitCode {
+    log_debug!("Running `cossh vault recovery-key`");
+    if let Err(err) = require_initialized_vault() {
+        eprintln!("{err}");
         return ExitCode::from(1);
     }
 
-    let Some(token) = super::transport::internal_askpass_token() else {
-        eprintln!("Missing internal askpass token");
+    let master_password = match prompt_existing_master_password() {
+        Ok(password) => password,
+        Err(err) => return command_failure("Failed to capture master password", err),
+    };
+    match super::keyslot::reset_recovery_key(master_password.expose_secret()) {
+        Ok(recovery_key) => {
+            println!("Password vault recovery key replaced; the previous key no longer works.");
+            print_recovery_key(&recovery_key);
+            ExitCode::SUCCESS
+        }
+        Err(err) => command_failure("Failed to replace password vault recovery key", err),
+    }
+}
+
+fn run_recover_cli() -> ExitCode {
+    log_debug!("Running `cossh vault recover`");
+    if let Err(err) = require_initialized_vault() {
+        eprintln!("{err}");
+        return ExitCode::from(1);
+    }
+
+    let recovery_key = match super::prompt_hidden_secret("Enter vault recovery key: ") {
+        Ok(recovery_key) => recovery_key,
+        Err(err) => return command_failure("Failed to capture recovery key", err),
+    };
+    if let Err(err) = super::keyslot::unlock_with_recovery_key(recovery_key.expose_secret()) {
+        return command_failure("Failed to recover password vault", err);
+    }
+    let new_password = match prompt_new_master_password_with_label("new") {
+        Ok(password) => password,
+        Err(err) => return command_failure("Failed to capture new master password", err),
+    };
+
+    match super::keyslot::recover_vault(recovery_key.expose_secret(), new_password.expose_secret()) {
+        Ok(()) => {
+            let _ = run_lock_cli();
+            println!("Password vault master password reset; any keyfile requirement was removed");
+            ExitCode::SUCCESS
+        }
+        Err(err) => command_failure("Failed to recover password vault", err),
+    }
+}
+
+fn run_vault_keyfile_cli(action: &args::VaultKeyfileAction) -> ExitCode {
+    log_debug!("Running `cossh vault keyfile`");
+    if let Err(err) = require_initialized_vault() {
+        eprintln!("{err}");
         return ExitCode::from(1);
+    }
+
+    let keyfile = match action {
+        args::VaultKeyfileAction::Show => {
+            match super::keyslot::configured_keyfile() {
+                Ok(Some(path)) => println!("Keyfile: {path}"),
+                Ok(None) => println!("Keyfile: none"),
+                Err(err) => return command_failure("Failed to read password vault metadata", err),
+            }
+            match super::keyslot::has_recovery_key() {
+                Ok(true) => println!("Recovery key: configured"),
+                Ok(false) => println!("Recovery key: none (run `cossh vault recovery-key` to create one)"),
+                Err(err) => return command_failure("Failed to read password vault metadata", err),
+            }
+            return ExitCode::SUCCESS;
+        }
+        args::VaultKeyfileAction::Set { path, generate } => {
+            if *generate {
+                if let Err(err) = super::keyslot::generate_keyfile(path) {
+                    return command_failure("Failed to generate keyfile", err);
+                }
+                println!("Generated keyfile {}", path.display());
+            }
+            Some(path.as_path())
+        }
+        args::VaultKeyfileAction::Remove => None,
     };
-    let token = SensitiveString::from_owned_string(token);
 
-    let client = match super::agent::AgentClient::new() {
-        Ok(client) => client,
-        Err(err) => return command_failure("Failed to access password vault agent", err),
+    let master_password = match prompt_existing_master_password() {
+        Ok(password) => password,
+        Err(err) => return command_failure("Failed to capture master password", err),
     };
+    if let Err(err) = super::keyslot::set_keyfile(master_password.expose_secret(), keyfile) {
+        if let (Some(path), args::VaultKeyfileAction::Set { generate: true, .. }) = (keyfile, action) {
+            let _ = std::fs::remove_file(path);
+        }
+        return command_failure("Failed to update password vault keyfile", err);
+    }
+    match keyfile {
+        Some(path) => println!("Unlocking now requires the master password and {}; keep a backup of it", path.display()),
+        None => println!("Unlocking no longer requires a keyfile"),
+    }
+    ExitCode::SUCCESS
+}
 
-    let secret = match client.get_secret(token.expose_secret()) {
-        Ok(secret) => secret,
-        Err(err) => return command_failure("Failed to read password vault entry", err),
+fn format_audit_record(record: &AuditRecord) -> String {
+    let mut line = format!("#{} {} {}", record.seq, record.timestamp, record.event.label());
+    if let Some(entry) = &record.entry {
+        line.push_str(&format!(" entry={entry}"));
+    }
+    if let Some(host) = &record.host {
+        line.push_str(&format!(" host={host}"));
+    }
+    if let Some(process) = &record.process {
+        line.push_str(&format!(" pid={process}"));
+    }
+    if let Some(detail) = &record.detail {
+        line.push_str(&format!(" [{detail}]"));
+    }
+    line
+}
+
+fn run_vault_audit_cli(tail: Option<usize>) -> ExitCode {
+    log_debug!("Running `cossh vault audit`");
+    let paths = match super::vault::VaultPaths::resolve_default() {
+        Ok(paths) => paths,
+        Err(err) => return command_failure("Failed to resolve password vault paths", err),
+    };
+    let verification = match audit::verify_audit_log(&paths) {
+        Ok(verification) => verification,
+        Err(err) => return command_failure("Failed to read password vault audit log", err),
+    };
+
+    let skip = tail.map_or(0, |tail| verification.records.len().saturating_sub(tail));
+    for record in verification.records.iter().skip(skip) {
+        println!("{}", format_audit_record(record));
+    }
+
+    match &verification.chain_break {
+        None => {
+            println!("Audit chain verified: {} record(s)", verification.records.len());
+            ExitCode::SUCCESS
+        }
+        Some(chain_break) => {
+            eprintln!("Audit chain verification failed at line {}: {}", chain_break.line, chain_break.reason);
+            eprintln!("Records from line {} onward may have been modified or removed.", chain_break.line);
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn confirm_doctor_repairs(count: usize) -> bool {
+    let answer = super::prompt_visible_value(&format!("Apply {count} safe repair(s)? [y/N]: ")).unwrap_or_default();
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+fn run_vault_doctor_cli(repair: bool) -> ExitCode {
+    log_debug!("Running `cossh vault doctor`");
+    if let Err(err) = require_initialized_vault() {
+        eprintln!("{err}");
+        return ExitCode::from(1);
+    }
+
+    let unlocked = match unlock_vault_for_cli(None) {
+        Ok(unlocked) => unlocked,
+        Err(err) => return command_failure("Failed to unlock password vault", err),
+    };
+    let report = match super::doctor::diagnose_vault(&unlocked) {
+        Ok(report) => report,
+        Err(err) => return command_failure("Failed to check password vault", err),
+    };
+
+    println!("Checked {} entry file(s): {} healthy", report.entries_checked, report.healthy_entries);
+    if report.issues.is_empty() {
+        println!("No problems found");
+        return ExitCode::SUCCESS;
+    }
+
+    for issue in &report.issues {
+        println!("{}: {}", issue.path.display(), issue.problem);
+        match &issue.repair {
+            Some(repair) => println!("  repair: {repair}"),
+            None => println!("  repair: manual review required"),
+        }
+    }
+
+    let repairable = report.repairable_issues().count();
+    let unrepairable = report.issues.len() - repairable;
+    if repairable == 0 || !(repair || confirm_doctor_repairs(repairable)) {
+        if repairable > 0 {
+            println!("No repairs applied. Run `cossh vault doctor --repair` to apply them.");
+        }
+        return ExitCode::from(1);
+    }
+
+    let mut failed = 0;
+    for issue in report.repairable_issues() {
+        if let Err(err) = super::doctor::apply_repair(unlocked.paths(), issue) {
+            failed += 1;
+            eprintln!("Failed to repair {}: {err}", issue.path.display());
+        }
+    }
+    println!("Applied {} of {} repair(s)", repairable - failed, repairable);
+    if failed > 0 || unrepairable > 0 {
+        return ExitCode::from(1);
+    }
+    ExitCode::SUCCESS
+}
+
+fn requested_kdf_params(rekey_args: &args::VaultRekeyArgs) -> Result<super::vault::KdfParams, String> {
+    let current = super::vault::current_kdf_params().map_err(|err| err.to_string())?;
+    let defaults = super::vault::KdfParams::DEFAULT;
+    // Never weaken an existing vault unless a lower value is requested explicitly.
+    let requested = super::vault::KdfParams {
+        memory_kib: rekey_args.memory_kib.unwrap_or(current.memory_kib.max(defaults.memory_kib)),
+        time_cost: rekey_args.time_cost.unwrap_or(current.time_cost.max(defaults.time_cost)),
+        parallelism: rekey_args.parallelism.unwrap_or(current.parallelism.max(defaults.parallelism)),
+    };
+    requested.validate().map_err(|err| err.to_string())?;
+
+    let Some(target_ms) = rekey_args.calibrate_ms else {
+        return Ok(requested);
+    };
+    println!("Calibrating Argon2id for about {target_ms} ms per unlock...");
+    super::vault::calibrate_kdf(requested, std::time::Duration::from_millis(target_ms)).map_err(|err| err.to_string())
+}
+
+fn run_vault_rekey_cli(rekey_args: &args::VaultRekeyArgs) -> ExitCode {
+    log_debug!("Running `cossh vault rekey`");
+    if let Err(err) = require_initialized_vault() {
+        eprintln!("{err}");
+        return ExitCode::from(1);
+    }
+
+    let kdf = match requested_kdf_params(rekey_args) {
+        Ok(kdf) => kdf,
+        Err(err) => return command_failure("Failed to choose KDF parameters", err),
+    };
+    let master_password = match prompt_existing_master_password() {
+        Ok(password) => password,
+        Err(err) => return command_failure("Failed to capture master password", err),
+    };
+
+    let options = super::rekey::RekeyOptions {
+        kdf,
+        rotate_data_key: rekey_args.rotate_data_key,
+    };
+    match super::rekey::rekey_vault(master_password.expose_secret(), options) {
+        Ok(outcome) => {
+            println!("Previous: {}", outcome.previous_kdf);
+            println!("Current:  {}", outcome.kdf);
+            if outcome.data_key_rotated {
+                println!("Data encryption key rotated; re-encrypted {} entry(s)", outcome.entries_reencrypted);
+                if let Some(recovery_key) = &outcome.recovery_key {
+                    println!("The previous recovery key no longer works.");
+                    print_recovery_key(recovery_key);
+                }
+                println!("Password vault locked");
+            }
+            println!("Password vault re-keyed");
+            ExitCode::SUCCESS
+        }
+        Err(err) => command_failure("Failed to re-key password vault", err),
+    }
+}
+
+pub(crate) fn run_internal_askpass() -> ExitCode {
+    log_debug!("Handling internal askpass invocation");
+    let prompt = super::transport::internal_askpass_prompt();
+    let prompt_decision = super::transport::classify_internal_askpass_prompt(prompt.as_deref());
+    log_debug!("Internal askpass prompt decision: {:?}", prompt_decision);
+    let token = match prompt_decision {
+        super::transport::AskpassPromptDecision::Allow => super::transport::internal_askpass_token(),
+        super::transport::AskpassPromptDecision::AllowPassphrase => super::transport::internal_askpass_passphrase_token(),
+        super::transport::AskpassPromptDecision::DenyMissing | super::transport::AskpassPromptDecision::DenyUnexpected => {
+            eprintln!("Password auto-login is unavailable for this SSH prompt.");
+            return ExitCode::from(1);
+        }
+    };
+    let forwarded = match prompt_decision {
+        super::transport::AskpassPromptDecision::Allow => super::transport::internal_askpass_forward_request(),
+        _ => None,
+    };
+    let secret = match (forwarded, token) {
+        (Some(forwarded), _) => {
+            // Answered by the vault on the machine this session was opened from.
+            match super::agent::request_forwarded_secret(&forwarded.socket_path, &forwarded.entry_name) {
+                Ok(secret) => secret,
+                Err(err) => return command_failure("Failed to read forwarded password vault entry", err),
+            }
+        }
+        (None, Some(token)) => {
+            let token = SensitiveString::from_owned_string(token);
+            let client = match super::agent::AgentClient::new() {
+                Ok(client) => client,
+                Err(err) => return command_failure("Failed to access password vault agent", err),
+            };
+            match client.get_secret(token.expose_secret()) {
+                Ok(secret) => secret,
+                Err(err) => return command_failure("Failed to read password vault entry", err),
+            }
+        }
+        (None, None) => {
+            // Only the other prompt kind is vault-backed (for example a key passphrase
+            // without a `vault_pass`), so this prompt goes back to the user.
+            log_debug!("No internal askpass token for this prompt kind; prompting on the terminal");
+            match super::prompt_hidden_secret(prompt.as_deref().unwrap_or_default()) {
+                Ok(secret) => secret,
+                Err(err) => return command_failure("Failed to read askpass response", err),
+            }
+        }
     };
 
     let result = {
@@ -344,12 +1058,25 @@ pub(crate) fn run_vault_command(vault_command: &args::VaultCommand) -> ExitCode
     log_debug!("Dispatching vault command '{}'", vault_command_name(vault_command));
     match vault_command {
         args::VaultCommand::Init => run_vault_init_cli(),
-        args::VaultCommand::AddPass(pass_name) => run_add_pass_cli(pass_name),
+        args::VaultCommand::AddPass(add_pass_args) => run_add_pass_cli(add_pass_args),
+        args::VaultCommand::AddKey(add_key_args) => run_add_key_cli(add_key_args),
         args::VaultCommand::RemovePass(pass_name) => run_remove_pass_cli(pass_name),
-        args::VaultCommand::List => run_list_passes_cli(),
+        args::VaultCommand::Rename(rename_args) => run_rename_pass_cli(rename_args),
+        args::VaultCommand::List(json) => run_list_passes_cli(*json),
         args::VaultCommand::Unlock => run_unlock_cli(),
         args::VaultCommand::Lock => run_lock_cli(),
-        args::VaultCommand::Status => run_vault_status_cli(),
+        args::VaultCommand::Status(json) => run_vault_status_cli(*json),
+        args::VaultCommand::Get(get_args) => run_vault_get_cli(get_args),
+        args::VaultCommand::Exec(exec_args) => run_vault_exec_cli(exec_args),
         args::VaultCommand::SetMasterPassword => run_set_master_password_cli(),
+        args::VaultCommand::Audit(tail) => run_vault_audit_cli(*tail),
+        args::VaultCommand::Doctor(repair) => run_vault_doctor_cli(*repair),
+        args::VaultCommand::Rekey(rekey_args) => run_vault_rekey_cli(rekey_args),
+        args::VaultCommand::Rotate(pass_name, source) => run_rotate_pass_cli(pass_name, source),
+        args::VaultCommand::Policy(policy_args) => run_entry_policy_cli(policy_args),
+        args::VaultCommand::RecoveryKey => run_recovery_key_cli(),
+        args::VaultCommand::Recover => run_recover_cli(),
+        args::VaultCommand::Keyfile(action) => run_vault_keyfile_cli(action),
+        args::VaultCommand::SshAgent => run_vault_ssh_agent_cli(),
     }
 }
@@ -0,0 +1,384 @@
+//! Vault integrity checks for `cossh vault doctor`.
+//!
+//! The doctor walks the vault directory with an unlocked data key, reports
+//! entries or history values that no longer decrypt, leftover
+//! `write_json_atomic` temp files and `cossh vault rekey` directories, and
+//! loosened permissions, and applies repairs that never discard data:
+//! damaged entries are moved to `vault/quarantine/` instead of deleted.
+//! Rekey directories are only removed when nothing in them decrypts under
+//! the current data key; a backup of entries that still do is restored.
+
+use crate::args::validate_vault_entry_name;
+use crate::auth::audit::{self, AuditEvent, AuditEventKind, AuditProcess};
+use crate::auth::rekey::{REKEY_BACKUP_PREFIX, REKEY_STAGING_PREFIX};
+use crate::auth::vault::{self, PRIVATE_DIRECTORY_MODE, PRIVATE_FILE_MODE, UnlockedVault, VaultError, VaultPaths};
+use chrono::Utc;
+use std::fmt;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const QUARANTINE_DIRNAME: &str = "quarantine";
+/// Temp files younger than this may belong to a write still in progress.
+const ORPHANED_TEMP_FILE_MIN_AGE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Safe repair available for one issue.
+pub enum DoctorRepair {
+    /// Delete a leftover temp file from an interrupted atomic write.
+    RemoveTempFile,
+    /// Reset permissions to the private vault mode.
+    RestrictPermissions { mode: u32 },
+    /// Rename an entry file so it matches the name it was encrypted under.
+    RenameEntry { to: PathBuf },
+    /// Move an unusable entry file out of `entries/`.
+    Quarantine,
+    /// Quarantine a copy of the entry file and keep the entry without its history.
+    DropHistory,
+    /// Move the entries of an interrupted rekey back into `entries/`,
+    /// quarantining the entries that replaced them.
+    RestoreRekeyBackup,
+    /// Delete a rekey directory whose entries no longer decrypt.
+    RemoveRekeyDirectory,
+}
+
+impl fmt::Display for DoctorRepair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RemoveTempFile => write!(f, "remove orphaned temp file"),
+            Self::RestrictPermissions { mode } => write!(f, "set permissions to {mode:o}"),
+            Self::RenameEntry { to } => write!(f, "rename to {}", to.display()),
+            Self::Quarantine => write!(f, "move to vault/{QUARANTINE_DIRNAME}/"),
+            Self::DropHistory => write!(f, "copy to vault/{QUARANTINE_DIRNAME}/ and drop the entry history"),
+            Self::RestoreRekeyBackup => write!(f, "restore these entries and move the current ones to vault/{QUARANTINE_DIRNAME}/"),
+            Self::RemoveRekeyDirectory => write!(f, "remove leftover rekey directory"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// One problem found by the doctor.
+pub struct DoctorIssue {
+    pub path: PathBuf,
+    pub problem: String,
+    pub repair: Option<DoctorRepair>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Outcome of one doctor run.
+pub struct DoctorReport {
+    /// Entry files that were inspected.
+    pub entries_checked: usize,
+    /// Entry files that decrypted and matched their file name.
+    pub healthy_entries: usize,
+    pub issues: Vec<DoctorIssue>,
+}
+
+impl DoctorReport {
+    /// Issues that have a safe repair.
+    pub fn repairable_issues(&self) -> impl Iterator<Item = &DoctorIssue> {
+        self.issues.iter().filter(|issue| issue.repair.is_some())
+    }
+
+    fn push(&mut self, path: &Path, problem: impl Into<String>, repair: Option<DoctorRepair>) {
+        self.issues.push(DoctorIssue {
+            path: path.to_path_buf(),
+            problem: problem.into(),
+            repair,
+        });
+    }
+}
+
+/// Check every entry, temp file, rekey directory and permission in the vault.
+pub fn diagnose_vault(unlocked: &UnlockedVault) -> Result<DoctorReport, VaultError> {
+    let paths = unlocked.paths();
+    let mut report = DoctorReport::default();
+
+    check_directory_permissions(&mut report, &paths.vault_dir())?;
+    check_directory_permissions(&mut report, &paths.entries_dir())?;
+    check_directory_permissions(&mut report, &paths.run_dir())?;
+    check_directory_permissions(&mut report, &quarantine_dir(paths))?;
+    check_file_permissions(&mut report, &paths.metadata_path())?;
+    check_file_permissions(&mut report, &audit::audit_log_path(paths))?;
+    check_file_permissions(&mut report, &audit::audit_head_path(paths))?;
+
+    check_temp_files(&mut report, &paths.vault_dir())?;
+    // Entries about to be replaced by a rekey backup are not worth checking.
+    let restoring = check_rekey_directories(&mut report, unlocked)?;
+    if paths.entries_dir().is_dir() {
+        check_temp_files(&mut report, &paths.entries_dir())?;
+        if !restoring {
+            check_entries(&mut report, unlocked)?;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Apply the repair recorded on `issue`.
+pub fn apply_repair(paths: &VaultPaths, issue: &DoctorIssue) -> Result<(), VaultError> {
+    let Some(repair) = &issue.repair else {
+        return Ok(());
+    };
+
+    match repair {
+        DoctorRepair::RemoveTempFile => fs::remove_file(&issue.path)?,
+        DoctorRepair::RestrictPermissions { mode } => fs::set_permissions(&issue.path, fs::Permissions::from_mode(*mode))?,
+        DoctorRepair::RenameEntry { to } => {
+            if to.exists() {
+                return Err(VaultError::InvalidVaultFormat(format!("{} already exists", to.display())));
+            }
+            fs::rename(&issue.path, to)?;
+            record_repair(paths, AuditEventKind::EntryStored, to, "renamed by vault doctor");
+        }
+        DoctorRepair::Quarantine => {
+            fs::rename(&issue.path, quarantine_target(paths, &issue.path)?)?;
+            record_repair(paths, AuditEventKind::EntryRemoved, &issue.path, "quarantined by vault doctor");
+        }
+        DoctorRepair::DropHistory => {
+            let mut entry = vault::read_entry_file(&issue.path)?;
+            fs::copy(&issue.path, quarantine_target(paths, &issue.path)?)?;
+            entry.history.clear();
+            vault::write_entry_file(&issue.path, &entry)?;
+            record_repair(paths, AuditEventKind::EntryStored, &issue.path, "history dropped by vault doctor");
+        }
+        DoctorRepair::RestoreRekeyBackup => {
+            let entries_dir = paths.entries_dir();
+            if entries_dir.exists() {
+                fs::rename(&entries_dir, quarantine_target(paths, &entries_dir)?)?;
+            }
+            fs::rename(issue.path.join("entries"), &entries_dir)?;
+            fs::remove_dir_all(&issue.path)?;
+            audit::record_event(
+                paths,
+                AuditEvent::new(AuditEventKind::EntryStored)
+                    .with_process(Some(AuditProcess::current()))
+                    .with_detail("entries restored from an interrupted rekey by vault doctor"),
+            );
+        }
+        DoctorRepair::RemoveRekeyDirectory => fs::remove_dir_all(&issue.path)?,
+    }
+    Ok(())
+}
+
+fn check_entries(report: &mut DoctorReport, unlocked: &UnlockedVault) -> Result<(), VaultError> {
+    let entries_dir = unlocked.paths().entries_dir();
+    let mut files: Vec<PathBuf> = fs::read_dir(&entries_dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    files.sort();
+
+    for path in files {
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            report.push(&path, "file name is not valid UTF-8", None);
+            continue;
+        };
+        if vault::is_atomic_temp_file_name(file_name) {
+            continue;
+        }
+        if !path.is_file() || path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+            report.push(&path, "unexpected file in entries directory", None);
+            continue;
+        }
+
+        report.entries_checked += 1;
+        check_file_permissions(report, &path)?;
+        let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+        if !validate_vault_entry_name(stem) {
+            report.push(&path, "entry file name is not a valid vault entry name", Some(DoctorRepair::Quarantine));
+            continue;
+        }
+
+        let entry = match vault::read_entry_file(&path) {
+            Ok(entry) => entry,
+            Err(err) => {
+                report.push(&path, format!("entry could not be read ({err})"), Some(DoctorRepair::Quarantine));
+                continue;
+            }
+        };
+
+        if let Err(err) = unlocked.decrypt_entry(&entry) {
+            let problem = match err {
+                VaultError::InvalidMasterPassword => "entry failed authentication (corrupted ciphertext or name/AAD mismatch)".to_string(),
+                other => format!("entry could not be decrypted ({other})"),
+            };
+            report.push(&path, problem, Some(DoctorRepair::Quarantine));
+            continue;
+        }
+        if let Err(err) = unlocked.decrypt_history(&entry) {
+            let problem = match err {
+                VaultError::InvalidMasterPassword => "entry history failed authentication (corrupted ciphertext)".to_string(),
+                other => format!("entry history could not be decrypted ({other})"),
+            };
+            report.push(&path, problem, Some(DoctorRepair::DropHistory));
+            continue;
+        }
+
+        if entry.name != stem {
+            // The ciphertext is bound to `entry.name`, so only a rename back
+            // to that name makes the entry usable again.
+            let repair = match unlocked.paths().entry_path(&entry.name) {
+                Ok(target) if !target.exists() => DoctorRepair::RenameEntry { to: target },
+                _ => DoctorRepair::Quarantine,
+            };
+            report.push(&path, format!("entry was encrypted as '{}' but stored as '{stem}'", entry.name), Some(repair));
+            continue;
+        }
+
+        report.healthy_entries += 1;
+    }
+
+    Ok(())
+}
+
+/// Report directories left behind by an interrupted `cossh vault rekey`.
+///
+/// Staging directories and backups taken after the new key was saved hold
+/// entries under a data key that is gone. A backup whose entries still
+/// decrypt was taken before the new key was saved, so it holds the only
+/// usable entries; returns whether such a backup will be restored.
+fn check_rekey_directories(report: &mut DoctorReport, unlocked: &UnlockedVault) -> Result<bool, VaultError> {
+    let vault_dir = unlocked.paths().vault_dir();
+    if !vault_dir.is_dir() {
+        return Ok(false);
+    }
+
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(&vault_dir)? {
+        let entry = entry?;
+        let is_rekey_dir = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.starts_with(REKEY_STAGING_PREFIX) || name.starts_with(REKEY_BACKUP_PREFIX));
+        if is_rekey_dir && entry.file_type()?.is_dir() {
+            dirs.push(entry.path());
+        }
+    }
+    dirs.sort();
+
+    let mut restoring = false;
+    for path in dirs {
+        let is_backup = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(REKEY_BACKUP_PREFIX));
+        if !is_backup {
+            report.push(
+                &path,
+                "staging directory left by an interrupted rekey",
+                Some(DoctorRepair::RemoveRekeyDirectory),
+            );
+        } else if !has_decryptable_entry(unlocked, &path.join("entries")) {
+            report.push(&path, "backup directory left by a finished rekey", Some(DoctorRepair::RemoveRekeyDirectory));
+        } else if !restoring {
+            report.push(
+                &path,
+                "rekey was interrupted before the new key was saved; the usable entries are in this backup",
+                Some(DoctorRepair::RestoreRekeyBackup),
+            );
+            restoring = true;
+        } else {
+            report.push(&path, "another backup of usable entries from an interrupted rekey", None);
+        }
+    }
+    Ok(restoring)
+}
+
+/// Whether any entry file in `dir` decrypts under the current data key.
+fn has_decryptable_entry(unlocked: &UnlockedVault, dir: &Path) -> bool {
+    let Ok(files) = fs::read_dir(dir) else {
+        return false;
+    };
+    files
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().and_then(|extension| extension.to_str()) == Some("json"))
+        .any(|path| vault::read_entry_file(&path).is_ok_and(|entry| unlocked.decrypt_entry(&entry).is_ok()))
+}
+
+fn check_temp_files(report: &mut DoctorReport, dir: &Path) -> Result<(), VaultError> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let Some(file_name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if !vault::is_atomic_temp_file_name(&file_name) || !entry.file_type()?.is_file() {
+            continue;
+        }
+
+        let age = entry
+            .metadata()?
+            .modified()
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .unwrap_or_default();
+        if age >= ORPHANED_TEMP_FILE_MIN_AGE {
+            report.push(
+                &entry.path(),
+                "orphaned temp file from an interrupted write",
+                Some(DoctorRepair::RemoveTempFile),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn check_directory_permissions(report: &mut DoctorReport, path: &Path) -> Result<(), VaultError> {
+    check_permissions(report, path, PRIVATE_DIRECTORY_MODE, true)
+}
+
+fn check_file_permissions(report: &mut DoctorReport, path: &Path) -> Result<(), VaultError> {
+    check_permissions(report, path, PRIVATE_FILE_MODE, false)
+}
+
+fn check_permissions(report: &mut DoctorReport, path: &Path, expected_mode: u32, expect_directory: bool) -> Result<(), VaultError> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+
+    if metadata.file_type().is_symlink() || metadata.is_dir() != expect_directory {
+        report.push(path, "unexpected file type", None);
+        return Ok(());
+    }
+
+    let mode = metadata.permissions().mode() & 0o777;
+    if mode & 0o077 != 0 {
+        report.push(
+            path,
+            format!("permissions {mode:o} allow group or other access"),
+            Some(DoctorRepair::RestrictPermissions { mode: expected_mode }),
+        );
+    }
+    Ok(())
+}
+
+fn quarantine_dir(paths: &VaultPaths) -> PathBuf {
+    paths.vault_dir().join(QUARANTINE_DIRNAME)
+}
+
+/// Timestamped path in the quarantine directory for `path`, creating the directory.
+fn quarantine_target(paths: &VaultPaths, path: &Path) -> Result<PathBuf, VaultError> {
+    let quarantine_dir = quarantine_dir(paths);
+    crate::platform::create_private_directory(&quarantine_dir, PRIVATE_DIRECTORY_MODE)?;
+    let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or("entry");
+    Ok(quarantine_dir.join(format!("{file_name}.{}", Utc::now().format("%Y%m%dT%H%M%S%.f"))))
+}
+
+fn record_repair(paths: &VaultPaths, kind: AuditEventKind, entry_path: &Path, detail: &str) {
+    let mut event = AuditEvent::new(kind).with_process(Some(AuditProcess::current())).with_detail(detail);
+    if let Some(name) = entry_path.file_stem().and_then(|stem| stem.to_str()) {
+        event = event.with_entry(name);
+    }
+    audit::record_event(paths, event);
+}
+
+#[cfg(test)]
+#[path = "../test/auth/doctor.rs"]
+mod tests;
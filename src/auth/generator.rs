@@ -0,0 +1,229 @@
+//! Password generation for `cossh vault add --generate` and `cossh vault rotate`.
+//!
+//! Random passwords draw uniformly from the enabled character classes and
+//! always contain at least one character from each class. Pronounceable
+//! passwords alternate consonants and vowels and append the other classes.
+//! The device-safe symbol set leaves out characters that network operating
+//! systems treat specially at the CLI, such as `?`, `!`, `#`, quotes and `$`.
+
+use crate::auth::secret::SensitiveString;
+use getrandom::fill as random_fill;
+use std::fmt;
+use zeroize::Zeroizing;
+
+pub const DEFAULT_PASSWORD_LENGTH: usize = 20;
+pub const MIN_PASSWORD_LENGTH: usize = 4;
+pub const MAX_PASSWORD_LENGTH: usize = 256;
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!#$%&()*+,-./:;<=>?@[]^_{|}~";
+const DEVICE_SAFE_SYMBOLS: &str = "-_.+=@%";
+const AMBIGUOUS: &str = "0Oo1lI|";
+const CONSONANTS: &str = "bcdfghjkmnprstvwxz";
+const VOWELS: &str = "aeiou";
+const PRONOUNCEABLE_DIGITS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Symbol characters a generated password may contain.
+pub enum SymbolSet {
+    None,
+    /// Symbols accepted by common network device CLIs without escaping.
+    DeviceSafe,
+    Full,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Options for one generated password.
+pub struct GeneratorOptions {
+    pub length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: SymbolSet,
+    /// Leave out characters that are easy to misread, such as `0`/`O` and `1`/`l`.
+    pub exclude_ambiguous: bool,
+    /// Build the password from consonant-vowel syllables.
+    pub pronounceable: bool,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            length: DEFAULT_PASSWORD_LENGTH,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: SymbolSet::Full,
+            exclude_ambiguous: false,
+            pronounceable: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Errors returned by password generation.
+pub enum GeneratorError {
+    InvalidOptions(String),
+    Random(String),
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidOptions(message) => write!(f, "invalid generator options: {message}"),
+            Self::Random(message) => write!(f, "secure random generation failed: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for GeneratorError {}
+
+impl GeneratorOptions {
+    fn character_classes(&self) -> Vec<Vec<char>> {
+        let symbols = match self.symbols {
+            SymbolSet::None => "",
+            SymbolSet::DeviceSafe => DEVICE_SAFE_SYMBOLS,
+            SymbolSet::Full => SYMBOLS,
+        };
+        [(self.lowercase, LOWERCASE), (self.uppercase, UPPERCASE), (self.digits, DIGITS), (true, symbols)]
+            .into_iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, class)| self.filter_ambiguous(class))
+            .filter(|class| !class.is_empty())
+            .collect()
+    }
+
+    fn filter_ambiguous(&self, class: &str) -> Vec<char> {
+        class.chars().filter(|ch| !self.exclude_ambiguous || !AMBIGUOUS.contains(*ch)).collect()
+    }
+
+    fn validate(&self, class_count: usize) -> Result<(), GeneratorError> {
+        if !(MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&self.length) {
+            return Err(GeneratorError::InvalidOptions(format!(
+                "length must be between {MIN_PASSWORD_LENGTH} and {MAX_PASSWORD_LENGTH}"
+            )));
+        }
+        if class_count == 0 {
+            return Err(GeneratorError::InvalidOptions("at least one character class must be enabled".to_string()));
+        }
+        if self.length < class_count {
+            return Err(GeneratorError::InvalidOptions(format!(
+                "length {} is too short for {class_count} required character classes",
+                self.length
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Generate one password for `options`.
+pub fn generate_password(options: &GeneratorOptions) -> Result<SensitiveString, GeneratorError> {
+    let password = if options.pronounceable {
+        generate_pronounceable(options)?
+    } else {
+        generate_random(options)?
+    };
+    Ok(SensitiveString::from_owned_string(password.iter().collect()))
+}
+
+fn generate_random(options: &GeneratorOptions) -> Result<Zeroizing<Vec<char>>, GeneratorError> {
+    let classes = options.character_classes();
+    options.validate(classes.len())?;
+
+    let alphabet: Vec<char> = classes.iter().flatten().copied().collect();
+    let mut password = Zeroizing::new(Vec::with_capacity(options.length));
+    for class in &classes {
+        password.push(pick(class)?);
+    }
+    while password.len() < options.length {
+        password.push(pick(&alphabet)?);
+    }
+    shuffle(&mut password)?;
+    Ok(password)
+}
+
+fn generate_pronounceable(options: &GeneratorOptions) -> Result<Zeroizing<Vec<char>>, GeneratorError> {
+    if !options.lowercase && !options.uppercase {
+        return Err(GeneratorError::InvalidOptions(
+            "pronounceable passwords need lowercase or uppercase letters".to_string(),
+        ));
+    }
+    let symbols = match options.symbols {
+        SymbolSet::None => Vec::new(),
+        SymbolSet::DeviceSafe => options.filter_ambiguous(DEVICE_SAFE_SYMBOLS),
+        SymbolSet::Full => options.filter_ambiguous(SYMBOLS),
+    };
+    let digits = if options.digits { options.filter_ambiguous(DIGITS) } else { Vec::new() };
+    let digit_count = if digits.is_empty() { 0 } else { PRONOUNCEABLE_DIGITS };
+    let symbol_count = usize::from(!symbols.is_empty());
+    let letter_count = options.length.saturating_sub(digit_count + symbol_count);
+    options.validate(1 + digit_count + symbol_count)?;
+    if letter_count < 2 {
+        return Err(GeneratorError::InvalidOptions(format!(
+            "length {} is too short for a pronounceable password",
+            options.length
+        )));
+    }
+
+    // Letters are filtered in the case they are emitted in, so an uppercased
+    // `i` cannot bring back the excluded `I`.
+    let syllable_letters = |uppercase: bool| {
+        [CONSONANTS, VOWELS].map(|class| {
+            let class = if uppercase { class.to_ascii_uppercase() } else { class.to_string() };
+            options.filter_ambiguous(&class)
+        })
+    };
+    let (lowercase_letters, uppercase_letters) = (syllable_letters(false), syllable_letters(true));
+    let uppercase_index = if options.lowercase && options.uppercase {
+        Some(random_index(letter_count)?)
+    } else {
+        None
+    };
+    let mut password = Zeroizing::new(Vec::with_capacity(options.length));
+    for index in 0..letter_count {
+        let letters = if !options.lowercase || uppercase_index == Some(index) {
+            &uppercase_letters
+        } else {
+            &lowercase_letters
+        };
+        password.push(pick(&letters[index % 2])?);
+    }
+    if symbol_count > 0 {
+        password.push(pick(&symbols)?);
+    }
+    for _ in 0..digit_count {
+        password.push(pick(&digits)?);
+    }
+    Ok(password)
+}
+
+fn pick(class: &[char]) -> Result<char, GeneratorError> {
+    Ok(class[random_index(class.len())?])
+}
+
+fn shuffle(values: &mut [char]) -> Result<(), GeneratorError> {
+    for index in (1..values.len()).rev() {
+        values.swap(index, random_index(index + 1)?);
+    }
+    Ok(())
+}
+
+/// Uniform index in `0..bound` using rejection sampling.
+fn random_index(bound: usize) -> Result<usize, GeneratorError> {
+    let bound = u32::try_from(bound).map_err(|_| GeneratorError::InvalidOptions("character set is too large".to_string()))?;
+    let zone = u32::MAX - (u32::MAX % bound);
+    loop {
+        let mut bytes = [0u8; 4];
+        random_fill(&mut bytes).map_err(|err| GeneratorError::Random(err.to_string()))?;
+        let value = u32::from_le_bytes(bytes);
+        if value < zone {
+            return Ok((value % bound) as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "../test/auth/generator.rs"]
+mod tests;
@@ -2,15 +2,17 @@
 
 use crate::auth::secret::{SensitiveString, serde_sensitive_string};
 use crate::auth::vault::VaultPaths;
+use crate::config::AuthSettings;
 use crate::log_debug;
 use interprocess::local_socket::traits::StreamCommon;
 use interprocess::local_socket::{GenericFilePath, ToFsName};
-use interprocess::local_socket::{Listener as LocalSocketListener, ListenerNonblockingMode, ListenerOptions, Stream as LocalSocketStream, prelude::*};
+use interprocess::local_socket::{Listener as LocalSocketListener, ListenerOptions, Stream as LocalSocketStream, prelude::*};
 use nix::unistd::Uid;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::{self, BufRead, BufReader, Read, Write};
 use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 use zeroize::Zeroizing;
@@ -18,6 +20,10 @@ use zeroize::Zeroizing;
 const AGENT_ENDPOINT_PREFIX: &str = "cossh-agent-v3-";
 const LEGACY_AGENT_STATE_FILENAME: &str = "agent-state.json";
 const VAULT_STATUS_EVENT_FILENAME: &str = "vault-events";
+const SSH_AGENT_SOCKET_FILENAME: &str = "ssh-agent.sock";
+/// File name prefix of the per-launch vault forwarding sockets.
+pub(crate) const VAULT_FORWARD_SOCKET_PREFIX: &str = "vault-forward-";
+const VAULT_FORWARD_SOCKET_TOKEN_BYTES: usize = 8;
 const UNIX_SOCKET_MODE: u32 = 0o600;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -53,6 +59,19 @@ impl AgentPeerTrust {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Identity of the process on the other end of an agent connection.
+pub(crate) struct AgentPeer {
+    pub(crate) trust: AgentPeerTrust,
+    pub(crate) pid: Option<u32>,
+}
+
+impl From<AgentPeerTrust> for AgentPeer {
+    fn from(trust: AgentPeerTrust) -> Self {
+        Self { trust, pid: None }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 /// Unlock timeout policy sent to the agent.
 pub struct UnlockPolicy {
@@ -60,14 +79,59 @@ pub struct UnlockPolicy {
     pub idle_timeout_seconds: u64,
     /// Absolute unlock lifetime cap.
     pub session_timeout_seconds: u64,
+    /// Events besides the timeouts that re-lock the vault.
+    #[serde(default)]
+    pub lock_triggers: LockTriggers,
 }
 
 impl UnlockPolicy {
-    /// Build a new unlock policy.
+    /// Build a new unlock policy with the default lock triggers.
     pub fn new(idle_timeout_seconds: u64, session_timeout_seconds: u64) -> Self {
         Self {
             idle_timeout_seconds,
             session_timeout_seconds,
+            lock_triggers: LockTriggers::default(),
+        }
+    }
+
+    /// Build the policy configured in `auth_settings`.
+    pub fn from_auth_settings(auth_settings: &AuthSettings) -> Self {
+        Self::new(auth_settings.idle_timeout_seconds, auth_settings.session_timeout_seconds).with_lock_triggers(LockTriggers {
+            tui_exit: auth_settings.lock_on_tui_exit,
+            signal: auth_settings.lock_on_signal,
+            resume: auth_settings.lock_on_resume,
+            max_failed_authorizations: auth_settings.lock_after_failed_authorizations,
+        })
+    }
+
+    /// Replace the lock triggers of this policy.
+    pub fn with_lock_triggers(mut self, lock_triggers: LockTriggers) -> Self {
+        self.lock_triggers = lock_triggers;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+/// Events that re-lock an unlocked vault before its timeouts elapse.
+pub struct LockTriggers {
+    /// Lock when the last session manager that saw the vault unlocked exits.
+    pub tui_exit: bool,
+    /// Lock when the agent process receives `SIGUSR1`.
+    pub signal: bool,
+    /// Lock when the machine resumes from suspend.
+    pub resume: bool,
+    /// Lock after this many untrusted clients, invalid askpass tokens or refused forwarded requests in a row; `0` disables it.
+    pub max_failed_authorizations: u32,
+}
+
+impl Default for LockTriggers {
+    fn default() -> Self {
+        Self {
+            tui_exit: false,
+            signal: true,
+            resume: true,
+            max_failed_authorizations: 5,
         }
     }
 }
@@ -136,6 +200,12 @@ pub enum AgentRequestPayload {
     },
     AuthorizeAskpass {
         name: String,
+        /// Host alias the secret is requested for, recorded in the audit log.
+        #[serde(default)]
+        host: Option<String>,
+        /// Destination hostname, matched against entry policy host patterns.
+        #[serde(default)]
+        destination: Option<String>,
     },
     EntryStatus {
         name: String,
@@ -144,7 +214,25 @@ pub enum AgentRequestPayload {
         #[serde(with = "serde_sensitive_string")]
         token: SensitiveString,
     },
+    /// Release `name` to a remote session opened by the calling process for `host`.
+    ForwardedSecret {
+        name: String,
+        /// Inventory alias the caller launched with `vault_forward`.
+        host: String,
+        /// Destination hostname, matched against entry policy host patterns.
+        #[serde(default)]
+        destination: Option<String>,
+    },
+    /// Change one entry with the unlocked session's data key, so a session
+    /// manager never derives it from the master password again.
+    ChangeEntry {
+        change: AgentEntryChange,
+    },
     Lock,
+    /// Register the calling session manager for the `tui_exit` lock trigger.
+    AttachTui,
+    /// Unregister the calling session manager as it exits.
+    DetachTui,
 }
 
 impl AgentRequestPayload {
@@ -156,11 +244,40 @@ impl AgentRequestPayload {
             Self::AuthorizeAskpass { .. } => "authorize_askpass",
             Self::EntryStatus { .. } => "entry_status",
             Self::GetSecret { .. } => "get_secret",
+            Self::ForwardedSecret { .. } => "forwarded_secret",
+            Self::ChangeEntry { .. } => "change_entry",
             Self::Lock => "lock",
+            Self::AttachTui => "attach_tui",
+            Self::DetachTui => "detach_tui",
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+/// Entry change requested through [`AgentRequestPayload::ChangeEntry`].
+pub enum AgentEntryChange {
+    /// Store a new entry.
+    Store {
+        name: String,
+        #[serde(with = "serde_sensitive_string")]
+        secret: SensitiveString,
+    },
+    /// Replace an entry's value, keeping the old one in history.
+    Replace {
+        name: String,
+        #[serde(with = "serde_sensitive_string")]
+        secret: SensitiveString,
+    },
+    Rename {
+        name: String,
+        new_name: String,
+    },
+    Remove {
+        name: String,
+    },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 /// Top-level client request wrapper.
 pub struct AgentRequest {
@@ -250,7 +367,18 @@ pub fn connect(paths: &VaultPaths) -> io::Result<LocalSocketStream> {
     Ok(stream)
 }
 
-pub(crate) fn agent_peer_trust(stream: &LocalSocketStream) -> AgentPeerTrust {
+pub(crate) fn agent_peer(stream: &LocalSocketStream) -> AgentPeer {
+    AgentPeer {
+        trust: agent_peer_trust(stream),
+        pid: stream
+            .peer_creds()
+            .ok()
+            .and_then(|credentials| credentials.pid())
+            .and_then(|pid| u32::try_from(pid).ok()),
+    }
+}
+
+fn agent_peer_trust(stream: &LocalSocketStream) -> AgentPeerTrust {
     match is_trusted_cossh_peer(stream) {
         Ok(true) => AgentPeerTrust::TrustedCossh,
         Ok(false) => AgentPeerTrust::Untrusted,
@@ -268,6 +396,68 @@ pub fn cleanup_endpoint(paths: &VaultPaths) -> io::Result<()> {
     cleanup_local_endpoint(paths)
 }
 
+/// Path of the OpenSSH agent-protocol socket served by the unlock agent.
+pub fn ssh_agent_socket_path(paths: &VaultPaths) -> PathBuf {
+    paths.run_dir().join(SSH_AGENT_SOCKET_FILENAME)
+}
+
+/// Bind the OpenSSH agent-protocol socket.
+///
+/// Only called after the unlock-agent endpoint was bound, so any socket
+/// already at the path belongs to a previous agent that did not clean up.
+pub fn bind_ssh_agent_listener(paths: &VaultPaths) -> io::Result<UnixListener> {
+    bind_run_dir_socket(paths, &ssh_agent_socket_path(paths))
+}
+
+/// Remove the OpenSSH agent-protocol socket.
+pub fn cleanup_ssh_agent_socket(paths: &VaultPaths) -> io::Result<()> {
+    remove_socket_file(&ssh_agent_socket_path(paths))
+}
+
+/// Bind a new vault forwarding socket for one `vault_forward` launch.
+///
+/// Each launch gets its own randomly named socket so the process serving it
+/// knows which host alias every request on it belongs to.
+pub fn bind_vault_forward_listener(paths: &VaultPaths) -> io::Result<(PathBuf, UnixListener)> {
+    let mut token = [0u8; VAULT_FORWARD_SOCKET_TOKEN_BYTES];
+    getrandom::fill(&mut token).map_err(|err| io::Error::other(format!("failed to name vault forwarding socket: {err}")))?;
+    let token = token.iter().map(|byte| format!("{byte:02x}")).collect::<String>();
+    let socket_path = paths.run_dir().join(format!("{VAULT_FORWARD_SOCKET_PREFIX}{token}.sock"));
+    let listener = bind_run_dir_socket(paths, &socket_path)?;
+    Ok((socket_path, listener))
+}
+
+/// Remove a socket bound by [`bind_vault_forward_listener`].
+pub fn cleanup_vault_forward_socket(socket_path: &Path) -> io::Result<()> {
+    remove_socket_file(socket_path)
+}
+
+fn bind_run_dir_socket(paths: &VaultPaths, socket_path: &Path) -> io::Result<UnixListener> {
+    fs::create_dir_all(paths.run_dir())?;
+    set_restrictive_directory_permissions(&paths.run_dir())?;
+    remove_socket_file(socket_path)?;
+    let listener = UnixListener::bind(socket_path)?;
+    set_restrictive_file_permissions(socket_path)?;
+    log_debug!("Bound password vault agent socket '{}'", socket_path.display());
+    Ok(listener)
+}
+
+/// Pid of the process connected to an SSH agent socket, when available.
+pub(crate) fn ssh_agent_peer_pid(stream: &UnixStream) -> Option<u32> {
+    #[cfg(target_os = "linux")]
+    {
+        nix::sys::socket::getsockopt(stream, nix::sys::socket::sockopt::PeerCredentials)
+            .ok()
+            .and_then(|credentials| u32::try_from(credentials.pid()).ok())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = stream;
+        None
+    }
+}
+
 /// Persist a vault status event for local consumers.
 pub fn broadcast_vault_status_event(paths: &VaultPaths, kind: VaultStatusEventKind, status: VaultStatus) -> io::Result<()> {
     let run_dir = paths.run_dir();
@@ -330,14 +520,14 @@ fn create_listener(paths: &VaultPaths) -> io::Result<LocalSocketListener> {
     create_listener_for_endpoint(paths, &endpoint)
 }
 
-fn write_json_line<T: Serialize, W: Write>(stream: &mut W, value: &T) -> io::Result<()> {
+pub(crate) fn write_json_line<T: Serialize, W: Write>(stream: &mut W, value: &T) -> io::Result<()> {
     let mut bytes = Zeroizing::new(serde_json::to_vec(value).map_err(|err| io::Error::other(format!("failed to serialize IPC message: {err}")))?);
     bytes.push(b'\n');
     stream.write_all(&bytes)?;
     stream.flush()
 }
 
-fn read_json_line<T: for<'de> Deserialize<'de>, R: Read>(stream: &mut R) -> io::Result<T> {
+pub(crate) fn read_json_line<T: for<'de> Deserialize<'de>, R: Read>(stream: &mut R) -> io::Result<T> {
     let mut reader = BufReader::new(stream);
     let mut line = Zeroizing::new(Vec::new());
     reader.read_until(b'\n', &mut line)?;
@@ -406,7 +596,7 @@ fn create_listener_for_endpoint(paths: &VaultPaths, endpoint: &AgentEndpoint) ->
     fs::create_dir_all(paths.run_dir())?;
     set_restrictive_directory_permissions(&paths.run_dir())?;
     let name = endpoint.socket_path.as_os_str().to_fs_name::<GenericFilePath>()?;
-    let listener = ListenerOptions::new().name(name).nonblocking(ListenerNonblockingMode::Accept).create_sync()?;
+    let listener = ListenerOptions::new().name(name).create_sync()?;
     set_restrictive_file_permissions(&endpoint.socket_path)?;
     Ok(listener)
 }
@@ -457,6 +647,18 @@ fn remove_stale_socket_file(paths: &VaultPaths) -> io::Result<bool> {
     Ok(false)
 }
 
+fn remove_socket_file(path: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(path),
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", path.display()),
+        )),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
 fn cleanup_local_endpoint(paths: &VaultPaths) -> io::Result<()> {
     let endpoint = agent_endpoint(paths);
     if !endpoint.socket_path.exists() {
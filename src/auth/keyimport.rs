@@ -0,0 +1,124 @@
+//! Import of on-disk OpenSSH private keys into vault key entries.
+//!
+//! `cossh vault add-key` reads a private key file, decrypts it with its
+//! passphrase when needed, and stores the plain key inside the encrypted vault
+//! so the vault SSH agent can serve it. The original file can then be shredded
+//! so the key no longer exists unencrypted (or weakly encrypted) on disk.
+
+use crate::auth::secret::{ExposeSecret, SensitiveString};
+use crate::auth::vault::{SshKeyConstraints, UnlockedVault, VaultError};
+use getrandom::fill as random_fill;
+use ssh_key::{HashAlg, LineEnding, PrivateKey};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Overwrite passes applied before a shredded file is unlinked.
+const SHRED_PASSES: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Summary of one imported key.
+pub struct ImportedKey {
+    pub algorithm: String,
+    pub fingerprint: String,
+    pub comment: String,
+    /// Whether the file was passphrase-protected before import.
+    pub was_encrypted: bool,
+}
+
+#[derive(Debug)]
+/// Errors returned while importing a key file.
+pub enum KeyImportError {
+    Io(io::Error),
+    InvalidKey(String),
+    WrongPassphrase,
+    Vault(VaultError),
+}
+
+impl fmt::Display for KeyImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read key file: {err}"),
+            Self::InvalidKey(message) => write!(f, "invalid OpenSSH private key: {message}"),
+            Self::WrongPassphrase => write!(f, "incorrect key passphrase"),
+            Self::Vault(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for KeyImportError {}
+
+impl From<io::Error> for KeyImportError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<VaultError> for KeyImportError {
+    fn from(err: VaultError) -> Self {
+        Self::Vault(err)
+    }
+}
+
+/// Store the private key at `path` as vault key entry `name`.
+///
+/// `passphrase` is only called when the key file is encrypted.
+pub fn import_private_key(
+    unlocked: &UnlockedVault,
+    name: &str,
+    path: &Path,
+    constraints: SshKeyConstraints,
+    passphrase: impl FnOnce() -> io::Result<SensitiveString>,
+) -> Result<ImportedKey, KeyImportError> {
+    let contents = Zeroizing::new(fs::read_to_string(path)?);
+    let mut private_key = PrivateKey::from_openssh(contents.as_str()).map_err(|err| KeyImportError::InvalidKey(err.to_string()))?;
+    let was_encrypted = private_key.is_encrypted();
+    if was_encrypted {
+        let passphrase = passphrase()?;
+        private_key = private_key
+            .decrypt(passphrase.expose_secret().as_bytes())
+            .map_err(|_| KeyImportError::WrongPassphrase)?;
+    }
+
+    let pem = private_key
+        .to_openssh(LineEnding::LF)
+        .map_err(|err| KeyImportError::InvalidKey(err.to_string()))?;
+    unlocked.store_ssh_key(name, &pem, constraints)?;
+    Ok(ImportedKey {
+        algorithm: private_key.algorithm().to_string(),
+        fingerprint: private_key.fingerprint(HashAlg::Sha256).to_string(),
+        comment: private_key.comment().to_string(),
+        was_encrypted,
+    })
+}
+
+/// Overwrite `path` with random data, flush it to disk and remove it.
+///
+/// Copy-on-write and journaling filesystems may keep older blocks, so this
+/// is best effort; it still keeps the key out of the live file tree.
+pub fn shred_file(path: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(path)?;
+    if !metadata.is_file() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "only regular files can be shredded"));
+    }
+
+    let len = usize::try_from(metadata.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "file is too large to shred"))?;
+    let mut noise = vec![0u8; len];
+    let mut file = fs::OpenOptions::new().write(true).open(path)?;
+    for _ in 0..SHRED_PASSES {
+        random_fill(&mut noise).map_err(|err| io::Error::other(err.to_string()))?;
+        file.write_all(&noise)?;
+        file.sync_all()?;
+        io::Seek::rewind(&mut file)?;
+    }
+    file.set_len(0)?;
+    file.sync_all()?;
+    drop(file);
+    fs::remove_file(path)
+}
+
+#[cfg(test)]
+#[path = "../test/auth/keyimport.rs"]
+mod tests;
@@ -0,0 +1,228 @@
+//! Keyfile and recovery-key unlock methods.
+//!
+//! The master password slot in `metadata.json` can additionally require a
+//! keyfile, whose digest is mixed into the Argon2 input. A recovery key shown
+//! once by `cossh vault init` wraps a second copy of the data key in
+//! `key_slots`, so a forgotten password or lost keyfile can be replaced
+//! without re-encrypting any entry.
+
+use crate::auth::audit::{self, AuditEvent, AuditEventKind, AuditProcess};
+use crate::auth::secret::{ExposeSecret, SensitiveString};
+use crate::auth::vault::{self, DATA_KEY_LEN, KdfParams, KeySlotKind, PRIVATE_FILE_MODE, UnlockedVault, VaultError, VaultMetadata, VaultPaths, WrappedKeySlot};
+use crate::log_debug;
+use chrono::Utc;
+use getrandom::fill as random_fill;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+const RECOVERY_KEY_BYTES: usize = 16;
+const RECOVERY_KEY_GROUP_LEN: usize = 4;
+const KEYFILE_BYTES: usize = 64;
+/// The recovery key is 128 random bits, so a memory-hard KDF adds nothing
+/// against guessing; a light cost keeps recovery slots cheap to create.
+pub(crate) const RECOVERY_KEY_KDF: KdfParams = KdfParams {
+    memory_kib: 8 * 1024,
+    time_cost: 1,
+    parallelism: 1,
+};
+
+/// Generate a random recovery key formatted as dash-separated hex groups.
+pub fn generate_recovery_key() -> Result<SensitiveString, VaultError> {
+    let mut bytes = Zeroizing::new([0u8; RECOVERY_KEY_BYTES]);
+    random_fill(&mut bytes[..]).map_err(|err| VaultError::EncryptFailed(format!("secure random generation failed: {err}")))?;
+    let hex = Zeroizing::new(bytes.iter().map(|byte| format!("{byte:02x}")).collect::<String>());
+    let groups: Vec<&str> = hex
+        .as_bytes()
+        .chunks(RECOVERY_KEY_GROUP_LEN)
+        .map(|group| std::str::from_utf8(group).unwrap_or_default())
+        .collect();
+    Ok(SensitiveString::from_owned_string(groups.join("-")))
+}
+
+/// Decode a recovery key, ignoring case, dashes and whitespace.
+fn recovery_key_bytes(recovery_key: &str) -> Result<Zeroizing<Vec<u8>>, VaultError> {
+    let digits = Zeroizing::new(
+        recovery_key
+            .chars()
+            .filter(|ch| *ch != '-' && !ch.is_whitespace())
+            .map(|ch| ch.to_ascii_lowercase())
+            .collect::<String>(),
+    );
+    if digits.len() != RECOVERY_KEY_BYTES * 2 {
+        return Err(VaultError::InvalidRecoveryKey);
+    }
+
+    let mut bytes = Zeroizing::new(Vec::with_capacity(RECOVERY_KEY_BYTES));
+    for pair in digits.as_bytes().chunks(2) {
+        let pair = std::str::from_utf8(pair).map_err(|_| VaultError::InvalidRecoveryKey)?;
+        bytes.push(u8::from_str_radix(pair, 16).map_err(|_| VaultError::InvalidRecoveryKey)?);
+    }
+    Ok(bytes)
+}
+
+/// Wrap `data_key` under `recovery_key`.
+pub(crate) fn recovery_key_slot(recovery_key: &SensitiveString, data_key: &[u8; DATA_KEY_LEN]) -> Result<WrappedKeySlot, VaultError> {
+    let passphrase = recovery_key_bytes(recovery_key.expose_secret())?;
+    vault::build_key_slot(KeySlotKind::Recovery, &passphrase, data_key, RECOVERY_KEY_KDF)
+}
+
+/// Returns `true` when the default vault has a recovery key.
+pub fn has_recovery_key() -> Result<bool, VaultError> {
+    Ok(vault::read_metadata(&VaultPaths::resolve_default()?)?
+        .key_slots
+        .iter()
+        .any(|slot| slot.kind == KeySlotKind::Recovery))
+}
+
+/// Keyfile configured for the default vault.
+pub fn configured_keyfile() -> Result<Option<String>, VaultError> {
+    Ok(vault::read_metadata(&VaultPaths::resolve_default()?)?.keyfile)
+}
+
+/// Unlock the default vault with its recovery key.
+pub fn unlock_with_recovery_key(recovery_key: &str) -> Result<UnlockedVault, VaultError> {
+    unlock_with_recovery_key_and_paths(&VaultPaths::resolve_default()?, recovery_key)
+}
+
+pub(crate) fn unlock_with_recovery_key_and_paths(paths: &VaultPaths, recovery_key: &str) -> Result<UnlockedVault, VaultError> {
+    let passphrase = recovery_key_bytes(recovery_key)?;
+    let metadata = vault::read_metadata(paths)?;
+    let mut slots = metadata.key_slots.iter().filter(|slot| slot.kind == KeySlotKind::Recovery).peekable();
+    if slots.peek().is_none() {
+        return Err(VaultError::InvalidRecoveryKey);
+    }
+
+    let mut result = Err(VaultError::InvalidRecoveryKey);
+    for slot in slots {
+        result = vault::open_key_slot(slot, &passphrase);
+        if !matches!(result, Err(VaultError::InvalidRecoveryKey)) {
+            break;
+        }
+    }
+    result.map(|data_key| UnlockedVault::from_data_key(paths.clone(), data_key))
+}
+
+/// Reset the default vault's master password with its recovery key.
+pub fn recover_vault(recovery_key: &str, new_password: &str) -> Result<(), VaultError> {
+    recover_vault_with_paths(&VaultPaths::resolve_default()?, recovery_key, new_password)
+}
+
+/// Replace the master password slot using the recovery key.
+///
+/// The new slot is password-only, since a lost keyfile is one of the reasons
+/// to recover. The recovery key itself stays valid.
+pub(crate) fn recover_vault_with_paths(paths: &VaultPaths, recovery_key: &str, new_password: &str) -> Result<(), VaultError> {
+    if new_password.is_empty() {
+        return Err(VaultError::InvalidMasterPassword);
+    }
+    let unlocked = match unlock_with_recovery_key_and_paths(paths, recovery_key) {
+        Ok(unlocked) => unlocked,
+        Err(err) => {
+            if matches!(err, VaultError::InvalidRecoveryKey) {
+                record_keyslot_event(paths, AuditEvent::new(AuditEventKind::UnlockFailed).with_detail("recovery key"));
+            }
+            return Err(err);
+        }
+    };
+
+    let existing = vault::read_metadata(paths)?;
+    let data_key = Zeroizing::new(unlocked.data_key_copy());
+    let updated = rewrapped_master_slot(new_password, None, &data_key, existing)?;
+    vault::write_metadata(paths, &updated)?;
+    record_keyslot_event(
+        paths,
+        AuditEvent::new(AuditEventKind::MasterPasswordRotated).with_detail("recovered with recovery key"),
+    );
+    Ok(())
+}
+
+/// Replace the default vault's recovery key.
+pub fn reset_recovery_key(master_password: &str) -> Result<SensitiveString, VaultError> {
+    reset_recovery_key_with_paths(&VaultPaths::resolve_default()?, master_password)
+}
+
+/// Generate a new recovery key, invalidating the previous one.
+pub(crate) fn reset_recovery_key_with_paths(paths: &VaultPaths, master_password: &str) -> Result<SensitiveString, VaultError> {
+    let unlocked = unlock_for_change(paths, master_password, "recovery key reset")?;
+    let mut metadata = vault::read_metadata(paths)?;
+    let recovery_key = generate_recovery_key()?;
+    let slot = recovery_key_slot(&recovery_key, &Zeroizing::new(unlocked.data_key_copy()))?;
+    metadata.key_slots.retain(|slot| slot.kind != KeySlotKind::Recovery);
+    metadata.key_slots.push(slot);
+    metadata.updated_at = Utc::now().to_rfc3339();
+    vault::write_metadata(paths, &metadata)?;
+    record_keyslot_event(paths, AuditEvent::new(AuditEventKind::UnlockMethodChanged).with_detail("recovery key reset"));
+    Ok(recovery_key)
+}
+
+/// Require, replace or remove the default vault's keyfile.
+pub fn set_keyfile(master_password: &str, keyfile: Option<&Path>) -> Result<(), VaultError> {
+    set_keyfile_with_paths(&VaultPaths::resolve_default()?, master_password, keyfile)
+}
+
+/// Rewrap the master password slot with `keyfile` combined into its input.
+///
+/// `None` removes the keyfile requirement.
+pub(crate) fn set_keyfile_with_paths(paths: &VaultPaths, master_password: &str, keyfile: Option<&Path>) -> Result<(), VaultError> {
+    let unlocked = unlock_for_change(paths, master_password, "keyfile change")?;
+    let keyfile = keyfile
+        .map(|path| {
+            path.canonicalize()
+                .map_err(|err| VaultError::KeyfileUnavailable(format!("{}: {err}", path.display())))
+                .map(|path| path.display().to_string())
+        })
+        .transpose()?;
+
+    let existing = vault::read_metadata(paths)?;
+    let data_key = Zeroizing::new(unlocked.data_key_copy());
+    let updated = rewrapped_master_slot(master_password, keyfile.as_deref(), &data_key, existing)?;
+    vault::write_metadata(paths, &updated)?;
+    let detail = match &keyfile {
+        Some(path) => format!("keyfile set to {path}"),
+        None => "keyfile removed".to_string(),
+    };
+    record_keyslot_event(paths, AuditEvent::new(AuditEventKind::UnlockMethodChanged).with_detail(detail));
+    Ok(())
+}
+
+/// Write a new random keyfile at `path`, refusing to overwrite an existing file.
+pub fn generate_keyfile(path: &Path) -> Result<(), VaultError> {
+    let mut contents = Zeroizing::new([0u8; KEYFILE_BYTES]);
+    random_fill(&mut contents[..]).map_err(|err| VaultError::EncryptFailed(format!("secure random generation failed: {err}")))?;
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    crate::platform::set_private_file_permissions(path, PRIVATE_FILE_MODE)?;
+    file.write_all(&contents[..])?;
+    file.sync_all()?;
+    log_debug!("Generated vault keyfile at '{}'", path.display());
+    Ok(())
+}
+
+fn unlock_for_change(paths: &VaultPaths, master_password: &str, detail: &str) -> Result<UnlockedVault, VaultError> {
+    vault::unlock_with_password_and_paths(paths, master_password).inspect_err(|err| {
+        if matches!(err, VaultError::InvalidMasterPassword) {
+            record_keyslot_event(paths, AuditEvent::new(AuditEventKind::UnlockFailed).with_detail(detail));
+        }
+    })
+}
+
+fn rewrapped_master_slot(
+    master_password: &str,
+    keyfile: Option<&str>,
+    data_key: &[u8; DATA_KEY_LEN],
+    existing: VaultMetadata,
+) -> Result<VaultMetadata, VaultError> {
+    let mut updated = vault::build_metadata_from_data_key(master_password, keyfile, data_key, KdfParams::from_metadata(&existing))?;
+    updated.created_at = existing.created_at;
+    updated.key_slots = existing.key_slots;
+    Ok(updated)
+}
+
+fn record_keyslot_event(paths: &VaultPaths, event: AuditEvent) {
+    audit::record_event(paths, event.with_process(Some(AuditProcess::current())));
+}
+
+#[cfg(test)]
+#[path = "../test/auth/keyslot.rs"]
+mod tests;
@@ -2,14 +2,31 @@
 //!
 //! This module provides:
 //! - encrypted local password vault primitives
+//! - hash-chained audit log of vault access
+//! - integrity checks and safe repairs for vault files
+//! - KDF re-keying and data-key rotation
+//! - keyfile and recovery-key unlock methods
+//! - password generation for new and rotated entries
+//! - TOTP codes from entries holding one-time password seeds
+//! - import of on-disk SSH private keys into vault key entries
 //! - unlock agent IPC/client/server plumbing
-//! - CLI flows for vault lifecycle commands
+//! - OpenSSH agent socket serving vault-stored SSH keys
+//! - CLI flows for vault lifecycle commands and scripted secret access
 //! - internal askpass transport for password auto-login
+//! - external secret-provider references (`pass:`, `op:`, `bw:`, ...)
 
 pub mod agent;
+pub mod audit;
 mod cli;
+pub mod doctor;
+pub mod generator;
 pub mod ipc;
+pub mod keyimport;
+pub mod keyslot;
+pub mod otp;
 mod prompt;
+pub mod provider;
+pub mod rekey;
 pub mod secret;
 pub mod transport;
 pub mod vault;
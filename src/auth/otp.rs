@@ -0,0 +1,167 @@
+//! Time-based one-time passwords for `cossh vault get --field otp`.
+//!
+//! An entry holding a TOTP seed stores either an `otpauth://totp/...` URI, as
+//! exported by most authenticator apps, or the bare base32 secret. Bare
+//! secrets use the RFC 6238 defaults: HMAC-SHA1, six digits and a 30 second
+//! period.
+
+use crate::auth::secret::SensitiveString;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+
+const OTPAUTH_TOTP_PREFIX: &str = "otpauth://totp/";
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const DEFAULT_DIGITS: u32 = 6;
+const DEFAULT_PERIOD_SECONDS: u64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// HMAC algorithm named by the `algorithm` URI parameter.
+pub enum TotpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Decoded TOTP seed and code parameters.
+pub struct TotpParams {
+    secret: Zeroizing<Vec<u8>>,
+    pub digits: u32,
+    pub period_seconds: u64,
+    pub algorithm: TotpAlgorithm,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OtpError {
+    /// The entry value is neither an `otpauth://totp` URI nor a base32 secret.
+    InvalidSeed(String),
+    Clock,
+}
+
+impl fmt::Display for OtpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidSeed(message) => write!(f, "entry does not hold a TOTP seed: {message}"),
+            Self::Clock => write!(f, "system clock is set before the Unix epoch"),
+        }
+    }
+}
+
+impl std::error::Error for OtpError {}
+
+/// Parse an entry value as an `otpauth://totp` URI or a bare base32 secret.
+pub fn parse_totp_seed(value: &str) -> Result<TotpParams, OtpError> {
+    let value = value.trim();
+    let Some(rest) = value.strip_prefix(OTPAUTH_TOTP_PREFIX) else {
+        if value.contains("://") {
+            return Err(OtpError::InvalidSeed("only otpauth://totp URIs are supported".to_string()));
+        }
+        return Ok(TotpParams {
+            secret: decode_base32(value)?,
+            digits: DEFAULT_DIGITS,
+            period_seconds: DEFAULT_PERIOD_SECONDS,
+            algorithm: TotpAlgorithm::Sha1,
+        });
+    };
+
+    let mut secret = None;
+    let mut digits = DEFAULT_DIGITS;
+    let mut period_seconds = DEFAULT_PERIOD_SECONDS;
+    let mut algorithm = TotpAlgorithm::Sha1;
+    let query = rest.split_once('?').map(|(_, query)| query).unwrap_or_default();
+    for (key, param) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        match key.to_ascii_lowercase().as_str() {
+            "secret" => secret = Some(decode_base32(param)?),
+            "digits" => {
+                digits = param
+                    .parse()
+                    .ok()
+                    .filter(|digits| (6..=9).contains(digits))
+                    .ok_or_else(|| OtpError::InvalidSeed(format!("unsupported digits '{param}'")))?;
+            }
+            "period" => {
+                period_seconds = param
+                    .parse()
+                    .ok()
+                    .filter(|period| *period > 0)
+                    .ok_or_else(|| OtpError::InvalidSeed(format!("invalid period '{param}'")))?;
+            }
+            "algorithm" => {
+                algorithm = match param.to_ascii_uppercase().as_str() {
+                    "SHA1" => TotpAlgorithm::Sha1,
+                    "SHA256" => TotpAlgorithm::Sha256,
+                    "SHA512" => TotpAlgorithm::Sha512,
+                    _ => return Err(OtpError::InvalidSeed(format!("unsupported algorithm '{param}'"))),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    Ok(TotpParams {
+        secret: secret.ok_or_else(|| OtpError::InvalidSeed("otpauth URI has no secret parameter".to_string()))?,
+        digits,
+        period_seconds,
+        algorithm,
+    })
+}
+
+/// Compute the code for the time step containing `unix_seconds`.
+pub fn totp_code_at(params: &TotpParams, unix_seconds: u64) -> SensitiveString {
+    let counter = (unix_seconds / params.period_seconds).to_be_bytes();
+    let digest = match params.algorithm {
+        TotpAlgorithm::Sha1 => hmac_digest::<Hmac<Sha1>>(&params.secret, &counter),
+        TotpAlgorithm::Sha256 => hmac_digest::<Hmac<Sha256>>(&params.secret, &counter),
+        TotpAlgorithm::Sha512 => hmac_digest::<Hmac<Sha512>>(&params.secret, &counter),
+    };
+
+    // RFC 4226 dynamic truncation.
+    let offset = usize::from(digest[digest.len() - 1] & 0x0f);
+    let binary = u32::from_be_bytes([digest[offset] & 0x7f, digest[offset + 1], digest[offset + 2], digest[offset + 3]]);
+    let code = u64::from(binary) % 10_u64.pow(params.digits);
+    SensitiveString::from_owned_string(format!("{code:0width$}", width = params.digits as usize))
+}
+
+/// Compute the current code for an entry value.
+pub fn current_totp_code(seed: &str) -> Result<SensitiveString, OtpError> {
+    let params = parse_totp_seed(seed)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|_| OtpError::Clock)?;
+    Ok(totp_code_at(&params, now.as_secs()))
+}
+
+fn hmac_digest<M: Mac + hmac::digest::KeyInit>(key: &[u8], message: &[u8]) -> Zeroizing<Vec<u8>> {
+    let mut mac = <M as hmac::digest::KeyInit>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    Zeroizing::new(mac.finalize().into_bytes().to_vec())
+}
+
+/// Decode RFC 4648 base32, ignoring case, padding, spaces and dashes.
+fn decode_base32(value: &str) -> Result<Zeroizing<Vec<u8>>, OtpError> {
+    let mut decoded = Zeroizing::new(Vec::with_capacity(value.len() * 5 / 8));
+    let mut buffer = 0_u32;
+    let mut bits = 0_u32;
+    for ch in value.chars().filter(|ch| !matches!(ch, ' ' | '-' | '=')) {
+        let Some(index) = BASE32_ALPHABET.iter().position(|symbol| char::from(*symbol) == ch.to_ascii_uppercase()) else {
+            return Err(OtpError::InvalidSeed("secret is not valid base32".to_string()));
+        };
+        buffer = (buffer << 5) | index as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    if decoded.is_empty() {
+        return Err(OtpError::InvalidSeed("secret is empty".to_string()));
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+#[path = "../test/auth/otp.rs"]
+mod tests;
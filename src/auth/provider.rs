@@ -0,0 +1,246 @@
+//! External secret-provider references for `vault_pass`.
+//!
+//! A `vault_pass` value is either a local vault entry name or a
+//! `<provider>:<locator>` reference that the unlock agent resolves by running
+//! an external command such as `pass`, `op`, `bw`, `secret-tool`, or a local
+//! script.
+
+use crate::args::validate_vault_entry_name;
+use crate::auth::secret::SensitiveString;
+use crate::log_debug;
+use std::fmt;
+use std::io;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use zeroize::Zeroize;
+
+const PROVIDER_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+const OP_REFERENCE_PREFIX: &str = "op://";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// External command families that can resolve a secret reference.
+pub enum SecretProvider {
+    /// `pass show <path>` (first line of the entry).
+    Pass,
+    /// 1Password CLI `op read <reference>`.
+    OnePassword,
+    /// Bitwarden CLI `bw get password <item>`.
+    Bitwarden,
+    /// libsecret `secret-tool lookup <attribute> <value>...`.
+    SecretTool,
+    /// Arbitrary local command executed through `sh -c`.
+    Command,
+}
+
+impl SecretProvider {
+    const ALL: [Self; 5] = [Self::Pass, Self::OnePassword, Self::Bitwarden, Self::SecretTool, Self::Command];
+
+    /// Reference prefix used in inventory `vault_pass` values.
+    pub fn scheme(self) -> &'static str {
+        match self {
+            Self::Pass => "pass",
+            Self::OnePassword => "op",
+            Self::Bitwarden => "bw",
+            Self::SecretTool => "secret-tool",
+            Self::Command => "cmd",
+        }
+    }
+
+    fn from_scheme(scheme: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|provider| provider.scheme() == scheme)
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Self::Pass => "pass",
+            Self::OnePassword => "op",
+            Self::Bitwarden => "bw",
+            Self::SecretTool => "secret-tool",
+            Self::Command => "sh",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// One `<provider>:<locator>` reference.
+pub struct ExternalSecretRef {
+    /// Provider used to resolve the secret.
+    pub provider: SecretProvider,
+    /// Provider-specific path, item name, or command line.
+    pub locator: String,
+}
+
+impl ExternalSecretRef {
+    fn command_args(&self) -> Result<Vec<String>, SecretProviderError> {
+        let locator = self.locator.clone();
+        match self.provider {
+            SecretProvider::Pass => Ok(vec!["show".to_string(), locator]),
+            SecretProvider::OnePassword => {
+                let reference = if locator.starts_with(OP_REFERENCE_PREFIX) {
+                    locator
+                } else {
+                    format!("{OP_REFERENCE_PREFIX}{locator}")
+                };
+                Ok(vec!["read".to_string(), "--no-newline".to_string(), reference])
+            }
+            SecretProvider::Bitwarden => Ok(vec!["get".to_string(), "password".to_string(), locator]),
+            SecretProvider::SecretTool => {
+                let mut args = vec!["lookup".to_string()];
+                for pair in locator.split_whitespace() {
+                    let Some((attribute, value)) = pair.split_once('=').filter(|(attribute, value)| !attribute.is_empty() && !value.is_empty()) else {
+                        return Err(SecretProviderError::InvalidReference(format!(
+                            "secret-tool lookups must use 'attribute=value' pairs, found '{pair}'"
+                        )));
+                    };
+                    args.push(attribute.to_string());
+                    args.push(value.to_string());
+                }
+                Ok(args)
+            }
+            SecretProvider::Command => Ok(vec!["-c".to_string(), locator]),
+        }
+    }
+}
+
+impl fmt::Display for ExternalSecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.provider.scheme(), self.locator)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Parsed `vault_pass` value.
+pub enum SecretReference {
+    /// Entry stored in the local encrypted vault.
+    VaultEntry(String),
+    /// Secret resolved through an external provider command.
+    External(ExternalSecretRef),
+}
+
+impl SecretReference {
+    /// Parse a `vault_pass` value, returning `None` when it is neither a valid
+    /// vault entry name nor a supported provider reference.
+    pub fn parse(value: &str) -> Option<Self> {
+        if validate_vault_entry_name(value) {
+            return Some(Self::VaultEntry(value.to_string()));
+        }
+
+        let (scheme, locator) = value.split_once(':')?;
+        let provider = SecretProvider::from_scheme(scheme)?;
+        let locator = locator.trim();
+        if locator.is_empty() || locator.contains(['\n', '\r', '\0']) {
+            return None;
+        }
+
+        Some(Self::External(ExternalSecretRef {
+            provider,
+            locator: locator.to_string(),
+        }))
+    }
+
+    /// Returns `true` when the reference points at an external provider.
+    pub fn is_external(&self) -> bool {
+        matches!(self, Self::External(_))
+    }
+}
+
+/// Returns `true` when `value` names an external provider reference.
+pub fn is_external_secret_reference(value: &str) -> bool {
+    SecretReference::parse(value).is_some_and(|reference| reference.is_external())
+}
+
+#[derive(Debug)]
+/// Errors returned while resolving an external secret reference.
+pub enum SecretProviderError {
+    InvalidReference(String),
+    CommandUnavailable { provider: &'static str, message: String },
+    CommandFailed { provider: &'static str, status: String, stderr: String },
+    TimedOut { provider: &'static str },
+    EmptySecret { provider: &'static str },
+    InvalidOutput { provider: &'static str },
+    Io(io::Error),
+}
+
+impl fmt::Display for SecretProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidReference(message) => write!(f, "invalid secret reference: {message}"),
+            Self::CommandUnavailable { provider, message } => write!(f, "secret provider '{provider}' is unavailable: {message}"),
+            Self::CommandFailed { provider, status, stderr } if stderr.is_empty() => write!(f, "secret provider '{provider}' failed ({status})"),
+            Self::CommandFailed { provider, status, stderr } => write!(f, "secret provider '{provider}' failed ({status}): {stderr}"),
+            Self::TimedOut { provider } => write!(
+                f,
+                "secret provider '{provider}' did not respond within {} seconds",
+                PROVIDER_COMMAND_TIMEOUT.as_secs()
+            ),
+            Self::EmptySecret { provider } => write!(f, "secret provider '{provider}' returned an empty secret"),
+            Self::InvalidOutput { provider } => write!(f, "secret provider '{provider}' returned output that was not valid UTF-8"),
+            Self::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for SecretProviderError {}
+
+impl From<io::Error> for SecretProviderError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Run the provider command for `reference` and return the first output line.
+pub fn resolve_external_secret(reference: &ExternalSecretRef) -> Result<SensitiveString, SecretProviderError> {
+    let provider = reference.provider.scheme();
+    let args = reference.command_args()?;
+    let program = crate::platform::resolve_known_command_path(reference.provider.program()).map_err(|err| SecretProviderError::CommandUnavailable {
+        provider,
+        message: err.to_string(),
+    })?;
+
+    log_debug!("Resolving external secret through provider '{}'", provider);
+    let mut child = Command::new(program)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .env_remove(crate::auth::transport::INTERNAL_ASKPASS_MODE_ENV)
+        .env_remove(crate::auth::transport::INTERNAL_ASKPASS_TOKEN_ENV)
+        .spawn()
+        .map_err(|err| SecretProviderError::CommandUnavailable {
+            provider,
+            message: err.to_string(),
+        })?;
+
+    let Some(mut output) = crate::platform::wait_with_output_timeout(&mut child, PROVIDER_COMMAND_TIMEOUT)? else {
+        return Err(SecretProviderError::TimedOut { provider });
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(SecretProviderError::CommandFailed {
+            provider,
+            status: output.status.to_string(),
+            stderr: stderr.lines().next().unwrap_or_default().trim().to_string(),
+        });
+    }
+
+    first_line_secret(std::mem::take(&mut *output.stdout), provider)
+}
+
+fn first_line_secret(mut output: Vec<u8>, provider: &'static str) -> Result<SensitiveString, SecretProviderError> {
+    let line_len = output.iter().position(|byte| matches!(byte, b'\n' | b'\r')).unwrap_or(output.len());
+    output[line_len..].zeroize();
+    output.truncate(line_len);
+    if output.is_empty() {
+        return Err(SecretProviderError::EmptySecret { provider });
+    }
+
+    SensitiveString::from_utf8_bytes(output).map_err(|err| {
+        let mut invalid_bytes = err.into_bytes();
+        invalid_bytes.zeroize();
+        SecretProviderError::InvalidOutput { provider }
+    })
+}
+
+#[cfg(test)]
+#[path = "../test/auth/provider.rs"]
+mod tests;
@@ -0,0 +1,227 @@
+//! Vault re-keying for `cossh vault rekey`.
+//!
+//! Re-keying rewraps the data encryption key under new Argon2id parameters
+//! and can optionally replace the data key itself. When the data key rotates,
+//! every entry is re-encrypted into a staging directory first and swapped in
+//! with directory renames, so a failure at any step leaves the previous
+//! entries and metadata in place. A running agent is locked before staging
+//! starts so it cannot keep writing entries under the retired data key. It is
+//! locked again before the swap, and the swap is abandoned when any entry or
+//! the metadata changed since staging, since those writes would otherwise be
+//! lost.
+
+use crate::auth::audit::{self, AuditEvent, AuditEventKind, AuditProcess};
+use crate::auth::ipc::{self, AgentRequestPayload, AgentResponse};
+use crate::auth::keyslot;
+use crate::auth::secret::SensitiveString;
+use crate::auth::vault::{self, DATA_KEY_LEN, KdfParams, KeySlotKind, PRIVATE_DIRECTORY_MODE, UnlockedVault, VaultError, VaultMetadata, VaultPaths};
+use crate::log_debug;
+use chrono::Utc;
+use getrandom::fill as random_fill;
+use std::fs;
+use std::io;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Name prefix of the directory entries are re-encrypted into.
+pub(crate) const REKEY_STAGING_PREFIX: &str = ".rekey-staging-";
+/// Name prefix of the directory the previous entries move to during the swap.
+pub(crate) const REKEY_BACKUP_PREFIX: &str = ".rekey-backup-";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Requested re-key settings.
+pub struct RekeyOptions {
+    /// KDF parameters for the new wrapped key.
+    pub kdf: KdfParams,
+    /// Generate a new data encryption key and re-encrypt every entry.
+    pub rotate_data_key: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Summary of a completed re-key.
+pub struct RekeyOutcome {
+    pub previous_kdf: KdfParams,
+    pub kdf: KdfParams,
+    pub data_key_rotated: bool,
+    pub entries_reencrypted: usize,
+    /// Replacement recovery key, issued when the data key rotated.
+    pub recovery_key: Option<SensitiveString>,
+}
+
+/// Re-key the default vault.
+pub fn rekey_vault(master_password: &str, options: RekeyOptions) -> Result<RekeyOutcome, VaultError> {
+    rekey_vault_with_paths(&VaultPaths::resolve_default()?, master_password, options)
+}
+
+pub(crate) fn rekey_vault_with_paths(paths: &VaultPaths, master_password: &str, options: RekeyOptions) -> Result<RekeyOutcome, VaultError> {
+    options.kdf.validate()?;
+    let current = match vault::unlock_with_password_and_paths(paths, master_password) {
+        Ok(unlocked) => unlocked,
+        Err(err) => {
+            if matches!(err, VaultError::InvalidMasterPassword) {
+                record_rekey_event(paths, AuditEvent::new(AuditEventKind::UnlockFailed).with_detail("vault rekey"));
+            }
+            return Err(err);
+        }
+    };
+    let existing = vault::read_metadata(paths)?;
+    let previous_kdf = KdfParams::from_metadata(&existing);
+
+    let mut entries_reencrypted = 0;
+    let mut recovery_key = None;
+    let metadata = if options.rotate_data_key {
+        lock_running_agent(paths)?;
+        let mut data_key = Zeroizing::new([0u8; DATA_KEY_LEN]);
+        random_fill(&mut data_key[..]).map_err(|err| VaultError::EncryptFailed(format!("secure random generation failed: {err}")))?;
+        let replacement = UnlockedVault::from_data_key(paths.clone(), *data_key);
+        let mut metadata = rewrapped_metadata(master_password, &data_key, options.kdf, &existing)?;
+        // Existing key slots wrap the retired data key, so the recovery key is reissued.
+        metadata.key_slots.clear();
+        if existing.key_slots.iter().any(|slot| slot.kind == KeySlotKind::Recovery) {
+            let key = keyslot::generate_recovery_key()?;
+            metadata.key_slots.push(keyslot::recovery_key_slot(&key, &data_key)?);
+            recovery_key = Some(key);
+        }
+
+        let suffix = Utc::now().timestamp_nanos_opt().unwrap_or_default();
+        let staging_dir = paths.vault_dir().join(format!("{REKEY_STAGING_PREFIX}{suffix}"));
+        let backup_dir = paths.vault_dir().join(format!("{REKEY_BACKUP_PREFIX}{suffix}"));
+        let staged = match stage_reencrypted_entries(paths, &current, &replacement, &staging_dir) {
+            Ok(staged) => staged,
+            Err(err) => {
+                let _ = fs::remove_dir_all(&staging_dir);
+                return Err(err);
+            }
+        };
+        entries_reencrypted = staged.len();
+        if let Err(err) =
+            ensure_unchanged_since_staging(paths, &staged, &existing).and_then(|()| swap_in_staged_entries(paths, &staging_dir, &backup_dir, &metadata))
+        {
+            let _ = fs::remove_dir_all(&staging_dir);
+            return Err(err);
+        }
+        metadata
+    } else {
+        let metadata = rewrapped_metadata(master_password, &current.data_key_copy(), options.kdf, &existing)?;
+        vault::write_metadata(paths, &metadata)?;
+        metadata
+    };
+
+    let outcome = RekeyOutcome {
+        previous_kdf,
+        kdf: KdfParams::from_metadata(&metadata),
+        data_key_rotated: options.rotate_data_key,
+        entries_reencrypted,
+        recovery_key,
+    };
+    let detail = if outcome.data_key_rotated {
+        format!("{}; data key rotated, {} entry(s) re-encrypted", outcome.kdf, outcome.entries_reencrypted)
+    } else {
+        outcome.kdf.to_string()
+    };
+    record_rekey_event(paths, AuditEvent::new(AuditEventKind::VaultRekeyed).with_detail(detail));
+    Ok(outcome)
+}
+
+/// Lock and stop a running agent; it holds the data key that is about to be
+/// retired. No agent listening is fine.
+fn lock_running_agent(paths: &VaultPaths) -> Result<(), VaultError> {
+    match ipc::send_request(paths, &AgentRequestPayload::Lock) {
+        Ok(AgentResponse::Error { message, .. }) => Err(VaultError::Io(io::Error::other(format!(
+            "password vault agent refused to lock before rekey: {message}"
+        )))),
+        Ok(_) => {
+            log_debug!("Locked password vault agent before rotating the data key");
+            Ok(())
+        }
+        Err(err) => {
+            log_debug!("No password vault agent to lock before rekey: {}", err);
+            Ok(())
+        }
+    }
+}
+
+fn rewrapped_metadata(master_password: &str, data_key: &[u8; DATA_KEY_LEN], kdf: KdfParams, existing: &VaultMetadata) -> Result<VaultMetadata, VaultError> {
+    let mut metadata = vault::build_metadata_from_data_key(master_password, existing.keyfile.as_deref(), data_key, kdf)?;
+    metadata.created_at = existing.created_at.clone();
+    metadata.key_slots = existing.key_slots.clone();
+    Ok(metadata)
+}
+
+/// Re-encrypt every entry into `staging_dir`, returning the name and nonce of
+/// each entry as it was staged.
+fn stage_reencrypted_entries(
+    paths: &VaultPaths,
+    current: &UnlockedVault,
+    replacement: &UnlockedVault,
+    staging_dir: &Path,
+) -> Result<Vec<(String, String)>, VaultError> {
+    crate::platform::create_private_directory(staging_dir, PRIVATE_DIRECTORY_MODE)?;
+    let mut staged = Vec::new();
+    for name in vault::list_entries_with_paths(paths)? {
+        let entry = vault::read_entry_file(&paths.entry_path(&name)?)?;
+        if entry.name != name {
+            return Err(VaultError::InvalidVaultFormat(format!("entry '{name}' did not match its file name")));
+        }
+        let reencrypted = current.reencrypt_entry(&entry, replacement)?;
+        vault::write_entry_file(&staging_dir.join(format!("{name}.json")), &reencrypted)?;
+        staged.push((name, entry.nonce));
+    }
+    log_debug!("Staged {} re-encrypted vault entry(s)", staged.len());
+    Ok(staged)
+}
+
+/// Lock the agent again and fail when an entry or the metadata changed while
+/// entries were staged. Every write re-encrypts an entry under a new nonce.
+fn ensure_unchanged_since_staging(paths: &VaultPaths, staged: &[(String, String)], metadata: &VaultMetadata) -> Result<(), VaultError> {
+    lock_running_agent(paths)?;
+    let mut current = Vec::new();
+    for name in vault::list_entries_with_paths(paths)? {
+        let entry = vault::read_entry_file(&paths.entry_path(&name)?)?;
+        current.push((name, entry.nonce));
+    }
+    if current != staged || vault::read_metadata(paths)? != *metadata {
+        return Err(VaultError::Io(io::Error::other(
+            "password vault changed while entries were re-encrypted; nothing was replaced, run the rekey again",
+        )));
+    }
+    Ok(())
+}
+
+fn swap_in_staged_entries(paths: &VaultPaths, staging_dir: &Path, backup_dir: &Path, metadata: &VaultMetadata) -> Result<(), VaultError> {
+    let entries_dir = paths.entries_dir();
+    crate::platform::create_private_directory(&entries_dir, PRIVATE_DIRECTORY_MODE)?;
+    crate::platform::create_private_directory(backup_dir, PRIVATE_DIRECTORY_MODE)?;
+    let backup_entries = backup_dir.join("entries");
+
+    if let Err(err) = fs::rename(&entries_dir, &backup_entries) {
+        let _ = fs::remove_dir_all(backup_dir);
+        return Err(err.into());
+    }
+    if let Err(err) = fs::rename(staging_dir, &entries_dir) {
+        let _ = fs::rename(&backup_entries, &entries_dir);
+        let _ = fs::remove_dir_all(backup_dir);
+        return Err(err.into());
+    }
+    // The metadata write is atomic, so on failure the old wrapped key is
+    // still in place and only the entries need to move back.
+    if let Err(err) = vault::write_metadata(paths, metadata) {
+        let _ = fs::rename(&entries_dir, staging_dir);
+        let _ = fs::rename(&backup_entries, &entries_dir);
+        let _ = fs::remove_dir_all(backup_dir);
+        return Err(err);
+    }
+
+    if let Err(err) = fs::remove_dir_all(backup_dir) {
+        log_debug!("Failed to remove vault rekey backup '{}': {}", backup_dir.display(), err);
+    }
+    Ok(())
+}
+
+fn record_rekey_event(paths: &VaultPaths, event: AuditEvent) {
+    audit::record_event(paths, event.with_process(Some(AuditProcess::current())));
+}
+
+#[cfg(test)]
+#[path = "../test/auth/rekey.rs"]
+mod tests;
@@ -1,19 +1,40 @@
 //! Internal askpass environment and prompt-classification helpers.
 
+use crate::auth::ipc::VAULT_FORWARD_SOCKET_PREFIX;
 use crate::{log_debug, platform};
 use std::io;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
 
 pub const INTERNAL_ASKPASS_MODE_ENV: &str = "COSSH_INTERNAL_ASKPASS";
 pub const INTERNAL_ASKPASS_TOKEN_ENV: &str = "COSSH_INTERNAL_ASKPASS_TOKEN";
+pub const INTERNAL_ASKPASS_PASSPHRASE_TOKEN_ENV: &str = "COSSH_INTERNAL_ASKPASS_PASSPHRASE_TOKEN";
+const INTERNAL_ASKPASS_FORWARD_SOCKET_ENV: &str = "COSSH_INTERNAL_ASKPASS_FORWARD_SOCKET";
+const INTERNAL_ASKPASS_FORWARD_ENTRY_ENV: &str = "COSSH_INTERNAL_ASKPASS_FORWARD_ENTRY";
+/// Where a remote `cossh` finds the forwarded vault socket; sent with `SendEnv` by the launching side.
+pub const VAULT_FORWARD_SOCKET_ENV: &str = "COSSH_VAULT_FORWARD_SOCK";
+/// Directory on the remote host holding the remote ends of vault forwards.
+const REMOTE_VAULT_FORWARD_DIR: &str = "/tmp";
+const REMOTE_VAULT_FORWARD_PREFIX: &str = "cossh-";
 const SSH_ASKPASS_ENV: &str = "SSH_ASKPASS";
 const SSH_ASKPASS_REQUIRE_ENV: &str = "SSH_ASKPASS_REQUIRE";
 const SSH_ASKPASS_FORCE: &str = "force";
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Password request to send over vault forwarding from the askpass helper.
+pub struct ForwardedAskpassRequest {
+    pub socket_path: PathBuf,
+    pub entry_name: String,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// Decision outcome for an incoming askpass prompt string.
 pub enum AskpassPromptDecision {
     /// Prompt appears to be a standard password prompt.
     Allow,
+    /// Prompt asks for the passphrase of an encrypted private key.
+    AllowPassphrase,
     /// Prompt was missing/empty.
     DenyMissing,
     /// Prompt looked unsafe or unsupported for auto-login.
@@ -22,18 +43,47 @@ pub enum AskpassPromptDecision {
 
 /// Configure environment so OpenSSH invokes `cossh` as askpass helper.
 pub fn configure_internal_askpass_env(command_env: &mut Vec<(String, String)>, token: &str) -> io::Result<()> {
+    configure_askpass_helper_env(command_env)?;
+    command_env.push((INTERNAL_ASKPASS_TOKEN_ENV.to_string(), token.to_string()));
+    Ok(())
+}
+
+/// Configure the askpass helper to answer private-key passphrase prompts.
+pub fn configure_internal_passphrase_askpass_env(command_env: &mut Vec<(String, String)>, token: &str) -> io::Result<()> {
+    configure_askpass_helper_env(command_env)?;
+    command_env.push((INTERNAL_ASKPASS_PASSPHRASE_TOKEN_ENV.to_string(), token.to_string()));
+    Ok(())
+}
+
+/// Configure the askpass helper to fetch the password from the forwarding machine's vault.
+pub fn configure_forwarded_askpass_env(command_env: &mut Vec<(String, String)>, request: &ForwardedAskpassRequest) -> io::Result<()> {
+    configure_askpass_helper_env(command_env)?;
+    command_env.push((
+        INTERNAL_ASKPASS_FORWARD_SOCKET_ENV.to_string(),
+        request.socket_path.to_string_lossy().into_owned(),
+    ));
+    command_env.push((INTERNAL_ASKPASS_FORWARD_ENTRY_ENV.to_string(), request.entry_name.clone()));
+    Ok(())
+}
+
+fn configure_askpass_helper_env(command_env: &mut Vec<(String, String)>) -> io::Result<()> {
+    if command_env.iter().any(|(key, _)| key == INTERNAL_ASKPASS_MODE_ENV) {
+        return Ok(());
+    }
     let cossh_path = platform::cossh_path()?;
     log_debug!("Configuring internal askpass helper");
     command_env.push((SSH_ASKPASS_ENV.to_string(), cossh_path.to_string_lossy().into_owned()));
     command_env.push((SSH_ASKPASS_REQUIRE_ENV.to_string(), SSH_ASKPASS_FORCE.to_string()));
     command_env.push((INTERNAL_ASKPASS_MODE_ENV.to_string(), "1".to_string()));
-    command_env.push((INTERNAL_ASKPASS_TOKEN_ENV.to_string(), token.to_string()));
     Ok(())
 }
 
 /// Returns `true` when process was launched as internal askpass helper.
 pub fn is_internal_askpass_invocation() -> bool {
-    std::env::var_os(INTERNAL_ASKPASS_MODE_ENV).is_some() && std::env::var_os(INTERNAL_ASKPASS_TOKEN_ENV).is_some()
+    std::env::var_os(INTERNAL_ASKPASS_MODE_ENV).is_some()
+        && (std::env::var_os(INTERNAL_ASKPASS_TOKEN_ENV).is_some()
+            || std::env::var_os(INTERNAL_ASKPASS_PASSPHRASE_TOKEN_ENV).is_some()
+            || std::env::var_os(INTERNAL_ASKPASS_FORWARD_ENTRY_ENV).is_some())
 }
 
 /// Read askpass token from environment.
@@ -41,6 +91,81 @@ pub fn internal_askpass_token() -> Option<String> {
     std::env::var(INTERNAL_ASKPASS_TOKEN_ENV).ok().filter(|token| !token.is_empty())
 }
 
+/// Read the key-passphrase askpass token from environment.
+pub fn internal_askpass_passphrase_token() -> Option<String> {
+    std::env::var(INTERNAL_ASKPASS_PASSPHRASE_TOKEN_ENV).ok().filter(|token| !token.is_empty())
+}
+
+/// Read the forwarded password request configured for this askpass helper.
+pub fn internal_askpass_forward_request() -> Option<ForwardedAskpassRequest> {
+    let non_empty = |key: &str| std::env::var(key).ok().filter(|value| !value.is_empty());
+    Some(ForwardedAskpassRequest {
+        socket_path: PathBuf::from(non_empty(INTERNAL_ASKPASS_FORWARD_SOCKET_ENV)?),
+        entry_name: non_empty(INTERNAL_ASKPASS_FORWARD_ENTRY_ENV)?,
+    })
+}
+
+/// Remote end of the `RemoteForward` for the launch whose local socket is `local_socket`.
+///
+/// sshd binds it before any remote command runs, so it cannot go into a
+/// directory made for it. The random per-launch name, taken from the local
+/// socket, keeps concurrent sessions apart and cannot be claimed in advance
+/// by another user; sshd's default `StreamLocalBindMask` leaves the socket
+/// to its owner.
+pub fn remote_vault_forward_socket(local_socket: &Path) -> PathBuf {
+    let file_name = local_socket.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    Path::new(REMOTE_VAULT_FORWARD_DIR).join(format!("{REMOTE_VAULT_FORWARD_PREFIX}{file_name}"))
+}
+
+/// Forwarded vault socket reachable from this session, when one exists.
+///
+/// `COSSH_VAULT_FORWARD_SOCK` names it when the remote sshd accepts the
+/// variable. Otherwise the one live forwarded socket this user owns is used;
+/// with several, the session they belong to cannot be told apart.
+pub fn forwarded_vault_socket_path() -> Option<PathBuf> {
+    if let Some(socket_path) = std::env::var_os(VAULT_FORWARD_SOCKET_ENV).filter(|value| !value.is_empty()) {
+        let socket_path = PathBuf::from(socket_path);
+        return is_owned_socket(&socket_path).then_some(socket_path);
+    }
+
+    let mut live_sockets = live_forwarded_vault_sockets(Path::new(REMOTE_VAULT_FORWARD_DIR));
+    if live_sockets.len() > 1 {
+        log_debug!(
+            "Found {} forwarded vault sockets; allow {} with AcceptEnv on this host to pick the session's own",
+            live_sockets.len(),
+            VAULT_FORWARD_SOCKET_ENV
+        );
+        return None;
+    }
+    live_sockets.pop()
+}
+
+/// Forwarded vault sockets in `dir` owned by this user that accept connections.
+fn live_forwarded_vault_sockets(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let forward_prefix = format!("{REMOTE_VAULT_FORWARD_PREFIX}{VAULT_FORWARD_SOCKET_PREFIX}");
+    let mut sockets: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&forward_prefix) && name.ends_with(".sock"))
+        })
+        .filter(|path| is_owned_socket(path) && UnixStream::connect(path).is_ok())
+        .collect();
+    sockets.sort();
+    sockets
+}
+
+fn is_owned_socket(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket() && metadata.uid() == nix::unistd::Uid::current().as_raw())
+}
+
 /// Read askpass prompt argument from process argv.
 pub fn internal_askpass_prompt() -> Option<String> {
     std::env::args_os()
@@ -56,6 +181,11 @@ pub fn classify_internal_askpass_prompt(prompt: Option<&str>) -> AskpassPromptDe
     };
 
     let normalized = prompt.to_ascii_lowercase();
+    // OpenSSH asks "Enter passphrase for key '<path>':" or "Enter passphrase for <path>:".
+    if normalized.starts_with("enter passphrase for ") {
+        return AskpassPromptDecision::AllowPassphrase;
+    }
+
     let deny_terms = [
         "passphrase",
         "pin",
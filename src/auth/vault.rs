@@ -4,7 +4,9 @@
 //! permissions and authenticated encryption at rest.
 
 use crate::args::validate_vault_entry_name;
-use crate::auth::secret::{SensitiveString, sensitive_string};
+use crate::auth::audit::{self, AuditEvent, AuditEventKind, AuditProcess};
+use crate::auth::keyslot;
+use crate::auth::secret::{ExposeSecret, SensitiveString, sensitive_string};
 use argon2::{Algorithm, Argon2, Params, Version};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 use chacha20poly1305::aead::{Aead, Payload};
@@ -12,10 +14,12 @@ use chacha20poly1305::{KeyInit, XChaCha20Poly1305, XNonce};
 use chrono::Utc;
 use getrandom::fill as random_fill;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fmt;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use zeroize::{Zeroize, Zeroizing};
 
 const VAULT_VERSION: u8 = 1;
@@ -23,15 +27,30 @@ const VAULT_METADATA_FILENAME: &str = "metadata.json";
 const VAULT_ENTRIES_DIRNAME: &str = "entries";
 const VAULT_DIRNAME: &str = "vault";
 const RUN_DIRNAME: &str = "run";
-const DATA_KEY_LEN: usize = 32;
+pub(crate) const DATA_KEY_LEN: usize = 32;
 const KDF_SALT_LEN: usize = 16;
 const WRAPPED_KEY_NONCE_LEN: usize = 24;
 const ENTRY_NONCE_LEN: usize = 24;
 const KDF_MEMORY_KIB: u32 = 64 * 1024;
 const KDF_TIME_COST: u32 = 3;
 const KDF_PARALLELISM: u32 = 1;
+const KDF_MAX_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+const KDF_MAX_TIME_COST: u32 = 64;
+const KDF_MAX_PARALLELISM: u32 = 16;
+const KDF_CALIBRATION_PASSWORD: &[u8] = b"color-ssh/kdf-calibration";
 const WRAPPED_KEY_AAD: &[u8] = b"color-ssh/vault-metadata/v1";
+const KEY_SLOT_AAD_PREFIX: &str = "color-ssh/vault-key-slot/v1:";
+const KEYFILE_DIGEST_PREFIX: &[u8] = b"color-ssh/vault-keyfile/v1";
 const ENTRY_AAD_PREFIX: &[u8] = b"color-ssh/vault-entry/v1:";
+const SSH_KEY_AAD_SEPARATOR: &str = "\nssh-key:";
+const POLICY_AAD_SEPARATOR: &str = "\npolicy:";
+const USER_AAD_SEPARATOR: &str = "\nuser:";
+const ENTRY_HISTORY_AAD_PREFIX: &[u8] = b"color-ssh/vault-entry-history/v1:";
+/// Previous values kept per entry by `cossh vault rotate`.
+pub(crate) const MAX_ENTRY_HISTORY: usize = 5;
+const ATOMIC_TEMP_MARKER: &str = ".tmp-";
+pub(crate) const PRIVATE_DIRECTORY_MODE: u32 = 0o700;
+pub(crate) const PRIVATE_FILE_MODE: u32 = 0o600;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 /// Vault metadata containing wrapped key material and KDF settings.
@@ -45,6 +64,41 @@ pub struct VaultMetadata {
     pub wrapped_dek_ciphertext: String,
     pub created_at: String,
     pub updated_at: String,
+    /// Keyfile whose digest is combined with the master password.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyfile: Option<String>,
+    /// Additional wrapped copies of the data key for other unlock methods.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub key_slots: Vec<WrappedKeySlot>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Unlock method protecting a [`WrappedKeySlot`].
+pub enum KeySlotKind {
+    /// Recovery key printed by `cossh vault init` or `cossh vault recovery-key`.
+    Recovery,
+}
+
+impl KeySlotKind {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Recovery => "recovery",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+/// Copy of the data key wrapped under an unlock method other than the master password.
+pub struct WrappedKeySlot {
+    pub kind: KeySlotKind,
+    pub kdf_salt: String,
+    pub kdf_memory_kib: u32,
+    pub kdf_time_cost: u32,
+    pub kdf_parallelism: u32,
+    pub wrapped_dek_nonce: String,
+    pub wrapped_dek_ciphertext: String,
+    pub created_at: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -55,6 +109,113 @@ pub struct VaultEntry {
     pub nonce: String,
     pub ciphertext: String,
     pub updated_at: String,
+    /// Previous values, newest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<VaultEntryVersion>,
+    /// Present when the entry holds an OpenSSH private key served by the SSH agent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_key: Option<SshKeyConstraints>,
+    /// Access policy the unlock agent enforces before releasing the entry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<EntryPolicy>,
+    /// Login user the password belongs to, bound into the entry's associated data.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+/// Use constraints the SSH agent applies to a key entry.
+///
+/// The constraints are bound into the entry's associated data, so editing
+/// them on disk makes the entry fail authentication.
+pub struct SshKeyConstraints {
+    /// Ask for confirmation through `SSH_ASKPASS` before every signature.
+    #[serde(default)]
+    pub confirm: bool,
+    /// Stop serving the key this many seconds after it was added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lifetime_seconds: Option<u64>,
+    /// When the key was stored, in seconds since the Unix epoch.
+    ///
+    /// Set by [`UnlockedVault::store_ssh_key`]; absent on entries stored
+    /// before it was recorded, whose lifetime counts from the vault unlock.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+/// Per-entry release policy for password entries.
+///
+/// Like [`SshKeyConstraints`], the policy is bound into the entry's associated
+/// data, so loosening it on disk makes the entry fail authentication.
+pub struct EntryPolicy {
+    /// Require an interactive confirmation before every release.
+    ///
+    /// The agent asks through `SSH_ASKPASS` itself, for local and forwarded
+    /// releases alike, and refuses the release when no helper is available.
+    #[serde(default)]
+    pub confirm: bool,
+    /// Host alias or destination patterns (`*` and `?` wildcards) the entry may be used for.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_hosts: Vec<String>,
+    /// Maximum number of releases per unlock session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_uses: Option<u32>,
+}
+
+impl EntryPolicy {
+    /// Returns `true` when the policy places no restriction on the entry.
+    pub fn is_unrestricted(&self) -> bool {
+        !self.confirm && self.allowed_hosts.is_empty() && self.max_uses.is_none()
+    }
+
+    /// Returns `true` when the host alias or destination matches an allowed pattern.
+    ///
+    /// Entries without host patterns may be used for any host.
+    pub fn allows_host(&self, host: Option<&str>, destination: Option<&str>) -> bool {
+        if self.allowed_hosts.is_empty() {
+            return true;
+        }
+        [host, destination].into_iter().flatten().any(|target| {
+            let target = target.to_ascii_lowercase();
+            self.allowed_hosts
+                .iter()
+                .any(|pattern| crate::inventory::matches_pattern(&target, &pattern.to_ascii_lowercase()))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+/// Encrypted previous value of a rotated entry.
+pub struct VaultEntryVersion {
+    pub nonce: String,
+    pub ciphertext: String,
+    pub replaced_at: String,
+}
+
+#[derive(Debug)]
+/// Decrypted previous value of a rotated entry.
+pub struct SecretVersion {
+    pub secret: SensitiveString,
+    pub replaced_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+/// Unencrypted fields of one entry, readable while the vault is locked.
+pub struct VaultEntrySummary {
+    pub name: String,
+    pub updated_at: String,
+    pub ssh_key: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Argon2id cost parameters used to wrap the data encryption key.
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub time_cost: u32,
+    pub parallelism: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -71,8 +232,15 @@ pub enum VaultError {
     VaultAlreadyInitialized,
     VaultNotInitialized,
     EntryNotFound,
+    EntryAlreadyExists,
+    NoEntryHistory,
+    WrongEntryKind(String),
+    InvalidSshKey(String),
     InvalidMasterPassword,
+    InvalidRecoveryKey,
+    KeyfileUnavailable(String),
     InvalidVaultFormat(String),
+    InvalidKdfParameters(String),
     EncryptFailed(String),
     Io(io::Error),
 }
@@ -85,8 +253,15 @@ impl fmt::Display for VaultError {
             Self::VaultAlreadyInitialized => write!(f, "password vault is already initialized"),
             Self::VaultNotInitialized => write!(f, "password vault is not initialized"),
             Self::EntryNotFound => write!(f, "password vault entry was not found"),
+            Self::EntryAlreadyExists => write!(f, "password vault entry already exists"),
+            Self::NoEntryHistory => write!(f, "password vault entry has no previous value to roll back to"),
+            Self::WrongEntryKind(message) => write!(f, "{message}"),
+            Self::InvalidSshKey(message) => write!(f, "invalid SSH key: {message}"),
             Self::InvalidMasterPassword => write!(f, "invalid master password"),
+            Self::InvalidRecoveryKey => write!(f, "invalid recovery key"),
+            Self::KeyfileUnavailable(message) => write!(f, "vault keyfile is unavailable: {message}"),
             Self::InvalidVaultFormat(message) => write!(f, "invalid vault data: {message}"),
+            Self::InvalidKdfParameters(message) => write!(f, "invalid KDF parameters: {message}"),
             Self::EncryptFailed(message) => write!(f, "vault encryption failed: {message}"),
             Self::Io(err) => write!(f, "{err}"),
         }
@@ -101,6 +276,84 @@ impl From<io::Error> for VaultError {
     }
 }
 
+impl KdfParams {
+    /// Parameters used for new vaults and the lowest cost `rekey` accepts.
+    pub const DEFAULT: Self = Self {
+        memory_kib: KDF_MEMORY_KIB,
+        time_cost: KDF_TIME_COST,
+        parallelism: KDF_PARALLELISM,
+    };
+
+    /// Parameters recorded in vault metadata.
+    pub fn from_metadata(metadata: &VaultMetadata) -> Self {
+        Self {
+            memory_kib: metadata.kdf_memory_kib,
+            time_cost: metadata.kdf_time_cost,
+            parallelism: metadata.kdf_parallelism,
+        }
+    }
+
+    /// Parameters recorded in a key slot.
+    pub fn from_key_slot(slot: &WrappedKeySlot) -> Self {
+        Self {
+            memory_kib: slot.kdf_memory_kib,
+            time_cost: slot.kdf_time_cost,
+            parallelism: slot.kdf_parallelism,
+        }
+    }
+
+    /// Reject parameters weaker than the defaults or too large to unlock in practice.
+    pub fn validate(self) -> Result<(), VaultError> {
+        self.check_bounds(Self::DEFAULT)
+    }
+
+    /// Reject stored parameters that are zero or above the `rekey` maximum,
+    /// so edited metadata cannot make an unlock allocate or run without bound.
+    fn validate_stored(self) -> Result<(), VaultError> {
+        let minimum = Self {
+            memory_kib: 1,
+            time_cost: 1,
+            parallelism: 1,
+        };
+        self.check_bounds(minimum)
+            .map_err(|err| VaultError::InvalidVaultFormat(format!("stored {err}")))
+    }
+
+    fn check_bounds(self, minimum: Self) -> Result<(), VaultError> {
+        let bounds = [
+            ("memory", self.memory_kib, minimum.memory_kib, KDF_MAX_MEMORY_KIB),
+            ("time cost", self.time_cost, minimum.time_cost, KDF_MAX_TIME_COST),
+            ("parallelism", self.parallelism, minimum.parallelism, KDF_MAX_PARALLELISM),
+        ];
+        for (label, value, min, max) in bounds {
+            if !(min..=max).contains(&value) {
+                return Err(VaultError::InvalidKdfParameters(format!(
+                    "{label} must be between {min} and {max}, got {value}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn argon2(self) -> Result<Argon2<'static>, VaultError> {
+        let params = Params::new(self.memory_kib, self.time_cost, self.parallelism, Some(DATA_KEY_LEN))
+            .map_err(|err| VaultError::InvalidKdfParameters(err.to_string()))?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+impl fmt::Display for KdfParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Argon2id memory={} MiB, time cost={}, parallelism={}",
+            self.memory_kib / 1024,
+            self.time_cost,
+            self.parallelism
+        )
+    }
+}
+
 impl VaultPaths {
     /// Resolve default paths rooted at `~/.color-ssh`.
     pub fn resolve_default() -> Result<Self, VaultError> {
@@ -176,42 +429,212 @@ impl UnlockedVault {
 
     /// Encrypt and store one secret under `name`.
     pub fn store_secret(&self, name: &str, secret: &str) -> Result<(), VaultError> {
+        self.store_secret_for_user(name, secret, None)
+    }
+
+    /// Encrypt and store one secret under `name` together with its login user.
+    ///
+    /// Without `user`, a replaced entry keeps the user it already had.
+    pub fn store_secret_for_user(&self, name: &str, secret: &str, user: Option<&str>) -> Result<(), VaultError> {
         if !validate_vault_entry_name(name) {
             return Err(VaultError::InvalidEntryName);
         }
 
         ensure_vault_layout(&self.paths)?;
+        // Replacing a value must not silently drop the entry's restrictions
+        // or its rollback history, and must never destroy a stored key.
+        let path = self.paths.entry_path(name)?;
+        if path.is_file() {
+            let current = read_entry_file(&path)?;
+            if current.ssh_key.is_some() {
+                return Err(VaultError::WrongEntryKind(format!("vault entry '{name}' already holds an SSH key")));
+            }
+            let user = user.map(str::to_string).or_else(|| current.user.clone());
+            return self.replace_secret(&current, secret, user, "replaced");
+        }
+        let entry = self.encrypt_entry_with(name, secret, None, None, user.map(str::to_string))?;
+        write_entry_file(&path, &entry)?;
+        record_local_event(&self.paths, AuditEvent::new(AuditEventKind::EntryStored).with_entry(name));
+        Ok(())
+    }
 
-        let mut nonce = [0u8; ENTRY_NONCE_LEN];
-        random_fill(&mut nonce).map_err(|err| VaultError::EncryptFailed(format!("secure random generation failed: {err}")))?;
+    /// Encrypt and store an unencrypted OpenSSH private key for the SSH agent.
+    ///
+    /// The current time is recorded as the key's `added_at`, which starts its lifetime.
+    pub fn store_ssh_key(&self, name: &str, private_key_openssh: &str, constraints: SshKeyConstraints) -> Result<(), VaultError> {
+        if !validate_vault_entry_name(name) {
+            return Err(VaultError::InvalidEntryName);
+        }
+        let private_key = ssh_key::PrivateKey::from_openssh(private_key_openssh)
+            .map_err(|err| VaultError::InvalidSshKey(format!("failed to parse OpenSSH private key: {err}")))?;
+        if private_key.is_encrypted() {
+            return Err(VaultError::InvalidSshKey("private key must be decrypted before it is stored".to_string()));
+        }
 
-        let cipher =
-            XChaCha20Poly1305::new_from_slice(&self.data_key[..]).map_err(|err| VaultError::EncryptFailed(format!("invalid cipher key material: {err}")))?;
-        let aad = entry_aad(name);
-        let ciphertext = cipher
-            .encrypt(
-                XNonce::from_slice(&nonce),
-                Payload {
-                    msg: secret.as_bytes(),
-                    aad: aad.as_bytes(),
-                },
-            )
-            .map_err(|_| VaultError::EncryptFailed("failed to encrypt vault entry".to_string()))?;
+        ensure_vault_layout(&self.paths)?;
+        let constraints = SshKeyConstraints {
+            added_at: Some(SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())),
+            ..constraints
+        };
+        let entry = self.encrypt_entry_with(name, private_key_openssh, Some(constraints), None, None)?;
+        write_entry_file(&self.paths.entry_path(name)?, &entry)?;
+        record_local_event(
+            &self.paths,
+            AuditEvent::new(AuditEventKind::EntryStored).with_entry(name).with_detail("ssh key"),
+        );
+        Ok(())
+    }
 
-        let entry = VaultEntry {
+    /// Encrypt `secret` into an entry bound to `name` without writing it.
+    fn encrypt_entry_with(
+        &self,
+        name: &str,
+        secret: &str,
+        ssh_key: Option<SshKeyConstraints>,
+        policy: Option<EntryPolicy>,
+        user: Option<String>,
+    ) -> Result<VaultEntry, VaultError> {
+        let (nonce, ciphertext) = self.seal(&entry_aad(name, ssh_key.as_ref(), policy.as_ref(), user.as_deref())?, secret)?;
+        Ok(VaultEntry {
             version: VAULT_VERSION,
             name: name.to_string(),
-            nonce: BASE64.encode(nonce),
-            ciphertext: BASE64.encode(ciphertext),
+            nonce,
+            ciphertext,
             updated_at: Utc::now().to_rfc3339(),
-        };
-        write_json_atomic(&self.paths.entry_path(name)?, &entry)?;
-        set_restrictive_file_permissions(&self.paths.entry_path(name)?)?;
+            history: Vec::new(),
+            ssh_key,
+            policy,
+            user,
+        })
+    }
+
+    /// Replace the value of an existing entry, keeping the previous value as history.
+    pub fn rotate_secret(&self, name: &str, secret: &str) -> Result<(), VaultError> {
+        let entry = self.read_password_entry(name, "SSH key entries cannot be rotated")?;
+        self.replace_secret(&entry, secret, entry.user.clone(), "rotated")
+    }
+
+    fn replace_secret(&self, entry: &VaultEntry, secret: &str, user: Option<String>, detail: &str) -> Result<(), VaultError> {
+        let current = self.decrypt_entry(entry)?;
+        let mut history = self.decrypt_history(entry)?;
+        history.insert(
+            0,
+            SecretVersion {
+                secret: current,
+                replaced_at: Utc::now().to_rfc3339(),
+            },
+        );
+        self.write_rotated_entry(entry, secret, &history, user, detail)
+    }
+
+    /// Restore the newest history value, keeping the replaced value as history.
+    pub fn rollback_secret(&self, name: &str) -> Result<(), VaultError> {
+        let entry = self.read_password_entry(name, "SSH key entries cannot be rotated")?;
+        let current = self.decrypt_entry(&entry)?;
+        let mut history = self.decrypt_history(&entry)?;
+        if history.is_empty() {
+            return Err(VaultError::NoEntryHistory);
+        }
+        let restored = history.remove(0);
+        history.insert(
+            0,
+            SecretVersion {
+                secret: current,
+                replaced_at: Utc::now().to_rfc3339(),
+            },
+        );
+        self.write_rotated_entry(&entry, restored.secret.expose_secret(), &history, entry.user.clone(), "rolled back")
+    }
+
+    /// Decrypt the previous values of one entry, newest first.
+    pub fn secret_history(&self, name: &str) -> Result<Vec<SecretVersion>, VaultError> {
+        let entry = self.read_entry(name)?;
+        self.decrypt_history(&entry)
+    }
+
+    /// Re-encrypt an entry and its history under `replacement`'s data key.
+    pub(crate) fn reencrypt_entry(&self, entry: &VaultEntry, replacement: &UnlockedVault) -> Result<VaultEntry, VaultError> {
+        let current = self.decrypt_entry(entry)?;
+        let history = self.decrypt_history(entry)?;
+        let mut reencrypted = replacement.encrypt_entry_with(&entry.name, current.expose_secret(), entry.ssh_key, entry.policy.clone(), entry.user.clone())?;
+        reencrypted.updated_at = entry.updated_at.clone();
+        reencrypted.history = replacement.encrypt_history(&entry.name, &history)?;
+        Ok(reencrypted)
+    }
+
+    fn write_rotated_entry(&self, current: &VaultEntry, secret: &str, history: &[SecretVersion], user: Option<String>, detail: &str) -> Result<(), VaultError> {
+        let name = current.name.as_str();
+        let mut entry = self.encrypt_entry_with(name, secret, None, current.policy.clone(), user)?;
+        let kept = history.len().min(MAX_ENTRY_HISTORY);
+        entry.history = self.encrypt_history(name, &history[..kept])?;
+        write_entry_file(&self.paths.entry_path(name)?, &entry)?;
+        record_local_event(&self.paths, AuditEvent::new(AuditEventKind::EntryStored).with_entry(name).with_detail(detail));
         Ok(())
     }
 
-    /// Decrypt and return one secret by `name`.
-    pub fn get_secret(&self, name: &str) -> Result<SensitiveString, VaultError> {
+    /// Authenticate a password entry and return its release policy.
+    pub fn entry_policy(&self, name: &str) -> Result<EntryPolicy, VaultError> {
+        let entry = self.read_password_entry(name, "SSH key entries use key constraints instead of entry policies")?;
+        self.decrypt_entry(&entry)?;
+        Ok(entry.policy.unwrap_or_default())
+    }
+
+    /// Replace the release policy of a password entry.
+    ///
+    /// The value and history are kept; only the bound policy changes.
+    pub fn set_entry_policy(&self, name: &str, policy: EntryPolicy) -> Result<(), VaultError> {
+        let entry = self.read_password_entry(name, "SSH key entries use key constraints instead of entry policies")?;
+        let current = self.decrypt_entry(&entry)?;
+        let policy = (!policy.is_unrestricted()).then_some(policy);
+        let mut updated = self.encrypt_entry_with(name, current.expose_secret(), None, policy, entry.user.clone())?;
+        updated.updated_at = entry.updated_at;
+        updated.history = entry.history;
+        write_entry_file(&self.paths.entry_path(name)?, &updated)?;
+        record_local_event(
+            &self.paths,
+            AuditEvent::new(AuditEventKind::EntryStored).with_entry(name).with_detail("policy updated"),
+        );
+        Ok(())
+    }
+
+    fn encrypt_history(&self, name: &str, history: &[SecretVersion]) -> Result<Vec<VaultEntryVersion>, VaultError> {
+        let aad = entry_history_aad(name);
+        history
+            .iter()
+            .map(|version| {
+                let (nonce, ciphertext) = self.seal(&aad, version.secret.expose_secret())?;
+                Ok(VaultEntryVersion {
+                    nonce,
+                    ciphertext,
+                    replaced_at: version.replaced_at.clone(),
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn decrypt_history(&self, entry: &VaultEntry) -> Result<Vec<SecretVersion>, VaultError> {
+        let aad = entry_history_aad(&entry.name);
+        entry
+            .history
+            .iter()
+            .map(|version| {
+                Ok(SecretVersion {
+                    secret: self.open(&aad, &version.nonce, &version.ciphertext)?,
+                    replaced_at: version.replaced_at.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn read_password_entry(&self, name: &str, ssh_key_message: &str) -> Result<VaultEntry, VaultError> {
+        let entry = self.read_entry(name)?;
+        if entry.ssh_key.is_some() {
+            return Err(VaultError::WrongEntryKind(ssh_key_message.to_string()));
+        }
+        Ok(entry)
+    }
+
+    fn read_entry(&self, name: &str) -> Result<VaultEntry, VaultError> {
         if !validate_vault_entry_name(name) {
             return Err(VaultError::InvalidEntryName);
         }
@@ -221,23 +644,40 @@ impl UnlockedVault {
             return Err(VaultError::EntryNotFound);
         }
 
-        let entry = read_json::<VaultEntry>(&path)?;
-        if entry.version != VAULT_VERSION {
-            return Err(VaultError::InvalidVaultFormat("unsupported entry version".to_string()));
-        }
+        let entry = read_entry_file(&path)?;
         if entry.name != name {
             return Err(VaultError::InvalidVaultFormat("entry name did not match file name".to_string()));
         }
+        Ok(entry)
+    }
+
+    fn seal(&self, aad: &str, plaintext: &str) -> Result<(String, String), VaultError> {
+        let mut nonce = [0u8; ENTRY_NONCE_LEN];
+        random_fill(&mut nonce).map_err(|err| VaultError::EncryptFailed(format!("secure random generation failed: {err}")))?;
+
+        let cipher =
+            XChaCha20Poly1305::new_from_slice(&self.data_key[..]).map_err(|err| VaultError::EncryptFailed(format!("invalid cipher key material: {err}")))?;
+        let ciphertext = cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| VaultError::EncryptFailed("failed to encrypt vault entry".to_string()))?;
+        Ok((BASE64.encode(nonce), BASE64.encode(ciphertext)))
+    }
 
-        let nonce = decode_fixed::<ENTRY_NONCE_LEN>(&entry.nonce, "entry nonce")?;
-        let ciphertext = decode_bytes(&entry.ciphertext, "entry ciphertext")?;
+    fn open(&self, aad: &str, nonce: &str, ciphertext: &str) -> Result<SensitiveString, VaultError> {
+        let nonce = decode_fixed::<ENTRY_NONCE_LEN>(nonce, "entry nonce")?;
+        let ciphertext = decode_bytes(ciphertext, "entry ciphertext")?;
         if ciphertext.is_empty() {
             return Err(VaultError::InvalidVaultFormat("entry ciphertext was empty".to_string()));
         }
 
         let cipher =
             XChaCha20Poly1305::new_from_slice(&self.data_key[..]).map_err(|err| VaultError::EncryptFailed(format!("invalid cipher key material: {err}")))?;
-        let aad = entry_aad(name);
         let plaintext = cipher
             .decrypt(
                 XNonce::from_slice(&nonce),
@@ -257,6 +697,60 @@ impl UnlockedVault {
         }
     }
 
+    /// Decrypt and return one secret by `name`.
+    pub fn get_secret(&self, name: &str) -> Result<SensitiveString, VaultError> {
+        let entry = self.read_password_entry(name, "entry holds an SSH key, not a password")?;
+        self.decrypt_entry(&entry)
+    }
+
+    /// Decrypt one SSH key entry, returning the OpenSSH private key and its constraints.
+    pub fn get_ssh_key(&self, name: &str) -> Result<(SensitiveString, SshKeyConstraints), VaultError> {
+        let entry = self.read_entry(name)?;
+        let Some(constraints) = entry.ssh_key else {
+            return Err(VaultError::WrongEntryKind(format!("entry '{name}' does not hold an SSH key")));
+        };
+        Ok((self.decrypt_entry(&entry)?, constraints))
+    }
+
+    /// Decrypt one parsed entry, authenticating it against its recorded name.
+    pub(crate) fn decrypt_entry(&self, entry: &VaultEntry) -> Result<SensitiveString, VaultError> {
+        if entry.version != VAULT_VERSION {
+            return Err(VaultError::InvalidVaultFormat("unsupported entry version".to_string()));
+        }
+        self.open(
+            &entry_aad(&entry.name, entry.ssh_key.as_ref(), entry.policy.as_ref(), entry.user.as_deref())?,
+            &entry.nonce,
+            &entry.ciphertext,
+        )
+    }
+
+    /// Move an entry to `new_name`, re-encrypting its value and history under the new name.
+    pub fn rename_entry(&self, name: &str, new_name: &str) -> Result<(), VaultError> {
+        if !validate_vault_entry_name(new_name) {
+            return Err(VaultError::InvalidEntryName);
+        }
+        let entry = self.read_entry(name)?;
+        let target = self.paths.entry_path(new_name)?;
+        if target.exists() {
+            return Err(VaultError::EntryAlreadyExists);
+        }
+
+        let current = self.decrypt_entry(&entry)?;
+        let history = self.decrypt_history(&entry)?;
+        let mut renamed = self.encrypt_entry_with(new_name, current.expose_secret(), entry.ssh_key, entry.policy.clone(), entry.user.clone())?;
+        renamed.updated_at = entry.updated_at;
+        renamed.history = self.encrypt_history(new_name, &history)?;
+        write_entry_file(&target, &renamed)?;
+        fs::remove_file(self.paths.entry_path(name)?)?;
+        record_local_event(
+            &self.paths,
+            AuditEvent::new(AuditEventKind::EntryStored)
+                .with_entry(new_name)
+                .with_detail(format!("renamed from {name}")),
+        );
+        Ok(())
+    }
+
     /// Remove one secret entry by `name`.
     pub fn remove_entry(&self, name: &str) -> Result<(), VaultError> {
         let path = self.paths.entry_path(name)?;
@@ -264,6 +758,7 @@ impl UnlockedVault {
             return Err(VaultError::EntryNotFound);
         }
         fs::remove_file(path)?;
+        record_local_event(&self.paths, AuditEvent::new(AuditEventKind::EntryRemoved).with_entry(name));
         Ok(())
     }
 
@@ -287,13 +782,35 @@ pub fn list_entries() -> Result<Vec<String>, VaultError> {
     list_entries_with_paths(&VaultPaths::resolve_default()?)
 }
 
+/// List every entry in the default vault with its unencrypted fields.
+pub fn list_entry_summaries() -> Result<Vec<VaultEntrySummary>, VaultError> {
+    list_entry_summaries_with_paths(&VaultPaths::resolve_default()?)
+}
+
 /// Returns whether the named entry exists in the default vault.
 pub fn entry_exists(name: &str) -> Result<bool, VaultError> {
     entry_exists_with_paths(&VaultPaths::resolve_default()?, name)
 }
 
+/// Return whether a default-vault entry holds an SSH private key.
+///
+/// Only the unencrypted entry marker is read, so this works while locked.
+pub fn is_ssh_key_entry(name: &str) -> Result<bool, VaultError> {
+    is_ssh_key_entry_with_paths(&VaultPaths::resolve_default()?, name)
+}
+
+/// Return the login user stored on a default-vault entry.
+///
+/// Only the unencrypted field is read, so this works while locked; a
+/// tampered user makes the entry's value fail to decrypt.
+pub fn entry_user(name: &str) -> Result<Option<String>, VaultError> {
+    entry_user_with_paths(&VaultPaths::resolve_default()?, name)
+}
+
 /// Initialize the default vault with a master password.
-pub fn initialize_vault(master_password: &str) -> Result<(), VaultError> {
+///
+/// Returns the recovery key, which is shown once and never stored.
+pub fn initialize_vault(master_password: &str) -> Result<SensitiveString, VaultError> {
     initialize_vault_with_paths(&VaultPaths::resolve_default()?, master_password)
 }
 
@@ -307,7 +824,7 @@ pub fn rotate_master_password(current_password: &str, new_password: &str) -> Res
     rotate_master_password_with_paths(&VaultPaths::resolve_default()?, current_password, new_password)
 }
 
-pub(crate) fn initialize_vault_with_paths(paths: &VaultPaths, master_password: &str) -> Result<(), VaultError> {
+pub(crate) fn initialize_vault_with_paths(paths: &VaultPaths, master_password: &str) -> Result<SensitiveString, VaultError> {
     if master_password.is_empty() {
         return Err(VaultError::InvalidMasterPassword);
     }
@@ -319,11 +836,17 @@ pub(crate) fn initialize_vault_with_paths(paths: &VaultPaths, master_password: &
 
     let mut data_key = [0u8; DATA_KEY_LEN];
     random_fill(&mut data_key).map_err(|err| VaultError::EncryptFailed(format!("secure random generation failed: {err}")))?;
-    let metadata = build_metadata_from_data_key(master_password, &data_key)?;
+    let recovery_key = keyslot::generate_recovery_key()?;
+    let metadata = build_metadata_from_data_key(master_password, None, &data_key, KdfParams::DEFAULT).and_then(|mut metadata| {
+        metadata.key_slots = vec![keyslot::recovery_key_slot(&recovery_key, &data_key)?];
+        Ok(metadata)
+    });
     data_key.zeroize();
+    let metadata = metadata?;
     write_json_atomic(&paths.metadata_path(), &metadata)?;
     set_restrictive_file_permissions(&paths.metadata_path())?;
-    Ok(())
+    record_local_event(paths, AuditEvent::new(AuditEventKind::VaultInitialized));
+    Ok(recovery_key)
 }
 
 pub(crate) fn unlock_with_password_and_paths(paths: &VaultPaths, master_password: &str) -> Result<UnlockedVault, VaultError> {
@@ -348,17 +871,59 @@ pub(crate) fn rotate_master_password_with_paths(paths: &VaultPaths, current_pass
     if new_password.is_empty() {
         return Err(VaultError::InvalidMasterPassword);
     }
-    let unlocked = unlock_with_password_and_paths(paths, current_password)?;
+    let unlocked = match unlock_with_password_and_paths(paths, current_password) {
+        Ok(unlocked) => unlocked,
+        Err(err) => {
+            if matches!(err, VaultError::InvalidMasterPassword) {
+                record_local_event(paths, AuditEvent::new(AuditEventKind::UnlockFailed).with_detail("master password rotation"));
+            }
+            return Err(err);
+        }
+    };
     let metadata_path = paths.metadata_path();
     let existing = read_json::<VaultMetadata>(&metadata_path)?;
-    let mut updated = build_metadata_from_data_key(new_password, &unlocked.data_key_copy())?;
+    let mut updated = build_metadata_from_data_key(
+        new_password,
+        existing.keyfile.as_deref(),
+        &unlocked.data_key_copy(),
+        KdfParams::from_metadata(&existing),
+    )?;
     updated.created_at = existing.created_at;
+    updated.key_slots = existing.key_slots;
     updated.updated_at = Utc::now().to_rfc3339();
     write_json_atomic(&metadata_path, &updated)?;
     set_restrictive_file_permissions(&metadata_path)?;
+    record_local_event(paths, AuditEvent::new(AuditEventKind::MasterPasswordRotated));
     Ok(())
 }
 
+/// Every SSH key entry, sorted by name.
+pub(crate) fn list_ssh_key_entries_with_paths(paths: &VaultPaths) -> Result<Vec<VaultEntry>, VaultError> {
+    let mut keys = Vec::new();
+    for name in list_entries_with_paths(paths)? {
+        let entry = read_entry_file(&paths.entry_path(&name)?)?;
+        if entry.ssh_key.is_some() && entry.name == name {
+            keys.push(entry);
+        }
+    }
+    Ok(keys)
+}
+
+pub(crate) fn list_entry_summaries_with_paths(paths: &VaultPaths) -> Result<Vec<VaultEntrySummary>, VaultError> {
+    list_entries_with_paths(paths)?
+        .into_iter()
+        .map(|name| {
+            let entry = read_entry_file(&paths.entry_path(&name)?)?;
+            Ok(VaultEntrySummary {
+                name,
+                updated_at: entry.updated_at,
+                ssh_key: entry.ssh_key.is_some(),
+                user: entry.user,
+            })
+        })
+        .collect()
+}
+
 pub(crate) fn list_entries_with_paths(paths: &VaultPaths) -> Result<Vec<String>, VaultError> {
     if !paths.metadata_path().is_file() {
         return Err(VaultError::VaultNotInitialized);
@@ -404,58 +969,184 @@ pub(crate) fn entry_exists_with_paths(paths: &VaultPaths, name: &str) -> Result<
     Ok(paths.entry_path(name)?.is_file())
 }
 
-fn build_metadata_from_data_key(master_password: &str, data_key: &[u8; DATA_KEY_LEN]) -> Result<VaultMetadata, VaultError> {
-    let mut salt = [0u8; KDF_SALT_LEN];
-    random_fill(&mut salt).map_err(|err| VaultError::EncryptFailed(format!("secure random generation failed: {err}")))?;
-    let mut nonce = [0u8; WRAPPED_KEY_NONCE_LEN];
-    random_fill(&mut nonce).map_err(|err| VaultError::EncryptFailed(format!("secure random generation failed: {err}")))?;
+pub(crate) fn is_ssh_key_entry_with_paths(paths: &VaultPaths, name: &str) -> Result<bool, VaultError> {
+    if !entry_exists_with_paths(paths, name)? {
+        return Err(VaultError::EntryNotFound);
+    }
+    Ok(read_entry_file(&paths.entry_path(name)?)?.ssh_key.is_some())
+}
 
-    let mut wrapping_key = Zeroizing::new([0u8; DATA_KEY_LEN]);
-    derive_key(master_password.as_bytes(), &salt, &mut wrapping_key)?;
-    let cipher =
-        XChaCha20Poly1305::new_from_slice(&wrapping_key[..]).map_err(|err| VaultError::EncryptFailed(format!("invalid cipher key material: {err}")))?;
-    let ciphertext = cipher
-        .encrypt(
-            XNonce::from_slice(&nonce),
-            Payload {
-                msg: data_key,
-                aad: WRAPPED_KEY_AAD,
-            },
-        )
-        .map_err(|_| VaultError::EncryptFailed("failed to wrap data key".to_string()))?;
+pub(crate) fn entry_user_with_paths(paths: &VaultPaths, name: &str) -> Result<Option<String>, VaultError> {
+    if !entry_exists_with_paths(paths, name)? {
+        return Err(VaultError::EntryNotFound);
+    }
+    Ok(read_entry_file(&paths.entry_path(name)?)?.user)
+}
+
+/// Read the KDF parameters of the default vault.
+pub fn current_kdf_params() -> Result<KdfParams, VaultError> {
+    current_kdf_params_with_paths(&VaultPaths::resolve_default()?)
+}
+
+pub(crate) fn current_kdf_params_with_paths(paths: &VaultPaths) -> Result<KdfParams, VaultError> {
+    Ok(KdfParams::from_metadata(&read_metadata(paths)?))
+}
+
+/// Raise `base.time_cost` until one key derivation takes about `target`.
+pub fn calibrate_kdf(base: KdfParams, target: Duration) -> Result<KdfParams, VaultError> {
+    let probe = KdfParams { time_cost: 1, ..base };
+    let mut output = Zeroizing::new([0u8; DATA_KEY_LEN]);
+    let started_at = Instant::now();
+    probe
+        .argon2()?
+        .hash_password_into(KDF_CALIBRATION_PASSWORD, &[0u8; KDF_SALT_LEN], &mut output[..])
+        .map_err(|err| VaultError::EncryptFailed(format!("failed to derive calibration key: {err}")))?;
+    let per_pass = started_at.elapsed().max(Duration::from_micros(1));
+
+    let passes = target.as_secs_f64() / per_pass.as_secs_f64();
+    let time_cost = (passes.ceil() as u32).clamp(base.time_cost, KDF_MAX_TIME_COST);
+    Ok(KdfParams { time_cost, ..base })
+}
+
+pub(crate) fn read_metadata(paths: &VaultPaths) -> Result<VaultMetadata, VaultError> {
+    let metadata_path = paths.metadata_path();
+    if !metadata_path.is_file() {
+        return Err(VaultError::VaultNotInitialized);
+    }
+    read_json(&metadata_path)
+}
+
+pub(crate) fn write_metadata(paths: &VaultPaths, metadata: &VaultMetadata) -> Result<(), VaultError> {
+    write_json_atomic(&paths.metadata_path(), metadata)?;
+    set_restrictive_file_permissions(&paths.metadata_path())
+}
+
+pub(crate) fn write_entry_file(path: &Path, entry: &VaultEntry) -> Result<(), VaultError> {
+    write_json_atomic(path, entry)?;
+    set_restrictive_file_permissions(path)
+}
+
+/// Wrap `data_key` under `master_password`, combined with `keyfile` when set.
+pub(crate) fn build_metadata_from_data_key(
+    master_password: &str,
+    keyfile: Option<&str>,
+    data_key: &[u8; DATA_KEY_LEN],
+    kdf: KdfParams,
+) -> Result<VaultMetadata, VaultError> {
+    let passphrase = master_passphrase(master_password, keyfile)?;
+    let (salt, nonce, ciphertext) = wrap_data_key(&passphrase, data_key, kdf, WRAPPED_KEY_AAD)?;
 
     let now = Utc::now().to_rfc3339();
     Ok(VaultMetadata {
         version: VAULT_VERSION,
-        kdf_salt: BASE64.encode(salt),
-        kdf_memory_kib: KDF_MEMORY_KIB,
-        kdf_time_cost: KDF_TIME_COST,
-        kdf_parallelism: KDF_PARALLELISM,
-        wrapped_dek_nonce: BASE64.encode(nonce),
-        wrapped_dek_ciphertext: BASE64.encode(ciphertext),
+        kdf_salt: salt,
+        kdf_memory_kib: kdf.memory_kib,
+        kdf_time_cost: kdf.time_cost,
+        kdf_parallelism: kdf.parallelism,
+        wrapped_dek_nonce: nonce,
+        wrapped_dek_ciphertext: ciphertext,
         created_at: now.clone(),
         updated_at: now,
+        keyfile: keyfile.map(str::to_string),
+        key_slots: Vec::new(),
+    })
+}
+
+/// Wrap `data_key` into a key slot of `kind` unlocked by `passphrase`.
+pub(crate) fn build_key_slot(kind: KeySlotKind, passphrase: &[u8], data_key: &[u8; DATA_KEY_LEN], kdf: KdfParams) -> Result<WrappedKeySlot, VaultError> {
+    let (salt, nonce, ciphertext) = wrap_data_key(passphrase, data_key, kdf, key_slot_aad(kind).as_bytes())?;
+    Ok(WrappedKeySlot {
+        kind,
+        kdf_salt: salt,
+        kdf_memory_kib: kdf.memory_kib,
+        kdf_time_cost: kdf.time_cost,
+        kdf_parallelism: kdf.parallelism,
+        wrapped_dek_nonce: nonce,
+        wrapped_dek_ciphertext: ciphertext,
+        created_at: Utc::now().to_rfc3339(),
+    })
+}
+
+/// Recover the data key from `slot`, failing with `InvalidRecoveryKey` on a wrong passphrase.
+pub(crate) fn open_key_slot(slot: &WrappedKeySlot, passphrase: &[u8]) -> Result<[u8; DATA_KEY_LEN], VaultError> {
+    unwrap_data_key(
+        passphrase,
+        KdfParams::from_key_slot(slot),
+        &slot.kdf_salt,
+        &slot.wrapped_dek_nonce,
+        &slot.wrapped_dek_ciphertext,
+        key_slot_aad(slot.kind).as_bytes(),
+    )
+    .map_err(|err| match err {
+        VaultError::InvalidMasterPassword => VaultError::InvalidRecoveryKey,
+        other => other,
     })
 }
 
+/// Argon2 input for the master password slot.
+///
+/// With a keyfile configured, the keyfile's SHA-256 digest is appended to the
+/// password, so unlocking needs both.
+pub(crate) fn master_passphrase(master_password: &str, keyfile: Option<&str>) -> Result<Zeroizing<Vec<u8>>, VaultError> {
+    let mut passphrase = Zeroizing::new(master_password.as_bytes().to_vec());
+    let Some(keyfile) = keyfile else {
+        return Ok(passphrase);
+    };
+
+    let contents = Zeroizing::new(fs::read(keyfile).map_err(|err| VaultError::KeyfileUnavailable(format!("{keyfile}: {err}")))?);
+    if contents.is_empty() {
+        return Err(VaultError::KeyfileUnavailable(format!("{keyfile} is empty")));
+    }
+    let digest = Sha256::new().chain_update(KEYFILE_DIGEST_PREFIX).chain_update(&contents[..]).finalize();
+    passphrase.push(0);
+    passphrase.extend_from_slice(&digest);
+    Ok(passphrase)
+}
+
 fn decrypt_wrapped_data_key(master_password: &str, metadata: &VaultMetadata) -> Result<[u8; DATA_KEY_LEN], VaultError> {
     if metadata.version != VAULT_VERSION {
         return Err(VaultError::InvalidVaultFormat("unsupported vault version".to_string()));
     }
-    if metadata.kdf_memory_kib == 0 || metadata.kdf_time_cost == 0 || metadata.kdf_parallelism == 0 {
-        return Err(VaultError::InvalidVaultFormat("invalid KDF parameters".to_string()));
-    }
 
-    let salt = decode_fixed::<KDF_SALT_LEN>(&metadata.kdf_salt, "KDF salt")?;
-    let nonce = decode_fixed::<WRAPPED_KEY_NONCE_LEN>(&metadata.wrapped_dek_nonce, "wrapped DEK nonce")?;
-    let ciphertext = decode_bytes(&metadata.wrapped_dek_ciphertext, "wrapped DEK ciphertext")?;
+    let passphrase = master_passphrase(master_password, metadata.keyfile.as_deref())?;
+    unwrap_data_key(
+        &passphrase,
+        KdfParams::from_metadata(metadata),
+        &metadata.kdf_salt,
+        &metadata.wrapped_dek_nonce,
+        &metadata.wrapped_dek_ciphertext,
+        WRAPPED_KEY_AAD,
+    )
+}
+
+fn wrap_data_key(passphrase: &[u8], data_key: &[u8; DATA_KEY_LEN], kdf: KdfParams, aad: &[u8]) -> Result<(String, String, String), VaultError> {
+    let mut salt = [0u8; KDF_SALT_LEN];
+    random_fill(&mut salt).map_err(|err| VaultError::EncryptFailed(format!("secure random generation failed: {err}")))?;
+    let mut nonce = [0u8; WRAPPED_KEY_NONCE_LEN];
+    random_fill(&mut nonce).map_err(|err| VaultError::EncryptFailed(format!("secure random generation failed: {err}")))?;
+
+    let mut wrapping_key = Zeroizing::new([0u8; DATA_KEY_LEN]);
+    derive_key(passphrase, &salt, kdf, &mut wrapping_key)?;
+    let cipher =
+        XChaCha20Poly1305::new_from_slice(&wrapping_key[..]).map_err(|err| VaultError::EncryptFailed(format!("invalid cipher key material: {err}")))?;
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: data_key, aad })
+        .map_err(|_| VaultError::EncryptFailed("failed to wrap data key".to_string()))?;
+
+    Ok((BASE64.encode(salt), BASE64.encode(nonce), BASE64.encode(ciphertext)))
+}
+
+fn unwrap_data_key(passphrase: &[u8], kdf: KdfParams, salt: &str, nonce: &str, ciphertext: &str, aad: &[u8]) -> Result<[u8; DATA_KEY_LEN], VaultError> {
+    kdf.validate_stored()?;
+
+    let salt = decode_fixed::<KDF_SALT_LEN>(salt, "KDF salt")?;
+    let nonce = decode_fixed::<WRAPPED_KEY_NONCE_LEN>(nonce, "wrapped DEK nonce")?;
+    let ciphertext = decode_bytes(ciphertext, "wrapped DEK ciphertext")?;
 
-    let params = Params::new(metadata.kdf_memory_kib, metadata.kdf_time_cost, metadata.kdf_parallelism, Some(DATA_KEY_LEN))
-        .map_err(|err| VaultError::InvalidVaultFormat(format!("invalid KDF parameters: {err}")))?;
-    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+    let argon2 = kdf.argon2().map_err(|err| VaultError::InvalidVaultFormat(err.to_string()))?;
     let mut wrapping_key = Zeroizing::new([0u8; DATA_KEY_LEN]);
     argon2
-        .hash_password_into(master_password.as_bytes(), &salt, &mut wrapping_key[..])
+        .hash_password_into(passphrase, &salt, &mut wrapping_key[..])
         .map_err(|_| VaultError::InvalidMasterPassword)?;
 
     let cipher =
@@ -465,7 +1156,7 @@ fn decrypt_wrapped_data_key(master_password: &str, metadata: &VaultMetadata) ->
             XNonce::from_slice(&nonce),
             Payload {
                 msg: ciphertext.as_slice(),
-                aad: WRAPPED_KEY_AAD,
+                aad,
             },
         )
         .map_err(|_| VaultError::InvalidMasterPassword)?;
@@ -480,15 +1171,16 @@ fn decrypt_wrapped_data_key(master_password: &str, metadata: &VaultMetadata) ->
     Ok(data_key)
 }
 
-fn derive_key(passphrase: &[u8], salt: &[u8], key_output: &mut [u8; DATA_KEY_LEN]) -> Result<(), VaultError> {
-    let params = Params::new(KDF_MEMORY_KIB, KDF_TIME_COST, KDF_PARALLELISM, Some(DATA_KEY_LEN))
-        .map_err(|err| VaultError::EncryptFailed(format!("invalid KDF parameters: {err}")))?;
-    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
-    argon2
+fn derive_key(passphrase: &[u8], salt: &[u8], kdf: KdfParams, key_output: &mut [u8; DATA_KEY_LEN]) -> Result<(), VaultError> {
+    kdf.argon2()?
         .hash_password_into(passphrase, salt, key_output)
         .map_err(|err| VaultError::EncryptFailed(format!("failed to derive encryption key: {err}")))
 }
 
+fn record_local_event(paths: &VaultPaths, event: AuditEvent) {
+    audit::record_event(paths, event.with_process(Some(AuditProcess::current())));
+}
+
 fn ensure_vault_layout(paths: &VaultPaths) -> Result<(), VaultError> {
     fs::create_dir_all(paths.vault_dir())?;
     set_restrictive_directory_permissions(&paths.vault_dir())?;
@@ -508,13 +1200,25 @@ fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> Result<(), VaultEr
 
     let serialized = serde_json::to_vec_pretty(value).map_err(|err| VaultError::InvalidVaultFormat(format!("failed to serialize JSON: {err}")))?;
     let file_name = path.file_name().and_then(|segment| segment.to_str()).unwrap_or("vault-data");
-    let tmp_path = parent.join(format!(".{file_name}.tmp-{}", Utc::now().timestamp_nanos_opt().unwrap_or_default()));
+    let tmp_path = parent.join(format!(
+        ".{file_name}{ATOMIC_TEMP_MARKER}{}",
+        Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
     fs::write(&tmp_path, serialized)?;
     set_restrictive_file_permissions(&tmp_path)?;
     fs::rename(&tmp_path, path)?;
     Ok(())
 }
 
+/// Returns `true` for temp-file names produced by `write_json_atomic`.
+pub(crate) fn is_atomic_temp_file_name(file_name: &str) -> bool {
+    file_name.starts_with('.') && file_name.contains(ATOMIC_TEMP_MARKER)
+}
+
+pub(crate) fn read_entry_file(path: &Path) -> Result<VaultEntry, VaultError> {
+    read_json(path)
+}
+
 fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T, VaultError> {
     let bytes = fs::read(path)?;
     serde_json::from_slice(&bytes).map_err(|err| VaultError::InvalidVaultFormat(format!("failed to parse JSON: {err}")))
@@ -536,16 +1240,41 @@ fn decode_fixed<const N: usize>(encoded: &str, label: &str) -> Result<[u8; N], V
     Ok(output)
 }
 
-fn entry_aad(name: &str) -> String {
-    format!("{}{}", String::from_utf8_lossy(ENTRY_AAD_PREFIX), name)
+fn entry_aad(name: &str, ssh_key: Option<&SshKeyConstraints>, policy: Option<&EntryPolicy>, user: Option<&str>) -> Result<String, VaultError> {
+    let mut aad = format!("{}{}", String::from_utf8_lossy(ENTRY_AAD_PREFIX), name);
+    if let Some(constraints) = ssh_key {
+        let constraints =
+            serde_json::to_string(constraints).map_err(|err| VaultError::InvalidVaultFormat(format!("failed to serialize SSH key constraints: {err}")))?;
+        aad.push_str(SSH_KEY_AAD_SEPARATOR);
+        aad.push_str(&constraints);
+    }
+    if let Some(policy) = policy {
+        let policy = serde_json::to_string(policy).map_err(|err| VaultError::InvalidVaultFormat(format!("failed to serialize entry policy: {err}")))?;
+        aad.push_str(POLICY_AAD_SEPARATOR);
+        aad.push_str(&policy);
+    }
+    if let Some(user) = user {
+        let user = serde_json::to_string(user).map_err(|err| VaultError::InvalidVaultFormat(format!("failed to serialize entry user: {err}")))?;
+        aad.push_str(USER_AAD_SEPARATOR);
+        aad.push_str(&user);
+    }
+    Ok(aad)
+}
+
+fn key_slot_aad(kind: KeySlotKind) -> String {
+    format!("{KEY_SLOT_AAD_PREFIX}{}", kind.label())
+}
+
+fn entry_history_aad(name: &str) -> String {
+    format!("{}{}", String::from_utf8_lossy(ENTRY_HISTORY_AAD_PREFIX), name)
 }
 
 fn set_restrictive_directory_permissions(path: &Path) -> Result<(), VaultError> {
-    Ok(crate::platform::set_private_directory_permissions(path, 0o700)?)
+    Ok(crate::platform::set_private_directory_permissions(path, PRIVATE_DIRECTORY_MODE)?)
 }
 
 fn set_restrictive_file_permissions(path: &Path) -> Result<(), VaultError> {
-    Ok(crate::platform::set_private_file_permissions(path, 0o600)?)
+    Ok(crate::platform::set_private_file_permissions(path, PRIVATE_FILE_MODE)?)
 }
 
 #[cfg(test)]
@@ -12,6 +12,7 @@ mod watcher;
 
 pub use errors::ConfigError;
 pub(crate) use highlight::CompiledHighlightRule;
+pub(crate) use paths::available_profiles;
 pub use schema::{AuthSettings, Config, HighlightOverlayAutoPolicy, HighlightOverlayMode, HighlightRule, InteractiveSettings, Metadata, Settings};
 #[cfg(test)]
 pub(crate) use watcher::queue_reload_notice;
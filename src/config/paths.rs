@@ -30,6 +30,26 @@ pub(crate) fn resolve_config_path(profile: Option<&str>) -> io::Result<PathBuf>
     create_default_config()
 }
 
+/// Profile names with a config file in one of the standard locations, plus `default`.
+pub(crate) fn available_profiles() -> Vec<String> {
+    let mut profiles = vec!["default".to_string()];
+    let config_dirs = config_candidates(DEFAULT_CONFIG_FILENAME).unwrap_or_default();
+    for config_dir in config_dirs.iter().filter_map(|candidate| candidate.parent()) {
+        let Ok(entries) = fs::read_dir(config_dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if let Some(profile_name) = entry.file_name().to_str().and_then(|name| name.strip_suffix(".cossh-config.yaml"))
+                && args::validate_profile_name(profile_name)
+                && !profiles.iter().any(|known| known == profile_name)
+            {
+                profiles.push(profile_name.to_string());
+            }
+        }
+    }
+    profiles
+}
+
 fn normalize_profile_name(profile: Option<&str>) -> io::Result<Option<String>> {
     match profile.map(str::trim) {
         Some("") | None => Ok(None),
@@ -75,6 +75,22 @@ pub struct AuthSettings {
     /// Whether TUI launches should attempt password auto-login.
     #[serde(default = "default_tui_password_autologin")]
     pub tui_password_autologin: bool,
+    /// Whether SSH launches should use the vault agent's key socket as `SSH_AUTH_SOCK`.
+    #[serde(default)]
+    pub ssh_agent: bool,
+    /// Lock the vault when the last session manager exits.
+    #[serde(default)]
+    pub lock_on_tui_exit: bool,
+    /// Lock the vault when the agent receives `SIGUSR1`, e.g. from a screen-lock hook.
+    #[serde(default = "default_lock_on_signal")]
+    pub lock_on_signal: bool,
+    /// Lock the vault when the machine resumes from suspend.
+    #[serde(default = "default_lock_on_resume")]
+    pub lock_on_resume: bool,
+    /// Lock the vault after this many untrusted clients, invalid askpass tokens or refused
+    /// forwarded requests in a row (`0` disables).
+    #[serde(default = "default_lock_after_failed_authorizations")]
+    pub lock_after_failed_authorizations: u32,
 }
 
 impl Default for AuthSettings {
@@ -84,6 +100,11 @@ impl Default for AuthSettings {
             session_timeout_seconds: default_session_timeout_seconds(),
             direct_password_autologin: default_direct_password_autologin(),
             tui_password_autologin: default_tui_password_autologin(),
+            ssh_agent: false,
+            lock_on_tui_exit: false,
+            lock_on_signal: default_lock_on_signal(),
+            lock_on_resume: default_lock_on_resume(),
+            lock_after_failed_authorizations: default_lock_after_failed_authorizations(),
         }
     }
 }
@@ -121,6 +142,10 @@ pub struct InteractiveSettings {
     /// Compatibility policy applied when `overlay_highlighting` is set to `auto`.
     #[serde(default)]
     pub overlay_auto_policy: HighlightOverlayAutoPolicy,
+    /// Sync `~/.ssh/config` with the inventory each time the inventory changes on disk.
+    /// Disabled by default.
+    #[serde(default = "default_inventory_ssh_sync")]
+    pub inventory_ssh_sync: bool,
 }
 
 /// Renderer-side syntax highlighting policy for embedded terminal views.
@@ -173,6 +198,18 @@ fn default_tui_password_autologin() -> bool {
     true
 }
 
+fn default_lock_on_signal() -> bool {
+    true
+}
+
+fn default_lock_on_resume() -> bool {
+    true
+}
+
+fn default_lock_after_failed_authorizations() -> u32 {
+    5
+}
+
 fn default_host_tree_uncollapsed() -> bool {
     false
 }
@@ -197,6 +234,10 @@ fn default_remote_clipboard_max_bytes() -> usize {
     4096
 }
 
+fn default_inventory_ssh_sync() -> bool {
+    false
+}
+
 fn deserialize_host_view_size<'de, D>(deserializer: D) -> Result<u16, D::Error>
 where
     D: Deserializer<'de>,
@@ -0,0 +1,866 @@
+//! Ansible inventory import for `cossh inventory import --from ansible`.
+//!
+//! Reads INI and YAML inventories together with the `group_vars/` and
+//! `host_vars/` files and directories next to them. Groups become folders, the connection
+//! variables map onto host fields and every other variable is reported back
+//! as skipped. Output goes through the same renderer as `--migrate`. Without
+//! an explicit output file the result is written to its own include file next
+//! to the main inventory, which only gains an `include:` entry.
+
+use super::edit::add_inventory_include;
+use super::migration::{RenderState, backup_existing_inventory, render_inventory_document};
+use super::path::get_default_inventory_path;
+use crate::log_warn;
+use crate::ssh_config::{SshHost, TreeFolder, apply_host_option};
+use serde_yml::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const ALL_GROUP: &str = "all";
+const UNGROUPED_GROUP: &str = "ungrouped";
+/// Most hosts one range pattern such as `web[01:50]` may expand to.
+const MAX_PATTERN_HOSTS: usize = 10_000;
+
+/// Connection variables in order of preference; later names are legacy aliases.
+const HOST_VARS: [&str; 2] = ["ansible_host", "ansible_ssh_host"];
+const USER_VARS: [&str; 2] = ["ansible_user", "ansible_ssh_user"];
+const PORT_VARS: [&str; 2] = ["ansible_port", "ansible_ssh_port"];
+const KEY_FILE_VARS: [&str; 2] = ["ansible_ssh_private_key_file", "ansible_private_key_file"];
+const SSH_ARGS_VARS: [&str; 2] = ["ansible_ssh_common_args", "ansible_ssh_extra_args"];
+
+#[derive(Debug, Clone)]
+pub(crate) struct AnsibleImportSummary {
+    /// Inventory output file path.
+    pub(crate) output_path: PathBuf,
+    /// Backup path when an existing inventory was replaced.
+    pub(crate) backup_path: Option<PathBuf>,
+    /// Main inventory that gained an `include:` entry for the output file.
+    pub(crate) included_from: Option<PathBuf>,
+    /// Number of hosts written to output.
+    pub(crate) hosts_written: usize,
+    /// Variables without an inventory equivalent, with the number of hosts they applied to.
+    pub(crate) skipped_variables: BTreeMap<String, usize>,
+}
+
+/// Import the Ansible inventory at `source_path` into `output_path`, or into
+/// a new include file of the default inventory when none is given.
+pub(crate) fn import_ansible_inventory(source_path: &Path, output_path: Option<&Path>) -> io::Result<AnsibleImportSummary> {
+    match output_path {
+        Some(path) => write_ansible_import(source_path, path),
+        None => {
+            let main_inventory =
+                get_default_inventory_path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Could not find home directory for ~/.color-ssh"))?;
+            import_ansible_inventory_as_include(source_path, &main_inventory)
+        }
+    }
+}
+
+/// Write the import to `ansible-<source name>.yaml` next to `main_inventory`
+/// and add that file to its `include:` list.
+fn import_ansible_inventory_as_include(source_path: &Path, main_inventory: &Path) -> io::Result<AnsibleImportSummary> {
+    let source_name = source_path
+        .file_stem()
+        .or_else(|| source_path.file_name())
+        .and_then(|segment| segment.to_str())
+        .unwrap_or("inventory");
+    let file_name = format!("ansible-{source_name}.yaml");
+    let output_path = main_inventory.with_file_name(&file_name);
+
+    let mut summary = write_ansible_import(source_path, &output_path)?;
+    if add_inventory_include(main_inventory, &format!("./{file_name}"))? {
+        summary.included_from = Some(main_inventory.to_path_buf());
+    }
+    Ok(summary)
+}
+
+fn write_ansible_import(source_path: &Path, inventory_path: &Path) -> io::Result<AnsibleImportSummary> {
+    let inventory = load_ansible_inventory(source_path)?;
+    let converted = convert_ansible_inventory(&inventory);
+    let mut render_state = RenderState::default();
+    let rendered = render_inventory_document(&converted.root, &converted.hosts, &mut render_state)?;
+
+    let backup_path = backup_existing_inventory(inventory_path)?;
+    if let Some(parent) = inventory_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(inventory_path, rendered)?;
+
+    Ok(AnsibleImportSummary {
+        output_path: inventory_path.to_path_buf(),
+        backup_path,
+        included_from: None,
+        hosts_written: render_state.hosts_written,
+        skipped_variables: converted.skipped_variables,
+    })
+}
+
+/// Variables by name; `None` marks a value that is not a scalar.
+type AnsibleVars = BTreeMap<String, Option<String>>;
+
+#[derive(Debug, Default)]
+struct AnsibleGroup {
+    hosts: Vec<String>,
+    children: Vec<String>,
+    vars: AnsibleVars,
+}
+
+#[derive(Debug, Default)]
+struct AnsibleInventory {
+    /// Group names in order of first appearance.
+    group_order: Vec<String>,
+    groups: HashMap<String, AnsibleGroup>,
+    /// Host names in order of first appearance.
+    host_order: Vec<String>,
+    host_vars: HashMap<String, AnsibleVars>,
+}
+
+impl AnsibleInventory {
+    fn group_mut(&mut self, name: &str) -> &mut AnsibleGroup {
+        if !self.groups.contains_key(name) {
+            self.group_order.push(name.to_string());
+        }
+        self.groups.entry(name.to_string()).or_default()
+    }
+
+    fn add_host(&mut self, group: &str, host: &str, vars: &AnsibleVars) {
+        if !self.host_vars.contains_key(host) {
+            self.host_order.push(host.to_string());
+        }
+        self.host_vars.entry(host.to_string()).or_default().extend(vars.clone());
+        let group = self.group_mut(group);
+        if !group.hosts.iter().any(|existing| existing == host) {
+            group.hosts.push(host.to_string());
+        }
+    }
+
+    fn add_child(&mut self, parent: &str, child: &str) {
+        self.group_mut(child);
+        let parent = self.group_mut(parent);
+        if !parent.children.iter().any(|existing| existing == child) {
+            parent.children.push(child.to_string());
+        }
+    }
+}
+
+fn invalid_data(path: &Path, message: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {message}", path.display()))
+}
+
+fn load_ansible_inventory(path: &Path) -> io::Result<AnsibleInventory> {
+    let contents = fs::read_to_string(path)?;
+    let is_yaml = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| ["yml", "yaml", "json"].iter().any(|yaml| extension.eq_ignore_ascii_case(yaml)));
+    let mut inventory = if is_yaml {
+        parse_yaml_inventory(&contents)
+    } else {
+        parse_ini_inventory(&contents)
+    }
+    .map_err(|err| invalid_data(path, err))?;
+
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    let mut group_names = inventory.group_order.clone();
+    if !inventory.groups.contains_key(ALL_GROUP) {
+        group_names.insert(0, ALL_GROUP.to_string());
+    }
+    for group in group_names {
+        if let Some(vars) = read_vars_file(&base_dir.join("group_vars"), &group)? {
+            inventory.group_mut(&group).vars.extend(vars);
+        }
+    }
+    for host in inventory.host_order.clone() {
+        if let Some(vars) = read_vars_file(&base_dir.join("host_vars"), &host)? {
+            inventory.host_vars.entry(host).or_default().extend(vars);
+        }
+    }
+    Ok(inventory)
+}
+
+/// Read `group_vars/<name>` or `host_vars/<name>`, with or without a YAML
+/// extension, or every file of a `<name>/` directory in name order.
+fn read_vars_file(dir: &Path, name: &str) -> io::Result<Option<AnsibleVars>> {
+    let vars_dir = dir.join(name);
+    if vars_dir.is_dir() {
+        let mut files = Vec::new();
+        collect_vars_dir_files(&vars_dir, &mut files)?;
+        let mut vars = AnsibleVars::new();
+        for path in files {
+            vars.extend(read_vars_path(&path)?.unwrap_or_default());
+        }
+        return Ok(Some(vars));
+    }
+
+    for file_name in [format!("{name}.yml"), format!("{name}.yaml"), name.to_string()] {
+        let path = dir.join(file_name);
+        if path.is_file() {
+            return read_vars_path(&path);
+        }
+    }
+    Ok(None)
+}
+
+/// Variables files under `dir` and its subdirectories, sorted by path as
+/// Ansible loads them. Hidden files and files with an extension other than
+/// YAML or JSON are skipped.
+fn collect_vars_dir_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        let hidden = path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_vars_dir_files(&path, files)?;
+            continue;
+        }
+        let is_vars_file = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_none_or(|extension| ["yml", "yaml", "json"].iter().any(|yaml| extension.eq_ignore_ascii_case(yaml)));
+        if is_vars_file {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read_vars_path(path: &Path) -> io::Result<Option<AnsibleVars>> {
+    let contents = fs::read_to_string(path)?;
+    if contents.starts_with("$ANSIBLE_VAULT") {
+        log_warn!("Skipping Ansible Vault encrypted variables file '{}'", path.display());
+        return Ok(None);
+    }
+    let value: Value = serde_yml::from_str(&contents).map_err(|err| invalid_data(path, err))?;
+    yaml_vars(&value).map(Some).map_err(|err| invalid_data(path, err))
+}
+
+enum IniSection {
+    Hosts(String),
+    Vars(String),
+    Children(String),
+}
+
+fn parse_ini_inventory(contents: &str) -> Result<AnsibleInventory, String> {
+    let mut inventory = AnsibleInventory::default();
+    let mut section = IniSection::Hosts(UNGROUPED_GROUP.to_string());
+
+    for (index, raw_line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            let header = header.trim();
+            section = match header.split_once(':') {
+                None => IniSection::Hosts(header.to_string()),
+                Some((group, "vars")) => IniSection::Vars(group.to_string()),
+                Some((group, "children")) => IniSection::Children(group.to_string()),
+                Some((_, kind)) => return Err(format!("line {line_number}: unknown section type ':{kind}'")),
+            };
+            let (IniSection::Hosts(group) | IniSection::Vars(group) | IniSection::Children(group)) = &section;
+            inventory.group_mut(group);
+            continue;
+        }
+
+        match &section {
+            IniSection::Hosts(group) => {
+                let words = split_shell_words(line).map_err(|err| format!("line {line_number}: {err}"))?;
+                let Some((pattern, assignments)) = words.split_first() else {
+                    continue;
+                };
+                let mut vars = AnsibleVars::new();
+                for assignment in assignments {
+                    let (key, value) = assignment
+                        .split_once('=')
+                        .ok_or_else(|| format!("line {line_number}: expected key=value, found '{assignment}'"))?;
+                    vars.insert(key.to_string(), Some(value.to_string()));
+                }
+                add_host_pattern(&mut inventory, group, pattern, vars).map_err(|err| format!("line {line_number}: {err}"))?;
+            }
+            IniSection::Vars(group) => {
+                let (key, value) = line
+                    .split_once('=')
+                    .ok_or_else(|| format!("line {line_number}: expected key=value in [{group}:vars]"))?;
+                inventory
+                    .group_mut(group)
+                    .vars
+                    .insert(key.trim().to_string(), Some(unquote(value.trim()).to_string()));
+            }
+            IniSection::Children(group) => inventory.add_child(group, line),
+        }
+    }
+
+    Ok(inventory)
+}
+
+fn parse_yaml_inventory(contents: &str) -> Result<AnsibleInventory, String> {
+    let document: Value = serde_yml::from_str(contents).map_err(|err| err.to_string())?;
+    let mut inventory = AnsibleInventory::default();
+    match document {
+        Value::Null => {}
+        Value::Mapping(groups) => {
+            for (name, group) in &groups {
+                parse_yaml_group(&mut inventory, name, group)?;
+            }
+        }
+        _ => return Err("expected a mapping of groups at the top level".to_string()),
+    }
+    Ok(inventory)
+}
+
+fn parse_yaml_group(inventory: &mut AnsibleInventory, name: &str, group: &Value) -> Result<(), String> {
+    inventory.group_mut(name);
+    let group = match group {
+        Value::Null => return Ok(()),
+        Value::Mapping(group) => group,
+        _ => return Err(format!("group '{name}' must be a mapping")),
+    };
+
+    for (key, value) in group {
+        match key.as_str() {
+            "hosts" => {
+                let hosts = match value {
+                    Value::Null => continue,
+                    Value::Mapping(hosts) => hosts,
+                    _ => return Err(format!("'hosts' of group '{name}' must be a mapping")),
+                };
+                for (pattern, vars) in hosts {
+                    let vars = yaml_vars(vars).map_err(|err| format!("host '{pattern}': {err}"))?;
+                    add_host_pattern(inventory, name, pattern, vars)?;
+                }
+            }
+            "vars" => {
+                let vars = yaml_vars(value).map_err(|err| format!("vars of group '{name}': {err}"))?;
+                inventory.group_mut(name).vars.extend(vars);
+            }
+            "children" => {
+                let children = match value {
+                    Value::Null => continue,
+                    Value::Mapping(children) => children,
+                    _ => return Err(format!("'children' of group '{name}' must be a mapping")),
+                };
+                for (child, child_group) in children {
+                    inventory.add_child(name, child);
+                    parse_yaml_group(inventory, child, child_group)?;
+                }
+            }
+            _ => {
+                return Err(format!("group '{name}' has unknown key '{key}'; expected hosts, vars or children"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn yaml_scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn yaml_vars(value: &Value) -> Result<AnsibleVars, String> {
+    match value {
+        Value::Null => Ok(AnsibleVars::new()),
+        Value::Mapping(vars) => Ok(vars.iter().map(|(key, value)| (key.clone(), yaml_scalar(value))).collect()),
+        _ => Err("variables must be a mapping".to_string()),
+    }
+}
+
+fn add_host_pattern(inventory: &mut AnsibleInventory, group: &str, pattern: &str, mut vars: AnsibleVars) -> Result<(), String> {
+    let (pattern, port) = split_host_port(pattern);
+    if let Some(port) = port {
+        vars.entry(PORT_VARS[0].to_string()).or_insert_with(|| Some(port.to_string()));
+    }
+    for host in expand_host_pattern(pattern)? {
+        inventory.add_host(group, &host, &vars);
+    }
+    Ok(())
+}
+
+/// Split a `host:port` pattern; IPv6 addresses and ranges keep their colons.
+fn split_host_port(pattern: &str) -> (&str, Option<&str>) {
+    let mut depth = 0usize;
+    let mut colons = Vec::new();
+    for (index, ch) in pattern.char_indices() {
+        match ch {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            ':' if depth == 0 => colons.push(index),
+            _ => {}
+        }
+    }
+    match colons.as_slice() {
+        [colon] if pattern[colon + 1..].chars().all(|ch| ch.is_ascii_digit()) && *colon + 1 < pattern.len() => {
+            (&pattern[..*colon], Some(&pattern[colon + 1..]))
+        }
+        _ => (pattern, None),
+    }
+}
+
+/// Expand Ansible host ranges such as `web[01:03]` or `db-[a:c]`, with an optional `:stride`.
+fn expand_host_pattern(pattern: &str) -> Result<Vec<String>, String> {
+    let Some(open) = pattern.find('[') else {
+        return Ok(vec![pattern.to_string()]);
+    };
+    let close = pattern[open..]
+        .find(']')
+        .map(|offset| open + offset)
+        .ok_or_else(|| format!("unterminated range in host pattern '{pattern}'"))?;
+    let (head, range, tail) = (&pattern[..open], &pattern[open + 1..close], &pattern[close + 1..]);
+
+    let bounds: Vec<&str> = range.split(':').collect();
+    let (start, end, stride) = match bounds.as_slice() {
+        [start, end] => (*start, *end, 1),
+        [start, end, stride] => (
+            *start,
+            *end,
+            stride
+                .parse::<usize>()
+                .ok()
+                .filter(|stride| *stride > 0)
+                .ok_or_else(|| format!("invalid range stride in host pattern '{pattern}'"))?,
+        ),
+        _ => return Err(format!("invalid range '[{range}]' in host pattern '{pattern}'")),
+    };
+
+    let too_many = || format!("range '[{range}]' in host pattern '{pattern}' expands to more than {MAX_PATTERN_HOSTS} hosts");
+    let values: Vec<String> = if let (Ok(first), Ok(last)) = (start.parse::<u64>(), end.parse::<u64>()) {
+        let count = if last < first { 0 } else { (last - first) / stride as u64 + 1 };
+        if count > MAX_PATTERN_HOSTS as u64 {
+            return Err(too_many());
+        }
+        // A leading zero pads every value to the width of the start bound.
+        let width = if start.len() > 1 && start.starts_with('0') { start.len() } else { 0 };
+        (first..=last).step_by(stride).map(|value| format!("{value:0width$}")).collect()
+    } else if let ([first], [last]) = (start.as_bytes(), end.as_bytes())
+        && first.is_ascii_alphabetic()
+        && last.is_ascii_alphabetic()
+    {
+        (*first..=*last).step_by(stride).map(|value| char::from(value).to_string()).collect()
+    } else {
+        return Err(format!("invalid range '[{range}]' in host pattern '{pattern}'"));
+    };
+    if values.is_empty() {
+        return Err(format!("range '[{range}]' in host pattern '{pattern}' is empty"));
+    }
+
+    let tails = expand_host_pattern(tail)?;
+    if values.len().saturating_mul(tails.len()) > MAX_PATTERN_HOSTS {
+        return Err(too_many());
+    }
+    Ok(values
+        .iter()
+        .flat_map(|value| tails.iter().map(move |tail| format!("{head}{value}{tail}")))
+        .collect())
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(quote).and_then(|rest| rest.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    value
+}
+
+/// Split `line` into words like the shell-style lexer Ansible uses for host lines.
+fn split_shell_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = line.chars();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(ch) => word.push(ch),
+                        None => return Err("unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(ch @ ('"' | '\\')) => word.push(ch),
+                            Some(ch) => {
+                                word.push('\\');
+                                word.push(ch);
+                            }
+                            None => return Err("unterminated double quote".to_string()),
+                        },
+                        Some(ch) => word.push(ch),
+                        None => return Err("unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(ch) = chars.next() {
+                    word.push(ch);
+                }
+            }
+            '#' if !in_word => break,
+            ch if ch.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            ch => {
+                in_word = true;
+                word.push(ch);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+struct ConvertedInventory {
+    root: TreeFolder,
+    hosts: Vec<SshHost>,
+    skipped_variables: BTreeMap<String, usize>,
+}
+
+/// Folder layout of Ansible groups, shared by `inventory import --from ansible`
+/// and Ansible inventory sources.
+///
+/// A group nests under the first group that lists it as a child, and a host
+/// lands in the first group, depth first, that lists it directly. Groups
+/// only under `all` are top-level folders; hosts only in `all` or
+/// `ungrouped`, or in no placed group, stay at the root.
+pub(super) struct AnsibleLayout<'a> {
+    /// Top-level group folders.
+    pub(super) folders: Vec<AnsibleFolder<'a>>,
+    /// Hosts placed at the root.
+    pub(super) hosts: Vec<&'a str>,
+    /// Groups that list each group as a child, in group order.
+    pub(super) parents: HashMap<&'a str, Vec<&'a str>>,
+}
+
+pub(super) struct AnsibleFolder<'a> {
+    pub(super) group: &'a str,
+    pub(super) hosts: Vec<&'a str>,
+    pub(super) children: Vec<AnsibleFolder<'a>>,
+}
+
+/// Hosts and child groups of one Ansible group.
+pub(super) struct AnsibleGroupMembers<'a> {
+    pub(super) name: &'a str,
+    pub(super) hosts: &'a [String],
+    pub(super) children: &'a [String],
+}
+
+/// Lay out `groups`, given in order of appearance, as folders. `host_order`
+/// lists every host in order of appearance and decides the order of root hosts.
+pub(super) fn layout_ansible_groups<'a>(groups: &[AnsibleGroupMembers<'a>], host_order: impl IntoIterator<Item = &'a str>) -> AnsibleLayout<'a> {
+    let mut layout = AnsibleLayoutBuilder {
+        groups: groups.iter().map(|group| (group.name, group)).collect(),
+        parents: HashMap::new(),
+        placed_groups: HashSet::new(),
+        placed_hosts: HashSet::new(),
+    };
+    for group in groups {
+        for child in group.children {
+            layout.parents.entry(child.as_str()).or_default().push(group.name);
+        }
+    }
+
+    let mut folders = Vec::new();
+    for group in groups {
+        if group.name == ALL_GROUP || group.name == UNGROUPED_GROUP {
+            continue;
+        }
+        let top_level = layout
+            .parents
+            .get(group.name)
+            .is_none_or(|parents| parents.iter().all(|parent| *parent == ALL_GROUP));
+        if top_level && let Some(folder) = layout.folder(group.name) {
+            folders.push(folder);
+        }
+    }
+    let hosts = host_order.into_iter().filter(|host| layout.placed_hosts.insert(host)).collect();
+
+    AnsibleLayout {
+        folders,
+        hosts,
+        parents: layout.parents,
+    }
+}
+
+struct AnsibleLayoutBuilder<'a, 'g> {
+    groups: HashMap<&'a str, &'g AnsibleGroupMembers<'a>>,
+    parents: HashMap<&'a str, Vec<&'a str>>,
+    placed_groups: HashSet<&'a str>,
+    placed_hosts: HashSet<&'a str>,
+}
+
+impl<'a> AnsibleLayoutBuilder<'a, '_> {
+    fn folder(&mut self, name: &'a str) -> Option<AnsibleFolder<'a>> {
+        let group = *self.groups.get(name)?;
+        if !self.placed_groups.insert(name) {
+            return None;
+        }
+
+        let hosts = group.hosts.iter().map(String::as_str).filter(|host| self.placed_hosts.insert(host)).collect();
+        let nested: Vec<&'a str> = group
+            .children
+            .iter()
+            .map(String::as_str)
+            .filter(|child| self.parents.get(child).and_then(|parents| parents.first()) == Some(&name))
+            .collect();
+        let children = nested.into_iter().filter_map(|child| self.folder(child)).collect();
+
+        Some(AnsibleFolder { group: name, hosts, children })
+    }
+}
+
+/// Arrange hosts into folders and resolve each host's variables.
+fn convert_ansible_inventory(inventory: &AnsibleInventory) -> ConvertedInventory {
+    let groups: Vec<AnsibleGroupMembers> = inventory
+        .group_order
+        .iter()
+        .map(|name| {
+            let group = &inventory.groups[name];
+            AnsibleGroupMembers {
+                name,
+                hosts: &group.hosts,
+                children: &group.children,
+            }
+        })
+        .collect();
+    let layout = layout_ansible_groups(&groups, inventory.host_order.iter().map(String::as_str));
+    let mut converter = AnsibleConverter {
+        inventory,
+        parents: layout.parents,
+        depths: HashMap::new(),
+        hosts: Vec::new(),
+        skipped_variables: BTreeMap::new(),
+        next_id: 1,
+    };
+
+    let mut root = TreeFolder {
+        id: 0,
+        name: ALL_GROUP.to_string(),
+        path: PathBuf::new(),
+        children: Vec::new(),
+        host_indices: Vec::new(),
+    };
+    for folder in &layout.folders {
+        let folder = converter.group_folder(folder);
+        root.children.push(folder);
+    }
+    for host in layout.hosts {
+        let index = converter.add_host(host);
+        root.host_indices.push(index);
+    }
+
+    ConvertedInventory {
+        root,
+        hosts: converter.hosts,
+        skipped_variables: converter.skipped_variables,
+    }
+}
+
+struct AnsibleConverter<'a> {
+    inventory: &'a AnsibleInventory,
+    parents: HashMap<&'a str, Vec<&'a str>>,
+    depths: HashMap<&'a str, usize>,
+    hosts: Vec<SshHost>,
+    skipped_variables: BTreeMap<String, usize>,
+    next_id: usize,
+}
+
+impl<'a> AnsibleConverter<'a> {
+    fn group_folder(&mut self, folder: &AnsibleFolder<'a>) -> TreeFolder {
+        let id = self.next_id;
+        self.next_id += 1;
+        let host_indices = folder.hosts.iter().map(|host| self.add_host(host)).collect();
+        let children = folder.children.iter().map(|child| self.group_folder(child)).collect();
+
+        TreeFolder {
+            id,
+            name: folder.group.to_string(),
+            // Groups have no file of their own, so the folder keeps its group name.
+            path: PathBuf::new(),
+            children,
+            host_indices,
+        }
+    }
+
+    fn add_host(&mut self, name: &str) -> usize {
+        let vars = self.host_vars(name);
+        let host = ansible_host(name, &vars, &mut self.skipped_variables);
+        self.hosts.push(host);
+        self.hosts.len() - 1
+    }
+
+    /// Variables of `host`: `all`, then its groups from shallow to deep and by name, then its own.
+    fn host_vars(&mut self, host: &str) -> AnsibleVars {
+        let mut groups: HashSet<&'a str> = HashSet::new();
+        let mut pending: Vec<&'a str> = self
+            .inventory
+            .group_order
+            .iter()
+            .filter(|group| self.inventory.groups[group.as_str()].hosts.iter().any(|member| member == host))
+            .map(String::as_str)
+            .collect();
+        while let Some(group) = pending.pop() {
+            if groups.insert(group) {
+                pending.extend(self.parents.get(group).into_iter().flatten().copied());
+            }
+        }
+
+        let mut ordered: Vec<(usize, &'a str)> = groups
+            .into_iter()
+            .filter(|group| *group != ALL_GROUP)
+            .map(|group| (self.group_depth(group, &mut HashSet::new()), group))
+            .collect();
+        ordered.sort_unstable();
+
+        let mut vars = self.inventory.groups.get(ALL_GROUP).map(|all| all.vars.clone()).unwrap_or_default();
+        for (_, group) in ordered {
+            vars.extend(self.inventory.groups[group].vars.clone());
+        }
+        if let Some(host_vars) = self.inventory.host_vars.get(host) {
+            vars.extend(host_vars.clone());
+        }
+        vars
+    }
+
+    fn group_depth(&mut self, group: &'a str, visiting: &mut HashSet<&'a str>) -> usize {
+        if let Some(depth) = self.depths.get(group) {
+            return *depth;
+        }
+        if !visiting.insert(group) {
+            return 0;
+        }
+        let parents = self.parents.get(group).cloned().unwrap_or_default();
+        let depth = parents
+            .into_iter()
+            .filter(|parent| *parent != ALL_GROUP)
+            .map(|parent| self.group_depth(parent, visiting) + 1)
+            .max()
+            .unwrap_or(1);
+        self.depths.insert(group, depth);
+        depth
+    }
+}
+
+fn first_var<'v>(vars: &'v AnsibleVars, names: &[&str]) -> Option<&'v str> {
+    names.iter().find_map(|name| vars.get(*name).and_then(Option::as_deref))
+}
+
+fn ansible_host(name: &str, vars: &AnsibleVars, skipped_variables: &mut BTreeMap<String, usize>) -> SshHost {
+    let mut host = SshHost::new(name.to_string());
+
+    // Extra ssh arguments go first so the dedicated connection variables win.
+    for args_var in SSH_ARGS_VARS {
+        if let Some(args) = vars.get(args_var).and_then(Option::as_deref) {
+            for unsupported in apply_ssh_args(&mut host, args) {
+                *skipped_variables.entry(format!("{args_var} ({unsupported})")).or_default() += 1;
+            }
+        }
+    }
+    if let Some(hostname) = first_var(vars, &HOST_VARS) {
+        host.hostname = Some(hostname.to_string());
+    }
+    if let Some(user) = first_var(vars, &USER_VARS) {
+        host.user = Some(user.to_string());
+    }
+    let port = first_var(vars, &PORT_VARS).and_then(|port| port.parse::<u16>().ok());
+    if port.is_some() {
+        host.port = port;
+    }
+    if let Some(key_file) = first_var(vars, &KEY_FILE_VARS) {
+        host.identity_files = vec![key_file.to_string()];
+    }
+
+    for (key, value) in vars {
+        let mapped = if PORT_VARS.contains(&key.as_str()) {
+            port.is_some()
+        } else {
+            value.is_some()
+                && [HOST_VARS, USER_VARS, KEY_FILE_VARS, SSH_ARGS_VARS]
+                    .iter()
+                    .any(|names| names.contains(&key.as_str()))
+        };
+        if !mapped {
+            *skipped_variables.entry(key.clone()).or_default() += 1;
+        }
+    }
+    host
+}
+
+/// Apply `ssh` command-line options to `host`, returning the ones without an inventory equivalent.
+fn apply_ssh_args(host: &mut SshHost, args: &str) -> Vec<String> {
+    let words = match split_shell_words(args) {
+        Ok(words) => words,
+        Err(_) => return vec![args.to_string()],
+    };
+    let mut unsupported = Vec::new();
+    let mut words = words.into_iter();
+
+    while let Some(word) = words.next() {
+        let keyword = match word.strip_prefix('-').and_then(|flag| flag.chars().next()) {
+            Some('o') => None,
+            Some('J') => Some("proxyjump"),
+            Some('i') => Some("identityfile"),
+            Some('p') => Some("port"),
+            Some('l') => Some("user"),
+            Some('L') => Some("localforward"),
+            Some('R') => Some("remoteforward"),
+            Some('A') if word == "-A" => {
+                apply_host_option(host, "forwardagent", "yes");
+                continue;
+            }
+            _ => {
+                unsupported.push(word);
+                continue;
+            }
+        };
+        let value = match &word[2..] {
+            "" => match words.next() {
+                Some(value) => value,
+                None => {
+                    unsupported.push(word);
+                    continue;
+                }
+            },
+            attached => attached.to_string(),
+        };
+
+        match keyword {
+            Some(keyword) => apply_host_option(host, keyword, &value),
+            None => match value.split_once(|ch: char| ch == '=' || ch.is_whitespace()) {
+                Some((option, option_value)) => apply_host_option(host, &option.to_ascii_lowercase(), option_value.trim()),
+                None => unsupported.push(format!("-o {value}")),
+            },
+        }
+    }
+    unsupported
+}
+
+#[cfg(test)]
+#[path = "../test/inventory/ansible.rs"]
+mod tests;
@@ -0,0 +1,269 @@
+//! Inventory linting for `cossh inventory check`.
+//!
+//! Problems the loader rejects end the check with a single error; everything
+//! else is collected so one run reports every host that needs attention.
+
+use super::include::matches_pattern;
+use super::model::{InventoryHost, InventoryTreeModel};
+use super::normalize_ssh_forward_spec;
+use super::tree::build_inventory_tree_with_report;
+use crate::auth::provider::SecretReference;
+use crate::ssh_config::is_known_ssh_option;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum CheckSeverity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for CheckSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// One problem found by [`check_inventory`].
+pub(crate) struct InventoryDiagnostic {
+    pub(crate) severity: CheckSeverity,
+    pub(crate) source_file: PathBuf,
+    /// Host the problem belongs to; `None` for file-level problems.
+    pub(crate) host: Option<String>,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for InventoryDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: ", self.severity, self.source_file.display())?;
+        if let Some(host) = &self.host {
+            write!(f, "host '{host}': ")?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// What host references are checked against.
+pub(crate) struct InventoryCheckContext {
+    /// Config profile names that exist.
+    pub(crate) profiles: Vec<String>,
+    /// Password vault entry names; `None` when there is no vault to check against.
+    pub(crate) vault_entries: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct InventoryCheckReport {
+    pub(crate) hosts_checked: usize,
+    pub(crate) diagnostics: Vec<InventoryDiagnostic>,
+}
+
+impl InventoryCheckReport {
+    pub(crate) fn count(&self, severity: CheckSeverity) -> usize {
+        self.diagnostics.iter().filter(|diagnostic| diagnostic.severity == severity).count()
+    }
+}
+
+/// Load `inventory_path` and everything it includes, and report problems.
+pub(crate) fn check_inventory(inventory_path: &Path, context: &InventoryCheckContext) -> InventoryCheckReport {
+    let mut report = InventoryCheckReport::default();
+    if !inventory_path.exists() {
+        return report;
+    }
+
+    let (tree, load_report) = match build_inventory_tree_with_report(inventory_path) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            report.diagnostics.push(InventoryDiagnostic {
+                severity: CheckSeverity::Error,
+                source_file: err.source_file().to_path_buf(),
+                host: None,
+                message: err.message().to_string(),
+            });
+            return report;
+        }
+    };
+
+    let mut push = |severity, source_file: &Path, host: Option<&str>, message: String| {
+        report.diagnostics.push(InventoryDiagnostic {
+            severity,
+            source_file: source_file.to_path_buf(),
+            host: host.map(str::to_string),
+            message,
+        });
+    };
+
+    for (pattern_file, pattern) in &load_report.empty_includes {
+        push(CheckSeverity::Error, pattern_file, None, format!("include '{pattern}' does not match any file"));
+    }
+    for (name, first_file, repeated_file) in &load_report.duplicate_hosts {
+        push(
+            CheckSeverity::Error,
+            repeated_file,
+            Some(name),
+            format!("duplicate host name; first defined in '{}'", first_file.display()),
+        );
+    }
+
+    let vault_references = tree.hosts.iter().any(|host| !host_vault_entries(host).is_empty());
+    if vault_references && context.vault_entries.is_none() {
+        push(
+            CheckSeverity::Warning,
+            &tree.root.path,
+            None,
+            "password vault is not initialized; vault_pass and vault_key entries were not checked".to_string(),
+        );
+    }
+
+    for host in &tree.hosts {
+        for (problem_severity, message) in check_host(host, context) {
+            push(problem_severity, &host.source_file, Some(&host.name), message);
+        }
+    }
+
+    report.hosts_checked = tree.hosts.len();
+    sort_diagnostics(&tree, &mut report.diagnostics);
+    report
+}
+
+fn check_host(host: &InventoryHost, context: &InventoryCheckContext) -> Vec<(CheckSeverity, String)> {
+    let mut problems = Vec::new();
+
+    if let Some(profile) = host.profile.as_deref().map(str::trim)
+        && !context.profiles.iter().any(|known| known == profile)
+    {
+        problems.push((
+            CheckSeverity::Error,
+            format!("profile '{profile}' has no {profile}.cossh-config.yaml config file"),
+        ));
+    }
+
+    if let Some(vault_entries) = &context.vault_entries {
+        for (field, entry) in host_vault_entries(host) {
+            if !vault_entries.iter().any(|known| known == entry) {
+                problems.push((
+                    CheckSeverity::Error,
+                    format!("{field} '{entry}' is not in the password vault; add it with `cossh vault add {entry}`"),
+                ));
+            }
+        }
+    }
+
+    for (field, specs, remote) in [
+        ("local_forward", &host.ssh.local_forward, false),
+        ("remote_forward", &host.ssh.remote_forward, true),
+    ] {
+        for spec in specs {
+            if let Some(problem) = forward_spec_problem(&normalize_ssh_forward_spec(spec), remote) {
+                problems.push((CheckSeverity::Error, format!("{field} '{spec}' {problem}")));
+            }
+        }
+    }
+
+    let ignored: Vec<&str> = host
+        .ssh
+        .extra_options
+        .iter()
+        .filter(|(option, _)| option.eq_ignore_ascii_case("IgnoreUnknown"))
+        .flat_map(|(_, values)| values.iter().flat_map(|value| value.split(',')).map(str::trim))
+        .collect();
+    for option in host.ssh.extra_options.keys() {
+        let lowercase = option.to_ascii_lowercase();
+        if !is_known_ssh_option(option) && !ignored.iter().any(|pattern| matches_pattern(&lowercase, &pattern.to_ascii_lowercase())) {
+            problems.push((
+                CheckSeverity::Warning,
+                format!("ssh_options key '{option}' is not an OpenSSH client option and will be rejected by ssh"),
+            ));
+        }
+    }
+
+    problems
+}
+
+/// `vault_pass`/`vault_key` values that name entries of the local vault.
+///
+/// Provider references are skipped; values that are neither fail to load and
+/// are already reported as load errors.
+fn host_vault_entries(host: &InventoryHost) -> Vec<(&'static str, &str)> {
+    let vault_pass = host
+        .vault_pass
+        .as_deref()
+        .filter(|value| matches!(SecretReference::parse(value), Some(SecretReference::VaultEntry(_))));
+    [("vault_pass", vault_pass), ("vault_key", host.ssh.vault_key.as_deref())]
+        .into_iter()
+        .filter_map(|(field, value)| value.map(|value| (field, value.trim())))
+        .collect()
+}
+
+/// Explain why a normalized `LocalForward`/`RemoteForward` spec is invalid.
+///
+/// Accepts `[bind:]port:host:hostport`, Unix socket paths, and for remote
+/// forwards the `[bind:]port` dynamic form.
+fn forward_spec_problem(spec: &str, remote: bool) -> Option<String> {
+    if spec.contains('/') {
+        return None;
+    }
+
+    let parts = split_forward_spec(spec);
+    let listen_index = match parts.len() {
+        3 | 4 => parts.len() - 3,
+        1 | 2 if remote => parts.len() - 1,
+        _ => return Some("is not in [bind_address:]port:host:hostport form".to_string()),
+    };
+
+    let listen_port = parts[listen_index];
+    let min_listen_port = if remote { 0 } else { 1 };
+    if !listen_port.parse::<u16>().is_ok_and(|port| port >= min_listen_port) {
+        return Some(format!("has invalid listen port '{listen_port}'"));
+    }
+    if parts.len() >= 3 {
+        let (destination, destination_port) = (parts[parts.len() - 2], parts[parts.len() - 1]);
+        if destination.is_empty() {
+            return Some("has an empty destination host".to_string());
+        }
+        if !destination_port.parse::<u16>().is_ok_and(|port| port > 0) {
+            return Some(format!("has invalid destination port '{destination_port}'"));
+        }
+    }
+    None
+}
+
+/// Split on `:` outside `[...]`, dropping the brackets around IPv6 addresses.
+fn split_forward_spec(spec: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut in_brackets = false;
+    for (index, ch) in spec.char_indices() {
+        match ch {
+            '[' => in_brackets = true,
+            ']' => in_brackets = false,
+            ':' if !in_brackets => {
+                parts.push(&spec[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&spec[start..]);
+    parts.into_iter().map(|part| part.trim_start_matches('[').trim_end_matches(']')).collect()
+}
+
+/// Errors first, then grouped by file with hosts in inventory order.
+fn sort_diagnostics(tree: &InventoryTreeModel, diagnostics: &mut [InventoryDiagnostic]) {
+    let host_order = |diagnostic: &InventoryDiagnostic| diagnostic.host.as_ref().and_then(|name| tree.hosts.iter().position(|host| host.name == *name));
+    diagnostics.sort_by(|left, right| {
+        right
+            .severity
+            .cmp(&left.severity)
+            .then_with(|| left.source_file.cmp(&right.source_file))
+            .then_with(|| host_order(left).cmp(&host_order(right)))
+    });
+}
+
+#[cfg(test)]
+#[path = "../test/inventory/check.rs"]
+mod tests;
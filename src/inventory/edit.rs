@@ -1,6 +1,7 @@
-//! Inventory YAML mutation helpers for TUI host entry management.
+//! Inventory YAML mutation helpers for TUI and `cossh inventory` host entry management.
 
-use super::{ConnectionProtocol, SshOptionMap, normalize_ssh_forward_spec};
+use super::{ConnectionProtocol, InventoryTreeModel, SshOptionMap, TreeFolder, normalize_ssh_forward_spec};
+use crate::args::validate_secret_reference;
 use serde_yml::{Mapping, Value};
 use std::fs;
 use std::io;
@@ -44,6 +45,110 @@ pub(crate) fn update_inventory_host_entry(source_file: &Path, original_name: &st
     write_inventory_document(source_file, &document)
 }
 
+/// Set or remove individual keys of one host entry, leaving its other keys untouched.
+///
+/// Keys are canonical inventory keys (`user`, `proxy_jump`, ...), and
+/// `ssh_options.<Option>` addresses one entry of the host's `ssh_options`.
+/// A `None` value removes the key.
+pub(crate) fn set_inventory_host_fields(source_file: &Path, host_name: &str, fields: &[(String, Option<Value>)]) -> io::Result<()> {
+    let mut document = load_inventory_document(source_file)?;
+    let nodes = inventory_nodes_mut(&mut document, source_file)?;
+    let mapping = find_host_mapping_mut(nodes, host_name).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("inventory host '{}' was not found in '{}'", host_name, source_file.display()),
+        )
+    })?;
+
+    apply_host_fields(mapping, fields);
+    write_inventory_document(source_file, &document)
+}
+
+/// Append a host named `host_name` with `fields` (as for [`set_inventory_host_fields`])
+/// to `folder_path`, creating missing folders.
+pub(crate) fn create_inventory_host_from_fields(
+    source_file: &Path,
+    folder_path: &[String],
+    host_name: &str,
+    fields: &[(String, Option<Value>)],
+) -> io::Result<()> {
+    let mut document = load_inventory_document(source_file)?;
+    let nodes = inventory_nodes_mut(&mut document, source_file)?;
+    let mut mapping = Mapping::new();
+    mapping.insert("name".to_string(), Value::String(host_name.to_string()));
+    apply_host_fields(&mut mapping, fields);
+    ensure_folder_nodes(nodes, folder_path, source_file)?.push(Value::Mapping(mapping));
+    write_inventory_document(source_file, &document)
+}
+
+/// Turn command-line `key=value` pairs into fields for [`set_inventory_host_fields`].
+///
+/// Keys may use any spelling the inventory accepts (`proxy-jump`, `ProxyJump`).
+/// An empty value removes the key, and repeating a list key or an
+/// `ssh_options.<Option>` key collects its values.
+pub(crate) fn parse_host_field_assignments(assignments: &[(String, String)]) -> io::Result<Vec<(String, Option<Value>)>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let mut fields: Vec<(String, Option<Value>)> = Vec::new();
+
+    for (raw_key, raw_value) in assignments {
+        let key = match raw_key.split_once('.') {
+            Some((prefix, option)) if canonical_host_key(prefix) == "ssh_options" && !option.trim().is_empty() => {
+                format!("ssh_options.{}", option.trim())
+            }
+            _ => {
+                let key = canonical_host_key(raw_key.trim());
+                if !SETTABLE_HOST_KEYS.contains(&key) {
+                    return Err(invalid(format!("unknown inventory field '{raw_key}'")));
+                }
+                key.to_string()
+            }
+        };
+
+        let value = raw_value.trim();
+        if value.is_empty() {
+            if matches!(key.as_str(), "name" | "host") {
+                return Err(invalid(format!("'{key}' cannot be removed")));
+            }
+            fields.retain(|(existing, _)| *existing != key);
+            fields.push((key, None));
+            continue;
+        }
+
+        let value = match key.as_str() {
+            "port" => {
+                let port = value
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|port| *port > 0)
+                    .ok_or_else(|| invalid(format!("port must be a number from 1 to 65535, not '{value}'")))?;
+                Value::Number(port.into())
+            }
+            "hidden" | "identities_only" | "vault_forward" => match value.to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => Value::Bool(true),
+                "0" | "false" | "no" | "off" => Value::Bool(false),
+                _ => return Err(invalid(format!("{key} must be true or false, not '{value}'"))),
+            },
+            "vault_pass" if !validate_secret_reference(value) => {
+                return Err(invalid(format!("'{value}' is not a valid vault entry name or secret provider reference")));
+            }
+            "local_forward" | "remote_forward" => Value::String(normalize_ssh_forward_spec(value)),
+            _ => Value::String(value.to_string()),
+        };
+
+        let repeatable = key.starts_with("ssh_options.") || matches!(key.as_str(), "identity_file" | "local_forward" | "remote_forward" | "rdp_args");
+        match fields.iter_mut().find(|(existing, _)| *existing == key) {
+            Some((_, Some(existing))) if repeatable => match existing {
+                Value::Sequence(values) => values.push(value),
+                single => *single = Value::Sequence(vec![single.clone(), value]),
+            },
+            Some((_, existing)) => *existing = Some(value),
+            None => fields.push((key, Some(value))),
+        }
+    }
+
+    Ok(fields)
+}
+
 pub(crate) fn delete_inventory_host_entry(source_file: &Path, host_name: &str) -> io::Result<()> {
     let mut document = load_inventory_document(source_file)?;
     let nodes = inventory_nodes_mut(&mut document, source_file)?;
@@ -66,6 +171,16 @@ pub(crate) fn create_inventory_host_entry(source_file: &Path, folder_path: &[Str
     write_inventory_document(source_file, &document)
 }
 
+/// Append each host to its folder path in a single write, creating missing folders.
+pub(crate) fn create_inventory_host_entries(source_file: &Path, entries: &[(Vec<String>, EditableInventoryHost)]) -> io::Result<()> {
+    let mut document = load_inventory_document(source_file)?;
+    let nodes = inventory_nodes_mut(&mut document, source_file)?;
+    for (folder_path, host) in entries {
+        ensure_folder_nodes(nodes, folder_path, source_file)?.push(Value::Mapping(editable_host_mapping(host)));
+    }
+    write_inventory_document(source_file, &document)
+}
+
 pub(crate) fn create_inventory_folder(source_file: &Path, parent_folder_path: &[String], folder_name: &str) -> io::Result<()> {
     let sanitized_name = folder_name.trim();
     if sanitized_name.is_empty() {
@@ -131,6 +246,31 @@ pub(crate) fn move_inventory_host_entry(source_file: &Path, host_name: &str, tar
     write_inventory_document(source_file, &document)
 }
 
+/// Move a host entry from `source_file` into `target_folder_path` of another inventory file.
+///
+/// The entry is written to `target_file` before it is removed from `source_file`,
+/// so a failed write never loses the host.
+pub(crate) fn transfer_inventory_host_entry(source_file: &Path, host_name: &str, target_file: &Path, target_folder_path: &[String]) -> io::Result<()> {
+    if source_file == target_file {
+        return move_inventory_host_entry(source_file, host_name, target_folder_path);
+    }
+
+    let mut source_document = load_inventory_document(source_file)?;
+    let source_nodes = inventory_nodes_mut(&mut source_document, source_file)?;
+    let (entry, _source_path) = take_host_entry_in_nodes(source_nodes, host_name, &mut Vec::new()).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("inventory host '{}' was not found in '{}'", host_name, source_file.display()),
+        )
+    })?;
+
+    let mut target_document = load_inventory_document(target_file)?;
+    let target_nodes = inventory_nodes_mut(&mut target_document, target_file)?;
+    ensure_folder_nodes(target_nodes, target_folder_path, target_file)?.push(entry);
+    write_inventory_document(target_file, &target_document)?;
+    write_inventory_document(source_file, &source_document)
+}
+
 pub(crate) fn relocate_inventory_folder(source_file: &Path, folder_path: &[String], target_parent_path: &[String], new_name: &str) -> io::Result<()> {
     if folder_path.is_empty() {
         return Err(io::Error::new(io::ErrorKind::InvalidInput, "cannot rename the inventory root folder"));
@@ -254,6 +394,122 @@ pub(crate) fn delete_inventory_folder(source_file: &Path, folder_path: &[String]
     Ok(removed_host_count)
 }
 
+/// Point every `vault_pass`/`vault_key` value equal to `old_name` in one file at `new_name`.
+///
+/// Hosts, `defaults:` blocks (at the file root and as `- defaults:` items) and
+/// `templates:` are all rewritten. Returns the number of entries changed; the
+/// file is only rewritten when it is non-zero.
+pub(crate) fn rename_vault_references(source_file: &Path, old_name: &str, new_name: &str) -> io::Result<usize> {
+    let mut document = load_inventory_document(source_file)?;
+    let Value::Mapping(root) = &mut document else {
+        return Ok(0);
+    };
+
+    let mut changed = 0;
+    for (key, value) in root.iter_mut() {
+        match compact_key(key).as_str() {
+            "defaults" => {
+                if let Value::Mapping(defaults) = value {
+                    changed += usize::from(rename_vault_references_in_mapping(defaults, old_name, new_name));
+                }
+            }
+            "templates" => {
+                if let Value::Mapping(templates) = value {
+                    for template in templates.values_mut() {
+                        if let Value::Mapping(template) = template {
+                            changed += usize::from(rename_vault_references_in_mapping(template, old_name, new_name));
+                        }
+                    }
+                }
+            }
+            "inventory" => {
+                if let Value::Sequence(nodes) = value {
+                    changed += rename_vault_references_in_nodes(nodes, old_name, new_name);
+                }
+            }
+            _ => {}
+        }
+    }
+    if changed > 0 {
+        write_inventory_document(source_file, &document)?;
+    }
+    Ok(changed)
+}
+
+/// Rewrite vault references in every file of the inventory tree.
+///
+/// Every file is checked, since a host can inherit the reference from a
+/// template or `defaults:` block in an including file. Returns each rewritten
+/// file with its number of changed entries, in inventory order.
+pub(crate) fn rename_vault_references_in_inventory(tree: &InventoryTreeModel, old_name: &str, new_name: &str) -> io::Result<Vec<(PathBuf, usize)>> {
+    let mut source_files: Vec<&Path> = Vec::new();
+    collect_folder_source_files(&tree.root, &mut source_files);
+    for host in &tree.hosts {
+        if !source_files.contains(&host.source_file.as_path()) {
+            source_files.push(&host.source_file);
+        }
+    }
+
+    let mut touched = Vec::new();
+    for source_file in source_files {
+        let changed = rename_vault_references(source_file, old_name, new_name)?;
+        if changed > 0 {
+            touched.push((source_file.to_path_buf(), changed));
+        }
+    }
+    Ok(touched)
+}
+
+fn collect_folder_source_files<'a>(folder: &'a TreeFolder, source_files: &mut Vec<&'a Path>) {
+    if !source_files.contains(&folder.path.as_path()) {
+        source_files.push(&folder.path);
+    }
+    for child in &folder.children {
+        collect_folder_source_files(child, source_files);
+    }
+}
+
+/// Add `pattern` to the `include:` list of `source_file`, creating the file if needed.
+///
+/// Returns `false` when the file already includes exactly `pattern`.
+pub(crate) fn add_inventory_include(source_file: &Path, pattern: &str) -> io::Result<bool> {
+    let mut document = load_inventory_document(source_file)?;
+    let Value::Mapping(root) = &mut document else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("inventory file root must be a mapping: '{}'", source_file.display()),
+        ));
+    };
+
+    let include_key = root
+        .keys()
+        .find(|key| canonical_top_level_key(key) == "include")
+        .cloned()
+        .unwrap_or_else(|| "include".to_string());
+    let entry = Value::String(pattern.to_string());
+    match root.get_mut(&include_key) {
+        None | Some(Value::Null) => {
+            root.insert(include_key, Value::Sequence(vec![entry]));
+        }
+        Some(Value::Sequence(items)) => {
+            if items.contains(&entry) {
+                return Ok(false);
+            }
+            items.push(entry);
+        }
+        Some(existing) => {
+            if *existing == entry {
+                return Ok(false);
+            }
+            let previous = std::mem::replace(existing, Value::Null);
+            *existing = Value::Sequence(vec![previous, entry]);
+        }
+    }
+
+    write_inventory_document(source_file, &document)?;
+    Ok(true)
+}
+
 fn load_inventory_document(source_file: &Path) -> io::Result<Value> {
     if !source_file.exists() {
         return Ok(Value::Mapping(Mapping::new()));
@@ -372,6 +628,90 @@ fn update_host_entry_in_nodes(nodes: &mut Vec<Value>, original_name: &str, host:
     false
 }
 
+fn find_host_mapping_mut<'a>(nodes: &'a mut [Value], host_name: &str) -> Option<&'a mut Mapping> {
+    for node in nodes {
+        if host_mapping_mut(node).is_some_and(|mapping| host_name_matches(mapping, host_name)) {
+            return host_mapping_mut(node);
+        }
+        if let Some(children) = folder_items_mut(node)
+            && let Some(mapping) = find_host_mapping_mut(children, host_name)
+        {
+            return Some(mapping);
+        }
+    }
+
+    None
+}
+
+/// Host keys `parse_host_field_assignments` accepts besides `ssh_options.<Option>`.
+const SETTABLE_HOST_KEYS: [&str; 21] = [
+    "name",
+    "template",
+    "description",
+    "protocol",
+    "host",
+    "user",
+    "port",
+    "profile",
+    "vault_pass",
+    "vault_key",
+    "vault_forward",
+    "hidden",
+    "identity_file",
+    "identities_only",
+    "proxy_jump",
+    "proxy_command",
+    "forward_agent",
+    "local_forward",
+    "remote_forward",
+    "rdp_domain",
+    "rdp_args",
+];
+
+fn apply_host_fields(mapping: &mut Mapping, fields: &[(String, Option<Value>)]) {
+    for (key, value) in fields {
+        match key.strip_prefix("ssh_options.") {
+            Some(option) => set_host_ssh_option(mapping, option, value.clone()),
+            None => set_mapping_key(mapping, |existing| canonical_host_key(existing) == key, key, value.clone()),
+        }
+    }
+}
+
+/// Replace the first key accepted by `matches`, insert `key` when none is, or remove it for `None`.
+fn set_mapping_key(mapping: &mut Mapping, matches: impl Fn(&str) -> bool, key: &str, value: Option<Value>) {
+    let existing = mapping.keys().find(|existing| matches(existing)).cloned();
+    match (existing, value) {
+        (Some(existing), Some(value)) => {
+            mapping.insert(existing, value);
+        }
+        (None, Some(value)) => {
+            mapping.insert(key.to_string(), value);
+        }
+        (Some(existing), None) => {
+            let _ = mapping.remove(&existing);
+        }
+        (None, None) => {}
+    }
+}
+
+fn set_host_ssh_option(mapping: &mut Mapping, option: &str, value: Option<Value>) {
+    let options_key = mapping
+        .keys()
+        .find(|key| canonical_host_key(key) == "ssh_options")
+        .cloned()
+        .unwrap_or_else(|| "ssh_options".to_string());
+    if let Some(Value::Mapping(options)) = mapping.get_mut(&options_key) {
+        set_mapping_key(options, |existing| existing.eq_ignore_ascii_case(option), option, value);
+        if options.is_empty() {
+            let _ = mapping.remove(&options_key);
+        }
+    } else if let Some(value) = value {
+        let mut options = Mapping::new();
+        options.insert(option.to_string(), value);
+        mapping.insert(options_key, Value::Mapping(options));
+    }
+}
+
 fn delete_host_entry_in_nodes(nodes: &mut Vec<Value>, host_name: &str) -> bool {
     let mut index = 0usize;
 
@@ -573,7 +913,13 @@ fn editable_host_mapping(host: &EditableInventoryHost) -> Mapping {
 
     mapping.insert("name", Value::String(host.name.clone()));
     mapping.insert("protocol", Value::String(host.protocol.as_str().to_string()));
-    mapping.insert("host", Value::String(host.host.clone()));
+    // An empty host is left to the template or folder defaults.
+    if !host.host.trim().is_empty() {
+        // An empty host is left to the host's template or folder defaults.
+        if !host.host.trim().is_empty() {
+            mapping.insert("host", Value::String(host.host.clone()));
+        }
+    }
     mapping.insert("hidden", Value::Bool(host.hidden));
 
     if let Some(description) = trimmed_option(&host.description) {
@@ -670,6 +1016,36 @@ fn editable_host_mapping(host: &EditableInventoryHost) -> Mapping {
     mapping
 }
 
+fn rename_vault_references_in_nodes(nodes: &mut [Value], old_name: &str, new_name: &str) -> usize {
+    let mut changed = 0;
+    for node in nodes {
+        let Value::Mapping(mapping) = node else {
+            continue;
+        };
+        if is_host_mapping(mapping) {
+            changed += usize::from(rename_vault_references_in_mapping(mapping, old_name, new_name));
+        } else if is_defaults_mapping(mapping) {
+            if let Some(Value::Mapping(defaults)) = mapping.values_mut().next() {
+                changed += usize::from(rename_vault_references_in_mapping(defaults, old_name, new_name));
+            }
+        } else if let Some(items) = folder_items_mut(node) {
+            changed += rename_vault_references_in_nodes(items, old_name, new_name);
+        }
+    }
+    changed
+}
+
+fn rename_vault_references_in_mapping(mapping: &mut Mapping, old_name: &str, new_name: &str) -> bool {
+    let mut changed = false;
+    for (key, value) in mapping.iter_mut() {
+        if matches!(canonical_host_key(key), "vault_pass" | "vault_key") && scalar_value_to_string(value).is_some_and(|current| current.trim() == old_name) {
+            *value = Value::String(new_name.to_string());
+            changed = true;
+        }
+    }
+    changed
+}
+
 fn host_name_matches(mapping: &Mapping, target_name: &str) -> bool {
     mapping.iter().any(|(key, value)| {
         if canonical_host_key(key) != "name" {
@@ -691,11 +1067,21 @@ fn is_host_mapping(mapping: &Mapping) -> bool {
     mapping.iter().any(|(key, _)| canonical_host_key(key) == "name")
 }
 
+/// `- defaults: {...}` entries set folder defaults and are not folders; like
+/// the loader, `- defaults:` holding a list or nothing is a folder.
+fn is_defaults_mapping(mapping: &Mapping) -> bool {
+    mapping.len() == 1
+        && mapping
+            .iter()
+            .next()
+            .is_some_and(|(key, value)| compact_key(key) == "defaults" && matches!(value, Value::Mapping(_)))
+}
+
 fn folder_entry_name(value: &Value) -> Option<&str> {
     let Value::Mapping(mapping) = value else {
         return None;
     };
-    if is_host_mapping(mapping) || mapping.len() != 1 {
+    if is_host_mapping(mapping) || mapping.len() != 1 || is_defaults_mapping(mapping) {
         return None;
     }
 
@@ -770,6 +1156,7 @@ fn canonical_top_level_key(key: &str) -> &str {
 fn canonical_host_key(key: &str) -> &str {
     match compact_key(key).as_str() {
         "name" => "name",
+        "template" => "template",
         "description" => "description",
         "protocol" => "protocol",
         "host" | "hostname" => "host",
@@ -777,6 +1164,8 @@ fn canonical_host_key(key: &str) -> &str {
         "port" => "port",
         "profile" => "profile",
         "vaultpass" => "vault_pass",
+        "vaultkey" => "vault_key",
+        "vaultforward" => "vault_forward",
         "hidden" => "hidden",
         "identityfile" => "identity_file",
         "identitiesonly" => "identities_only",
@@ -22,6 +22,15 @@ impl InventoryError {
             message: message.into(),
         }
     }
+
+    /// Inventory file the error was found in.
+    pub(crate) fn source_file(&self) -> &Path {
+        &self.source_file
+    }
+
+    pub(crate) fn message(&self) -> &str {
+        &self.message
+    }
 }
 
 impl fmt::Display for InventoryError {
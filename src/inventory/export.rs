@@ -0,0 +1,506 @@
+//! Inventory export to OpenSSH config, Ansible, CSV and JSON for `cossh inventory export`.
+//!
+//! The OpenSSH renderer writes the `#_Desc`/`#_pass`/`#_Protocol` comment tags
+//! the SSH config parser reads, so an exported file migrates back unchanged.
+//! CSV and JSON use the column names an inventory `source:` file accepts.
+
+use super::migration::backup_existing_inventory;
+use super::model::{ConnectionProtocol, InventoryHost, InventoryTreeModel};
+use super::tree::{InventoryHostEntry, canonical_path, collect_tree_hosts};
+use serde_json::{Map, Value as JsonValue};
+use serde_yml::{Mapping, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Formats `cossh inventory export` writes.
+pub(crate) enum InventoryExportFormat {
+    /// OpenSSH client config for `ssh`, `scp`, `rsync` and friends.
+    SshConfig,
+    /// Ansible YAML inventory with folders as groups.
+    Ansible,
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct InventoryExport {
+    /// Main exported document.
+    pub(crate) document: String,
+    /// OpenSSH files pulled in by `Include` lines of the main document.
+    pub(crate) include_files: Vec<(PathBuf, String)>,
+    pub(crate) hosts_exported: usize,
+    /// Hosts left out, with the reason.
+    pub(crate) skipped: Vec<(String, String)>,
+}
+
+/// Render every host of `tree` in `format`.
+///
+/// With `include_dir`, OpenSSH hosts loaded from included inventory files are
+/// written to one `<file stem>.conf` per inventory file in that directory and
+/// pulled into the main document with `Include`.
+pub(crate) fn render_inventory_export(tree: &InventoryTreeModel, format: InventoryExportFormat, include_dir: Option<&Path>) -> InventoryExport {
+    let mut export = InventoryExport::default();
+    let entries = collect_tree_hosts(&tree.root, &tree.hosts, Vec::new());
+
+    match format {
+        InventoryExportFormat::SshConfig => render_ssh_config(tree, &entries, include_dir, &mut export),
+        InventoryExportFormat::Ansible => render_ansible(&entries, &mut export),
+        InventoryExportFormat::Csv => render_csv(&entries, &mut export),
+        InventoryExportFormat::Json => render_json(&entries, &mut export),
+    }
+    export
+}
+
+/// Write an export to `output` and its include files, backing up each file
+/// that already exists first. Returns the backup paths.
+pub(crate) fn write_inventory_export(export: &InventoryExport, output: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut backup_paths = Vec::new();
+    for (path, contents) in &export.include_files {
+        backup_paths.extend(backup_existing_inventory(path)?);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+    }
+    backup_paths.extend(backup_existing_inventory(output)?);
+    if let Some(parent) = output.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(output, &export.document)?;
+    Ok(backup_paths)
+}
+
+/// One OpenSSH document being assembled, with the folder of its last host.
+#[derive(Default)]
+struct SshConfigDocument {
+    contents: String,
+    current_folder: Option<Vec<String>>,
+}
+
+impl SshConfigDocument {
+    fn push_host(&mut self, folder_path: &[String], host: &InventoryHost) {
+        if self.current_folder.as_deref() != Some(folder_path) {
+            if !folder_path.is_empty() {
+                self.contents.push_str(&format!("# Folder: {}\n\n", folder_path.join("/")));
+            }
+            self.current_folder = Some(folder_path.to_vec());
+        }
+        self.contents.push_str(&render_ssh_host(host));
+        self.contents.push('\n');
+    }
+}
+
+fn render_ssh_config(tree: &InventoryTreeModel, entries: &[InventoryHostEntry<'_>], include_dir: Option<&Path>, export: &mut InventoryExport) {
+    let root_file = canonical_path(&tree.root.path);
+    let mut main = SshConfigDocument::default();
+    let mut include_documents: Vec<(PathBuf, SshConfigDocument)> = Vec::new();
+    let mut include_paths: HashMap<PathBuf, usize> = HashMap::new();
+    let mut used_file_names = HashSet::new();
+
+    for entry in entries {
+        let host = entry.host;
+        if host.name.contains(char::is_whitespace) {
+            export
+                .skipped
+                .push((host.name.clone(), "OpenSSH host aliases cannot contain whitespace".to_string()));
+            continue;
+        }
+        export.hosts_exported += 1;
+
+        let source_file = canonical_path(&host.source_file);
+        let document = match include_dir {
+            Some(include_dir) if source_file != root_file => {
+                let index = *include_paths.entry(source_file.clone()).or_insert_with(|| {
+                    let file_name = unique_include_file_name(&source_file, &mut used_file_names);
+                    include_documents.push((include_dir.join(file_name), SshConfigDocument::default()));
+                    include_documents.len() - 1
+                });
+                &mut include_documents[index].1
+            }
+            _ => &mut main,
+        };
+        document.push_host(&entry.folder_path, host);
+    }
+
+    let mut document = format!("# Exported from {} by `cossh inventory export`.\n\n", tree.root.path.display());
+    for (path, _) in &include_documents {
+        document.push_str(&format!("Include {}\n", quote_ssh_value(&path.to_string_lossy())));
+    }
+    if !include_documents.is_empty() {
+        document.push('\n');
+    }
+    document.push_str(&main.contents);
+    export.document = document;
+    export.include_files = include_documents.into_iter().map(|(path, document)| (path, document.contents)).collect();
+}
+
+fn unique_include_file_name(source_file: &Path, used: &mut HashSet<String>) -> String {
+    let stem = source_file.file_stem().and_then(|stem| stem.to_str()).unwrap_or("inventory");
+    let mut file_name = format!("{stem}.conf");
+    let mut suffix = 2;
+    while !used.insert(file_name.clone()) {
+        file_name = format!("{stem}-{suffix}.conf");
+        suffix += 1;
+    }
+    file_name
+}
+
+fn render_ssh_host(host: &InventoryHost) -> String {
+    let mut lines = vec![format!("Host {}", host.name)];
+    let mut push = |keyword: &str, value: &str| lines.push(format!("    {keyword} {value}"));
+
+    if let Some(description) = &host.description {
+        push("#_Desc", description);
+    }
+    if host.protocol != ConnectionProtocol::Ssh {
+        push("#_Protocol", host.protocol.as_str());
+    }
+    if let Some(profile) = &host.profile {
+        push("#_Profile", profile);
+    }
+    if let Some(vault_pass) = &host.vault_pass {
+        push("#_pass", vault_pass);
+    }
+    if let Some(domain) = &host.rdp.domain {
+        push("#_RdpDomain", domain);
+    }
+    if !host.rdp.args.is_empty() {
+        push("#_RdpArgs", &host.rdp.args.join(" "));
+    }
+    if host.hidden {
+        push("#_hidden", "true");
+    }
+
+    push("HostName", &host.host);
+    if let Some(user) = &host.user {
+        push("User", user);
+    }
+    if let Some(port) = host.port {
+        push("Port", &port.to_string());
+    }
+    for identity_file in &host.ssh.identity_files {
+        push("IdentityFile", &quote_ssh_value(identity_file));
+    }
+    if let Some(identities_only) = host.ssh.identities_only {
+        push("IdentitiesOnly", if identities_only { "yes" } else { "no" });
+    }
+    if let Some(proxy_jump) = &host.ssh.proxy_jump {
+        push("ProxyJump", proxy_jump);
+    }
+    if let Some(proxy_command) = &host.ssh.proxy_command {
+        push("ProxyCommand", proxy_command);
+    }
+    if let Some(forward_agent) = &host.ssh.forward_agent {
+        push("ForwardAgent", forward_agent);
+    }
+    for spec in &host.ssh.local_forward {
+        push("LocalForward", &ssh_forward_arguments(spec));
+    }
+    for spec in &host.ssh.remote_forward {
+        push("RemoteForward", &ssh_forward_arguments(spec));
+    }
+    for (option, values) in &host.ssh.extra_options {
+        for value in values {
+            push(option, value);
+        }
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Turn an inventory forward spec (`8080:localhost:80`) back into the two
+/// OpenSSH arguments (`8080 localhost:80`).
+pub(crate) fn ssh_forward_arguments(spec: &str) -> String {
+    if spec.contains(char::is_whitespace) {
+        return spec.to_string();
+    }
+    let Some((rest, port)) = spec.rsplit_once(':') else {
+        return spec.to_string();
+    };
+    let destination_start = if rest.ends_with(']') {
+        rest.rfind('[')
+    } else {
+        rest.rfind(':').map(|index| index + 1)
+    };
+    match destination_start {
+        Some(start) if start > 0 => format!("{} {}:{port}", rest[..start].trim_end_matches(':'), &rest[start..]),
+        _ => spec.to_string(),
+    }
+}
+
+pub(super) fn quote_ssh_value(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{value}\"")
+    } else {
+        value.to_string()
+    }
+}
+
+fn render_ansible(entries: &[InventoryHostEntry<'_>], export: &mut InventoryExport) {
+    let mut root = AnsibleGroup::default();
+    let mut group_names: HashMap<String, Vec<String>> = HashMap::new();
+
+    for entry in entries {
+        let host = entry.host;
+        if host.protocol != ConnectionProtocol::Ssh {
+            export
+                .skipped
+                .push((host.name.clone(), format!("{} hosts have no Ansible equivalent", host.protocol)));
+            continue;
+        }
+        export.hosts_exported += 1;
+
+        let mut group = &mut root;
+        for depth in 1..=entry.folder_path.len() {
+            let name = ansible_group_name(&entry.folder_path[..depth], &mut group_names);
+            let index = match group.children.iter().position(|(child, _)| *child == name) {
+                Some(index) => index,
+                None => {
+                    group.children.push((name, AnsibleGroup::default()));
+                    group.children.len() - 1
+                }
+            };
+            group = &mut group.children[index].1;
+        }
+        group.hosts.push((host.name.clone(), ansible_host_vars(host)));
+    }
+
+    let mut document = Mapping::new();
+    document.insert("all".to_string(), root.into_value());
+    export.document = serde_yml::to_string(&Value::Mapping(document)).unwrap_or_default();
+}
+
+#[derive(Default)]
+struct AnsibleGroup {
+    hosts: Vec<(String, Mapping)>,
+    children: Vec<(String, AnsibleGroup)>,
+}
+
+impl AnsibleGroup {
+    fn into_value(self) -> Value {
+        let mut group = Mapping::new();
+        if !self.hosts.is_empty() {
+            let hosts = self
+                .hosts
+                .into_iter()
+                .map(|(name, vars)| (name, if vars.is_empty() { Value::Null } else { Value::Mapping(vars) }))
+                .collect();
+            group.insert("hosts".to_string(), Value::Mapping(hosts));
+        }
+        if !self.children.is_empty() {
+            let children = self.children.into_iter().map(|(name, child)| (name, child.into_value())).collect();
+            group.insert("children".to_string(), Value::Mapping(children));
+        }
+        if group.is_empty() { Value::Null } else { Value::Mapping(group) }
+    }
+}
+
+/// Ansible group names are global identifiers, so folders are sanitized and a
+/// name already used by another folder path is qualified with its parents.
+fn ansible_group_name(folder_path: &[String], used: &mut HashMap<String, Vec<String>>) -> String {
+    let sanitize = |name: &str| -> String {
+        let name: String = name
+            .chars()
+            .map(|ch| if ch.is_ascii_alphanumeric() { ch.to_ascii_lowercase() } else { '_' })
+            .collect();
+        if name.starts_with(|ch: char| ch.is_ascii_digit()) {
+            format!("_{name}")
+        } else {
+            name
+        }
+    };
+
+    let mut name = sanitize(folder_path.last().map(String::as_str).unwrap_or_default());
+    if used.get(&name).is_some_and(|path| path != folder_path) {
+        name = sanitize(&folder_path.join("_"));
+    }
+    used.entry(name.clone()).or_insert_with(|| folder_path.to_vec());
+    name
+}
+
+fn ansible_host_vars(host: &InventoryHost) -> Mapping {
+    let mut vars = Mapping::new();
+    let mut insert = |key: &str, value: Value| {
+        vars.insert(key.to_string(), value);
+    };
+    if host.host != host.name {
+        insert("ansible_host", Value::String(host.host.clone()));
+    }
+    if let Some(user) = &host.user {
+        insert("ansible_user", Value::String(user.clone()));
+    }
+    if let Some(port) = host.port {
+        insert("ansible_port", Value::Number(port.into()));
+    }
+    let mut identity_files = host.ssh.identity_files.iter();
+    if let Some(identity_file) = identity_files.next() {
+        insert("ansible_ssh_private_key_file", Value::String(identity_file.clone()));
+    }
+
+    let mut ssh_args: Vec<String> = identity_files.map(|identity_file| format!("-i {}", shell_quote(identity_file))).collect();
+    let mut option = |name: &str, value: &str| ssh_args.push(format!("-o {}", shell_quote(&format!("{name}={value}"))));
+    if let Some(identities_only) = host.ssh.identities_only {
+        option("IdentitiesOnly", if identities_only { "yes" } else { "no" });
+    }
+    if let Some(proxy_jump) = &host.ssh.proxy_jump {
+        option("ProxyJump", proxy_jump);
+    }
+    if let Some(proxy_command) = &host.ssh.proxy_command {
+        option("ProxyCommand", proxy_command);
+    }
+    if let Some(forward_agent) = &host.ssh.forward_agent {
+        option("ForwardAgent", forward_agent);
+    }
+    for (name, values) in &host.ssh.extra_options {
+        for value in values {
+            option(name, value);
+        }
+    }
+    ssh_args.extend(host.ssh.local_forward.iter().map(|spec| format!("-L {}", shell_quote(spec))));
+    ssh_args.extend(host.ssh.remote_forward.iter().map(|spec| format!("-R {}", shell_quote(spec))));
+    if !ssh_args.is_empty() {
+        insert("ansible_ssh_common_args", Value::String(ssh_args.join(" ")));
+    }
+    vars
+}
+
+fn shell_quote(value: &str) -> String {
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.' | '/' | ':' | '=' | '@' | '%' | '+' | ',' | '~'))
+    {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+const CSV_COLUMNS: [&str; 12] = [
+    "name",
+    "host",
+    "user",
+    "port",
+    "protocol",
+    "folder",
+    "description",
+    "profile",
+    "vault_pass",
+    "proxy_jump",
+    "identity_file",
+    "hidden",
+];
+
+fn render_csv(entries: &[InventoryHostEntry<'_>], export: &mut InventoryExport) {
+    let mut document = CSV_COLUMNS.join(",");
+    document.push('\n');
+    for entry in entries {
+        let host = entry.host;
+        let fields = [
+            host.name.clone(),
+            host.host.clone(),
+            host.user.clone().unwrap_or_default(),
+            host.port.map(|port| port.to_string()).unwrap_or_default(),
+            host.protocol.to_string(),
+            entry.folder_path.join("/"),
+            host.description.clone().unwrap_or_default(),
+            host.profile.clone().unwrap_or_default(),
+            host.vault_pass.clone().unwrap_or_default(),
+            host.ssh.proxy_jump.clone().unwrap_or_default(),
+            host.ssh.identity_files.first().cloned().unwrap_or_default(),
+            if host.hidden { "true".to_string() } else { String::new() },
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        document.push_str(&row.join(","));
+        document.push('\n');
+        export.hosts_exported += 1;
+    }
+    export.document = document;
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn render_json(entries: &[InventoryHostEntry<'_>], export: &mut InventoryExport) {
+    let records: Vec<JsonValue> = entries
+        .iter()
+        .map(|entry| JsonValue::Object(json_host_record(&entry.folder_path, entry.host)))
+        .collect();
+    export.hosts_exported = records.len();
+    export.document = serde_json::to_string_pretty(&records).unwrap_or_default();
+    export.document.push('\n');
+}
+
+pub(super) fn json_host_record(folder_path: &[String], host: &InventoryHost) -> Map<String, JsonValue> {
+    let mut record = Map::new();
+    let string = |value: &str| JsonValue::String(value.to_string());
+    let strings = |values: &[String]| JsonValue::Array(values.iter().map(|value| string(value)).collect());
+
+    record.insert("name".to_string(), string(&host.name));
+    record.insert("host".to_string(), string(&host.host));
+    record.insert("protocol".to_string(), string(host.protocol.as_str()));
+    if !folder_path.is_empty() {
+        record.insert("folder".to_string(), string(&folder_path.join("/")));
+    }
+    let optional = [
+        ("description", &host.description),
+        ("user", &host.user),
+        ("profile", &host.profile),
+        ("vault_pass", &host.vault_pass),
+        ("vault_key", &host.ssh.vault_key),
+        ("proxy_jump", &host.ssh.proxy_jump),
+        ("proxy_command", &host.ssh.proxy_command),
+        ("forward_agent", &host.ssh.forward_agent),
+        ("rdp_domain", &host.rdp.domain),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value {
+            record.insert(key.to_string(), string(value));
+        }
+    }
+    if let Some(port) = host.port {
+        record.insert("port".to_string(), JsonValue::from(port));
+    }
+    if host.hidden {
+        record.insert("hidden".to_string(), JsonValue::Bool(true));
+    }
+    if let Some(identities_only) = host.ssh.identities_only {
+        record.insert("identities_only".to_string(), JsonValue::Bool(identities_only));
+    }
+    if host.ssh.vault_forward {
+        record.insert("vault_forward".to_string(), JsonValue::Bool(true));
+    }
+    let lists = [
+        ("identity_file", &host.ssh.identity_files),
+        ("local_forward", &host.ssh.local_forward),
+        ("remote_forward", &host.ssh.remote_forward),
+        ("rdp_args", &host.rdp.args),
+    ];
+    for (key, values) in lists {
+        if !values.is_empty() {
+            record.insert(key.to_string(), strings(values));
+        }
+    }
+    if !host.ssh.extra_options.is_empty() {
+        let options = host
+            .ssh
+            .extra_options
+            .iter()
+            .map(|(option, values)| (option.clone(), strings(values)))
+            .collect();
+        record.insert("ssh_options".to_string(), JsonValue::Object(options));
+    }
+    record
+}
+
+#[cfg(test)]
+#[path = "../test/inventory/export.rs"]
+mod tests;
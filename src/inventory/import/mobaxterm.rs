@@ -0,0 +1,55 @@
+//! MobaXterm sessions from a `.mxtsessions` export.
+//!
+//! Each `[Bookmarks*]` section is one folder, named by its `SubRep` path, with
+//! one `name=#icon#type%host%port%user%...` entry per session.
+
+use super::{ImportedSession, ParsedSessions, parse_ini_sections, session_host, split_folder_path};
+use crate::inventory::ConnectionProtocol;
+
+const SSH_SESSION: &str = "0";
+const RDP_SESSION: &str = "4";
+
+pub(super) fn parse_mobaxterm_sessions(contents: &str) -> ParsedSessions {
+    let mut parsed = ParsedSessions::default();
+    for (section, entries) in parse_ini_sections(contents) {
+        if !section.starts_with("Bookmarks") {
+            continue;
+        }
+        let folder_path = entries
+            .iter()
+            .find(|(key, _)| key == "SubRep")
+            .map(|(_, path)| split_folder_path(path, '\\'))
+            .unwrap_or_default();
+
+        for (name, value) in entries.iter().filter(|(key, _)| key != "SubRep" && key != "ImgNum") {
+            let settings = value.split('#').nth(2).unwrap_or_default();
+            let fields: Vec<&str> = settings.split('%').map(str::trim).collect();
+            let protocol = match fields.first().copied() {
+                Some(SSH_SESSION) => ConnectionProtocol::Ssh,
+                Some(RDP_SESSION) => ConnectionProtocol::Rdp,
+                Some(kind) if !kind.is_empty() => {
+                    parsed
+                        .unsupported
+                        .push((name.clone(), format!("MobaXterm session type {kind} is not supported")));
+                    continue;
+                }
+                _ => {
+                    parsed.unsupported.push((name.clone(), "unrecognized session settings".to_string()));
+                    continue;
+                }
+            };
+            let Some(host_name) = fields.get(1).copied().filter(|host| !host.is_empty()) else {
+                parsed.unsupported.push((name.clone(), "no host name".to_string()));
+                continue;
+            };
+            let port = fields.get(2).and_then(|port| port.parse().ok());
+            let user = fields.get(3).copied();
+
+            parsed.sessions.push(ImportedSession {
+                folder_path: folder_path.clone(),
+                host: session_host(name, protocol, host_name, user, port),
+            });
+        }
+    }
+    parsed
+}
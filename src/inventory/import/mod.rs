@@ -0,0 +1,287 @@
+//! Session imports from other SSH and RDP clients for `cossh inventory import`.
+//!
+//! Each client has its own parser producing [`ImportedSession`]s. Sessions are
+//! merged into an existing inventory file below a chosen folder; names that
+//! are already taken are skipped, renamed or replaced as requested.
+
+mod mobaxterm;
+mod putty;
+mod remmina;
+mod securecrt;
+mod termius;
+
+use super::edit::{EditableInventoryHost, create_inventory_host_entries, update_inventory_host_entry};
+use super::path::get_default_inventory_path;
+use super::{ConnectionProtocol, build_inventory_tree};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Client exports `cossh inventory import` reads sessions from.
+pub(crate) enum SessionImportFormat {
+    /// `regedit` export of the PuTTY sessions key.
+    Putty,
+    /// `.mxtsessions` export.
+    MobaXterm,
+    /// `.remmina` profile or a directory of them.
+    Remmina,
+    /// XML settings export or a `Sessions` directory of `.ini` files.
+    SecureCrt,
+    /// CSV or JSON host export.
+    Termius,
+}
+
+impl SessionImportFormat {
+    /// Client name, also used as the default import folder.
+    pub(crate) fn client_name(self) -> &'static str {
+        match self {
+            Self::Putty => "PuTTY",
+            Self::MobaXterm => "MobaXterm",
+            Self::Remmina => "Remmina",
+            Self::SecureCrt => "SecureCRT",
+            Self::Termius => "Termius",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// What happens to an imported session whose name is already taken.
+pub(crate) enum ImportConflict {
+    #[default]
+    Skip,
+    /// Import it under the first free `<name>-<n>`.
+    Rename,
+    /// Overwrite the existing host where it is defined.
+    Replace,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SessionImportOptions {
+    pub(crate) format: SessionImportFormat,
+    /// Export file or directory to read.
+    pub(crate) path: PathBuf,
+    /// Inventory file to add hosts to; the default inventory when `None`.
+    pub(crate) inventory_path: Option<PathBuf>,
+    /// Folder below the inventory root; the client name when `None`.
+    pub(crate) folder: Option<Vec<String>>,
+    pub(crate) on_conflict: ImportConflict,
+    /// Report what would change without writing.
+    pub(crate) dry_run: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Outcome for one imported session.
+pub(crate) enum ImportedHostAction {
+    Added,
+    /// Added under a new name; holds the session's original name.
+    Renamed(String),
+    /// Replaced the existing host in this inventory file.
+    Replaced(PathBuf),
+    /// Left out because the name is already taken.
+    Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ImportedHost {
+    pub(crate) name: String,
+    pub(crate) protocol: ConnectionProtocol,
+    pub(crate) host: String,
+    /// Folder the host is added to.
+    pub(crate) folder_path: Vec<String>,
+    pub(crate) action: ImportedHostAction,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SessionImportSummary {
+    /// Inventory file hosts were added to.
+    pub(crate) inventory_path: PathBuf,
+    pub(crate) dry_run: bool,
+    pub(crate) hosts: Vec<ImportedHost>,
+    /// Sessions that cannot be imported, with the reason.
+    pub(crate) unsupported: Vec<(String, String)>,
+}
+
+/// A session read from a client export.
+#[derive(Debug, Clone, Default)]
+pub(super) struct ImportedSession {
+    /// Folder below the import folder, from the client's own grouping.
+    pub(super) folder_path: Vec<String>,
+    pub(super) host: EditableInventoryHost,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct ParsedSessions {
+    pub(super) sessions: Vec<ImportedSession>,
+    /// Sessions that cannot be imported, with the reason.
+    pub(super) unsupported: Vec<(String, String)>,
+}
+
+/// Import the sessions of a client export into the inventory.
+pub(crate) fn import_sessions(options: &SessionImportOptions) -> io::Result<SessionImportSummary> {
+    let inventory_path = match &options.inventory_path {
+        Some(path) => path.clone(),
+        None => get_default_inventory_path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Could not find home directory for ~/.color-ssh"))?,
+    };
+    let parsed = parse_sessions(options.format, &options.path)?;
+    if parsed.sessions.is_empty() && parsed.unsupported.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("no {} sessions found in '{}'", options.format.client_name(), options.path.display()),
+        ));
+    }
+
+    // Hosts from inventory sources can be matched but not replaced.
+    let existing: HashMap<String, Option<PathBuf>> = if inventory_path.exists() {
+        build_inventory_tree(&inventory_path)?
+            .hosts
+            .into_iter()
+            .map(|host| {
+                let source_file = host.dynamic_source.is_none().then_some(host.source_file);
+                (host.name, source_file)
+            })
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    let base_folder = options.folder.clone().unwrap_or_else(|| vec![options.format.client_name().to_string()]);
+
+    let mut taken: HashSet<String> = existing.keys().cloned().collect();
+    let mut replaced: HashSet<String> = HashSet::new();
+    let mut additions = Vec::new();
+    let mut replacements = Vec::new();
+    let mut hosts = Vec::new();
+    for session in parsed.sessions {
+        let mut host = session.host;
+        let folder_path: Vec<String> = base_folder.iter().cloned().chain(session.folder_path).collect();
+        let action = if !taken.contains(&host.name) {
+            ImportedHostAction::Added
+        } else {
+            match options.on_conflict {
+                ImportConflict::Skip => ImportedHostAction::Skipped,
+                ImportConflict::Rename => {
+                    let renamed = free_host_name(&host.name, &taken);
+                    ImportedHostAction::Renamed(std::mem::replace(&mut host.name, renamed))
+                }
+                ImportConflict::Replace => match existing.get(&host.name) {
+                    Some(Some(source_file)) if !replaced.contains(&host.name) => ImportedHostAction::Replaced(source_file.clone()),
+                    _ => ImportedHostAction::Skipped,
+                },
+            }
+        };
+
+        match &action {
+            ImportedHostAction::Added | ImportedHostAction::Renamed(_) => {
+                taken.insert(host.name.clone());
+                additions.push((folder_path.clone(), host.clone()));
+            }
+            ImportedHostAction::Replaced(source_file) => {
+                replaced.insert(host.name.clone());
+                replacements.push((source_file.clone(), host.clone()));
+            }
+            ImportedHostAction::Skipped => {}
+        }
+        hosts.push(ImportedHost {
+            name: host.name,
+            protocol: host.protocol,
+            host: host.host,
+            folder_path,
+            action,
+        });
+    }
+
+    if !options.dry_run {
+        if !additions.is_empty() {
+            create_inventory_host_entries(&inventory_path, &additions)?;
+        }
+        for (source_file, host) in &replacements {
+            update_inventory_host_entry(source_file, &host.name, host)?;
+        }
+    }
+
+    Ok(SessionImportSummary {
+        inventory_path,
+        dry_run: options.dry_run,
+        hosts,
+        unsupported: parsed.unsupported,
+    })
+}
+
+fn parse_sessions(format: SessionImportFormat, path: &Path) -> io::Result<ParsedSessions> {
+    let parsed = match format {
+        SessionImportFormat::Putty => putty::parse_putty_sessions(&read_export_text(path)?),
+        SessionImportFormat::MobaXterm => Ok(mobaxterm::parse_mobaxterm_sessions(&read_export_text(path)?)),
+        SessionImportFormat::Remmina => return remmina::load_remmina_sessions(path),
+        SessionImportFormat::SecureCrt => return securecrt::load_securecrt_sessions(path),
+        SessionImportFormat::Termius => termius::parse_termius_sessions(&read_export_text(path)?),
+    };
+    parsed.map_err(|err| invalid_export(path, err))
+}
+
+fn free_host_name(name: &str, taken: &HashSet<String>) -> String {
+    (2..)
+        .map(|suffix| format!("{name}-{suffix}"))
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap_or_else(|| name.to_string())
+}
+
+pub(super) fn invalid_export(path: &Path, message: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {message}", path.display()))
+}
+
+/// Read a client export, decoding the UTF-16 files Windows tools such as `regedit` write.
+pub(super) fn read_export_text(path: &Path) -> io::Result<String> {
+    let bytes = fs::read(path)?;
+    if let Some(utf16) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        let units: Vec<u16> = utf16.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+        return Ok(String::from_utf16_lossy(&units));
+    }
+    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(&bytes);
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// `[section]` blocks of `key=value` lines, in file order.
+pub(super) fn parse_ini_sections(contents: &str) -> Vec<(String, Vec<(String, String)>)> {
+    let mut sections: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            sections.push((section.trim().to_string(), Vec::new()));
+            continue;
+        }
+        if let (Some((_, entries)), Some((key, value))) = (sections.last_mut(), line.split_once('=')) {
+            entries.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    sections
+}
+
+/// Host entry for a session; a port equal to the protocol default is left out.
+pub(super) fn session_host(name: &str, protocol: ConnectionProtocol, host: &str, user: Option<&str>, port: Option<u16>) -> EditableInventoryHost {
+    let default_port = if protocol == ConnectionProtocol::Rdp { 3389 } else { 22 };
+    EditableInventoryHost {
+        name: name.trim().to_string(),
+        host: host.trim().to_string(),
+        user: user.map(str::trim).filter(|user| !user.is_empty()).map(str::to_string),
+        port: port.filter(|port| *port != default_port),
+        protocol,
+        ..EditableInventoryHost::default()
+    }
+}
+
+/// Split a client folder path such as `Servers\Prod` or `Servers/Prod`.
+pub(super) fn split_folder_path(path: &str, separator: char) -> Vec<String> {
+    path.split(separator)
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+#[path = "../../test/inventory/import/mod.rs"]
+mod tests;
@@ -0,0 +1,139 @@
+//! PuTTY sessions from a `regedit` export of `HKCU\Software\SimonTatham\PuTTY\Sessions`.
+
+use super::{ImportedSession, ParsedSessions, session_host};
+use crate::inventory::{ConnectionProtocol, EditableInventoryHost};
+use std::collections::BTreeMap;
+
+const SESSIONS_KEY: &str = "\\SimonTatham\\PuTTY\\Sessions\\";
+const DEFAULT_SESSION: &str = "Default Settings";
+
+#[derive(Debug)]
+enum RegistryValue {
+    String(String),
+    Dword(u32),
+}
+
+pub(super) fn parse_putty_sessions(contents: &str) -> Result<ParsedSessions, String> {
+    if !contents.contains(SESSIONS_KEY) {
+        return Err("not a PuTTY registry export (no SimonTatham\\PuTTY\\Sessions keys)".to_string());
+    }
+
+    let mut sessions: Vec<(String, BTreeMap<String, RegistryValue>)> = Vec::new();
+    let mut in_session = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        if let Some(key) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            in_session = false;
+            if let Some((_, name)) = key.split_once(SESSIONS_KEY)
+                && !key.starts_with('-')
+                && !name.is_empty()
+                && !name.contains('\\')
+            {
+                sessions.push((percent_decode(name), BTreeMap::new()));
+                in_session = true;
+            }
+            continue;
+        }
+        if in_session
+            && let Some((name, value)) = parse_registry_value(line)
+            && let Some((_, values)) = sessions.last_mut()
+        {
+            values.insert(name, value);
+        }
+    }
+
+    let mut parsed = ParsedSessions::default();
+    for (name, values) in sessions {
+        if name == DEFAULT_SESSION {
+            continue;
+        }
+        let string = |key: &str| match values.get(key) {
+            Some(RegistryValue::String(value)) => Some(value.as_str()).filter(|value| !value.is_empty()),
+            _ => None,
+        };
+        let dword = |key: &str| match values.get(key) {
+            Some(RegistryValue::Dword(value)) => Some(*value),
+            _ => None,
+        };
+
+        let protocol = string("Protocol").unwrap_or("ssh");
+        if protocol != "ssh" {
+            parsed.unsupported.push((name, format!("{protocol} sessions are not supported")));
+            continue;
+        }
+        let Some(host_name) = string("HostName") else {
+            parsed.unsupported.push((name, "no host name".to_string()));
+            continue;
+        };
+        let (user, host_name) = match host_name.rsplit_once('@') {
+            Some((user, host_name)) => (Some(user), host_name),
+            None => (string("UserName"), host_name),
+        };
+        let port = dword("PortNumber").and_then(|port| u16::try_from(port).ok());
+
+        let mut host = session_host(&name, ConnectionProtocol::Ssh, host_name, user, port);
+        if dword("AgentFwd") == Some(1) {
+            host.ssh_forward_agent = Some("yes".to_string());
+        }
+        if let Some(forwardings) = string("PortForwardings") {
+            apply_port_forwardings(&mut host, forwardings);
+        }
+        parsed.sessions.push(ImportedSession { folder_path: Vec::new(), host });
+    }
+    Ok(parsed)
+}
+
+/// `"Name"="text"` or `"Name"=dword:0000001a`.
+fn parse_registry_value(line: &str) -> Option<(String, RegistryValue)> {
+    let (name, value) = line.strip_prefix('"')?.split_once("\"=")?;
+    if let Some(dword) = value.strip_prefix("dword:") {
+        return Some((name.to_string(), RegistryValue::Dword(u32::from_str_radix(dword.trim(), 16).ok()?)));
+    }
+    let quoted = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut text = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => text.extend(chars.next()),
+            _ => text.push(ch),
+        }
+    }
+    Some((name.to_string(), RegistryValue::String(text)))
+}
+
+/// PuTTY stores session names with `%XX` escapes for spaces and punctuation.
+fn percent_decode(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%'
+            && let Some(byte) = name.get(index + 1..index + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            index += 3;
+            continue;
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// `PortForwardings` is a comma separated list such as
+/// `L8080=localhost:80,4R2222=localhost:22,D1080`.
+fn apply_port_forwardings(host: &mut EditableInventoryHost, forwardings: &str) {
+    for spec in forwardings.split(',').map(str::trim).filter(|spec| !spec.is_empty()) {
+        let spec = spec.trim_start_matches(['4', '6']);
+        let mut chars = spec.chars();
+        let kind = chars.next();
+        let rest = chars.as_str();
+        let (listen, destination) = rest.split_once('=').unwrap_or((rest, ""));
+        match kind {
+            Some('L') if !destination.is_empty() => host.ssh_local_forward.push(format!("{listen}:{destination}")),
+            Some('R') if !destination.is_empty() => host.ssh_remote_forward.push(format!("{listen}:{destination}")),
+            Some('D') if !listen.is_empty() => host.ssh_options.entry("DynamicForward".to_string()).or_default().push(listen.to_string()),
+            _ => {}
+        }
+    }
+}
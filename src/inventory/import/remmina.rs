@@ -0,0 +1,135 @@
+//! Remmina connection profiles: a single `.remmina` file or a directory of them.
+
+use super::{ImportedSession, ParsedSessions, invalid_export, parse_ini_sections, read_export_text, session_host, split_folder_path};
+use crate::inventory::ConnectionProtocol;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+struct RemminaProfile(HashMap<String, String>);
+
+impl RemminaProfile {
+    fn parse(contents: &str) -> Self {
+        Self(
+            parse_ini_sections(contents)
+                .into_iter()
+                .filter(|(section, _)| section == "remmina")
+                .flat_map(|(_, entries)| entries)
+                .collect(),
+        )
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(|value| value.trim()).filter(|value| !value.is_empty())
+    }
+
+    fn enabled(&self, key: &str) -> bool {
+        self.get(key) == Some("1")
+    }
+}
+
+pub(super) fn load_remmina_sessions(path: &Path) -> io::Result<ParsedSessions> {
+    let files = if path.is_dir() {
+        let mut files: Vec<PathBuf> = fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| file.extension().is_some_and(|extension| extension == "remmina"))
+            .collect();
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut parsed = ParsedSessions::default();
+    for file in files {
+        let profile = RemminaProfile::parse(&read_export_text(&file)?);
+        if profile.0.is_empty() {
+            return Err(invalid_export(&file, "not a Remmina profile (no [remmina] section)"));
+        }
+        let name = profile
+            .get("name")
+            .map(str::to_string)
+            .unwrap_or_else(|| file.file_stem().unwrap_or_default().to_string_lossy().into_owned());
+        match remmina_session(&name, &profile) {
+            Ok(session) => parsed.sessions.push(session),
+            Err(reason) => parsed.unsupported.push((name, reason)),
+        }
+    }
+    Ok(parsed)
+}
+
+fn remmina_session(name: &str, profile: &RemminaProfile) -> Result<ImportedSession, String> {
+    let protocol = match profile.get("protocol").unwrap_or("RDP").to_ascii_uppercase().as_str() {
+        "SSH" => ConnectionProtocol::Ssh,
+        "RDP" => ConnectionProtocol::Rdp,
+        other => return Err(format!("{other} connections are not supported")),
+    };
+    let server = profile.get("server").ok_or("no server")?;
+    let (host_name, port) = split_server_port(server);
+
+    let mut host = session_host(name, protocol, host_name, profile.get("username"), port);
+    if host.protocol == ConnectionProtocol::Rdp {
+        host.rdp_domain = profile.get("domain").map(str::to_string);
+        host.rdp_args = remmina_rdp_args(profile);
+    } else {
+        host.ssh_identity_files.extend(profile.get("ssh_privatekey").map(str::to_string));
+    }
+    Ok(ImportedSession {
+        folder_path: profile.get("group").map(|group| split_folder_path(group, '/')).unwrap_or_default(),
+        host,
+    })
+}
+
+/// FreeRDP arguments for the Remmina display, gateway and redirection settings.
+fn remmina_rdp_args(profile: &RemminaProfile) -> Vec<String> {
+    let mut args = Vec::new();
+    // Mode 2 is a custom resolution; the others follow the client window.
+    if profile.get("resolution_mode") == Some("2")
+        && let (Some(width), Some(height)) = (profile.get("resolution_width"), profile.get("resolution_height"))
+    {
+        args.push(format!("/size:{width}x{height}"));
+    }
+    if let Some(depth) = profile.get("colordepth").filter(|depth| matches!(*depth, "8" | "15" | "16" | "24" | "32")) {
+        args.push(format!("/bpp:{depth}"));
+    }
+    if let Some(security) = profile.get("security") {
+        args.push(format!("/sec:{security}"));
+    }
+    if profile.enabled("gateway_usage")
+        && let Some(gateway) = profile.get("gateway_server")
+    {
+        args.push(format!("/g:{gateway}"));
+        args.extend(profile.get("gateway_username").map(|user| format!("/gu:{user}")));
+        args.extend(profile.get("gateway_domain").map(|domain| format!("/gd:{domain}")));
+    }
+    if profile.enabled("console") {
+        args.push("/admin".to_string());
+    }
+    if let Some(folder) = profile.get("sharefolder") {
+        args.push(format!("/drive:share,{folder}"));
+    }
+    if profile.enabled("shareprinter") {
+        args.push("/printer".to_string());
+    }
+    if profile.enabled("disableclipboard") {
+        args.push("-clipboard".to_string());
+    }
+    if profile.get("sound") == Some("local") {
+        args.push("/sound".to_string());
+    }
+    args
+}
+
+/// Split `host`, `host:port` or `[v6 address]:port`.
+pub(super) fn split_server_port(server: &str) -> (&str, Option<u16>) {
+    if let Some(rest) = server.strip_prefix('[')
+        && let Some((address, port)) = rest.split_once(']')
+    {
+        return (address, port.strip_prefix(':').and_then(|port| port.parse().ok()));
+    }
+    match server.split_once(':') {
+        Some((host, port)) if !port.contains(':') => (host, port.parse().ok()),
+        _ => (server, None),
+    }
+}
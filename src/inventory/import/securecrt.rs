@@ -0,0 +1,247 @@
+//! SecureCRT sessions from an XML settings export or the `Sessions` directory.
+//!
+//! The XML export nests `<key name="...">` elements below a `Sessions` key: a
+//! key with a `Hostname` is a session and any other key is a folder. Session
+//! `.ini` files store typed values such as `S:"Hostname"=...` and
+//! `D:"[SSH2] Port"=00000016`, with folders as subdirectories.
+
+use super::{ImportedSession, ParsedSessions, invalid_export, read_export_text, session_host};
+use crate::inventory::ConnectionProtocol;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const FOLDER_DATA_FILE: &str = "__FolderData__.ini";
+const DEFAULT_SESSION: &str = "Default";
+
+type SessionValues = HashMap<String, String>;
+
+pub(super) fn load_securecrt_sessions(path: &Path) -> io::Result<ParsedSessions> {
+    let mut parsed = ParsedSessions::default();
+    if path.is_dir() {
+        collect_ini_sessions(path, &mut Vec::new(), &mut parsed)?;
+        return Ok(parsed);
+    }
+
+    let contents = read_export_text(path)?;
+    if contents.trim_start().starts_with('<') {
+        let root = parse_xml(&contents).map_err(|err| invalid_export(path, err))?;
+        let sessions = find_key(&root, "Sessions").ok_or_else(|| invalid_export(path, "no Sessions key in the SecureCRT export"))?;
+        collect_xml_sessions(sessions, &mut Vec::new(), &mut parsed);
+    } else {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        push_session(&mut parsed, &name, Vec::new(), &parse_session_ini(&contents));
+    }
+    Ok(parsed)
+}
+
+fn push_session(parsed: &mut ParsedSessions, name: &str, folder_path: Vec<String>, values: &SessionValues) {
+    match securecrt_session(name, folder_path, values) {
+        Ok(session) => parsed.sessions.push(session),
+        Err(reason) => parsed.unsupported.push((name.to_string(), reason)),
+    }
+}
+
+fn securecrt_session(name: &str, folder_path: Vec<String>, values: &SessionValues) -> Result<ImportedSession, String> {
+    let value = |key: &str| values.get(key).map(|value| value.trim()).filter(|value| !value.is_empty());
+
+    let protocol = value("Protocol Name").unwrap_or("SSH2");
+    let port_key = match protocol {
+        "SSH2" => "[SSH2] Port",
+        "SSH1" => "[SSH1] Port",
+        other => return Err(format!("{other} sessions are not supported")),
+    };
+    let host_name = value("Hostname").ok_or("no host name")?;
+    let port = value(port_key).and_then(|port| port.parse().ok());
+
+    let mut host = session_host(name, ConnectionProtocol::Ssh, host_name, value("Username"), port);
+    host.description = value("Description").map(str::to_string);
+    // `Identity Filename V2` carries a `::rawkey` or fingerprint suffix.
+    if let Some(identity) = value("Identity Filename V2").and_then(|identity| identity.split("::").next()) {
+        host.ssh_identity_files.push(identity.to_string());
+    }
+    Ok(ImportedSession { folder_path, host })
+}
+
+fn collect_ini_sessions(directory: &Path, folder_path: &mut Vec<String>, parsed: &mut ParsedSessions) -> io::Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(directory)?.filter_map(Result::ok).map(|entry| entry.path()).collect();
+    entries.sort();
+    for entry in entries {
+        let file_name = entry.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        if entry.is_dir() {
+            folder_path.push(file_name);
+            collect_ini_sessions(&entry, folder_path, parsed)?;
+            folder_path.pop();
+            continue;
+        }
+        let Some(name) = file_name.strip_suffix(".ini") else {
+            continue;
+        };
+        if file_name == FOLDER_DATA_FILE || (folder_path.is_empty() && name == DEFAULT_SESSION) {
+            continue;
+        }
+        push_session(parsed, name, folder_path.clone(), &parse_session_ini(&read_export_text(&entry)?));
+    }
+    Ok(())
+}
+
+/// String (`S:`) and dword (`D:`) values plus the `Z:` description lines.
+fn parse_session_ini(contents: &str) -> SessionValues {
+    let mut values = SessionValues::new();
+    let mut lines = contents.lines();
+    while let Some(line) = lines.next() {
+        let Some((kind, rest)) = line.split_once(":\"") else {
+            continue;
+        };
+        let Some((name, value)) = rest.split_once("\"=") else {
+            continue;
+        };
+        match kind {
+            "S" => {
+                values.insert(name.to_string(), value.to_string());
+            }
+            "D" => {
+                if let Ok(number) = u32::from_str_radix(value.trim(), 16) {
+                    values.insert(name.to_string(), number.to_string());
+                }
+            }
+            "Z" => {
+                let count = usize::from_str_radix(value.trim(), 16).unwrap_or(0);
+                let text: Vec<&str> = lines.by_ref().take(count).map(str::trim).collect();
+                values.insert(name.to_string(), text.join(" "));
+            }
+            _ => {}
+        }
+    }
+    values
+}
+
+fn collect_xml_sessions(key: &XmlElement, folder_path: &mut Vec<String>, parsed: &mut ParsedSessions) {
+    for child in key.children.iter().filter(|child| child.tag == "key") {
+        let name = child.name.as_deref().unwrap_or_default();
+        if name.is_empty() || (folder_path.is_empty() && name == DEFAULT_SESSION) {
+            continue;
+        }
+        let values = xml_session_values(child);
+        if values.contains_key("Hostname") || values.contains_key("Protocol Name") {
+            push_session(parsed, name, folder_path.clone(), &values);
+        } else {
+            folder_path.push(name.to_string());
+            collect_xml_sessions(child, folder_path, parsed);
+            folder_path.pop();
+        }
+    }
+}
+
+fn xml_session_values(key: &XmlElement) -> SessionValues {
+    let mut values = SessionValues::new();
+    for child in &key.children {
+        let Some(name) = child.name.clone() else {
+            continue;
+        };
+        match child.tag.as_str() {
+            "string" | "dword" => {
+                values.insert(name, child.text.trim().to_string());
+            }
+            "array" => {
+                let text: Vec<&str> = child.children.iter().map(|line| line.text.trim()).collect();
+                values.insert(name, text.join(" "));
+            }
+            _ => {}
+        }
+    }
+    values
+}
+
+fn find_key<'a>(element: &'a XmlElement, name: &str) -> Option<&'a XmlElement> {
+    element.children.iter().find_map(|child| {
+        if child.tag == "key" && child.name.as_deref() == Some(name) {
+            Some(child)
+        } else {
+            find_key(child, name)
+        }
+    })
+}
+
+#[derive(Debug, Default)]
+struct XmlElement {
+    tag: String,
+    name: Option<String>,
+    text: String,
+    children: Vec<XmlElement>,
+}
+
+/// Minimal XML reader for the element-only layout of SecureCRT exports.
+/// Returns a synthetic root holding the document's top-level elements.
+fn parse_xml(contents: &str) -> Result<XmlElement, String> {
+    let mut stack = vec![XmlElement::default()];
+    let mut rest = contents;
+    while let Some(open) = rest.find('<') {
+        if let Some(element) = stack.last_mut() {
+            element.text.push_str(&unescape_xml(&rest[..open]));
+        }
+        rest = &rest[open..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment.find("-->").ok_or("unterminated XML comment")?;
+            rest = &comment[end + 3..];
+            continue;
+        }
+        let end = rest.find('>').ok_or("unterminated XML tag")?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+
+        if let Some(closing) = tag.strip_prefix('/') {
+            let element = stack.pop().filter(|_| !stack.is_empty()).ok_or("unexpected closing XML tag")?;
+            if element.tag != closing.trim() {
+                return Err(format!("expected </{}> but found </{}>", element.tag, closing.trim()));
+            }
+            if let Some(parent) = stack.last_mut() {
+                parent.children.push(element);
+            }
+            continue;
+        }
+
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let (tag_name, attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+        let element = XmlElement {
+            tag: tag_name.to_string(),
+            name: xml_attribute(attributes, "name"),
+            ..XmlElement::default()
+        };
+        match stack.last_mut() {
+            Some(parent) if self_closing => parent.children.push(element),
+            _ => stack.push(element),
+        }
+    }
+    if stack.len() != 1 {
+        return Err("XML export ends inside an element".to_string());
+    }
+    stack.pop().ok_or_else(|| "empty XML export".to_string())
+}
+
+fn xml_attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes;
+    while let Some((key, value)) = rest.split_once('=') {
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|quote| *quote == '"' || *quote == '\'')?;
+        let (text, after) = value[1..].split_once(quote)?;
+        if key.trim() == name {
+            return Some(unescape_xml(text));
+        }
+        rest = after;
+    }
+    None
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,86 @@
+//! Termius hosts from a CSV or JSON export.
+//!
+//! Column and field names are matched loosely (`Label`, `Hostname / IP`,
+//! `Group`, ...) since Termius versions and third-party exporters differ.
+
+use super::{ImportedSession, ParsedSessions, session_host, split_folder_path};
+use crate::inventory::ConnectionProtocol;
+use crate::inventory::parse::compact_key;
+use crate::inventory::source::{json_scalar, parse_csv_rows};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+const NAME_KEYS: &[&str] = &["label", "name", "alias", "title"];
+const HOST_KEYS: &[&str] = &["hostname", "hostnameip", "address", "host", "ip"];
+const PORT_KEYS: &[&str] = &["port", "sshport"];
+const USER_KEYS: &[&str] = &["username", "user", "login", "sshusername"];
+const GROUP_KEYS: &[&str] = &["group", "groupname", "folder"];
+const PROTOCOL_KEYS: &[&str] = &["protocol", "type"];
+const DESCRIPTION_KEYS: &[&str] = &["description", "notes"];
+
+/// One exported host with its field names compacted.
+type TermiusRecord = HashMap<String, String>;
+
+pub(super) fn parse_termius_sessions(contents: &str) -> Result<ParsedSessions, String> {
+    let trimmed = contents.trim_start_matches('\u{feff}').trim_start();
+    let records = if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        json_records(trimmed)?
+    } else {
+        csv_records(contents)?
+    };
+
+    let mut parsed = ParsedSessions::default();
+    for (index, record) in records.iter().enumerate() {
+        let field = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| record.get(*key))
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+        let Some(host_name) = field(HOST_KEYS) else {
+            let name = field(NAME_KEYS).map_or_else(|| format!("record {}", index + 1), str::to_string);
+            parsed.unsupported.push((name, "no host name".to_string()));
+            continue;
+        };
+        let name = field(NAME_KEYS).unwrap_or(host_name);
+        if let Some(protocol) = field(PROTOCOL_KEYS).filter(|protocol| !protocol.eq_ignore_ascii_case("ssh")) {
+            parsed.unsupported.push((name.to_string(), format!("{protocol} hosts are not supported")));
+            continue;
+        }
+        let port = field(PORT_KEYS).and_then(|port| port.parse().ok());
+
+        let mut host = session_host(name, ConnectionProtocol::Ssh, host_name, field(USER_KEYS), port);
+        host.description = field(DESCRIPTION_KEYS).map(str::to_string);
+        parsed.sessions.push(ImportedSession {
+            folder_path: field(GROUP_KEYS).map(|group| split_folder_path(group, '/')).unwrap_or_default(),
+            host,
+        });
+    }
+    Ok(parsed)
+}
+
+fn csv_records(contents: &str) -> Result<Vec<TermiusRecord>, String> {
+    let mut rows = parse_csv_rows(contents)?.into_iter();
+    let header: Vec<String> = rows.next().ok_or("CSV export has no header row")?.iter().map(|key| compact_key(key)).collect();
+    Ok(rows.map(|row| header.iter().cloned().zip(row).collect()).collect())
+}
+
+fn json_records(contents: &str) -> Result<Vec<TermiusRecord>, String> {
+    let value: JsonValue = serde_json::from_str(contents).map_err(|err| format!("export is not valid JSON: {err}"))?;
+    let records = match &value {
+        JsonValue::Array(records) => records,
+        JsonValue::Object(object) => object
+            .get("hosts")
+            .and_then(JsonValue::as_array)
+            .ok_or("expected a JSON list of hosts or an object with a 'hosts' list")?,
+        _ => return Err("expected a JSON list of hosts".to_string()),
+    };
+    records
+        .iter()
+        .enumerate()
+        .map(|(index, record)| match record {
+            JsonValue::Object(fields) => Ok(fields.iter().filter_map(|(key, value)| Some((compact_key(key), json_scalar(value)?))).collect()),
+            _ => Err(format!("record {} is not a JSON object", index + 1)),
+        })
+        .collect()
+}
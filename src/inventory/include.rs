@@ -48,7 +48,7 @@ pub(super) fn expand_include_pattern(pattern: &str) -> Vec<PathBuf> {
     matched_paths
 }
 
-fn matches_pattern(text: &str, pattern: &str) -> bool {
+pub(crate) fn matches_pattern(text: &str, pattern: &str) -> bool {
     // Lightweight wildcard matcher supporting only '*' and '?'.
     let pattern_chars: Vec<char> = pattern.chars().collect();
     let text_chars: Vec<char> = text.chars().collect();
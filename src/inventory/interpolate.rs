@@ -0,0 +1,62 @@
+//! `${...}` variable interpolation in inventory host fields.
+//!
+//! Supported variables are `${name}` (the host alias), `${folder}` (the
+//! innermost folder holding the host) and `${env:VAR}`. `$${` produces a
+//! literal `${`; any other `$` is left alone.
+
+use std::env;
+
+/// Values available to `${...}` variables for one host.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct InterpolationContext<'a> {
+    pub name: &'a str,
+    pub folder: Option<&'a str>,
+}
+
+/// Whether `value` contains a `${...}` variable or escape.
+pub(super) fn has_interpolation(value: &str) -> bool {
+    value.contains("${")
+}
+
+/// Expand every `${...}` variable in `value`.
+pub(super) fn interpolate(value: &str, context: InterpolationContext<'_>) -> Result<String, String> {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        if let Some(escaped) = after.strip_prefix("${") {
+            expanded.push_str("${");
+            rest = escaped;
+            continue;
+        }
+        let Some(body) = after.strip_prefix('{') else {
+            expanded.push('$');
+            rest = after;
+            continue;
+        };
+        let end = body.find('}').ok_or_else(|| format!("unterminated '${{' in '{value}'"))?;
+        expanded.push_str(&resolve_variable(&body[..end], context)?);
+        rest = &body[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+fn resolve_variable(variable: &str, context: InterpolationContext<'_>) -> Result<String, String> {
+    match variable.trim() {
+        "name" => Ok(context.name.to_string()),
+        "folder" => context
+            .folder
+            .map(str::to_string)
+            .ok_or_else(|| "'${folder}' is only available to hosts inside a folder".to_string()),
+        other => match other.strip_prefix("env:") {
+            Some(env_var) => env::var(env_var.trim()).map_err(|_| format!("environment variable '{}' is not set", env_var.trim())),
+            None => Err(format!("unknown variable '${{{other}}}'; use ${{name}}, ${{folder}} or ${{env:VAR}}")),
+        },
+    }
+}
+
+#[cfg(test)]
+#[path = "../test/inventory/interpolate.rs"]
+mod tests;
@@ -0,0 +1,319 @@
+//! Host and folder management for `cossh inventory list|show|add|set|rm|mv|mkdir`.
+//!
+//! Folders are addressed by their path in the tree (`Prod/Web`), so a folder
+//! that comes from an included inventory file resolves to that file and the
+//! remaining segments become folders inside it.
+
+use super::build_inventory_tree;
+use super::edit::{
+    create_inventory_folder, create_inventory_host_from_fields, delete_inventory_host_entry, parse_host_field_assignments, set_inventory_host_fields,
+    transfer_inventory_host_entry,
+};
+use super::export::json_host_record;
+use super::model::{ConnectionProtocol, InventoryHost, InventoryTreeModel, TreeFolder};
+use super::tree::{InventoryHostEntry, canonical_path, collect_tree_hosts};
+use serde_json::Value as JsonValue;
+use serde_yml::Value;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The inventory file and in-file folder path a tree folder path maps to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct InventoryFolderLocation {
+    pub(crate) source_file: PathBuf,
+    pub(crate) folder_path: Vec<String>,
+    /// Whether every segment already exists in the tree.
+    pub(crate) exists: bool,
+}
+
+/// Split a `/`-separated folder path, treating `""` and `/` as the root.
+pub(crate) fn split_inventory_folder_path(folder: &str) -> Vec<String> {
+    folder
+        .split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Map a tree folder path to the file that owns it.
+///
+/// Segments past the last existing folder are placed in the file of that folder.
+pub(crate) fn locate_inventory_folder(tree: &InventoryTreeModel, folder: &str) -> InventoryFolderLocation {
+    let mut location = InventoryFolderLocation {
+        source_file: canonical_path(&tree.root.path),
+        folder_path: Vec::new(),
+        exists: true,
+    };
+    let mut current = Some(&tree.root);
+
+    for segment in split_inventory_folder_path(folder) {
+        let child = current.and_then(|folder| folder.children.iter().find(|child| child.name == segment));
+        match child {
+            // An included file shows up as a child folder pointing at that file.
+            Some(child) if canonical_path(&child.path) != location.source_file => {
+                location.source_file = canonical_path(&child.path);
+                location.folder_path.clear();
+            }
+            Some(_) => location.folder_path.push(segment),
+            None => {
+                location.exists = false;
+                location.folder_path.push(segment);
+            }
+        }
+        current = child;
+    }
+
+    location
+}
+
+/// Hosts under `folder` (including subfolders) with `protocol`, in tree order.
+pub(crate) fn list_inventory_hosts<'a>(tree: &'a InventoryTreeModel, folder: Option<&str>, protocol: Option<&str>) -> io::Result<Vec<InventoryHostEntry<'a>>> {
+    let folder_path = folder.map(split_inventory_folder_path).unwrap_or_default();
+    let start = find_tree_folder(&tree.root, &folder_path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("inventory folder '{}' was not found", folder.unwrap_or_default()),
+        )
+    })?;
+    let protocol = protocol.map(ConnectionProtocol::from);
+
+    let mut entries = collect_tree_hosts(start, &tree.hosts, folder_path);
+    entries.retain(|entry| protocol.as_ref().is_none_or(|protocol| entry.host.protocol == *protocol));
+    Ok(entries)
+}
+
+/// Find a host by name along with its tree folder path.
+pub(crate) fn find_inventory_host<'a>(tree: &'a InventoryTreeModel, host_name: &str) -> io::Result<InventoryHostEntry<'a>> {
+    collect_tree_hosts(&tree.root, &tree.hosts, Vec::new())
+        .into_iter()
+        .find(|entry| entry.host.name == host_name)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("inventory host '{host_name}' was not found")))
+}
+
+/// Render hosts as aligned `NAME PROTOCOL HOST USER PORT FOLDER` columns.
+pub(crate) fn render_inventory_host_table(entries: &[InventoryHostEntry<'_>]) -> String {
+    let header = ["NAME", "PROTOCOL", "HOST", "USER", "PORT", "FOLDER"].map(str::to_string);
+    let rows: Vec<[String; 6]> = entries
+        .iter()
+        .map(|entry| {
+            [
+                entry.host.name.clone(),
+                entry.host.protocol.as_str().to_string(),
+                entry.host.host.clone(),
+                entry.host.user.clone().unwrap_or_else(|| "-".to_string()),
+                entry.host.port.map_or_else(|| "-".to_string(), |port| port.to_string()),
+                format!("/{}", entry.folder_path.join("/")),
+            ]
+        })
+        .collect();
+
+    let mut widths = header.clone().map(|column| column.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+/// Hosts as the JSON records `cossh inventory export --format json` writes.
+pub(crate) fn inventory_hosts_json(entries: &[InventoryHostEntry<'_>]) -> JsonValue {
+    JsonValue::Array(entries.iter().map(inventory_host_json).collect())
+}
+
+/// One host as a JSON record, plus the file that defines it.
+pub(crate) fn inventory_host_json(entry: &InventoryHostEntry<'_>) -> JsonValue {
+    let mut record = json_host_record(&entry.folder_path, entry.host);
+    record.insert("source_file".to_string(), JsonValue::String(entry.host.source_file.display().to_string()));
+    if let Some(source) = &entry.host.dynamic_source {
+        record.insert("dynamic_source".to_string(), JsonValue::String(source.clone()));
+    }
+    JsonValue::Object(record)
+}
+
+/// Render one host as `key: value` lines.
+pub(crate) fn render_inventory_host_details(entry: &InventoryHostEntry<'_>) -> String {
+    let JsonValue::Object(mut record) = inventory_host_json(entry) else {
+        return String::new();
+    };
+
+    // JSON records are key-sorted; lead with the fields that identify the host.
+    let leading: Vec<_> = ["name", "host", "protocol", "folder"]
+        .into_iter()
+        .filter_map(|key| record.remove(key).map(|value| (key.to_string(), value)))
+        .collect();
+    let mut details = String::new();
+    for (key, value) in leading.into_iter().chain(record) {
+        match value {
+            JsonValue::Object(options) => {
+                for (option, values) in options {
+                    details.push_str(&format!("{key}.{option}: {}\n", json_display(&values)));
+                }
+            }
+            value => details.push_str(&format!("{key}: {}\n", json_display(&value))),
+        }
+    }
+    details
+}
+
+/// Add a host named `host_name` under `folder` from `key=value` assignments.
+///
+/// Returns the inventory file the host was written to.
+pub(crate) fn add_inventory_host(tree: &InventoryTreeModel, host_name: &str, assignments: &[(String, String)], folder: &str) -> io::Result<PathBuf> {
+    let host_name = validate_host_name(host_name)?;
+    ensure_host_name_free(tree, host_name)?;
+    let fields = parse_host_field_assignments(assignments)?;
+    if fields.iter().any(|(key, _)| key == "name") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "set the host name with the NAME argument, not name=",
+        ));
+    }
+
+    let location = locate_inventory_folder(tree, folder);
+    write_checked(tree, &[&location.source_file], || {
+        create_inventory_host_from_fields(&location.source_file, &location.folder_path, host_name, &fields)
+    })?;
+    Ok(location.source_file)
+}
+
+/// Apply `key=value` assignments to an existing host. `name=` renames it.
+pub(crate) fn set_inventory_host(tree: &InventoryTreeModel, host_name: &str, assignments: &[(String, String)]) -> io::Result<PathBuf> {
+    let host = editable_host(tree, host_name)?;
+    let fields = parse_host_field_assignments(assignments)?;
+    for (key, value) in &fields {
+        if key == "name"
+            && let Some(Value::String(new_name)) = value
+            && new_name != host_name
+        {
+            validate_host_name(new_name)?;
+            ensure_host_name_free(tree, new_name)?;
+        }
+    }
+
+    write_checked(tree, &[&host.source_file], || set_inventory_host_fields(&host.source_file, host_name, &fields))?;
+    Ok(host.source_file.clone())
+}
+
+/// Remove a host from the file that defines it.
+pub(crate) fn remove_inventory_host(tree: &InventoryTreeModel, host_name: &str) -> io::Result<PathBuf> {
+    let host = editable_host(tree, host_name)?;
+    delete_inventory_host_entry(&host.source_file, host_name)?;
+    Ok(host.source_file.clone())
+}
+
+/// Move a host to `folder`, which may live in another inventory file.
+pub(crate) fn move_inventory_host(tree: &InventoryTreeModel, host_name: &str, folder: &str) -> io::Result<InventoryFolderLocation> {
+    let host = editable_host(tree, host_name)?;
+    let location = locate_inventory_folder(tree, folder);
+    let source_file = canonical_path(&host.source_file);
+    write_checked(tree, &[&source_file, &location.source_file], || {
+        transfer_inventory_host_entry(&source_file, host_name, &location.source_file, &location.folder_path)
+    })?;
+    Ok(location)
+}
+
+/// Create `folder` and any missing parents.
+pub(crate) fn make_inventory_folder(tree: &InventoryTreeModel, folder: &str) -> io::Result<InventoryFolderLocation> {
+    let location = locate_inventory_folder(tree, folder);
+    if location.exists {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("inventory folder '{}' already exists", folder.trim()),
+        ));
+    }
+    let Some((name, parent)) = location.folder_path.split_last() else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "folder path cannot be empty"));
+    };
+    create_inventory_folder(&location.source_file, parent, name)?;
+    Ok(location)
+}
+
+/// Run `write`, then reload the inventory and restore `files` if it no longer loads,
+/// for example because a new host has no `host` or names an unknown template.
+fn write_checked(tree: &InventoryTreeModel, files: &[&Path], write: impl FnOnce() -> io::Result<()>) -> io::Result<()> {
+    let snapshots = files
+        .iter()
+        .map(|path| match fs::read_to_string(path) {
+            Ok(contents) => Ok((*path, Some(contents))),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok((*path, None)),
+            Err(err) => Err(err),
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    write()?;
+    let Err(err) = build_inventory_tree(&tree.root.path) else {
+        return Ok(());
+    };
+    for (path, contents) in snapshots {
+        match contents {
+            Some(contents) => fs::write(path, contents)?,
+            None => fs::remove_file(path)?,
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidInput, format!("change was not saved: {err}")))
+}
+
+fn editable_host<'a>(tree: &'a InventoryTreeModel, host_name: &str) -> io::Result<&'a InventoryHost> {
+    let host = find_inventory_host(tree, host_name)?.host;
+    if let Some(source) = &host.dynamic_source {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("inventory host '{host_name}' comes from source '{source}' and is read-only"),
+        ));
+    }
+    Ok(host)
+}
+
+fn validate_host_name(host_name: &str) -> io::Result<&str> {
+    let host_name = host_name.trim();
+    if host_name.is_empty() || host_name.contains(char::is_whitespace) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{host_name}' is not a valid host name; names cannot be empty or contain whitespace"),
+        ));
+    }
+    Ok(host_name)
+}
+
+fn ensure_host_name_free(tree: &InventoryTreeModel, host_name: &str) -> io::Result<()> {
+    match tree.hosts.iter().find(|host| host.name == host_name) {
+        Some(existing) => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("inventory host '{host_name}' already exists in '{}'", existing.source_file.display()),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn find_tree_folder<'a>(folder: &'a TreeFolder, folder_path: &[String]) -> Option<&'a TreeFolder> {
+    folder_path
+        .iter()
+        .try_fold(folder, |folder, segment| folder.children.iter().find(|child| child.name == *segment))
+}
+
+fn json_display(value: &JsonValue) -> String {
+    match value {
+        JsonValue::String(value) => value.clone(),
+        JsonValue::Array(values) => values.iter().map(json_display).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+#[path = "../test/inventory/manage.rs"]
+mod tests;
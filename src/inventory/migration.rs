@@ -1,9 +1,11 @@
 //! Legacy OpenSSH config migration into the YAML inventory format.
 
 use super::path::get_default_inventory_path;
-use crate::ssh_config::{SshHost, TreeFolder, get_default_ssh_config_path, parse_ssh_config_for_migration};
+use crate::ssh_config::{
+    FolderId, SshConfigRules, SshHost, SshMatchContext, TreeFolder, apply_host_option, get_default_ssh_config_path, parse_ssh_config_for_migration,
+};
 use chrono::Local;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -16,10 +18,10 @@ pub(crate) struct MigrationSummary {
     pub(crate) backup_path: Option<PathBuf>,
     /// Number of hosts written to output.
     pub(crate) hosts_written: usize,
-    /// Number of wildcard aliases skipped.
-    pub(crate) wildcard_aliases_skipped: usize,
-    /// Number of unsupported OpenSSH `Match` blocks skipped.
-    pub(crate) unsupported_blocks_skipped: usize,
+    /// Number of wildcard aliases whose settings were folded into matching hosts.
+    pub(crate) wildcard_aliases_folded: usize,
+    /// Number of OpenSSH `Match` blocks left out because they run commands or use unsupported criteria.
+    pub(crate) match_blocks_skipped: usize,
 }
 
 /// Migrate `~/.ssh/config` into the default YAML inventory path.
@@ -34,8 +36,10 @@ pub(crate) fn migrate_default_ssh_config_to_inventory() -> io::Result<MigrationS
 
 fn migrate_ssh_config_to_inventory(ssh_config_path: &Path, inventory_path: &Path) -> io::Result<MigrationSummary> {
     let parsed = parse_ssh_config_for_migration(ssh_config_path)?;
+    let rules = SshConfigRules::load(ssh_config_path)?;
     let mut render_state = RenderState::default();
-    let rendered = render_inventory_document(&parsed.root, &parsed.hosts, &mut render_state)?;
+    let hosts = fold_shared_settings(&parsed.root, &parsed.hosts, &rules, &mut render_state);
+    let rendered = render_inventory_document(&parsed.root, &hosts, &mut render_state)?;
 
     let backup_path = backup_existing_inventory(inventory_path)?;
     if let Some(parent) = inventory_path.parent() {
@@ -48,37 +52,163 @@ fn migrate_ssh_config_to_inventory(ssh_config_path: &Path, inventory_path: &Path
         output_path: inventory_path.to_path_buf(),
         backup_path,
         hosts_written: render_state.hosts_written,
-        wildcard_aliases_skipped: render_state.wildcard_aliases_skipped,
-        unsupported_blocks_skipped: parsed.unsupported_blocks,
+        wildcard_aliases_folded: render_state.wildcard_aliases_skipped,
+        match_blocks_skipped: rules.unsupported_match_blocks(false),
     })
 }
 
-fn backup_existing_inventory(inventory_path: &Path) -> io::Result<Option<PathBuf>> {
+/// Settings a host gets from `Match` blocks and `Host` patterns that do not name it.
+type SharedSettings = BTreeMap<String, Vec<String>>;
+
+/// Resolve each host against the whole SSH config the way `ssh` would, then
+/// move settings shared by every host below a folder into its `defaults:`.
+///
+/// `Match exec` blocks are not run, so their settings are left out.
+fn fold_shared_settings(root: &TreeFolder, hosts: &[SshHost], rules: &SshConfigRules, state: &mut RenderState) -> Vec<SshHost> {
+    let mut migrated = Vec::with_capacity(hosts.len());
+    let mut shared = HashMap::new();
+
+    for (host_idx, host) in hosts.iter().enumerate() {
+        if is_wildcard_alias(&host.name) {
+            migrated.push(host.clone());
+            continue;
+        }
+
+        let mut context = SshMatchContext::new(host.name.clone());
+        context.run_exec = false;
+        let effective = rules.resolve(&context);
+
+        // A keyword set by the host's own block keeps all of its values on the host,
+        // since inventory lists replace inherited ones instead of adding to them.
+        let own_keywords: HashSet<&str> = effective
+            .options
+            .iter()
+            .filter(|option| !option.shared || option.keyword == "hostname")
+            .map(|option| option.keyword.as_str())
+            .collect();
+
+        let mut rebuilt = SshHost {
+            protocol: host.protocol.clone(),
+            description: host.description.clone(),
+            profile: host.profile.clone(),
+            pass_key: host.pass_key.clone(),
+            rdp_domain: host.rdp_domain.clone(),
+            rdp_args: host.rdp_args.clone(),
+            hidden: host.hidden,
+            ..SshHost::new(host.name.clone())
+        };
+        let mut host_shared = SharedSettings::new();
+        for option in &effective.options {
+            if own_keywords.contains(option.keyword.as_str()) {
+                apply_host_option(&mut rebuilt, &option.keyword, &option.value);
+            } else {
+                host_shared.entry(option.keyword.clone()).or_default().push(option.value.clone());
+            }
+        }
+
+        shared.insert(host_idx, host_shared);
+        migrated.push(rebuilt);
+    }
+
+    hoist_folder_defaults(root, &mut shared, &mut state.folder_defaults);
+    for (host_idx, settings) in shared {
+        for (keyword, values) in settings {
+            for value in values {
+                apply_host_option(&mut migrated[host_idx], &keyword, &value);
+            }
+        }
+    }
+
+    migrated
+}
+
+fn hoist_folder_defaults(folder: &TreeFolder, shared: &mut HashMap<usize, SharedSettings>, defaults: &mut HashMap<FolderId, SshHost>) {
+    let mut subtree = Vec::new();
+    collect_subtree_hosts(folder, &mut subtree);
+
+    let mut members = subtree.iter().filter_map(|host_idx| shared.get(host_idx));
+    if let Some(first) = members.next() {
+        let mut common = first.clone();
+        for settings in members {
+            common.retain(|keyword, values| settings.get(keyword) == Some(values));
+        }
+
+        if !common.is_empty() {
+            for host_idx in &subtree {
+                if let Some(settings) = shared.get_mut(host_idx) {
+                    settings.retain(|keyword, _| !common.contains_key(keyword));
+                }
+            }
+
+            let mut folder_defaults = SshHost::new(String::new());
+            for (keyword, values) in &common {
+                for value in values {
+                    apply_host_option(&mut folder_defaults, keyword, value);
+                }
+            }
+            defaults.insert(folder.id, folder_defaults);
+        }
+    }
+
+    for child in &folder.children {
+        hoist_folder_defaults(child, shared, defaults);
+    }
+}
+
+fn collect_subtree_hosts(folder: &TreeFolder, host_indices: &mut Vec<usize>) {
+    host_indices.extend_from_slice(&folder.host_indices);
+    for child in &folder.children {
+        collect_subtree_hosts(child, host_indices);
+    }
+}
+
+fn is_wildcard_alias(name: &str) -> bool {
+    name.contains('*') || name.contains('?') || name.starts_with('!')
+}
+
+/// Copy an existing inventory file aside before it is replaced.
+pub(super) fn backup_existing_inventory(inventory_path: &Path) -> io::Result<Option<PathBuf>> {
     if !inventory_path.exists() {
         return Ok(None);
     }
 
     let timestamp = Local::now().format("%Y%m%dT%H%M%S");
-    let backup_name = format!("cossh-inventory.yaml.bak-{timestamp}");
+    let file_name = inventory_path
+        .file_name()
+        .and_then(|segment| segment.to_str())
+        .unwrap_or("cossh-inventory.yaml");
+    let backup_name = format!("{file_name}.bak-{timestamp}");
     let backup_path = inventory_path.with_file_name(backup_name);
     fs::copy(inventory_path, &backup_path)?;
     Ok(Some(backup_path))
 }
 
 #[derive(Default)]
-struct RenderState {
+pub(super) struct RenderState {
     seen_names: HashSet<String>,
-    hosts_written: usize,
-    wildcard_aliases_skipped: usize,
+    pub(super) hosts_written: usize,
+    pub(super) wildcard_aliases_skipped: usize,
+    /// `defaults:` settings by folder, rendered ahead of the folder's hosts.
+    pub(super) folder_defaults: HashMap<FolderId, SshHost>,
 }
 
-fn render_inventory_document(root: &TreeFolder, hosts: &[SshHost], state: &mut RenderState) -> io::Result<String> {
+/// Render `hosts`, arranged by the folder tree under `root`, as an inventory file.
+///
+/// Folders are named after the stem of their source file, or after their
+/// `name` when they have no path.
+pub(super) fn render_inventory_document(root: &TreeFolder, hosts: &[SshHost], state: &mut RenderState) -> io::Result<String> {
     let items = render_root_items(root, hosts, state)?;
     if items.is_empty() {
         return Ok("inventory: []\n".to_string());
     }
 
-    let mut output = String::from("inventory:\n\n");
+    let mut output = String::new();
+    if let Some(defaults) = state.folder_defaults.get(&root.id) {
+        output.push_str("defaults:\n");
+        output.push_str(&render_host_fields(defaults, 2).join("\n"));
+        output.push_str("\n\n");
+    }
+    output.push_str("inventory:\n\n");
     output.push_str(&items.join("\n\n"));
     output.push('\n');
     Ok(output)
@@ -106,6 +236,10 @@ fn render_root_items(root: &TreeFolder, hosts: &[SshHost], state: &mut RenderSta
 
 fn render_folder_item(folder: &TreeFolder, hosts: &[SshHost], indent: usize, state: &mut RenderState) -> io::Result<Option<String>> {
     let mut nested_items = Vec::new();
+    let defaults = state
+        .folder_defaults
+        .get(&folder.id)
+        .map(|defaults| format!("{}- defaults:\n{}", " ".repeat(indent + 4), render_host_fields(defaults, indent + 8).join("\n")));
 
     for &host_idx in &folder.host_indices {
         if let Some(host) = hosts.get(host_idx)
@@ -124,6 +258,9 @@ fn render_folder_item(folder: &TreeFolder, hosts: &[SshHost], indent: usize, sta
     if nested_items.is_empty() {
         return Ok(None);
     }
+    if let Some(defaults) = defaults {
+        nested_items.insert(0, defaults);
+    }
 
     let indent_str = " ".repeat(indent);
     Ok(Some(format!(
@@ -134,7 +271,7 @@ fn render_folder_item(folder: &TreeFolder, hosts: &[SshHost], indent: usize, sta
 }
 
 fn render_host_item(host: &SshHost, indent: usize, state: &mut RenderState) -> io::Result<Option<String>> {
-    if host.name.contains('*') || host.name.contains('?') {
+    if is_wildcard_alias(&host.name) {
         state.wildcard_aliases_skipped += 1;
         return Ok(None);
     }
@@ -149,7 +286,6 @@ fn render_host_item(host: &SshHost, indent: usize, state: &mut RenderState) -> i
     state.hosts_written += 1;
     let indent_str = " ".repeat(indent);
     let field_indent = " ".repeat(indent + 2);
-    let list_indent = " ".repeat(indent + 4);
 
     let mut lines = Vec::new();
     lines.push(format!("{indent_str}- name: {}", quote_yaml_string(&host.name)));
@@ -162,7 +298,17 @@ fn render_host_item(host: &SshHost, indent: usize, state: &mut RenderState) -> i
         "{field_indent}host: {}",
         quote_yaml_string(host.hostname.as_deref().unwrap_or(&host.name))
     ));
+    lines.extend(render_host_fields(host, indent + 2));
+
+    Ok(Some(lines.join("\n")))
+}
+
+/// Render the connection settings of `host` as mapping lines at `indent`.
+fn render_host_fields(host: &SshHost, indent: usize) -> Vec<String> {
+    let field_indent = " ".repeat(indent);
+    let list_indent = " ".repeat(indent + 2);
 
+    let mut lines = Vec::new();
     if let Some(user) = host.user.as_ref() {
         lines.push(format!("{field_indent}user: {}", quote_yaml_string(user)));
     }
@@ -239,7 +385,7 @@ fn render_host_item(host: &SshHost, indent: usize, state: &mut RenderState) -> i
                     }
                 } else {
                     lines.push(format!("{list_indent}{}:", quote_yaml_key(key)));
-                    let nested_indent = " ".repeat(indent + 6);
+                    let nested_indent = " ".repeat(indent + 4);
                     for value in values {
                         lines.push(format!("{nested_indent}- {}", quote_yaml_string(value)));
                     }
@@ -248,7 +394,7 @@ fn render_host_item(host: &SshHost, indent: usize, state: &mut RenderState) -> i
         }
     }
 
-    Ok(Some(lines.join("\n")))
+    lines
 }
 
 fn folder_display_name(folder: &TreeFolder) -> String {
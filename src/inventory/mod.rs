@@ -1,13 +1,21 @@
-//! YAML inventory loading and SSH config migration.
+//! YAML inventory loading, editing and checks, SSH config migration and sync, imports and exports.
 
+mod ansible;
+mod check;
 mod edit;
 mod error;
+mod export;
+mod import;
 mod include;
+mod interpolate;
+mod manage;
 mod migration;
 mod model;
 mod normalize;
 mod parse;
 mod path;
+mod source;
+mod sync;
 mod tree;
 mod watcher;
 
@@ -17,11 +25,23 @@ pub use path::{expand_tilde, get_default_inventory_path};
 use std::io;
 use std::path::Path;
 
+pub(crate) use ansible::import_ansible_inventory;
+pub(crate) use check::{CheckSeverity, InventoryCheckContext, check_inventory};
 pub(crate) use edit::{
     EditableInventoryHost, create_inventory_folder, create_inventory_host_entry, delete_inventory_folder, delete_inventory_host_entry,
-    move_inventory_host_entry, relocate_inventory_folder, update_inventory_host_entry,
+    move_inventory_host_entry, relocate_inventory_folder, rename_vault_references_in_inventory, update_inventory_host_entry,
+};
+pub(crate) use export::{InventoryExportFormat, render_inventory_export, ssh_forward_arguments, write_inventory_export};
+pub(crate) use import::{ImportConflict, ImportedHostAction, SessionImportFormat, SessionImportOptions, SessionImportSummary, import_sessions};
+pub(crate) use include::matches_pattern;
+pub(crate) use manage::{
+    add_inventory_host, find_inventory_host, inventory_host_json, inventory_hosts_json, list_inventory_hosts, make_inventory_folder, move_inventory_host,
+    remove_inventory_host, render_inventory_host_details, render_inventory_host_table, set_inventory_host,
 };
 pub(crate) use migration::migrate_default_ssh_config_to_inventory;
+pub(crate) use sync::{
+    FieldSync, HostSyncAction, InventorySyncPlan, InventorySyncSummary, SyncSide, apply_inventory_sync, plan_inventory_sync, sync_default_ssh_config,
+};
 pub(crate) use tree::sort_tree_folder_by_host_name;
 pub(crate) use watcher::{InventoryWatchPlan, build_inventory_watch_plan, should_reload_for_inventory_event};
 
@@ -49,13 +69,36 @@ pub(crate) fn build_inventory_tree(inventory_path: &Path) -> io::Result<Inventor
     tree::build_inventory_tree(inventory_path)
 }
 
+/// Build a tree model like [`build_inventory_tree`], running every command
+/// `source:` again instead of reusing its cached output.
+pub(crate) fn refresh_inventory_tree(inventory_path: &Path) -> io::Result<InventoryTreeModel> {
+    tree::refresh_inventory_tree(inventory_path)
+}
+
+/// Build a tree model like [`build_inventory_tree`] without running any
+/// command `source:`. Cached output of any age is used, a source without one
+/// loads empty, and the tree's `source_refresh_due` reports when to refresh.
+pub(crate) fn build_cached_inventory_tree(inventory_path: &Path) -> io::Result<InventoryTreeModel> {
+    tree::build_cached_inventory_tree(inventory_path)
+}
+
 /// Load the default inventory (`~/.color-ssh/cossh-inventory.yaml`).
 pub(crate) fn load_inventory_tree() -> io::Result<InventoryTreeModel> {
+    load_default_inventory_tree_with(build_inventory_tree)
+}
+
+/// Load the default inventory like [`load_inventory_tree`] from cached
+/// command source output only.
+pub(crate) fn load_cached_inventory_tree() -> io::Result<InventoryTreeModel> {
+    load_default_inventory_tree_with(build_cached_inventory_tree)
+}
+
+fn load_default_inventory_tree_with(build: fn(&Path) -> io::Result<InventoryTreeModel>) -> io::Result<InventoryTreeModel> {
     let inventory_path = get_default_inventory_path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Could not find home directory"))?;
 
     if !inventory_path.exists() {
         return Ok(InventoryTreeModel::empty(inventory_path));
     }
 
-    build_inventory_tree(&inventory_path)
+    build(&inventory_path)
 }
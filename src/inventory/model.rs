@@ -1,10 +1,11 @@
 //! Inventory domain models.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::Infallible;
 use std::fmt;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::{Duration, SystemTime};
 
 /// Stable folder identifier used by the TUI tree.
 pub type FolderId = usize;
@@ -77,6 +78,10 @@ pub struct SshHostOptions {
     pub identity_files: Vec<String>,
     /// Equivalent to SSH `IdentitiesOnly`.
     pub identities_only: Option<bool>,
+    /// Vault entry holding this host's private key or the passphrase for its identity file.
+    pub vault_key: Option<String>,
+    /// Forward the local vault agent so `cossh` on this host can request askpass secrets.
+    pub vault_forward: bool,
     /// SSH `ProxyJump`.
     pub proxy_jump: Option<String>,
     /// SSH `ProxyCommand`.
@@ -129,6 +134,19 @@ pub struct InventoryHost {
     pub source_file: PathBuf,
     /// Folder path from root to this host.
     pub source_folder_path: Vec<String>,
+    /// Template named by the host's `template:` key.
+    pub template: Option<String>,
+    /// Fields taken from the host's template or `defaults:` blocks rather than
+    /// set on the host itself, as canonical inventory keys (`user`,
+    /// `proxy_jump`, ...). Inherited `ssh_options` entries are recorded as
+    /// `ssh_options.<Option>`.
+    pub inherited_fields: BTreeSet<String>,
+    /// Values the host sets itself that contained `${...}` variables, as
+    /// written before interpolation and keyed like `inherited_fields`.
+    pub templated_values: BTreeMap<String, Vec<String>>,
+    /// Name of the `source:` include that produced this host. Such hosts are
+    /// owned by the external source and are not edited in place.
+    pub dynamic_source: Option<String>,
 }
 
 impl InventoryHost {
@@ -148,8 +166,22 @@ impl InventoryHost {
             rdp: RdpHostOptions::default(),
             source_file: PathBuf::new(),
             source_folder_path: Vec::new(),
+            template: None,
+            inherited_fields: BTreeSet::new(),
+            templated_values: BTreeMap::new(),
+            dynamic_source: None,
         }
     }
+
+    /// Whether `field` (a canonical inventory key) came from a template or `defaults:` block.
+    pub fn is_inherited(&self, field: &str) -> bool {
+        self.inherited_fields.contains(field)
+    }
+
+    /// Whether the `ssh_options` entry `option` came from a template or `defaults:` block.
+    pub fn is_inherited_ssh_option(&self, option: &str) -> bool {
+        self.inherited_fields.contains(&format!("ssh_options.{option}"))
+    }
 }
 
 /// Tree folder node used by the TUI tree.
@@ -174,6 +206,8 @@ pub struct InventoryTreeModel {
     pub root: TreeFolder,
     /// Flattened hosts in tree traversal order.
     pub hosts: Vec<InventoryHost>,
+    /// Earliest time the cached output of a command `source:` goes stale.
+    pub source_refresh_due: Option<SystemTime>,
 }
 
 impl InventoryTreeModel {
@@ -192,6 +226,7 @@ impl InventoryTreeModel {
                 host_indices: Vec::new(),
             },
             hosts: Vec::new(),
+            source_refresh_due: None,
         }
     }
 }
@@ -199,24 +234,65 @@ impl InventoryTreeModel {
 #[derive(Debug, Clone, Default)]
 pub(super) struct ParsedInventoryDocument {
     pub include: Vec<InventoryIncludeRaw>,
+    /// `source:` entries from the `include:` block.
+    pub sources: Vec<InventorySourceRaw>,
+    /// File-level `defaults:` applied to every host in the file and its includes.
+    pub defaults: InventoryHostRaw,
+    /// Host templates usable by hosts in the file and its includes.
+    pub templates: InventoryTemplates,
     pub inventory: Vec<InventoryNodeRaw>,
 }
 
+/// Named host templates (`templates:`), keyed by template name.
+pub(super) type InventoryTemplates = BTreeMap<String, InventoryHostRaw>;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(super) struct InventoryIncludeRaw {
     pub pattern: String,
     pub folder_path: Vec<String>,
 }
 
+/// A dynamic inventory `source:` whose hosts are loaded under folder `name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct InventorySourceRaw {
+    pub name: String,
+    pub kind: InventorySourceKind,
+    pub format: Option<InventorySourceFormat>,
+    /// How long a command's cached output is reused before it runs again.
+    pub refresh: Duration,
+    pub folder_path: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum InventorySourceKind {
+    /// Executable printing JSON on stdout, Ansible dynamic inventory style.
+    Command { program: String, args: Vec<String> },
+    /// CSV or JSON export, for example from NetBox or Nautobot.
+    File(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum InventorySourceFormat {
+    Json,
+    Csv,
+}
+
 #[derive(Debug, Clone)]
 pub(super) enum InventoryNodeRaw {
     Host(Box<InventoryHostRaw>),
-    Folder { name: String, items: Vec<InventoryNodeRaw> },
+    Folder {
+        name: String,
+        defaults: Box<InventoryHostRaw>,
+        items: Vec<InventoryNodeRaw>,
+    },
 }
 
+/// Host fields as written in YAML; unset fields are `None` or empty so
+/// templates and `defaults:` blocks, which share this shape, can fill them in.
 #[derive(Debug, Clone, Default)]
 pub(super) struct InventoryHostRaw {
     pub name: String,
+    pub template: Option<String>,
     pub description: Option<String>,
     pub protocol: ConnectionProtocol,
     pub host: Option<String>,
@@ -227,6 +303,8 @@ pub(super) struct InventoryHostRaw {
     pub hidden: bool,
     pub identity_files: Vec<String>,
     pub identities_only: Option<bool>,
+    pub vault_key: Option<String>,
+    pub vault_forward: Option<bool>,
     pub proxy_jump: Option<String>,
     pub proxy_command: Option<String>,
     pub forward_agent: Option<String>,
@@ -1,10 +1,81 @@
+//! Host normalization, template and `defaults:` inheritance, and interpolation.
+//!
+//! Defaults are layered from the outside in: the including file's defaults,
+//! then the included file's root `defaults:`, then each enclosing folder's
+//! `- defaults:` entry, innermost last. The host's `template:` sits above
+//! all defaults, and a value set on the host always wins. Scalars and lists
+//! are taken whole from the innermost layer that sets them; `ssh_options`
+//! are merged per option. `${...}` variables are expanded after merging, so
+//! a template value such as `${name}.mgmt.example` resolves per host.
+
 use super::error::{InventoryResult, invalid_inventory};
-use super::model::{InventoryHost, InventoryHostRaw, RdpHostOptions, SshHostOptions};
+use super::interpolate::{InterpolationContext, has_interpolation, interpolate};
+use super::model::{InventoryHost, InventoryHostRaw, InventoryTemplates, RdpHostOptions, SshHostOptions};
 use super::path::expand_tilde;
-use crate::args::validate_vault_entry_name;
+use crate::args::{validate_secret_reference, validate_vault_entry_name};
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::Path;
 
-pub(super) fn normalize_inventory_host(raw: InventoryHostRaw, source_file: &Path, folder_path: &[String]) -> InventoryResult<InventoryHost> {
+/// Fill the fields `target` leaves unset from `defaults`, returning the
+/// canonical keys that were filled in.
+pub(super) fn inherit_host_defaults(target: &mut InventoryHostRaw, defaults: &InventoryHostRaw) -> BTreeSet<String> {
+    fn inherit<T: Clone>(field: &mut Option<T>, default: &Option<T>, key: &str, inherited: &mut BTreeSet<String>) {
+        if field.is_none() && default.is_some() {
+            field.clone_from(default);
+            inherited.insert(key.to_string());
+        }
+    }
+    fn inherit_list(field: &mut Vec<String>, default: &[String], key: &str, inherited: &mut BTreeSet<String>) {
+        if field.is_empty() && !default.is_empty() {
+            *field = default.to_vec();
+            inherited.insert(key.to_string());
+        }
+    }
+
+    let mut inherited = BTreeSet::new();
+    inherit(&mut target.description, &defaults.description, "description", &mut inherited);
+    inherit(&mut target.host, &defaults.host, "host", &mut inherited);
+    inherit(&mut target.user, &defaults.user, "user", &mut inherited);
+    inherit(&mut target.port, &defaults.port, "port", &mut inherited);
+    inherit(&mut target.profile, &defaults.profile, "profile", &mut inherited);
+    inherit(&mut target.vault_pass, &defaults.vault_pass, "vault_pass", &mut inherited);
+    inherit_list(&mut target.identity_files, &defaults.identity_files, "identity_file", &mut inherited);
+    inherit(&mut target.identities_only, &defaults.identities_only, "identities_only", &mut inherited);
+    inherit(&mut target.vault_key, &defaults.vault_key, "vault_key", &mut inherited);
+    inherit(&mut target.vault_forward, &defaults.vault_forward, "vault_forward", &mut inherited);
+    inherit(&mut target.proxy_jump, &defaults.proxy_jump, "proxy_jump", &mut inherited);
+    inherit(&mut target.proxy_command, &defaults.proxy_command, "proxy_command", &mut inherited);
+    inherit(&mut target.forward_agent, &defaults.forward_agent, "forward_agent", &mut inherited);
+    inherit_list(&mut target.local_forward, &defaults.local_forward, "local_forward", &mut inherited);
+    inherit_list(&mut target.remote_forward, &defaults.remote_forward, "remote_forward", &mut inherited);
+    for (option, values) in &defaults.ssh_options {
+        if !target.ssh_options.contains_key(option) {
+            target.ssh_options.insert(option.clone(), values.clone());
+            inherited.insert(format!("ssh_options.{option}"));
+        }
+    }
+    inherit(&mut target.rdp_domain, &defaults.rdp_domain, "rdp_domain", &mut inherited);
+    inherit_list(&mut target.rdp_args, &defaults.rdp_args, "rdp_args", &mut inherited);
+    inherited
+}
+
+pub(super) fn normalize_inventory_host(
+    mut raw: InventoryHostRaw,
+    defaults: &InventoryHostRaw,
+    templates: &InventoryTemplates,
+    source_file: &Path,
+    folder_path: &[String],
+) -> InventoryResult<InventoryHost> {
+    let mut inherited_fields = BTreeSet::new();
+    if let Some(template_name) = raw.template.as_deref() {
+        let template = templates
+            .get(template_name)
+            .ok_or_else(|| invalid_inventory(source_file, format!("inventory host '{}' uses unknown template '{}'", raw.name, template_name)))?;
+        inherited_fields = inherit_host_defaults(&mut raw, template);
+    }
+    inherited_fields.append(&mut inherit_host_defaults(&mut raw, defaults));
+    let templated_values = interpolate_host_fields(&mut raw, &inherited_fields, source_file, folder_path)?;
+
     let host = raw
         .host
         .clone()
@@ -12,7 +83,7 @@ pub(super) fn normalize_inventory_host(raw: InventoryHostRaw, source_file: &Path
         .ok_or_else(|| invalid_inventory(source_file, format!("inventory host '{}' is missing required field 'host'", raw.name)))?;
 
     if let Some(vault_pass) = raw.vault_pass.as_deref()
-        && !validate_vault_entry_name(vault_pass)
+        && !validate_secret_reference(vault_pass)
     {
         return Err(invalid_inventory(
             source_file,
@@ -20,6 +91,15 @@ pub(super) fn normalize_inventory_host(raw: InventoryHostRaw, source_file: &Path
         ));
     }
 
+    if let Some(vault_key) = raw.vault_key.as_deref()
+        && !validate_vault_entry_name(vault_key)
+    {
+        return Err(invalid_inventory(
+            source_file,
+            format!("inventory host '{}' has invalid vault_key '{}'", raw.name, vault_key),
+        ));
+    }
+
     Ok(InventoryHost {
         name: raw.name,
         description: raw.description,
@@ -33,6 +113,8 @@ pub(super) fn normalize_inventory_host(raw: InventoryHostRaw, source_file: &Path
         ssh: SshHostOptions {
             identity_files: raw.identity_files.into_iter().map(|value| expand_tilde(&value)).collect(),
             identities_only: raw.identities_only,
+            vault_key: raw.vault_key,
+            vault_forward: raw.vault_forward.unwrap_or(false),
             proxy_jump: raw.proxy_jump,
             proxy_command: raw.proxy_command,
             forward_agent: raw.forward_agent,
@@ -54,5 +136,54 @@ pub(super) fn normalize_inventory_host(raw: InventoryHostRaw, source_file: &Path
         },
         source_file: source_file.to_path_buf(),
         source_folder_path: folder_path.to_vec(),
+        template: raw.template,
+        inherited_fields,
+        templated_values,
+        dynamic_source: None,
     })
 }
+
+/// Expand `${...}` variables in the fields that support them, returning the
+/// host's own values as written before expansion.
+fn interpolate_host_fields(
+    raw: &mut InventoryHostRaw,
+    inherited_fields: &BTreeSet<String>,
+    source_file: &Path,
+    folder_path: &[String],
+) -> InventoryResult<BTreeMap<String, Vec<String>>> {
+    let name = raw.name.clone();
+    let context = InterpolationContext {
+        name: &name,
+        folder: folder_path.last().map(String::as_str),
+    };
+    let mut templated_values = BTreeMap::new();
+    let mut expand = |key: String, value: &mut String| -> InventoryResult<()> {
+        if !has_interpolation(value) {
+            return Ok(());
+        }
+        let expanded = interpolate(value, context)
+            .map_err(|err| invalid_inventory(source_file, format!("inventory host '{name}' has an invalid '{key}' value: {err}")))?;
+        let original = std::mem::replace(value, expanded);
+        if !inherited_fields.contains(&key) {
+            templated_values.entry(key).or_insert_with(Vec::new).push(original);
+        }
+        Ok(())
+    };
+
+    for (key, field) in [
+        ("host", &mut raw.host),
+        ("user", &mut raw.user),
+        ("proxy_jump", &mut raw.proxy_jump),
+        ("description", &mut raw.description),
+    ] {
+        if let Some(value) = field.as_mut() {
+            expand(key.to_string(), value)?;
+        }
+    }
+    for (option, values) in &mut raw.ssh_options {
+        for value in values {
+            expand(format!("ssh_options.{option}"), value)?;
+        }
+    }
+    Ok(templated_values)
+}
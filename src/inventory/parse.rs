@@ -1,10 +1,16 @@
 //! YAML inventory parser and normalization helpers.
 
 use super::error::{InventoryResult, invalid_inventory};
-use super::model::{ConnectionProtocol, InventoryHostRaw, InventoryIncludeRaw, InventoryNodeRaw, ParsedInventoryDocument};
+use super::model::{
+    ConnectionProtocol, InventoryHostRaw, InventoryIncludeRaw, InventoryNodeRaw, InventorySourceFormat, InventorySourceKind, InventorySourceRaw,
+    InventoryTemplates, ParsedInventoryDocument,
+};
+use super::normalize::inherit_host_defaults;
+use super::source::DEFAULT_SOURCE_REFRESH;
 use serde_yml::{Mapping, Value};
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
 
 pub(super) fn parse_inventory_document(inventory_path: &Path) -> InventoryResult<ParsedInventoryDocument> {
     let contents = fs::read_to_string(inventory_path)
@@ -19,34 +25,92 @@ fn parse_inventory_document_value(yaml: &Value, source_file: &Path) -> Inventory
         return Err(invalid_inventory(source_file, "inventory file root must be a mapping"));
     };
 
-    let include = match mapping_value(mapping, "include") {
+    let (include, sources) = match mapping_value(mapping, "include") {
         Some(value) => parse_include_entries(value, source_file)?,
-        None => Vec::new(),
+        None => (Vec::new(), Vec::new()),
     };
-    let inventory = match mapping_value(mapping, "inventory") {
+    let file_defaults = match mapping_value(mapping, "defaults") {
+        Some(value) => parse_inventory_defaults(value, source_file)?,
+        None => InventoryHostRaw::default(),
+    };
+    let (mut defaults, inventory) = match mapping_value(mapping, "inventory") {
         Some(value) => parse_inventory_nodes(value, source_file)?,
-        None => Vec::new(),
+        None => (InventoryHostRaw::default(), Vec::new()),
+    };
+    // A `- defaults:` item in the root list refines the file-level block.
+    inherit_host_defaults(&mut defaults, &file_defaults);
+    let templates = match mapping_value(mapping, "templates") {
+        Some(value) => parse_inventory_templates(value, source_file)?,
+        None => InventoryTemplates::new(),
+    };
+
+    Ok(ParsedInventoryDocument {
+        include,
+        sources,
+        defaults,
+        templates,
+        inventory,
+    })
+}
+
+fn parse_inventory_templates(value: &Value, source_file: &Path) -> InventoryResult<InventoryTemplates> {
+    let mapping = match value {
+        Value::Null => return Ok(InventoryTemplates::new()),
+        Value::Mapping(mapping) => mapping,
+        _ => return Err(invalid_inventory(source_file, "templates must be a mapping of template names to host fields")),
     };
 
-    Ok(ParsedInventoryDocument { include, inventory })
+    let mut templates = InventoryTemplates::new();
+    for (template_name, fields) in mapping {
+        let Value::Mapping(fields) = fields else {
+            return Err(invalid_inventory(
+                source_file,
+                format!("template '{template_name}' must be a mapping of host fields"),
+            ));
+        };
+        if let Some(key) = fields
+            .keys()
+            .find(|key| matches!(canonical_host_key(key), "name" | "protocol" | "hidden" | "template"))
+        {
+            return Err(invalid_inventory(
+                source_file,
+                format!("template '{template_name}' cannot set '{key}'; it must be given on each host"),
+            ));
+        }
+        templates.insert(template_name.clone(), parse_host_fields(fields, source_file)?);
+    }
+    Ok(templates)
 }
 
-fn parse_include_entries(value: &Value, source_file: &Path) -> InventoryResult<Vec<InventoryIncludeRaw>> {
+fn parse_include_entries(value: &Value, source_file: &Path) -> InventoryResult<(Vec<InventoryIncludeRaw>, Vec<InventorySourceRaw>)> {
     let mut include = Vec::new();
-    parse_include_entries_with_path(value, source_file, &[], &mut include)?;
-    Ok(include)
+    let mut sources = Vec::new();
+    parse_include_entries_with_path(value, source_file, &[], &mut include, &mut sources)?;
+    Ok((include, sources))
 }
 
-fn parse_include_entries_with_path(value: &Value, source_file: &Path, folder_path: &[String], include: &mut Vec<InventoryIncludeRaw>) -> InventoryResult<()> {
+fn parse_include_entries_with_path(
+    value: &Value,
+    source_file: &Path,
+    folder_path: &[String],
+    include: &mut Vec<InventoryIncludeRaw>,
+    sources: &mut Vec<InventorySourceRaw>,
+) -> InventoryResult<()> {
     match value {
         Value::Null => Ok(()),
         Value::Sequence(sequence) => {
             for item in sequence {
-                parse_include_entries_with_path(item, source_file, folder_path, include)?;
+                parse_include_entries_with_path(item, source_file, folder_path, include, sources)?;
             }
             Ok(())
         }
-        Value::Mapping(mapping) => parse_include_mapping(mapping, source_file, folder_path, include),
+        Value::Mapping(mapping) => match source_entry_value(mapping) {
+            Some(source) => {
+                sources.push(parse_inventory_source(source, source_file, folder_path)?);
+                Ok(())
+            }
+            None => parse_include_mapping(mapping, source_file, folder_path, include, sources),
+        },
         _ => {
             let pattern = scalar_to_string(value, source_file, "include")?;
             include.push(InventoryIncludeRaw {
@@ -58,22 +122,178 @@ fn parse_include_entries_with_path(value: &Value, source_file: &Path, folder_pat
     }
 }
 
-fn parse_include_mapping(mapping: &Mapping, source_file: &Path, folder_path: &[String], include: &mut Vec<InventoryIncludeRaw>) -> InventoryResult<()> {
+fn parse_include_mapping(
+    mapping: &Mapping,
+    source_file: &Path,
+    folder_path: &[String],
+    include: &mut Vec<InventoryIncludeRaw>,
+    sources: &mut Vec<InventorySourceRaw>,
+) -> InventoryResult<()> {
     for (raw_folder_name, value) in mapping {
         let folder_name = raw_folder_name.clone();
         let mut nested_folder_path = folder_path.to_vec();
         nested_folder_path.push(folder_name);
-        parse_include_entries_with_path(value, source_file, &nested_folder_path, include)?;
+        parse_include_entries_with_path(value, source_file, &nested_folder_path, include, sources)?;
     }
     Ok(())
 }
 
-fn parse_inventory_nodes(value: &Value, source_file: &Path) -> InventoryResult<Vec<InventoryNodeRaw>> {
+/// Value of a `- source: {...}` include item; a `source` key holding patterns is a folder.
+fn source_entry_value(mapping: &Mapping) -> Option<&Mapping> {
+    if mapping.len() != 1 {
+        return None;
+    }
+    match mapping.iter().next()? {
+        (key, Value::Mapping(source)) if compact_key(key) == "source" => Some(source),
+        _ => None,
+    }
+}
+
+fn parse_inventory_source(mapping: &Mapping, source_file: &Path, folder_path: &[String]) -> InventoryResult<InventorySourceRaw> {
+    let mut name = None;
+    let mut command = None;
+    let mut args = None;
+    let mut file = None;
+    let mut format = None;
+    let mut refresh = None;
+    for (key, value) in mapping {
+        match compact_key(key).as_str() {
+            "name" => name = Some(scalar_to_string(value, source_file, "source name")?),
+            "command" => command = Some(scalar_to_string(value, source_file, "source command")?),
+            "args" => args = Some(parse_string_list(value, source_file, "source args", false)?),
+            "file" => file = Some(scalar_to_string(value, source_file, "source file")?),
+            "format" => {
+                format = Some(
+                    match scalar_to_string(value, source_file, "source format")?.trim().to_ascii_lowercase().as_str() {
+                        "json" => InventorySourceFormat::Json,
+                        "csv" => InventorySourceFormat::Csv,
+                        other => return Err(invalid_inventory(source_file, format!("unsupported source format '{other}'; use json or csv"))),
+                    },
+                );
+            }
+            "refresh" => refresh = Some(parse_refresh_interval(value, source_file)?),
+            _ => return Err(invalid_inventory(source_file, format!("unknown source key '{key}'"))),
+        }
+    }
+
+    let name = name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty() && !name.contains('/'))
+        .ok_or_else(|| invalid_inventory(source_file, "source entries need a 'name' (the folder its hosts are loaded into) without '/'"))?;
+    let kind = match (command, file) {
+        (Some(program), None) => {
+            if format == Some(InventorySourceFormat::Csv) {
+                return Err(invalid_inventory(source_file, format!("source '{name}' runs a command, which must print JSON")));
+            }
+            InventorySourceKind::Command {
+                program,
+                // Ansible calls inventory scripts with `--list`.
+                args: args.unwrap_or_else(|| vec!["--list".to_string()]),
+            }
+        }
+        (None, Some(path)) => {
+            if args.is_some() || refresh.is_some() {
+                return Err(invalid_inventory(
+                    source_file,
+                    format!("source '{name}' reads a file; 'args' and 'refresh' only apply to command sources"),
+                ));
+            }
+            InventorySourceKind::File(path)
+        }
+        _ => {
+            return Err(invalid_inventory(
+                source_file,
+                format!("source '{name}' must set exactly one of 'command' or 'file'"),
+            ));
+        }
+    };
+
+    Ok(InventorySourceRaw {
+        name,
+        kind,
+        format,
+        refresh: refresh.unwrap_or(DEFAULT_SOURCE_REFRESH),
+        folder_path: folder_path.to_vec(),
+    })
+}
+
+/// Parse a refresh interval given in seconds or with an `s`, `m`, `h` or `d` suffix.
+fn parse_refresh_interval(value: &Value, source_file: &Path) -> InventoryResult<Duration> {
+    let text = scalar_to_string(value, source_file, "source refresh")?;
+    let trimmed = text.trim();
+    let (digits, unit_seconds) = match trimmed.char_indices().last() {
+        Some((index, 's')) => (&trimmed[..index], 1),
+        Some((index, 'm')) => (&trimmed[..index], 60),
+        Some((index, 'h')) => (&trimmed[..index], 60 * 60),
+        Some((index, 'd')) => (&trimmed[..index], 24 * 60 * 60),
+        _ => (trimmed, 1),
+    };
+    digits
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|count| count.checked_mul(unit_seconds))
+        .map(Duration::from_secs)
+        .ok_or_else(|| {
+            invalid_inventory(
+                source_file,
+                format!("source refresh '{trimmed}' must be a number of seconds or use an s/m/h/d suffix"),
+            )
+        })
+}
+
+/// Parse one inventory list, returning its `- defaults:` block (if any) and its nodes.
+fn parse_inventory_nodes(value: &Value, source_file: &Path) -> InventoryResult<(InventoryHostRaw, Vec<InventoryNodeRaw>)> {
     let Value::Sequence(sequence) = value else {
         return Err(invalid_inventory(source_file, "inventory must be a YAML list"));
     };
 
-    sequence.iter().map(|item| parse_inventory_node(item, source_file)).collect()
+    let mut defaults = None;
+    let mut nodes = Vec::new();
+    for item in sequence {
+        if let Value::Mapping(mapping) = item
+            && let Some(value) = defaults_entry_value(mapping)
+        {
+            if defaults.is_some() {
+                return Err(invalid_inventory(source_file, "a folder can only contain one 'defaults' entry"));
+            }
+            defaults = Some(parse_inventory_defaults(value, source_file)?);
+            continue;
+        }
+        nodes.push(parse_inventory_node(item, source_file)?);
+    }
+
+    Ok((defaults.unwrap_or_default(), nodes))
+}
+
+/// Value of a `- defaults: {...}` list item; a `defaults` key holding a list,
+/// or nothing, is a folder.
+fn defaults_entry_value(mapping: &Mapping) -> Option<&Value> {
+    if mapping.len() != 1 || mapping_has_key(mapping, "name") {
+        return None;
+    }
+    let (key, value) = mapping.iter().next()?;
+    (compact_key(key) == "defaults" && value.is_mapping()).then_some(value)
+}
+
+fn parse_inventory_defaults(value: &Value, source_file: &Path) -> InventoryResult<InventoryHostRaw> {
+    let mapping = match value {
+        Value::Null => return Ok(InventoryHostRaw::default()),
+        Value::Mapping(mapping) => mapping,
+        _ => return Err(invalid_inventory(source_file, "defaults must be a mapping of host fields")),
+    };
+
+    if let Some(key) = mapping
+        .keys()
+        .find(|key| matches!(canonical_host_key(key), "name" | "host" | "description" | "protocol" | "hidden" | "template"))
+    {
+        return Err(invalid_inventory(
+            source_file,
+            format!("defaults cannot set '{key}'; it must be given on each host"),
+        ));
+    }
+
+    parse_host_fields(mapping, source_file)
 }
 
 fn parse_inventory_node(value: &Value, source_file: &Path) -> InventoryResult<InventoryNodeRaw> {
@@ -100,11 +320,25 @@ fn parse_inventory_node(value: &Value, source_file: &Path) -> InventoryResult<In
         .next()
         .ok_or_else(|| invalid_inventory(source_file, "folder entry cannot be empty"))?;
     let folder_name = folder_name.clone();
-    let items = parse_inventory_nodes(folder_items, source_file)?;
-    Ok(InventoryNodeRaw::Folder { name: folder_name, items })
+    let (defaults, items) = parse_inventory_nodes(folder_items, source_file)?;
+    Ok(InventoryNodeRaw::Folder {
+        name: folder_name,
+        defaults: Box::new(defaults),
+        items,
+    })
 }
 
 fn parse_inventory_host(mapping: &Mapping, source_file: &Path) -> InventoryResult<InventoryHostRaw> {
+    let host = parse_host_fields(mapping, source_file)?;
+    if host.name.trim().is_empty() {
+        return Err(invalid_inventory(source_file, "inventory host is missing required field 'name'"));
+    }
+
+    Ok(host)
+}
+
+/// Parse the fields shared by host entries and `defaults:` blocks.
+pub(super) fn parse_host_fields(mapping: &Mapping, source_file: &Path) -> InventoryResult<InventoryHostRaw> {
     let mut host = InventoryHostRaw::default();
 
     for (raw_key, value) in mapping {
@@ -112,6 +346,7 @@ fn parse_inventory_host(mapping: &Mapping, source_file: &Path) -> InventoryResul
         let canonical_key = canonical_host_key(&original_key);
         match canonical_key {
             "name" => host.name = scalar_to_string(value, source_file, "name")?,
+            "template" => host.template = optional_scalar_to_string(value, source_file, "template")?,
             "description" => host.description = optional_scalar_to_string(value, source_file, "description")?,
             "protocol" => {
                 let value = scalar_to_string(value, source_file, "protocol")?;
@@ -125,6 +360,8 @@ fn parse_inventory_host(mapping: &Mapping, source_file: &Path) -> InventoryResul
             "hidden" => host.hidden = parse_bool(value, source_file, "hidden")?.unwrap_or(false),
             "identity_file" => host.identity_files = parse_string_list(value, source_file, "identity_file", false)?,
             "identities_only" => host.identities_only = parse_bool(value, source_file, "identities_only")?,
+            "vault_key" => host.vault_key = optional_scalar_to_string(value, source_file, "vault_key")?,
+            "vault_forward" => host.vault_forward = parse_bool(value, source_file, "vault_forward")?,
             "proxy_jump" => host.proxy_jump = optional_scalar_to_string(value, source_file, "proxy_jump")?,
             "proxy_command" => host.proxy_command = optional_scalar_to_string(value, source_file, "proxy_command")?,
             "forward_agent" => host.forward_agent = parse_forward_agent(value, source_file)?,
@@ -143,10 +380,6 @@ fn parse_inventory_host(mapping: &Mapping, source_file: &Path) -> InventoryResul
         }
     }
 
-    if host.name.trim().is_empty() {
-        return Err(invalid_inventory(source_file, "inventory host is missing required field 'name'"));
-    }
-
     Ok(host)
 }
 
@@ -307,6 +540,8 @@ fn scalar_to_string(value: &Value, source_file: &Path, field: &str) -> Inventory
 fn canonical_top_level_key(key: &str) -> &str {
     match compact_key(key).as_str() {
         "include" => "include",
+        "defaults" => "defaults",
+        "templates" => "templates",
         "inventory" => "inventory",
         _ => key,
     }
@@ -315,6 +550,7 @@ fn canonical_top_level_key(key: &str) -> &str {
 fn canonical_host_key(key: &str) -> &str {
     match compact_key(key).as_str() {
         "name" => "name",
+        "template" => "template",
         "description" => "description",
         "protocol" => "protocol",
         "host" | "hostname" => "host",
@@ -322,6 +558,8 @@ fn canonical_host_key(key: &str) -> &str {
         "port" => "port",
         "profile" => "profile",
         "vaultpass" => "vault_pass",
+        "vaultkey" => "vault_key",
+        "vaultforward" => "vault_forward",
         "hidden" => "hidden",
         "identityfile" => "identity_file",
         "identitiesonly" => "identities_only",
@@ -337,6 +575,6 @@ fn canonical_host_key(key: &str) -> &str {
     }
 }
 
-fn compact_key(key: &str) -> String {
+pub(super) fn compact_key(key: &str) -> String {
     key.chars().filter(|ch| ch.is_ascii_alphanumeric()).flat_map(char::to_lowercase).collect()
 }
@@ -0,0 +1,559 @@
+//! Dynamic inventory `source:` includes.
+//!
+//! A source is either an executable printing JSON, in the shape of an Ansible
+//! dynamic inventory script, or a CSV/JSON device export such as a NetBox or
+//! Nautobot download. Its hosts are loaded under a folder named after the
+//! source. Command output is cached under `cache/inventory-sources/` next to
+//! the root inventory file and reused until the source's `refresh` interval
+//! passes; when the command fails, the last cached output is used instead.
+//! A source may set `vault_pass` only to a local vault entry name; external
+//! provider references are dropped because they would run local commands.
+
+use super::ansible::{AnsibleFolder, AnsibleGroupMembers, layout_ansible_groups};
+use super::error::{InventoryError, InventoryResult, invalid_inventory};
+use super::include::resolve_include_pattern;
+use super::model::{InventoryNodeRaw, InventorySourceFormat, InventorySourceKind, InventorySourceRaw};
+use super::parse::{compact_key, parse_host_fields};
+use crate::args::validate_vault_entry_name;
+use crate::platform::{create_private_directory, set_private_file_permissions, wait_with_output_timeout};
+use crate::{log_debug, log_warn};
+use serde_json::{Map, Value as JsonValue};
+use serde_yml::{Mapping, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime};
+
+/// Refresh interval of command sources that do not set `refresh:`.
+pub(super) const DEFAULT_SOURCE_REFRESH: Duration = Duration::from_secs(15 * 60);
+const SOURCE_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+const SOURCE_CACHE_DIR_MODE: u32 = 0o700;
+const SOURCE_CACHE_FILE_MODE: u32 = 0o600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How a tree build treats cached command output.
+pub(super) enum SourceRefresh {
+    /// Reuse cached output until the source's refresh interval passes.
+    UseCache,
+    /// Run every command source again.
+    Force,
+    /// Never run commands: use cached output of any age, or load a source
+    /// without a cache as empty. The source's refresh is due at once.
+    CacheOnly,
+}
+
+/// Loads the `source:` entries of one tree build.
+#[derive(Debug)]
+pub(super) struct SourceLoader {
+    cache_dir: PathBuf,
+    refresh: SourceRefresh,
+    refresh_due: Option<SystemTime>,
+}
+
+impl SourceLoader {
+    pub(super) fn new(root_inventory_path: &Path, refresh: SourceRefresh) -> Self {
+        let root_dir = root_inventory_path.parent().unwrap_or(Path::new("."));
+        Self {
+            cache_dir: root_dir.join("cache").join("inventory-sources"),
+            refresh,
+            refresh_due: None,
+        }
+    }
+
+    /// Earliest time the output of a loaded command source goes stale.
+    pub(super) fn refresh_due(&self) -> Option<SystemTime> {
+        self.refresh_due
+    }
+
+    /// Load `source`, declared in `declaring_file`, as a folder named after it.
+    pub(super) fn load(&mut self, source: &InventorySourceRaw, declaring_file: &Path) -> InventoryResult<InventoryNodeRaw> {
+        let base_dir = declaring_file.parent().unwrap_or(Path::new("."));
+        let (contents, format, uncached_path) = match &source.kind {
+            InventorySourceKind::Command { program, args } => {
+                let (contents, uncached_path) = self.command_output(source, program, args, base_dir, declaring_file)?;
+                (contents, InventorySourceFormat::Json, uncached_path)
+            }
+            InventorySourceKind::File(path) => {
+                let path = PathBuf::from(resolve_include_pattern(path, base_dir));
+                let contents =
+                    fs::read_to_string(&path).map_err(|err| source_error(declaring_file, source, format!("failed to read '{}': {err}", path.display())))?;
+                (contents, source.format.unwrap_or_else(|| format_for_path(&path)), None)
+            }
+        };
+
+        let group = match format {
+            InventorySourceFormat::Json => parse_json_source(&contents),
+            InventorySourceFormat::Csv => parse_csv_source(&contents),
+        }
+        .map_err(|err| source_error(declaring_file, source, err))?;
+        let node = source_group_node(source.name.clone(), group, declaring_file)?;
+
+        // Output is only cached once it parsed, so a bad run never replaces a good cache.
+        if let Some(cache_path) = uncached_path
+            && let Err(err) = write_cache_file(&cache_path, &contents)
+        {
+            log_warn!(
+                "Failed to cache output of inventory source '{}' at '{}': {}",
+                source.name,
+                cache_path.display(),
+                err
+            );
+        }
+        Ok(node)
+    }
+
+    /// Output of a command source and, when it was freshly run, the cache file to store it in.
+    fn command_output(
+        &mut self,
+        source: &InventorySourceRaw,
+        program: &str,
+        args: &[String],
+        base_dir: &Path,
+        declaring_file: &Path,
+    ) -> InventoryResult<(String, Option<PathBuf>)> {
+        let cache_path = self.cache_dir.join(cache_file_name(source, program, args, declaring_file));
+        let now = SystemTime::now();
+        if self.refresh == SourceRefresh::CacheOnly {
+            let cached = fs::metadata(&cache_path)
+                .and_then(|metadata| metadata.modified())
+                .and_then(|cached_at| Ok((cached_at, fs::read_to_string(&cache_path)?)));
+            return Ok(match cached {
+                Ok((cached_at, contents)) => {
+                    self.note_refresh_due(cached_at + source.refresh);
+                    (contents, None)
+                }
+                Err(_) => {
+                    log_debug!("Inventory source '{}' has no cached output yet", source.name);
+                    self.note_refresh_due(now);
+                    ("[]".to_string(), None)
+                }
+            });
+        }
+        if self.refresh == SourceRefresh::UseCache
+            && let Ok(cached_at) = fs::metadata(&cache_path).and_then(|metadata| metadata.modified())
+            && now.duration_since(cached_at).is_ok_and(|age| age < source.refresh)
+            && let Ok(contents) = fs::read_to_string(&cache_path)
+        {
+            log_debug!("Using cached output of inventory source '{}' from '{}'", source.name, cache_path.display());
+            self.note_refresh_due(cached_at + source.refresh);
+            return Ok((contents, None));
+        }
+
+        log_debug!("Running inventory source '{}' command '{}'", source.name, program);
+        self.note_refresh_due(now + source.refresh);
+        match run_source_command(&resolve_program(program, base_dir), args, base_dir) {
+            Ok(output) => Ok((output, Some(cache_path))),
+            Err(err) => match fs::read_to_string(&cache_path) {
+                Ok(contents) => {
+                    log_warn!("Inventory source '{}' failed ({}); using its last cached output", source.name, err);
+                    Ok((contents, None))
+                }
+                Err(_) => Err(source_error(declaring_file, source, err)),
+            },
+        }
+    }
+
+    fn note_refresh_due(&mut self, due: SystemTime) {
+        self.refresh_due = Some(self.refresh_due.map_or(due, |current| current.min(due)));
+    }
+}
+
+fn source_error(declaring_file: &Path, source: &InventorySourceRaw, message: impl std::fmt::Display) -> InventoryError {
+    invalid_inventory(declaring_file, format!("inventory source '{}': {message}", source.name))
+}
+
+fn format_for_path(path: &Path) -> InventorySourceFormat {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("csv") => InventorySourceFormat::Csv,
+        _ => InventorySourceFormat::Json,
+    }
+}
+
+/// Programs given as a path resolve like include paths; bare names are looked up on `PATH`.
+fn resolve_program(program: &str, base_dir: &Path) -> PathBuf {
+    if program.contains('/') {
+        PathBuf::from(resolve_include_pattern(program, base_dir))
+    } else {
+        PathBuf::from(program)
+    }
+}
+
+fn cache_file_name(source: &InventorySourceRaw, program: &str, args: &[String], declaring_file: &Path) -> String {
+    let mut hasher = Sha256::new();
+    for part in [declaring_file.to_string_lossy().as_ref(), source.name.as_str(), program]
+        .into_iter()
+        .chain(args.iter().map(String::as_str))
+    {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    let digest = hasher.finalize().iter().take(8).map(|byte| format!("{byte:02x}")).collect::<String>();
+    let name = source
+        .name
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_') { ch } else { '_' })
+        .collect::<String>();
+    format!("{name}-{digest}.json")
+}
+
+fn write_cache_file(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        create_private_directory(parent, SOURCE_CACHE_DIR_MODE)?;
+    }
+    fs::write(path, contents)?;
+    set_private_file_permissions(path, SOURCE_CACHE_FILE_MODE)
+}
+
+fn run_source_command(program: &Path, args: &[String], base_dir: &Path) -> Result<String, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(base_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("failed to run '{}': {err}", program.display()))?;
+
+    let output = wait_with_output_timeout(&mut child, SOURCE_COMMAND_TIMEOUT)
+        .map_err(|err| format!("failed to wait for '{}': {err}", program.display()))?
+        .ok_or_else(|| {
+            format!(
+                "'{}' did not finish within {} seconds",
+                program.display(),
+                SOURCE_COMMAND_TIMEOUT.as_secs()
+            )
+        })?;
+    let (status, stderr) = (output.status, String::from_utf8_lossy(&output.stderr));
+
+    if !status.success() {
+        let detail = stderr.lines().next().unwrap_or_default().trim();
+        return Err(if detail.is_empty() {
+            format!("'{}' failed ({status})", program.display())
+        } else {
+            format!("'{}' failed ({status}): {detail}", program.display())
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Hosts and groups read from a source, as inventory host fields.
+#[derive(Debug, Default)]
+struct SourceGroup {
+    /// Fields every host in the group inherits, like a folder `defaults:` entry.
+    vars: Mapping,
+    hosts: Vec<Mapping>,
+    children: Vec<(String, SourceGroup)>,
+}
+
+fn source_group_node(name: String, group: SourceGroup, declaring_file: &Path) -> InventoryResult<InventoryNodeRaw> {
+    let defaults = parse_host_fields(&group.vars, declaring_file)?;
+    let mut items = Vec::with_capacity(group.hosts.len() + group.children.len());
+    for host in &group.hosts {
+        items.push(InventoryNodeRaw::Host(Box::new(parse_host_fields(host, declaring_file)?)));
+    }
+    for (child_name, child) in group.children {
+        items.push(source_group_node(child_name, child, declaring_file)?);
+    }
+    Ok(InventoryNodeRaw::Folder {
+        name,
+        defaults: Box::new(defaults),
+        items,
+    })
+}
+
+/// Inventory field for a source column, Ansible variable or NetBox/Nautobot attribute.
+/// `folder` is not a host field; records are grouped by it.
+fn source_host_field(key: &str) -> Option<&'static str> {
+    Some(match compact_key(key).as_str() {
+        "name" | "device" | "devicename" | "inventoryhostname" => "name",
+        "host" | "hostname" | "address" | "ipaddress" | "primaryip" | "primaryip4" | "primaryipv4" | "primaryip6" | "primaryipv6" | "ansiblehost"
+        | "ansiblesshhost" => "host",
+        "user" | "username" | "ansibleuser" | "ansiblesshuser" => "user",
+        "port" | "ansibleport" | "ansiblesshport" => "port",
+        "description" | "comments" => "description",
+        "protocol" => "protocol",
+        "profile" => "profile",
+        "vaultpass" => "vault_pass",
+        "proxyjump" => "proxy_jump",
+        "identityfile" | "ansiblesshprivatekeyfile" => "identity_file",
+        "folder" | "site" => "folder",
+        _ => return None,
+    })
+}
+
+/// Map source fields onto inventory host fields; the first non-empty value of each field wins.
+fn source_host_fields<'a>(fields: impl IntoIterator<Item = (&'a str, Option<String>)>) -> Mapping {
+    let mut mapping = Mapping::new();
+    for (key, value) in fields {
+        let (Some(field), Some(value)) = (source_host_field(key), value) else {
+            continue;
+        };
+        let value = value.trim();
+        if value.is_empty() || mapping.contains_key(field) {
+            continue;
+        }
+        // Provider references run local commands; external data may only name vault entries.
+        if field == "vault_pass" && !validate_vault_entry_name(value) {
+            log_warn!("Ignoring inventory source vault_pass '{}': only vault entry names are accepted", value);
+            continue;
+        }
+        // IPAM exports write addresses with their prefix length (`10.0.0.1/24`).
+        let value = match value.split_once('/') {
+            Some((address, prefix)) if field == "host" && !prefix.is_empty() && prefix.bytes().all(|byte| byte.is_ascii_digit()) => address,
+            _ => value,
+        };
+        mapping.insert(field, Value::String(value.to_string()));
+    }
+    mapping
+}
+
+/// Group flat device records by their `folder`/`site` field.
+fn records_group(records: Vec<Mapping>) -> Result<SourceGroup, String> {
+    let mut group = SourceGroup::default();
+    for (index, mut record) in records.into_iter().enumerate() {
+        let Some(Value::String(name)) = record.get("name").cloned() else {
+            return Err(format!("record {} has no name", index + 1));
+        };
+        if !record.contains_key("host") {
+            record.insert("host", Value::String(name));
+        }
+        match record.remove("folder") {
+            Some(Value::String(folder)) => match group.children.iter_mut().find(|(existing, _)| *existing == folder) {
+                Some((_, child)) => child.hosts.push(record),
+                None => group.children.push((
+                    folder,
+                    SourceGroup {
+                        hosts: vec![record],
+                        ..SourceGroup::default()
+                    },
+                )),
+            },
+            _ => group.hosts.push(record),
+        }
+    }
+    Ok(group)
+}
+
+fn parse_csv_source(contents: &str) -> Result<SourceGroup, String> {
+    let mut rows = parse_csv_rows(contents)?.into_iter();
+    let header = rows.next().ok_or("CSV export has no header row")?;
+    records_group(
+        rows.map(|row| source_host_fields(header.iter().map(String::as_str).zip(row.into_iter().map(Some))))
+            .collect(),
+    )
+}
+
+/// Split RFC 4180 style CSV into rows, dropping blank lines.
+pub(super) fn parse_csv_rows(contents: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = contents.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(ch) = chars.next() {
+        if in_quotes {
+            match ch {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => field.push(ch),
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(ch),
+        }
+    }
+    if in_quotes {
+        return Err("CSV export ends inside a quoted field".to_string());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|row| row.iter().any(|field| !field.trim().is_empty()));
+    Ok(rows)
+}
+
+fn parse_json_source(contents: &str) -> Result<SourceGroup, String> {
+    let value: JsonValue = serde_json::from_str(contents).map_err(|err| format!("output is not valid JSON: {err}"))?;
+    match value {
+        JsonValue::Array(records) => json_records_group(&records),
+        // NetBox and Nautobot API responses wrap the device list in `results`.
+        JsonValue::Object(object) if !object.contains_key("_meta") && object.get("results").is_some_and(JsonValue::is_array) => {
+            json_records_group(object["results"].as_array().map(Vec::as_slice).unwrap_or_default())
+        }
+        JsonValue::Object(object) => ansible_inventory_group(object),
+        _ => Err("expected a JSON list of devices or an Ansible inventory object".to_string()),
+    }
+}
+
+fn json_records_group(records: &[JsonValue]) -> Result<SourceGroup, String> {
+    let records = records
+        .iter()
+        .enumerate()
+        .map(|(index, record)| match record {
+            JsonValue::Object(fields) => Ok(json_host_fields(fields)),
+            _ => Err(format!("record {} is not a JSON object", index + 1)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    records_group(records)
+}
+
+fn json_host_fields(fields: &Map<String, JsonValue>) -> Mapping {
+    source_host_fields(fields.iter().map(|(key, value)| (key.as_str(), json_scalar(value))))
+}
+
+/// Scalar text of a JSON value; nested NetBox objects such as `primary_ip`
+/// and `site` contribute their `address`, `name` or `display`.
+pub(super) fn json_scalar(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::String(text) => Some(text.clone()),
+        JsonValue::Number(number) => Some(number.to_string()),
+        JsonValue::Bool(boolean) => Some(boolean.to_string()),
+        JsonValue::Object(object) => ["address", "name", "display"].into_iter().find_map(|key| object.get(key).and_then(json_scalar)),
+        JsonValue::Array(_) | JsonValue::Null => None,
+    }
+}
+
+#[derive(Debug, Default)]
+struct AnsibleGroup {
+    hosts: Vec<String>,
+    children: Vec<String>,
+    vars: Mapping,
+}
+
+/// Convert `--list` output: groups become folders laid out like
+/// `inventory import --from ansible`, `_meta.hostvars` become host fields, and
+/// group `vars` become folder defaults. Groups that add no hosts are dropped.
+fn ansible_inventory_group(mut object: Map<String, JsonValue>) -> Result<SourceGroup, String> {
+    let hostvars = match object.remove("_meta") {
+        Some(JsonValue::Object(mut meta)) => match meta.remove("hostvars") {
+            Some(JsonValue::Object(hostvars)) => hostvars,
+            None | Some(JsonValue::Null) => Map::new(),
+            Some(_) => return Err("'_meta.hostvars' must be an object".to_string()),
+        },
+        None | Some(JsonValue::Null) => Map::new(),
+        Some(_) => return Err("'_meta' must be an object".to_string()),
+    };
+
+    let mut groups = BTreeMap::new();
+    for (name, value) in object {
+        let group = ansible_group(&name, value)?;
+        groups.insert(name, group);
+    }
+    let members: Vec<AnsibleGroupMembers> = groups
+        .iter()
+        .map(|(name, group)| AnsibleGroupMembers {
+            name,
+            hosts: &group.hosts,
+            children: &group.children,
+        })
+        .collect();
+    let host_order = ["all", "ungrouped"]
+        .into_iter()
+        .filter_map(|name| groups.get(name))
+        .chain(groups.values())
+        .flat_map(|group| group.hosts.iter().map(String::as_str))
+        .chain(hostvars.keys().map(String::as_str));
+    let layout = layout_ansible_groups(&members, host_order);
+
+    let mut root = SourceGroup::default();
+    for name in ["all", "ungrouped"] {
+        if let Some(group) = groups.get(name) {
+            root.vars.extend(group.vars.clone());
+        }
+    }
+    root.hosts = layout.hosts.iter().map(|name| ansible_host_fields(&hostvars, name)).collect();
+    root.children = layout
+        .folders
+        .iter()
+        .filter_map(|folder| ansible_source_group(&groups, &hostvars, folder).map(|group| (folder.group.to_string(), group)))
+        .collect();
+    Ok(root)
+}
+
+/// Source group for a laid out folder, or `None` when it adds no hosts.
+fn ansible_source_group(groups: &BTreeMap<String, AnsibleGroup>, hostvars: &Map<String, JsonValue>, folder: &AnsibleFolder) -> Option<SourceGroup> {
+    let hosts: Vec<Mapping> = folder.hosts.iter().map(|name| ansible_host_fields(hostvars, name)).collect();
+    let children: Vec<(String, SourceGroup)> = folder
+        .children
+        .iter()
+        .filter_map(|child| ansible_source_group(groups, hostvars, child).map(|group| (child.group.to_string(), group)))
+        .collect();
+    (!hosts.is_empty() || !children.is_empty()).then(|| SourceGroup {
+        vars: groups[folder.group].vars.clone(),
+        hosts,
+        children,
+    })
+}
+
+/// Host fields of `name` from `_meta.hostvars`.
+fn ansible_host_fields(hostvars: &Map<String, JsonValue>, name: &str) -> Mapping {
+    let mut fields = match hostvars.get(name) {
+        Some(JsonValue::Object(vars)) => json_host_fields(vars),
+        _ => Mapping::new(),
+    };
+    fields.remove("folder");
+    fields.insert("name", Value::String(name.to_string()));
+    if !fields.contains_key("host") {
+        fields.insert("host", Value::String(name.to_string()));
+    }
+    fields
+}
+
+fn ansible_group(name: &str, value: JsonValue) -> Result<AnsibleGroup, String> {
+    let names = |value: Option<&JsonValue>, field: &str| -> Result<Vec<String>, String> {
+        match value {
+            None | Some(JsonValue::Null) => Ok(Vec::new()),
+            Some(JsonValue::Array(items)) => items
+                .iter()
+                .map(|item| {
+                    item.as_str()
+                        .map(str::to_string)
+                        .ok_or_else(|| format!("group '{name}' has a non-string {field} entry"))
+                })
+                .collect(),
+            Some(_) => Err(format!("group '{name}' {field} must be a list")),
+        }
+    };
+
+    match value {
+        JsonValue::Null => Ok(AnsibleGroup::default()),
+        // Old-style scripts list a group's hosts directly.
+        JsonValue::Array(_) => Ok(AnsibleGroup {
+            hosts: names(Some(&value), "hosts")?,
+            ..AnsibleGroup::default()
+        }),
+        JsonValue::Object(group) => {
+            let mut vars = match group.get("vars") {
+                Some(JsonValue::Object(vars)) => json_host_fields(vars),
+                None | Some(JsonValue::Null) => Mapping::new(),
+                Some(_) => return Err(format!("group '{name}' vars must be an object")),
+            };
+            vars.remove("name");
+            vars.remove("folder");
+            Ok(AnsibleGroup {
+                hosts: names(group.get("hosts"), "hosts")?,
+                children: names(group.get("children"), "children")?,
+                vars,
+            })
+        }
+        _ => Err(format!("group '{name}' must be an object or a list of hosts")),
+    }
+}
+
+#[cfg(test)]
+#[path = "../test/inventory/source.rs"]
+mod tests;
@@ -0,0 +1,871 @@
+//! Two-way sync between an OpenSSH config and the YAML inventory.
+//!
+//! Hosts are matched by alias and compared field by field against the
+//! snapshot the previous sync saved under `cache/` next to the inventory. A
+//! field changed on one side since then is copied to the other; a field
+//! changed on both sides, or any differing field before the first sync, is a
+//! conflict to resolve. SSH config edits only rewrite the lines of the fields
+//! they change, so comments and formatting in `Host` blocks survive.
+//!
+//! A missing SSH config, or one that lost every host the last sync saw, is an
+//! error rather than a request to empty the inventory. Automatic syncs never
+//! remove hosts; that is left to an explicit `cossh inventory sync`.
+
+use super::edit::{EditableInventoryHost, create_inventory_host_entries, delete_inventory_host_entry, set_inventory_host_fields};
+use super::export::{quote_ssh_value, ssh_forward_arguments};
+use super::migration::backup_existing_inventory;
+use super::model::{ConnectionProtocol, InventoryHost, TreeFolder};
+use super::path::expand_tilde;
+use super::{build_inventory_tree, normalize_ssh_forward_spec};
+use crate::ssh_config::{SshHost, get_default_ssh_config_path, parse_ssh_config_for_migration};
+use serde::{Deserialize, Serialize};
+use serde_yml::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Synced values of one host by canonical inventory key (`user`,
+/// `ssh_options.<option>`, ...); unset fields are absent.
+pub(crate) type SyncFields = BTreeMap<String, Vec<String>>;
+
+/// Inventory fields stored in `Host` blocks, with the keyword or `#_` tag holding each.
+const SSH_CONFIG_FIELDS: [(&str, &str); 17] = [
+    ("description", "#_Desc"),
+    ("protocol", "#_Protocol"),
+    ("profile", "#_Profile"),
+    ("vault_pass", "#_pass"),
+    ("rdp_domain", "#_RdpDomain"),
+    ("rdp_args", "#_RdpArgs"),
+    ("hidden", "#_hidden"),
+    ("host", "HostName"),
+    ("user", "User"),
+    ("port", "Port"),
+    ("identity_file", "IdentityFile"),
+    ("identities_only", "IdentitiesOnly"),
+    ("proxy_jump", "ProxyJump"),
+    ("proxy_command", "ProxyCommand"),
+    ("forward_agent", "ForwardAgent"),
+    ("local_forward", "LocalForward"),
+    ("remote_forward", "RemoteForward"),
+];
+
+const SNAPSHOT_FILE_NAME: &str = "ssh-config-sync.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// One side of a sync.
+pub(crate) enum SyncSide {
+    SshConfig,
+    Inventory,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// One field whose value differs between the two sides.
+pub(crate) struct FieldSync {
+    pub(crate) field: String,
+    pub(crate) ssh_config: Vec<String>,
+    pub(crate) inventory: Vec<String>,
+    /// Side whose value is kept; `None` for an unresolved conflict, which is left alone.
+    pub(crate) winner: Option<SyncSide>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum HostSyncAction {
+    /// Only in the SSH config; added to the inventory.
+    AddToInventory,
+    /// Only in the inventory; added to the SSH config.
+    AddToSshConfig,
+    /// Deleted from the SSH config since the last sync; removed from the inventory.
+    RemoveFromInventory,
+    /// Deleted from the inventory since the last sync; removed from the SSH config.
+    RemoveFromSshConfig,
+    /// On both sides with differing fields.
+    Update(Vec<FieldSync>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HostSync {
+    pub(crate) name: String,
+    pub(crate) action: HostSyncAction,
+}
+
+#[derive(Debug, Clone)]
+struct SshConfigHost {
+    fields: SyncFields,
+    /// Config file holding the host's `Host` block.
+    file: PathBuf,
+    /// Inventory folder for the host, named after the include files leading to it.
+    folder_path: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct InventorySideHost {
+    fields: SyncFields,
+    source_file: PathBuf,
+}
+
+/// Differences between one SSH config and one inventory, ready to apply.
+#[derive(Debug, Clone)]
+pub(crate) struct InventorySyncPlan {
+    pub(crate) ssh_config_path: PathBuf,
+    pub(crate) inventory_path: PathBuf,
+    /// Hosts that need a change, by alias.
+    pub(crate) hosts: Vec<HostSync>,
+    /// Aliases left out of the sync, with the reason.
+    pub(crate) skipped: Vec<(String, String)>,
+    /// Whether an earlier sync left a snapshot to compare against.
+    pub(crate) has_snapshot: bool,
+    ssh_hosts: BTreeMap<String, SshConfigHost>,
+    inventory_hosts: BTreeMap<String, InventorySideHost>,
+    snapshot: BTreeMap<String, SyncFields>,
+    /// Spelling of inventory `ssh_options` keys by lowercase name.
+    option_names: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct InventorySyncSummary {
+    pub(crate) added_to_inventory: usize,
+    pub(crate) added_to_ssh_config: usize,
+    pub(crate) removed_from_inventory: usize,
+    pub(crate) removed_from_ssh_config: usize,
+    pub(crate) updated_hosts: usize,
+    /// Conflicting fields left unchanged on both sides.
+    pub(crate) unresolved_conflicts: usize,
+    /// Copies of the SSH config files taken before they were rewritten.
+    pub(crate) backups: Vec<PathBuf>,
+    /// Removals an automatic sync left for an explicit `cossh inventory sync`.
+    pub(crate) deferred_removals: usize,
+}
+
+impl InventorySyncSummary {
+    pub(crate) fn changed_anything(&self) -> bool {
+        self.added_to_inventory + self.added_to_ssh_config + self.removed_from_inventory + self.removed_from_ssh_config + self.updated_hosts > 0
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SyncSnapshot {
+    ssh_config: PathBuf,
+    hosts: BTreeMap<String, SyncFields>,
+}
+
+impl InventorySyncPlan {
+    /// Pick a winner for each unresolved conflict; `choose` returning `None` leaves it unresolved.
+    pub(crate) fn resolve_conflicts<E>(&mut self, mut choose: impl FnMut(&str, &FieldSync) -> Result<Option<SyncSide>, E>) -> Result<(), E> {
+        for host in &mut self.hosts {
+            let HostSyncAction::Update(fields) = &mut host.action else {
+                continue;
+            };
+            for field in fields.iter_mut().filter(|field| field.winner.is_none()) {
+                field.winner = choose(&host.name, field)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop host removals from the plan, returning how many were dropped.
+    ///
+    /// Their snapshot entries are kept, so the next sync offers them again
+    /// instead of adding the hosts back to the other side.
+    pub(crate) fn defer_removals(&mut self) -> usize {
+        let before = self.hosts.len();
+        self.hosts
+            .retain(|host| !matches!(host.action, HostSyncAction::RemoveFromInventory | HostSyncAction::RemoveFromSshConfig));
+        before - self.hosts.len()
+    }
+}
+
+/// Compare `ssh_config_path` with the inventory at `inventory_path` by host alias.
+pub(crate) fn plan_inventory_sync(ssh_config_path: &Path, inventory_path: &Path) -> io::Result<InventorySyncPlan> {
+    let mut skipped = Vec::new();
+    let mut ssh_hosts = load_ssh_config_hosts(ssh_config_path, &mut skipped)?;
+    let (mut inventory_hosts, option_names) = load_inventory_hosts(inventory_path, &mut skipped)?;
+    for (name, _) in &skipped {
+        ssh_hosts.remove(name);
+        inventory_hosts.remove(name);
+    }
+
+    let snapshot = load_snapshot(inventory_path, ssh_config_path);
+    let has_snapshot = snapshot.is_some();
+    let snapshot = snapshot.unwrap_or_default();
+    // An emptied or freshly replaced config would otherwise plan every host for removal.
+    if ssh_hosts.is_empty() && !snapshot.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "SSH config '{}' has no hosts, but the last sync saw {}; refusing to remove them from the inventory",
+                ssh_config_path.display(),
+                snapshot.len()
+            ),
+        ));
+    }
+
+    let names: BTreeSet<&String> = ssh_hosts.keys().chain(inventory_hosts.keys()).collect();
+    let mut hosts = Vec::new();
+    for name in names {
+        let base = snapshot.get(name);
+        let action = match (ssh_hosts.get(name), inventory_hosts.get(name)) {
+            (Some(ssh), Some(inventory)) => {
+                let fields = diff_fields(&ssh.fields, &inventory.fields, base);
+                if fields.is_empty() {
+                    continue;
+                }
+                HostSyncAction::Update(fields)
+            }
+            // A host edited on one side since it was deleted on the other is kept.
+            (Some(ssh), None) if base == Some(&ssh.fields) => HostSyncAction::RemoveFromSshConfig,
+            (Some(_), None) => HostSyncAction::AddToInventory,
+            (None, Some(inventory)) if base == Some(&inventory.fields) => HostSyncAction::RemoveFromInventory,
+            (None, Some(_)) => HostSyncAction::AddToSshConfig,
+            (None, None) => continue,
+        };
+        hosts.push(HostSync { name: name.clone(), action });
+    }
+
+    Ok(InventorySyncPlan {
+        ssh_config_path: ssh_config_path.to_path_buf(),
+        inventory_path: inventory_path.to_path_buf(),
+        hosts,
+        skipped,
+        has_snapshot,
+        ssh_hosts,
+        inventory_hosts,
+        snapshot,
+        option_names,
+    })
+}
+
+/// Write the plan to both sides and save the snapshot for the next sync.
+pub(crate) fn apply_inventory_sync(plan: &InventorySyncPlan) -> io::Result<InventorySyncSummary> {
+    let mut summary = InventorySyncSummary::default();
+    let actions: HashMap<&str, &HostSyncAction> = plan.hosts.iter().map(|host| (host.name.as_str(), &host.action)).collect();
+    let mut inventory_additions = Vec::new();
+    let mut ssh_edits: BTreeMap<&Path, Vec<SshConfigEdit<'_>>> = BTreeMap::new();
+    let mut snapshot = BTreeMap::new();
+
+    let names: BTreeSet<&String> = plan.ssh_hosts.keys().chain(plan.inventory_hosts.keys()).collect();
+    for name in names {
+        let ssh = plan.ssh_hosts.get(name);
+        let inventory = plan.inventory_hosts.get(name);
+        match (actions.get(name.as_str()), ssh, inventory) {
+            (None, Some(ssh), Some(_)) => {
+                snapshot.insert(name.clone(), ssh.fields.clone());
+            }
+            // A deferred removal keeps its snapshot entry until it is applied.
+            (None, _, _) => {
+                if let Some(base) = plan.snapshot.get(name.as_str()) {
+                    snapshot.insert(name.clone(), base.clone());
+                }
+            }
+            (Some(HostSyncAction::AddToInventory), Some(ssh), _) => {
+                inventory_additions.push((ssh.folder_path.clone(), editable_host_from_fields(name, &ssh.fields)));
+                snapshot.insert(name.clone(), ssh.fields.clone());
+                summary.added_to_inventory += 1;
+            }
+            (Some(HostSyncAction::AddToSshConfig), _, Some(inventory)) => {
+                ssh_edits.entry(&plan.ssh_config_path).or_default().push(SshConfigEdit::Append {
+                    name,
+                    fields: &inventory.fields,
+                });
+                snapshot.insert(name.clone(), inventory.fields.clone());
+                summary.added_to_ssh_config += 1;
+            }
+            (Some(HostSyncAction::RemoveFromInventory), _, Some(inventory)) => {
+                delete_inventory_host_entry(&inventory.source_file, name)?;
+                summary.removed_from_inventory += 1;
+            }
+            (Some(HostSyncAction::RemoveFromSshConfig), Some(ssh), _) => {
+                ssh_edits.entry(&ssh.file).or_default().push(SshConfigEdit::Remove { name });
+                summary.removed_from_ssh_config += 1;
+            }
+            (Some(HostSyncAction::Update(fields)), Some(ssh), Some(inventory)) => {
+                let base = plan.snapshot.get(name.as_str());
+                let mut synced = ssh.fields.clone();
+                let mut ssh_fields = ssh.fields.clone();
+                let mut ssh_changes = Vec::new();
+                let mut inventory_changes = Vec::new();
+                let mut unresolved = 0;
+                for field in fields {
+                    let value = match field.winner {
+                        Some(SyncSide::SshConfig) => {
+                            inventory_changes.push((field.field.clone(), inventory_value(&field.field, &field.ssh_config)));
+                            field.ssh_config.as_slice()
+                        }
+                        Some(SyncSide::Inventory) => {
+                            set_field(&mut ssh_fields, &field.field, &field.inventory);
+                            ssh_changes.push(field.field.as_str());
+                            field.inventory.as_slice()
+                        }
+                        None => {
+                            unresolved += 1;
+                            base.and_then(|base| base.get(&field.field)).map_or(&[] as &[String], Vec::as_slice)
+                        }
+                    };
+                    set_field(&mut synced, &field.field, value);
+                }
+
+                if !inventory_changes.is_empty() || !ssh_changes.is_empty() {
+                    summary.updated_hosts += 1;
+                }
+                if !inventory_changes.is_empty() {
+                    set_inventory_host_fields(&inventory.source_file, name, &inventory_changes)?;
+                }
+                if !ssh_changes.is_empty() {
+                    ssh_edits.entry(&ssh.file).or_default().push(SshConfigEdit::Update {
+                        name,
+                        changes: ssh_changes,
+                        fields: ssh_fields,
+                    });
+                }
+                // Without a snapshot entry an unresolved field is offered again next time.
+                if unresolved == 0 || base.is_some() {
+                    snapshot.insert(name.clone(), synced);
+                }
+                summary.unresolved_conflicts += unresolved;
+            }
+            _ => {}
+        }
+    }
+
+    if !inventory_additions.is_empty() {
+        create_inventory_host_entries(&plan.inventory_path, &inventory_additions)?;
+    }
+    for (path, edits) in ssh_edits {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        summary.backups.extend(backup_existing_inventory(path)?);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, edit_ssh_config_text(&contents, &edits, &plan.option_names))?;
+    }
+
+    save_snapshot(&plan.inventory_path, &plan.ssh_config_path, snapshot)?;
+    Ok(summary)
+}
+
+/// Sync `~/.ssh/config` with `inventory_path`, settling every conflict in favour of `prefer`.
+///
+/// Used for automatic syncs: host removals are left for an explicit
+/// `cossh inventory sync` and only counted in the summary.
+pub(crate) fn sync_default_ssh_config(inventory_path: &Path, prefer: SyncSide) -> io::Result<InventorySyncSummary> {
+    let ssh_config_path =
+        get_default_ssh_config_path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Could not find home directory for ~/.ssh/config"))?;
+    sync_ssh_config_without_removals(&ssh_config_path, inventory_path, prefer)
+}
+
+fn sync_ssh_config_without_removals(ssh_config_path: &Path, inventory_path: &Path, prefer: SyncSide) -> io::Result<InventorySyncSummary> {
+    let mut plan = plan_inventory_sync(ssh_config_path, inventory_path)?;
+    plan.resolve_conflicts(|_, _| Ok::<_, io::Error>(Some(prefer)))?;
+    let deferred_removals = plan.defer_removals();
+    let mut summary = if plan.hosts.is_empty() && plan.has_snapshot {
+        InventorySyncSummary::default()
+    } else {
+        apply_inventory_sync(&plan)?
+    };
+    summary.deferred_removals = deferred_removals;
+    Ok(summary)
+}
+
+fn load_ssh_config_hosts(ssh_config_path: &Path, skipped: &mut Vec<(String, String)>) -> io::Result<BTreeMap<String, SshConfigHost>> {
+    let mut hosts = BTreeMap::new();
+    if !ssh_config_path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("SSH config '{}' does not exist; create it before syncing", ssh_config_path.display()),
+        ));
+    }
+
+    let parsed = parse_ssh_config_for_migration(ssh_config_path)?;
+    let mut duplicates = BTreeSet::new();
+    collect_ssh_config_hosts(&parsed.root, &parsed.hosts, &mut Vec::new(), &mut hosts, &mut duplicates);
+    for name in duplicates {
+        skipped.push((name, "defined by more than one Host block in the SSH config".to_string()));
+    }
+    Ok(hosts)
+}
+
+fn collect_ssh_config_hosts(
+    folder: &TreeFolder,
+    hosts: &[SshHost],
+    folder_path: &mut Vec<String>,
+    collected: &mut BTreeMap<String, SshConfigHost>,
+    duplicates: &mut BTreeSet<String>,
+) {
+    for host in folder.host_indices.iter().filter_map(|&index| hosts.get(index)) {
+        // Wildcard and negated patterns hold settings for other hosts, not hosts of their own.
+        if host.name.contains(['*', '?', '!']) {
+            continue;
+        }
+        let entry = SshConfigHost {
+            fields: ssh_host_fields(host),
+            file: folder.path.clone(),
+            folder_path: folder_path.clone(),
+        };
+        if collected.insert(host.name.clone(), entry).is_some() {
+            duplicates.insert(host.name.clone());
+        }
+    }
+
+    for child in &folder.children {
+        let child_name = child.path.file_stem().and_then(|segment| segment.to_str()).unwrap_or(&child.name).to_string();
+        folder_path.push(child_name);
+        collect_ssh_config_hosts(child, hosts, folder_path, collected, duplicates);
+        folder_path.pop();
+    }
+}
+
+type InventoryHosts = (BTreeMap<String, InventorySideHost>, HashMap<String, String>);
+
+fn load_inventory_hosts(inventory_path: &Path, skipped: &mut Vec<(String, String)>) -> io::Result<InventoryHosts> {
+    let mut hosts = BTreeMap::new();
+    let mut option_names = HashMap::new();
+    if !inventory_path.exists() {
+        return Ok((hosts, option_names));
+    }
+
+    for host in build_inventory_tree(inventory_path)?.hosts {
+        if let Some(source) = &host.dynamic_source {
+            skipped.push((host.name.clone(), format!("comes from inventory source '{source}'")));
+            continue;
+        }
+        if host.name.contains(char::is_whitespace) {
+            skipped.push((host.name.clone(), "names with whitespace cannot be OpenSSH aliases".to_string()));
+            continue;
+        }
+        for option in host.ssh.extra_options.keys() {
+            option_names.entry(option.to_ascii_lowercase()).or_insert_with(|| option.clone());
+        }
+        let entry = InventorySideHost {
+            fields: inventory_host_fields(&host),
+            source_file: host.source_file.clone(),
+        };
+        hosts.insert(host.name, entry);
+    }
+    Ok((hosts, option_names))
+}
+
+fn ssh_host_fields(host: &SshHost) -> SyncFields {
+    let mut fields = SyncFields::new();
+    insert_field(&mut fields, "description", host.description.clone());
+    insert_field(
+        &mut fields,
+        "protocol",
+        (host.protocol != ConnectionProtocol::Ssh).then(|| host.protocol.to_string()),
+    );
+    insert_field(&mut fields, "profile", host.profile.clone());
+    insert_field(&mut fields, "vault_pass", host.pass_key.clone());
+    insert_field(&mut fields, "rdp_domain", host.rdp_domain.clone());
+    insert_field(&mut fields, "rdp_args", host.rdp_args.iter().cloned());
+    insert_field(&mut fields, "hidden", host.hidden.then(|| "true".to_string()));
+    insert_field(&mut fields, "host", Some(host.hostname.clone().unwrap_or_else(|| host.name.clone())));
+    insert_field(&mut fields, "user", host.user.clone());
+    insert_field(&mut fields, "port", host.port.map(|port| port.to_string()));
+    insert_field(
+        &mut fields,
+        "identity_file",
+        host.identity_files.iter().map(|path| normalize_identity_file(path)),
+    );
+    insert_field(&mut fields, "identities_only", host.identities_only.map(yes_no));
+    insert_field(&mut fields, "proxy_jump", host.proxy_jump.clone());
+    insert_field(&mut fields, "proxy_command", host.proxy_command.clone());
+    insert_field(&mut fields, "forward_agent", host.forward_agent.as_deref().map(normalize_flag));
+    insert_field(
+        &mut fields,
+        "local_forward",
+        host.local_forward.iter().map(|spec| normalize_ssh_forward_spec(spec)),
+    );
+    insert_field(
+        &mut fields,
+        "remote_forward",
+        host.remote_forward.iter().map(|spec| normalize_ssh_forward_spec(spec)),
+    );
+    for (option, values) in &host.other_options {
+        insert_field(&mut fields, &format!("ssh_options.{}", option.to_ascii_lowercase()), values.iter().cloned());
+    }
+    fields
+}
+
+fn inventory_host_fields(host: &InventoryHost) -> SyncFields {
+    let mut fields = SyncFields::new();
+    insert_field(&mut fields, "description", host.description.clone());
+    insert_field(
+        &mut fields,
+        "protocol",
+        (host.protocol != ConnectionProtocol::Ssh).then(|| host.protocol.to_string()),
+    );
+    insert_field(&mut fields, "profile", host.profile.clone());
+    insert_field(&mut fields, "vault_pass", host.vault_pass.clone());
+    insert_field(&mut fields, "rdp_domain", host.rdp.domain.clone());
+    insert_field(&mut fields, "rdp_args", host.rdp.args.iter().cloned());
+    insert_field(&mut fields, "hidden", host.hidden.then(|| "true".to_string()));
+    insert_field(&mut fields, "host", Some(host.host.clone()));
+    insert_field(&mut fields, "user", host.user.clone());
+    insert_field(&mut fields, "port", host.port.map(|port| port.to_string()));
+    insert_field(
+        &mut fields,
+        "identity_file",
+        host.ssh.identity_files.iter().map(|path| normalize_identity_file(path)),
+    );
+    insert_field(&mut fields, "identities_only", host.ssh.identities_only.map(yes_no));
+    insert_field(&mut fields, "proxy_jump", host.ssh.proxy_jump.clone());
+    insert_field(&mut fields, "proxy_command", host.ssh.proxy_command.clone());
+    insert_field(&mut fields, "forward_agent", host.ssh.forward_agent.as_deref().map(normalize_flag));
+    insert_field(
+        &mut fields,
+        "local_forward",
+        host.ssh.local_forward.iter().map(|spec| normalize_ssh_forward_spec(spec)),
+    );
+    insert_field(
+        &mut fields,
+        "remote_forward",
+        host.ssh.remote_forward.iter().map(|spec| normalize_ssh_forward_spec(spec)),
+    );
+    for (option, values) in &host.ssh.extra_options {
+        insert_field(&mut fields, &format!("ssh_options.{}", option.to_ascii_lowercase()), values.iter().cloned());
+    }
+    fields
+}
+
+fn insert_field(fields: &mut SyncFields, field: &str, values: impl IntoIterator<Item = String>) {
+    let values: Vec<String> = values
+        .into_iter()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect();
+    set_field(fields, field, &values);
+}
+
+fn set_field(fields: &mut SyncFields, field: &str, values: &[String]) {
+    if values.is_empty() {
+        fields.remove(field);
+    } else {
+        fields.insert(field.to_string(), values.to_vec());
+    }
+}
+
+fn normalize_identity_file(path: &str) -> String {
+    let path = path.trim();
+    let path = path.strip_prefix('"').and_then(|path| path.strip_suffix('"')).unwrap_or(path);
+    expand_tilde(path)
+}
+
+fn normalize_flag(value: &str) -> String {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => "yes".to_string(),
+        "0" | "false" | "no" | "off" => "no".to_string(),
+        _ => value.trim().to_string(),
+    }
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_string()
+}
+
+fn diff_fields(ssh: &SyncFields, inventory: &SyncFields, base: Option<&SyncFields>) -> Vec<FieldSync> {
+    let keys: BTreeSet<&String> = ssh.keys().chain(inventory.keys()).collect();
+    keys.into_iter()
+        .filter_map(|field| {
+            let ssh_value = ssh.get(field).cloned().unwrap_or_default();
+            let inventory_value = inventory.get(field).cloned().unwrap_or_default();
+            if ssh_value == inventory_value {
+                return None;
+            }
+            let winner = base.and_then(|base| {
+                let base_value = base.get(field).map_or(&[] as &[String], Vec::as_slice);
+                if ssh_value == base_value {
+                    Some(SyncSide::Inventory)
+                } else if inventory_value == base_value {
+                    Some(SyncSide::SshConfig)
+                } else {
+                    None
+                }
+            });
+            Some(FieldSync {
+                field: field.clone(),
+                ssh_config: ssh_value,
+                inventory: inventory_value,
+                winner,
+            })
+        })
+        .collect()
+}
+
+/// YAML value for an inventory field, or `None` to remove the key.
+fn inventory_value(field: &str, values: &[String]) -> Option<Value> {
+    let first = values.first()?;
+    let strings = || Value::Sequence(values.iter().cloned().map(Value::String).collect());
+    Some(match field {
+        "port" => first
+            .parse::<u16>()
+            .map_or_else(|_| Value::String(first.clone()), |port| Value::Number(port.into())),
+        "hidden" => Value::Bool(true),
+        "identities_only" => Value::Bool(first == "yes"),
+        "identity_file" | "local_forward" | "remote_forward" | "rdp_args" => strings(),
+        _ if values.len() > 1 => strings(),
+        _ => Value::String(first.clone()),
+    })
+}
+
+fn editable_host_from_fields(name: &str, fields: &SyncFields) -> EditableInventoryHost {
+    let scalar = |field: &str| fields.get(field).and_then(|values| values.first()).cloned();
+    let list = |field: &str| fields.get(field).cloned().unwrap_or_default();
+    EditableInventoryHost {
+        name: name.to_string(),
+        description: scalar("description"),
+        protocol: scalar("protocol").map_or(ConnectionProtocol::Ssh, |protocol| ConnectionProtocol::from(protocol.as_str())),
+        host: scalar("host").unwrap_or_else(|| name.to_string()),
+        user: scalar("user"),
+        port: scalar("port").and_then(|port| port.parse().ok()),
+        profile: scalar("profile"),
+        vault_pass: scalar("vault_pass"),
+        hidden: fields.contains_key("hidden"),
+        ssh_identity_files: list("identity_file"),
+        ssh_identities_only: scalar("identities_only").map(|value| value == "yes"),
+        ssh_proxy_jump: scalar("proxy_jump"),
+        ssh_proxy_command: scalar("proxy_command"),
+        ssh_forward_agent: scalar("forward_agent"),
+        ssh_local_forward: list("local_forward"),
+        ssh_remote_forward: list("remote_forward"),
+        ssh_options: fields
+            .iter()
+            .filter_map(|(field, values)| Some((field.strip_prefix("ssh_options.")?.to_string(), values.clone())))
+            .collect(),
+        rdp_domain: scalar("rdp_domain"),
+        rdp_args: list("rdp_args"),
+    }
+}
+
+fn snapshot_path(inventory_path: &Path) -> PathBuf {
+    inventory_path.parent().unwrap_or(Path::new(".")).join("cache").join(SNAPSHOT_FILE_NAME)
+}
+
+/// Hosts as of the last sync, when it was made against the same SSH config.
+fn load_snapshot(inventory_path: &Path, ssh_config_path: &Path) -> Option<BTreeMap<String, SyncFields>> {
+    let contents = fs::read_to_string(snapshot_path(inventory_path)).ok()?;
+    let snapshot: SyncSnapshot = serde_json::from_str(&contents).ok()?;
+    (snapshot.ssh_config == std::path::absolute(ssh_config_path).ok()?).then_some(snapshot.hosts)
+}
+
+fn save_snapshot(inventory_path: &Path, ssh_config_path: &Path, hosts: BTreeMap<String, SyncFields>) -> io::Result<()> {
+    let snapshot = SyncSnapshot {
+        ssh_config: std::path::absolute(ssh_config_path)?,
+        hosts,
+    };
+    let path = snapshot_path(inventory_path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&snapshot).map_err(io::Error::other)?)
+}
+
+/// One change to the text of an SSH config file.
+enum SshConfigEdit<'a> {
+    Append {
+        name: &'a str,
+        fields: &'a SyncFields,
+    },
+    Remove {
+        name: &'a str,
+    },
+    /// Rewrite the lines of `changes`; `fields` are all of the host's values after the sync.
+    Update {
+        name: &'a str,
+        changes: Vec<&'a str>,
+        fields: SyncFields,
+    },
+}
+
+fn edit_ssh_config_text(contents: &str, edits: &[SshConfigEdit<'_>], option_names: &HashMap<String, String>) -> String {
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    for edit in edits {
+        match edit {
+            SshConfigEdit::Append { name, fields } => {
+                let index = new_block_position(&lines);
+                let mut block = render_host_block(name, fields, option_names);
+                if index > 0 && !lines[index - 1].trim().is_empty() {
+                    block.insert(0, String::new());
+                }
+                if index < lines.len() {
+                    block.push(String::new());
+                }
+                lines.splice(index..index, block);
+            }
+            SshConfigEdit::Remove { name } => remove_host_alias(&mut lines, name),
+            SshConfigEdit::Update { name, changes, fields } => update_host_block(&mut lines, name, changes, fields, option_names),
+        }
+    }
+
+    let mut output = lines.join("\n");
+    output.push('\n');
+    output
+}
+
+/// Where new blocks go: ahead of the first `Match` block or wildcard-only
+/// `Host` block and the comments above it, since OpenSSH takes the first value
+/// it finds and those blocks usually hold defaults for every host.
+fn new_block_position(lines: &[String]) -> usize {
+    let Some(mut index) = lines.iter().position(|line| match line_keyword(line).as_deref() {
+        Some("match") => true,
+        Some("host") => host_line_aliases(line).iter().all(|alias| alias.contains(['*', '?', '!'])),
+        _ => false,
+    }) else {
+        return lines.len();
+    };
+    while index > 0 && lines[index - 1].trim_start().starts_with('#') && !lines[index - 1].trim_start().starts_with("#_") {
+        index -= 1;
+    }
+    index
+}
+
+/// The `Host` block naming `alias`: its `Host` line through its last option
+/// or `#_` tag line, leaving blank lines and comments before the next block out.
+fn find_host_block(lines: &[String], alias: &str) -> Option<Range<usize>> {
+    let start = lines
+        .iter()
+        .position(|line| line_keyword(line).as_deref() == Some("host") && host_line_aliases(line).contains(&alias))?;
+    let next_block = lines[start + 1..]
+        .iter()
+        .position(|line| matches!(line_keyword(line).as_deref(), Some("host" | "match")))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+    let end = (start + 1..next_block)
+        .rev()
+        .find(|&index| line_keyword(&lines[index]).is_some() || lines[index].trim_start().starts_with("#_"))
+        .map_or(start + 1, |index| index + 1);
+    Some(start..end)
+}
+
+/// Lowercase keyword of an option line; `None` for blank lines and comments.
+fn line_keyword(line: &str) -> Option<String> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('#') {
+        return None;
+    }
+    trimmed.split_whitespace().next().map(str::to_ascii_lowercase)
+}
+
+fn host_line_aliases(line: &str) -> Vec<&str> {
+    line.split_whitespace().skip(1).collect()
+}
+
+fn remove_host_alias(lines: &mut Vec<String>, alias: &str) {
+    let Some(block) = find_host_block(lines, alias) else {
+        return;
+    };
+    let aliases = host_line_aliases(&lines[block.start]);
+    if aliases.len() > 1 {
+        lines[block.start] = host_line_without(&lines[block.start], alias);
+        return;
+    }
+
+    let start = block.start;
+    lines.drain(block);
+    // Drop the separator left behind so blocks stay one blank line apart.
+    if lines.get(start).is_some_and(|line| line.trim().is_empty()) && (start == 0 || lines[start - 1].trim().is_empty()) {
+        lines.remove(start);
+    }
+}
+
+fn host_line_without(line: &str, alias: &str) -> String {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let mut tokens = line.split_whitespace();
+    let keyword = tokens.next().unwrap_or("Host");
+    let aliases: Vec<&str> = tokens.filter(|token| *token != alias).collect();
+    format!("{indent}{keyword} {}", aliases.join(" "))
+}
+
+fn update_host_block(lines: &mut Vec<String>, alias: &str, changes: &[&str], fields: &SyncFields, option_names: &HashMap<String, String>) {
+    let Some(block) = find_host_block(lines, alias) else {
+        return;
+    };
+
+    // A block shared with other aliases keeps their settings; the host gets a block of its own.
+    if host_line_aliases(&lines[block.start]).len() > 1 {
+        lines[block.start] = host_line_without(&lines[block.start], alias);
+        let mut own_block = vec![String::new()];
+        own_block.extend(render_host_block(alias, fields, option_names));
+        lines.splice(block.end..block.end, own_block);
+        return;
+    }
+
+    let indent = lines[block.start + 1..block.end]
+        .iter()
+        .find(|line| !line.trim().is_empty())
+        .map_or("    ".to_string(), |line| line[..line.len() - line.trim_start().len()].to_string());
+    for field in changes {
+        let Some(block) = find_host_block(lines, alias) else {
+            return;
+        };
+        let existing: Vec<usize> = (block.start + 1..block.end).filter(|&index| line_holds_field(&lines[index], field)).collect();
+        // Keep the keyword as the file spells it.
+        let keyword = existing
+            .first()
+            .and_then(|&index| lines[index].split_whitespace().next())
+            .map_or_else(|| ssh_config_keyword(field, option_names).to_string(), str::to_string);
+        let new_lines = ssh_config_lines(field, &keyword, fields.get(*field).map_or(&[], Vec::as_slice), &indent);
+        let insert_at = existing.first().copied().unwrap_or(block.end);
+        for &index in existing.iter().rev() {
+            lines.remove(index);
+        }
+        lines.splice(insert_at..insert_at, new_lines);
+    }
+}
+
+fn line_holds_field(line: &str, field: &str) -> bool {
+    let keyword = field.strip_prefix("ssh_options.").unwrap_or_else(|| field_keyword(field));
+    if keyword.starts_with("#_") {
+        line.trim_start().starts_with(keyword)
+    } else {
+        line_keyword(line).is_some_and(|line_keyword| line_keyword.eq_ignore_ascii_case(keyword))
+    }
+}
+
+fn ssh_config_keyword<'a>(field: &'a str, option_names: &'a HashMap<String, String>) -> &'a str {
+    if let Some(option) = field.strip_prefix("ssh_options.") {
+        return option_names.get(option).map_or(option, String::as_str);
+    }
+    field_keyword(field)
+}
+
+fn field_keyword(field: &str) -> &str {
+    SSH_CONFIG_FIELDS.iter().find(|(key, _)| *key == field).map_or(field, |(_, keyword)| keyword)
+}
+
+fn ssh_config_lines(field: &str, keyword: &str, values: &[String], indent: &str) -> Vec<String> {
+    if values.is_empty() {
+        return Vec::new();
+    }
+    if field == "rdp_args" {
+        return vec![format!("{indent}{keyword} {}", values.join(" "))];
+    }
+    values
+        .iter()
+        .map(|value| {
+            let value = match field {
+                "identity_file" => quote_ssh_value(value),
+                "local_forward" | "remote_forward" => ssh_forward_arguments(value),
+                _ => value.clone(),
+            };
+            format!("{indent}{keyword} {value}")
+        })
+        .collect()
+}
+
+fn render_host_block(alias: &str, fields: &SyncFields, option_names: &HashMap<String, String>) -> Vec<String> {
+    let mut lines = vec![format!("Host {alias}")];
+    let options = fields.keys().filter(|field| field.starts_with("ssh_options.")).map(String::as_str);
+    for field in SSH_CONFIG_FIELDS.iter().map(|(field, _)| *field).chain(options) {
+        if let Some(values) = fields.get(field) {
+            lines.extend(ssh_config_lines(field, ssh_config_keyword(field, option_names), values, "    "));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+#[path = "../test/inventory/sync.rs"]
+mod tests;
@@ -2,14 +2,55 @@
 
 use super::error::{InventoryResult, invalid_inventory};
 use super::include::{expand_include_pattern, resolve_include_pattern};
-use super::model::{FolderId, InventoryHost, InventoryNodeRaw, InventoryTreeModel, ParsedInventoryDocument, TreeFolder};
-use super::normalize::normalize_inventory_host;
+use super::model::{FolderId, InventoryHost, InventoryHostRaw, InventoryNodeRaw, InventoryTemplates, InventoryTreeModel, ParsedInventoryDocument, TreeFolder};
+use super::normalize::{inherit_host_defaults, normalize_inventory_host};
 use super::parse::parse_inventory_document;
+use super::source::{SourceLoader, SourceRefresh};
 use crate::log_debug;
 use std::collections::{HashMap, HashSet};
 use std::io;
 use std::path::{Path, PathBuf};
 
+/// Defaults and templates in effect at one point of the inventory tree.
+#[derive(Debug, Clone, Default)]
+struct InheritedScope {
+    defaults: InventoryHostRaw,
+    templates: InventoryTemplates,
+}
+
+impl InheritedScope {
+    /// Scope for a document's own entries: its defaults layer over the
+    /// parent's and its templates shadow same-named parent templates.
+    fn nested(&self, mut defaults: InventoryHostRaw, templates: InventoryTemplates) -> Self {
+        inherit_host_defaults(&mut defaults, &self.defaults);
+        let mut merged_templates = self.templates.clone();
+        merged_templates.extend(templates);
+        Self {
+            defaults,
+            templates: merged_templates,
+        }
+    }
+}
+
+/// Hosts collected so far and the bookkeeping shared by one tree build.
+struct TreeLoadState {
+    hosts: Vec<InventoryHost>,
+    seen_host_names: HashMap<String, PathBuf>,
+    visited: HashSet<PathBuf>,
+    sources: SourceLoader,
+    report: InventoryLoadReport,
+}
+
+/// Problems the loader steps over instead of failing, for `cossh inventory check`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct InventoryLoadReport {
+    /// Host name, the file that defined it first, and the file repeating it.
+    /// The repeated definition is left out of the tree.
+    pub(crate) duplicate_hosts: Vec<(String, PathBuf, PathBuf)>,
+    /// Inventory file and `include:` pattern that matched no files.
+    pub(crate) empty_includes: Vec<(PathBuf, String)>,
+}
+
 #[derive(Debug)]
 struct FolderAccumulator {
     name: String,
@@ -40,10 +81,49 @@ impl FolderAccumulator {
 }
 
 pub(crate) fn build_inventory_tree(inventory_path: &Path) -> io::Result<InventoryTreeModel> {
-    build_inventory_tree_internal(inventory_path).map_err(io::Error::from)
+    build_inventory_tree_internal(inventory_path, SourceRefresh::UseCache)
+        .and_then(reject_duplicate_hosts)
+        .map_err(io::Error::from)
+}
+
+/// Build the tree, running every command `source:` again instead of reusing cached output.
+pub(crate) fn refresh_inventory_tree(inventory_path: &Path) -> io::Result<InventoryTreeModel> {
+    build_inventory_tree_internal(inventory_path, SourceRefresh::Force)
+        .and_then(reject_duplicate_hosts)
+        .map_err(io::Error::from)
+}
+
+/// Build the tree from cached command source output only, so it never waits
+/// on a command; stale or missing sources are reported as due for refresh.
+pub(crate) fn build_cached_inventory_tree(inventory_path: &Path) -> io::Result<InventoryTreeModel> {
+    build_inventory_tree_internal(inventory_path, SourceRefresh::CacheOnly)
+        .and_then(reject_duplicate_hosts)
+        .map_err(io::Error::from)
+}
+
+/// Build the tree without failing on duplicate host names, reporting them and
+/// includes that matched nothing instead.
+pub(crate) fn build_inventory_tree_with_report(inventory_path: &Path) -> InventoryResult<(InventoryTreeModel, InventoryLoadReport)> {
+    build_inventory_tree_internal(inventory_path, SourceRefresh::UseCache)
+}
+
+// Host aliases are globally unique across merged include files.
+fn reject_duplicate_hosts((tree, report): (InventoryTreeModel, InventoryLoadReport)) -> InventoryResult<InventoryTreeModel> {
+    match report.duplicate_hosts.into_iter().next() {
+        Some((name, first_file, repeated_file)) => Err(invalid_inventory(
+            &repeated_file,
+            format!(
+                "duplicate inventory host '{}' found in '{}' and '{}'",
+                name,
+                first_file.display(),
+                repeated_file.display()
+            ),
+        )),
+        None => Ok(tree),
+    }
 }
 
-fn build_inventory_tree_internal(inventory_path: &Path) -> InventoryResult<InventoryTreeModel> {
+fn build_inventory_tree_internal(inventory_path: &Path, source_refresh: SourceRefresh) -> InventoryResult<(InventoryTreeModel, InventoryLoadReport)> {
     log_debug!("Building inventory tree from '{}'", inventory_path.display());
 
     let root_name = inventory_path
@@ -52,12 +132,17 @@ fn build_inventory_tree_internal(inventory_path: &Path) -> InventoryResult<Inven
         .unwrap_or("cossh-inventory.yaml")
         .to_string();
     let mut root = FolderAccumulator::new(root_name, inventory_path.to_path_buf());
-    let mut hosts = Vec::new();
-    let mut seen_host_names = HashMap::new();
-    let mut visited = HashSet::new();
+    let mut state = TreeLoadState {
+        hosts: Vec::new(),
+        seen_host_names: HashMap::new(),
+        visited: HashSet::new(),
+        sources: SourceLoader::new(inventory_path, source_refresh),
+        report: InventoryLoadReport::default(),
+    };
 
-    load_document_recursive(inventory_path, &mut root, &mut hosts, &mut seen_host_names, &mut visited, &[])?;
+    load_document_recursive(inventory_path, &mut root, &mut state, &[], &InheritedScope::default())?;
 
+    let TreeLoadState { hosts, sources, report, .. } = state;
     log_debug!(
         "Inventory tree build complete: {} host(s) loaded from '{}'",
         hosts.len(),
@@ -67,32 +152,44 @@ fn build_inventory_tree_internal(inventory_path: &Path) -> InventoryResult<Inven
     let mut next_id: FolderId = 0;
     let mut tree_root = finalize_folder(root, &mut next_id);
     sort_tree_folder_by_host_name(&mut tree_root, &hosts, |host| host.name.as_str());
-    Ok(InventoryTreeModel { root: tree_root, hosts })
+    let tree = InventoryTreeModel {
+        root: tree_root,
+        hosts,
+        source_refresh_due: sources.refresh_due(),
+    };
+    Ok((tree, report))
 }
 
 fn load_document_recursive(
     inventory_path: &Path,
     folder: &mut FolderAccumulator,
-    hosts: &mut Vec<InventoryHost>,
-    seen_host_names: &mut HashMap<String, PathBuf>,
-    visited: &mut HashSet<PathBuf>,
+    state: &mut TreeLoadState,
     folder_path: &[String],
+    inherited: &InheritedScope,
 ) -> InventoryResult<()> {
-    let canonical = inventory_path.canonicalize().unwrap_or_else(|_| inventory_path.to_path_buf());
+    let canonical = canonical_path(inventory_path);
 
     log_debug!("Loading inventory document '{}'", canonical.display());
 
-    if !visited.insert(canonical.clone()) {
+    if !state.visited.insert(canonical.clone()) {
         // Include cycles are ignored once a file has already been loaded.
         log_debug!("Skipping already visited inventory file (possible include cycle): {}", canonical.display());
         return Ok(());
     }
 
-    let ParsedInventoryDocument { include, inventory } = parse_inventory_document(&canonical)?;
+    let ParsedInventoryDocument {
+        include,
+        sources,
+        defaults,
+        templates,
+        inventory,
+    } = parse_inventory_document(&canonical)?;
+    let scope = inherited.nested(defaults, templates);
     log_debug!(
-        "Parsed inventory document '{}' (include count: {}, inventory node count: {})",
+        "Parsed inventory document '{}' (include count: {}, source count: {}, inventory node count: {})",
         canonical.display(),
         include.len(),
+        sources.len(),
         inventory.len()
     );
     let parent_dir = canonical.parent().unwrap_or(Path::new("."));
@@ -109,6 +206,7 @@ fn load_document_recursive(
         let resolved_pattern = resolve_include_pattern(&include_pattern, parent_dir);
         let include_paths = expand_include_pattern(&resolved_pattern);
         if include_paths.is_empty() {
+            state.report.empty_includes.push((canonical.clone(), include_pattern.clone()));
             log_debug!(
                 "Inventory include '{}' in '{}' resolved to '{}' but matched no files",
                 include_pattern,
@@ -127,12 +225,34 @@ fn load_document_recursive(
 
         for include_path in include_paths {
             log_debug!("Loading include '{}' referenced by '{}'", include_path.display(), canonical.display());
-            load_include_document(&include_path, include_parent, hosts, seen_host_names, visited, &include_folder_path)?;
+            load_include_document(&include_path, include_parent, state, &include_folder_path, &scope)?;
+        }
+    }
+
+    for source in sources {
+        let mut source_parent = &mut *folder;
+        let mut source_folder_path = folder_path.to_vec();
+        for segment in &source.folder_path {
+            source_parent = source_parent.child_mut(segment, &canonical);
+            source_folder_path.push(segment.clone());
+        }
+
+        let source_folder = state.sources.load(&source, &canonical)?;
+        let first_host_idx = state.hosts.len();
+        add_inventory_node(source_folder, source_parent, state, &canonical, &source_folder_path, &scope)?;
+        log_debug!(
+            "Loaded {} host(s) from inventory source '{}' in '{}'",
+            state.hosts.len() - first_host_idx,
+            source.name,
+            canonical.display()
+        );
+        for host in &mut state.hosts[first_host_idx..] {
+            host.dynamic_source = Some(source.name.clone());
         }
     }
 
     for node in inventory {
-        add_inventory_node(node, folder, hosts, seen_host_names, &canonical, folder_path)?;
+        add_inventory_node(node, folder, state, &canonical, folder_path, &scope)?;
     }
 
     Ok(())
@@ -141,16 +261,15 @@ fn load_document_recursive(
 fn load_include_document(
     inventory_path: &Path,
     parent_folder: &mut FolderAccumulator,
-    hosts: &mut Vec<InventoryHost>,
-    seen_host_names: &mut HashMap<String, PathBuf>,
-    visited: &mut HashSet<PathBuf>,
+    state: &mut TreeLoadState,
     parent_folder_path: &[String],
+    inherited: &InheritedScope,
 ) -> InventoryResult<()> {
-    let canonical = inventory_path.canonicalize().unwrap_or_else(|_| inventory_path.to_path_buf());
+    let canonical = canonical_path(inventory_path);
 
     log_debug!("Preparing included inventory file '{}'", canonical.display());
 
-    if visited.contains(&canonical) {
+    if state.visited.contains(&canonical) {
         // Child include may point back to an ancestor; skip already-loaded file.
         log_debug!("Skipping already visited inventory file (possible include cycle): {}", canonical.display());
         return Ok(());
@@ -161,7 +280,40 @@ fn load_include_document(
     let child = parent_folder.child_mut(&folder_name, &canonical);
     let mut child_path = parent_folder_path.to_vec();
     child_path.push(folder_name);
-    load_document_recursive(&canonical, child, hosts, seen_host_names, visited, &child_path)
+    load_document_recursive(&canonical, child, state, &child_path, inherited)
+}
+
+/// A host with the tree folder path it is shown under.
+pub(crate) struct InventoryHostEntry<'a> {
+    pub(crate) folder_path: Vec<String>,
+    pub(crate) host: &'a InventoryHost,
+}
+
+/// Hosts under `folder` and its subfolders in tree order, with folder paths
+/// continuing from `folder_path`, the path of `folder` itself.
+pub(crate) fn collect_tree_hosts<'a>(folder: &TreeFolder, hosts: &'a [InventoryHost], mut folder_path: Vec<String>) -> Vec<InventoryHostEntry<'a>> {
+    let mut entries = Vec::new();
+    collect_folder_hosts(folder, hosts, &mut folder_path, &mut entries);
+    entries
+}
+
+fn collect_folder_hosts<'a>(folder: &TreeFolder, hosts: &'a [InventoryHost], folder_path: &mut Vec<String>, entries: &mut Vec<InventoryHostEntry<'a>>) {
+    for host in folder.host_indices.iter().filter_map(|&index| hosts.get(index)) {
+        entries.push(InventoryHostEntry {
+            folder_path: folder_path.clone(),
+            host,
+        });
+    }
+    for child in &folder.children {
+        folder_path.push(child.name.clone());
+        collect_folder_hosts(child, hosts, folder_path, entries);
+        folder_path.pop();
+    }
+}
+
+/// `path` with symlinks and `..` resolved, or unchanged when it cannot be.
+pub(crate) fn canonical_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
 }
 
 fn inventory_folder_name(path: &Path) -> String {
@@ -214,26 +366,22 @@ pub(crate) fn sort_tree_folder_by_host_name<T>(folder: &mut TreeFolder, hosts: &
 fn add_inventory_node(
     node: InventoryNodeRaw,
     folder: &mut FolderAccumulator,
-    hosts: &mut Vec<InventoryHost>,
-    seen_host_names: &mut HashMap<String, PathBuf>,
+    state: &mut TreeLoadState,
     source_file: &Path,
     folder_path: &[String],
+    scope: &InheritedScope,
 ) -> InventoryResult<()> {
     match node {
         InventoryNodeRaw::Host(raw) => {
-            let host = normalize_inventory_host(*raw, source_file, folder_path)?;
-            // Host aliases are globally unique across merged include files.
-            if let Some(previous_path) = seen_host_names.insert(host.name.clone(), host.source_file.clone()) {
-                return Err(invalid_inventory(
-                    source_file,
-                    format!(
-                        "duplicate inventory host '{}' found in '{}' and '{}'",
-                        host.name,
-                        previous_path.display(),
-                        host.source_file.display()
-                    ),
-                ));
+            let host = normalize_inventory_host(*raw, &scope.defaults, &scope.templates, source_file, folder_path)?;
+            if let Some(previous_path) = state.seen_host_names.get(&host.name) {
+                state
+                    .report
+                    .duplicate_hosts
+                    .push((host.name.clone(), previous_path.clone(), host.source_file.clone()));
+                return Ok(());
             }
+            state.seen_host_names.insert(host.name.clone(), host.source_file.clone());
 
             log_debug!(
                 "Loaded inventory host '{}' (protocol='{}', source='{}')",
@@ -241,16 +389,21 @@ fn add_inventory_node(
                 host.protocol.as_str(),
                 host.source_file.display()
             );
-            folder.host_indices.push(hosts.len());
-            hosts.push(host);
+            folder.host_indices.push(state.hosts.len());
+            state.hosts.push(host);
             Ok(())
         }
-        InventoryNodeRaw::Folder { name, items } => {
+        InventoryNodeRaw::Folder {
+            name,
+            defaults: folder_defaults,
+            items,
+        } => {
             let child = folder.child_mut(&name, source_file);
             let mut child_path = folder_path.to_vec();
             child_path.push(name);
+            let folder_scope = scope.nested(*folder_defaults, InventoryTemplates::new());
             for item in items {
-                add_inventory_node(item, child, hosts, seen_host_names, source_file, &child_path)?;
+                add_inventory_node(item, child, state, source_file, &child_path, &folder_scope)?;
             }
             Ok(())
         }
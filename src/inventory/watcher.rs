@@ -2,6 +2,7 @@
 
 use super::error::InventoryResult;
 use super::include::{expand_include_pattern, resolve_include_pattern};
+use super::model::InventorySourceKind;
 use super::parse::parse_inventory_document;
 use notify::Event;
 use std::collections::HashSet;
@@ -81,6 +82,13 @@ fn collect_watch_targets(
         }
     }
 
+    // File exports read by `source:` entries reload like included files.
+    for source in document.sources {
+        if let InventorySourceKind::File(path) = source.kind {
+            tracked_files.push(canonicalize_or_self(Path::new(&resolve_include_pattern(&path, parent_dir))));
+        }
+    }
+
     Ok(())
 }
 
@@ -0,0 +1,105 @@
+use nix::fcntl::{FcntlArg, OFlag, fcntl};
+use std::io::{self, Read};
+use std::os::fd::AsFd;
+use std::process::{Child, ExitStatus};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
+
+const CHILD_POLL_INTERVAL: Duration = Duration::from_millis(10);
+const PIPE_READ_CHUNK: usize = 4096;
+
+#[derive(Debug)]
+/// Exit status and captured pipes of a finished child process.
+pub(crate) struct ChildOutput {
+    pub(crate) status: ExitStatus,
+    pub(crate) stdout: Zeroizing<Vec<u8>>,
+    pub(crate) stderr: Zeroizing<Vec<u8>>,
+}
+
+/// Wait up to `timeout` for `child` to exit while draining its stdout and stderr.
+///
+/// The pipes are read on helper threads so a child writing more than the pipe
+/// buffer cannot stall until the timeout. Once the child exits, only output
+/// already in the pipes is collected, so a background process that inherited
+/// them cannot hold the call open. Captured bytes are wiped when dropped.
+/// Returns `Ok(None)` after killing a child that did not exit in time.
+pub(crate) fn wait_with_output_timeout(child: &mut Child, timeout: Duration) -> io::Result<Option<ChildOutput>> {
+    let deadline = Instant::now() + timeout;
+    let stop = Arc::new(AtomicBool::new(false));
+    let stdout_reader = child.stdout.take().map(|pipe| spawn_pipe_reader(pipe, &stop, deadline)).transpose()?;
+    let stderr_reader = child.stderr.take().map(|pipe| spawn_pipe_reader(pipe, &stop, deadline)).transpose()?;
+    let join = |reader: Option<thread::JoinHandle<Zeroizing<Vec<u8>>>>| reader.and_then(|reader| reader.join().ok()).unwrap_or_default();
+
+    let status = loop {
+        let status = match child.try_wait() {
+            Ok(status) => status,
+            Err(err) => {
+                stop.store(true, Ordering::Release);
+                return Err(err);
+            }
+        };
+        if let Some(status) = status {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            stop.store(true, Ordering::Release);
+            join(stdout_reader);
+            join(stderr_reader);
+            return Ok(None);
+        }
+        thread::sleep(CHILD_POLL_INTERVAL);
+    };
+
+    stop.store(true, Ordering::Release);
+    Ok(Some(ChildOutput {
+        status,
+        stdout: join(stdout_reader),
+        stderr: join(stderr_reader),
+    }))
+}
+
+fn spawn_pipe_reader(pipe: impl Read + AsFd + Send + 'static, stop: &Arc<AtomicBool>, deadline: Instant) -> io::Result<thread::JoinHandle<Zeroizing<Vec<u8>>>> {
+    let flags = OFlag::from_bits_truncate(fcntl(&pipe, FcntlArg::F_GETFL)?);
+    fcntl(&pipe, FcntlArg::F_SETFL(flags | OFlag::O_NONBLOCK))?;
+    let stop = Arc::clone(stop);
+    Ok(thread::spawn(move || read_pipe(pipe, &stop, deadline)))
+}
+
+/// Read `pipe` until EOF, or until `stop` is set and the pipe is drained or `deadline` passed.
+fn read_pipe(mut pipe: impl Read, stop: &AtomicBool, deadline: Instant) -> Zeroizing<Vec<u8>> {
+    let mut output = Zeroizing::new(Vec::new());
+    let mut chunk = Zeroizing::new([0u8; PIPE_READ_CHUNK]);
+    loop {
+        if stop.load(Ordering::Acquire) && Instant::now() >= deadline {
+            break;
+        }
+        match pipe.read(&mut chunk[..]) {
+            Ok(0) => break,
+            Ok(read) => append_wiped(&mut output, &chunk[..read]),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                if stop.load(Ordering::Acquire) {
+                    break;
+                }
+                thread::sleep(CHILD_POLL_INTERVAL);
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(_) => break,
+        }
+    }
+    output
+}
+
+/// Append to `output`, growing it by hand so outgrown buffers are wiped rather than reallocated.
+fn append_wiped(output: &mut Zeroizing<Vec<u8>>, bytes: &[u8]) {
+    if output.capacity() - output.len() < bytes.len() {
+        let mut grown = Zeroizing::new(Vec::with_capacity((output.len() + bytes.len()).max(output.capacity() * 2)));
+        grown.extend_from_slice(output);
+        *output = grown;
+    }
+    output.extend_from_slice(bytes);
+}
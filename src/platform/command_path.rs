@@ -65,6 +65,7 @@ pub(crate) fn resolve_known_command_path(command: &str) -> io::Result<PathBuf> {
         "ssh" => ssh_path(),
         "xfreerdp3" | "xfreerdp" => xfreerdp_path(),
         "cossh" => cossh_path(),
+        "pass" | "op" | "bw" | "secret-tool" | "sh" => resolve_path_from_env(command),
         _ => Err(io::Error::new(
             io::ErrorKind::InvalidInput,
             format!("unsupported command path lookup: {command}"),
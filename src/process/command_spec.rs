@@ -1,5 +1,6 @@
 //! Prepared command model shared by SSH/RDP builders.
 
+use crate::auth::agent::VaultForward;
 use crate::auth::secret::SensitiveString;
 use crate::platform;
 use std::fmt;
@@ -17,6 +18,8 @@ pub(crate) struct PreparedCommand {
     pub(crate) stdin_payload: Option<SensitiveString>,
     /// Optional user-facing notice when fallback behavior was used.
     pub(crate) fallback_notice: Option<String>,
+    /// Vault forwarding socket of this launch; must outlive the spawned process.
+    pub(crate) vault_forward: Option<VaultForward>,
 }
 
 impl fmt::Debug for PreparedCommand {
@@ -28,6 +31,7 @@ impl fmt::Debug for PreparedCommand {
             .field("env_keys", &env_keys)
             .field("stdin_payload", &self.stdin_payload.as_ref().map(|_| "[REDACTED]"))
             .field("fallback_notice", &self.fallback_notice)
+            .field("vault_forward", &self.vault_forward.as_ref().map(VaultForward::socket_path))
             .finish()
     }
 }
@@ -40,6 +44,7 @@ impl PreparedCommand {
             env: Vec::new(),
             stdin_payload: None,
             fallback_notice: None,
+            vault_forward: None,
         }
     }
 }
@@ -17,6 +17,7 @@ pub(crate) use pty_output::{PtyLogTarget, spawn_pty_output_reader};
 pub(crate) use rdp_builder::{RdpLaunchMode, build_rdp_command_for_host_with_auth_settings, build_rdp_command_for_host_with_manual_password};
 pub(crate) use spawn_common::{io_other_error, spawn_captured_command, spawn_pty_command};
 pub(crate) use ssh_builder::{build_ssh_command_for_host, resolve_host_by_destination};
+pub(crate) use vault::{forwarded_vault_socket_for_entry, read_vault_entry_from_unlocked_agent};
 pub(crate) const DISABLE_VAULT_AUTOLOGIN_ENV: &str = "COSSH_DISABLE_VAULT_AUTOLOGIN";
 
 pub(crate) fn prefer_pty_centered_interactive_ssh_runtime() -> bool {
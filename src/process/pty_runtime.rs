@@ -277,6 +277,8 @@ pub(super) fn run_interactive_ssh(command_spec: PreparedCommand) -> Result<std::
 fn run_interactive_command(mut command_spec: PreparedCommand, log_target: PtyLogTarget) -> Result<std::process::ExitCode> {
     let fallback_notice = command_spec.fallback_notice.take();
     let stdin_payload = command_spec.stdin_payload.take();
+    // Held until the session ends so its `RemoteForward` keeps a local end.
+    let _vault_forward = command_spec.vault_forward.take();
     let history_buffer = direct_history_buffer();
     let mut runtime = spawn_interactive_pty_runtime(command_spec, history_buffer, log_target)?;
 
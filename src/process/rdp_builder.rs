@@ -276,8 +276,8 @@ fn resolve_direct_rdp_auth_mode_with(
         return Ok((RdpAuthMode::NativePrompt, None));
     };
 
-    let unlock_policy = UnlockPolicy::new(auth_settings.idle_timeout_seconds, auth_settings.session_timeout_seconds);
-    match resolve_vault_password_with_policy(&pass_entry_name, unlock_policy) {
+    let unlock_policy = UnlockPolicy::from_auth_settings(auth_settings);
+    match resolve_vault_password_with_policy(&pass_entry_name, unlock_policy, host) {
         Ok(password) => {
             if let Err(err) = validate_rdp_password_for_startup(&password) {
                 return direct_rdp_vault_autologin_failure(err.to_string(), explicit_pass_entry.is_some(), terminal_prompting_enabled);
@@ -333,9 +333,9 @@ pub(crate) fn build_rdp_command_for_host_with_auth_settings(
     explicit_pass_entry: Option<&str>,
     auth_settings: &AuthSettings,
 ) -> io::Result<PreparedRdpLaunch> {
-    let unlock_policy = UnlockPolicy::new(auth_settings.idle_timeout_seconds, auth_settings.session_timeout_seconds);
+    let unlock_policy = UnlockPolicy::from_auth_settings(auth_settings);
     let (auth_mode, fallback_notice) = resolve_rdp_auth_mode_with(host, explicit_pass_entry, |pass_entry_name| {
-        resolve_vault_password_with_policy(pass_entry_name, unlock_policy.clone()).map_err(|err| err.to_string())
+        resolve_vault_password_with_policy(pass_entry_name, unlock_policy.clone(), host).map_err(|err| err.to_string())
     });
     build_prepared_rdp_command(host, auth_mode, fallback_notice)
 }
@@ -53,7 +53,7 @@ pub(crate) fn spawn_command(command_spec: PreparedCommand, stdout: Stdio, stderr
     Ok(child)
 }
 
-pub(crate) fn spawn_passthrough(command_spec: PreparedCommand) -> Result<ExitCode> {
+pub(crate) fn spawn_passthrough(mut command_spec: PreparedCommand) -> Result<ExitCode> {
     log_debug!(
         "Spawning {} in passthrough mode: arg_count={} env_override_count={} stdin_payload={}",
         command_spec.program,
@@ -62,6 +62,8 @@ pub(crate) fn spawn_passthrough(command_spec: PreparedCommand) -> Result<ExitCod
         command_spec.stdin_payload.is_some()
     );
     log_debug_raw!("Spawning {} in passthrough mode with args: {:?}", command_spec.program, command_spec.args);
+    // Held until the child exits so its `RemoteForward` keeps a local end.
+    let _vault_forward = command_spec.vault_forward.take();
 
     let mut child = spawn_command(command_spec, Stdio::inherit(), Stdio::inherit()).map_err(|err| {
         log_error!("Failed to execute command in passthrough mode: {}", err);
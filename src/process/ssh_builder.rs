@@ -2,15 +2,17 @@
 
 use super::DISABLE_VAULT_AUTOLOGIN_ENV;
 use super::command_spec::{PreparedCommand, build_plain_ssh_command};
-use super::vault::{VaultAccessError, authorize_vault_entry};
+use super::vault::{VaultAccessError, authorize_vault_entry, forwarded_vault_socket_for_entry};
 use crate::args;
-use crate::args::validate_vault_entry_name;
-use crate::auth::{agent, secret::ExposeSecret, transport};
+use crate::args::validate_secret_reference;
+use crate::auth::vault::{self, VaultPaths};
+use crate::auth::{agent, ipc, secret::ExposeSecret, transport};
 use crate::config;
 use crate::inventory::{ConnectionProtocol, InventoryHost};
 use crate::log_debug;
 use std::collections::HashSet;
 use std::io;
+use std::path::Path;
 
 const SSH_FLAGS_WITH_SEPARATE_VALUES: &[&str] = &[
     "-b", "-B", "-c", "-D", "-E", "-e", "-F", "-I", "-i", "-J", "-L", "-l", "-m", "-O", "-o", "-p", "-P", "-Q", "-R", "-S", "-w", "-W",
@@ -371,8 +373,13 @@ fn inject_ssh_option(args: &mut Vec<String>, key: &str, value: impl Into<String>
     args.push(format!("{key}={}", value.into()));
 }
 
-fn configure_internal_askpass_for_entry(command: &mut PreparedCommand, pass_entry_name: &str) -> io::Result<()> {
-    if !validate_vault_entry_name(pass_entry_name) {
+fn configure_internal_askpass_for_entry(
+    command: &mut PreparedCommand,
+    pass_entry_name: &str,
+    host_alias: Option<&str>,
+    destination: Option<&str>,
+) -> io::Result<()> {
+    if !validate_secret_reference(pass_entry_name) {
         log_debug!("Resolved password vault entry name was invalid");
         return Err(io::Error::new(
             io::ErrorKind::InvalidInput,
@@ -381,7 +388,7 @@ fn configure_internal_askpass_for_entry(command: &mut PreparedCommand, pass_entr
     }
 
     let client = agent::AgentClient::new().map_err(|err| io::Error::other(err.to_string()))?;
-    let askpass_token = client.authorize_askpass(pass_entry_name).map_err(|err| {
+    let askpass_token = client.authorize_askpass(pass_entry_name, host_alias, destination).map_err(|err| {
         log_debug!("Failed to authorize internal askpass token: {}", err);
         io::Error::new(io::ErrorKind::PermissionDenied, format!("failed to authorize vault askpass token: {err}"))
     })?;
@@ -394,7 +401,135 @@ fn configure_internal_askpass_for_entry(command: &mut PreparedCommand, pass_entr
     Ok(())
 }
 
-pub(crate) fn synthesize_ssh_args(args: &[String], host: &InventoryHost) -> Vec<String> {
+/// Answer password prompts from the forwarding machine's vault instead of a local one.
+fn configure_forwarded_askpass_for_entry(command: &mut PreparedCommand, socket_path: &Path, pass_entry_name: &str) -> io::Result<()> {
+    let request = transport::ForwardedAskpassRequest {
+        socket_path: socket_path.to_path_buf(),
+        entry_name: pass_entry_name.to_string(),
+    };
+    transport::configure_forwarded_askpass_env(&mut command.env, &request).map_err(|err| {
+        log_debug!("Failed to configure forwarded askpass helper: {}", err);
+        io::Error::other(format!("failed to configure internal askpass helper: {err}"))
+    })
+}
+
+/// Open this launch's vault forwarding socket when the host has `vault_forward` enabled.
+///
+/// Every forwarded request is confirmed through `SSH_ASKPASS`, so a launch
+/// without a helper fails here instead of having each request refused.
+fn open_vault_forward(host: &InventoryHost) -> io::Result<Option<agent::VaultForward>> {
+    if !host.ssh.vault_forward {
+        return Ok(None);
+    }
+    if agent::askpass_helper().is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "vault_forward for host '{}' needs an SSH_ASKPASS helper to confirm remote requests; set SSH_ASKPASS or install ssh-askpass",
+                host.name
+            ),
+        ));
+    }
+    let Ok(paths) = VaultPaths::resolve_default() else {
+        return Ok(None);
+    };
+    match agent::open_vault_forward(&paths, &host.name, Some(&host.host)) {
+        Ok(vault_forward) => Ok(Some(vault_forward)),
+        Err(err) => {
+            log_debug!("Vault forwarding unavailable for host '{}': {}", host.name, err);
+            Ok(None)
+        }
+    }
+}
+
+/// Keep the launch's vault forward open with `command` and export the remote
+/// socket path for `SendEnv`.
+fn attach_vault_forward(command: &mut PreparedCommand, vault_forward: Option<agent::VaultForward>) {
+    if let Some(vault_forward) = vault_forward.as_ref() {
+        let remote_socket = transport::remote_vault_forward_socket(vault_forward.socket_path());
+        command
+            .env
+            .push((transport::VAULT_FORWARD_SOCKET_ENV.to_string(), remote_socket.to_string_lossy().into_owned()));
+    }
+    command.vault_forward = vault_forward;
+}
+
+/// Point SSH at the vault agent's key socket when `auth_settings.ssh_agent` is enabled.
+fn configure_vault_ssh_agent(command: &mut PreparedCommand) {
+    if !config::auth_settings().ssh_agent {
+        return;
+    }
+    let Ok(paths) = VaultPaths::resolve_default() else {
+        return;
+    };
+    let socket_path = ipc::ssh_agent_socket_path(&paths);
+    if !socket_path.exists() {
+        log_debug!("Vault SSH agent socket is not running; keeping the inherited SSH_AUTH_SOCK");
+        return;
+    }
+    command.env.push(("SSH_AUTH_SOCK".to_string(), socket_path.to_string_lossy().into_owned()));
+    log_debug!("Configured SSH_AUTH_SOCK for the vault SSH agent");
+}
+
+fn vault_key_identity_agent(vault_key: &str) -> Option<String> {
+    VaultPaths::resolve_default()
+        .ok()
+        .and_then(|paths| vault_key_identity_agent_with_paths(&paths, vault_key))
+}
+
+/// Agent socket to use when `vault_key` names a vault SSH key entry.
+///
+/// Passphrase entries return `None`; they are answered by the askpass helper.
+pub(crate) fn vault_key_identity_agent_with_paths(paths: &VaultPaths, vault_key: &str) -> Option<String> {
+    match vault::is_ssh_key_entry_with_paths(paths, vault_key) {
+        Ok(true) => Some(ipc::ssh_agent_socket_path(paths).to_string_lossy().into_owned()),
+        Ok(false) => None,
+        Err(err) => {
+            log_debug!("Could not inspect vault_key entry '{}': {}", vault_key, err);
+            None
+        }
+    }
+}
+
+/// Unlock the vault for a host's `vault_key` and wire passphrase answers.
+///
+/// Key entries only need the vault unlocked so the SSH agent serves them.
+/// Passphrase entries also get a single-use askpass token for the
+/// `Enter passphrase for key` prompt.
+fn configure_vault_key_for_host(command: &mut PreparedCommand, host: &InventoryHost, authorize_unlock: bool) -> io::Result<()> {
+    let Some(vault_key) = host.ssh.vault_key.as_deref() else {
+        return Ok(());
+    };
+    let client = agent::AgentClient::new().map_err(|err| io::Error::other(err.to_string()))?;
+    if authorize_unlock {
+        match authorize_vault_entry(&client, vault_key) {
+            Ok(()) => {}
+            Err(VaultAccessError::UnlockFailed(err)) => {
+                log_debug!("Password vault unlock failed while preparing vault_key '{}': {}", vault_key, err);
+                command.fallback_notice = Some("Vault key access is unavailable because vault unlock failed; continuing without it.".to_string());
+                return Ok(());
+            }
+            Err(err) => return Err(io::Error::new(io::ErrorKind::PermissionDenied, format!("vault_key '{vault_key}': {err}"))),
+        }
+    }
+
+    if vault::is_ssh_key_entry(vault_key).map_err(|err| io::Error::other(err.to_string()))? {
+        log_debug!("Host vault_key '{}' is served by the vault SSH agent", vault_key);
+        return Ok(());
+    }
+    let token = client
+        .authorize_askpass(vault_key, Some(&host.name), Some(&host.host))
+        .map_err(|err| io::Error::new(io::ErrorKind::PermissionDenied, format!("failed to authorize key passphrase: {err}")))?;
+    transport::configure_internal_passphrase_askpass_env(&mut command.env, token.expose_secret())?;
+    log_debug!("Configured askpass helper to answer key passphrase prompts from vault entry '{}'", vault_key);
+    Ok(())
+}
+
+/// Merge inventory settings for `host` into `args`.
+///
+/// `vault_forward_socket` is the local end of the launch's vault forwarding
+/// socket; no `RemoteForward` is added without one.
+pub(crate) fn synthesize_ssh_args(args: &[String], host: &InventoryHost, vault_forward_socket: Option<&Path>) -> Vec<String> {
     let inspection = inspect_ssh_args(args);
     let Some(destination_index) = inspection.destination_index else {
         return args.to_vec();
@@ -462,6 +597,25 @@ pub(crate) fn synthesize_ssh_args(args: &[String], host: &InventoryHost) -> Vec<
         }
     }
 
+    if host.ssh.vault_forward
+        && let Some(local_socket) = vault_forward_socket
+    {
+        injected.push("-R".to_string());
+        injected.push(format!(
+            "{}:{}",
+            transport::remote_vault_forward_socket(local_socket).display(),
+            local_socket.display()
+        ));
+        // Names the remote socket for hosts whose sshd accepts the variable.
+        inject_ssh_option(&mut injected, "SendEnv", transport::VAULT_FORWARD_SOCKET_ENV);
+    }
+
+    if !inspection.option_keys.contains("identityagent")
+        && let Some(identity_agent) = host.ssh.vault_key.as_deref().and_then(vault_key_identity_agent)
+    {
+        inject_ssh_option(&mut injected, "IdentityAgent", identity_agent);
+    }
+
     for (key, values) in &host.ssh.extra_options {
         let normalized_key = key.trim().to_ascii_lowercase();
         if inspection.option_keys.contains(&normalized_key)
@@ -501,8 +655,14 @@ pub(crate) fn build_ssh_command(args: &[String], explicit_pass_entry: Option<&st
         .filter(|host| matches!(&host.protocol, ConnectionProtocol::Ssh))
         .cloned();
 
-    let effective_args = resolved_host.as_ref().map_or_else(|| args.to_vec(), |host| synthesize_ssh_args(args, host));
+    let vault_forward = resolved_host.as_ref().map(open_vault_forward).transpose()?.flatten();
+    let effective_args = resolved_host.as_ref().map_or_else(
+        || args.to_vec(),
+        |host| synthesize_ssh_args(args, host, vault_forward.as_ref().map(agent::VaultForward::socket_path)),
+    );
     let mut command = build_plain_ssh_command(&effective_args);
+    attach_vault_forward(&mut command, vault_forward);
+    configure_vault_ssh_agent(&mut command);
 
     if std::env::var_os(DISABLE_VAULT_AUTOLOGIN_ENV).is_some() {
         log_debug!("Direct password auto-login disabled by environment override");
@@ -515,6 +675,10 @@ pub(crate) fn build_ssh_command(args: &[String], explicit_pass_entry: Option<&st
         return Ok(command);
     }
 
+    if let Some(host) = resolved_host.as_ref() {
+        configure_vault_key_for_host(&mut command, host, true)?;
+    }
+
     let pass_entry_source = if explicit_pass_entry.is_some() {
         "direct override"
     } else {
@@ -529,7 +693,7 @@ pub(crate) fn build_ssh_command(args: &[String], explicit_pass_entry: Option<&st
     };
     log_debug!("Resolved password vault entry for direct SSH launch via {}", pass_entry_source);
 
-    if !validate_vault_entry_name(&pass_entry_name) {
+    if !validate_secret_reference(&pass_entry_name) {
         log_debug!("Resolved password vault entry name was invalid");
         return Err(io::Error::new(
             io::ErrorKind::InvalidInput,
@@ -537,9 +701,17 @@ pub(crate) fn build_ssh_command(args: &[String], explicit_pass_entry: Option<&st
         ));
     }
 
+    let host_alias = resolved_host.as_ref().map(|host| host.name.as_str()).or(destination.as_deref());
+    let host_destination = resolved_host.as_ref().map(|host| host.host.as_str()).or(destination.as_deref());
+    if let Some(socket_path) = forwarded_vault_socket_for_entry(&pass_entry_name) {
+        configure_forwarded_askpass_for_entry(&mut command, &socket_path, &pass_entry_name)?;
+        log_debug!("Configured forwarded askpass helper for direct SSH launch");
+        return Ok(command);
+    }
+
     let client = agent::AgentClient::new().map_err(|err| io::Error::other(err.to_string()))?;
     match authorize_vault_entry(&client, &pass_entry_name) {
-        Ok(_entry_status) => {}
+        Ok(()) => {}
         Err(VaultAccessError::VaultNotInitialized) => {
             log_debug!("Password vault is not initialized during direct SSH launch");
             return Err(io::Error::new(
@@ -571,21 +743,38 @@ pub(crate) fn build_ssh_command(args: &[String], explicit_pass_entry: Option<&st
         }
     };
 
-    configure_internal_askpass_for_entry(&mut command, &pass_entry_name)?;
+    configure_internal_askpass_for_entry(&mut command, &pass_entry_name, host_alias, host_destination)?;
     // At this point SSH can request password prompts through the internal helper.
     log_debug!("Configured internal askpass helper for direct SSH launch");
     Ok(command)
 }
 
 pub(crate) fn build_ssh_command_for_host(host: &InventoryHost, explicit_pass_entry: Option<&str>) -> io::Result<PreparedCommand> {
-    let effective_args = synthesize_ssh_args(std::slice::from_ref(&host.name), host);
+    let vault_forward = open_vault_forward(host)?;
+    let effective_args = synthesize_ssh_args(
+        std::slice::from_ref(&host.name),
+        host,
+        vault_forward.as_ref().map(agent::VaultForward::socket_path),
+    );
     let mut command = build_plain_ssh_command(&effective_args);
+    attach_vault_forward(&mut command, vault_forward);
+    configure_vault_ssh_agent(&mut command);
+    if let Err(err) = configure_vault_key_for_host(&mut command, host, false) {
+        // The TUI unlocks the vault for `vault_pass` only; a locked vault just skips the key.
+        log_debug!("Skipping vault_key for TUI SSH host launch: {}", err);
+    }
 
     let Some(pass_entry_name) = explicit_pass_entry.map(|name| name.to_string()).or_else(|| host.vault_pass.clone()) else {
         return Ok(command);
     };
 
-    configure_internal_askpass_for_entry(&mut command, &pass_entry_name)?;
+    if let Some(socket_path) = forwarded_vault_socket_for_entry(&pass_entry_name) {
+        configure_forwarded_askpass_for_entry(&mut command, &socket_path, &pass_entry_name)?;
+        log_debug!("Configured forwarded askpass helper for TUI SSH host launch");
+        return Ok(command);
+    }
+
+    configure_internal_askpass_for_entry(&mut command, &pass_entry_name, Some(&host.name), Some(&host.host))?;
     log_debug!("Configured internal askpass helper for TUI SSH host launch");
     Ok(command)
 }
@@ -4,12 +4,16 @@ use crate::args::validate_vault_entry_name;
 use crate::auth::{
     self, agent,
     ipc::UnlockPolicy,
+    provider::SecretReference,
     secret::{ExposeSecret, SensitiveString},
+    transport,
 };
 use crate::config;
+use crate::inventory::InventoryHost;
 use crate::log_debug;
 use std::fmt;
 use std::io::{self, IsTerminal};
+use std::path::PathBuf;
 
 #[derive(Debug)]
 pub(super) enum VaultAccessError {
@@ -36,9 +40,20 @@ impl fmt::Display for VaultAccessError {
     }
 }
 
+/// Forwarded vault socket to answer `pass_entry_name` from, when this machine
+/// has no vault of its own and was reached from a `vault_forward` host.
+pub(crate) fn forwarded_vault_socket_for_entry(pass_entry_name: &str) -> Option<PathBuf> {
+    if !validate_vault_entry_name(pass_entry_name) || auth::vault::vault_exists().unwrap_or(true) {
+        return None;
+    }
+    let socket_path = transport::forwarded_vault_socket_path()?;
+    log_debug!("Using forwarded password vault socket '{}'", socket_path.display());
+    Some(socket_path)
+}
+
 fn current_unlock_policy() -> UnlockPolicy {
     let auth_settings = config::auth_settings();
-    UnlockPolicy::new(auth_settings.idle_timeout_seconds, auth_settings.session_timeout_seconds)
+    UnlockPolicy::from_auth_settings(&auth_settings)
 }
 
 fn unlock_agent_interactively(client: &agent::AgentClient, policy: UnlockPolicy) -> io::Result<agent::AgentVaultStatus> {
@@ -119,17 +134,28 @@ fn query_vault_entry_status_with_policy(
     Ok(entry_status)
 }
 
-pub(super) fn authorize_vault_entry(client: &agent::AgentClient, pass_entry_name: &str) -> std::result::Result<agent::AgentEntryStatus, VaultAccessError> {
+pub(super) fn authorize_vault_entry(client: &agent::AgentClient, pass_entry_name: &str) -> std::result::Result<(), VaultAccessError> {
+    if SecretReference::parse(pass_entry_name).is_some_and(|reference| reference.is_external()) {
+        // External providers are resolved by the agent without a vault unlock.
+        log_debug!("Password entry uses an external secret provider; skipping vault unlock");
+        return Ok(());
+    }
+
     let policy = current_unlock_policy();
-    query_vault_entry_status_with_policy(client, pass_entry_name, &policy)
+    query_vault_entry_status_with_policy(client, pass_entry_name, &policy).map(|_| ())
 }
 
-pub(super) fn resolve_vault_password_with_policy(pass_entry_name: &str, policy: UnlockPolicy) -> io::Result<SensitiveString> {
-    if !validate_vault_entry_name(pass_entry_name) {
+pub(super) fn resolve_vault_password_with_policy(pass_entry_name: &str, policy: UnlockPolicy, host: &InventoryHost) -> io::Result<SensitiveString> {
+    let Some(reference) = SecretReference::parse(pass_entry_name) else {
         return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid password vault entry name for RDP launch"));
-    }
+    };
 
     let client = agent::AgentClient::new().map_err(|err| io::Error::other(err.to_string()))?;
+    if reference.is_external() {
+        log_debug!("Resolving RDP password through an external secret provider");
+        return resolve_password_with_askpass_token(&client, pass_entry_name, host);
+    }
+
     let entry_status = match query_vault_entry_status_with_policy(&client, pass_entry_name, &policy) {
         Ok(entry_status) => entry_status,
         Err(VaultAccessError::VaultNotInitialized) => {
@@ -150,14 +176,6 @@ pub(super) fn resolve_vault_password_with_policy(pass_entry_name: &str, policy:
         Err(err) => return Err(io::Error::new(io::ErrorKind::PermissionDenied, err.to_string())),
     };
 
-    resolve_vault_password_with_status(&client, pass_entry_name, entry_status)
-}
-
-fn resolve_vault_password_with_status(
-    client: &agent::AgentClient,
-    pass_entry_name: &str,
-    entry_status: agent::AgentEntryStatus,
-) -> io::Result<SensitiveString> {
     if !entry_status.exists {
         return Err(io::Error::new(
             io::ErrorKind::NotFound,
@@ -165,12 +183,46 @@ fn resolve_vault_password_with_status(
         ));
     }
 
+    resolve_password_with_askpass_token(&client, pass_entry_name, host)
+}
+
+fn resolve_password_with_askpass_token(client: &agent::AgentClient, pass_entry_name: &str, host: &InventoryHost) -> io::Result<SensitiveString> {
     // Reuse short-lived askpass token flow to avoid exposing entry names broadly.
     let askpass_token = client
-        .authorize_askpass(pass_entry_name)
+        .authorize_askpass(pass_entry_name, Some(&host.name), Some(&host.host))
         .map_err(|err| io::Error::new(io::ErrorKind::PermissionDenied, err.to_string()))?;
 
     client
         .get_secret(askpass_token.expose_secret())
         .map_err(|err| io::Error::new(io::ErrorKind::PermissionDenied, err.to_string()))
 }
+
+/// Read a vault entry for `cossh vault get`/`exec` from an already unlocked agent.
+///
+/// Never prompts for the master password, so scripts fail instead of hanging.
+/// Entries that need confirmation are confirmed by the agent itself.
+pub(crate) fn read_vault_entry_from_unlocked_agent(pass_entry_name: &str, host: Option<&InventoryHost>) -> io::Result<SensitiveString> {
+    let client = agent::AgentClient::new().map_err(|err| io::Error::other(err.to_string()))?;
+    let status = client.status().map_err(|err| io::Error::other(err.to_string()))?;
+    if !status.vault_exists {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "password vault is not initialized; run `cossh vault init` first",
+        ));
+    }
+    if !status.unlocked {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "password vault is locked; run `cossh vault unlock` first",
+        ));
+    }
+
+    let host_alias = host.map(|host| host.name.as_str());
+    let destination = host.map(|host| host.host.as_str());
+    let askpass_token = client
+        .authorize_askpass(pass_entry_name, host_alias, destination)
+        .map_err(|err| io::Error::new(io::ErrorKind::PermissionDenied, err.to_string()))?;
+    client
+        .get_secret(askpass_token.expose_secret())
+        .map_err(|err| io::Error::new(io::ErrorKind::PermissionDenied, err.to_string()))
+}
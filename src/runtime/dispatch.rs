@@ -2,8 +2,8 @@
 
 use super::logging::{APP_VERSION, apply_debug_logging, apply_ssh_logging, flush_debug_logs, resolve_logging_settings, update_session_name_for_logging};
 use super::startup::{initialize_config_or_exit, load_runtime_config_settings, print_title_banner};
-use crate::{Result, args, auth, config, inventory, log, log_debug, log_debug_raw, log_error, log_info, process, tui};
-use std::io;
+use crate::{Result, args, auth, config, inventory, log, log_debug, log_debug_raw, log_error, log_info, process, ssh_config, tui};
+use std::io::{self, IsTerminal};
 use std::process::ExitCode;
 
 fn run_interactive_session(logger: &log::Logger, args: &args::MainArgs) -> Result<ExitCode> {
@@ -41,8 +41,8 @@ fn run_inventory_migration() -> Result<ExitCode> {
 
     println!("Inventory migration completed.");
     println!("Hosts written: {}", summary.hosts_written);
-    println!("Wildcard aliases skipped: {}", summary.wildcard_aliases_skipped);
-    println!("Unsupported Match blocks skipped: {}", summary.unsupported_blocks_skipped);
+    println!("Wildcard aliases folded into hosts and defaults: {}", summary.wildcard_aliases_folded);
+    println!("Match blocks skipped (exec or unsupported criteria): {}", summary.match_blocks_skipped);
     if let Some(backup_path) = summary.backup_path {
         println!("Backup created: {}", backup_path.display());
     }
@@ -50,6 +50,497 @@ fn run_inventory_migration() -> Result<ExitCode> {
     Ok(ExitCode::SUCCESS)
 }
 
+fn run_inventory_command(command: &args::InventoryCommand) -> Result<ExitCode> {
+    match command {
+        args::InventoryCommand::Import(import) => match import.format {
+            args::InventoryImportFormat::Ansible => run_ansible_import(import),
+            args::InventoryImportFormat::Putty => run_session_import(import, inventory::SessionImportFormat::Putty),
+            args::InventoryImportFormat::MobaXterm => run_session_import(import, inventory::SessionImportFormat::MobaXterm),
+            args::InventoryImportFormat::Remmina => run_session_import(import, inventory::SessionImportFormat::Remmina),
+            args::InventoryImportFormat::SecureCrt => run_session_import(import, inventory::SessionImportFormat::SecureCrt),
+            args::InventoryImportFormat::Termius => run_session_import(import, inventory::SessionImportFormat::Termius),
+        },
+        args::InventoryCommand::Export(export) => run_inventory_export(export),
+        args::InventoryCommand::Sync(sync) => run_inventory_sync(sync),
+        args::InventoryCommand::Effective(effective) => run_inventory_effective(effective),
+        args::InventoryCommand::List(list) => run_inventory_list(list),
+        args::InventoryCommand::Show(show) => run_inventory_show(show),
+        args::InventoryCommand::Add(add) => run_inventory_add(add),
+        args::InventoryCommand::Set(set) => run_inventory_set(set),
+        args::InventoryCommand::Remove(remove) => run_inventory_remove(remove),
+        args::InventoryCommand::Move(move_args) => run_inventory_move(move_args),
+        args::InventoryCommand::Mkdir(mkdir) => run_inventory_mkdir(mkdir),
+        args::InventoryCommand::Check(check) => run_inventory_check(check),
+    }
+}
+
+/// Log and convert a failed `cossh inventory <command>` step into the runtime error.
+fn inventory_command_error(command: &str) -> impl FnOnce(io::Error) -> io::Error + '_ {
+    move |err| {
+        log_error!("Inventory {} failed: {}", command, err);
+        io::Error::other(err.to_string())
+    }
+}
+
+fn run_inventory_list(list: &args::InventoryListArgs) -> Result<ExitCode> {
+    let tree = inventory::load_inventory_tree().map_err(inventory_command_error("list"))?;
+    let entries = inventory::list_inventory_hosts(&tree, list.folder.as_deref(), list.protocol.as_deref()).map_err(inventory_command_error("list"))?;
+    match list.format {
+        args::InventoryListFormat::Table => print!("{}", inventory::render_inventory_host_table(&entries)),
+        args::InventoryListFormat::Json => println!("{:#}", inventory::inventory_hosts_json(&entries)),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_inventory_show(show: &args::InventoryShowArgs) -> Result<ExitCode> {
+    let tree = inventory::load_inventory_tree().map_err(inventory_command_error("show"))?;
+    let entry = inventory::find_inventory_host(&tree, &show.name).map_err(inventory_command_error("show"))?;
+    if show.json {
+        println!("{:#}", inventory::inventory_host_json(&entry));
+    } else {
+        print!("{}", inventory::render_inventory_host_details(&entry));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_inventory_add(add: &args::InventoryAddArgs) -> Result<ExitCode> {
+    let tree = inventory::load_inventory_tree().map_err(inventory_command_error("add"))?;
+    let folder = add.folder.as_deref().unwrap_or("/");
+    let source_file = inventory::add_inventory_host(&tree, &add.name, &add.fields, folder).map_err(inventory_command_error("add"))?;
+    println!("Added {} to {}", add.name, source_file.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_inventory_set(set: &args::InventorySetArgs) -> Result<ExitCode> {
+    let tree = inventory::load_inventory_tree().map_err(inventory_command_error("set"))?;
+    let source_file = inventory::set_inventory_host(&tree, &set.name, &set.fields).map_err(inventory_command_error("set"))?;
+    println!("Updated {} in {}", set.name, source_file.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_inventory_remove(remove: &args::InventoryRemoveArgs) -> Result<ExitCode> {
+    let tree = inventory::load_inventory_tree().map_err(inventory_command_error("rm"))?;
+    // Check every name first so a typo does not leave a half-applied removal.
+    for name in &remove.names {
+        inventory::find_inventory_host(&tree, name).map_err(inventory_command_error("rm"))?;
+    }
+    for name in &remove.names {
+        let source_file = inventory::remove_inventory_host(&tree, name).map_err(inventory_command_error("rm"))?;
+        println!("Removed {} from {}", name, source_file.display());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_inventory_move(move_args: &args::InventoryMoveArgs) -> Result<ExitCode> {
+    let tree = inventory::load_inventory_tree().map_err(inventory_command_error("mv"))?;
+    let location = inventory::move_inventory_host(&tree, &move_args.name, &move_args.folder).map_err(inventory_command_error("mv"))?;
+    println!(
+        "Moved {} to /{} in {}",
+        move_args.name,
+        location.folder_path.join("/"),
+        location.source_file.display()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_inventory_mkdir(mkdir: &args::InventoryMkdirArgs) -> Result<ExitCode> {
+    let tree = inventory::load_inventory_tree().map_err(inventory_command_error("mkdir"))?;
+    let location = inventory::make_inventory_folder(&tree, &mkdir.folder).map_err(inventory_command_error("mkdir"))?;
+    println!("Created folder /{} in {}", location.folder_path.join("/"), location.source_file.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_inventory_check(check: &args::InventoryCheckArgs) -> Result<ExitCode> {
+    let inventory_path = match &check.path {
+        Some(path) => path.clone(),
+        None => inventory::get_default_inventory_path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Could not find home directory"))?,
+    };
+    let vault_entries = match auth::vault::vault_exists() {
+        Ok(true) => Some(auth::vault::list_entries().map_err(|err| {
+            log_error!("Inventory check failed to list vault entries: {}", err);
+            io::Error::other(err.to_string())
+        })?),
+        _ => None,
+    };
+    let context = inventory::InventoryCheckContext {
+        profiles: config::available_profiles(),
+        vault_entries,
+    };
+
+    let report = inventory::check_inventory(&inventory_path, &context);
+    for diagnostic in &report.diagnostics {
+        println!("{diagnostic}");
+    }
+    let errors = report.count(inventory::CheckSeverity::Error);
+    let warnings = report.count(inventory::CheckSeverity::Warning);
+    println!("Checked {} host(s): {} error(s), {} warning(s)", report.hosts_checked, errors, warnings);
+
+    if errors > 0 || (check.strict && warnings > 0) {
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_ansible_import(import: &args::InventoryImportArgs) -> Result<ExitCode> {
+    let summary = inventory::import_ansible_inventory(&import.path, import.output.as_deref()).map_err(|err| {
+        log_error!("Ansible inventory import failed: {}", err);
+        std::io::Error::other(err.to_string())
+    })?;
+
+    println!("Ansible inventory import completed.");
+    println!("Hosts written: {}", summary.hosts_written);
+    if !summary.skipped_variables.is_empty() {
+        println!("Skipped variables:");
+        for (variable, hosts) in &summary.skipped_variables {
+            println!("  {variable} ({hosts} host{})", if *hosts == 1 { "" } else { "s" });
+        }
+    }
+    if let Some(backup_path) = summary.backup_path {
+        println!("Backup created: {}", backup_path.display());
+    }
+    println!("Output path: {}", summary.output_path.display());
+    if let Some(main_inventory) = summary.included_from {
+        println!("Included from: {}", main_inventory.display());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_session_import(import: &args::InventoryImportArgs, format: inventory::SessionImportFormat) -> Result<ExitCode> {
+    let options = inventory::SessionImportOptions {
+        format,
+        path: import.path.clone(),
+        inventory_path: import.output.clone(),
+        folder: import.folder.as_deref().map(|folder| {
+            folder
+                .split('/')
+                .map(str::trim)
+                .filter(|segment| !segment.is_empty())
+                .map(str::to_string)
+                .collect()
+        }),
+        on_conflict: match import.on_conflict {
+            args::InventoryImportConflict::Skip => inventory::ImportConflict::Skip,
+            args::InventoryImportConflict::Rename => inventory::ImportConflict::Rename,
+            args::InventoryImportConflict::Replace => inventory::ImportConflict::Replace,
+        },
+        dry_run: import.dry_run,
+    };
+    let summary = inventory::import_sessions(&options).map_err(|err| {
+        log_error!("{} session import failed: {}", format.client_name(), err);
+        std::io::Error::other(err.to_string())
+    })?;
+    print_session_import_summary(format, &summary);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_session_import_summary(format: inventory::SessionImportFormat, summary: &inventory::SessionImportSummary) {
+    if summary.dry_run {
+        println!("{} import plan (dry run, nothing written):", format.client_name());
+    } else {
+        println!("{} session import completed.", format.client_name());
+    }
+
+    let (mut added, mut replaced, mut skipped) = (0, 0, 0);
+    for host in &summary.hosts {
+        let target = format!("{} ({} {})", host.name, host.protocol, host.host);
+        let folder = if host.folder_path.is_empty() {
+            "/".to_string()
+        } else {
+            host.folder_path.join("/")
+        };
+        match &host.action {
+            inventory::ImportedHostAction::Added => {
+                added += 1;
+                println!("  + {target} -> {folder}");
+            }
+            inventory::ImportedHostAction::Renamed(original) => {
+                added += 1;
+                println!("  + {target} -> {folder}, renamed from '{original}'");
+            }
+            inventory::ImportedHostAction::Replaced(source_file) => {
+                replaced += 1;
+                println!("  ~ {target} replaces the host in {}", source_file.display());
+            }
+            inventory::ImportedHostAction::Skipped => {
+                skipped += 1;
+                println!("  = {target} skipped, the name already exists");
+            }
+        }
+    }
+    if !summary.unsupported.is_empty() {
+        println!("Unsupported sessions:");
+        for (name, reason) in &summary.unsupported {
+            println!("  {name}: {reason}");
+        }
+    }
+    println!(
+        "Added: {added}, replaced: {replaced}, skipped: {skipped}, unsupported: {}",
+        summary.unsupported.len()
+    );
+    println!("Inventory path: {}", summary.inventory_path.display());
+}
+
+fn run_inventory_export(export: &args::InventoryExportArgs) -> Result<ExitCode> {
+    let tree = inventory::load_inventory_tree().map_err(|err| {
+        log_error!("Inventory export failed: {}", err);
+        std::io::Error::other(err.to_string())
+    })?;
+    let format = match export.format {
+        args::InventoryExportFormat::SshConfig => inventory::InventoryExportFormat::SshConfig,
+        args::InventoryExportFormat::Ansible => inventory::InventoryExportFormat::Ansible,
+        args::InventoryExportFormat::Csv => inventory::InventoryExportFormat::Csv,
+        args::InventoryExportFormat::Json => inventory::InventoryExportFormat::Json,
+    };
+    // `~/.ssh/cossh.conf` includes files from `~/.ssh/cossh.conf.d/`.
+    let include_dir = match &export.output {
+        Some(output) if export.split_includes => {
+            let mut include_dir = std::path::absolute(output)?.into_os_string();
+            include_dir.push(".d");
+            Some(std::path::PathBuf::from(include_dir))
+        }
+        _ => None,
+    };
+    let rendered = inventory::render_inventory_export(&tree, format, include_dir.as_deref());
+
+    for (name, reason) in &rendered.skipped {
+        eprintln!("Skipped {name}: {reason}");
+    }
+    let Some(output) = &export.output else {
+        print!("{}", rendered.document);
+        return Ok(ExitCode::SUCCESS);
+    };
+
+    let backup_paths = inventory::write_inventory_export(&rendered, output).map_err(|err| {
+        log_error!("Inventory export failed: {}", err);
+        std::io::Error::other(err.to_string())
+    })?;
+    println!("Inventory export completed.");
+    println!("Hosts written: {}", rendered.hosts_exported);
+    for (path, _) in &rendered.include_files {
+        println!("Include file: {}", path.display());
+    }
+    for backup_path in backup_paths {
+        println!("Backup created: {}", backup_path.display());
+    }
+    println!("Output path: {}", output.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_inventory_effective(effective: &args::InventoryEffectiveArgs) -> Result<ExitCode> {
+    let ssh_config_path = match &effective.ssh_config {
+        Some(path) => path.clone(),
+        None => ssh_config::get_default_ssh_config_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Could not find home directory for ~/.ssh/config"))?,
+    };
+    // Like `ssh -F`, an explicit config is read without the system-wide one.
+    let rules = match effective.ssh_config {
+        Some(_) => ssh_config::SshConfigRules::load(&ssh_config_path),
+        None => ssh_config::SshConfigRules::load_with_system_config(&ssh_config_path, &ssh_config::get_system_ssh_config_path()),
+    }
+    .map_err(|err| {
+        log_error!("Failed to read SSH config {}: {}", ssh_config_path.display(), err);
+        io::Error::other(err.to_string())
+    })?;
+
+    // Inventory hosts are launched as `ssh <options> <host>`, so their settings act as command-line options.
+    let inventory_hosts = inventory::load_inventory_tree().map(|tree| tree.hosts).unwrap_or_default();
+    let inventory_host = inventory_hosts
+        .iter()
+        .find(|host| host.name == effective.target && host.protocol == inventory::ConnectionProtocol::Ssh);
+    let context = match inventory_host {
+        Some(host) => ssh_config::SshMatchContext {
+            command_line_options: inventory_host_ssh_options(host),
+            ..ssh_config::SshMatchContext::new(host.host.clone())
+        },
+        None => match effective.target.rsplit_once('@') {
+            Some((user, host)) => ssh_config::SshMatchContext {
+                command_line_options: vec![("user".to_string(), user.to_string())],
+                ..ssh_config::SshMatchContext::new(host)
+            },
+            None => ssh_config::SshMatchContext::new(effective.target.clone()),
+        },
+    };
+    let context = ssh_config::SshMatchContext {
+        run_exec: !effective.no_exec,
+        ..context
+    };
+
+    let resolved = rules.resolve(&context);
+    println!("hostname {}", resolved.hostname());
+    println!("user {}", resolved.user());
+    println!("port {}", resolved.port());
+    for option in resolved
+        .options
+        .iter()
+        .filter(|option| !matches!(option.keyword.as_str(), "hostname" | "user" | "port"))
+    {
+        println!("{} {}", option.keyword, option.value);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// SSH settings of an inventory host as the options `cossh` passes on the command line.
+fn inventory_host_ssh_options(host: &inventory::InventoryHost) -> Vec<(String, String)> {
+    let mut options = Vec::new();
+    let mut push = |keyword: &str, value: &str| options.push((keyword.to_string(), value.to_string()));
+
+    if let Some(user) = host.user.as_deref() {
+        push("user", user);
+    }
+    if let Some(port) = host.port {
+        push("port", &port.to_string());
+    }
+    for identity_file in &host.ssh.identity_files {
+        push("identityfile", identity_file);
+    }
+    if let Some(identities_only) = host.ssh.identities_only {
+        push("identitiesonly", if identities_only { "yes" } else { "no" });
+    }
+    if let Some(proxy_jump) = host.ssh.proxy_jump.as_deref() {
+        push("proxyjump", proxy_jump);
+    }
+    if let Some(proxy_command) = host.ssh.proxy_command.as_deref() {
+        push("proxycommand", proxy_command);
+    }
+    if let Some(forward_agent) = host.ssh.forward_agent.as_deref() {
+        push("forwardagent", forward_agent);
+    }
+    for forward in &host.ssh.local_forward {
+        push(
+            "localforward",
+            &inventory::ssh_forward_arguments(&inventory::normalize_ssh_forward_spec(forward)),
+        );
+    }
+    for forward in &host.ssh.remote_forward {
+        push(
+            "remoteforward",
+            &inventory::ssh_forward_arguments(&inventory::normalize_ssh_forward_spec(forward)),
+        );
+    }
+    for (key, values) in &host.ssh.extra_options {
+        for value in values {
+            push(&key.to_ascii_lowercase(), value);
+        }
+    }
+    options
+}
+
+fn run_inventory_sync(sync: &args::InventorySyncArgs) -> Result<ExitCode> {
+    let ssh_config_path = match &sync.ssh_config {
+        Some(path) => path.clone(),
+        None => ssh_config::get_default_ssh_config_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Could not find home directory for ~/.ssh/config"))?,
+    };
+    let inventory_path =
+        inventory::get_default_inventory_path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Could not find home directory for ~/.color-ssh"))?;
+    let mut plan = inventory::plan_inventory_sync(&ssh_config_path, &inventory_path).map_err(|err| {
+        log_error!("Inventory sync failed: {}", err);
+        io::Error::other(err.to_string())
+    })?;
+
+    print_inventory_sync_plan(&plan);
+    if sync.dry_run || (plan.hosts.is_empty() && plan.has_snapshot) {
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    match sync.prefer {
+        Some(prefer) => {
+            let side = match prefer {
+                args::InventorySyncPreference::SshConfig => inventory::SyncSide::SshConfig,
+                args::InventorySyncPreference::Inventory => inventory::SyncSide::Inventory,
+            };
+            plan.resolve_conflicts(|_, _| Ok::<_, io::Error>(Some(side)))?;
+        }
+        None if io::stdin().is_terminal() => plan.resolve_conflicts(prompt_sync_conflict)?,
+        None => {}
+    }
+
+    let summary = inventory::apply_inventory_sync(&plan).map_err(|err| {
+        log_error!("Inventory sync failed: {}", err);
+        io::Error::other(err.to_string())
+    })?;
+    println!("Inventory sync completed.");
+    println!(
+        "Added to inventory: {}, added to SSH config: {}, removed from inventory: {}, removed from SSH config: {}, updated: {}",
+        summary.added_to_inventory, summary.added_to_ssh_config, summary.removed_from_inventory, summary.removed_from_ssh_config, summary.updated_hosts
+    );
+    if summary.unresolved_conflicts > 0 {
+        println!(
+            "Conflicts left unchanged: {} (rerun with --prefer to settle them)",
+            summary.unresolved_conflicts
+        );
+    }
+    for backup_path in &summary.backups {
+        println!("Backup created: {}", backup_path.display());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_inventory_sync_plan(plan: &inventory::InventorySyncPlan) {
+    println!("Syncing {} with {}", plan.ssh_config_path.display(), plan.inventory_path.display());
+    if !plan.has_snapshot {
+        println!("No earlier sync found; every field that differs is a conflict.");
+    }
+    if plan.hosts.is_empty() {
+        println!("Hosts are in sync.");
+    }
+
+    for host in &plan.hosts {
+        match &host.action {
+            inventory::HostSyncAction::AddToInventory => println!("  + {} -> inventory", host.name),
+            inventory::HostSyncAction::AddToSshConfig => println!("  + {} -> SSH config", host.name),
+            inventory::HostSyncAction::RemoveFromInventory => println!("  - {} removed from the inventory, it was deleted from the SSH config", host.name),
+            inventory::HostSyncAction::RemoveFromSshConfig => println!("  - {} removed from the SSH config, it was deleted from the inventory", host.name),
+            inventory::HostSyncAction::Update(fields) => {
+                println!("  ~ {}", host.name);
+                for field in fields {
+                    match field.winner {
+                        Some(inventory::SyncSide::SshConfig) => {
+                            println!("      {}: {} -> inventory", field.field, format_sync_values(&field.ssh_config))
+                        }
+                        Some(inventory::SyncSide::Inventory) => {
+                            println!("      {}: {} -> SSH config", field.field, format_sync_values(&field.inventory))
+                        }
+                        None => println!(
+                            "      {}: conflict, SSH config {}, inventory {}",
+                            field.field,
+                            format_sync_values(&field.ssh_config),
+                            format_sync_values(&field.inventory)
+                        ),
+                    }
+                }
+            }
+        }
+    }
+    if !plan.skipped.is_empty() {
+        println!("Skipped hosts:");
+        for (name, reason) in &plan.skipped {
+            println!("  {name}: {reason}");
+        }
+    }
+}
+
+fn format_sync_values(values: &[String]) -> String {
+    if values.is_empty() { "(unset)".to_string() } else { values.join(", ") }
+}
+
+fn prompt_sync_conflict(host: &str, field: &inventory::FieldSync) -> io::Result<Option<inventory::SyncSide>> {
+    let prompt = format!(
+        "{host} {}: keep [s]sh config ({}), [i]nventory ({}) or [l]eave both? ",
+        field.field,
+        format_sync_values(&field.ssh_config),
+        format_sync_values(&field.inventory)
+    );
+    loop {
+        match auth::prompt_visible_value(&prompt)?.trim().to_ascii_lowercase().as_str() {
+            "s" | "ssh" | "ssh-config" => return Ok(Some(inventory::SyncSide::SshConfig)),
+            "i" | "inventory" => return Ok(Some(inventory::SyncSide::Inventory)),
+            "" | "l" | "leave" => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
 fn run_completion_hosts(protocol: &args::CompletionProtocol) -> ExitCode {
     let tree = match inventory::load_inventory_tree() {
         Ok(tree) => tree,
@@ -236,6 +727,10 @@ pub(crate) fn run() -> Result<ExitCode> {
         return run_inventory_migration();
     }
 
+    if let Some(args::MainCommand::Inventory(inventory_command)) = args.command.as_ref() {
+        return run_inventory_command(inventory_command);
+    }
+
     if let Some(args::MainCommand::Vault(vault_command)) = args.command.as_ref() {
         return Ok(run_vault_mode(logger, &args, vault_command));
     }
@@ -48,7 +48,7 @@ pub(super) fn expand_include_pattern(pattern: &str) -> Vec<PathBuf> {
     matched_paths
 }
 
-fn matches_pattern(text: &str, pattern: &str) -> bool {
+pub(super) fn matches_pattern(text: &str, pattern: &str) -> bool {
     // Lightweight wildcard matcher supporting only '*' and '?'.
     let pattern_chars: Vec<char> = pattern.chars().collect();
     let text_chars: Vec<char> = text.chars().collect();
@@ -90,6 +90,11 @@ fn matches_pattern(text: &str, pattern: &str) -> bool {
         }
     }
 
+    // A trailing '*' also matches an empty remainder.
+    while pattern_chars.get(pattern_idx) == Some(&'*') {
+        pattern_idx += 1;
+    }
+
     pattern_idx == pattern_chars.len() && text_idx == text_chars.len()
 }
 
@@ -1,14 +1,20 @@
-//! SSH config parsing and include-tree loading.
+//! SSH config parsing, include-tree loading and effective option resolution.
 
 mod include;
+mod keywords;
 mod model;
 mod parser;
 mod path;
+mod resolve;
 
 pub use crate::inventory::{ConnectionProtocol, FolderId, TreeFolder};
+/// Known `ssh_config(5)` option keywords.
+pub use keywords::is_known_ssh_option;
 /// Parsed host record and tree model types from SSH config.
 pub use model::{SshHost, SshHostTreeModel};
 /// Parser entrypoints for runtime use and migration use.
-pub use parser::{parse_ssh_config, parse_ssh_config_for_migration};
-/// Default `~/.ssh/config` discovery helper.
-pub use path::get_default_ssh_config_path;
+pub use parser::{apply_host_option, parse_ssh_config, parse_ssh_config_for_migration};
+/// Default `~/.ssh/config` and system-wide config discovery helpers.
+pub use path::{get_default_ssh_config_path, get_system_ssh_config_path};
+/// `Host`/`Match` evaluation for one destination, like `ssh -G`.
+pub use resolve::{EffectiveSshConfig, EffectiveSshOption, SshConfigRules, SshMatchContext};
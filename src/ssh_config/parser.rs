@@ -3,7 +3,7 @@
 use super::include::{expand_include_pattern, resolve_include_pattern};
 use super::model::{SshHost, SshHostTreeModel};
 use super::path::expand_tilde;
-use crate::args::validate_vault_entry_name;
+use crate::args::validate_secret_reference;
 use crate::inventory::{ConnectionProtocol, FolderId, TreeFolder, sort_tree_folder_by_host_name};
 use crate::log_debug;
 use std::collections::HashSet;
@@ -15,7 +15,6 @@ use std::path::{Path, PathBuf};
 struct ParsedConfigFile {
     hosts: Vec<SshHost>,
     include_patterns: Vec<String>,
-    unsupported_blocks: usize,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -30,8 +29,6 @@ pub struct MigrationParseResult {
     pub root: TreeFolder,
     /// Flattened host list discovered across includes.
     pub hosts: Vec<SshHost>,
-    /// Number of unsupported `Match` blocks skipped.
-    pub unsupported_blocks: usize,
 }
 
 impl ParseOptions {
@@ -58,19 +55,9 @@ pub fn parse_ssh_config_for_migration(config_path: &Path) -> io::Result<Migratio
     let mut hosts = Vec::new();
     let mut visited = HashSet::new();
     let mut next_id: FolderId = 0;
-    let mut unsupported_blocks = 0usize;
     let root_name = config_path.file_name().and_then(|segment| segment.to_str()).unwrap_or("config").to_string();
 
-    let root = parse_tree_folder(
-        config_path,
-        &root_name,
-        &mut hosts,
-        &mut visited,
-        &mut next_id,
-        ParseOptions::MIGRATION,
-        &mut unsupported_blocks,
-    )?
-    .unwrap_or_else(|| TreeFolder {
+    let root = parse_tree_folder(config_path, &root_name, &mut hosts, &mut visited, &mut next_id, ParseOptions::MIGRATION)?.unwrap_or_else(|| TreeFolder {
         id: 0,
         name: root_name,
         path: config_path.to_path_buf(),
@@ -78,11 +65,7 @@ pub fn parse_ssh_config_for_migration(config_path: &Path) -> io::Result<Migratio
         host_indices: Vec::new(),
     });
 
-    Ok(MigrationParseResult {
-        root,
-        hosts,
-        unsupported_blocks,
-    })
+    Ok(MigrationParseResult { root, hosts })
 }
 
 pub(super) fn build_ssh_host_tree(config_path: &Path) -> io::Result<SshHostTreeModel> {
@@ -91,17 +74,7 @@ pub(super) fn build_ssh_host_tree(config_path: &Path) -> io::Result<SshHostTreeM
     let mut next_id: FolderId = 0;
     let root_name = config_path.file_name().and_then(|segment| segment.to_str()).unwrap_or("config").to_string();
 
-    let mut unsupported_blocks = 0usize;
-    let mut root = parse_tree_folder(
-        config_path,
-        &root_name,
-        &mut hosts,
-        &mut visited,
-        &mut next_id,
-        ParseOptions::RUNTIME,
-        &mut unsupported_blocks,
-    )?
-    .unwrap_or_else(|| TreeFolder {
+    let mut root = parse_tree_folder(config_path, &root_name, &mut hosts, &mut visited, &mut next_id, ParseOptions::RUNTIME)?.unwrap_or_else(|| TreeFolder {
         id: 0,
         name: root_name,
         path: config_path.to_path_buf(),
@@ -120,7 +93,6 @@ fn parse_tree_folder(
     visited: &mut HashSet<PathBuf>,
     next_id: &mut FolderId,
     options: ParseOptions,
-    unsupported_blocks: &mut usize,
 ) -> io::Result<Option<TreeFolder>> {
     let canonical = config_path.canonicalize().unwrap_or_else(|_| config_path.to_path_buf());
 
@@ -131,7 +103,6 @@ fn parse_tree_folder(
     }
 
     let parsed = parse_config_file(&canonical, options)?;
-    *unsupported_blocks += parsed.unsupported_blocks;
     let folder_id = *next_id;
     *next_id += 1;
 
@@ -149,7 +120,7 @@ fn parse_tree_folder(
         for include_path in expand_include_pattern(&resolved_pattern) {
             let child_name = include_path.file_name().and_then(|segment| segment.to_str()).unwrap_or("include").to_string();
 
-            if let Some(child) = parse_tree_folder(&include_path, &child_name, hosts, visited, next_id, options, unsupported_blocks)? {
+            if let Some(child) = parse_tree_folder(&include_path, &child_name, hosts, visited, next_id, options)? {
                 children.push(child);
             }
         }
@@ -194,6 +165,27 @@ fn push_other_option(host: &mut SshHost, key: &str, value: &str) {
     host.other_options.entry(key.to_string()).or_default().push(value.to_string());
 }
 
+/// Apply one `Host` block option, given by its lowercase keyword, to `host`.
+pub fn apply_host_option(host: &mut SshHost, keyword: &str, value: &str) {
+    match keyword {
+        "hostname" => host.hostname = Some(value.to_string()),
+        "user" => host.user = Some(value.to_string()),
+        "port" => {
+            if let Ok(port) = value.parse::<u16>() {
+                host.port = Some(port);
+            }
+        }
+        "identityfile" => host.identity_files.push(expand_tilde(value)),
+        "identitiesonly" => host.identities_only = parse_bool_like(value),
+        "proxyjump" => host.proxy_jump = Some(value.to_string()),
+        "proxycommand" => host.proxy_command = Some(value.to_string()),
+        "forwardagent" => host.forward_agent = Some(normalize_yes_no_string(value)),
+        "localforward" => host.local_forward.push(value.to_string()),
+        "remoteforward" => host.remote_forward.push(value.to_string()),
+        _ => push_other_option(host, keyword, value),
+    }
+}
+
 fn parse_config_file(config_path: &Path, options: ParseOptions) -> io::Result<ParsedConfigFile> {
     let file = File::open(config_path)?;
     let reader = BufReader::new(file);
@@ -231,7 +223,7 @@ fn parse_config_file(config_path: &Path, options: ParseOptions) -> io::Result<Pa
             }
             if let Some(pass_val) = trimmed.strip_prefix("#_pass") {
                 let pass_key = pass_val.trim();
-                if validate_vault_entry_name(pass_key) {
+                if validate_secret_reference(pass_key) {
                     for host in &mut current_hosts {
                         host.pass_key = Some(pass_key.to_string());
                     }
@@ -288,73 +280,19 @@ fn parse_config_file(config_path: &Path, options: ParseOptions) -> io::Result<Pa
                     current_hosts.push(SshHost::new(value.to_string()));
                 }
             }
-            "hostname" => {
-                for host in &mut current_hosts {
-                    host.hostname = Some(value.to_string());
-                }
-            }
-            "user" => {
-                for host in &mut current_hosts {
-                    host.user = Some(value.to_string());
-                }
-            }
-            "port" => {
-                if let Ok(port) = value.parse::<u16>() {
-                    for host in &mut current_hosts {
-                        host.port = Some(port);
-                    }
-                }
-            }
-            "identityfile" => {
-                let identity = expand_tilde(value);
-                for host in &mut current_hosts {
-                    host.identity_files.push(identity.clone());
-                }
-            }
-            "identitiesonly" => {
-                let parsed_bool = parse_bool_like(value);
-                for host in &mut current_hosts {
-                    host.identities_only = parsed_bool;
-                }
-            }
-            "proxyjump" => {
-                for host in &mut current_hosts {
-                    host.proxy_jump = Some(value.to_string());
-                }
-            }
-            "proxycommand" => {
-                for host in &mut current_hosts {
-                    host.proxy_command = Some(value.to_string());
-                }
-            }
-            "forwardagent" => {
-                for host in &mut current_hosts {
-                    host.forward_agent = Some(normalize_yes_no_string(value));
-                }
-            }
-            "localforward" => {
-                for host in &mut current_hosts {
-                    host.local_forward.push(value.to_string());
-                }
-            }
-            "remoteforward" => {
-                for host in &mut current_hosts {
-                    host.remote_forward.push(value.to_string());
-                }
-            }
             "include" => {
                 for token in value.split_whitespace() {
                     parsed.include_patterns.push(token.to_string());
                 }
             }
             "match" => {
+                // `Match` options are not tied to one alias; `SshConfigRules` resolves them per destination.
                 finalize_current_hosts(&mut parsed, &mut current_hosts, options);
-                parsed.unsupported_blocks += 1;
                 in_match_block = true;
             }
             _ => {
                 for host in &mut current_hosts {
-                    push_other_option(host, &keyword, value);
+                    apply_host_option(host, &keyword, value);
                 }
             }
         }
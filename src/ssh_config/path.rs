@@ -17,3 +17,16 @@ pub(super) fn expand_tilde(path: &str) -> String {
 pub fn get_default_ssh_config_path() -> Option<PathBuf> {
     dirs::home_dir().map(|home| home.join(".ssh").join("config"))
 }
+
+/// Returns the system-wide config `ssh` reads after the user's own.
+pub fn get_system_ssh_config_path() -> PathBuf {
+    #[cfg(windows)]
+    {
+        let program_data = std::env::var_os("PROGRAMDATA").unwrap_or_else(|| r"C:\ProgramData".into());
+        PathBuf::from(program_data).join("ssh").join("ssh_config")
+    }
+    #[cfg(not(windows))]
+    {
+        PathBuf::from("/etc/ssh/ssh_config")
+    }
+}
@@ -0,0 +1,523 @@
+//! Effective option resolution for one destination, following the order in
+//! which OpenSSH evaluates `Host` and `Match` sections (as `ssh -G` prints it).
+
+use super::include::{expand_include_pattern, matches_pattern, resolve_include_pattern};
+use crate::log_debug;
+use crate::platform::resolve_known_command_path;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Keywords whose values accumulate across sections instead of keeping the first one.
+const MULTI_VALUE_KEYWORDS: [&str; 6] = ["identityfile", "certificatefile", "localforward", "remoteforward", "dynamicforward", "sendenv"];
+
+/// Include nesting limit, matching OpenSSH.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+#[derive(Debug, Clone)]
+enum ConfigStatement {
+    Host(Vec<String>),
+    Match(Vec<MatchCriterion>),
+    Option { keyword: String, value: String },
+    Include(Vec<ConfigStatement>),
+}
+
+#[derive(Debug, Clone)]
+struct MatchCriterion {
+    negated: bool,
+    condition: MatchCondition,
+}
+
+#[derive(Debug, Clone)]
+enum MatchCondition {
+    All,
+    /// `canonical` and `final`; both match only on the final pass.
+    Final,
+    Exec(String),
+    Host(String),
+    OriginalHost(String),
+    User(String),
+    LocalUser(String),
+    /// Criteria such as `localnetwork` or `tagged`, which never match here.
+    Unsupported,
+}
+
+/// Destination and command-line settings an SSH config is evaluated for.
+#[derive(Debug, Clone)]
+pub struct SshMatchContext {
+    /// Host as given on the command line (`%n`, `Host` patterns, `Match originalhost`).
+    pub original_host: String,
+    /// Options given on the command line, as lowercase keywords; they win over the config.
+    pub command_line_options: Vec<(String, String)>,
+    /// Local user name for `Match localuser` and `%u`.
+    pub local_user: String,
+    /// Whether `Match exec` commands are run; otherwise those sections never match.
+    pub run_exec: bool,
+}
+
+impl SshMatchContext {
+    /// Context for connecting to `original_host` as the current local user.
+    pub fn new(original_host: impl Into<String>) -> Self {
+        Self {
+            original_host: original_host.into(),
+            command_line_options: Vec::new(),
+            local_user: local_user_name(),
+            run_exec: true,
+        }
+    }
+}
+
+/// One resolved option value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveSshOption {
+    /// Lowercase option keyword.
+    pub keyword: String,
+    /// Option value, with `HostName` tokens expanded.
+    pub value: String,
+    /// Whether the value came from a `Match` block or a `Host` line that does
+    /// not name the destination itself (wildcards, negations, top-level options).
+    pub shared: bool,
+}
+
+/// Options in effect for one destination, in the order they were set.
+#[derive(Debug, Clone)]
+pub struct EffectiveSshConfig {
+    /// Resolved options; single-valued keywords appear once.
+    pub options: Vec<EffectiveSshOption>,
+    original_host: String,
+    local_user: String,
+}
+
+impl EffectiveSshConfig {
+    /// First value of `keyword` (lowercase).
+    pub fn value(&self, keyword: &str) -> Option<&str> {
+        self.options.iter().find(|option| option.keyword == keyword).map(|option| option.value.as_str())
+    }
+
+    /// Host name to connect to, defaulting to the original host.
+    pub fn hostname(&self) -> &str {
+        self.value("hostname").unwrap_or(&self.original_host)
+    }
+
+    /// Remote user, defaulting to the local user.
+    pub fn user(&self) -> &str {
+        self.value("user").unwrap_or(&self.local_user)
+    }
+
+    /// Remote port, defaulting to 22.
+    pub fn port(&self) -> &str {
+        self.value("port").unwrap_or("22")
+    }
+}
+
+/// SSH config statements, with includes expanded in place, ready for resolution.
+#[derive(Debug, Clone, Default)]
+pub struct SshConfigRules {
+    /// Statements of each config file in the order OpenSSH reads them.
+    configs: Vec<Vec<ConfigStatement>>,
+    has_final_match: bool,
+}
+
+impl SshConfigRules {
+    /// Load `config_path` and its includes; a missing file yields no rules.
+    pub fn load(config_path: &Path) -> io::Result<Self> {
+        Self::load_files(&[config_path])
+    }
+
+    /// Load the user config and then the system-wide config, as `ssh` does
+    /// without `-F`; values from the user config win.
+    pub fn load_with_system_config(config_path: &Path, system_config_path: &Path) -> io::Result<Self> {
+        Self::load_files(&[config_path, system_config_path])
+    }
+
+    fn load_files(config_paths: &[&Path]) -> io::Result<Self> {
+        let mut configs = Vec::new();
+        for config_path in config_paths.iter().filter(|config_path| config_path.exists()) {
+            let mut ancestors = Vec::new();
+            configs.push(load_statements(config_path, &mut ancestors)?);
+        }
+        let has_final_match = configs.iter().any(|statements| statements_have_final_match(statements));
+        Ok(Self { configs, has_final_match })
+    }
+
+    /// Resolve the options OpenSSH would use for `context`.
+    pub fn resolve(&self, context: &SshMatchContext) -> EffectiveSshConfig {
+        let mut state = ResolveState {
+            context,
+            options: Vec::new(),
+            final_pass: false,
+        };
+        for (keyword, value) in &context.command_line_options {
+            state.apply(&keyword.to_ascii_lowercase(), value, false);
+        }
+
+        // Each file starts outside any `Host` or `Match` section.
+        for statements in &self.configs {
+            state.walk(statements, true, true);
+        }
+        if self.has_final_match {
+            // OpenSSH reads the config once more when a `Match final` (or `canonical`)
+            // section exists; values from the first pass still win.
+            state.final_pass = true;
+            for statements in &self.configs {
+                state.walk(statements, true, true);
+            }
+        }
+
+        EffectiveSshConfig {
+            options: state.options,
+            original_host: context.original_host.clone(),
+            local_user: context.local_user.clone(),
+        }
+    }
+
+    /// Number of `Match` sections that can never match here: those with
+    /// unsupported criteria, and `exec` ones when commands are not run.
+    pub fn unsupported_match_blocks(&self, run_exec: bool) -> usize {
+        self.configs.iter().map(|statements| count_unsupported_match_blocks(statements, run_exec)).sum()
+    }
+}
+
+struct ResolveState<'a> {
+    context: &'a SshMatchContext,
+    options: Vec<EffectiveSshOption>,
+    final_pass: bool,
+}
+
+impl ResolveState<'_> {
+    fn walk(&mut self, statements: &[ConfigStatement], mut active: bool, mut shared: bool) {
+        for statement in statements {
+            match statement {
+                ConfigStatement::Host(patterns) => {
+                    let original_host = self.context.original_host.to_ascii_lowercase();
+                    active = matches_pattern_list(&original_host, patterns.iter().map(String::as_str), true);
+                    shared = !patterns.iter().any(|pattern| pattern.eq_ignore_ascii_case(&self.context.original_host));
+                }
+                ConfigStatement::Match(criteria) => {
+                    active = self.matches(criteria);
+                    shared = true;
+                }
+                ConfigStatement::Option { keyword, value } => {
+                    if active {
+                        self.apply(keyword, value, shared);
+                    }
+                }
+                ConfigStatement::Include(included) => {
+                    // Sections inside an included file only apply while the including one does.
+                    if active {
+                        self.walk(included, active, shared);
+                    }
+                }
+            }
+        }
+    }
+
+    fn apply(&mut self, keyword: &str, value: &str, shared: bool) {
+        if !MULTI_VALUE_KEYWORDS.contains(&keyword) && self.options.iter().any(|option| option.keyword == keyword) {
+            return;
+        }
+
+        let value = if keyword == "hostname" {
+            expand_tokens(value, |token| match token {
+                'h' => Some(self.context.original_host.clone()),
+                _ => None,
+            })
+        } else {
+            value.to_string()
+        };
+        // Repeated values (e.g. from the final pass) are ignored, as OpenSSH does for identities.
+        if self.options.iter().any(|option| option.keyword == keyword && option.value == value) {
+            return;
+        }
+        self.options.push(EffectiveSshOption {
+            keyword: keyword.to_string(),
+            value,
+            shared,
+        });
+    }
+
+    fn value(&self, keyword: &str) -> Option<&str> {
+        self.options.iter().find(|option| option.keyword == keyword).map(|option| option.value.as_str())
+    }
+
+    fn matches(&self, criteria: &[MatchCriterion]) -> bool {
+        if criteria.is_empty() {
+            return false;
+        }
+
+        // Criteria are checked in order, so `exec` only runs once the earlier ones matched.
+        criteria.iter().all(|criterion| {
+            let matched = match &criterion.condition {
+                MatchCondition::All => true,
+                MatchCondition::Final => self.final_pass,
+                MatchCondition::Host(patterns) => {
+                    let hostname = self.value("hostname").unwrap_or(&self.context.original_host).to_ascii_lowercase();
+                    matches_pattern_list(&hostname, patterns.split(','), true)
+                }
+                MatchCondition::OriginalHost(patterns) => matches_pattern_list(&self.context.original_host.to_ascii_lowercase(), patterns.split(','), true),
+                MatchCondition::User(patterns) => {
+                    let user = self.value("user").unwrap_or(&self.context.local_user);
+                    matches_pattern_list(user, patterns.split(','), false)
+                }
+                MatchCondition::LocalUser(patterns) => matches_pattern_list(&self.context.local_user, patterns.split(','), false),
+                MatchCondition::Exec(command) => {
+                    if !self.context.run_exec {
+                        return false;
+                    }
+                    self.run_exec(command)
+                }
+                MatchCondition::Unsupported => return false,
+            };
+            matched != criterion.negated
+        })
+    }
+
+    fn run_exec(&self, command: &str) -> bool {
+        let command = expand_tokens(command, |token| match token {
+            'h' => Some(self.value("hostname").unwrap_or(&self.context.original_host).to_string()),
+            'n' => Some(self.context.original_host.clone()),
+            'p' => Some(self.value("port").unwrap_or("22").to_string()),
+            'r' => Some(self.value("user").unwrap_or(&self.context.local_user).to_string()),
+            'u' => Some(self.context.local_user.clone()),
+            'd' => dirs::home_dir().map(|home| home.to_string_lossy().to_string()),
+            _ => None,
+        });
+
+        // Like OpenSSH, run the command through the user's shell, falling back to `sh`.
+        let shell = match std::env::var_os("SHELL").filter(|shell| !shell.is_empty()) {
+            Some(shell) => PathBuf::from(shell),
+            None => match resolve_known_command_path("sh") {
+                Ok(shell) => shell,
+                Err(err) => {
+                    log_debug!("Cannot run Match exec command: {}", err);
+                    return false;
+                }
+            },
+        };
+        let status = Command::new(shell)
+            .arg("-c")
+            .arg(&command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        match status {
+            Ok(status) => status.success(),
+            Err(err) => {
+                log_debug!("Match exec command failed to start: {}", err);
+                false
+            }
+        }
+    }
+}
+
+/// Match `text` against patterns that may be negated with `!`; a negated hit always fails.
+fn matches_pattern_list<'a>(text: &str, patterns: impl IntoIterator<Item = &'a str>, ignore_case: bool) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        let hit = if ignore_case {
+            matches_pattern(text, &pattern.to_ascii_lowercase())
+        } else {
+            matches_pattern(text, pattern)
+        };
+        if hit {
+            if negated {
+                return false;
+            }
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// Expand `%x` tokens through `lookup`; `%%` is a literal percent and unknown tokens are kept.
+fn expand_tokens(value: &str, lookup: impl Fn(char) -> Option<String>) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            expanded.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some(token) => match lookup(token) {
+                Some(replacement) => expanded.push_str(&replacement),
+                None => {
+                    expanded.push('%');
+                    expanded.push(token);
+                }
+            },
+            None => expanded.push('%'),
+        }
+    }
+    expanded
+}
+
+fn local_user_name() -> String {
+    nix::unistd::User::from_uid(nix::unistd::Uid::current())
+        .ok()
+        .flatten()
+        .map(|user| user.name)
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_default()
+}
+
+fn load_statements(config_path: &Path, ancestors: &mut Vec<PathBuf>) -> io::Result<Vec<ConfigStatement>> {
+    let canonical = config_path.canonicalize().unwrap_or_else(|_| config_path.to_path_buf());
+    if ancestors.contains(&canonical) || ancestors.len() >= MAX_INCLUDE_DEPTH {
+        log_debug!("Skipping recursive SSH include while resolving options: {}", canonical.display());
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&canonical)?;
+    ancestors.push(canonical.clone());
+    let parent_dir = canonical.parent().unwrap_or(Path::new(".")).to_path_buf();
+
+    let mut statements = Vec::new();
+    for line in contents.lines() {
+        let Some((keyword, value)) = split_config_line(line) else {
+            continue;
+        };
+
+        match keyword.as_str() {
+            "host" => statements.push(ConfigStatement::Host(split_arguments(value))),
+            "match" => statements.push(ConfigStatement::Match(parse_match_criteria(value))),
+            "include" => {
+                let mut included = Vec::new();
+                for pattern in split_arguments(value) {
+                    let resolved_pattern = resolve_include_pattern(&pattern, &parent_dir);
+                    for include_path in expand_include_pattern(&resolved_pattern) {
+                        included.extend(load_statements(&include_path, ancestors)?);
+                    }
+                }
+                statements.push(ConfigStatement::Include(included));
+            }
+            _ => statements.push(ConfigStatement::Option {
+                keyword,
+                value: unquote(value).to_string(),
+            }),
+        }
+    }
+
+    ancestors.pop();
+    Ok(statements)
+}
+
+/// Split a config line into its lowercase keyword and value (`Keyword value` or `Keyword=value`).
+fn split_config_line(line: &str) -> Option<(String, &str)> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
+
+    let keyword_end = trimmed.find(|ch: char| ch.is_whitespace() || ch == '=')?;
+    let (keyword, rest) = trimmed.split_at(keyword_end);
+    let rest = rest.trim_start();
+    let value = rest.strip_prefix('=').unwrap_or(rest).trim();
+    (!value.is_empty()).then(|| (keyword.to_ascii_lowercase(), value))
+}
+
+/// Split on whitespace, keeping double-quoted arguments together.
+fn split_arguments(value: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_argument = false;
+
+    for ch in value.chars() {
+        match ch {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_argument = true;
+            }
+            ch if ch.is_whitespace() && !in_quotes => {
+                if has_argument {
+                    arguments.push(std::mem::take(&mut current));
+                    has_argument = false;
+                }
+            }
+            ch => {
+                current.push(ch);
+                has_argument = true;
+            }
+        }
+    }
+    if has_argument {
+        arguments.push(current);
+    }
+    arguments
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+        .filter(|inner| !inner.contains('"'))
+        .unwrap_or(value)
+}
+
+fn parse_match_criteria(value: &str) -> Vec<MatchCriterion> {
+    let mut arguments = split_arguments(value).into_iter();
+    let mut criteria = Vec::new();
+
+    while let Some(argument) = arguments.next() {
+        let (negated, keyword) = match argument.strip_prefix('!') {
+            Some(keyword) => (true, keyword.to_ascii_lowercase()),
+            None => (false, argument.to_ascii_lowercase()),
+        };
+        let condition = match keyword.as_str() {
+            "all" => MatchCondition::All,
+            "canonical" | "final" => MatchCondition::Final,
+            _ => {
+                let pattern = arguments.next().unwrap_or_default();
+                match keyword.as_str() {
+                    "exec" => MatchCondition::Exec(pattern),
+                    "host" => MatchCondition::Host(pattern),
+                    "originalhost" => MatchCondition::OriginalHost(pattern),
+                    "user" => MatchCondition::User(pattern),
+                    "localuser" => MatchCondition::LocalUser(pattern),
+                    _ => MatchCondition::Unsupported,
+                }
+            }
+        };
+        criteria.push(MatchCriterion { negated, condition });
+    }
+
+    criteria
+}
+
+fn statements_have_final_match(statements: &[ConfigStatement]) -> bool {
+    statements.iter().any(|statement| match statement {
+        ConfigStatement::Match(criteria) => criteria.iter().any(|criterion| matches!(criterion.condition, MatchCondition::Final)),
+        ConfigStatement::Include(included) => statements_have_final_match(included),
+        _ => false,
+    })
+}
+
+fn count_unsupported_match_blocks(statements: &[ConfigStatement], run_exec: bool) -> usize {
+    statements
+        .iter()
+        .map(|statement| match statement {
+            ConfigStatement::Match(criteria) => usize::from(
+                criteria.is_empty()
+                    || criteria.iter().any(|criterion| match criterion.condition {
+                        MatchCondition::Unsupported => true,
+                        MatchCondition::Exec(_) => !run_exec,
+                        _ => false,
+                    }),
+            ),
+            ConfigStatement::Include(included) => count_unsupported_match_blocks(included, run_exec),
+            _ => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+#[path = "../test/ssh_config/resolve.rs"]
+mod tests;
@@ -5,6 +5,7 @@
 //! consume terminal state through the contained `TerminalEngine`.
 
 use super::{TerminalEngine, TerminalInputWriter, TerminalSelection, TerminalSessionSnapshot};
+use crate::auth::agent::VaultForward;
 use crate::log_error;
 use portable_pty::{Child as PtyChild, MasterPty, PtySize};
 use std::io::{self, Write};
@@ -24,6 +25,8 @@ pub(crate) struct TerminalSession {
     engine: Arc<Mutex<TerminalEngine>>,
     exited: Arc<Mutex<bool>>,
     render_epoch: Arc<AtomicU64>,
+    /// Vault forwarding socket the backing `ssh` was launched with.
+    vault_forward: Option<VaultForward>,
 }
 
 impl TerminalSession {
@@ -43,9 +46,16 @@ impl TerminalSession {
             engine,
             exited,
             render_epoch,
+            vault_forward: None,
         }
     }
 
+    /// Keep the launch's vault forwarding socket open for as long as this session.
+    pub(crate) fn with_vault_forward(mut self, vault_forward: Option<VaultForward>) -> Self {
+        self.vault_forward = vault_forward;
+        self
+    }
+
     /// Return the canonical terminal engine for this session.
     pub(crate) fn engine(&self) -> &Arc<Mutex<TerminalEngine>> {
         &self.engine
@@ -1,7 +1,11 @@
 use super::{
-    CompletionProtocol, MainArgs, MainCommand, ProtocolCommand, RdpCommandArgs, SshCommandArgs, VaultCommand, build_cli_command, parse_main_args_from,
-    try_parse_main_args_from,
+    CompletionProtocol, InventoryAddArgs, InventoryCheckArgs, InventoryCommand, InventoryEffectiveArgs, InventoryExportArgs, InventoryExportFormat,
+    InventoryImportArgs, InventoryImportConflict, InventoryImportFormat, InventoryListArgs, InventoryListFormat, InventoryMoveArgs, InventoryRemoveArgs,
+    InventorySyncArgs, InventorySyncPreference, MainArgs, MainCommand, PasswordGeneratorArgs, ProtocolCommand, RdpCommandArgs, SshCommandArgs, VaultAddKeyArgs,
+    VaultAddPassArgs, VaultCommand, VaultExecArgs, VaultGetArgs, VaultKeyfileAction, VaultPolicyArgs, VaultRekeyArgs, VaultRenameArgs, VaultRotateSource,
+    VaultSecretField, build_cli_command, parse_main_args_from, try_parse_main_args_from,
 };
+use std::path::PathBuf;
 
 fn parse_ok(args: &[&str]) -> MainArgs {
     let command = build_cli_command();
@@ -34,6 +38,9 @@ fn parse_main_args_default_and_debug_flags_set_interactive_and_debug_levels() {
 
     let with_pass_entry = parse_ok(&["cossh", "--pass-entry", "shared", "ssh", "host"]);
     assert_eq!(with_pass_entry.pass_entry.as_deref(), Some("shared"));
+
+    let with_provider_reference = parse_ok(&["cossh", "--pass-entry", "pass:network/core-sw", "ssh", "host"]);
+    assert_eq!(with_provider_reference.pass_entry.as_deref(), Some("pass:network/core-sw"));
 }
 
 #[test]
@@ -89,10 +96,181 @@ fn parse_main_args_vault_and_migrate_commands_map_to_expected_variants() {
     let vault_cases: Vec<(Vec<&str>, MainCommand)> = vec![
         (
             vec!["cossh", "vault", "add", "office_fw"],
-            MainCommand::Vault(VaultCommand::AddPass("office_fw".to_string())),
+            MainCommand::Vault(VaultCommand::AddPass(VaultAddPassArgs {
+                name: "office_fw".to_string(),
+                generator: None,
+                user: None,
+            })),
+        ),
+        (
+            vec![
+                "cossh",
+                "vault",
+                "add",
+                "core_sw",
+                "--generate",
+                "--device-safe",
+                "-L",
+                "16",
+                "--user",
+                "netops",
+            ],
+            MainCommand::Vault(VaultCommand::AddPass(VaultAddPassArgs {
+                name: "core_sw".to_string(),
+                generator: Some(PasswordGeneratorArgs {
+                    length: Some(16),
+                    device_safe: true,
+                    ..PasswordGeneratorArgs::default()
+                }),
+                user: Some("netops".to_string()),
+            })),
+        ),
+        (
+            vec!["cossh", "vault", "rotate", "core_sw", "--pronounceable"],
+            MainCommand::Vault(VaultCommand::Rotate(
+                "core_sw".to_string(),
+                VaultRotateSource::Generate(PasswordGeneratorArgs {
+                    pronounceable: true,
+                    ..PasswordGeneratorArgs::default()
+                }),
+            )),
+        ),
+        (
+            vec!["cossh", "vault", "rotate", "core_sw", "--rollback"],
+            MainCommand::Vault(VaultCommand::Rotate("core_sw".to_string(), VaultRotateSource::Rollback)),
+        ),
+        (vec!["cossh", "vault", "ssh-agent"], MainCommand::Vault(VaultCommand::SshAgent)),
+        (
+            vec!["cossh", "vault", "add-key", "deploy", "~/.ssh/id_ed25519", "--shred", "--lifetime", "3600"],
+            MainCommand::Vault(VaultCommand::AddKey(VaultAddKeyArgs {
+                name: "deploy".to_string(),
+                path: "~/.ssh/id_ed25519".into(),
+                shred: true,
+                confirm: false,
+                lifetime_seconds: Some(3600),
+            })),
+        ),
+        (
+            vec!["cossh", "vault", "rename", "core_sw", "core_switch", "--update-inventory"],
+            MainCommand::Vault(VaultCommand::Rename(VaultRenameArgs {
+                name: "core_sw".to_string(),
+                new_name: "core_switch".to_string(),
+                update_inventory: true,
+            })),
+        ),
+        (
+            vec!["cossh", "vault", "get", "core_sw"],
+            MainCommand::Vault(VaultCommand::Get(VaultGetArgs {
+                name: "core_sw".to_string(),
+                field: VaultSecretField::Password,
+                host: None,
+            })),
+        ),
+        (
+            vec!["cossh", "vault", "get", "core_sw", "--field", "otp", "--host", "core-sw1"],
+            MainCommand::Vault(VaultCommand::Get(VaultGetArgs {
+                name: "core_sw".to_string(),
+                field: VaultSecretField::Otp,
+                host: Some("core-sw1".to_string()),
+            })),
+        ),
+        (
+            vec!["cossh", "vault", "exec", "core_sw", "--", "ansible-playbook", "-k", "site.yml"],
+            MainCommand::Vault(VaultCommand::Exec(VaultExecArgs {
+                name: "core_sw".to_string(),
+                field: VaultSecretField::Password,
+                host: None,
+                env: "COSSH_SECRET".to_string(),
+                stdin: false,
+                command: vec!["ansible-playbook".to_string(), "-k".to_string(), "site.yml".to_string()],
+            })),
+        ),
+        (
+            vec!["cossh", "vault", "exec", "core_sw", "-f", "user", "--stdin", "--", "netmiko-run"],
+            MainCommand::Vault(VaultCommand::Exec(VaultExecArgs {
+                name: "core_sw".to_string(),
+                field: VaultSecretField::User,
+                host: None,
+                env: "COSSH_SECRET".to_string(),
+                stdin: true,
+                command: vec!["netmiko-run".to_string()],
+            })),
+        ),
+        (vec!["cossh", "vault", "status", "--json"], MainCommand::Vault(VaultCommand::Status(true))),
+        (vec!["cossh", "vault", "list", "--json"], MainCommand::Vault(VaultCommand::List(true))),
+        (
+            vec!["cossh", "vault", "policy", "core_sw"],
+            MainCommand::Vault(VaultCommand::Policy(VaultPolicyArgs {
+                name: "core_sw".to_string(),
+                ..VaultPolicyArgs::default()
+            })),
+        ),
+        (
+            vec![
+                "cossh",
+                "vault",
+                "policy",
+                "core_sw",
+                "--confirm",
+                "--allow-host",
+                "prod-*",
+                "--allow-host",
+                "10.0.0.*",
+                "--max-uses",
+                "3",
+            ],
+            MainCommand::Vault(VaultCommand::Policy(VaultPolicyArgs {
+                name: "core_sw".to_string(),
+                confirm: Some(true),
+                allowed_hosts: vec!["prod-*".to_string(), "10.0.0.*".to_string()],
+                max_uses: Some(3),
+                ..VaultPolicyArgs::default()
+            })),
         ),
-        (vec!["cossh", "vault", "list"], MainCommand::Vault(VaultCommand::List)),
+        (vec!["cossh", "vault", "recovery-key"], MainCommand::Vault(VaultCommand::RecoveryKey)),
+        (vec!["cossh", "vault", "recover"], MainCommand::Vault(VaultCommand::Recover)),
+        (
+            vec!["cossh", "vault", "keyfile"],
+            MainCommand::Vault(VaultCommand::Keyfile(VaultKeyfileAction::Show)),
+        ),
+        (
+            vec!["cossh", "vault", "keyfile", "/media/usb/vault.key", "--generate"],
+            MainCommand::Vault(VaultCommand::Keyfile(VaultKeyfileAction::Set {
+                path: "/media/usb/vault.key".into(),
+                generate: true,
+            })),
+        ),
+        (
+            vec!["cossh", "vault", "keyfile", "--remove"],
+            MainCommand::Vault(VaultCommand::Keyfile(VaultKeyfileAction::Remove)),
+        ),
+        (vec!["cossh", "vault", "list"], MainCommand::Vault(VaultCommand::List(false))),
         (vec!["cossh", "vault", "init"], MainCommand::Vault(VaultCommand::Init)),
+        (vec!["cossh", "vault", "audit"], MainCommand::Vault(VaultCommand::Audit(None))),
+        (
+            vec!["cossh", "vault", "audit", "--tail", "20"],
+            MainCommand::Vault(VaultCommand::Audit(Some(20))),
+        ),
+        (
+            vec!["cossh", "vault", "rekey"],
+            MainCommand::Vault(VaultCommand::Rekey(VaultRekeyArgs::default())),
+        ),
+        (
+            vec!["cossh", "vault", "rekey", "--calibrate", "--rotate-key"],
+            MainCommand::Vault(VaultCommand::Rekey(VaultRekeyArgs {
+                calibrate_ms: Some(500),
+                rotate_data_key: true,
+                ..VaultRekeyArgs::default()
+            })),
+        ),
+        (
+            vec!["cossh", "vault", "rekey", "--memory-kib", "131072", "--time-cost", "4"],
+            MainCommand::Vault(VaultCommand::Rekey(VaultRekeyArgs {
+                memory_kib: Some(131_072),
+                time_cost: Some(4),
+                ..VaultRekeyArgs::default()
+            })),
+        ),
     ];
 
     for (args, expected_command) in vault_cases {
@@ -100,6 +278,115 @@ fn parse_main_args_vault_and_migrate_commands_map_to_expected_variants() {
     }
 
     assert_eq!(parse_ok(&["cossh", "--migrate"]).command, Some(MainCommand::MigrateInventory));
+    assert_eq!(
+        parse_ok(&["cossh", "inventory", "import", "--from", "ansible", "hosts.ini", "-o", "imported.yaml"]).command,
+        Some(MainCommand::Inventory(InventoryCommand::Import(InventoryImportArgs {
+            format: InventoryImportFormat::Ansible,
+            path: PathBuf::from("hosts.ini"),
+            output: Some(PathBuf::from("imported.yaml")),
+            folder: None,
+            on_conflict: InventoryImportConflict::Skip,
+            dry_run: false,
+        })))
+    );
+    assert_eq!(
+        parse_ok(&[
+            "cossh",
+            "inventory",
+            "import",
+            "--from",
+            "mobaxterm",
+            "sessions.mxtsessions",
+            "--folder",
+            "Clients/Moba",
+            "--on-conflict",
+            "rename",
+            "--dry-run",
+        ])
+        .command,
+        Some(MainCommand::Inventory(InventoryCommand::Import(InventoryImportArgs {
+            format: InventoryImportFormat::MobaXterm,
+            path: PathBuf::from("sessions.mxtsessions"),
+            output: None,
+            folder: Some("Clients/Moba".to_string()),
+            on_conflict: InventoryImportConflict::Rename,
+            dry_run: true,
+        })))
+    );
+    assert_eq!(
+        parse_ok(&["cossh", "inventory", "export", "--format", "ssh-config", "-o", "cossh.conf", "--split-includes"]).command,
+        Some(MainCommand::Inventory(InventoryCommand::Export(InventoryExportArgs {
+            format: InventoryExportFormat::SshConfig,
+            output: Some(PathBuf::from("cossh.conf")),
+            split_includes: true,
+        })))
+    );
+    assert_eq!(
+        parse_ok(&[
+            "cossh",
+            "inventory",
+            "sync",
+            "--ssh-config",
+            "ssh/config",
+            "--prefer",
+            "ssh-config",
+            "--dry-run"
+        ])
+        .command,
+        Some(MainCommand::Inventory(InventoryCommand::Sync(InventorySyncArgs {
+            ssh_config: Some(PathBuf::from("ssh/config")),
+            prefer: Some(InventorySyncPreference::SshConfig),
+            dry_run: true,
+        })))
+    );
+    assert_eq!(
+        parse_ok(&["cossh", "inventory", "effective", "root@web01", "--no-exec"]).command,
+        Some(MainCommand::Inventory(InventoryCommand::Effective(InventoryEffectiveArgs {
+            target: "root@web01".to_string(),
+            ssh_config: None,
+            no_exec: true,
+        })))
+    );
+    assert_eq!(
+        parse_ok(&["cossh", "inventory", "list", "--folder", "Prod/Web", "--protocol", "ssh", "-f", "json"]).command,
+        Some(MainCommand::Inventory(InventoryCommand::List(InventoryListArgs {
+            folder: Some("Prod/Web".to_string()),
+            protocol: Some("ssh".to_string()),
+            format: InventoryListFormat::Json,
+        })))
+    );
+    assert_eq!(
+        parse_ok(&["cossh", "inventory", "add", "web02", "host=10.0.0.12", "user=", "--folder", "Prod"]).command,
+        Some(MainCommand::Inventory(InventoryCommand::Add(InventoryAddArgs {
+            name: "web02".to_string(),
+            fields: vec![("host".to_string(), "10.0.0.12".to_string()), ("user".to_string(), String::new())],
+            folder: Some("Prod".to_string()),
+        })))
+    );
+    assert_eq!(
+        parse_ok(&["cossh", "inventory", "rm", "web01", "web02"]).command,
+        Some(MainCommand::Inventory(InventoryCommand::Remove(InventoryRemoveArgs {
+            names: vec!["web01".to_string(), "web02".to_string()],
+        })))
+    );
+    assert_eq!(
+        parse_ok(&["cossh", "inventory", "mv", "web01", "/"]).command,
+        Some(MainCommand::Inventory(InventoryCommand::Move(InventoryMoveArgs {
+            name: "web01".to_string(),
+            folder: "/".to_string(),
+        })))
+    );
+    assert_eq!(
+        parse_ok(&["cossh", "inventory", "check", "--strict"]).command,
+        Some(MainCommand::Inventory(InventoryCommand::Check(InventoryCheckArgs { path: None, strict: true })))
+    );
+    assert_eq!(
+        parse_ok(&["cossh", "inventory", "check", "ci/inventory.yaml"]).command,
+        Some(MainCommand::Inventory(InventoryCommand::Check(InventoryCheckArgs {
+            path: Some(PathBuf::from("ci/inventory.yaml")),
+            strict: false,
+        })))
+    );
     assert_eq!(
         parse_ok(&["cossh", "__complete", "hosts", "--protocol", "ssh"]).command,
         Some(MainCommand::CompletionHosts(CompletionProtocol::Ssh))
@@ -110,8 +397,36 @@ fn parse_main_args_vault_and_migrate_commands_map_to_expected_variants() {
 fn parse_main_args_invalid_combinations_and_profile_names_return_parse_errors() {
     let invalid_cases: Vec<Vec<&str>> = vec![
         vec!["cossh", "vault", "add", "office_fw", "user@example.com"],
+        vec!["cossh", "vault", "add", "office_fw", "--length", "16"],
+        vec!["cossh", "vault", "rotate", "office_fw", "--rollback", "--length", "16"],
+        vec!["cossh", "vault", "rotate", "office_fw", "--no-symbols", "--device-safe"],
+        vec!["cossh", "vault", "add-key", "deploy"],
+        vec!["cossh", "vault", "add-key", "deploy", "id_ed25519", "--lifetime", "0"],
+        vec!["cossh", "vault", "policy", "core_sw", "--confirm", "--no-confirm"],
+        vec!["cossh", "vault", "policy", "core_sw", "--allow-host", "prod-*", "--any-host"],
+        vec!["cossh", "vault", "policy", "core_sw", "--max-uses", "0"],
+        vec!["cossh", "vault", "keyfile", "--generate"],
+        vec!["cossh", "vault", "get", "core_sw", "--field", "totp"],
+        vec!["cossh", "vault", "exec", "core_sw"],
+        vec!["cossh", "vault", "exec", "core_sw", "--env", "1PASSWORD", "--", "env"],
+        vec!["cossh", "vault", "exec", "core_sw", "--env", "PASS", "--stdin", "--", "cat"],
+        vec!["cossh", "vault", "keyfile", "vault.key", "--remove"],
         vec!["cossh", "--migrate", "ssh", "host"],
         vec!["cossh", "--migrate", "--profile", "network"],
+        vec!["cossh", "inventory", "import", "hosts.ini"],
+        vec!["cossh", "inventory", "import", "--from", "chef", "hosts.ini"],
+        vec!["cossh", "inventory", "import", "--from", "ansible", "hosts.ini", "--dry-run"],
+        vec!["cossh", "inventory", "import", "--from", "putty", "putty.reg", "--on-conflict", "merge"],
+        vec!["cossh", "inventory", "export", "--format", "ssh-config", "--split-includes"],
+        vec!["cossh", "inventory", "export", "--format", "csv", "-o", "hosts.csv", "--split-includes"],
+        vec!["cossh", "inventory", "sync", "--prefer", "both"],
+        vec!["cossh", "inventory", "effective"],
+        vec!["cossh", "inventory", "list", "--format", "yaml"],
+        vec!["cossh", "inventory", "set", "web01"],
+        vec!["cossh", "inventory", "set", "web01", "user"],
+        vec!["cossh", "inventory", "add", "web02", "=10.0.0.12"],
+        vec!["cossh", "inventory", "mv", "web01"],
+        vec!["cossh", "inventory", "check", "one.yaml", "two.yaml"],
         vec!["cossh", "user@example.com"],
         vec!["cossh", "ssh"],
         vec!["cossh", "--profile", "../prod", "ssh", "host"],
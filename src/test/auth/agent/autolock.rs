@@ -0,0 +1,27 @@
+use super::*;
+
+#[test]
+fn resume_detector_reports_wall_clock_jumps_beyond_monotonic_time() {
+    let start = Instant::now();
+    let wall = SystemTime::now();
+    let mut detector = ResumeDetector::starting_at(start, wall);
+
+    // Both clocks advance together while the machine is awake.
+    assert!(!detector.observe_at(start + Duration::from_secs(60), wall + Duration::from_secs(61)));
+    // An hour of sleep: the wall clock moved, the monotonic clock barely did.
+    assert!(detector.observe_at(start + Duration::from_secs(61), wall + Duration::from_secs(3_662)));
+    // The next poll compares against the post-resume sample.
+    assert!(!detector.observe_at(start + Duration::from_secs(62), wall + Duration::from_secs(3_663)));
+    // A wall clock stepped backwards is not a resume.
+    assert!(!detector.observe_at(start + Duration::from_secs(63), wall));
+}
+
+#[test]
+fn process_is_running_tracks_child_lifetime() {
+    assert!(process_is_running(std::process::id()));
+
+    let mut child = std::process::Command::new("true").spawn().expect("spawn child");
+    let pid = child.id();
+    child.wait().expect("wait for child");
+    assert!(!process_is_running(pid), "reaped child {pid} should not be reported as running");
+}
@@ -7,5 +7,18 @@ fn map_remote_error_maps_known_codes() {
         map_remote_error("invalid_master_password", "bad".to_string()),
         AgentError::InvalidMasterPassword
     ));
+    assert!(matches!(
+        map_remote_error("confirmation_required", "confirm".to_string()),
+        AgentError::ConfirmationRequired
+    ));
+    assert!(matches!(
+        map_remote_error("confirmation_unavailable", "no helper".to_string()),
+        AgentError::ConfirmationUnavailable
+    ));
+    assert!(matches!(map_remote_error("host_not_allowed", "host".to_string()), AgentError::HostNotAllowed));
+    assert!(matches!(
+        map_remote_error("usage_limit_reached", "limit".to_string()),
+        AgentError::UsageLimitReached
+    ));
     assert!(matches!(map_remote_error("vault_error", "oops".to_string()), AgentError::Protocol(_)));
 }
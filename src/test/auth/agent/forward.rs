@@ -0,0 +1,175 @@
+use super::*;
+use crate::auth::audit::{self, AuditEventKind};
+use crate::auth::ipc::UnlockPolicy;
+use crate::auth::vault::EntryPolicy;
+use crate::test::support::auth::TestVaultEnv;
+use interprocess::local_socket::traits::Listener as _;
+use std::cell::RefCell;
+
+fn unlocked_runtime(env: &TestVaultEnv) -> Mutex<AgentRuntime> {
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("shared", "top-secret").expect("store secret");
+    let mut runtime = AgentRuntime::new();
+    runtime.unlock(unlocked.data_key_copy(), UnlockPolicy::new(900, 3600));
+    Mutex::new(runtime)
+}
+
+fn error_code(response: &ForwardedSecretResponse) -> &str {
+    match response {
+        ForwardedSecretResponse::Error { code, .. } => code,
+        ForwardedSecretResponse::Secret { .. } => panic!("unexpected secret response"),
+    }
+}
+
+#[test]
+fn forwarded_request_releases_only_after_local_confirmation_and_is_audited_as_remote() {
+    let env = TestVaultEnv::new("forward_release");
+    let runtime = unlocked_runtime(&env);
+    let prompts = RefCell::new(Vec::new());
+
+    let declined = handle_forwarded_request(env.paths(), &runtime, "shared", "jump01", None, None, |prompt| {
+        prompts.borrow_mut().push(prompt.to_string());
+        Some(false)
+    });
+    assert_eq!(error_code(&declined), "confirmation_declined");
+
+    let unavailable = handle_forwarded_request(env.paths(), &runtime, "shared", "jump01", None, None, |_| None);
+    assert_eq!(error_code(&unavailable), "confirmation_unavailable");
+
+    let released = handle_forwarded_request(env.paths(), &runtime, "shared", "jump01", None, None, |prompt| {
+        prompts.borrow_mut().push(prompt.to_string());
+        Some(true)
+    });
+    match released {
+        ForwardedSecretResponse::Secret { secret } => assert_eq!(secret.expose_secret(), "top-secret"),
+        other => panic!("unexpected forwarded response: {other:?}"),
+    }
+    assert_eq!(
+        prompts.into_inner(),
+        vec!["Release password vault entry 'shared' to remote host 'jump01'?".to_string(); 2]
+    );
+
+    let verification = audit::verify_audit_log(env.paths()).expect("verify audit log");
+    let events: Vec<_> = verification
+        .records
+        .iter()
+        .filter(|record| {
+            matches!(
+                record.event,
+                AuditEventKind::EntryDenied | AuditEventKind::RemoteEntryRead | AuditEventKind::EntryRead
+            )
+        })
+        .map(|record| (record.event, record.host.as_deref(), record.detail.as_deref()))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            (AuditEventKind::EntryDenied, Some("jump01"), Some("remote forward: declined")),
+            (AuditEventKind::EntryDenied, Some("jump01"), Some("remote forward: confirmation_unavailable")),
+            (AuditEventKind::RemoteEntryRead, Some("jump01"), Some("remote forward")),
+        ]
+    );
+}
+
+#[test]
+fn forwarded_request_is_refused_without_prompting_when_it_cannot_be_served() {
+    let env = TestVaultEnv::new("forward_refused");
+    let runtime = unlocked_runtime(&env);
+    env.unlock("master-pass")
+        .set_entry_policy(
+            "shared",
+            EntryPolicy {
+                allowed_hosts: vec!["prod-*".to_string()],
+                ..EntryPolicy::default()
+            },
+        )
+        .expect("set entry policy");
+    let never_prompt = |prompt: &str| -> Option<bool> { panic!("unexpected confirmation prompt: {prompt}") };
+
+    let external = handle_forwarded_request(env.paths(), &runtime, "op:Infra/web/password", "jump01", None, None, never_prompt);
+    assert_eq!(error_code(&external), "invalid_entry_name");
+
+    let missing = handle_forwarded_request(env.paths(), &runtime, "missing", "prod-web", None, None, never_prompt);
+    assert_eq!(error_code(&missing), "entry_not_found");
+
+    let wrong_host = handle_forwarded_request(env.paths(), &runtime, "shared", "lab", None, None, never_prompt);
+    assert_eq!(error_code(&wrong_host), "host_not_allowed");
+
+    lock_runtime(&runtime).lock();
+    let locked = handle_forwarded_request(env.paths(), &runtime, "shared", "prod-web", None, None, never_prompt);
+    assert_eq!(error_code(&locked), "locked");
+}
+
+#[test]
+fn forwarded_confirmation_satisfies_confirm_policy_and_counts_towards_usage_limit() {
+    let env = TestVaultEnv::new("forward_policy");
+    let runtime = unlocked_runtime(&env);
+    env.unlock("master-pass")
+        .set_entry_policy(
+            "shared",
+            EntryPolicy {
+                confirm: true,
+                max_uses: Some(1),
+                ..EntryPolicy::default()
+            },
+        )
+        .expect("set entry policy");
+
+    let released = handle_forwarded_request(env.paths(), &runtime, "shared", "jump01", None, None, |_| Some(true));
+    assert!(matches!(released, ForwardedSecretResponse::Secret { .. }));
+
+    let exhausted = handle_forwarded_request(env.paths(), &runtime, "shared", "jump01", None, None, |_| Some(true));
+    assert_eq!(error_code(&exhausted), "usage_limit_reached");
+}
+
+#[test]
+fn vault_forward_sockets_are_unique_per_launch_and_removed_when_dropped() {
+    let env = TestVaultEnv::new("forward_sockets");
+
+    let first = open_vault_forward(env.paths(), "jump01", None).expect("open first forward");
+    let second = open_vault_forward(env.paths(), "jump01", Some("jump01.example")).expect("open second forward");
+    assert_ne!(first.socket_path(), second.socket_path());
+    assert!(first.socket_path().starts_with(env.paths().run_dir()));
+
+    let first_path = first.socket_path().to_path_buf();
+    drop(first);
+    assert!(!first_path.exists(), "dropped forward left its socket behind");
+    assert!(second.socket_path().exists());
+}
+
+#[test]
+fn relayed_requests_name_the_launching_alias_not_the_remote_claim() {
+    let env = TestVaultEnv::new("forward_relay");
+    let (request_tx, request_rx) = std::sync::mpsc::channel();
+    let ipc::ListenerBindResult::Bound(listener) = ipc::bind_listener(env.paths()).expect("bind agent endpoint") else {
+        panic!("test agent endpoint already in use");
+    };
+    let agent = std::thread::spawn(move || {
+        let mut stream = listener.accept().expect("accept relay");
+        let request = ipc::read_request(&mut stream).expect("read relayed request");
+        let response = AgentResponse::Error {
+            status: crate::auth::ipc::VaultStatus::locked(true),
+            code: "host_not_allowed".to_string(),
+            message: "refused".to_string(),
+        };
+        ipc::write_response(&mut stream, &response).expect("write response");
+        request_tx.send(request.payload).expect("send payload");
+    });
+
+    let vault_forward = open_vault_forward(env.paths(), "prod-web", Some("10.0.0.5")).expect("open forward");
+    let mut stream = UnixStream::connect(vault_forward.socket_path()).expect("connect forward");
+    // An older remote `cossh` still names a host; the relay must ignore it.
+    ipc::write_json_line(&mut stream, &serde_json::json!({"name": "shared", "host": "lab", "destination": "lab.example"})).expect("write request");
+    let response: ForwardedSecretResponse = ipc::read_json_line(&mut stream).expect("read response");
+
+    assert_eq!(error_code(&response), "host_not_allowed");
+    agent.join().expect("fake agent");
+    assert_eq!(
+        request_rx.recv().expect("relayed payload"),
+        AgentRequestPayload::ForwardedSecret {
+            name: "shared".to_string(),
+            host: "prod-web".to_string(),
+            destination: Some("10.0.0.5".to_string()),
+        }
+    );
+}
@@ -1,19 +1,39 @@
-use super::{AGENT_IDLE_SHUTDOWN_POLL_INTERVAL_MIN, AgentRuntime, next_idle_shutdown_poll_interval};
+use super::AgentRuntime;
+use crate::auth::agent::AgentError;
 use crate::auth::ipc::UnlockPolicy;
+use crate::auth::secret::sensitive_string;
+use crate::auth::vault::EntryPolicy;
 use std::time::{Duration, Instant};
 
 #[test]
-fn runtime_expiry_and_poll_backoff_follow_timeout_rules() {
+fn runtime_expiry_and_expiry_deadline_follow_timeout_rules() {
     let mut runtime = AgentRuntime::new();
+    assert_eq!(runtime.time_until_expiry(), None);
+
+    runtime.unlock([7u8; 32], UnlockPolicy::new(60, 10));
+    let remaining = runtime.time_until_expiry().expect("unlocked deadline");
+    assert!(remaining <= Duration::from_secs(10) && remaining > Duration::from_secs(9));
+
     runtime.unlock([7u8; 32], UnlockPolicy::new(1, 10));
     runtime.last_activity_at = Some(Instant::now() - Duration::from_secs(2));
+    assert_eq!(runtime.time_until_expiry(), Some(Duration::ZERO));
     assert!(runtime.expire_if_needed());
     assert!(runtime.data_key.is_none());
+    assert_eq!(runtime.time_until_expiry(), None);
+}
+
+#[test]
+fn cached_external_secrets_set_a_deadline_while_locked_and_are_cleared_when_it_passes() {
+    let mut runtime = AgentRuntime::new();
+    runtime.cache_external_secret("pass:network/core-sw", sensitive_string("core-secret"), UnlockPolicy::new(60, 10));
+    let remaining = runtime.time_until_expiry().expect("cache deadline while locked");
+    assert!(remaining <= Duration::from_secs(10) && remaining > Duration::from_secs(9));
 
-    let first = next_idle_shutdown_poll_interval(AGENT_IDLE_SHUTDOWN_POLL_INTERVAL_MIN);
-    let second = next_idle_shutdown_poll_interval(first);
-    assert_eq!(first, Duration::from_millis(10));
-    assert_eq!(second, Duration::from_millis(20));
+    runtime.external_secrets[0].cached_at = Instant::now() - Duration::from_secs(11);
+    assert_eq!(runtime.time_until_expiry(), Some(Duration::ZERO));
+    assert!(!runtime.expire_if_needed());
+    assert!(runtime.external_secrets.is_empty());
+    assert_eq!(runtime.time_until_expiry(), None);
 }
 
 #[test]
@@ -25,3 +45,118 @@ fn runtime_debug_redacts_key_material() {
     assert!(!debug.contains("[7, 7"));
     assert!(debug.contains("[REDACTED]"));
 }
+
+#[test]
+fn runtime_external_secret_cache_follows_idle_timeout_and_lock() {
+    let mut runtime = AgentRuntime::new();
+    runtime.cache_external_secret("pass:network/core-sw", sensitive_string("core-secret"), UnlockPolicy::new(1, 10));
+
+    assert!(runtime.cached_external_secret("pass:network/core-sw").is_some());
+
+    runtime.external_secrets[0].last_used_at = Instant::now() - Duration::from_secs(2);
+    assert!(runtime.cached_external_secret("pass:network/core-sw").is_none());
+
+    runtime.cache_external_secret("pass:network/core-sw", sensitive_string("core-secret"), UnlockPolicy::new(900, 3600));
+    runtime.lock();
+    assert!(runtime.external_secrets.is_empty());
+}
+
+#[test]
+fn runtime_external_cache_policy_is_the_unlock_policy_and_never_set_while_locked() {
+    let mut runtime = AgentRuntime::new();
+    assert_eq!(runtime.external_cache_policy(), None);
+
+    runtime.unlock([7u8; 32], UnlockPolicy::new(900, 3600));
+    assert_eq!(runtime.external_cache_policy(), Some(UnlockPolicy::new(900, 3600)));
+
+    runtime.lock();
+    assert_eq!(runtime.external_cache_policy(), None);
+}
+
+#[test]
+fn runtime_askpass_token_enforces_entry_host_patterns() {
+    let mut runtime = AgentRuntime::new();
+    runtime.unlock([7u8; 32], UnlockPolicy::new(900, 3600));
+    let policy = EntryPolicy {
+        allowed_hosts: vec!["prod-*".to_string(), "10.0.0.*".to_string()],
+        ..EntryPolicy::default()
+    };
+
+    assert!(runtime.issue_askpass_token("shared", Some("prod-web"), None, &policy, false).is_ok());
+    assert!(runtime.issue_askpass_token("shared", Some("lab"), Some("10.0.0.7"), &policy, false).is_ok());
+    assert!(matches!(
+        runtime.issue_askpass_token("shared", Some("lab"), Some("lab.example.com"), &policy, false),
+        Err(AgentError::HostNotAllowed)
+    ));
+    assert!(matches!(
+        runtime.issue_askpass_token("shared", None, None, &policy, false),
+        Err(AgentError::HostNotAllowed)
+    ));
+}
+
+#[test]
+fn runtime_askpass_token_usage_limit_resets_on_lock() {
+    let mut runtime = AgentRuntime::new();
+    runtime.unlock([7u8; 32], UnlockPolicy::new(900, 3600));
+    let policy = EntryPolicy {
+        max_uses: Some(2),
+        ..EntryPolicy::default()
+    };
+
+    assert!(runtime.issue_askpass_token("shared", None, None, &policy, false).is_ok());
+    assert!(runtime.issue_askpass_token("shared", None, None, &policy, false).is_ok());
+    assert!(matches!(
+        runtime.issue_askpass_token("shared", None, None, &policy, false),
+        Err(AgentError::UsageLimitReached)
+    ));
+    assert!(runtime.issue_askpass_token("other", None, None, &policy, false).is_ok());
+
+    runtime.lock();
+    runtime.unlock([7u8; 32], UnlockPolicy::new(900, 3600));
+    assert!(runtime.issue_askpass_token("shared", None, None, &policy, false).is_ok());
+}
+
+#[test]
+fn runtime_askpass_token_requires_agent_confirmation_for_confirm_entries() {
+    let mut runtime = AgentRuntime::new();
+    runtime.unlock([7u8; 32], UnlockPolicy::new(900, 3600));
+    let policy = EntryPolicy {
+        confirm: true,
+        allowed_hosts: vec!["prod-*".to_string()],
+        ..EntryPolicy::default()
+    };
+
+    assert!(matches!(
+        runtime.issue_askpass_token("shared", Some("prod-web"), None, &policy, false),
+        Err(AgentError::ConfirmationRequired)
+    ));
+    assert!(matches!(
+        runtime.release_refusal("shared", Some("db01"), None, &policy),
+        Some(AgentError::HostNotAllowed)
+    ));
+    assert!(matches!(
+        runtime.issue_askpass_token("shared", Some("db01"), None, &policy, true),
+        Err(AgentError::HostNotAllowed)
+    ));
+    assert!(runtime.release_refusal("shared", Some("prod-web"), None, &policy).is_none());
+    assert!(runtime.issue_askpass_token("shared", Some("prod-web"), None, &policy, true).is_ok());
+}
+
+#[test]
+fn runtime_prunes_exited_session_managers_and_forgets_them_on_lock() {
+    let mut runtime = AgentRuntime::new();
+    runtime.attach_tui(100);
+    assert!(!runtime.prune_exited_tuis(|_| false), "nothing attaches while locked");
+
+    runtime.unlock([7u8; 32], UnlockPolicy::new(900, 3600));
+    runtime.attach_tui(100);
+    runtime.attach_tui(200);
+    assert!(!runtime.prune_exited_tuis(|pid| pid == 200));
+    assert!(runtime.prune_exited_tuis(|_| false));
+    assert!(!runtime.prune_exited_tuis(|_| false), "an empty set is only reported once");
+
+    runtime.attach_tui(300);
+    runtime.lock();
+    runtime.unlock([7u8; 32], UnlockPolicy::new(900, 3600));
+    assert!(!runtime.detach_tui(300));
+}
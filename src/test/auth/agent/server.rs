@@ -1,8 +1,17 @@
-use super::handle_request;
-use crate::auth::agent::runtime::AgentRuntime;
-use crate::auth::ipc::{self, AgentPeerTrust, AgentRequest, AgentRequestPayload, AgentResponse, UnlockPolicy, VaultStatusEventKind};
+use super::{authorize_vault_askpass, handle_request, serve};
+use crate::auth::agent::runtime::{AgentRuntime, lock_runtime};
+use crate::auth::audit::{self, AuditEventKind};
+use crate::auth::ipc::{
+    self, AgentEntryChange, AgentPeer, AgentPeerTrust, AgentRequest, AgentRequestPayload, AgentResponse, LockTriggers, UnlockPolicy, VaultStatusEventKind,
+};
 use crate::auth::secret::{ExposeSecret, sensitive_string};
+use crate::auth::vault::EntryPolicy;
 use crate::test::support::auth::TestVaultEnv;
+use crate::test::support::fs::TestWorkspace;
+use std::sync::{Arc, Barrier, Mutex};
+use std::thread;
+
+const CONCURRENT_ASKPASS_CLIENTS: usize = 40;
 
 #[test]
 fn handle_request_unlock_authorize_and_get_secret_happy_path() {
@@ -10,28 +19,32 @@ fn handle_request_unlock_authorize_and_get_secret_happy_path() {
     let unlocked = env.init_and_unlock("master-pass");
     unlocked.store_secret("shared", "top-secret").expect("store secret");
 
-    let mut runtime = AgentRuntime::new();
+    let runtime = Mutex::new(AgentRuntime::new());
 
     let unlock = handle_request(
         env.paths(),
-        &mut runtime,
+        &runtime,
         AgentRequest {
             payload: AgentRequestPayload::Unlock {
                 master_password: sensitive_string("master-pass"),
                 policy: UnlockPolicy::new(900, 28_800),
             },
         },
-        AgentPeerTrust::TrustedCossh,
+        AgentPeerTrust::TrustedCossh.into(),
     );
     assert!(matches!(unlock, AgentResponse::Success { .. }));
 
     let token = match handle_request(
         env.paths(),
-        &mut runtime,
+        &runtime,
         AgentRequest {
-            payload: AgentRequestPayload::AuthorizeAskpass { name: "shared".to_string() },
+            payload: AgentRequestPayload::AuthorizeAskpass {
+                name: "shared".to_string(),
+                host: None,
+                destination: None,
+            },
         },
-        AgentPeerTrust::TrustedCossh,
+        AgentPeerTrust::TrustedCossh.into(),
     ) {
         AgentResponse::AskpassAuthorized { token, .. } => token,
         other => panic!("unexpected authorize response: {other:?}"),
@@ -39,11 +52,11 @@ fn handle_request_unlock_authorize_and_get_secret_happy_path() {
 
     let response = handle_request(
         env.paths(),
-        &mut runtime,
+        &runtime,
         AgentRequest {
             payload: AgentRequestPayload::GetSecret { token },
         },
-        AgentPeerTrust::TrustedCossh,
+        AgentPeerTrust::TrustedCossh.into(),
     );
 
     assert!(matches!(response, AgentResponse::Secret { secret, .. } if secret.expose_secret() == "top-secret"));
@@ -54,17 +67,17 @@ fn lock_and_unlock_requests_emit_status_events_and_clear_runtime_state() {
     let env = TestVaultEnv::new("lock_unlock_events");
     env.init("master-pass");
 
-    let mut runtime = AgentRuntime::new();
+    let runtime = Mutex::new(AgentRuntime::new());
     let unlock = handle_request(
         env.paths(),
-        &mut runtime,
+        &runtime,
         AgentRequest {
             payload: AgentRequestPayload::Unlock {
                 master_password: sensitive_string("master-pass"),
                 policy: UnlockPolicy::new(900, 28_800),
             },
         },
-        AgentPeerTrust::TrustedCossh,
+        AgentPeerTrust::TrustedCossh.into(),
     );
     assert!(matches!(unlock, AgentResponse::Success { .. }));
     assert_eq!(
@@ -74,14 +87,14 @@ fn lock_and_unlock_requests_emit_status_events_and_clear_runtime_state() {
 
     let lock = handle_request(
         env.paths(),
-        &mut runtime,
+        &runtime,
         AgentRequest {
             payload: AgentRequestPayload::Lock,
         },
-        AgentPeerTrust::TrustedCossh,
+        AgentPeerTrust::TrustedCossh.into(),
     );
     assert!(matches!(lock, AgentResponse::Success { .. }));
-    assert!(runtime.data_key.is_none());
+    assert!(lock_runtime(&runtime).data_key.is_none());
     assert_eq!(
         ipc::read_vault_status_event(env.paths()).expect("read locked event").kind,
         VaultStatusEventKind::Locked
@@ -94,16 +107,20 @@ fn askpass_token_single_use_and_locked_runtime_errors() {
     let unlocked = env.init_and_unlock("master-pass");
     unlocked.store_secret("shared", "top-secret").expect("store secret");
 
-    let mut runtime = AgentRuntime::new();
-    runtime.unlock(unlocked.data_key_copy(), UnlockPolicy::new(900, 28_800));
+    let runtime = Mutex::new(AgentRuntime::new());
+    lock_runtime(&runtime).unlock(unlocked.data_key_copy(), UnlockPolicy::new(900, 28_800));
 
     let token = match handle_request(
         env.paths(),
-        &mut runtime,
+        &runtime,
         AgentRequest {
-            payload: AgentRequestPayload::AuthorizeAskpass { name: "shared".to_string() },
+            payload: AgentRequestPayload::AuthorizeAskpass {
+                name: "shared".to_string(),
+                host: None,
+                destination: None,
+            },
         },
-        AgentPeerTrust::TrustedCossh,
+        AgentPeerTrust::TrustedCossh.into(),
     ) {
         AgentResponse::AskpassAuthorized { token, .. } => token,
         other => panic!("unexpected authorize response: {other:?}"),
@@ -111,31 +128,35 @@ fn askpass_token_single_use_and_locked_runtime_errors() {
 
     let first = handle_request(
         env.paths(),
-        &mut runtime,
+        &runtime,
         AgentRequest {
             payload: AgentRequestPayload::GetSecret { token: token.clone() },
         },
-        AgentPeerTrust::TrustedCossh,
+        AgentPeerTrust::TrustedCossh.into(),
     );
     assert!(matches!(first, AgentResponse::Secret { .. }));
 
     let second = handle_request(
         env.paths(),
-        &mut runtime,
+        &runtime,
         AgentRequest {
             payload: AgentRequestPayload::GetSecret { token },
         },
-        AgentPeerTrust::TrustedCossh,
+        AgentPeerTrust::TrustedCossh.into(),
     );
     assert!(matches!(second, AgentResponse::Error { code, .. } if code == "invalid_or_expired_askpass_token"));
 
     let locked_authorize = handle_request(
         env.paths(),
-        &mut AgentRuntime::new(),
+        &Mutex::new(AgentRuntime::new()),
         AgentRequest {
-            payload: AgentRequestPayload::AuthorizeAskpass { name: "shared".to_string() },
+            payload: AgentRequestPayload::AuthorizeAskpass {
+                name: "shared".to_string(),
+                host: None,
+                destination: None,
+            },
         },
-        AgentPeerTrust::TrustedCossh,
+        AgentPeerTrust::TrustedCossh.into(),
     );
     assert!(matches!(locked_authorize, AgentResponse::Error { code, .. } if code == "locked"));
 }
@@ -146,26 +167,34 @@ fn secret_bearing_requests_require_trusted_peer() {
     let unlocked = env.init_and_unlock("master-pass");
     unlocked.store_secret("shared", "top-secret").expect("store secret");
 
-    let mut runtime = AgentRuntime::new();
-    runtime.unlock(unlocked.data_key_copy(), UnlockPolicy::new(900, 28_800));
+    let runtime = Mutex::new(AgentRuntime::new());
+    lock_runtime(&runtime).unlock(unlocked.data_key_copy(), UnlockPolicy::new(900, 28_800));
 
     let untrusted_authorize = handle_request(
         env.paths(),
-        &mut runtime,
+        &runtime,
         AgentRequest {
-            payload: AgentRequestPayload::AuthorizeAskpass { name: "shared".to_string() },
+            payload: AgentRequestPayload::AuthorizeAskpass {
+                name: "shared".to_string(),
+                host: None,
+                destination: None,
+            },
         },
-        AgentPeerTrust::Untrusted,
+        AgentPeerTrust::Untrusted.into(),
     );
     assert!(matches!(untrusted_authorize, AgentResponse::Error { code, .. } if code == "unauthorized_client"));
 
     let token = match handle_request(
         env.paths(),
-        &mut runtime,
+        &runtime,
         AgentRequest {
-            payload: AgentRequestPayload::AuthorizeAskpass { name: "shared".to_string() },
+            payload: AgentRequestPayload::AuthorizeAskpass {
+                name: "shared".to_string(),
+                host: None,
+                destination: None,
+            },
         },
-        AgentPeerTrust::TrustedCossh,
+        AgentPeerTrust::TrustedCossh.into(),
     ) {
         AgentResponse::AskpassAuthorized { token, .. } => token,
         other => panic!("unexpected authorize response: {other:?}"),
@@ -173,21 +202,688 @@ fn secret_bearing_requests_require_trusted_peer() {
 
     let untrusted_get = handle_request(
         env.paths(),
-        &mut runtime,
+        &runtime,
         AgentRequest {
             payload: AgentRequestPayload::GetSecret { token: token.clone() },
         },
-        AgentPeerTrust::Untrusted,
+        AgentPeerTrust::Untrusted.into(),
     );
     assert!(matches!(untrusted_get, AgentResponse::Error { code, .. } if code == "unauthorized_client"));
 
     let trusted_get = handle_request(
         env.paths(),
-        &mut runtime,
+        &runtime,
         AgentRequest {
             payload: AgentRequestPayload::GetSecret { token },
         },
-        AgentPeerTrust::TrustedCossh,
+        AgentPeerTrust::TrustedCossh.into(),
     );
     assert!(matches!(trusted_get, AgentResponse::Secret { secret, .. } if secret.expose_secret() == "top-secret"));
 }
+
+fn change_entry(env: &TestVaultEnv, runtime: &Mutex<AgentRuntime>, change: AgentEntryChange, trust: AgentPeerTrust) -> AgentResponse {
+    handle_request(
+        env.paths(),
+        runtime,
+        AgentRequest {
+            payload: AgentRequestPayload::ChangeEntry { change },
+        },
+        trust.into(),
+    )
+}
+
+#[test]
+fn change_entry_requests_use_the_unlocked_session_and_a_trusted_peer() {
+    let env = TestVaultEnv::new("change_entry");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("shared", "old-secret").expect("store secret");
+    let runtime = Mutex::new(AgentRuntime::new());
+    let replace = || AgentEntryChange::Replace {
+        name: "shared".to_string(),
+        secret: sensitive_string("new-secret"),
+    };
+
+    let locked = change_entry(&env, &runtime, replace(), AgentPeerTrust::TrustedCossh);
+    assert!(matches!(locked, AgentResponse::Error { code, .. } if code == "locked"));
+
+    lock_runtime(&runtime).unlock(unlocked.data_key_copy(), UnlockPolicy::new(900, 28_800));
+    let untrusted = change_entry(&env, &runtime, replace(), AgentPeerTrust::Untrusted);
+    assert!(matches!(untrusted, AgentResponse::Error { code, .. } if code == "unauthorized_client"));
+    assert_eq!(unlocked.get_secret("shared").expect("get secret").expose_secret(), "old-secret");
+
+    let replaced = change_entry(&env, &runtime, replace(), AgentPeerTrust::TrustedCossh);
+    assert!(matches!(replaced, AgentResponse::Success { .. }), "{replaced:?}");
+    let renamed = change_entry(
+        &env,
+        &runtime,
+        AgentEntryChange::Rename {
+            name: "shared".to_string(),
+            new_name: "renamed".to_string(),
+        },
+        AgentPeerTrust::TrustedCossh,
+    );
+    assert!(matches!(renamed, AgentResponse::Success { .. }), "{renamed:?}");
+    assert_eq!(unlocked.get_secret("renamed").expect("get secret").expose_secret(), "new-secret");
+
+    let missing = change_entry(
+        &env,
+        &runtime,
+        AgentEntryChange::Remove { name: "shared".to_string() },
+        AgentPeerTrust::TrustedCossh,
+    );
+    assert!(
+        matches!(&missing, AgentResponse::Error { code, .. } if code == "entry_not_found"),
+        "{missing:?}"
+    );
+}
+
+#[test]
+fn external_reference_askpass_resolves_without_unlock_and_caches_only_while_unlocked() {
+    let env = TestVaultEnv::new("external_provider");
+    let workspace = TestWorkspace::new("auth_agent", "external_provider").expect("workspace");
+    let counter = workspace.join("invocations");
+    let script = workspace
+        .write_script("provider.sh", &format!("echo run >> '{}'\necho provider-secret", counter.display()))
+        .expect("write stub provider");
+    let reference = format!("cmd:{}", script.display());
+    let runtime = Mutex::new(AgentRuntime::new());
+    let launch = || {
+        let token = match handle_request(
+            env.paths(),
+            &runtime,
+            AgentRequest {
+                payload: AgentRequestPayload::AuthorizeAskpass {
+                    name: reference.clone(),
+                    host: None,
+                    destination: None,
+                },
+            },
+            AgentPeerTrust::TrustedCossh.into(),
+        ) {
+            AgentResponse::AskpassAuthorized { token, .. } => token,
+            other => panic!("unexpected authorize response: {other:?}"),
+        };
+
+        let response = handle_request(
+            env.paths(),
+            &runtime,
+            AgentRequest {
+                payload: AgentRequestPayload::GetSecret { token },
+            },
+            AgentPeerTrust::TrustedCossh.into(),
+        );
+        assert!(matches!(response, AgentResponse::Secret { secret, .. } if secret.expose_secret() == "provider-secret"));
+    };
+    let invocations = || std::fs::read_to_string(&counter).expect("read invocation counter").lines().count();
+
+    launch();
+    launch();
+    assert!(lock_runtime(&runtime).data_key.is_none());
+    assert!(lock_runtime(&runtime).external_secrets.is_empty(), "nothing is cached while locked");
+    assert_eq!(invocations(), 2, "each launch resolves once while locked");
+
+    lock_runtime(&runtime).unlock([7u8; 32], UnlockPolicy::new(900, 3_600));
+    launch();
+    launch();
+    assert_eq!(invocations(), 3, "the unlock session caches the secret");
+}
+
+#[test]
+fn external_reference_provider_failure_returns_provider_error_code() {
+    let env = TestVaultEnv::new("external_provider_failure");
+    let workspace = TestWorkspace::new("auth_agent", "external_provider_failure").expect("workspace");
+    let script = workspace.write_script("provider.sh", "exit 1").expect("write stub provider");
+
+    let response = handle_request(
+        env.paths(),
+        &Mutex::new(AgentRuntime::new()),
+        AgentRequest {
+            payload: AgentRequestPayload::AuthorizeAskpass {
+                name: format!("cmd:{}", script.display()),
+                host: None,
+                destination: None,
+            },
+        },
+        AgentPeerTrust::TrustedCossh.into(),
+    );
+
+    assert!(matches!(response, AgentResponse::Error { code, .. } if code == "secret_provider_error"));
+}
+
+#[test]
+fn served_secrets_and_lock_requests_are_recorded_in_the_audit_log() {
+    let env = TestVaultEnv::new("audit_events");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("shared", "top-secret").expect("store secret");
+
+    let runtime = Mutex::new(AgentRuntime::new());
+    lock_runtime(&runtime).unlock(unlocked.data_key_copy(), UnlockPolicy::new(900, 28_800));
+    let peer = ipc::AgentPeer {
+        trust: AgentPeerTrust::TrustedCossh,
+        pid: Some(std::process::id()),
+    };
+
+    let token = match handle_request(
+        env.paths(),
+        &runtime,
+        AgentRequest {
+            payload: AgentRequestPayload::AuthorizeAskpass {
+                name: "shared".to_string(),
+                host: Some("core-sw".to_string()),
+                destination: None,
+            },
+        },
+        peer,
+    ) {
+        AgentResponse::AskpassAuthorized { token, .. } => token,
+        other => panic!("unexpected authorize response: {other:?}"),
+    };
+    let response = handle_request(
+        env.paths(),
+        &runtime,
+        AgentRequest {
+            payload: AgentRequestPayload::GetSecret { token },
+        },
+        peer,
+    );
+    assert!(matches!(response, AgentResponse::Secret { .. }));
+    handle_request(
+        env.paths(),
+        &runtime,
+        AgentRequest {
+            payload: AgentRequestPayload::Lock,
+        },
+        peer,
+    );
+
+    let verification = audit::verify_audit_log(env.paths()).expect("verify audit log");
+    assert!(verification.is_intact());
+    let events: Vec<_> = verification.records.iter().map(|record| record.event).collect();
+    assert_eq!(
+        events,
+        vec![
+            AuditEventKind::VaultInitialized,
+            AuditEventKind::EntryStored,
+            AuditEventKind::EntryRead,
+            AuditEventKind::Locked
+        ]
+    );
+    let read = &verification.records[2];
+    assert_eq!(read.entry.as_deref(), Some("shared"));
+    assert_eq!(read.host.as_deref(), Some("core-sw"));
+    assert_eq!(read.process.as_ref().map(|process| process.pid), Some(std::process::id()));
+}
+
+#[test]
+fn secrets_are_not_released_when_the_audit_log_cannot_be_written() {
+    let env = TestVaultEnv::new("audit_refuse");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("shared", "top-secret").expect("store secret");
+    let runtime = Mutex::new(AgentRuntime::new());
+    lock_runtime(&runtime).unlock(unlocked.data_key_copy(), UnlockPolicy::new(900, 28_800));
+
+    let token = match authorize_for_host(&env, &runtime, "shared", "core-sw") {
+        AgentResponse::AskpassAuthorized { token, .. } => token,
+        other => panic!("unexpected authorize response: {other:?}"),
+    };
+    let audit_log = audit::audit_log_path(env.paths());
+    std::fs::remove_file(&audit_log).expect("remove audit log");
+    std::fs::create_dir(&audit_log).expect("block audit log path");
+
+    let response = handle_request(
+        env.paths(),
+        &runtime,
+        AgentRequest {
+            payload: AgentRequestPayload::GetSecret { token },
+        },
+        AgentPeerTrust::TrustedCossh.into(),
+    );
+
+    assert!(
+        matches!(response, AgentResponse::Error { ref code, .. } if code == "audit_failed"),
+        "{response:?}"
+    );
+}
+
+fn authorize_for_host(env: &TestVaultEnv, runtime: &Mutex<AgentRuntime>, name: &str, host: &str) -> AgentResponse {
+    handle_request(
+        env.paths(),
+        runtime,
+        AgentRequest {
+            payload: AgentRequestPayload::AuthorizeAskpass {
+                name: name.to_string(),
+                host: Some(host.to_string()),
+                destination: None,
+            },
+        },
+        AgentPeerTrust::TrustedCossh.into(),
+    )
+}
+
+#[test]
+fn entry_policy_gates_askpass_release_and_audits_denials() {
+    let env = TestVaultEnv::new("entry_policy");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("shared", "top-secret").expect("store secret");
+    unlocked
+        .set_entry_policy(
+            "shared",
+            EntryPolicy {
+                confirm: true,
+                allowed_hosts: vec!["prod-*".to_string()],
+                max_uses: Some(1),
+            },
+        )
+        .expect("set policy");
+
+    let runtime = Mutex::new(AgentRuntime::new());
+    let unlock = handle_request(
+        env.paths(),
+        &runtime,
+        AgentRequest {
+            payload: AgentRequestPayload::Unlock {
+                master_password: sensitive_string("master-pass"),
+                policy: UnlockPolicy::new(900, 28_800),
+            },
+        },
+        AgentPeerTrust::TrustedCossh.into(),
+    );
+    assert!(matches!(unlock, AgentResponse::Success { .. }));
+
+    let confirm_for_host = |host: &str, answer: Option<bool>| {
+        authorize_vault_askpass(env.paths(), &runtime, "shared", Some(host), None, AgentPeerTrust::TrustedCossh.into(), |_| {
+            answer
+        })
+    };
+
+    let denied = confirm_for_host("lab", Some(true));
+    assert!(matches!(denied, AgentResponse::Error { code, .. } if code == "host_not_allowed"));
+
+    let declined = confirm_for_host("prod-web", Some(false));
+    assert!(matches!(declined, AgentResponse::Error { code, .. } if code == "confirmation_declined"));
+
+    let unavailable = confirm_for_host("prod-web", None);
+    assert!(matches!(unavailable, AgentResponse::Error { code, .. } if code == "confirmation_unavailable"));
+
+    let released = confirm_for_host("prod-web", Some(true));
+    assert!(matches!(released, AgentResponse::AskpassAuthorized { .. }));
+
+    let exhausted = confirm_for_host("prod-web", Some(true));
+    assert!(matches!(exhausted, AgentResponse::Error { code, .. } if code == "usage_limit_reached"));
+
+    let verification = audit::verify_audit_log(env.paths()).expect("verify audit log");
+    let denials: Vec<_> = verification
+        .records
+        .iter()
+        .filter(|record| record.event == AuditEventKind::EntryDenied)
+        .map(|record| (record.host.as_deref(), record.detail.as_deref()))
+        .collect();
+    assert_eq!(
+        denials,
+        vec![
+            (Some("lab"), Some("host_not_allowed")),
+            (Some("prod-web"), Some("confirmation_declined")),
+            (Some("prod-web"), Some("confirmation_unavailable")),
+            (Some("prod-web"), Some("usage_limit_reached")),
+        ]
+    );
+}
+
+fn unlock_with_triggers(env: &TestVaultEnv, runtime: &Mutex<AgentRuntime>, lock_triggers: LockTriggers) {
+    let unlock = handle_request(
+        env.paths(),
+        runtime,
+        AgentRequest {
+            payload: AgentRequestPayload::Unlock {
+                master_password: sensitive_string("master-pass"),
+                policy: UnlockPolicy::new(900, 28_800).with_lock_triggers(lock_triggers),
+            },
+        },
+        AgentPeerTrust::TrustedCossh.into(),
+    );
+    assert!(matches!(unlock, AgentResponse::Success { .. }));
+}
+
+fn tui_request(env: &TestVaultEnv, runtime: &Mutex<AgentRuntime>, payload: AgentRequestPayload, pid: u32) -> AgentResponse {
+    handle_request(
+        env.paths(),
+        runtime,
+        AgentRequest { payload },
+        AgentPeer {
+            trust: AgentPeerTrust::TrustedCossh,
+            pid: Some(pid),
+        },
+    )
+}
+
+fn last_locked_detail(env: &TestVaultEnv) -> Option<String> {
+    let verification = audit::verify_audit_log(env.paths()).expect("verify audit log");
+    verification
+        .records
+        .iter()
+        .rev()
+        .find(|record| record.event == AuditEventKind::Locked)
+        .and_then(|record| record.detail.clone())
+}
+
+#[test]
+fn detaching_the_last_session_manager_locks_when_tui_exit_is_enabled() {
+    let env = TestVaultEnv::new("tui_exit_lock");
+    env.init("master-pass");
+    let runtime = Mutex::new(AgentRuntime::new());
+    unlock_with_triggers(
+        &env,
+        &runtime,
+        LockTriggers {
+            tui_exit: true,
+            ..LockTriggers::default()
+        },
+    );
+
+    assert!(matches!(
+        tui_request(&env, &runtime, AgentRequestPayload::AttachTui, 100),
+        AgentResponse::Status { .. }
+    ));
+    tui_request(&env, &runtime, AgentRequestPayload::AttachTui, 200);
+
+    tui_request(&env, &runtime, AgentRequestPayload::DetachTui, 100);
+    assert!(lock_runtime(&runtime).is_unlocked(), "another session manager is still attached");
+
+    tui_request(&env, &runtime, AgentRequestPayload::DetachTui, 200);
+    assert!(!lock_runtime(&runtime).is_unlocked());
+    assert_eq!(
+        ipc::read_vault_status_event(env.paths()).expect("read locked event").kind,
+        VaultStatusEventKind::Locked
+    );
+    assert_eq!(last_locked_detail(&env).as_deref(), Some("last session manager exited"));
+
+    let untrusted = handle_request(
+        env.paths(),
+        &runtime,
+        AgentRequest {
+            payload: AgentRequestPayload::AttachTui,
+        },
+        AgentPeerTrust::Untrusted.into(),
+    );
+    assert!(matches!(untrusted, AgentResponse::Error { code, .. } if code == "unauthorized_client"));
+}
+
+#[test]
+fn detaching_session_managers_keeps_the_vault_unlocked_by_default() {
+    let env = TestVaultEnv::new("tui_exit_default");
+    env.init("master-pass");
+    let runtime = Mutex::new(AgentRuntime::new());
+    unlock_with_triggers(&env, &runtime, LockTriggers::default());
+
+    tui_request(&env, &runtime, AgentRequestPayload::AttachTui, 100);
+    tui_request(&env, &runtime, AgentRequestPayload::DetachTui, 100);
+    assert!(lock_runtime(&runtime).is_unlocked());
+}
+
+#[test]
+fn repeated_failed_authorizations_lock_the_vault() {
+    let env = TestVaultEnv::new("failed_authorizations");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("shared", "top-secret").expect("store secret");
+    unlocked
+        .set_entry_policy(
+            "shared",
+            EntryPolicy {
+                allowed_hosts: vec!["prod-*".to_string()],
+                ..EntryPolicy::default()
+            },
+        )
+        .expect("set policy");
+    let runtime = Mutex::new(AgentRuntime::new());
+    unlock_with_triggers(
+        &env,
+        &runtime,
+        LockTriggers {
+            max_failed_authorizations: 3,
+            ..LockTriggers::default()
+        },
+    );
+    let bad_token = || {
+        handle_request(
+            env.paths(),
+            &runtime,
+            AgentRequest {
+                payload: AgentRequestPayload::GetSecret {
+                    token: sensitive_string("not-a-token"),
+                },
+            },
+            AgentPeerTrust::TrustedCossh.into(),
+        )
+    };
+
+    // Entry policy refusals are not failed authorizations.
+    for _ in 0..4 {
+        let denied = authorize_for_host(&env, &runtime, "shared", "lab");
+        assert!(matches!(denied, AgentResponse::Error { code, .. } if code == "host_not_allowed"));
+    }
+    for _ in 0..2 {
+        assert!(matches!(bad_token(), AgentResponse::Error { code, .. } if code == "invalid_or_expired_askpass_token"));
+    }
+    // A successful authorization starts the count again.
+    let released = authorize_for_host(&env, &runtime, "shared", "prod-web");
+    assert!(matches!(released, AgentResponse::AskpassAuthorized { .. }));
+    for _ in 0..2 {
+        bad_token();
+    }
+    assert!(lock_runtime(&runtime).is_unlocked());
+
+    assert!(matches!(bad_token(), AgentResponse::Error { code, .. } if code == "invalid_or_expired_askpass_token"));
+    assert!(!lock_runtime(&runtime).is_unlocked());
+    assert_eq!(last_locked_detail(&env).as_deref(), Some("failed authorizations"));
+}
+
+#[test]
+fn refused_forwarded_requests_count_as_failed_authorizations() {
+    let env = TestVaultEnv::new("failed_forwards");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("shared", "top-secret").expect("store secret");
+    unlocked
+        .set_entry_policy(
+            "shared",
+            EntryPolicy {
+                allowed_hosts: vec!["prod-*".to_string()],
+                ..EntryPolicy::default()
+            },
+        )
+        .expect("set policy");
+    let runtime = Mutex::new(AgentRuntime::new());
+    unlock_with_triggers(
+        &env,
+        &runtime,
+        LockTriggers {
+            max_failed_authorizations: 3,
+            ..LockTriggers::default()
+        },
+    );
+    let forwarded = |name: &str| {
+        handle_request(
+            env.paths(),
+            &runtime,
+            AgentRequest {
+                payload: AgentRequestPayload::ForwardedSecret {
+                    name: name.to_string(),
+                    host: "jump01".to_string(),
+                    destination: None,
+                },
+            },
+            AgentPeerTrust::TrustedCossh.into(),
+        )
+    };
+
+    assert!(matches!(forwarded("shared"), AgentResponse::Error { code, .. } if code == "host_not_allowed"));
+    assert!(matches!(forwarded("missing"), AgentResponse::Error { code, .. } if code == "entry_not_found"));
+    assert!(lock_runtime(&runtime).is_unlocked());
+
+    forwarded("shared");
+    assert!(!lock_runtime(&runtime).is_unlocked());
+    assert_eq!(last_locked_detail(&env).as_deref(), Some("failed authorizations"));
+}
+
+#[test]
+fn server_answers_many_simultaneous_askpass_clients_while_one_client_stalls() {
+    let env = TestVaultEnv::new("concurrent_askpass");
+    let unlocked = env.init_and_unlock("master-pass");
+    for index in 0..4 {
+        unlocked
+            .store_secret(&format!("shared{index}"), &format!("top-secret-{index}"))
+            .expect("store secret");
+    }
+
+    let listener = match ipc::bind_listener(env.paths()).expect("bind agent endpoint") {
+        ipc::ListenerBindResult::Bound(listener) => listener,
+        ipc::ListenerBindResult::AlreadyRunning => panic!("test agent endpoint already in use"),
+    };
+    let paths = env.paths().clone();
+    let server = thread::spawn(move || serve(paths, listener, None));
+
+    let unlock = ipc::send_request(
+        env.paths(),
+        &AgentRequestPayload::Unlock {
+            master_password: sensitive_string("master-pass"),
+            policy: UnlockPolicy::new(900, 28_800),
+        },
+    )
+    .expect("unlock request");
+    assert!(matches!(unlock, AgentResponse::Success { .. }), "unexpected unlock response: {unlock:?}");
+
+    // A client that connects but never sends a request must not hold up the rest.
+    let _stalled = ipc::connect(env.paths()).expect("stalled client connects");
+
+    let barrier = Arc::new(Barrier::new(CONCURRENT_ASKPASS_CLIENTS));
+    let clients: Vec<_> = (0..CONCURRENT_ASKPASS_CLIENTS)
+        .map(|client| {
+            let paths = env.paths().clone();
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                let name = format!("shared{}", client % 4);
+                barrier.wait();
+                let token = match ipc::send_request(
+                    &paths,
+                    &AgentRequestPayload::AuthorizeAskpass {
+                        name: name.clone(),
+                        host: None,
+                        destination: None,
+                    },
+                )
+                .expect("authorize request")
+                {
+                    AgentResponse::AskpassAuthorized { token, .. } => token,
+                    other => panic!("unexpected authorize response: {other:?}"),
+                };
+                match ipc::send_request(&paths, &AgentRequestPayload::GetSecret { token }).expect("get secret request") {
+                    AgentResponse::Secret { name: served, secret, .. } => (client, served, secret.expose_secret().to_string()),
+                    other => panic!("unexpected secret response: {other:?}"),
+                }
+            })
+        })
+        .collect();
+
+    for client in clients {
+        let (client, served, secret) = client.join().expect("client thread");
+        assert_eq!(served, format!("shared{}", client % 4));
+        assert_eq!(secret, format!("top-secret-{}", client % 4));
+    }
+
+    let lock = ipc::send_request(env.paths(), &AgentRequestPayload::Lock).expect("lock request");
+    assert!(matches!(lock, AgentResponse::Success { .. }), "unexpected lock response: {lock:?}");
+    server.join().expect("server thread").expect("server exits cleanly after lock");
+}
+
+#[test]
+fn server_answers_other_clients_while_an_external_provider_hangs() {
+    let env = TestVaultEnv::new("hung_provider");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("shared", "top-secret").expect("store secret");
+    let workspace = TestWorkspace::new("auth_agent", "hung_provider").expect("workspace");
+    let started = workspace.join("started");
+    let release = workspace.join("release");
+    let script = workspace
+        .write_script(
+            "provider.sh",
+            &format!(
+                "touch '{}'\nwhile [ ! -f '{}' ]; do sleep 0.05; done\necho slow-secret",
+                started.display(),
+                release.display()
+            ),
+        )
+        .expect("write stub provider");
+
+    let listener = match ipc::bind_listener(env.paths()).expect("bind agent endpoint") {
+        ipc::ListenerBindResult::Bound(listener) => listener,
+        ipc::ListenerBindResult::AlreadyRunning => panic!("test agent endpoint already in use"),
+    };
+    let paths = env.paths().clone();
+    let server = thread::spawn(move || serve(paths, listener, None));
+
+    let unlock = ipc::send_request(
+        env.paths(),
+        &AgentRequestPayload::Unlock {
+            master_password: sensitive_string("master-pass"),
+            policy: UnlockPolicy::new(900, 28_800),
+        },
+    )
+    .expect("unlock request");
+    assert!(matches!(unlock, AgentResponse::Success { .. }), "unexpected unlock response: {unlock:?}");
+
+    let slow_paths = env.paths().clone();
+    let reference = format!("cmd:{}", script.display());
+    let slow_client = thread::spawn(move || {
+        ipc::send_request(
+            &slow_paths,
+            &AgentRequestPayload::AuthorizeAskpass {
+                name: reference,
+                host: None,
+                destination: None,
+            },
+        )
+        .expect("slow authorize request")
+    });
+    while !started.exists() {
+        thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    // The provider is still blocked; every other client must be answered meanwhile.
+    let clients: Vec<_> = (0..CONCURRENT_ASKPASS_CLIENTS)
+        .map(|_| {
+            let paths = env.paths().clone();
+            thread::spawn(move || {
+                let token = match ipc::send_request(
+                    &paths,
+                    &AgentRequestPayload::AuthorizeAskpass {
+                        name: "shared".to_string(),
+                        host: None,
+                        destination: None,
+                    },
+                )
+                .expect("authorize request")
+                {
+                    AgentResponse::AskpassAuthorized { token, .. } => token,
+                    other => panic!("unexpected authorize response: {other:?}"),
+                };
+                match ipc::send_request(&paths, &AgentRequestPayload::GetSecret { token }).expect("get secret request") {
+                    AgentResponse::Secret { secret, .. } => secret.expose_secret().to_string(),
+                    other => panic!("unexpected secret response: {other:?}"),
+                }
+            })
+        })
+        .collect();
+    for client in clients {
+        assert_eq!(client.join().expect("client thread"), "top-secret");
+    }
+    let status = ipc::send_request(env.paths(), &AgentRequestPayload::Status).expect("status request");
+    assert!(matches!(status, AgentResponse::Status { .. }));
+    assert!(!slow_client.is_finished(), "provider should still be blocked");
+
+    std::fs::write(&release, "").expect("release provider");
+    let slow = slow_client.join().expect("slow client thread");
+    assert!(matches!(slow, AgentResponse::AskpassAuthorized { .. }), "unexpected slow response: {slow:?}");
+
+    let lock = ipc::send_request(env.paths(), &AgentRequestPayload::Lock).expect("lock request");
+    assert!(matches!(lock, AgentResponse::Success { .. }), "unexpected lock response: {lock:?}");
+    server.join().expect("server thread").expect("server exits cleanly after lock");
+}
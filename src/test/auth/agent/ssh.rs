@@ -0,0 +1,286 @@
+use super::*;
+use crate::auth::audit::{self, AuditEventKind};
+use crate::auth::ipc::UnlockPolicy;
+use crate::test::support::auth::{TEST_ECDSA_KEY, TEST_ED25519_KEY, TestVaultEnv};
+use crate::test::support::fs::TestWorkspace;
+use rsa::signature::Verifier;
+use ssh_key::public::KeyData;
+use std::time::Instant;
+
+fn unlocked_runtime_without_keys(env: &TestVaultEnv) -> Mutex<AgentRuntime> {
+    let unlocked = env.init_and_unlock("master-pass");
+    let mut runtime = AgentRuntime::new();
+    runtime.unlock(unlocked.data_key_copy(), UnlockPolicy::new(900, 3600));
+    Mutex::new(runtime)
+}
+
+fn unlocked_runtime(env: &TestVaultEnv) -> Mutex<AgentRuntime> {
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked
+        .store_ssh_key("deploy", TEST_ED25519_KEY, SshKeyConstraints::default())
+        .expect("store ed25519 key");
+    unlocked
+        .store_ssh_key(
+            "backup",
+            TEST_ECDSA_KEY,
+            SshKeyConstraints {
+                lifetime_seconds: Some(60),
+                ..SshKeyConstraints::default()
+            },
+        )
+        .expect("store ecdsa key");
+    let mut runtime = AgentRuntime::new();
+    runtime.unlock(unlocked.data_key_copy(), UnlockPolicy::new(900, 3600));
+    Mutex::new(runtime)
+}
+
+fn listed_identities(response: &[u8]) -> Vec<(Vec<u8>, String)> {
+    let (&kind, mut body) = response.split_first().expect("response type");
+    assert_eq!(kind, SSH_AGENT_IDENTITIES_ANSWER);
+    let count = u32::decode(&mut body).expect("identity count");
+    (0..count)
+        .map(|_| (Vec::<u8>::decode(&mut body).expect("key blob"), String::decode(&mut body).expect("comment")))
+        .collect()
+}
+
+fn sign_request(key_pem: &str, data: &[u8]) -> (KeyData, Vec<u8>) {
+    let public_key = PrivateKey::from_openssh(key_pem).expect("parse key").public_key().clone();
+    let mut message = vec![SSH_AGENTC_SIGN_REQUEST];
+    public_key.to_bytes().expect("key blob").encode(&mut message).expect("encode blob");
+    data.encode(&mut message).expect("encode data");
+    0u32.encode(&mut message).expect("encode flags");
+    (public_key.key_data().clone(), message)
+}
+
+fn signature_from(response: &[u8]) -> Signature {
+    let (&kind, mut body) = response.split_first().expect("response type");
+    assert_eq!(kind, SSH_AGENT_SIGN_RESPONSE, "unexpected response {response:?}");
+    let encoded = Vec::<u8>::decode(&mut body).expect("signature blob");
+    Signature::decode(&mut encoded.as_slice()).expect("decode signature")
+}
+
+#[test]
+fn request_identities_lists_unlocked_keys_and_hides_locked_or_expired_ones() {
+    let env = TestVaultEnv::new("ssh_agent_identities");
+    let runtime = unlocked_runtime(&env);
+    let request = [SSH_AGENTC_REQUEST_IDENTITIES];
+
+    let mut names: Vec<String> = listed_identities(&handle_ssh_agent_message(env.paths(), &runtime, &request, None))
+        .into_iter()
+        .map(|(_, comment)| comment)
+        .collect();
+    names.sort();
+    assert_eq!(names, ["backup", "deploy"]);
+
+    lock_runtime(&runtime).unlocked_at = Some(Instant::now() - Duration::from_secs(61));
+    let mut names: Vec<String> = listed_identities(&handle_ssh_agent_message(env.paths(), &runtime, &request, None))
+        .into_iter()
+        .map(|(_, comment)| comment)
+        .collect();
+    names.sort();
+    assert_eq!(names, ["backup", "deploy"], "key lifetimes count from the add, not the unlock");
+
+    lock_runtime(&runtime).lock();
+    assert!(listed_identities(&handle_ssh_agent_message(env.paths(), &runtime, &request, None)).is_empty());
+}
+
+#[test]
+fn key_lifetime_counts_from_the_add_time_and_falls_back_to_the_unlock_for_older_entries() {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).expect("clock").as_secs();
+    let constraints = |added_at: Option<u64>| SshKeyConstraints {
+        lifetime_seconds: Some(60),
+        added_at,
+        ..SshKeyConstraints::default()
+    };
+    let just_unlocked = Some(Instant::now());
+    let unlocked_long_ago = Some(Instant::now() - Duration::from_secs(61));
+
+    assert!(!lifetime_elapsed(unlocked_long_ago, constraints(Some(now - 30))));
+    assert!(lifetime_elapsed(just_unlocked, constraints(Some(now - 61))));
+    assert!(!lifetime_elapsed(just_unlocked, constraints(None)));
+    assert!(lifetime_elapsed(unlocked_long_ago, constraints(None)));
+    assert!(!lifetime_elapsed(unlocked_long_ago, SshKeyConstraints::default()));
+}
+
+#[test]
+fn sign_request_signs_with_vault_keys_and_records_an_audit_event() {
+    let env = TestVaultEnv::new("ssh_agent_sign");
+    let runtime = unlocked_runtime(&env);
+
+    for key_pem in [TEST_ED25519_KEY, TEST_ECDSA_KEY] {
+        let (public_key, request) = sign_request(key_pem, b"session-id");
+        let signature = signature_from(&handle_ssh_agent_message(env.paths(), &runtime, &request, Some(4242)));
+        public_key.verify(b"session-id", &signature).expect("signature verifies");
+    }
+
+    let verification = audit::verify_audit_log(env.paths()).expect("verify audit log");
+    let signed = verification
+        .records
+        .iter()
+        .filter(|record| record.event == AuditEventKind::EntryRead && record.detail.as_deref() == Some("ssh agent signature"))
+        .count();
+    assert_eq!(signed, 2);
+
+    lock_runtime(&runtime).lock();
+    let (_, request) = sign_request(TEST_ED25519_KEY, b"session-id");
+    assert_eq!(handle_ssh_agent_message(env.paths(), &runtime, &request, None), [SSH_AGENT_FAILURE]);
+}
+
+#[test]
+fn confirm_constraint_requires_askpass_approval() {
+    let env = TestVaultEnv::new("ssh_agent_confirm");
+    let workspace = TestWorkspace::new("auth_agent", "ssh_confirm").expect("workspace");
+    let deny = workspace.write_script("deny.sh", "exit 1").expect("deny script");
+    let allow = workspace
+        .write_script("allow.sh", "[ \"$SSH_ASKPASS_PROMPT\" = confirm ]")
+        .expect("allow script");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked
+        .store_ssh_key(
+            "deploy",
+            TEST_ED25519_KEY,
+            SshKeyConstraints {
+                confirm: true,
+                ..SshKeyConstraints::default()
+            },
+        )
+        .expect("store key");
+    let mut runtime = AgentRuntime::new();
+    runtime.unlock(unlocked.data_key_copy(), UnlockPolicy::new(900, 3600));
+    let runtime = Mutex::new(runtime);
+    let (public_key, request) = sign_request(TEST_ED25519_KEY, b"payload");
+
+    let denied = temp_env::with_var("SSH_ASKPASS", Some(deny.as_os_str()), || {
+        handle_ssh_agent_message(env.paths(), &runtime, &request, None)
+    });
+    assert_eq!(denied, [SSH_AGENT_FAILURE]);
+
+    let allowed = temp_env::with_var("SSH_ASKPASS", Some(allow.as_os_str()), || {
+        handle_ssh_agent_message(env.paths(), &runtime, &request, None)
+    });
+    public_key.verify(b"payload", &signature_from(&allowed)).expect("signature verifies");
+}
+
+fn add_request(key_pem: &str, comment: &str, constraints: &[u8]) -> Vec<u8> {
+    let private_key = PrivateKey::from_openssh(key_pem).expect("parse key");
+    let kind = if constraints.is_empty() {
+        SSH_AGENTC_ADD_IDENTITY
+    } else {
+        SSH_AGENTC_ADD_ID_CONSTRAINED
+    };
+    let mut request = vec![kind];
+    private_key.key_data().encode(&mut request).expect("encode key");
+    comment.encode(&mut request).expect("encode comment");
+    request.extend_from_slice(constraints);
+    request
+}
+
+fn lifetime_constraint(seconds: u32) -> Vec<u8> {
+    let mut constraint = vec![SSH_AGENT_CONSTRAIN_LIFETIME];
+    seconds.encode(&mut constraint).expect("encode lifetime");
+    constraint
+}
+
+#[test]
+fn add_identity_holds_keys_in_memory_until_the_vault_locks() {
+    let env = TestVaultEnv::new("ssh_agent_add");
+    let runtime = unlocked_runtime_without_keys(&env);
+    let data_key = lock_runtime(&runtime).data_key.expect("unlocked");
+    let mut constraints = lifetime_constraint(300);
+    constraints.push(SSH_AGENT_CONSTRAIN_CONFIRM);
+
+    let request = add_request(TEST_ECDSA_KEY, "ops key", &constraints);
+    assert_eq!(handle_ssh_agent_message(env.paths(), &runtime, &request, None), [SSH_AGENT_SUCCESS]);
+    assert!(
+        vault::list_entries_with_paths(env.paths()).expect("list entries").is_empty(),
+        "added keys are never written to the vault"
+    );
+    {
+        let runtime = lock_runtime(&runtime);
+        let [added] = runtime.session_ssh_keys.as_slice() else {
+            panic!("expected one session key");
+        };
+        assert_eq!(added.name, "ops_key");
+        assert!(added.constraints.confirm);
+        assert_eq!(added.constraints.lifetime_seconds, Some(300));
+        assert!(added.constraints.added_at.is_some(), "the add time starts the lifetime");
+    }
+
+    let request = add_request(TEST_ED25519_KEY, "deploy", &[]);
+    assert_eq!(handle_ssh_agent_message(env.paths(), &runtime, &request, None), [SSH_AGENT_SUCCESS]);
+    let (public_key, request) = sign_request(TEST_ED25519_KEY, b"payload");
+    let signature = signature_from(&handle_ssh_agent_message(env.paths(), &runtime, &request, None));
+    public_key.verify(b"payload", &signature).expect("signature verifies");
+    let mut names: Vec<String> = listed_identities(&handle_ssh_agent_message(env.paths(), &runtime, &[SSH_AGENTC_REQUEST_IDENTITIES], None))
+        .into_iter()
+        .map(|(_, comment)| comment)
+        .collect();
+    names.sort();
+    assert_eq!(names, ["deploy", "ops_key"]);
+
+    lock_runtime(&runtime).lock();
+    assert_eq!(handle_ssh_agent_message(env.paths(), &runtime, &request, None), [SSH_AGENT_FAILURE]);
+    lock_runtime(&runtime).unlock(data_key, UnlockPolicy::new(900, 3600));
+    assert!(listed_identities(&handle_ssh_agent_message(env.paths(), &runtime, &[SSH_AGENTC_REQUEST_IDENTITIES], None)).is_empty());
+}
+
+#[test]
+fn added_keys_are_dropped_once_their_lifetime_passes_and_replaced_when_added_again() {
+    let env = TestVaultEnv::new("ssh_agent_add_lifetime");
+    let runtime = unlocked_runtime_without_keys(&env);
+    let identities = |runtime: &Mutex<AgentRuntime>| {
+        let mut names: Vec<String> = listed_identities(&handle_ssh_agent_message(env.paths(), runtime, &[SSH_AGENTC_REQUEST_IDENTITIES], None))
+            .into_iter()
+            .map(|(_, comment)| comment)
+            .collect();
+        names.sort();
+        names
+    };
+    for _ in 0..2 {
+        let request = add_request(TEST_ED25519_KEY, "temp@ci", &lifetime_constraint(60));
+        assert_eq!(handle_ssh_agent_message(env.paths(), &runtime, &request, None), [SSH_AGENT_SUCCESS]);
+    }
+    assert_eq!(lock_runtime(&runtime).session_ssh_keys.len(), 1, "re-adding a key replaces it");
+    assert_eq!(identities(&runtime), ["temp_ci"]);
+
+    let expired_at = SystemTime::now().duration_since(UNIX_EPOCH).expect("clock").as_secs() - 61;
+    lock_runtime(&runtime).session_ssh_keys[0].constraints.added_at = Some(expired_at);
+    assert!(identities(&runtime).is_empty());
+    assert!(lock_runtime(&runtime).session_ssh_keys.is_empty(), "expired keys are removed from memory");
+}
+
+#[test]
+fn request_identities_caches_public_keys_until_an_entry_changes() {
+    let env = TestVaultEnv::new("ssh_agent_public_key_cache");
+    let runtime = unlocked_runtime(&env);
+    let request = [SSH_AGENTC_REQUEST_IDENTITIES];
+    let public_blob = |key_pem: &str| PrivateKey::from_openssh(key_pem).expect("parse key").public_key().to_bytes().expect("key blob");
+    let deploy_blob = |runtime: &Mutex<AgentRuntime>| {
+        listed_identities(&handle_ssh_agent_message(env.paths(), runtime, &request, None))
+            .into_iter()
+            .find_map(|(blob, comment)| (comment == "deploy").then_some(blob))
+            .expect("deploy listed")
+    };
+
+    assert_eq!(deploy_blob(&runtime), public_blob(TEST_ED25519_KEY));
+    assert_eq!(lock_runtime(&runtime).ssh_public_keys.len(), 2);
+
+    let unlocked = lock_runtime(&runtime).unlocked_vault(env.paths()).expect("unlocked vault");
+    unlocked
+        .store_ssh_key("deploy", TEST_ECDSA_KEY, SshKeyConstraints::default())
+        .expect("replace key");
+    assert_eq!(deploy_blob(&runtime), public_blob(TEST_ECDSA_KEY), "a replaced entry is decrypted again");
+
+    lock_runtime(&runtime).lock();
+    assert!(lock_runtime(&runtime).ssh_public_keys.is_empty());
+}
+
+#[test]
+fn unsupported_and_truncated_requests_fail_closed() {
+    let env = TestVaultEnv::new("ssh_agent_unsupported");
+    let runtime = unlocked_runtime(&env);
+
+    for request in [vec![], vec![SSH_AGENTC_SIGN_REQUEST, 0, 0], vec![27u8], vec![18u8]] {
+        assert_eq!(handle_ssh_agent_message(env.paths(), &runtime, &request, None), [SSH_AGENT_FAILURE]);
+    }
+}
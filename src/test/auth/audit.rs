@@ -0,0 +1,145 @@
+use super::*;
+use crate::test::support::auth::TestVaultEnv;
+
+fn append_reads(env: &TestVaultEnv, count: usize) {
+    for index in 0..count {
+        append_event(
+            env.paths(),
+            AuditEvent::new(AuditEventKind::EntryRead)
+                .with_entry(format!("entry-{index}"))
+                .with_host(Some("core-sw"))
+                .with_process(Some(AuditProcess::from_pid(4242)))
+                .with_detail("x".repeat(200)),
+        )
+        .expect("append audit event");
+    }
+}
+
+#[test]
+fn append_event_chains_records_across_multiple_tail_chunks() {
+    let env = TestVaultEnv::new("audit_chain");
+    append_reads(&env, 40);
+
+    let verification = verify_audit_log(env.paths()).expect("verify audit log");
+
+    assert!(verification.is_intact(), "unexpected break: {:?}", verification.chain_break);
+    assert_eq!(verification.records.len(), 40);
+    assert_eq!(verification.records[0].prev_hash, AUDIT_GENESIS_HASH);
+    for pair in verification.records.windows(2) {
+        assert_eq!(pair[1].seq, pair[0].seq + 1);
+        assert_eq!(pair[1].prev_hash, pair[0].hash);
+    }
+    assert_eq!(verification.records[39].entry.as_deref(), Some("entry-39"));
+}
+
+#[test]
+fn verify_audit_log_reports_first_tampered_line() {
+    type Tamper = fn(&mut Vec<String>);
+    let cases: [(&str, Tamper, usize); 4] = [
+        ("edited entry", |lines| lines[2] = lines[2].replace("entry-2", "entry-9"), 3),
+        ("deleted line", |lines| drop(lines.remove(1)), 2),
+        ("swapped lines", |lines| lines.swap(1, 2), 2),
+        ("garbage line", |lines| lines[3] = "not json".to_string(), 4),
+    ];
+
+    for (label, tamper, expected_line) in cases {
+        let env = TestVaultEnv::new("audit_tamper");
+        append_reads(&env, 5);
+        let path = audit_log_path(env.paths());
+        let mut lines: Vec<String> = fs::read_to_string(&path).expect("read audit log").lines().map(str::to_string).collect();
+        tamper(&mut lines);
+        fs::write(&path, format!("{}\n", lines.join("\n"))).expect("write tampered log");
+
+        let verification = verify_audit_log(env.paths()).expect("verify audit log");
+
+        let chain_break = verification.chain_break.unwrap_or_else(|| panic!("{label}: tampering was not detected"));
+        assert_eq!(chain_break.line, expected_line, "{label}: {}", chain_break.reason);
+        assert_eq!(verification.records.len(), expected_line - 1, "{label}");
+    }
+}
+
+#[test]
+fn verify_audit_log_treats_missing_log_as_empty_chain() {
+    let env = TestVaultEnv::new("audit_missing");
+
+    let verification = verify_audit_log(env.paths()).expect("verify audit log");
+
+    assert!(verification.is_intact());
+    assert!(verification.records.is_empty());
+}
+
+fn keep_first_three_records(env: &TestVaultEnv) {
+    let path = audit_log_path(env.paths());
+    let lines: Vec<String> = fs::read_to_string(&path).expect("read audit log").lines().map(str::to_string).collect();
+    fs::write(&path, format!("{}\n", lines[..3].join("\n"))).expect("truncate log");
+}
+
+#[test]
+fn verify_audit_log_checks_the_log_against_its_head() {
+    type Tamper = fn(&TestVaultEnv);
+    let cases: [(&str, Tamper, usize, usize); 4] = [
+        ("truncated log", keep_first_three_records, 4, 3),
+        (
+            "truncated log with a later append",
+            |env| {
+                keep_first_three_records(env);
+                append_reads(env, 1);
+            },
+            4,
+            3,
+        ),
+        (
+            "rewritten log",
+            |env| {
+                fs::remove_file(audit_log_path(env.paths())).expect("remove log");
+                let head = fs::read(audit_head_path(env.paths())).expect("read head");
+                fs::remove_file(audit_head_path(env.paths())).expect("remove head");
+                append_reads(env, 5);
+                fs::write(audit_head_path(env.paths()), head).expect("restore head");
+            },
+            6,
+            5,
+        ),
+        ("removed head", |env| fs::remove_file(audit_head_path(env.paths())).expect("remove head"), 6, 5),
+    ];
+
+    for (label, tamper, expected_line, expected_records) in cases {
+        let env = TestVaultEnv::new("audit_head");
+        append_reads(&env, 5);
+        tamper(&env);
+
+        let verification = verify_audit_log(env.paths()).expect("verify audit log");
+
+        let chain_break = verification.chain_break.unwrap_or_else(|| panic!("{label}: tampering was not detected"));
+        assert_eq!(chain_break.line, expected_line, "{label}: {}", chain_break.reason);
+        assert_eq!(verification.records.len(), expected_records, "{label}");
+    }
+}
+
+#[test]
+fn a_head_left_one_record_behind_by_a_crash_is_caught_up_by_the_next_append() {
+    let env = TestVaultEnv::new("audit_stale_head");
+    append_reads(&env, 4);
+    let stale_head = fs::read(audit_head_path(env.paths())).expect("read head");
+    append_reads(&env, 1);
+    fs::write(audit_head_path(env.paths()), stale_head).expect("restore stale head");
+
+    let verification = verify_audit_log(env.paths()).expect("verify audit log");
+    assert!(verification.is_intact(), "unexpected break: {:?}", verification.chain_break);
+
+    append_reads(&env, 1);
+    let verification = verify_audit_log(env.paths()).expect("verify audit log");
+    assert!(verification.is_intact(), "unexpected break: {:?}", verification.chain_break);
+    assert_eq!(verification.records.len(), 6);
+    assert_eq!(read_head(env.paths()).expect("read head").map(|head| head.seq), Some(6));
+}
+
+#[test]
+fn record_release_event_reports_an_unwritable_audit_log() {
+    let env = TestVaultEnv::new("audit_unwritable");
+    fs::create_dir_all(audit_log_path(env.paths())).expect("block audit log path");
+
+    let result = record_release_event(env.paths(), AuditEvent::new(AuditEventKind::EntryRead).with_entry("shared"));
+
+    assert!(result.expect_err("release must be refused").contains("audit log could not be written"));
+}
@@ -0,0 +1,141 @@
+use super::*;
+use crate::auth::secret::ExposeSecret;
+use crate::test::support::auth::TestVaultEnv;
+use std::fs::File;
+
+fn issue_for<'a>(report: &'a DoctorReport, file_name: &str) -> &'a DoctorIssue {
+    report
+        .issues
+        .iter()
+        .find(|issue| issue.path.file_name().and_then(|name| name.to_str()) == Some(file_name))
+        .unwrap_or_else(|| panic!("expected an issue for {file_name}: {:?}", report.issues))
+}
+
+#[test]
+fn diagnose_vault_reports_and_repairs_damaged_entries_temp_files_and_permissions() {
+    let env = TestVaultEnv::new("doctor_repairs");
+    let unlocked = env.init_and_unlock("master-pass");
+    let entries_dir = env.paths().entries_dir();
+    for name in ["healthy", "corrupted", "moved", "truncated", "loose"] {
+        unlocked.store_secret(name, &format!("{name}-secret")).expect("store secret");
+    }
+
+    let corrupted_path = entries_dir.join("corrupted.json");
+    let corrupted = fs::read_to_string(&corrupted_path).expect("read entry");
+    let ciphertext = vault::read_entry_file(&corrupted_path).expect("parse entry").ciphertext;
+    let tampered = format!("{}{}", if ciphertext.starts_with('A') { 'B' } else { 'A' }, &ciphertext[1..]);
+    fs::write(&corrupted_path, corrupted.replace(&ciphertext, &tampered)).expect("tamper entry");
+    fs::rename(entries_dir.join("moved.json"), entries_dir.join("renamed.json")).expect("rename entry");
+    fs::write(entries_dir.join("truncated.json"), "{\"version\":1,\"na").expect("truncate entry");
+    fs::set_permissions(entries_dir.join("loose.json"), fs::Permissions::from_mode(0o644)).expect("loosen permissions");
+    let orphan = entries_dir.join(".healthy.json.tmp-1");
+    File::create(&orphan)
+        .and_then(|file| file.set_modified(SystemTime::now() - Duration::from_secs(3_600)))
+        .expect("write orphan temp file");
+    File::create(entries_dir.join(".loose.json.tmp-2")).expect("write in-flight temp file");
+
+    let report = diagnose_vault(&unlocked).expect("diagnose vault");
+
+    assert_eq!(report.entries_checked, 5);
+    assert_eq!(report.healthy_entries, 2);
+    assert_eq!(report.issues.len(), 5, "{:?}", report.issues);
+    assert_eq!(issue_for(&report, "corrupted.json").repair, Some(DoctorRepair::Quarantine));
+    assert_eq!(issue_for(&report, "truncated.json").repair, Some(DoctorRepair::Quarantine));
+    assert_eq!(
+        issue_for(&report, "renamed.json").repair,
+        Some(DoctorRepair::RenameEntry {
+            to: entries_dir.join("moved.json")
+        })
+    );
+    assert_eq!(
+        issue_for(&report, "loose.json").repair,
+        Some(DoctorRepair::RestrictPermissions { mode: PRIVATE_FILE_MODE })
+    );
+    assert_eq!(issue_for(&report, ".healthy.json.tmp-1").repair, Some(DoctorRepair::RemoveTempFile));
+
+    for issue in report.repairable_issues() {
+        apply_repair(env.paths(), issue).expect("apply repair");
+    }
+
+    let report = diagnose_vault(&unlocked).expect("diagnose repaired vault");
+    assert!(report.issues.is_empty(), "{:?}", report.issues);
+    assert_eq!(report.healthy_entries, 3);
+    assert_eq!(unlocked.get_secret("moved").expect("renamed entry").expose_secret(), "moved-secret");
+    assert_eq!(fs::read_dir(quarantine_dir(env.paths())).expect("quarantine dir").count(), 2);
+}
+
+#[test]
+fn diagnose_vault_keeps_the_current_value_when_only_the_history_is_damaged() {
+    let env = TestVaultEnv::new("doctor_history");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("shared", "old-secret").expect("store secret");
+    unlocked.rotate_secret("shared", "new-secret").expect("rotate secret");
+    let entry_path = env.paths().entry_path("shared").expect("entry path");
+    let mut entry = vault::read_entry_file(&entry_path).expect("read entry");
+    entry.history[0].nonce = entry.nonce.clone();
+    vault::write_entry_file(&entry_path, &entry).expect("tamper history");
+
+    let report = diagnose_vault(&unlocked).expect("diagnose vault");
+
+    assert_eq!(report.healthy_entries, 0);
+    assert_eq!(issue_for(&report, "shared.json").repair, Some(DoctorRepair::DropHistory));
+    for issue in report.repairable_issues() {
+        apply_repair(env.paths(), issue).expect("apply repair");
+    }
+    assert_eq!(unlocked.get_secret("shared").expect("get secret").expose_secret(), "new-secret");
+    assert!(unlocked.secret_history("shared").expect("read history").is_empty());
+    assert_eq!(fs::read_dir(quarantine_dir(env.paths())).expect("quarantine dir").count(), 1);
+    assert!(diagnose_vault(&unlocked).expect("diagnose repaired vault").issues.is_empty());
+}
+
+#[test]
+fn diagnose_vault_removes_dead_rekey_directories_and_restores_an_interrupted_swap() {
+    let env = TestVaultEnv::new("doctor_rekey");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("alpha", "alpha-secret").expect("store secret");
+    let vault_dir = env.paths().vault_dir();
+    let staging_dir = vault_dir.join(format!("{REKEY_STAGING_PREFIX}1"));
+    let finished_dir = vault_dir.join(format!("{REKEY_BACKUP_PREFIX}1"));
+    fs::create_dir_all(&staging_dir).expect("create staging dir");
+    fs::create_dir_all(finished_dir.join("entries")).expect("create finished backup");
+    let retired = vault::UnlockedVault::from_data_key(env.paths().clone(), [9u8; vault::DATA_KEY_LEN]);
+    let entry = vault::read_entry_file(&env.paths().entry_path("alpha").expect("entry path")).expect("read entry");
+    let retired_entry = unlocked.reencrypt_entry(&entry, &retired).expect("re-encrypt entry");
+    vault::write_entry_file(&finished_dir.join("entries/alpha.json"), &retired_entry).expect("write retired entry");
+
+    let report = diagnose_vault(&unlocked).expect("diagnose vault");
+    assert_eq!(report.healthy_entries, 1);
+    assert_eq!(
+        issue_for(&report, &format!("{REKEY_STAGING_PREFIX}1")).repair,
+        Some(DoctorRepair::RemoveRekeyDirectory)
+    );
+    assert_eq!(
+        issue_for(&report, &format!("{REKEY_BACKUP_PREFIX}1")).repair,
+        Some(DoctorRepair::RemoveRekeyDirectory)
+    );
+    for issue in report.repairable_issues() {
+        apply_repair(env.paths(), issue).expect("apply repair");
+    }
+    assert!(!staging_dir.exists() && !finished_dir.exists());
+
+    // A swap cut short after moving the entries aside but before saving the new key.
+    let interrupted_dir = vault_dir.join(format!("{REKEY_BACKUP_PREFIX}2"));
+    fs::create_dir_all(&interrupted_dir).expect("create interrupted backup");
+    fs::rename(env.paths().entries_dir(), interrupted_dir.join("entries")).expect("move entries aside");
+    fs::create_dir_all(env.paths().entries_dir()).expect("create swapped entries");
+    vault::write_entry_file(&env.paths().entry_path("alpha").expect("entry path"), &retired_entry).expect("write swapped entry");
+
+    let report = diagnose_vault(&unlocked).expect("diagnose vault");
+    assert_eq!(report.entries_checked, 0, "entries about to be replaced are not checked");
+    assert_eq!(
+        issue_for(&report, &format!("{REKEY_BACKUP_PREFIX}2")).repair,
+        Some(DoctorRepair::RestoreRekeyBackup)
+    );
+    for issue in report.repairable_issues() {
+        apply_repair(env.paths(), issue).expect("apply repair");
+    }
+    assert!(!interrupted_dir.exists());
+    assert_eq!(unlocked.get_secret("alpha").expect("get restored secret").expose_secret(), "alpha-secret");
+    assert_eq!(fs::read_dir(quarantine_dir(env.paths())).expect("quarantine dir").count(), 1);
+    assert!(diagnose_vault(&unlocked).expect("diagnose repaired vault").issues.is_empty());
+}
@@ -0,0 +1,144 @@
+use super::*;
+use crate::auth::secret::ExposeSecret;
+
+fn generate(options: GeneratorOptions) -> String {
+    generate_password(&options).expect("generate password").expose_secret().to_string()
+}
+
+#[test]
+fn generate_password_respects_length_classes_and_excluded_characters() {
+    let cases = [
+        ("default", GeneratorOptions::default(), format!("{LOWERCASE}{UPPERCASE}{DIGITS}{SYMBOLS}")),
+        (
+            "device safe",
+            GeneratorOptions {
+                length: 12,
+                symbols: SymbolSet::DeviceSafe,
+                ..GeneratorOptions::default()
+            },
+            format!("{LOWERCASE}{UPPERCASE}{DIGITS}{DEVICE_SAFE_SYMBOLS}"),
+        ),
+        (
+            "digits only",
+            GeneratorOptions {
+                length: 6,
+                lowercase: false,
+                uppercase: false,
+                symbols: SymbolSet::None,
+                ..GeneratorOptions::default()
+            },
+            DIGITS.to_string(),
+        ),
+        (
+            "no ambiguous",
+            GeneratorOptions {
+                length: 64,
+                exclude_ambiguous: true,
+                ..GeneratorOptions::default()
+            },
+            format!("{LOWERCASE}{UPPERCASE}{DIGITS}{SYMBOLS}")
+                .chars()
+                .filter(|ch| !AMBIGUOUS.contains(*ch))
+                .collect(),
+        ),
+    ];
+
+    for (label, options, allowed) in cases {
+        let classes = options.character_classes();
+        for _ in 0..50 {
+            let password = generate(options);
+            assert_eq!(password.chars().count(), options.length, "{label}");
+            assert!(password.chars().all(|ch| allowed.contains(ch)), "{label}: unexpected character in {password}");
+            for class in &classes {
+                assert!(password.chars().any(|ch| class.contains(&ch)), "{label}: missing class in {password}");
+            }
+        }
+    }
+}
+
+#[test]
+fn generate_password_pronounceable_alternates_syllables_then_symbol_and_digits() {
+    let options = GeneratorOptions {
+        length: 14,
+        symbols: SymbolSet::DeviceSafe,
+        exclude_ambiguous: true,
+        pronounceable: true,
+        ..GeneratorOptions::default()
+    };
+
+    for _ in 0..50 {
+        let password: Vec<char> = generate(options).chars().collect();
+        assert_eq!(password.len(), 14);
+        let (letters, tail) = password.split_at(11);
+        for (index, letter) in letters.iter().enumerate() {
+            let expected = if index % 2 == 0 { CONSONANTS } else { VOWELS };
+            assert!(expected.contains(letter.to_ascii_lowercase()), "unexpected letter in {password:?}");
+        }
+        assert_eq!(letters.iter().filter(|letter| letter.is_ascii_uppercase()).count(), 1);
+        assert!(DEVICE_SAFE_SYMBOLS.contains(tail[0]));
+        assert!(tail[1..].iter().all(|ch| ch.is_ascii_digit() && !AMBIGUOUS.contains(*ch)));
+    }
+}
+
+#[test]
+fn generate_password_pronounceable_excludes_ambiguous_letters_after_case_conversion() {
+    for (label, lowercase) in [("uppercase only", false), ("mixed case", true)] {
+        let options = GeneratorOptions {
+            length: 64,
+            lowercase,
+            uppercase: true,
+            digits: false,
+            symbols: SymbolSet::None,
+            exclude_ambiguous: true,
+            pronounceable: true,
+        };
+        for _ in 0..50 {
+            let password = generate(options);
+            assert!(!password.chars().any(|ch| AMBIGUOUS.contains(ch)), "{label}: ambiguous character in {password}");
+        }
+    }
+}
+
+#[test]
+fn generate_password_rejects_unsatisfiable_options() {
+    let cases = [
+        (
+            "too short",
+            GeneratorOptions {
+                length: 3,
+                ..GeneratorOptions::default()
+            },
+        ),
+        (
+            "shorter than classes",
+            GeneratorOptions {
+                length: 4,
+                pronounceable: true,
+                ..GeneratorOptions::default()
+            },
+        ),
+        (
+            "no classes",
+            GeneratorOptions {
+                lowercase: false,
+                uppercase: false,
+                digits: false,
+                symbols: SymbolSet::None,
+                ..GeneratorOptions::default()
+            },
+        ),
+        (
+            "pronounceable without letters",
+            GeneratorOptions {
+                lowercase: false,
+                uppercase: false,
+                pronounceable: true,
+                ..GeneratorOptions::default()
+            },
+        ),
+    ];
+
+    for (label, options) in cases {
+        assert!(matches!(generate_password(&options), Err(GeneratorError::InvalidOptions(_))), "{label}");
+    }
+}
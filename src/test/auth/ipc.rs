@@ -54,7 +54,11 @@ fn secret_fields_are_redacted_in_debug_output() {
     assert!(!debug.contains("master-pass"));
     assert!(debug.contains("[REDACTED]"));
 
-    let authorize = AgentRequestPayload::AuthorizeAskpass { name: "shared".to_string() };
+    let authorize = AgentRequestPayload::AuthorizeAskpass {
+        name: "shared".to_string(),
+        host: None,
+        destination: None,
+    };
     let debug = format!("{authorize:?}");
     assert!(debug.contains("shared"));
 
@@ -0,0 +1,46 @@
+use super::*;
+use crate::auth::secret::sensitive_string;
+use crate::test::support::auth::{TEST_ED25519_KEY, TEST_ENCRYPTED_ED25519_KEY, TestVaultEnv};
+use crate::test::support::fs::TestWorkspace;
+
+#[test]
+fn import_private_key_stores_plain_and_decrypted_keys() {
+    let env = TestVaultEnv::new("key_import");
+    let workspace = TestWorkspace::new("auth", "key_import").expect("workspace");
+    let unlocked = env.init_and_unlock("master-pass");
+    let plain = workspace.write_rel("id_plain", TEST_ED25519_KEY).expect("plain key");
+    let encrypted = workspace.write_rel("id_encrypted", TEST_ENCRYPTED_ED25519_KEY).expect("encrypted key");
+
+    let imported = import_private_key(&unlocked, "deploy", &plain, SshKeyConstraints::default(), || {
+        panic!("plain keys must not ask for a passphrase")
+    })
+    .expect("import plain key");
+    assert_eq!(imported.comment, "deploy@ci");
+    assert!(!imported.was_encrypted);
+
+    assert!(matches!(
+        import_private_key(&unlocked, "enc", &encrypted, SshKeyConstraints::default(), || Ok(sensitive_string("wrong"))),
+        Err(KeyImportError::WrongPassphrase)
+    ));
+    let imported = import_private_key(&unlocked, "enc", &encrypted, SshKeyConstraints::default(), || {
+        Ok(sensitive_string("fixture-pass"))
+    })
+    .expect("import encrypted key");
+    assert!(imported.was_encrypted);
+
+    let (stored, _) = unlocked.get_ssh_key("enc").expect("read stored key");
+    let stored = PrivateKey::from_openssh(stored.expose_secret()).expect("parse stored key");
+    assert!(!stored.is_encrypted());
+    assert_eq!(stored.fingerprint(HashAlg::Sha256).to_string(), imported.fingerprint);
+}
+
+#[test]
+fn shred_file_removes_regular_files_only() {
+    let workspace = TestWorkspace::new("auth", "shred").expect("workspace");
+    let key = workspace.write_rel("id_ed25519", TEST_ED25519_KEY).expect("key file");
+
+    shred_file(&key).expect("shred key");
+    assert!(!key.exists());
+    assert!(shred_file(&workspace.join("missing")).is_err());
+    assert!(shred_file(&workspace.join("")).is_err());
+}
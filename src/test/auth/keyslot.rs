@@ -0,0 +1,102 @@
+use super::*;
+use crate::auth::vault::{initialize_vault_with_paths, unlock_with_password_and_paths};
+use crate::test::support::auth::TestVaultEnv;
+use crate::test::support::fs::TestWorkspace;
+use std::fs;
+
+#[test]
+fn recovery_key_format_round_trips_and_rejects_malformed_input() {
+    let recovery_key = generate_recovery_key().expect("generate recovery key");
+    let formatted = recovery_key.expose_secret();
+    assert_eq!(formatted.len(), RECOVERY_KEY_BYTES * 2 + RECOVERY_KEY_BYTES * 2 / RECOVERY_KEY_GROUP_LEN - 1);
+    assert_eq!(formatted.split('-').count(), RECOVERY_KEY_BYTES * 2 / RECOVERY_KEY_GROUP_LEN);
+
+    let expected = recovery_key_bytes(formatted).expect("decode recovery key");
+    let retyped = formatted.to_uppercase().replace('-', " ");
+    assert_eq!(recovery_key_bytes(&retyped).expect("decode retyped key"), expected);
+
+    for malformed in [
+        "",
+        "abcd-efgh",
+        &formatted[1..],
+        &format!("{formatted}0"),
+        &formatted.replace(&formatted[..1], "z"),
+    ] {
+        assert!(matches!(recovery_key_bytes(malformed), Err(VaultError::InvalidRecoveryKey)), "{malformed}");
+    }
+}
+
+#[test]
+fn recovery_key_from_init_resets_the_master_password_without_touching_entries() {
+    let env = TestVaultEnv::new("recovery_key");
+    let recovery_key = initialize_vault_with_paths(env.paths(), "master-pass").expect("initialize vault");
+    env.unlock("master-pass").store_secret("shared", "top-secret").expect("store secret");
+
+    assert!(matches!(
+        unlock_with_recovery_key_and_paths(env.paths(), "0000-0000-0000-0000-0000-0000-0000-0000"),
+        Err(VaultError::InvalidRecoveryKey)
+    ));
+    assert!(matches!(
+        recover_vault_with_paths(env.paths(), recovery_key.expose_secret(), ""),
+        Err(VaultError::InvalidMasterPassword)
+    ));
+
+    recover_vault_with_paths(env.paths(), recovery_key.expose_secret(), "new-pass").expect("recover vault");
+    assert!(matches!(
+        unlock_with_password_and_paths(env.paths(), "master-pass"),
+        Err(VaultError::InvalidMasterPassword)
+    ));
+    assert_eq!(env.unlock("new-pass").get_secret("shared").expect("read secret").expose_secret(), "top-secret");
+    assert!(unlock_with_recovery_key_and_paths(env.paths(), recovery_key.expose_secret()).is_ok());
+
+    let replacement = reset_recovery_key_with_paths(env.paths(), "new-pass").expect("reset recovery key");
+    assert!(matches!(
+        unlock_with_recovery_key_and_paths(env.paths(), recovery_key.expose_secret()),
+        Err(VaultError::InvalidRecoveryKey)
+    ));
+    assert!(unlock_with_recovery_key_and_paths(env.paths(), replacement.expose_secret()).is_ok());
+}
+
+#[test]
+fn keyfile_is_required_alongside_the_master_password_and_survives_password_rotation() {
+    let env = TestVaultEnv::new("keyfile");
+    let workspace = TestWorkspace::new("auth_keyslot", "keyfile").expect("workspace");
+    let keyfile = workspace.join("vault.key");
+    let other_keyfile = workspace.join("other.key");
+    let recovery_key = initialize_vault_with_paths(env.paths(), "master-pass").expect("initialize vault");
+    env.unlock("master-pass").store_secret("shared", "top-secret").expect("store secret");
+
+    generate_keyfile(&keyfile).expect("generate keyfile");
+    assert!(generate_keyfile(&keyfile).is_err(), "existing keyfiles must not be overwritten");
+    generate_keyfile(&other_keyfile).expect("generate other keyfile");
+    assert!(matches!(
+        set_keyfile_with_paths(env.paths(), "wrong-pass", Some(&keyfile)),
+        Err(VaultError::InvalidMasterPassword)
+    ));
+    set_keyfile_with_paths(env.paths(), "master-pass", Some(&keyfile)).expect("set keyfile");
+    assert_eq!(
+        env.unlock("master-pass").get_secret("shared").expect("read secret").expose_secret(),
+        "top-secret"
+    );
+
+    vault::rotate_master_password_with_paths(env.paths(), "master-pass", "new-pass").expect("rotate master password");
+    let metadata = vault::read_metadata(env.paths()).expect("read metadata");
+    let keyfile_path = metadata.keyfile.clone().expect("keyfile kept");
+    fs::copy(&other_keyfile, &keyfile_path).expect("swap keyfile contents");
+    assert!(matches!(
+        unlock_with_password_and_paths(env.paths(), "new-pass"),
+        Err(VaultError::InvalidMasterPassword)
+    ));
+    fs::remove_file(&keyfile_path).expect("remove keyfile");
+    assert!(matches!(
+        unlock_with_password_and_paths(env.paths(), "new-pass"),
+        Err(VaultError::KeyfileUnavailable(_))
+    ));
+
+    recover_vault_with_paths(env.paths(), recovery_key.expose_secret(), "recovered-pass").expect("recover vault");
+    assert_eq!(vault::read_metadata(env.paths()).expect("read metadata").keyfile, None);
+    assert_eq!(
+        env.unlock("recovered-pass").get_secret("shared").expect("read secret").expose_secret(),
+        "top-secret"
+    );
+}
@@ -0,0 +1,44 @@
+use super::*;
+use crate::auth::secret::ExposeSecret;
+
+const RFC6238_SHA1_SEED: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+const RFC6238_SHA256_SEED: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZA====";
+
+fn code_at(seed: &str, unix_seconds: u64) -> String {
+    let params = parse_totp_seed(seed).expect("parse seed");
+    totp_code_at(&params, unix_seconds).expose_secret().to_string()
+}
+
+#[test]
+fn totp_codes_match_rfc6238_test_vectors() {
+    let sha1 = format!("otpauth://totp/test?secret={RFC6238_SHA1_SEED}&digits=8");
+    assert_eq!(code_at(&sha1, 59), "94287082");
+    assert_eq!(code_at(&sha1, 1_111_111_109), "07081804");
+
+    let sha256 = format!("otpauth://totp/test?secret={RFC6238_SHA256_SEED}&digits=8&algorithm=SHA256");
+    assert_eq!(code_at(&sha256, 59), "46119246");
+}
+
+#[test]
+fn bare_base32_secrets_use_rfc6238_defaults() {
+    let params = parse_totp_seed(" gezd gnbv-gy3t qojq gezd gnbv gy3t qojq ").expect("parse bare seed");
+    assert_eq!((params.digits, params.period_seconds, params.algorithm), (6, 30, TotpAlgorithm::Sha1));
+    assert_eq!(totp_code_at(&params, 59).expose_secret(), "287082");
+}
+
+#[test]
+fn parse_totp_seed_rejects_values_that_are_not_seeds() {
+    for value in [
+        "hunter2!",
+        "",
+        "otpauth://hotp/test?secret=GEZDGNBV",
+        "otpauth://totp/test?issuer=Example",
+        "otpauth://totp/test?secret=GEZDGNBV&algorithm=MD5",
+        "otpauth://totp/test?secret=GEZDGNBV&digits=4",
+    ] {
+        assert!(
+            matches!(parse_totp_seed(value), Err(OtpError::InvalidSeed(_))),
+            "expected '{value}' to be rejected"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use super::*;
+use crate::auth::secret::ExposeSecret;
+use crate::test::support::fs::TestWorkspace;
+
+#[test]
+fn secret_reference_parse_distinguishes_vault_entries_and_provider_references() {
+    let cases: Vec<(&str, Option<SecretReference>)> = vec![
+        ("shared", Some(SecretReference::VaultEntry("shared".to_string()))),
+        (
+            "pass:network/core-sw",
+            Some(SecretReference::External(ExternalSecretRef {
+                provider: SecretProvider::Pass,
+                locator: "network/core-sw".to_string(),
+            })),
+        ),
+        (
+            "op:Infra/core-sw/password",
+            Some(SecretReference::External(ExternalSecretRef {
+                provider: SecretProvider::OnePassword,
+                locator: "Infra/core-sw/password".to_string(),
+            })),
+        ),
+        (
+            "cmd:/opt/bin/fetch-secret core-sw",
+            Some(SecretReference::External(ExternalSecretRef {
+                provider: SecretProvider::Command,
+                locator: "/opt/bin/fetch-secret core-sw".to_string(),
+            })),
+        ),
+        ("pass:", None),
+        ("vault:shared", None),
+        ("../bad", None),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(SecretReference::parse(input), expected, "input: {input}");
+    }
+}
+
+#[test]
+fn external_reference_command_args_match_provider_cli_conventions() {
+    let cases = [
+        ("pass:network/core-sw", vec!["show", "network/core-sw"]),
+        ("op:Infra/core-sw/password", vec!["read", "--no-newline", "op://Infra/core-sw/password"]),
+        ("op:op://Infra/core-sw/password", vec!["read", "--no-newline", "op://Infra/core-sw/password"]),
+        ("bw:core-sw", vec!["get", "password", "core-sw"]),
+        ("secret-tool:service=cossh user=admin", vec!["lookup", "service", "cossh", "user", "admin"]),
+    ];
+
+    for (input, expected) in cases {
+        let Some(SecretReference::External(reference)) = SecretReference::parse(input) else {
+            panic!("expected external reference for {input}");
+        };
+        assert_eq!(reference.command_args().expect("command args"), expected, "input: {input}");
+    }
+
+    let Some(SecretReference::External(invalid)) = SecretReference::parse("secret-tool:service") else {
+        panic!("expected external reference");
+    };
+    assert!(matches!(invalid.command_args(), Err(SecretProviderError::InvalidReference(_))));
+}
+
+#[test]
+fn resolve_external_secret_returns_first_line_of_command_output() {
+    let workspace = TestWorkspace::new("auth_provider", "first_line").expect("workspace");
+    let script = workspace
+        .write_script("provider.sh", "printf 'stub-secret\\nsecond line\\n'")
+        .expect("write stub script");
+    let reference = ExternalSecretRef {
+        provider: SecretProvider::Command,
+        locator: script.display().to_string(),
+    };
+
+    let secret = resolve_external_secret(&reference).expect("resolve stub secret");
+
+    assert_eq!(secret.expose_secret(), "stub-secret");
+}
+
+#[test]
+fn resolve_external_secret_surfaces_command_failures_and_empty_output() {
+    let workspace = TestWorkspace::new("auth_provider", "failures").expect("workspace");
+    let failing = workspace
+        .write_script("failing.sh", "echo 'entry not found' >&2\nexit 3")
+        .expect("write stub script");
+    let empty = workspace.write_script("empty.sh", "exit 0").expect("write stub script");
+
+    let err = resolve_external_secret(&ExternalSecretRef {
+        provider: SecretProvider::Command,
+        locator: failing.display().to_string(),
+    })
+    .expect_err("failing provider");
+    assert!(matches!(&err, SecretProviderError::CommandFailed { stderr, .. } if stderr == "entry not found"));
+
+    let err = resolve_external_secret(&ExternalSecretRef {
+        provider: SecretProvider::Command,
+        locator: empty.display().to_string(),
+    })
+    .expect_err("empty provider output");
+    assert!(matches!(err, SecretProviderError::EmptySecret { provider: "cmd" }));
+}
+
+#[test]
+fn resolve_external_secret_drains_output_larger_than_the_pipe_buffer() {
+    let workspace = TestWorkspace::new("auth_provider", "large_output").expect("workspace");
+    let script = workspace
+        .write_script(
+            "chatty.sh",
+            "printf 'stub-secret\\n'\nhead -c 262144 /dev/zero | tr '\\0' 'x'\nhead -c 262144 /dev/zero | tr '\\0' 'y' >&2",
+        )
+        .expect("write stub script");
+    let started_at = std::time::Instant::now();
+
+    let secret = resolve_external_secret(&ExternalSecretRef {
+        provider: SecretProvider::Command,
+        locator: script.display().to_string(),
+    })
+    .expect("resolve chatty provider");
+
+    assert_eq!(secret.expose_secret(), "stub-secret");
+    assert!(started_at.elapsed() < PROVIDER_COMMAND_TIMEOUT / 2);
+}
+
+#[test]
+fn resolve_external_secret_does_not_wait_for_background_jobs_holding_the_pipes() {
+    let workspace = TestWorkspace::new("auth_provider", "background_job").expect("workspace");
+    let script = workspace
+        .write_script("daemonizing.sh", "sleep 60 &\necho stub-secret")
+        .expect("write stub script");
+    let started_at = std::time::Instant::now();
+
+    let secret = resolve_external_secret(&ExternalSecretRef {
+        provider: SecretProvider::Command,
+        locator: script.display().to_string(),
+    })
+    .expect("resolve provider with a background job");
+
+    assert_eq!(secret.expose_secret(), "stub-secret");
+    assert!(started_at.elapsed() < PROVIDER_COMMAND_TIMEOUT / 2);
+}
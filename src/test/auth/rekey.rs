@@ -0,0 +1,175 @@
+use super::*;
+use crate::auth::secret::ExposeSecret;
+use crate::test::support::auth::TestVaultEnv;
+
+const RAISED_KDF: KdfParams = KdfParams {
+    memory_kib: KdfParams::DEFAULT.memory_kib,
+    time_cost: KdfParams::DEFAULT.time_cost + 1,
+    parallelism: KdfParams::DEFAULT.parallelism,
+};
+
+fn staging_dirs(paths: &VaultPaths) -> Vec<String> {
+    fs::read_dir(paths.vault_dir())
+        .expect("read vault dir")
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.starts_with(REKEY_STAGING_PREFIX) || name.starts_with(REKEY_BACKUP_PREFIX))
+        .collect()
+}
+
+#[test]
+fn rekey_vault_raises_kdf_cost_and_keeps_the_data_key() {
+    let env = TestVaultEnv::new("rekey_kdf");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("shared", "top-secret").expect("store secret");
+
+    let outcome = rekey_vault_with_paths(
+        env.paths(),
+        "master-pass",
+        RekeyOptions {
+            kdf: RAISED_KDF,
+            rotate_data_key: false,
+        },
+    )
+    .expect("rekey vault");
+
+    assert_eq!(outcome.previous_kdf, KdfParams::DEFAULT);
+    assert_eq!(outcome.kdf, RAISED_KDF);
+    assert_eq!(outcome.recovery_key, None);
+    assert_eq!(vault::current_kdf_params_with_paths(env.paths()).expect("read kdf"), RAISED_KDF);
+    let reopened = env.unlock("master-pass");
+    assert_eq!(reopened.data_key_copy(), unlocked.data_key_copy());
+    assert_eq!(reopened.get_secret("shared").expect("get secret").expose_secret(), "top-secret");
+}
+
+#[test]
+fn rekey_vault_rotates_data_key_and_reencrypts_every_entry() {
+    let env = TestVaultEnv::new("rekey_rotate");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("alpha", "alpha-secret").expect("store secret");
+    unlocked.store_secret("beta", "beta-old").expect("store secret");
+    unlocked.rotate_secret("beta", "beta-secret").expect("rotate secret");
+
+    let outcome = rekey_vault_with_paths(
+        env.paths(),
+        "master-pass",
+        RekeyOptions {
+            kdf: KdfParams::DEFAULT,
+            rotate_data_key: true,
+        },
+    )
+    .expect("rekey vault");
+
+    assert!(outcome.data_key_rotated);
+    assert_eq!(outcome.entries_reencrypted, 2);
+    let reopened = env.unlock("master-pass");
+    assert_ne!(reopened.data_key_copy(), unlocked.data_key_copy());
+    assert_eq!(reopened.get_secret("alpha").expect("get alpha").expose_secret(), "alpha-secret");
+    assert_eq!(reopened.get_secret("beta").expect("get beta").expose_secret(), "beta-secret");
+    assert_eq!(reopened.secret_history("beta").expect("beta history")[0].secret.expose_secret(), "beta-old");
+    assert!(unlocked.get_secret("alpha").is_err(), "retired data key must no longer decrypt entries");
+    assert!(staging_dirs(env.paths()).is_empty());
+
+    let recovery_key = outcome.recovery_key.expect("recovery key reissued");
+    let recovered = keyslot::unlock_with_recovery_key_and_paths(env.paths(), recovery_key.expose_secret()).expect("unlock with recovery key");
+    assert_eq!(recovered.data_key_copy(), reopened.data_key_copy());
+}
+
+#[test]
+fn rekey_vault_leaves_vault_untouched_when_an_entry_cannot_be_reencrypted() {
+    let env = TestVaultEnv::new("rekey_rollback");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("alpha", "alpha-secret").expect("store secret");
+    unlocked.store_secret("broken", "broken-secret").expect("store secret");
+    fs::write(env.paths().entry_path("broken").expect("entry path"), "{}").expect("corrupt entry");
+    let metadata_before = fs::read(env.paths().metadata_path()).expect("read metadata");
+
+    let err = rekey_vault_with_paths(
+        env.paths(),
+        "master-pass",
+        RekeyOptions {
+            kdf: RAISED_KDF,
+            rotate_data_key: true,
+        },
+    )
+    .expect_err("rekey should fail");
+
+    assert!(matches!(err, VaultError::InvalidVaultFormat(_)), "{err}");
+    assert_eq!(fs::read(env.paths().metadata_path()).expect("read metadata"), metadata_before);
+    assert_eq!(unlocked.get_secret("alpha").expect("get alpha").expose_secret(), "alpha-secret");
+    assert!(staging_dirs(env.paths()).is_empty());
+}
+
+#[test]
+fn rekey_vault_locks_a_running_agent_before_staging_entries() {
+    use interprocess::local_socket::traits::Listener as _;
+
+    let env = TestVaultEnv::new("rekey_lock_agent");
+    env.init_and_unlock("master-pass").store_secret("alpha", "alpha-secret").expect("store secret");
+    let ipc::ListenerBindResult::Bound(listener) = ipc::bind_listener(env.paths()).expect("bind agent endpoint") else {
+        panic!("test agent endpoint already in use");
+    };
+    let agent_paths = env.paths().clone();
+    let agent = std::thread::spawn(move || {
+        let mut stream = listener.accept().expect("accept lock request");
+        let request = ipc::read_request(&mut stream).expect("read request");
+        let staged_when_locked = staging_dirs(&agent_paths);
+        let response = AgentResponse::Success {
+            status: ipc::VaultStatus::locked(true),
+            message: "locked".to_string(),
+        };
+        ipc::write_response(&mut stream, &response).expect("write response");
+        (request.payload, staged_when_locked)
+    });
+
+    rekey_vault_with_paths(
+        env.paths(),
+        "master-pass",
+        RekeyOptions {
+            kdf: KdfParams::DEFAULT,
+            rotate_data_key: true,
+        },
+    )
+    .expect("rekey vault");
+
+    let (payload, staged_when_locked) = agent.join().expect("fake agent");
+    assert_eq!(payload, AgentRequestPayload::Lock);
+    assert!(staged_when_locked.is_empty(), "agent was locked after staging began: {staged_when_locked:?}");
+}
+
+#[test]
+fn rekey_vault_keeps_entries_written_while_staging_by_refusing_the_swap() {
+    let env = TestVaultEnv::new("rekey_concurrent_write");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("alpha", "alpha-secret").expect("store secret");
+    let existing = vault::read_metadata(env.paths()).expect("read metadata");
+    let replacement = UnlockedVault::from_data_key(env.paths().clone(), [9u8; DATA_KEY_LEN]);
+    let staging_dir = env.paths().vault_dir().join(format!("{REKEY_STAGING_PREFIX}test"));
+    let staged = stage_reencrypted_entries(env.paths(), &unlocked, &replacement, &staging_dir).expect("stage entries");
+
+    ensure_unchanged_since_staging(env.paths(), &staged, &existing).expect("nothing changed yet");
+    unlocked.rotate_secret("alpha", "rotated").expect("rotate during staging");
+    ensure_unchanged_since_staging(env.paths(), &staged, &existing).expect_err("a rewritten entry must stop the swap");
+    let staged = stage_reencrypted_entries(env.paths(), &unlocked, &replacement, &staging_dir).expect("stage entries again");
+    unlocked.store_secret("beta", "beta-secret").expect("store during staging");
+    ensure_unchanged_since_staging(env.paths(), &staged, &existing).expect_err("a new entry must stop the swap");
+}
+
+#[test]
+fn swap_removes_the_backup_directory_when_entries_cannot_be_moved_aside() {
+    let env = TestVaultEnv::new("rekey_swap_backup");
+    env.init_and_unlock("master-pass").store_secret("alpha", "alpha-secret").expect("store secret");
+    let metadata = vault::read_metadata(env.paths()).expect("read metadata");
+    let staging_dir = env.paths().vault_dir().join(format!("{REKEY_STAGING_PREFIX}test"));
+    let backup_dir = env.paths().vault_dir().join(format!("{REKEY_BACKUP_PREFIX}test"));
+    fs::create_dir_all(&staging_dir).expect("create staging dir");
+    // A non-empty directory in the way makes the entries rename fail.
+    fs::create_dir_all(backup_dir.join("entries/blocker")).expect("block backup entries");
+
+    swap_in_staged_entries(env.paths(), &staging_dir, &backup_dir, &metadata).expect_err("swap should fail");
+
+    assert!(!backup_dir.exists(), "failed swap left its backup directory behind");
+    assert_eq!(
+        env.unlock("master-pass").get_secret("alpha").expect("get alpha").expose_secret(),
+        "alpha-secret"
+    );
+}
@@ -1,4 +1,6 @@
 use super::*;
+use crate::test::support::fs::TestWorkspace;
+use std::os::unix::net::UnixListener;
 
 #[test]
 fn askpass_prompt_classification_distinguishes_allowed_missing_and_unexpected_prompts() {
@@ -10,6 +12,14 @@ fn askpass_prompt_classification_distinguishes_allowed_missing_and_unexpected_pr
         classify_internal_askpass_prompt(Some("Verification code:")),
         AskpassPromptDecision::DenyUnexpected
     );
+    assert_eq!(
+        classify_internal_askpass_prompt(Some("Enter passphrase for key '/home/alice/.ssh/id_ed25519': ")),
+        AskpassPromptDecision::AllowPassphrase
+    );
+    assert_eq!(
+        classify_internal_askpass_prompt(Some("Enter new passphrase (empty for no passphrase):")),
+        AskpassPromptDecision::DenyUnexpected
+    );
     assert_eq!(classify_internal_askpass_prompt(None), AskpassPromptDecision::DenyMissing);
 }
 
@@ -21,3 +31,54 @@ fn configure_internal_askpass_env_sets_token_binding() {
 
     assert!(env.iter().any(|(key, value)| { key == INTERNAL_ASKPASS_TOKEN_ENV && value == "askpass-token" }));
 }
+
+#[test]
+fn configure_password_and_passphrase_askpass_env_share_one_helper_binding() {
+    let mut env = Vec::new();
+
+    configure_internal_askpass_env(&mut env, "password-token").expect("configure askpass env");
+    configure_internal_passphrase_askpass_env(&mut env, "passphrase-token").expect("configure passphrase env");
+
+    assert_eq!(env.iter().filter(|(key, _)| key == INTERNAL_ASKPASS_MODE_ENV).count(), 1);
+    assert!(env.iter().any(|(key, value)| key == INTERNAL_ASKPASS_TOKEN_ENV && value == "password-token"));
+    assert!(
+        env.iter()
+            .any(|(key, value)| key == INTERNAL_ASKPASS_PASSPHRASE_TOKEN_ENV && value == "passphrase-token")
+    );
+}
+
+#[test]
+fn configure_forwarded_askpass_env_binds_entry_and_socket() {
+    let mut env = Vec::new();
+    let request = ForwardedAskpassRequest {
+        socket_path: remote_vault_forward_socket(Path::new("/run/cossh/vault-forward-0123456789abcdef.sock")),
+        entry_name: "core_sw".to_string(),
+    };
+
+    configure_forwarded_askpass_env(&mut env, &request).expect("configure forwarded askpass env");
+
+    assert_eq!(request.socket_path, PathBuf::from("/tmp/cossh-vault-forward-0123456789abcdef.sock"));
+    assert!(env.iter().any(|(key, value)| key == INTERNAL_ASKPASS_MODE_ENV && value == "1"));
+    assert!(
+        env.iter()
+            .any(|(key, value)| key == INTERNAL_ASKPASS_FORWARD_SOCKET_ENV && value == "/tmp/cossh-vault-forward-0123456789abcdef.sock")
+    );
+    assert!(env.iter().any(|(key, value)| key == INTERNAL_ASKPASS_FORWARD_ENTRY_ENV && value == "core_sw"));
+    assert!(
+        !env.iter().any(|(key, _)| key == INTERNAL_ASKPASS_TOKEN_ENV),
+        "no local askpass token is issued"
+    );
+}
+
+#[test]
+fn live_forwarded_vault_sockets_skip_stale_and_unrelated_sockets() {
+    let workspace = TestWorkspace::new("auth", "fwd_sockets").expect("temp workspace");
+    let dir = workspace.join("tmp");
+    std::fs::create_dir_all(&dir).expect("create socket dir");
+    let live = dir.join("cossh-vault-forward-live.sock");
+    let _listener = UnixListener::bind(&live).expect("bind live socket");
+    drop(UnixListener::bind(dir.join("cossh-vault-forward-stale.sock")).expect("bind stale socket"));
+    let _other = UnixListener::bind(dir.join("other.sock")).expect("bind unrelated socket");
+
+    assert_eq!(live_forwarded_vault_sockets(&dir), vec![live]);
+}
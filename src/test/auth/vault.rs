@@ -1,7 +1,7 @@
 use super::*;
 use crate::args::validate_vault_entry_name;
 use crate::auth::secret::ExposeSecret;
-use crate::test::support::auth::TestVaultEnv;
+use crate::test::support::auth::{TEST_ED25519_KEY, TestVaultEnv};
 
 #[test]
 fn validate_vault_entry_name_accepts_safe_and_rejects_unsafe_inputs() {
@@ -64,3 +64,246 @@ fn rotate_master_password_preserves_encrypted_data() {
     let unlocked_new = unlock_with_password_and_paths(env.paths(), "new-pass").expect("unlock with new password");
     assert_eq!(unlocked_new.get_secret("shared").expect("read secret").expose_secret(), "top-secret");
 }
+
+#[test]
+fn rotate_secret_keeps_bounded_history_and_rollback_restores_previous_value() {
+    let env = TestVaultEnv::new("entry_history");
+    let unlocked = env.init_and_unlock("master-pass");
+    assert!(matches!(unlocked.rotate_secret("core-sw", "next"), Err(VaultError::EntryNotFound)));
+    unlocked.store_secret("core-sw", "secret-0").expect("store secret");
+    assert!(matches!(unlocked.rollback_secret("core-sw"), Err(VaultError::NoEntryHistory)));
+
+    for index in 1..=MAX_ENTRY_HISTORY + 2 {
+        unlocked.rotate_secret("core-sw", &format!("secret-{index}")).expect("rotate secret");
+    }
+
+    let history: Vec<String> = unlocked
+        .secret_history("core-sw")
+        .expect("read history")
+        .iter()
+        .map(|version| version.secret.expose_secret().to_string())
+        .collect();
+    assert_eq!(history, ["secret-6", "secret-5", "secret-4", "secret-3", "secret-2"]);
+    let raw = fs::read_to_string(env.paths().entry_path("core-sw").expect("entry path")).expect("read entry");
+    assert!(!raw.contains("secret-"), "history must be stored encrypted");
+
+    unlocked.rollback_secret("core-sw").expect("roll back secret");
+    assert_eq!(unlocked.get_secret("core-sw").expect("read secret").expose_secret(), "secret-6");
+    let history = unlocked.secret_history("core-sw").expect("read history");
+    assert_eq!(history[0].secret.expose_secret(), "secret-7");
+    assert_eq!(history.len(), MAX_ENTRY_HISTORY);
+}
+
+#[test]
+fn store_secret_over_an_existing_entry_keeps_its_history() {
+    let env = TestVaultEnv::new("store_history");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("core-sw", "secret-0").expect("store secret");
+    unlocked.rotate_secret("core-sw", "secret-1").expect("rotate secret");
+
+    unlocked.store_secret("core-sw", "secret-2").expect("replace secret");
+
+    let history: Vec<String> = unlocked
+        .secret_history("core-sw")
+        .expect("read history")
+        .iter()
+        .map(|version| version.secret.expose_secret().to_string())
+        .collect();
+    assert_eq!(history, ["secret-1", "secret-0"]);
+    unlocked.rollback_secret("core-sw").expect("roll back secret");
+    assert_eq!(unlocked.get_secret("core-sw").expect("read secret").expose_secret(), "secret-1");
+}
+
+#[test]
+fn kdf_params_validate_rejects_weaker_than_default_and_oversized_costs() {
+    let cases = [
+        (KdfParams::DEFAULT, true),
+        (
+            KdfParams {
+                memory_kib: 256 * 1024,
+                time_cost: 6,
+                parallelism: 4,
+            },
+            true,
+        ),
+        (
+            KdfParams {
+                memory_kib: 19 * 1024,
+                ..KdfParams::DEFAULT
+            },
+            false,
+        ),
+        (
+            KdfParams {
+                time_cost: 1,
+                ..KdfParams::DEFAULT
+            },
+            false,
+        ),
+        (
+            KdfParams {
+                time_cost: 65,
+                ..KdfParams::DEFAULT
+            },
+            false,
+        ),
+        (
+            KdfParams {
+                parallelism: 0,
+                ..KdfParams::DEFAULT
+            },
+            false,
+        ),
+    ];
+
+    for (params, valid) in cases {
+        assert_eq!(params.validate().is_ok(), valid, "{params:?}");
+    }
+}
+
+#[test]
+fn unlock_rejects_stored_kdf_costs_above_the_rekey_maximum() {
+    let env = TestVaultEnv::new("vault_oversized_stored_kdf");
+    env.init_and_unlock("master-pass");
+    let mut metadata = read_metadata(env.paths()).expect("read metadata");
+    metadata.kdf_memory_kib = u32::MAX;
+    write_metadata(env.paths(), &metadata).expect("write metadata");
+
+    let err = unlock_with_password_and_paths(env.paths(), "master-pass").expect_err("oversized KDF must be refused");
+
+    assert!(matches!(err, VaultError::InvalidVaultFormat(_)), "{err}");
+}
+
+#[test]
+fn calibrate_kdf_never_drops_below_the_base_time_cost() {
+    let calibrated = calibrate_kdf(KdfParams::DEFAULT, Duration::from_millis(1)).expect("calibrate");
+
+    assert_eq!(calibrated, KdfParams::DEFAULT);
+}
+
+#[test]
+fn ssh_key_entries_bind_constraints_and_stay_out_of_password_paths() {
+    let env = TestVaultEnv::new("ssh_key_entry");
+    let unlocked = env.init_and_unlock("master-pass");
+    let constraints = SshKeyConstraints {
+        confirm: true,
+        lifetime_seconds: Some(600),
+        ..SshKeyConstraints::default()
+    };
+    assert!(matches!(
+        unlocked.store_ssh_key("deploy", "not a key", constraints),
+        Err(VaultError::InvalidSshKey(_))
+    ));
+    unlocked.store_ssh_key("deploy", TEST_ED25519_KEY, constraints).expect("store ssh key");
+
+    let (key, stored) = unlocked.get_ssh_key("deploy").expect("read ssh key");
+    assert_eq!(key.expose_secret(), TEST_ED25519_KEY);
+    assert!(stored.added_at.is_some(), "storing a key records when it was added");
+    assert_eq!(SshKeyConstraints { added_at: None, ..stored }, constraints);
+    let listed: Vec<_> = list_ssh_key_entries_with_paths(env.paths())
+        .expect("list keys")
+        .into_iter()
+        .map(|entry| (entry.name, entry.ssh_key))
+        .collect();
+    assert_eq!(listed, [("deploy".to_string(), Some(stored))]);
+    assert!(is_ssh_key_entry_with_paths(env.paths(), "deploy").expect("entry kind"));
+    assert!(matches!(is_ssh_key_entry_with_paths(env.paths(), "missing"), Err(VaultError::EntryNotFound)));
+    assert!(matches!(unlocked.get_secret("deploy"), Err(VaultError::WrongEntryKind(_))));
+    assert!(matches!(unlocked.rotate_secret("deploy", "next"), Err(VaultError::WrongEntryKind(_))));
+    assert!(matches!(unlocked.store_secret("deploy", "password"), Err(VaultError::WrongEntryKind(_))));
+    assert_eq!(unlocked.get_ssh_key("deploy").expect("key survives").0.expose_secret(), TEST_ED25519_KEY);
+
+    let path = env.paths().entry_path("deploy").expect("entry path");
+    let mut entry = read_entry_file(&path).expect("read entry");
+    entry.ssh_key = Some(SshKeyConstraints::default());
+    write_entry_file(&path, &entry).expect("write tampered entry");
+    assert!(unlocked.get_ssh_key("deploy").is_err(), "edited constraints must fail authentication");
+}
+
+#[test]
+fn entry_policies_survive_rotation_and_are_bound_to_the_entry() {
+    let env = TestVaultEnv::new("entry_policy");
+    let unlocked = env.init_and_unlock("master-pass");
+    let policy = EntryPolicy {
+        confirm: true,
+        allowed_hosts: vec!["prod-*".to_string()],
+        max_uses: Some(3),
+    };
+    assert!(matches!(unlocked.set_entry_policy("shared", policy.clone()), Err(VaultError::EntryNotFound)));
+    unlocked.store_secret("shared", "secret-0").expect("store secret");
+    assert!(unlocked.entry_policy("shared").expect("read policy").is_unrestricted());
+
+    unlocked.set_entry_policy("shared", policy.clone()).expect("set policy");
+    unlocked.store_secret("shared", "secret-1").expect("overwrite secret");
+    unlocked.rotate_secret("shared", "secret-2").expect("rotate secret");
+    unlocked.rollback_secret("shared").expect("roll back secret");
+    assert_eq!(unlocked.entry_policy("shared").expect("read policy"), policy);
+    assert!(policy.allows_host(Some("PROD-web"), None));
+    assert!(!policy.allows_host(Some("lab"), Some("lab.example.com")));
+
+    let path = env.paths().entry_path("shared").expect("entry path");
+    let mut entry = read_entry_file(&path).expect("read entry");
+    entry.policy = None;
+    write_entry_file(&path, &entry).expect("write tampered entry");
+    assert!(unlocked.get_secret("shared").is_err(), "removing a policy must fail authentication");
+
+    unlocked
+        .store_ssh_key("deploy", TEST_ED25519_KEY, SshKeyConstraints::default())
+        .expect("store ssh key");
+    assert!(matches!(
+        unlocked.set_entry_policy("deploy", policy.clone()),
+        Err(VaultError::WrongEntryKind(_))
+    ));
+}
+
+#[test]
+fn entry_users_are_kept_across_changes_and_bound_to_the_entry() {
+    let env = TestVaultEnv::new("entry_user");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret_for_user("shared", "secret-0", Some("netops")).expect("store secret");
+    unlocked.store_secret("shared", "secret-1").expect("replace without a user");
+    unlocked.rotate_secret("shared", "secret-2").expect("rotate secret");
+    unlocked.rename_entry("shared", "core").expect("rename entry");
+    assert_eq!(entry_user_with_paths(env.paths(), "core").expect("read user").as_deref(), Some("netops"));
+
+    unlocked.store_secret_for_user("core", "secret-3", Some("admin")).expect("replace with a user");
+    assert_eq!(entry_user_with_paths(env.paths(), "core").expect("read user").as_deref(), Some("admin"));
+    assert_eq!(unlocked.get_secret("core").expect("get secret").expose_secret(), "secret-3");
+    let summaries = list_entry_summaries_with_paths(env.paths()).expect("entry summaries");
+    assert_eq!(summaries[0].user.as_deref(), Some("admin"));
+
+    let path = env.paths().entry_path("core").expect("entry path");
+    let mut entry = read_entry_file(&path).expect("read entry");
+    entry.user = Some("root".to_string());
+    write_entry_file(&path, &entry).expect("write tampered entry");
+    assert!(unlocked.get_secret("core").is_err(), "changing the user must fail authentication");
+    assert!(matches!(entry_user_with_paths(env.paths(), "missing"), Err(VaultError::EntryNotFound)));
+}
+
+#[test]
+fn rename_entry_rebinds_value_and_history_to_the_new_name() {
+    let env = TestVaultEnv::new("rename_entry");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_secret("old", "secret-1").expect("store secret");
+    unlocked.rotate_secret("old", "secret-2").expect("rotate secret");
+    unlocked.store_secret("taken", "other").expect("store second secret");
+    let updated_at = read_entry_file(&env.paths().entry_path("old").expect("entry path"))
+        .expect("read entry")
+        .updated_at;
+
+    assert!(matches!(unlocked.rename_entry("old", "taken"), Err(VaultError::EntryAlreadyExists)));
+    assert!(matches!(unlocked.rename_entry("old", "../bad"), Err(VaultError::InvalidEntryName)));
+    assert!(matches!(unlocked.rename_entry("missing", "new"), Err(VaultError::EntryNotFound)));
+    unlocked.rename_entry("old", "new").expect("rename entry");
+
+    assert!(!entry_exists_with_paths(env.paths(), "old").expect("old entry exists"));
+    assert_eq!(unlocked.get_secret("new").expect("renamed secret").expose_secret(), "secret-2");
+    let history = unlocked.secret_history("new").expect("renamed history");
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].secret.expose_secret(), "secret-1");
+
+    let summaries = list_entry_summaries_with_paths(env.paths()).expect("entry summaries");
+    assert_eq!(summaries.iter().map(|summary| summary.name.as_str()).collect::<Vec<_>>(), ["new", "taken"]);
+    assert_eq!(summaries[0].updated_at, updated_at);
+    assert!(!summaries[0].ssh_key);
+}
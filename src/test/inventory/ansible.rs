@@ -0,0 +1,221 @@
+use super::{expand_host_pattern, import_ansible_inventory, import_ansible_inventory_as_include, split_host_port};
+use crate::inventory::{InventoryHost, InventoryTreeModel, build_inventory_tree, expand_tilde};
+use crate::test::support::fs::TestWorkspace;
+
+fn host_named<'a>(tree: &'a InventoryTreeModel, name: &str) -> &'a InventoryHost {
+    tree.hosts
+        .iter()
+        .find(|host| host.name == name)
+        .unwrap_or_else(|| panic!("missing host '{name}'"))
+}
+
+fn folder_path(host: &InventoryHost) -> Vec<&str> {
+    host.source_folder_path.iter().map(String::as_str).collect()
+}
+
+#[test]
+fn ini_inventory_maps_groups_to_folders_and_connection_variables_to_fields() {
+    let workspace = TestWorkspace::new("inventory", "ansible_ini").expect("temp workspace");
+    let source_path = workspace
+        .write_rel(
+            "ansible/hosts",
+            r#"
+bastion.example.com:2200 ansible_user=jump   # jump host
+
+[web]
+web[01:02].example.com ansible_ssh_common_args='-o ProxyJump=bastion.example.com -o ServerAliveInterval=30 -C'
+
+[db]
+db1 ansible_host=10.0.2.11 ansible_python_interpreter=/usr/bin/python3
+web01.example.com
+
+[prod:children]
+web
+db
+
+[prod:vars]
+ansible_user=deploy
+ansible_ssh_private_key_file=~/.ssh/prod_ed25519
+"#,
+        )
+        .expect("write ansible inventory");
+    workspace
+        .write_rel("ansible/group_vars/db.yml", "ansible_port: 2222\nbackup_window: nightly\n")
+        .expect("write group vars");
+    workspace
+        .write_rel("ansible/host_vars/db1.yml", "ansible_user: postgres\n")
+        .expect("write host vars");
+    let output_path = workspace.join("cossh-inventory.yaml");
+    workspace.write(&output_path, "inventory: []\n").expect("write existing inventory");
+
+    let summary = import_ansible_inventory(&source_path, Some(&output_path)).expect("import ansible inventory");
+
+    assert_eq!(summary.hosts_written, 4);
+    assert!(summary.backup_path.is_some(), "existing inventory should be backed up");
+    assert_eq!(summary.skipped_variables.get("ansible_python_interpreter"), Some(&1));
+    assert_eq!(summary.skipped_variables.get("backup_window"), Some(&2));
+    assert_eq!(summary.skipped_variables.get("ansible_ssh_common_args (-C)"), Some(&2));
+
+    let tree = build_inventory_tree(&output_path).expect("load imported inventory");
+    let bastion = host_named(&tree, "bastion.example.com");
+    assert_eq!((bastion.port, bastion.user.as_deref()), (Some(2200), Some("jump")));
+    assert!(folder_path(bastion).is_empty());
+
+    let web01 = host_named(&tree, "web01.example.com");
+    assert_eq!(folder_path(web01), vec!["prod", "web"]);
+    assert_eq!(web01.user.as_deref(), Some("deploy"));
+    assert_eq!(web01.ssh.proxy_jump.as_deref(), Some("bastion.example.com"));
+    assert_eq!(web01.ssh.identity_files, vec![expand_tilde("~/.ssh/prod_ed25519")]);
+    assert_eq!(web01.ssh.extra_options.get("serveraliveinterval"), Some(&vec!["30".to_string()]));
+    assert_eq!(web01.port, Some(2222), "web01 is also in db and inherits its group_vars");
+    assert_eq!(folder_path(host_named(&tree, "web02.example.com")), vec!["prod", "web"]);
+
+    let db1 = host_named(&tree, "db1");
+    assert_eq!(folder_path(db1), vec!["prod", "db"]);
+    assert_eq!((db1.host.as_str(), db1.port, db1.user.as_deref()), ("10.0.2.11", Some(2222), Some("postgres")));
+}
+
+#[test]
+fn yaml_inventory_nests_children_and_applies_group_vars_by_depth() {
+    let workspace = TestWorkspace::new("inventory", "ansible_yaml").expect("temp workspace");
+    let source_path = workspace
+        .write_rel(
+            "inventory.yml",
+            r#"
+all:
+  vars:
+    ansible_user: ops
+  hosts:
+    mgmt1:
+      ansible_host: 192.0.2.10
+  children:
+    network:
+      vars:
+        ansible_user: netops
+        ansible_connection: network_cli
+      children:
+        switches:
+          vars:
+            ansible_user: switch-admin
+          hosts:
+            "sw[a:c]":
+            core1:
+              ansible_port: 830
+        routers:
+          hosts:
+            rtr1:
+              ansible_ssh_extra_args: -J bastion -A
+"#,
+        )
+        .expect("write ansible inventory");
+    let output_path = workspace.join("imported.yaml");
+
+    let summary = import_ansible_inventory(&source_path, Some(&output_path)).expect("import ansible inventory");
+
+    assert_eq!(summary.hosts_written, 6);
+    assert_eq!(summary.backup_path, None);
+    assert_eq!(summary.skipped_variables.get("ansible_connection"), Some(&5));
+
+    let tree = build_inventory_tree(&output_path).expect("load imported inventory");
+    let mgmt1 = host_named(&tree, "mgmt1");
+    assert_eq!((mgmt1.host.as_str(), mgmt1.user.as_deref()), ("192.0.2.10", Some("ops")));
+    assert!(folder_path(mgmt1).is_empty());
+
+    let swb = host_named(&tree, "swb");
+    assert_eq!(folder_path(swb), vec!["network", "switches"]);
+    assert_eq!((swb.host.as_str(), swb.user.as_deref()), ("swb", Some("switch-admin")));
+    assert_eq!(host_named(&tree, "core1").port, Some(830));
+
+    let rtr1 = host_named(&tree, "rtr1");
+    assert_eq!(folder_path(rtr1), vec!["network", "routers"]);
+    assert_eq!(rtr1.user.as_deref(), Some("netops"));
+    assert_eq!(rtr1.ssh.proxy_jump.as_deref(), Some("bastion"));
+    assert_eq!(rtr1.ssh.forward_agent.as_deref(), Some("yes"));
+}
+
+#[test]
+fn vars_directories_merge_every_file_in_name_order() {
+    let workspace = TestWorkspace::new("inventory", "ansible_vars_dirs").expect("temp workspace");
+    let source_path = workspace
+        .write_rel("ansible/hosts", "[web]\nweb1 ansible_host=10.0.1.11\n")
+        .expect("write ansible inventory");
+    workspace
+        .write_rel("ansible/group_vars/web/10-connection.yml", "ansible_user: deploy\nansible_port: 2200\n")
+        .expect("write group vars");
+    workspace
+        .write_rel("ansible/group_vars/web/20-overrides.yaml", "ansible_port: 2222\n")
+        .expect("write group vars");
+    workspace
+        .write_rel("ansible/group_vars/web/vault.yml", "$ANSIBLE_VAULT;1.1;AES256\n3133\n")
+        .expect("write encrypted vars");
+    workspace.write_rel("ansible/group_vars/web/notes.txt", "not: vars\n").expect("write notes");
+    workspace
+        .write_rel("ansible/host_vars/web1/main.yml", "ansible_user: admin\n")
+        .expect("write host vars");
+    let output_path = workspace.join("cossh-inventory.yaml");
+
+    import_ansible_inventory(&source_path, Some(&output_path)).expect("import ansible inventory");
+
+    let tree = build_inventory_tree(&output_path).expect("load imported inventory");
+    let web1 = host_named(&tree, "web1");
+    assert_eq!((web1.port, web1.user.as_deref()), (Some(2222), Some("admin")));
+}
+
+#[test]
+fn import_without_output_adds_an_include_file_and_keeps_the_main_inventory() {
+    let workspace = TestWorkspace::new("inventory", "ansible_include").expect("temp workspace");
+    let source_path = workspace
+        .write_rel("ansible/hosts", "[web]\nweb1 ansible_host=10.0.1.11\n")
+        .expect("write ansible inventory");
+    let main_inventory = workspace.join("cossh-inventory.yaml");
+    workspace
+        .write(&main_inventory, "inventory:\n  - name: bastion\n    host: bastion.example.com\n")
+        .expect("write existing inventory");
+
+    let summary = import_ansible_inventory_as_include(&source_path, &main_inventory).expect("import ansible inventory");
+
+    assert_eq!(summary.output_path, workspace.join("ansible-hosts.yaml"));
+    assert_eq!(summary.backup_path, None);
+    assert_eq!(summary.included_from.as_deref(), Some(main_inventory.as_path()));
+    let tree = build_inventory_tree(&main_inventory).expect("load inventory");
+    assert!(folder_path(host_named(&tree, "bastion")).is_empty());
+    assert_eq!(folder_path(host_named(&tree, "web1")), vec!["ansible-hosts", "web"]);
+
+    let again = import_ansible_inventory_as_include(&source_path, &main_inventory).expect("import again");
+    assert!(again.backup_path.is_some(), "previous import should be backed up");
+    assert_eq!(again.included_from, None, "include entry should not be added twice");
+    assert_eq!(build_inventory_tree(&main_inventory).expect("reload inventory").hosts.len(), 2);
+}
+
+#[test]
+fn host_patterns_expand_ranges_and_split_ports() {
+    assert_eq!(
+        expand_host_pattern("web[08:10:2].example.com").expect("numeric range"),
+        vec!["web08.example.com".to_string(), "web10.example.com".to_string()]
+    );
+    assert_eq!(
+        expand_host_pattern("r[1:2]-[a:b]").expect("nested ranges"),
+        vec!["r1-a", "r1-b", "r2-a", "r2-b"]
+    );
+    assert!(expand_host_pattern("web[3:1]").unwrap_err().contains("is empty"));
+    assert!(expand_host_pattern("web[1:").unwrap_err().contains("unterminated range"));
+    assert!(expand_host_pattern("web[0:99999999999]").unwrap_err().contains("more than 10000 hosts"));
+    assert!(expand_host_pattern("r[1:200]-[1:200]").unwrap_err().contains("more than 10000 hosts"));
+
+    assert_eq!(split_host_port("db1:5432"), ("db1", Some("5432")));
+    assert_eq!(split_host_port("web[01:03]:22"), ("web[01:03]", Some("22")));
+    assert_eq!(split_host_port("web[01:03]"), ("web[01:03]", None));
+    assert_eq!(split_host_port("2001:db8::1"), ("2001:db8::1", None));
+}
+
+#[test]
+fn malformed_ini_inventory_reports_the_line() {
+    let workspace = TestWorkspace::new("inventory", "ansible_invalid").expect("temp workspace");
+    let source_path = workspace.write_rel("hosts.ini", "[web]\nweb1 ansible_port\n").expect("write ansible inventory");
+
+    let message = import_ansible_inventory(&source_path, Some(&workspace.join("out.yaml")))
+        .expect_err("import should fail")
+        .to_string();
+    assert!(message.contains("line 2: expected key=value"), "unexpected error: {message}");
+    assert!(!workspace.join("out.yaml").exists());
+}
@@ -0,0 +1,172 @@
+use super::{CheckSeverity, InventoryCheckContext, check_inventory, forward_spec_problem};
+use crate::test::support::fs::TestWorkspace;
+use std::path::Path;
+
+fn context(vault_entries: Option<&[&str]>) -> InventoryCheckContext {
+    InventoryCheckContext {
+        profiles: vec!["default".to_string(), "network".to_string()],
+        vault_entries: vault_entries.map(|entries| entries.iter().map(|entry| entry.to_string()).collect()),
+    }
+}
+
+fn file_name(path: &Path) -> &str {
+    path.file_name().and_then(|name| name.to_str()).unwrap_or_default()
+}
+
+#[test]
+fn check_inventory_reports_every_problem_with_its_file_and_host() {
+    let workspace = TestWorkspace::new("inventory", "check_problems").expect("temp workspace");
+    let inventory_path = workspace
+        .write_rel(
+            "cossh-inventory.yaml",
+            r#"
+include:
+  - ./sites/*.yaml
+  - ./missing.yaml
+inventory:
+  - name: branch-fw
+    host: 10.0.0.254
+  - name: core-sw
+    host: 10.0.0.1
+    profile: netwrok
+    vault_pass: core_sw
+  - name: web01
+    host: web01.example
+    vault_pass: "pass:infra/web01"
+    vault_key: deploy_key
+    local_forward:
+      - "8080 localhost:80"
+      - "8080:localhost"
+    remote_forward: "0:localhost:22"
+    ssh_options:
+      ServerAliveInterval: 30
+      StrictHostKeyCheck: no
+  - name: mac
+    host: mac.example
+    profile: network
+    ssh_options:
+      IgnoreUnknown: UseKeychain,Apple*
+      AppleMultipath: yes
+"#,
+        )
+        .expect("write inventory");
+    let branch_path = workspace
+        .write_rel("sites/branch.yaml", "inventory:\n  - name: branch-fw\n    host: 10.1.0.254\n")
+        .expect("write include");
+    let duplicate_message = format!(
+        "duplicate host name; first defined in '{}'",
+        branch_path.canonicalize().expect("canonical include").display()
+    );
+
+    let report = check_inventory(&inventory_path, &context(Some(&["core_sw"])));
+    let found: Vec<(CheckSeverity, &str, Option<&str>, &str)> = report
+        .diagnostics
+        .iter()
+        .map(|diagnostic| {
+            (
+                diagnostic.severity,
+                file_name(&diagnostic.source_file),
+                diagnostic.host.as_deref(),
+                diagnostic.message.as_str(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        found,
+        vec![
+            (
+                CheckSeverity::Error,
+                "cossh-inventory.yaml",
+                None,
+                "include './missing.yaml' does not match any file"
+            ),
+            (CheckSeverity::Error, "cossh-inventory.yaml", Some("branch-fw"), duplicate_message.as_str()),
+            (
+                CheckSeverity::Error,
+                "cossh-inventory.yaml",
+                Some("core-sw"),
+                "profile 'netwrok' has no netwrok.cossh-config.yaml config file"
+            ),
+            (
+                CheckSeverity::Error,
+                "cossh-inventory.yaml",
+                Some("web01"),
+                "vault_key 'deploy_key' is not in the password vault; add it with `cossh vault add deploy_key`"
+            ),
+            (
+                CheckSeverity::Error,
+                "cossh-inventory.yaml",
+                Some("web01"),
+                "local_forward '8080:localhost' is not in [bind_address:]port:host:hostport form"
+            ),
+            (
+                CheckSeverity::Warning,
+                "cossh-inventory.yaml",
+                Some("web01"),
+                "ssh_options key 'StrictHostKeyCheck' is not an OpenSSH client option and will be rejected by ssh"
+            ),
+        ]
+    );
+    assert_eq!(report.hosts_checked, 4);
+    assert_eq!(report.count(CheckSeverity::Error), 5);
+}
+
+#[test]
+fn check_inventory_stops_at_load_errors_and_notes_a_missing_vault() {
+    let workspace = TestWorkspace::new("inventory", "check_load").expect("temp workspace");
+    let inventory_path = workspace
+        .write_rel("cossh-inventory.yaml", "inventory:\n  - name: db\n    host: db.example\n    vault_pass: db\n")
+        .expect("write inventory");
+
+    let report = check_inventory(&inventory_path, &context(None));
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(report.diagnostics[0].severity, CheckSeverity::Warning);
+    assert!(report.diagnostics[0].message.starts_with("password vault is not initialized"));
+
+    workspace
+        .write(&inventory_path, "inventory:\n  - name: db\n    template: missing\n")
+        .expect("rewrite inventory");
+    let report = check_inventory(&inventory_path, &context(None));
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(report.diagnostics[0].severity, CheckSeverity::Error);
+    assert!(report.diagnostics[0].message.contains("unknown template 'missing'"));
+    assert!(report.diagnostics[0].to_string().starts_with("error: "));
+
+    workspace
+        .write(
+            &inventory_path,
+            "inventory:\n  - name: lab\n    host: lab.example\n    vault_pass: \"vault:lab fw\"\n",
+        )
+        .expect("rewrite inventory");
+    let report = check_inventory(&inventory_path, &context(Some(&[])));
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(report.diagnostics[0].severity, CheckSeverity::Error);
+    assert!(report.diagnostics[0].message.contains("invalid vault_pass 'vault:lab fw'"));
+
+    assert!(check_inventory(&workspace.join("absent.yaml"), &context(None)).diagnostics.is_empty());
+}
+
+#[test]
+fn forward_spec_problem_accepts_openssh_forward_forms() {
+    for (spec, remote) in [
+        ("8080:localhost:80", false),
+        ("127.0.0.1:8080:db.internal:5432", false),
+        ("[::1]:8080:[2001:db8::1]:80", false),
+        ("/tmp/local.sock:/run/remote.sock", false),
+        ("0:localhost:22", true),
+        ("1080", true),
+        ("*:1080", true),
+    ] {
+        assert_eq!(forward_spec_problem(spec, remote), None, "{spec} should be accepted");
+    }
+    for (spec, remote) in [
+        ("1080", false),
+        ("0:localhost:22", false),
+        ("8080:localhost:0", false),
+        ("web:localhost:80", false),
+        ("8080::80", false),
+    ] {
+        assert!(forward_spec_problem(spec, remote).is_some(), "{spec} should be rejected");
+    }
+}
@@ -1,9 +1,11 @@
 use super::{
     EditableInventoryHost, create_inventory_folder, create_inventory_host_entry, delete_inventory_folder, delete_inventory_host_entry,
-    move_inventory_host_entry, relocate_inventory_folder, update_inventory_host_entry,
+    move_inventory_host_entry, parse_host_field_assignments, relocate_inventory_folder, rename_vault_references, rename_vault_references_in_inventory,
+    set_inventory_host_fields, transfer_inventory_host_entry, update_inventory_host_entry,
 };
 use crate::inventory::{ConnectionProtocol, build_inventory_tree};
 use crate::test::support::fs::TestWorkspace;
+use serde_yml::Value;
 use std::collections::BTreeMap;
 use std::fs;
 
@@ -56,6 +58,54 @@ inventory:
     assert!(rendered.contains("custom_keep: still-here"));
 }
 
+#[test]
+fn set_inventory_host_fields_changes_only_the_named_keys() {
+    let workspace = TestWorkspace::new("inventory", "edit_set_fields").expect("temp workspace");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+    workspace
+        .write(
+            &inventory_path,
+            r#"
+inventory:
+  - Lab:
+      - name: alpha
+        template: base
+        HostName: old.example
+        port: 22
+        ssh_options:
+          ServerAliveInterval: 30
+          Compression: yes
+templates:
+  base:
+    user: ops
+"#,
+        )
+        .expect("write inventory");
+
+    set_inventory_host_fields(
+        &inventory_path,
+        "alpha",
+        &[
+            ("host".to_string(), Some(serde_yml::Value::String("new.example".to_string()))),
+            ("port".to_string(), None),
+            ("ssh_options.serveraliveinterval".to_string(), Some(serde_yml::Value::String("60".to_string()))),
+            ("ssh_options.compression".to_string(), None),
+        ],
+    )
+    .expect("set host fields");
+
+    let tree = build_inventory_tree(&inventory_path).expect("reload inventory");
+    let host = tree.hosts.iter().find(|host| host.name == "alpha").expect("updated host");
+    assert_eq!(host.host, "new.example");
+    assert_eq!(host.port, None);
+    assert_eq!(host.user.as_deref(), Some("ops"), "template reference kept");
+    assert_eq!(
+        host.ssh.extra_options,
+        BTreeMap::from([("ServerAliveInterval".to_string(), vec!["60".to_string()])])
+    );
+    assert!(!fs::read_to_string(&inventory_path).expect("read inventory").contains("host: new.example"));
+}
+
 #[test]
 fn delete_inventory_host_entry_removes_host_recursively_from_nested_folder() {
     let workspace = TestWorkspace::new("inventory", "edit_delete").expect("temp workspace");
@@ -83,6 +133,93 @@ inventory:
     assert_eq!(tree.hosts[0].name, "beta");
 }
 
+#[test]
+fn rename_vault_references_rewrites_nested_vault_pass_and_vault_key_values_only() {
+    let workspace = TestWorkspace::new("inventory", "edit_rename_vault").expect("temp workspace");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+    workspace
+        .write(
+            &inventory_path,
+            r#"
+inventory:
+  - name: alpha
+    host: alpha.example
+    vault_pass: shared
+  - Group:
+      - name: beta
+        host: beta.example
+        vault_pass: other
+        vault_key: shared
+      - name: shared
+        host: shared.example
+"#,
+        )
+        .expect("write inventory");
+
+    assert_eq!(rename_vault_references(&inventory_path, "shared", "network").expect("rename references"), 2);
+
+    let tree = build_inventory_tree(&inventory_path).expect("reload inventory");
+    assert_eq!(tree.hosts[0].vault_pass.as_deref(), Some("network"));
+    assert_eq!(tree.hosts[1].vault_pass.as_deref(), Some("other"));
+    assert_eq!(tree.hosts[1].ssh.vault_key.as_deref(), Some("network"));
+    assert_eq!(tree.hosts[2].name, "shared", "host names matching the entry are left alone");
+
+    let before = fs::read_to_string(&inventory_path).expect("read inventory");
+    assert_eq!(rename_vault_references(&inventory_path, "missing", "network").expect("rename references"), 0);
+    assert_eq!(fs::read_to_string(&inventory_path).expect("read inventory"), before);
+}
+
+#[test]
+fn rename_vault_references_in_inventory_rewrites_inherited_defaults_and_templates() {
+    let workspace = TestWorkspace::new("inventory", "edit_rename_vault_inherited").expect("temp workspace");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+    let lab_path = workspace.join("lab.yaml");
+    workspace
+        .write(
+            &inventory_path,
+            r#"
+include:
+  - ./lab.yaml
+defaults:
+  vault_pass: shared
+templates:
+  switch:
+    vault_key: shared
+"#,
+        )
+        .expect("write inventory");
+    workspace
+        .write(
+            &lab_path,
+            r#"
+inventory:
+  - name: web
+    host: web.example
+  - Network:
+      - defaults:
+          vault_key: shared
+      - name: core
+        host: core.example
+        template: switch
+"#,
+        )
+        .expect("write lab inventory");
+
+    let tree = build_inventory_tree(&inventory_path).expect("load inventory");
+    let touched = rename_vault_references_in_inventory(&tree, "shared", "network").expect("rename references");
+    let touched: Vec<_> = touched
+        .iter()
+        .map(|(path, changed)| (path.file_name().and_then(|name| name.to_str()), *changed))
+        .collect();
+    assert_eq!(touched, vec![(Some("cossh-inventory.yaml"), 2), (Some("lab.yaml"), 1)]);
+
+    let tree = build_inventory_tree(&inventory_path).expect("reload inventory");
+    for host in &tree.hosts {
+        assert_eq!(host.vault_pass.as_deref(), Some("network"), "{}", host.name);
+    }
+    assert_eq!(tree.hosts[1].ssh.vault_key.as_deref(), Some("network"));
+}
+
 #[test]
 fn create_inventory_host_entry_creates_nested_folders_from_folder_path() {
     let workspace = TestWorkspace::new("inventory", "edit_create_folder").expect("temp workspace");
@@ -386,3 +523,76 @@ inventory:
     assert!(rendered.contains("Keep:"));
     assert!(!rendered.contains("Remove:"));
 }
+
+fn assignments(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+}
+
+#[test]
+fn parse_host_field_assignments_canonicalizes_keys_and_collects_lists() {
+    let fields = parse_host_field_assignments(&assignments(&[
+        ("HostName", "10.0.0.5"),
+        ("port", "2222"),
+        ("identities-only", "yes"),
+        ("IdentityFile", "~/.ssh/a"),
+        ("identity_file", "~/.ssh/b"),
+        ("local_forward", "8080 localhost:80"),
+        ("ssh_options.ServerAliveInterval", "30"),
+        ("description", ""),
+    ]))
+    .expect("parse assignments");
+
+    assert_eq!(
+        fields,
+        vec![
+            ("host".to_string(), Some(Value::String("10.0.0.5".to_string()))),
+            ("port".to_string(), Some(Value::Number(2222.into()))),
+            ("identities_only".to_string(), Some(Value::Bool(true))),
+            (
+                "identity_file".to_string(),
+                Some(Value::Sequence(vec![
+                    Value::String("~/.ssh/a".to_string()),
+                    Value::String("~/.ssh/b".to_string())
+                ]))
+            ),
+            ("local_forward".to_string(), Some(Value::String("8080:localhost:80".to_string()))),
+            ("ssh_options.ServerAliveInterval".to_string(), Some(Value::String("30".to_string()))),
+            ("description".to_string(), None),
+        ]
+    );
+
+    for invalid in [
+        ("prot", "ssh"),
+        ("port", "0"),
+        ("port", "ssh"),
+        ("hidden", "maybe"),
+        ("host", ""),
+        ("vault_pass", "bad name"),
+    ] {
+        assert!(
+            parse_host_field_assignments(&assignments(&[invalid])).is_err(),
+            "{invalid:?} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn transfer_inventory_host_entry_moves_hosts_between_files() {
+    let workspace = TestWorkspace::new("inventory", "edit_transfer").expect("temp workspace");
+    let inventory_path = workspace
+        .write_rel(
+            "cossh-inventory.yaml",
+            "include:\n  - ./prod.yaml\ninventory:\n  - name: alpha\n    host: alpha.example\n    custom_keep: still-here\n",
+        )
+        .expect("write inventory");
+    let include_path = workspace.write_rel("prod.yaml", "inventory: []\n").expect("write include");
+
+    transfer_inventory_host_entry(&inventory_path, "alpha", &include_path, &["Web".to_string()]).expect("transfer host");
+
+    let tree = build_inventory_tree(&inventory_path).expect("reload inventory");
+    let alpha = tree.hosts.iter().find(|host| host.name == "alpha").expect("moved host");
+    assert_eq!(alpha.source_file, include_path.canonicalize().expect("canonical include"));
+    assert_eq!(alpha.source_folder_path, vec!["prod".to_string(), "Web".to_string()]);
+    assert!(fs::read_to_string(&include_path).expect("read include").contains("custom_keep: still-here"));
+    assert!(!fs::read_to_string(&inventory_path).expect("read inventory").contains("alpha"));
+}
@@ -0,0 +1,149 @@
+use super::{InventoryExportFormat, render_inventory_export, ssh_forward_arguments, write_inventory_export};
+use crate::inventory::{ConnectionProtocol, InventoryTreeModel, build_inventory_tree};
+use crate::ssh_config::{SshHost, parse_ssh_config_for_migration};
+use crate::test::support::fs::TestWorkspace;
+use std::fs;
+
+fn exported_inventory(workspace: &TestWorkspace) -> InventoryTreeModel {
+    let inventory_path = workspace
+        .write_rel(
+            "cossh-inventory.yaml",
+            r#"
+include:
+  - ./lab.yaml
+inventory:
+  - name: bastion
+    description: Jump host
+    host: bastion.example.com
+    user: ops
+    vault_pass: bastion_pass
+  - Prod:
+      - Web:
+          - name: web1
+            host: 10.0.1.10
+            port: 2222
+            proxy_jump: bastion
+            identity_file: ~/.ssh/prod key
+            local_forward: 8080:localhost:80
+            ssh_options:
+              ServerAliveInterval: 30
+          - name: desktop
+            protocol: rdp
+            host: desktop.example.com
+            rdp_domain: CORP
+            rdp_args: [/size:1920x1080, +clipboard]
+            hidden: true
+  - name: core switch
+    host: 10.0.0.1
+"#,
+        )
+        .expect("write inventory");
+    workspace
+        .write_rel("lab.yaml", "inventory:\n  - name: lab1\n    host: lab1.example.com\n    profile: lab\n")
+        .expect("write include");
+    build_inventory_tree(&inventory_path).expect("load inventory")
+}
+
+fn ssh_host<'a>(hosts: &'a [SshHost], name: &str) -> &'a SshHost {
+    hosts.iter().find(|host| host.name == name).unwrap_or_else(|| panic!("missing host '{name}'"))
+}
+
+#[test]
+fn ssh_config_export_round_trips_comment_tags_through_the_parser() {
+    let workspace = TestWorkspace::new("inventory", "export_ssh").expect("temp workspace");
+    let tree = exported_inventory(&workspace);
+    let output = workspace.join("ssh/cossh.conf");
+
+    let export = render_inventory_export(&tree, InventoryExportFormat::SshConfig, Some(&workspace.join("ssh/cossh.conf.d")));
+    assert_eq!(export.hosts_exported, 4);
+    assert_eq!(export.skipped.len(), 1, "'core switch' cannot be an OpenSSH alias");
+    assert!(export.document.contains("# Folder: Prod/Web\n"));
+    assert!(export.document.contains("    LocalForward 8080 localhost:80\n"));
+    assert!(write_inventory_export(&export, &output).expect("write export").is_empty());
+    assert!(fs::read_to_string(&output).expect("read export").contains("Include "));
+    let include_path = workspace.join("ssh/cossh.conf.d/lab.conf");
+    fs::write(&include_path, "Host hand-edited\n").expect("edit include file");
+    let backups = write_inventory_export(&export, &output).expect("write export again");
+    assert_eq!(backups.len(), 2, "both the include file and the output are backed up: {backups:?}");
+    let include_backup = backups
+        .iter()
+        .find(|path| path.starts_with(workspace.join("ssh/cossh.conf.d")))
+        .expect("include backup");
+    assert_eq!(fs::read_to_string(include_backup).expect("read include backup"), "Host hand-edited\n");
+
+    let parsed = parse_ssh_config_for_migration(&output).expect("parse exported config");
+    let bastion = ssh_host(&parsed.hosts, "bastion");
+    assert_eq!(bastion.description.as_deref(), Some("Jump host"));
+    assert_eq!(bastion.pass_key.as_deref(), Some("bastion_pass"));
+    let web1 = ssh_host(&parsed.hosts, "web1");
+    assert_eq!((web1.hostname.as_deref(), web1.port), (Some("10.0.1.10"), Some(2222)));
+    assert_eq!(web1.proxy_jump.as_deref(), Some("bastion"));
+    assert_eq!(web1.other_options.get("serveraliveinterval"), Some(&vec!["30".to_string()]));
+    let desktop = ssh_host(&parsed.hosts, "desktop");
+    assert_eq!(desktop.protocol, ConnectionProtocol::Rdp);
+    assert_eq!(desktop.rdp_domain.as_deref(), Some("CORP"));
+    assert_eq!(desktop.rdp_args, vec!["/size:1920x1080".to_string(), "+clipboard".to_string()]);
+    assert!(desktop.hidden);
+
+    assert_eq!(ssh_host(&parsed.hosts, "lab1").profile.as_deref(), Some("lab"));
+    assert_eq!(parsed.root.children.len(), 1);
+    assert_eq!(parsed.root.children[0].name, "lab.conf");
+}
+
+#[test]
+fn ansible_export_nests_folders_as_groups_and_skips_rdp_hosts() {
+    let workspace = TestWorkspace::new("inventory", "export_ansible").expect("temp workspace");
+    let tree = exported_inventory(&workspace);
+
+    let export = render_inventory_export(&tree, InventoryExportFormat::Ansible, None);
+
+    assert_eq!(
+        export.skipped,
+        vec![("desktop".to_string(), "rdp hosts have no Ansible equivalent".to_string())]
+    );
+    let document: serde_yml::Value = serde_yml::from_str(&export.document).expect("parse ansible yaml");
+    let web1 = &document["all"]["children"]["prod"]["children"]["web"]["hosts"]["web1"];
+    assert_eq!(web1["ansible_host"].as_str(), Some("10.0.1.10"));
+    assert_eq!(web1["ansible_port"].as_u64(), Some(2222));
+    assert_eq!(
+        web1["ansible_ssh_common_args"].as_str(),
+        Some("-o ProxyJump=bastion -o ServerAliveInterval=30 -L 8080:localhost:80")
+    );
+    assert_eq!(document["all"]["hosts"]["bastion"]["ansible_user"].as_str(), Some("ops"));
+}
+
+#[test]
+fn csv_and_json_exports_use_source_field_names() {
+    let workspace = TestWorkspace::new("inventory", "export_tables").expect("temp workspace");
+    let tree = exported_inventory(&workspace);
+
+    let csv = render_inventory_export(&tree, InventoryExportFormat::Csv, None).document;
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("name,host,user,port,protocol,folder,description,profile,vault_pass,proxy_jump,identity_file,hidden")
+    );
+    assert!(csv.contains("\nweb1,10.0.1.10,,2222,ssh,Prod/Web,,,,bastion,"));
+    assert!(csv.contains("\ndesktop,desktop.example.com,,,rdp,Prod/Web,,,,,,true\n"));
+
+    let json = render_inventory_export(&tree, InventoryExportFormat::Json, None);
+    assert_eq!(json.hosts_exported, 5);
+    let records: serde_json::Value = serde_json::from_str(&json.document).expect("parse json export");
+    let web1 = records
+        .as_array()
+        .and_then(|records| records.iter().find(|record| record["name"] == "web1"))
+        .expect("web1 record");
+    assert_eq!(web1["folder"], "Prod/Web");
+    assert_eq!(web1["port"], 2222);
+    assert_eq!(web1["local_forward"][0], "8080:localhost:80");
+    assert_eq!(web1["ssh_options"]["ServerAliveInterval"][0], "30");
+}
+
+#[test]
+fn forward_specs_split_into_listen_and_destination_arguments() {
+    assert_eq!(ssh_forward_arguments("8080:localhost:80"), "8080 localhost:80");
+    assert_eq!(ssh_forward_arguments("127.0.0.1:8080:db.internal:5432"), "127.0.0.1:8080 db.internal:5432");
+    assert_eq!(ssh_forward_arguments("8080:[::1]:80"), "8080 [::1]:80");
+    assert_eq!(ssh_forward_arguments("8080 localhost:80"), "8080 localhost:80");
+    assert_eq!(ssh_forward_arguments("1080"), "1080");
+}
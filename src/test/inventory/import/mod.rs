@@ -0,0 +1,300 @@
+use super::remmina::split_server_port;
+use super::*;
+use crate::inventory::{InventoryHost, InventoryTreeModel};
+use crate::test::support::fs::TestWorkspace;
+
+fn host_named<'a>(tree: &'a InventoryTreeModel, name: &str) -> &'a InventoryHost {
+    tree.hosts
+        .iter()
+        .find(|host| host.name == name)
+        .unwrap_or_else(|| panic!("missing host '{name}'"))
+}
+
+fn folder_path(host: &InventoryHost) -> Vec<&str> {
+    host.source_folder_path.iter().map(String::as_str).collect()
+}
+
+fn import_options(format: SessionImportFormat, path: PathBuf, inventory_path: &Path) -> SessionImportOptions {
+    SessionImportOptions {
+        format,
+        path,
+        inventory_path: Some(inventory_path.to_path_buf()),
+        folder: None,
+        on_conflict: ImportConflict::Skip,
+        dry_run: false,
+    }
+}
+
+#[test]
+fn putty_registry_export_imports_ssh_sessions_from_utf16() {
+    let workspace = TestWorkspace::new("inventory", "import_putty").expect("temp workspace");
+    let export = r#"Windows Registry Editor Version 5.00
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions]
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Default%20Settings]
+"HostName"=""
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\core%20switch]
+"HostName"="admin@10.0.0.1"
+"PortNumber"=dword:00000016
+"Protocol"="ssh"
+"AgentFwd"=dword:00000001
+"PortForwardings"="L8080=localhost:80,4R2222=127.0.0.1:22,D1080"
+"PublicKeyFile"="C:\\Users\\ops\\key.ppk"
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\lab]
+"HostName"="lab.example.com"
+"UserName"="lab"
+"PortNumber"=dword:000008ae
+"Protocol"="ssh"
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\console]
+"HostName"="COM3"
+"Protocol"="serial"
+"#;
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(export.replace('\n', "\r\n").encode_utf16().flat_map(u16::to_le_bytes));
+    let export_path = workspace.join("putty.reg");
+    fs::write(&export_path, bytes).expect("write export");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+
+    let summary = import_sessions(&import_options(SessionImportFormat::Putty, export_path, &inventory_path)).expect("import putty");
+
+    assert_eq!(summary.hosts.len(), 2);
+    assert_eq!(
+        summary.unsupported,
+        vec![("console".to_string(), "serial sessions are not supported".to_string())]
+    );
+    let tree = build_inventory_tree(&inventory_path).expect("load inventory");
+    let core = host_named(&tree, "core switch");
+    assert_eq!(folder_path(core), vec!["PuTTY"]);
+    assert_eq!((core.host.as_str(), core.user.as_deref(), core.port), ("10.0.0.1", Some("admin"), None));
+    assert_eq!(core.ssh.forward_agent.as_deref(), Some("yes"));
+    assert_eq!(core.ssh.local_forward, vec!["8080:localhost:80".to_string()]);
+    assert_eq!(core.ssh.remote_forward, vec!["2222:127.0.0.1:22".to_string()]);
+    assert_eq!(core.ssh.extra_options.get("DynamicForward"), Some(&vec!["1080".to_string()]));
+    assert!(core.ssh.identity_files.is_empty(), ".ppk keys cannot be used by OpenSSH");
+    let lab = host_named(&tree, "lab");
+    assert_eq!((lab.user.as_deref(), lab.port), (Some("lab"), Some(2222)));
+}
+
+#[test]
+fn mobaxterm_bookmarks_keep_their_folders() {
+    let workspace = TestWorkspace::new("inventory", "import_mobaxterm").expect("temp workspace");
+    let export_path = workspace
+        .write_rel(
+            "sessions.mxtsessions",
+            "[Bookmarks]\r\nSubRep=\r\nImgNum=42\r\njump=#109#0%jump.example.com%22%ops%%-1%-1%%%%%0%0%0%%%-1%0%0%0%%1080%%0%0%1#MobaFont%10%0%0%-1%15%236,236,236%30,30,30%180,180,192%0%-1%0%%xterm%-1%0%_Std_Colors_0_%80%24%0%1%-1%<none>%%0%0%-1#0# #-1\r\n\r\n\
+             [Bookmarks_1]\r\nSubRep=Customers\\Acme\r\nImgNum=41\r\nacme-dc=#91#4%dc01.acme.local%3390%administrator%-1%0%0%0%-1%0%0%-1#MobaFont%10#0# #-1\r\nacme-vnc=#128#5%vnc.acme.local%5900%%-1#MobaFont#0# #-1\r\n",
+        )
+        .expect("write export");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+    let mut options = import_options(SessionImportFormat::MobaXterm, export_path, &inventory_path);
+    options.folder = Some(vec!["Imported".to_string(), "Moba".to_string()]);
+
+    let summary = import_sessions(&options).expect("import mobaxterm");
+
+    assert_eq!(summary.unsupported.len(), 1);
+    assert!(summary.unsupported[0].1.contains("session type 5"));
+    let tree = build_inventory_tree(&inventory_path).expect("load inventory");
+    let jump = host_named(&tree, "jump");
+    assert_eq!(folder_path(jump), vec!["Imported", "Moba"]);
+    assert_eq!((jump.host.as_str(), jump.user.as_deref(), jump.port), ("jump.example.com", Some("ops"), None));
+    let dc = host_named(&tree, "acme-dc");
+    assert_eq!(folder_path(dc), vec!["Imported", "Moba", "Customers", "Acme"]);
+    assert_eq!(dc.protocol, ConnectionProtocol::Rdp);
+    assert_eq!((dc.user.as_deref(), dc.port), (Some("administrator"), Some(3390)));
+}
+
+#[test]
+fn remmina_profiles_map_rdp_settings_to_freerdp_args() {
+    let workspace = TestWorkspace::new("inventory", "import_remmina").expect("temp workspace");
+    workspace
+        .write_rel(
+            "remmina/1700000000000.remmina",
+            "[remmina]\nname=Finance desktop\nprotocol=RDP\nserver=[2001:db8::10]:3390\nusername=jdoe\ndomain=CORP\ngroup=Office/Finance\n\
+             resolution_mode=2\nresolution_width=1920\nresolution_height=1080\ncolordepth=32\nsecurity=nla\n\
+             gateway_usage=1\ngateway_server=gw.corp.example\ngateway_username=jdoe\nsharefolder=/home/jdoe/share\ndisableclipboard=1\nsound=local\n",
+        )
+        .expect("write rdp profile");
+    workspace
+        .write_rel(
+            "remmina/1700000000001.remmina",
+            "[remmina]\nname=build box\nprotocol=SSH\nserver=build.example:2201\nusername=ci\nssh_privatekey=/home/jdoe/.ssh/ci_ed25519\n",
+        )
+        .expect("write ssh profile");
+    workspace
+        .write_rel("remmina/1700000000002.remmina", "[remmina]\nname=kiosk\nprotocol=VNC\nserver=kiosk:5900\n")
+        .expect("write vnc profile");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+
+    let summary = import_sessions(&import_options(SessionImportFormat::Remmina, workspace.join("remmina"), &inventory_path)).expect("import remmina");
+
+    assert_eq!(
+        summary.unsupported,
+        vec![("kiosk".to_string(), "VNC connections are not supported".to_string())]
+    );
+    let tree = build_inventory_tree(&inventory_path).expect("load inventory");
+    let desktop = host_named(&tree, "Finance desktop");
+    assert_eq!(folder_path(desktop), vec!["Remmina", "Office", "Finance"]);
+    assert_eq!((desktop.host.as_str(), desktop.port), ("2001:db8::10", Some(3390)));
+    assert_eq!(desktop.rdp.domain.as_deref(), Some("CORP"));
+    assert_eq!(
+        desktop.rdp.args,
+        vec![
+            "/size:1920x1080",
+            "/bpp:32",
+            "/sec:nla",
+            "/g:gw.corp.example",
+            "/gu:jdoe",
+            "/drive:share,/home/jdoe/share",
+            "-clipboard",
+            "/sound"
+        ]
+    );
+    let build = host_named(&tree, "build box");
+    assert_eq!((build.protocol.clone(), build.port), (ConnectionProtocol::Ssh, Some(2201)));
+    assert_eq!(build.ssh.identity_files, vec!["/home/jdoe/.ssh/ci_ed25519".to_string()]);
+
+    assert_eq!(split_server_port("rdp.example"), ("rdp.example", None));
+    assert_eq!(split_server_port("2001:db8::1"), ("2001:db8::1", None));
+}
+
+#[test]
+fn securecrt_xml_export_and_session_directory_import_the_same_hosts() {
+    let workspace = TestWorkspace::new("inventory", "import_securecrt").expect("temp workspace");
+    let xml_path = workspace
+        .write_rel(
+            "securecrt.xml",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<VanDyke version="3.0">
+  <key name="Sessions">
+    <key name="Default">
+      <string name="Hostname"></string>
+    </key>
+    <key name="Routers &amp; Switches">
+      <key name="edge-rtr">
+        <string name="Hostname">edge.example.net</string>
+        <string name="Protocol Name">SSH2</string>
+        <dword name="[SSH2] Port">2222</dword>
+        <string name="Username">netops</string>
+        <string name="Identity Filename V2">/home/ops/.ssh/id_ed25519::rawkey</string>
+        <array name="Description">
+          <string>Edge router</string>
+          <string>rack 4</string>
+        </array>
+      </key>
+    </key>
+    <key name="legacy">
+      <string name="Hostname">legacy.example.net</string>
+      <string name="Protocol Name">Telnet</string>
+    </key>
+  </key>
+</VanDyke>
+"#,
+        )
+        .expect("write xml export");
+    workspace.write_rel("Sessions/Default.ini", "S:\"Hostname\"=\n").expect("write default session");
+    workspace
+        .write_rel("Sessions/Routers & Switches/__FolderData__.ini", "S:\"Folder List\"=\n")
+        .expect("write folder data");
+    workspace
+        .write_rel(
+            "Sessions/Routers & Switches/edge-rtr.ini",
+            "S:\"Hostname\"=edge.example.net\nS:\"Protocol Name\"=SSH2\nD:\"[SSH2] Port\"=000008ae\nS:\"Username\"=netops\n\
+             S:\"Identity Filename V2\"=/home/ops/.ssh/id_ed25519::rawkey\nZ:\"Description\"=00000002\n Edge router\n rack 4\n\
+             B:\"Key Exchange\"=00000010\n 00 01 02 03\n",
+        )
+        .expect("write session ini");
+
+    for (prefix, path) in [("xml", xml_path), ("ini", workspace.join("Sessions"))] {
+        let inventory_path = workspace.join(&format!("{prefix}-inventory.yaml"));
+        let summary = import_sessions(&import_options(SessionImportFormat::SecureCrt, path, &inventory_path)).expect("import securecrt");
+        assert_eq!(summary.hosts.len(), 1, "{prefix}: only edge-rtr is importable");
+
+        let tree = build_inventory_tree(&inventory_path).expect("load inventory");
+        let edge = host_named(&tree, "edge-rtr");
+        assert_eq!(folder_path(edge), vec!["SecureCRT", "Routers & Switches"], "{prefix}");
+        assert_eq!(
+            (edge.host.as_str(), edge.user.as_deref(), edge.port),
+            ("edge.example.net", Some("netops"), Some(2222))
+        );
+        assert_eq!(edge.description.as_deref(), Some("Edge router rack 4"));
+        assert_eq!(edge.ssh.identity_files, vec!["/home/ops/.ssh/id_ed25519".to_string()]);
+    }
+}
+
+#[test]
+fn termius_csv_and_json_exports_match_columns_loosely() {
+    let csv = termius::parse_termius_sessions(
+        "\u{feff}Label,Hostname / IP,Port,Username,Group,Protocol\r\nweb,web.example.com,22,deploy,Prod/Web,ssh\r\n,10.0.0.9,,,,\r\nold,old.example.com,23,,,telnet\r\n",
+    )
+    .expect("parse csv export");
+    assert_eq!(csv.sessions.len(), 2);
+    assert_eq!(csv.sessions[0].folder_path, vec!["Prod".to_string(), "Web".to_string()]);
+    assert_eq!(csv.sessions[0].host.user.as_deref(), Some("deploy"));
+    assert_eq!(csv.sessions[0].host.port, None);
+    assert_eq!(
+        (csv.sessions[1].host.name.as_str(), csv.sessions[1].host.host.as_str()),
+        ("10.0.0.9", "10.0.0.9")
+    );
+    assert_eq!(csv.unsupported, vec![("old".to_string(), "telnet hosts are not supported".to_string())]);
+
+    let json = termius::parse_termius_sessions(
+        r#"{"hosts": [{"label": "db", "address": "db.internal", "port": 2200, "group": {"name": "Data"}}, {"label": "orphan"}]}"#,
+    )
+    .expect("parse json export");
+    assert_eq!(json.sessions[0].host.port, Some(2200));
+    assert_eq!(json.sessions[0].folder_path, vec!["Data".to_string()]);
+    assert_eq!(json.unsupported, vec![("orphan".to_string(), "no host name".to_string())]);
+
+    assert!(termius::parse_termius_sessions("[1]").unwrap_err().contains("record 1"));
+}
+
+#[test]
+fn name_conflicts_are_skipped_renamed_or_replaced() {
+    let workspace = TestWorkspace::new("inventory", "import_conflicts").expect("temp workspace");
+    let export_path = workspace
+        .write_rel(
+            "hosts.csv",
+            "name,host,user\nweb,web-new.example.com,deploy\nweb,web-dup.example.com,\ndb,db.example.com,\n",
+        )
+        .expect("write export");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+    let original_inventory = "inventory:\n  - name: web\n    host: web-old.example.com\n  - name: web-2\n    host: web2.example.com\n";
+    workspace.write(&inventory_path, original_inventory).expect("write inventory");
+    let mut options = import_options(SessionImportFormat::Termius, export_path, &inventory_path);
+
+    options.dry_run = true;
+    let summary = import_sessions(&options).expect("dry run");
+    let actions: Vec<_> = summary.hosts.iter().map(|host| (host.name.as_str(), host.action.clone())).collect();
+    assert_eq!(
+        actions,
+        vec![
+            ("web", ImportedHostAction::Skipped),
+            ("web", ImportedHostAction::Skipped),
+            ("db", ImportedHostAction::Added)
+        ]
+    );
+    assert_eq!(fs::read_to_string(&inventory_path).expect("read inventory"), original_inventory);
+
+    options.dry_run = false;
+    options.on_conflict = ImportConflict::Rename;
+    options.folder = Some(Vec::new());
+    let summary = import_sessions(&options).expect("rename import");
+    let names: Vec<_> = summary.hosts.iter().map(|host| host.name.as_str()).collect();
+    assert_eq!(names, vec!["web-3", "web-4", "db"], "web-2 is already taken");
+    let tree = build_inventory_tree(&inventory_path).expect("load inventory");
+    assert_eq!(host_named(&tree, "web").host, "web-old.example.com");
+    assert_eq!(host_named(&tree, "web-3").host, "web-new.example.com");
+    assert!(folder_path(host_named(&tree, "web-3")).is_empty());
+
+    options.on_conflict = ImportConflict::Replace;
+    let summary = import_sessions(&options).expect("replace import");
+    assert_eq!(summary.hosts[0].action, ImportedHostAction::Replaced(inventory_path.clone()));
+    assert_eq!(summary.hosts[1].action, ImportedHostAction::Skipped, "a name is replaced at most once");
+    let tree = build_inventory_tree(&inventory_path).expect("reload inventory");
+    let web = host_named(&tree, "web");
+    assert_eq!((web.host.as_str(), web.user.as_deref()), ("web-new.example.com", Some("deploy")));
+}
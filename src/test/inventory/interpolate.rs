@@ -0,0 +1,35 @@
+use super::*;
+
+const CONTEXT: InterpolationContext<'static> = InterpolationContext {
+    name: "core-sw1",
+    folder: Some("core"),
+};
+
+#[test]
+fn interpolate_expands_name_folder_and_environment_variables() {
+    assert_eq!(interpolate("${name}.${folder}.example", CONTEXT).unwrap(), "core-sw1.core.example");
+    assert_eq!(interpolate("${ name }", CONTEXT).unwrap(), "core-sw1");
+    let home = env::var("HOME").expect("HOME is set");
+    assert_eq!(interpolate("${env:HOME}/keys", CONTEXT).unwrap(), format!("{home}/keys"));
+}
+
+#[test]
+fn interpolate_keeps_escapes_and_bare_dollars_literal() {
+    assert_eq!(interpolate("$${name} costs $5", CONTEXT).unwrap(), "${name} costs $5");
+    assert!(!has_interpolation("plain $value"));
+    assert!(has_interpolation("$${name}"));
+}
+
+#[test]
+fn interpolate_rejects_unknown_unterminated_and_unavailable_variables() {
+    let root = InterpolationContext { name: "edge1", folder: None };
+
+    assert!(interpolate("${host}", CONTEXT).unwrap_err().contains("unknown variable '${host}'"));
+    assert!(interpolate("${name", CONTEXT).unwrap_err().contains("unterminated"));
+    assert!(interpolate("${folder}", root).unwrap_err().contains("only available to hosts inside a folder"));
+    assert!(
+        interpolate("${env:COSSH_TEST_UNSET_VARIABLE}", CONTEXT)
+            .unwrap_err()
+            .contains("'COSSH_TEST_UNSET_VARIABLE' is not set")
+    );
+}
@@ -0,0 +1,139 @@
+use super::{
+    InventoryFolderLocation, add_inventory_host, list_inventory_hosts, locate_inventory_folder, make_inventory_folder, move_inventory_host,
+    remove_inventory_host, render_inventory_host_details, render_inventory_host_table, set_inventory_host,
+};
+use crate::inventory::{InventoryTreeModel, build_inventory_tree};
+use crate::test::support::fs::TestWorkspace;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn assignments(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+}
+
+fn segments(path: &[&str]) -> Vec<String> {
+    path.iter().map(|segment| segment.to_string()).collect()
+}
+
+fn host_names(tree: &InventoryTreeModel, folder: Option<&str>, protocol: Option<&str>) -> Vec<String> {
+    list_inventory_hosts(tree, folder, protocol)
+        .expect("list hosts")
+        .into_iter()
+        .map(|entry| entry.host.name.clone())
+        .collect()
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().expect("canonical path")
+}
+
+fn write_inventory(workspace: &TestWorkspace) -> (PathBuf, PathBuf) {
+    let inventory_path = workspace
+        .write_rel(
+            "cossh-inventory.yaml",
+            r#"
+include:
+  - ./prod.yaml
+inventory:
+  - name: bastion
+    host: bastion.example
+  - Lab:
+      - name: lab-desktop
+        protocol: rdp
+        host: 10.0.0.20
+"#,
+        )
+        .expect("write inventory");
+    let include_path = workspace
+        .write_rel(
+            "prod.yaml",
+            r#"
+inventory:
+  - Web:
+      - name: web01
+        host: web01.example
+        user: deploy
+        port: 2222
+"#,
+        )
+        .expect("write include");
+    (inventory_path, include_path)
+}
+
+#[test]
+fn locate_inventory_folder_maps_tree_paths_to_their_inventory_file() {
+    let workspace = TestWorkspace::new("inventory", "manage_locate").expect("temp workspace");
+    let (inventory_path, include_path) = write_inventory(&workspace);
+    let tree = build_inventory_tree(&inventory_path).expect("load inventory");
+
+    let location = |source_file: &Path, folder_path: &[&str], exists: bool| InventoryFolderLocation {
+        source_file: canonical(source_file),
+        folder_path: segments(folder_path),
+        exists,
+    };
+    assert_eq!(locate_inventory_folder(&tree, "/"), location(&inventory_path, &[], true));
+    assert_eq!(locate_inventory_folder(&tree, "Lab"), location(&inventory_path, &["Lab"], true));
+    assert_eq!(locate_inventory_folder(&tree, "/prod/Web/"), location(&include_path, &["Web"], true));
+    assert_eq!(
+        locate_inventory_folder(&tree, "prod/Db/Primary"),
+        location(&include_path, &["Db", "Primary"], false)
+    );
+}
+
+#[test]
+fn list_and_render_filter_by_folder_and_protocol() {
+    let workspace = TestWorkspace::new("inventory", "manage_list").expect("temp workspace");
+    let (inventory_path, _) = write_inventory(&workspace);
+    let tree = build_inventory_tree(&inventory_path).expect("load inventory");
+
+    assert_eq!(host_names(&tree, None, None), vec!["bastion", "lab-desktop", "web01"]);
+    assert_eq!(host_names(&tree, Some("prod"), None), vec!["web01"]);
+    assert_eq!(host_names(&tree, None, Some("RDP")), vec!["lab-desktop"]);
+    assert!(list_inventory_hosts(&tree, Some("Missing"), None).is_err());
+
+    let entries = list_inventory_hosts(&tree, Some("prod"), None).expect("list hosts");
+    assert_eq!(
+        render_inventory_host_table(&entries),
+        "NAME   PROTOCOL  HOST           USER    PORT  FOLDER\nweb01  ssh       web01.example  deploy  2222  /prod/Web\n"
+    );
+    let details = render_inventory_host_details(&entries[0]);
+    assert!(details.starts_with("name: web01\nhost: web01.example\nprotocol: ssh\nfolder: prod/Web\n"));
+    assert!(details.contains("source_file: "));
+}
+
+#[test]
+fn mutations_write_to_the_file_that_owns_the_folder_or_host() {
+    let workspace = TestWorkspace::new("inventory", "manage_mutations").expect("temp workspace");
+    let (inventory_path, include_path) = write_inventory(&workspace);
+    let reload = || build_inventory_tree(&inventory_path).expect("reload inventory");
+
+    let written = add_inventory_host(&reload(), "db01", &assignments(&[("host", "10.0.1.5"), ("user", "postgres")]), "prod/Db").expect("add host");
+    assert_eq!(written, canonical(&include_path));
+    assert!(add_inventory_host(&reload(), "web01", &[], "/").is_err(), "duplicate names are rejected");
+    assert!(add_inventory_host(&reload(), "bad name", &[], "/").is_err());
+    let before = fs::read_to_string(&inventory_path).expect("read inventory");
+    assert!(add_inventory_host(&reload(), "no-host", &assignments(&[("user", "root")]), "Lab").is_err());
+    assert_eq!(
+        fs::read_to_string(&inventory_path).expect("read inventory"),
+        before,
+        "invalid hosts are rolled back"
+    );
+
+    set_inventory_host(&reload(), "db01", &assignments(&[("name", "db-primary"), ("user", ""), ("port", "5432")])).expect("set fields");
+    assert!(set_inventory_host(&reload(), "db-primary", &assignments(&[("name", "web01")])).is_err());
+
+    move_inventory_host(&reload(), "web01", "Lab/Web").expect("move host");
+    make_inventory_folder(&reload(), "prod/Cache").expect("make folder");
+    assert!(make_inventory_folder(&reload(), "Lab").is_err());
+    remove_inventory_host(&reload(), "bastion").expect("remove host");
+
+    let tree = reload();
+    let db = tree.hosts.iter().find(|host| host.name == "db-primary").expect("renamed host");
+    assert_eq!((db.host.as_str(), db.user.as_deref(), db.port), ("10.0.1.5", None, Some(5432)));
+    assert_eq!(db.source_folder_path, segments(&["prod", "Db"]));
+    let web = tree.hosts.iter().find(|host| host.name == "web01").expect("moved host");
+    assert_eq!(web.source_file, canonical(&inventory_path));
+    assert_eq!(web.source_folder_path, segments(&["Lab", "Web"]));
+    assert!(tree.hosts.iter().all(|host| host.name != "bastion"));
+    assert!(fs::read_to_string(&include_path).expect("read include").contains("Cache: []"));
+}
@@ -41,7 +41,7 @@ Port 3390
     let summary = migrate_ssh_config_to_inventory(&ssh_config_path, &inventory_path).expect("migrate inventory");
 
     assert_eq!(summary.hosts_written, 3);
-    assert_eq!(summary.wildcard_aliases_skipped, 1);
+    assert_eq!(summary.wildcard_aliases_folded, 1);
 
     let tree = build_inventory_tree(&inventory_path).expect("load migrated inventory");
     assert_eq!(host_named(&tree, "app-a").protocol, ConnectionProtocol::Ssh);
@@ -50,3 +50,42 @@ Port 3390
     assert_eq!(desktop.protocol, ConnectionProtocol::Rdp);
     assert_eq!(desktop.rdp.domain.as_deref(), Some("ACME"));
 }
+
+#[test]
+fn migrate_folds_wildcard_and_match_settings_into_folder_defaults() {
+    let workspace = TestWorkspace::new("inventory", "migration_defaults").expect("temp workspace");
+    let ssh_config_path = workspace.join("config");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+
+    workspace
+        .write(
+            &ssh_config_path,
+            "Include conf.d/*.conf\n\nHost app\n    HostName app.internal\n    User admin\n\nMatch originalhost lab-*\n    ProxyJump bastion\n\nMatch exec \"test -f /etc/vpn\"\n    ProxyJump vpn-gw\n\nHost *\n    User deploy\n    ServerAliveInterval 30\n",
+        )
+        .expect("write ssh config");
+    workspace
+        .write_rel("conf.d/lab.conf", "Host lab-1 lab-2\n    HostName %h.lab.example\n")
+        .expect("write include");
+
+    let summary = migrate_ssh_config_to_inventory(&ssh_config_path, &inventory_path).expect("migrate inventory");
+    assert_eq!((summary.hosts_written, summary.match_blocks_skipped), (3, 1));
+
+    let rendered = std::fs::read_to_string(&inventory_path).expect("read inventory");
+    assert!(
+        rendered.starts_with("defaults:\n  ssh_options:\n    'serveraliveinterval': '30'\n\ninventory:\n"),
+        "{rendered}"
+    );
+    assert!(
+        rendered.contains("  - 'lab':\n      - defaults:\n          user: 'deploy'\n          proxy_jump: 'bastion'\n"),
+        "{rendered}"
+    );
+
+    let tree = build_inventory_tree(&inventory_path).expect("load migrated inventory");
+    let app = host_named(&tree, "app");
+    assert_eq!((app.user.as_deref(), app.ssh.proxy_jump.as_deref()), (Some("admin"), None));
+    assert_eq!(app.ssh.extra_options.get("serveraliveinterval"), Some(&vec!["30".to_string()]));
+
+    let lab = host_named(&tree, "lab-2");
+    assert_eq!(lab.host, "lab-2.lab.example");
+    assert_eq!((lab.user.as_deref(), lab.ssh.proxy_jump.as_deref()), (Some("deploy"), Some("bastion")));
+}
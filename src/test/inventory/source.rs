@@ -0,0 +1,195 @@
+use super::*;
+use crate::inventory::{InventoryHost, InventoryTreeModel, build_inventory_tree, refresh_inventory_tree};
+use crate::test::support::fs::TestWorkspace;
+
+const ANSIBLE_SCRIPT: &str = r#"[ "$1" = "--list" ] || exit 3
+echo run >> runs.log
+cat <<'JSON'
+{
+  "_meta": {
+    "hostvars": {
+      "core-sw1": {"ansible_host": "10.0.0.1"},
+      "lab-sw1": {"ansible_host": "10.9.0.1", "ansible_user": "lab"},
+      "jump": {"ansible_host": "jump.example", "ansible_port": 2222}
+    }
+  },
+  "all": {"children": ["ungrouped", "core"], "vars": {"ansible_user": "ops"}},
+  "core": {"hosts": ["core-sw1"], "children": ["lab"], "vars": {"ansible_user": "netops"}},
+  "lab": {"hosts": ["lab-sw1", "core-sw1"]},
+  "ungrouped": {"hosts": ["jump"]}
+}
+JSON"#;
+
+fn host_named<'a>(tree: &'a InventoryTreeModel, name: &str) -> &'a InventoryHost {
+    tree.hosts.iter().find(|host| host.name == name).expect("host should exist")
+}
+
+fn run_count(workspace: &TestWorkspace) -> usize {
+    fs::read_to_string(workspace.join("runs.log")).map(|runs| runs.lines().count()).unwrap_or(0)
+}
+
+fn command_source_workspace(prefix: &str) -> (TestWorkspace, PathBuf) {
+    let workspace = TestWorkspace::new("inventory", prefix).expect("temp workspace");
+    workspace.write_script("cmdb.sh", ANSIBLE_SCRIPT).expect("write source script");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+    workspace
+        .write(
+            &inventory_path,
+            r#"
+include:
+  - source:
+      name: cmdb
+      command: ./cmdb.sh
+      refresh: 1h
+inventory:
+  - name: local
+    host: local.example
+"#,
+        )
+        .expect("write inventory");
+    (workspace, inventory_path)
+}
+
+#[test]
+fn command_source_loads_ansible_groups_as_folders_and_reuses_cached_output() {
+    let (workspace, inventory_path) = command_source_workspace("source_ansible");
+
+    let tree = build_inventory_tree(&inventory_path).expect("load inventory");
+    let core_sw1 = host_named(&tree, "core-sw1");
+    assert_eq!(core_sw1.host, "10.0.0.1");
+    assert_eq!(core_sw1.user.as_deref(), Some("netops"));
+    assert_eq!(core_sw1.source_folder_path, vec!["cmdb".to_string(), "core".to_string()]);
+    assert_eq!(core_sw1.dynamic_source.as_deref(), Some("cmdb"));
+    let lab_sw1 = host_named(&tree, "lab-sw1");
+    assert_eq!(lab_sw1.source_folder_path, vec!["cmdb".to_string(), "core".to_string(), "lab".to_string()]);
+    assert_eq!(lab_sw1.user.as_deref(), Some("lab"));
+    let jump = host_named(&tree, "jump");
+    assert_eq!((jump.host.as_str(), jump.port, jump.user.as_deref()), ("jump.example", Some(2222), Some("ops")));
+    assert_eq!(jump.source_folder_path, vec!["cmdb".to_string()]);
+    assert_eq!(host_named(&tree, "local").dynamic_source, None);
+    assert!(tree.source_refresh_due.is_some());
+
+    build_inventory_tree(&inventory_path).expect("reload inventory");
+    assert_eq!(run_count(&workspace), 1, "cached output should be reused within the refresh interval");
+
+    refresh_inventory_tree(&inventory_path).expect("refresh inventory");
+    assert_eq!(run_count(&workspace), 2, "a refresh should run the command again");
+}
+
+#[test]
+fn failing_command_source_falls_back_to_cached_output_or_reports_the_error() {
+    let (workspace, inventory_path) = command_source_workspace("source_failure");
+    build_inventory_tree(&inventory_path).expect("load inventory");
+
+    workspace
+        .write_script("cmdb.sh", "echo 'cmdb unreachable' >&2\nexit 1")
+        .expect("replace source script");
+    let tree = refresh_inventory_tree(&inventory_path).expect("stale cache should be used");
+    assert_eq!(host_named(&tree, "core-sw1").host, "10.0.0.1");
+
+    fs::remove_dir_all(workspace.join("cache")).expect("remove source cache");
+    let message = build_inventory_tree(&inventory_path).expect_err("load should fail").to_string();
+    assert!(message.contains("inventory source 'cmdb'"), "unexpected error: {message}");
+    assert!(message.contains("cmdb unreachable"), "error should include the command's stderr: {message}");
+}
+
+#[test]
+fn csv_file_source_groups_devices_by_site() {
+    let workspace = TestWorkspace::new("inventory", "source_csv").expect("temp workspace");
+    workspace
+        .write_rel(
+            "exports/netbox.csv",
+            "\u{feff}Name,Status,Site,Primary IPv4,Description\r\n\
+             core-sw1,Active,DC1,10.0.0.1/24,\"Core switch, rack \"\"4\"\"\"\r\n\
+             edge1,Active,,192.0.2.1/32,\r\n\r\n",
+        )
+        .expect("write export");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+    workspace
+        .write(
+            &inventory_path,
+            r#"
+include:
+  Datacenters:
+    - source:
+        name: netbox
+        file: exports/netbox.csv
+"#,
+        )
+        .expect("write inventory");
+
+    let tree = build_inventory_tree(&inventory_path).expect("load inventory");
+    let core_sw1 = host_named(&tree, "core-sw1");
+    assert_eq!(core_sw1.host, "10.0.0.1");
+    assert_eq!(core_sw1.description.as_deref(), Some("Core switch, rack \"4\""));
+    assert_eq!(
+        core_sw1.source_folder_path,
+        vec!["Datacenters".to_string(), "netbox".to_string(), "DC1".to_string()]
+    );
+    let edge1 = host_named(&tree, "edge1");
+    assert_eq!(edge1.host, "192.0.2.1");
+    assert_eq!(edge1.source_folder_path, vec!["Datacenters".to_string(), "netbox".to_string()]);
+    assert_eq!(tree.source_refresh_due, None);
+}
+
+#[test]
+fn json_source_reads_netbox_api_results() {
+    let group = parse_json_source(
+        r#"{"count": 2, "results": [
+            {"name": "rtr1", "primary_ip": {"address": "10.1.1.1/24"}, "site": {"name": "Lab", "slug": "lab"}},
+            {"name": "rtr2", "primary_ip": null}
+        ]}"#,
+    )
+    .expect("parse export");
+
+    assert_eq!(group.hosts.len(), 1);
+    assert_eq!(group.hosts[0].get("host"), Some(&Value::String("rtr2".to_string())));
+    let (site, lab) = &group.children[0];
+    assert_eq!(site, "Lab");
+    assert_eq!(lab.hosts[0].get("host"), Some(&Value::String("10.1.1.1".to_string())));
+
+    assert!(
+        parse_json_source(r#"[{"primary_ip": "10.0.0.1"}]"#)
+            .unwrap_err()
+            .contains("record 1 has no name")
+    );
+    assert!(parse_csv_rows("name\n\"unterminated").unwrap_err().contains("quoted field"));
+}
+
+#[test]
+fn source_vault_pass_accepts_only_vault_entry_names() {
+    let group = parse_json_source(
+        r#"[
+            {"name": "rtr1", "vault_pass": "netops"},
+            {"name": "rtr2", "vault_pass": "cmd:touch /tmp/pwned"},
+            {"name": "rtr3", "vault_pass": "pass:network/rtr3"}
+        ]"#,
+    )
+    .expect("parse export");
+
+    let vault_pass = |index: usize| group.hosts[index].get("vault_pass").cloned();
+    assert_eq!(vault_pass(0), Some(Value::String("netops".to_string())));
+    assert_eq!(vault_pass(1), None);
+    assert_eq!(vault_pass(2), None);
+}
+
+#[test]
+fn source_entries_need_a_name_and_exactly_one_origin() {
+    let workspace = TestWorkspace::new("inventory", "source_invalid").expect("temp workspace");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+    for (source, expected) in [
+        (
+            "{name: cmdb, command: ./cmdb.sh, file: hosts.csv}",
+            "must set exactly one of 'command' or 'file'",
+        ),
+        ("{command: ./cmdb.sh}", "need a 'name'"),
+        ("{name: cmdb, file: hosts.csv, refresh: 5m}", "only apply to command sources"),
+        ("{name: cmdb, command: ./cmdb.sh, refresh: soon}", "source refresh 'soon'"),
+    ] {
+        workspace
+            .write(&inventory_path, &format!("include:\n  - source: {source}\n"))
+            .expect("write inventory");
+        let message = build_inventory_tree(&inventory_path).expect_err("load should fail").to_string();
+        assert!(message.contains(expected), "unexpected error for {source}: {message}");
+    }
+}
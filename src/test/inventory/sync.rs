@@ -0,0 +1,235 @@
+use super::{
+    HostSyncAction, SshConfigEdit, SyncFields, SyncSide, apply_inventory_sync, delete_inventory_host_entry, edit_ssh_config_text, plan_inventory_sync,
+    set_inventory_host_fields, sync_ssh_config_without_removals,
+};
+use crate::inventory::{InventoryHost, build_inventory_tree};
+use crate::test::support::fs::TestWorkspace;
+use serde_yml::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn synced_workspace(workspace: &TestWorkspace) -> (PathBuf, PathBuf) {
+    let ssh_config = workspace
+        .write_rel(
+            "ssh/config",
+            "# Personal hosts\nHost shared\n    HostName shared.example.com\n    Port 2200\n    User ops\n\nHost only-ssh\n    HostName 10.0.0.5\n    #_Desc Build box\n\n# Defaults\nHost *\n    ServerAliveInterval 30\n",
+        )
+        .expect("write ssh config");
+    let inventory = workspace
+        .write_rel(
+            "cossh-inventory.yaml",
+            r#"
+inventory:
+  - name: shared
+    host: shared.example.com
+    port: 2222
+    user: ops
+  - Lab:
+      - name: only-inventory
+        host: lab.example.com
+        local_forward: 8080:localhost:80
+"#,
+        )
+        .expect("write inventory");
+    (ssh_config, inventory)
+}
+
+fn inventory_host(inventory: &Path, name: &str) -> InventoryHost {
+    build_inventory_tree(inventory)
+        .expect("load inventory")
+        .hosts
+        .into_iter()
+        .find(|host| host.name == name)
+        .unwrap_or_else(|| panic!("missing inventory host '{name}'"))
+}
+
+fn action<'a>(plan: &'a super::InventorySyncPlan, name: &str) -> &'a HostSyncAction {
+    &plan
+        .hosts
+        .iter()
+        .find(|host| host.name == name)
+        .unwrap_or_else(|| panic!("no change for '{name}'"))
+        .action
+}
+
+#[test]
+fn first_sync_adds_hosts_both_ways_and_asks_about_every_difference() {
+    let workspace = TestWorkspace::new("inventory", "sync_first").expect("temp workspace");
+    let (ssh_config, inventory) = synced_workspace(&workspace);
+
+    let mut plan = plan_inventory_sync(&ssh_config, &inventory).expect("plan sync");
+    assert!(!plan.has_snapshot);
+    assert_eq!(action(&plan, "only-ssh"), &HostSyncAction::AddToInventory);
+    assert_eq!(action(&plan, "only-inventory"), &HostSyncAction::AddToSshConfig);
+    let HostSyncAction::Update(fields) = action(&plan, "shared") else {
+        panic!("shared should differ");
+    };
+    assert_eq!(fields.len(), 1);
+    assert_eq!((fields[0].field.as_str(), fields[0].winner), ("port", None));
+
+    plan.resolve_conflicts(|_, _| Ok::<_, ()>(Some(SyncSide::Inventory))).expect("resolve");
+    let summary = apply_inventory_sync(&plan).expect("apply sync");
+    assert_eq!((summary.added_to_inventory, summary.added_to_ssh_config, summary.updated_hosts), (1, 1, 1));
+    assert_eq!(summary.backups.len(), 1);
+
+    let config = fs::read_to_string(&ssh_config).expect("read ssh config");
+    assert!(config.starts_with("# Personal hosts\nHost shared\n    HostName shared.example.com\n    Port 2222\n"));
+    assert!(
+        config.contains("Host only-inventory\n    HostName lab.example.com\n    LocalForward 8080 localhost:80\n\n# Defaults\nHost *\n"),
+        "new hosts go ahead of the wildcard defaults:\n{config}"
+    );
+    assert_eq!(inventory_host(&inventory, "only-ssh").description.as_deref(), Some("Build box"));
+
+    let plan = plan_inventory_sync(&ssh_config, &inventory).expect("plan again");
+    assert!(plan.has_snapshot);
+    assert!(plan.hosts.is_empty(), "unexpected changes: {:?}", plan.hosts);
+}
+
+#[test]
+fn later_syncs_merge_each_field_from_the_side_that_changed_it() {
+    let workspace = TestWorkspace::new("inventory", "sync_merge").expect("temp workspace");
+    let (ssh_config, inventory) = synced_workspace(&workspace);
+    let mut plan = plan_inventory_sync(&ssh_config, &inventory).expect("plan sync");
+    plan.resolve_conflicts(|_, _| Ok::<_, ()>(Some(SyncSide::Inventory))).expect("resolve");
+    apply_inventory_sync(&plan).expect("first sync");
+
+    let config = fs::read_to_string(&ssh_config).expect("read ssh config");
+    fs::write(&ssh_config, config.replace("    User ops", "    User admin").replace("Port 2222", "Port 2300")).expect("edit ssh config");
+    set_inventory_host_fields(
+        &inventory,
+        "shared",
+        &[
+            ("description".to_string(), Some(Value::String("Shared box".to_string()))),
+            ("port".to_string(), Some(Value::Number(2400.into()))),
+        ],
+    )
+    .expect("edit inventory");
+    delete_inventory_host_entry(&inventory, "only-inventory").expect("delete inventory host");
+
+    let plan = plan_inventory_sync(&ssh_config, &inventory).expect("plan sync");
+    assert_eq!(action(&plan, "only-inventory"), &HostSyncAction::RemoveFromSshConfig);
+    let HostSyncAction::Update(fields) = action(&plan, "shared") else {
+        panic!("shared should differ");
+    };
+    let winners: Vec<_> = fields.iter().map(|field| (field.field.as_str(), field.winner)).collect();
+    assert_eq!(
+        winners,
+        vec![("description", Some(SyncSide::Inventory)), ("port", None), ("user", Some(SyncSide::SshConfig))]
+    );
+
+    let summary = apply_inventory_sync(&plan).expect("apply sync");
+    assert_eq!(
+        (summary.removed_from_ssh_config, summary.updated_hosts, summary.unresolved_conflicts),
+        (1, 1, 1)
+    );
+    let config = fs::read_to_string(&ssh_config).expect("read ssh config");
+    assert!(config.contains("Host shared\n    HostName shared.example.com\n    Port 2300\n    User admin\n    #_Desc Shared box\n"));
+    assert!(!config.contains("only-inventory"));
+    let shared = inventory_host(&inventory, "shared");
+    assert_eq!((shared.user.as_deref(), shared.port), (Some("admin"), Some(2400)));
+
+    // The conflict stays open until one side is picked.
+    let plan = plan_inventory_sync(&ssh_config, &inventory).expect("plan again");
+    let HostSyncAction::Update(fields) = action(&plan, "shared") else {
+        panic!("port conflict should remain");
+    };
+    assert_eq!(fields.iter().map(|field| field.field.as_str()).collect::<Vec<_>>(), vec!["port"]);
+}
+
+#[test]
+fn missing_or_emptied_ssh_config_is_an_error_instead_of_a_mass_removal() {
+    let workspace = TestWorkspace::new("inventory", "sync_missing").expect("temp workspace");
+    let (ssh_config, inventory) = synced_workspace(&workspace);
+    let missing = workspace.join("ssh/missing-config");
+    assert_eq!(
+        plan_inventory_sync(&missing, &inventory).expect_err("missing config").kind(),
+        std::io::ErrorKind::NotFound
+    );
+
+    let mut plan = plan_inventory_sync(&ssh_config, &inventory).expect("plan sync");
+    plan.resolve_conflicts(|_, _| Ok::<_, ()>(Some(SyncSide::Inventory))).expect("resolve");
+    apply_inventory_sync(&plan).expect("first sync");
+
+    fs::write(&ssh_config, "").expect("empty ssh config");
+    assert_eq!(
+        plan_inventory_sync(&ssh_config, &inventory).expect_err("emptied config").kind(),
+        std::io::ErrorKind::InvalidData
+    );
+    inventory_host(&inventory, "shared");
+}
+
+#[test]
+fn automatic_sync_leaves_removals_for_an_explicit_sync() {
+    let workspace = TestWorkspace::new("inventory", "sync_auto_removals").expect("temp workspace");
+    let (ssh_config, inventory) = synced_workspace(&workspace);
+    let mut plan = plan_inventory_sync(&ssh_config, &inventory).expect("plan sync");
+    plan.resolve_conflicts(|_, _| Ok::<_, ()>(Some(SyncSide::Inventory))).expect("resolve");
+    apply_inventory_sync(&plan).expect("first sync");
+
+    let config = fs::read_to_string(&ssh_config).expect("read ssh config");
+    fs::write(&ssh_config, config.replace("Host only-ssh\n", "Host gone\n")).expect("rename ssh host");
+
+    let summary = sync_ssh_config_without_removals(&ssh_config, &inventory, SyncSide::Inventory).expect("automatic sync");
+    assert_eq!((summary.added_to_inventory, summary.deferred_removals), (1, 1));
+    inventory_host(&inventory, "only-ssh");
+
+    // The removal is still offered, not turned into an addition to the SSH config.
+    let plan = plan_inventory_sync(&ssh_config, &inventory).expect("plan again");
+    assert_eq!(action(&plan, "only-ssh"), &HostSyncAction::RemoveFromInventory);
+    apply_inventory_sync(&plan).expect("explicit sync");
+    assert!(
+        build_inventory_tree(&inventory)
+            .expect("load inventory")
+            .hosts
+            .iter()
+            .all(|host| host.name != "only-ssh")
+    );
+}
+
+#[test]
+fn changing_one_alias_of_a_shared_host_block_splits_it_out() {
+    let workspace = TestWorkspace::new("inventory", "sync_split").expect("temp workspace");
+    let ssh_config = workspace
+        .write_rel("ssh/config", "Host web1 web2\n    User deploy\n    HostName web.example.com\n")
+        .expect("write ssh config");
+    let inventory = workspace
+        .write_rel(
+            "cossh-inventory.yaml",
+            "inventory:\n  - name: web1\n    host: web.example.com\n    user: deploy\n  - name: web2\n    host: web.example.com\n    user: deploy\n",
+        )
+        .expect("write inventory");
+    apply_inventory_sync(&plan_inventory_sync(&ssh_config, &inventory).expect("plan")).expect("first sync");
+
+    set_inventory_host_fields(&inventory, "web1", &[("user".to_string(), Some(Value::String("root".to_string())))]).expect("edit inventory");
+    let plan = plan_inventory_sync(&ssh_config, &inventory).expect("plan sync");
+    apply_inventory_sync(&plan).expect("apply sync");
+
+    assert_eq!(
+        fs::read_to_string(&ssh_config).expect("read ssh config"),
+        "Host web2\n    User deploy\n    HostName web.example.com\n\nHost web1\n    HostName web.example.com\n    User root\n"
+    );
+}
+
+#[test]
+fn ssh_config_edits_keep_comments_spelling_and_indentation() {
+    let config = "Host db\n  # primary database\n  hostname db.internal\n  user postgres\n\nHost cache\n  HostName cache.internal\n\nHost old\n  HostName old.internal\n";
+    let fields = SyncFields::from([
+        ("host".to_string(), vec!["db.internal".to_string()]),
+        ("user".to_string(), vec!["admin".to_string()]),
+        ("port".to_string(), vec!["5433".to_string()]),
+    ]);
+    let edits = [
+        SshConfigEdit::Update {
+            name: "db",
+            changes: vec!["user", "port"],
+            fields,
+        },
+        SshConfigEdit::Remove { name: "cache" },
+    ];
+
+    assert_eq!(
+        edit_ssh_config_text(config, &edits, &HashMap::new()),
+        "Host db\n  # primary database\n  hostname db.internal\n  user admin\n  Port 5433\n\nHost old\n  HostName old.internal\n"
+    );
+}
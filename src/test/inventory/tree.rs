@@ -53,6 +53,8 @@ inventory:
     user: admin
     port: "22"
     Vault_pass: shared
+    VaultKey: switch-key-passphrase
+    vault-forward: yes
   - name: desktop01
     protocol: rdp
     host: 10.0.0.20
@@ -68,6 +70,8 @@ inventory:
     assert_eq!(switch.protocol, ConnectionProtocol::Ssh);
     assert_eq!(switch.host, "10.0.0.10");
     assert_eq!(switch.vault_pass.as_deref(), Some("shared"));
+    assert_eq!(switch.ssh.vault_key.as_deref(), Some("switch-key-passphrase"));
+    assert!(switch.ssh.vault_forward);
 
     let desktop = host_named(&tree, "desktop01");
     assert_eq!(desktop.protocol, ConnectionProtocol::Rdp);
@@ -161,3 +165,192 @@ inventory:
 
     assert_eq!(host_names, vec!["a", "b", "root"]);
 }
+
+#[test]
+fn build_inventory_tree_folder_defaults_inherit_with_inner_layers_winning() {
+    let fixture = InventoryFixture::new(
+        "defaults",
+        r#"
+defaults:
+  user: ops
+  ssh_options:
+    ServerAliveInterval: 30
+include:
+  Branches: branches.yaml
+inventory:
+  - core-switches:
+      - defaults:
+          user: netops
+          vault_pass: core-sw
+          proxy_jump: bastion
+          ssh_options:
+            StrictHostKeyChecking: "no"
+      - name: core-sw1
+        host: 10.0.0.1
+      - name: core-sw2
+        host: 10.0.0.2
+        user: admin
+        ssh_options:
+          StrictHostKeyChecking: ask
+      - lab:
+          - defaults:
+              proxy_jump: lab-jump
+          - name: lab-sw1
+            host: 10.9.0.1
+  - name: bastion
+    host: bastion.example
+"#,
+    );
+    fixture.write_rel(
+        "branches.yaml",
+        r#"
+defaults:
+  port: 2222
+inventory:
+  - name: branch-rtr1
+    host: 10.1.0.1
+"#,
+    );
+
+    let tree = fixture.load();
+
+    let core_sw1 = host_named(&tree, "core-sw1");
+    assert_eq!(core_sw1.user.as_deref(), Some("netops"));
+    assert_eq!(core_sw1.vault_pass.as_deref(), Some("core-sw"));
+    assert_eq!(core_sw1.ssh.proxy_jump.as_deref(), Some("bastion"));
+    assert_eq!(core_sw1.ssh.extra_options["StrictHostKeyChecking"], vec!["no".to_string()]);
+    assert_eq!(core_sw1.ssh.extra_options["ServerAliveInterval"], vec!["30".to_string()]);
+    assert!(core_sw1.is_inherited("user"));
+    assert!(core_sw1.is_inherited_ssh_option("ServerAliveInterval"));
+
+    let core_sw2 = host_named(&tree, "core-sw2");
+    assert_eq!(core_sw2.user.as_deref(), Some("admin"));
+    assert!(!core_sw2.is_inherited("user"));
+    assert_eq!(core_sw2.ssh.extra_options["StrictHostKeyChecking"], vec!["ask".to_string()]);
+    assert!(!core_sw2.is_inherited_ssh_option("StrictHostKeyChecking"));
+
+    let lab_sw1 = host_named(&tree, "lab-sw1");
+    assert_eq!(lab_sw1.ssh.proxy_jump.as_deref(), Some("lab-jump"));
+    assert_eq!(lab_sw1.user.as_deref(), Some("netops"));
+
+    let bastion = host_named(&tree, "bastion");
+    assert_eq!(bastion.user.as_deref(), Some("ops"));
+    assert_eq!(bastion.vault_pass, None);
+
+    let branch = host_named(&tree, "branch-rtr1");
+    assert_eq!((branch.user.as_deref(), branch.port), (Some("ops"), Some(2222)));
+    assert!(branch.is_inherited("port"));
+}
+
+#[test]
+fn build_inventory_tree_reads_an_empty_defaults_entry_as_a_folder() {
+    let fixture = InventoryFixture::new(
+        "defaults_empty_folder",
+        r#"
+inventory:
+  - lab:
+      - defaults:
+      - name: lab-sw1
+        host: 10.9.0.1
+"#,
+    );
+
+    // Only a mapping is a defaults block; `defaults` with no value is a folder without its item list.
+    let message = fixture.load_err_message();
+    assert!(message.contains("inventory must be a YAML list"), "unexpected error: {message}");
+}
+
+#[test]
+fn build_inventory_tree_rejects_per_host_fields_in_defaults() {
+    let fixture = InventoryFixture::new(
+        "defaults_invalid",
+        r#"
+inventory:
+  - lab:
+      - defaults:
+          host: 10.0.0.1
+      - name: lab-sw1
+        host: 10.9.0.1
+"#,
+    );
+
+    let message = fixture.load_err_message();
+    assert!(message.contains("defaults cannot set 'host'"), "unexpected error: {message}");
+}
+
+#[test]
+fn build_inventory_tree_templates_apply_before_defaults_and_interpolate_per_host() {
+    let fixture = InventoryFixture::new(
+        "templates",
+        r#"
+templates:
+  cisco-ios:
+    host: ${name}.mgmt.example
+    user: netops
+    description: ${folder} switch ${name}
+    ssh_options:
+      KexAlgorithms: +diffie-hellman-group14-sha1
+inventory:
+  - core:
+      - defaults:
+          user: ops
+          proxy_jump: jump-${folder}
+      - name: core-sw1
+        template: cisco-ios
+      - name: core-sw2
+        template: cisco-ios
+        host: 10.0.0.2
+        user: ${env:HOME}
+"#,
+    );
+
+    let tree = fixture.load();
+
+    let core_sw1 = host_named(&tree, "core-sw1");
+    assert_eq!(core_sw1.template.as_deref(), Some("cisco-ios"));
+    assert_eq!(core_sw1.host, "core-sw1.mgmt.example");
+    assert_eq!(core_sw1.user.as_deref(), Some("netops"));
+    assert_eq!(core_sw1.description.as_deref(), Some("core switch core-sw1"));
+    assert_eq!(core_sw1.ssh.proxy_jump.as_deref(), Some("jump-core"));
+    assert!(core_sw1.is_inherited("host"));
+    assert!(core_sw1.is_inherited_ssh_option("KexAlgorithms"));
+    assert!(core_sw1.templated_values.is_empty());
+
+    let core_sw2 = host_named(&tree, "core-sw2");
+    assert_eq!(core_sw2.host, "10.0.0.2");
+    assert_eq!(core_sw2.user, std::env::var("HOME").ok());
+    assert!(!core_sw2.is_inherited("user"));
+    assert_eq!(core_sw2.templated_values["user"], vec!["${env:HOME}".to_string()]);
+}
+
+#[test]
+fn build_inventory_tree_reports_unknown_templates_and_bad_variables() {
+    let fixture = InventoryFixture::new(
+        "templates_unknown",
+        r#"
+inventory:
+  - name: edge1
+    template: juniper
+"#,
+    );
+    let message = fixture.load_err_message();
+    assert!(
+        message.contains("inventory host 'edge1' uses unknown template 'juniper'"),
+        "unexpected error: {message}"
+    );
+
+    let fixture = InventoryFixture::new(
+        "templates_root_folder",
+        r#"
+inventory:
+  - name: edge1
+    host: ${name}.${folder}.example
+"#,
+    );
+    let message = fixture.load_err_message();
+    assert!(
+        message.contains("inventory host 'edge1' has an invalid 'host' value"),
+        "unexpected error: {message}"
+    );
+    assert!(message.contains("cossh-inventory.yaml"), "error should name the file: {message}");
+}
@@ -1,5 +1,8 @@
-use super::{build_ssh_command_for_host, synthesize_ssh_args};
+use super::{build_ssh_command_for_host, synthesize_ssh_args, vault_key_identity_agent_with_paths};
+use crate::auth::ipc;
 use crate::inventory::InventoryHost;
+use crate::test::support::auth::{TEST_ED25519_KEY, TestVaultEnv};
+use std::path::Path;
 
 fn assert_pair(args: &[String], flag: &str, value: &str) {
     assert!(
@@ -20,7 +23,7 @@ fn synthesize_ssh_args_inventory_defaults_and_cli_overrides_apply_expected_prece
     host.port = Some(2222);
     host.ssh.proxy_jump = Some("bastion".to_string());
 
-    let default_args = synthesize_ssh_args(&["switch".to_string()], &host);
+    let default_args = synthesize_ssh_args(&["switch".to_string()], &host, None);
     assert_pair(&default_args, "-l", "admin");
     assert_pair(&default_args, "-p", "2222");
     assert_contains(&default_args, "ProxyJump=bastion");
@@ -32,13 +35,42 @@ fn synthesize_ssh_args_inventory_defaults_and_cli_overrides_apply_expected_prece
         "ProxyJump=direct".to_string(),
         "user@switch".to_string(),
     ];
-    let overridden_args = synthesize_ssh_args(&cli_args, &host);
+    let overridden_args = synthesize_ssh_args(&cli_args, &host, None);
 
     assert_pair(&overridden_args, "-l", "override");
     assert_contains(&overridden_args, "ProxyJump=direct");
     assert_contains(&overridden_args, "user@10.0.0.10");
 }
 
+#[test]
+fn synthesize_ssh_args_forwards_the_vault_socket_only_for_opted_in_hosts() {
+    let mut host = InventoryHost::new("jump".to_string());
+    host.host = "jump.example".to_string();
+    let local_socket = Path::new("/run/cossh/vault-forward-0123456789abcdef.sock");
+
+    let plain_args = synthesize_ssh_args(&["jump".to_string()], &host, Some(local_socket));
+    assert!(
+        !plain_args.iter().any(|arg| arg.contains("cossh-vault-")),
+        "unexpected forward in {plain_args:?}"
+    );
+
+    host.ssh.vault_forward = true;
+    let forwarded_args = synthesize_ssh_args(&["jump".to_string()], &host, Some(local_socket));
+    assert_pair(
+        &forwarded_args,
+        "-R",
+        "/tmp/cossh-vault-forward-0123456789abcdef.sock:/run/cossh/vault-forward-0123456789abcdef.sock",
+    );
+    assert_contains(&forwarded_args, "SendEnv=COSSH_VAULT_FORWARD_SOCK");
+    assert!(
+        !forwarded_args.iter().any(|arg| arg.starts_with("StreamLocalBindUnlink")),
+        "a per-launch socket never replaces another session's"
+    );
+
+    let unavailable_args = synthesize_ssh_args(&["jump".to_string()], &host, None);
+    assert!(!unavailable_args.iter().any(|arg| arg == "-R"), "unexpected forward in {unavailable_args:?}");
+}
+
 #[test]
 fn build_ssh_command_for_host_uses_synthesized_inventory_defaults() {
     let mut host = InventoryHost::new("switch".to_string());
@@ -54,3 +86,16 @@ fn build_ssh_command_for_host_uses_synthesized_inventory_defaults() {
     assert_contains(&command.args, "10.0.0.10");
     assert!(command.stdin_payload.is_none());
 }
+
+#[test]
+fn vault_key_identity_agent_points_key_entries_at_the_vault_agent_socket() {
+    let env = TestVaultEnv::new("vault_key_agent");
+    let unlocked = env.init_and_unlock("master-pass");
+    unlocked.store_ssh_key("deploy-key", TEST_ED25519_KEY, Default::default()).expect("store key");
+    unlocked.store_secret("deploy-passphrase", "key-passphrase").expect("store passphrase");
+    let socket_path = ipc::ssh_agent_socket_path(env.paths()).to_string_lossy().into_owned();
+
+    assert_eq!(vault_key_identity_agent_with_paths(env.paths(), "deploy-key"), Some(socket_path));
+    assert_eq!(vault_key_identity_agent_with_paths(env.paths(), "deploy-passphrase"), None);
+    assert_eq!(vault_key_identity_agent_with_paths(env.paths(), "missing"), None);
+}
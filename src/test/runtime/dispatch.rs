@@ -145,6 +145,6 @@ fn protocol_command_for_non_interactive_requires_protocol_command() {
     assert!(protocol_command_for_non_interactive(&no_command).is_err());
 
     let mut vault_command = base_args(0, false, false);
-    vault_command.command = Some(MainCommand::Vault(VaultCommand::Status));
+    vault_command.command = Some(MainCommand::Vault(VaultCommand::Status(false)));
     assert!(protocol_command_for_non_interactive(&vault_command).is_err());
 }
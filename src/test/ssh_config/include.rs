@@ -7,6 +7,8 @@ fn matches_pattern_star_and_question_wildcards_match_expected_paths() {
         ("a1.conf", "a?.conf", true),
         ("abc.conf", "a?.conf", false),
         ("abc.txt", "*.conf", false),
+        ("abc", "abc*", true),
+        ("", "*", true),
     ];
 
     for (value, pattern, expected) in cases {
@@ -0,0 +1,139 @@
+use super::{SshConfigRules, SshMatchContext};
+use crate::test::support::fs::TestWorkspace;
+
+fn context(original_host: &str) -> SshMatchContext {
+    SshMatchContext {
+        local_user: "alice".to_string(),
+        ..SshMatchContext::new(original_host)
+    }
+}
+
+fn resolved(rules: &SshConfigRules, context: &SshMatchContext) -> Vec<(String, String)> {
+    rules
+        .resolve(context)
+        .options
+        .into_iter()
+        .map(|option| (option.keyword, option.value))
+        .collect()
+}
+
+fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+    expected.iter().map(|(keyword, value)| (keyword.to_string(), value.to_string())).collect()
+}
+
+#[test]
+fn host_patterns_apply_in_order_with_first_value_winning() {
+    let workspace = TestWorkspace::new("ssh_config", "resolve_hosts").expect("temp workspace");
+    let config_path = workspace
+        .write_rel(
+            "config",
+            "Compression yes\n\nHost web-* !web-legacy\n    User deploy\n    IdentityFile ~/.ssh/web\n\nHost web-1\n    HostName %h.example.com\n    User root\n    Include web.d/*.conf\n\nHost WEB-LEGACY\n    Port 2222\n\nHost *\n    User fallback\n    IdentityFile ~/.ssh/id_ed25519\n    ServerAliveInterval=30\n",
+        )
+        .expect("write config");
+    workspace
+        .write_rel("web.d/extra.conf", "ProxyJump bastion\n\nHost other\n    Port 2200\n")
+        .expect("write include");
+    let rules = SshConfigRules::load(&config_path).expect("load rules");
+
+    assert_eq!(
+        resolved(&rules, &context("web-1")),
+        pairs(&[
+            ("compression", "yes"),
+            ("user", "deploy"),
+            ("identityfile", "~/.ssh/web"),
+            ("hostname", "web-1.example.com"),
+            ("proxyjump", "bastion"),
+            ("identityfile", "~/.ssh/id_ed25519"),
+            ("serveraliveinterval", "30"),
+        ])
+    );
+    assert_eq!(
+        resolved(&rules, &context("web-legacy")),
+        pairs(&[
+            ("compression", "yes"),
+            ("port", "2222"),
+            ("user", "fallback"),
+            ("identityfile", "~/.ssh/id_ed25519"),
+            ("serveraliveinterval", "30"),
+        ])
+    );
+
+    let effective = rules.resolve(&context("db"));
+    assert_eq!((effective.hostname(), effective.user(), effective.port()), ("db", "fallback", "22"));
+    assert!(effective.options.iter().all(|option| option.shared));
+    let own: Vec<_> = rules
+        .resolve(&context("web-1"))
+        .options
+        .into_iter()
+        .filter(|option| !option.shared)
+        .map(|option| option.keyword)
+        .collect();
+    assert_eq!(own, vec!["hostname", "proxyjump"]);
+}
+
+#[test]
+fn match_criteria_follow_openssh_semantics() {
+    let workspace = TestWorkspace::new("ssh_config", "resolve_match").expect("temp workspace");
+    let config_path = workspace
+        .write_rel(
+            "config",
+            "Host db\n    HostName db.internal\n\nMatch host *.internal !originalhost db-admin\n    Port 5432\n\nMatch user root localuser alice\n    ForwardAgent no\n\nMatch exec \"exit 1\"\n    ProxyJump never\n\nMatch originalhost db* exec \"test %n = db\"\n    ProxyJump bastion\n\nMatch final\n    ControlMaster auto\n\nMatch localnetwork 10.0.0.0/8\n    Compression yes\n\nMatch all\n    User ops\n",
+        )
+        .expect("write config");
+    let rules = SshConfigRules::load(&config_path).expect("load rules");
+
+    assert_eq!(
+        resolved(&rules, &context("db")),
+        pairs(&[
+            ("hostname", "db.internal"),
+            ("port", "5432"),
+            ("proxyjump", "bastion"),
+            ("user", "ops"),
+            ("controlmaster", "auto"),
+        ])
+    );
+
+    // Command-line options win, and `Match user` sees the command-line user.
+    let as_root = SshMatchContext {
+        command_line_options: vec![("User".to_string(), "root".to_string())],
+        run_exec: false,
+        ..context("db")
+    };
+    assert_eq!(
+        resolved(&rules, &as_root),
+        pairs(&[
+            ("user", "root"),
+            ("hostname", "db.internal"),
+            ("port", "5432"),
+            ("forwardagent", "no"),
+            ("controlmaster", "auto"),
+        ])
+    );
+
+    assert_eq!(rules.unsupported_match_blocks(true), 1);
+    assert_eq!(rules.unsupported_match_blocks(false), 3);
+}
+
+#[test]
+fn system_config_is_read_after_the_user_config_from_a_fresh_section() {
+    let workspace = TestWorkspace::new("ssh_config", "resolve_system").expect("temp workspace");
+    let config_path = workspace
+        .write_rel("config", "Port 2222\n\nHost web\n    User deploy\n")
+        .expect("write user config");
+    let system_config_path = workspace
+        .write_rel("etc/ssh_config", "Include ssh_config.d/*.conf\n\nHost *\n    Port 22\n    User root\n")
+        .expect("write system config");
+    workspace
+        .write_rel("etc/ssh_config.d/defaults.conf", "SendEnv LANG\n")
+        .expect("write system include");
+
+    let rules = SshConfigRules::load_with_system_config(&config_path, &system_config_path).expect("load rules");
+
+    assert_eq!(
+        resolved(&rules, &context("db")),
+        pairs(&[("port", "2222"), ("sendenv", "LANG"), ("user", "root")])
+    );
+    assert_eq!(rules.resolve(&context("web")).user(), "deploy");
+    let missing_system = SshConfigRules::load_with_system_config(&config_path, &workspace.join("absent")).expect("load rules");
+    assert_eq!(resolved(&missing_system, &context("db")), pairs(&[("port", "2222")]));
+}
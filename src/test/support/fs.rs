@@ -1,5 +1,6 @@
 use std::fs;
 use std::io;
+use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -39,6 +40,12 @@ impl TestWorkspace {
         self.write(&path, contents)?;
         Ok(path)
     }
+
+    pub(crate) fn write_script(&self, rel: &str, body: &str) -> io::Result<PathBuf> {
+        let path = self.write_rel(rel, &format!("#!/bin/sh\n{body}\n"))?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o700))?;
+        Ok(path)
+    }
 }
 
 impl Drop for TestWorkspace {
@@ -416,6 +416,143 @@ inventory:
     assert!(!rendered.contains("profile: default"));
 }
 
+#[test]
+fn edit_entry_keeps_folder_defaults_inherited_on_save() {
+    let workspace = TestWorkspace::new("tui", "host_editor_inherited").expect("temp workspace");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+    workspace
+        .write(
+            &inventory_path,
+            r#"
+inventory:
+  - core:
+      - defaults:
+          user: netops
+          ssh_options:
+            ServerAliveInterval: 30
+      - name: alpha
+        protocol: ssh
+        host: alpha.example
+        ssh_options:
+          StrictHostKeyChecking: ask
+"#,
+        )
+        .expect("write inventory");
+
+    let mut app = AppState::new_for_tests();
+    seed_app_from_inventory(&mut app, &inventory_path);
+    app.set_selected_row(find_host_row(&app, "alpha"));
+
+    app.handle_manager_key(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::NONE))
+        .expect("open edit modal");
+
+    let form = app.selected_host_editor_mut().expect("host editor state");
+    assert_eq!(form.user.value, "");
+    assert_eq!(form.inherited_hint(HostEditorField::User), Some("netops"));
+    assert_eq!(form.ssh_options.value, r#"{"StrictHostKeyChecking":["ask"]}"#);
+    form.host.value = "alpha-updated.example".to_string();
+
+    app.submit_host_editor();
+
+    let host = app.hosts.iter().find(|host| host.name == "alpha").expect("updated host");
+    assert_eq!(host.user.as_deref(), Some("netops"));
+    assert!(host.is_inherited("user"));
+
+    let rendered = fs::read_to_string(&inventory_path).expect("read inventory");
+    assert_eq!(rendered.matches("netops").count(), 1, "inherited user was written to the host:\n{rendered}");
+    assert_eq!(rendered.matches("ServerAliveInterval").count(), 1);
+
+    app.set_selected_row(find_host_row(&app, "alpha"));
+    app.handle_manager_key(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::NONE))
+        .expect("reopen edit modal");
+    let form = app.selected_host_editor_mut().expect("host editor state");
+    form.user.value = "admin".to_string();
+    assert_eq!(form.inherited_hint(HostEditorField::User), None);
+}
+
+#[test]
+fn edit_entry_keeps_template_host_and_variables_on_save() {
+    let workspace = TestWorkspace::new("tui", "host_editor_template").expect("temp workspace");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+    workspace
+        .write(
+            &inventory_path,
+            r#"
+templates:
+  cisco-ios:
+    host: ${name}.mgmt.example
+inventory:
+  - name: alpha
+    template: cisco-ios
+    user: ${name}-admin
+"#,
+        )
+        .expect("write inventory");
+
+    let mut app = AppState::new_for_tests();
+    seed_app_from_inventory(&mut app, &inventory_path);
+    app.set_selected_row(find_host_row(&app, "alpha"));
+
+    app.handle_manager_key(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::NONE))
+        .expect("open edit modal");
+
+    let form = app.selected_host_editor_mut().expect("host editor state");
+    assert_eq!(form.host.value, "");
+    assert_eq!(form.inherited_hint(HostEditorField::Host), Some("alpha.mgmt.example"));
+    assert_eq!(form.user.value, "${name}-admin");
+    form.port.value = "2222".to_string();
+
+    app.submit_host_editor();
+
+    let host = app.hosts.iter().find(|host| host.name == "alpha").expect("updated host");
+    assert_eq!(host.host, "alpha.mgmt.example");
+    assert_eq!((host.user.as_deref(), host.port), (Some("alpha-admin"), Some(2222)));
+
+    let rendered = fs::read_to_string(&inventory_path).expect("read inventory");
+    assert!(rendered.contains("template: cisco-ios"), "template reference was dropped:\n{rendered}");
+    assert!(rendered.contains("${name}-admin"), "user variable was expanded on save:\n{rendered}");
+    assert_eq!(
+        rendered.matches("mgmt.example").count(),
+        1,
+        "template host was written to the host:\n{rendered}"
+    );
+}
+
+#[test]
+fn hosts_from_inventory_sources_are_not_edited_in_place() {
+    let workspace = TestWorkspace::new("tui", "host_editor_source").expect("temp workspace");
+    workspace
+        .write_rel("devices.csv", "name,host\ncmdb-sw1,10.0.0.1\n")
+        .expect("write source export");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+    workspace
+        .write(
+            &inventory_path,
+            r#"
+include:
+  - source:
+      name: cmdb
+      file: devices.csv
+"#,
+        )
+        .expect("write inventory");
+
+    let mut app = AppState::new_for_tests();
+    seed_app_from_inventory(&mut app, &inventory_path);
+    app.set_selected_row(find_host_row(&app, "cmdb-sw1"));
+
+    app.handle_manager_key(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::NONE))
+        .expect("handle edit key");
+
+    assert!(app.selected_host_editor().is_none());
+    let toast = app.reload_notice_toast.as_ref().expect("read-only notice");
+    assert!(
+        toast.message().contains("comes from inventory source 'cmdb'"),
+        "unexpected notice: {}",
+        toast.message()
+    );
+}
+
 #[test]
 fn edit_entry_selecting_default_profile_removes_profile_key() {
     let workspace = TestWorkspace::new("tui", "host_editor_profile_default_edit").expect("temp workspace");
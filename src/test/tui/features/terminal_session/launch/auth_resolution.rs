@@ -47,6 +47,30 @@ fn resolve_host_pass_password_for_rdp_with_tui_autologin_disabled_is_launchable(
     );
 }
 
+#[test]
+fn resolve_host_pass_password_with_external_provider_reference_skips_vault_unlock() {
+    let mut app = AppState::new_for_tests();
+    let mut host = sample_rdp_host();
+    host.vault_pass = Some("pass:windows/desktop01".to_string());
+    let action = VaultUnlockAction::OpenHostTab {
+        host: Box::new(host.clone()),
+        force_ssh_logging: false,
+        auth_settings: AuthSettings::default(),
+    };
+
+    let resolution = app.resolve_host_pass_password_with_autologin(&host, action, true);
+
+    assert_eq!(
+        resolution,
+        Some(HostPassResolution {
+            pass_entry_override: Some("pass:windows/desktop01".to_string()),
+            pass_fallback_notice: None,
+            disable_vault_autologin: false,
+            manual_rdp_password: None,
+        })
+    );
+}
+
 #[test]
 fn resolve_host_pass_password_uses_inventory_profile_auth_settings() {
     let workspace = TestWorkspace::new("tui", "profile_launch").expect("test workspace");
@@ -320,3 +320,150 @@ inventory:
             .is_some_and(|message| message.contains("failed to parse inventory YAML"))
     );
 }
+
+#[test]
+fn refresh_inventory_sources_runs_commands_off_the_event_loop() {
+    let workspace = TestWorkspace::new("tui", "inventory_source_refresh").expect("temp workspace");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+    workspace
+        .write(
+            &inventory_path,
+            r#"
+inventory:
+  - name: alpha
+    protocol: ssh
+    host: alpha.example
+"#,
+        )
+        .expect("write initial inventory");
+    let mut app = AppState::new_for_tests();
+    seed_app_from_inventory(&mut app, &inventory_path);
+
+    let release = workspace.join("release");
+    workspace
+        .write_script(
+            "cmdb.sh",
+            &format!(
+                "while [ ! -f '{}' ]; do sleep 0.05; done\necho '[{{\"name\": \"cmdb-sw1\", \"host\": \"10.0.0.1\"}}]'",
+                release.display()
+            ),
+        )
+        .expect("write source script");
+    workspace
+        .write(
+            &inventory_path,
+            r#"
+include:
+  - source:
+      name: cmdb
+      command: ./cmdb.sh
+inventory:
+  - name: alpha
+    protocol: ssh
+    host: alpha.example
+"#,
+        )
+        .expect("write inventory with source");
+
+    // The source command is still blocked, so returning at all shows it runs elsewhere.
+    app.refresh_inventory_sources();
+    app.apply_inventory_reload_notifications();
+    assert!(!app.hosts.iter().any(|host| host.name == "cmdb-sw1"));
+
+    std::fs::write(&release, "").expect("release source command");
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while !app.hosts.iter().any(|host| host.name == "cmdb-sw1") {
+        assert!(std::time::Instant::now() < deadline, "refreshed inventory was never applied");
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        app.apply_inventory_reload_notifications();
+    }
+    assert!(
+        app.reload_notice_toast
+            .as_ref()
+            .is_some_and(|toast| toast.message().contains("Inventory sources refreshed"))
+    );
+}
+
+#[test]
+fn reload_inventory_tree_from_path_never_runs_command_sources() {
+    let workspace = TestWorkspace::new("tui", "inventory_cached_reload").expect("temp workspace");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+    let ran = workspace.join("ran");
+    workspace
+        .write_script(
+            "cmdb.sh",
+            &format!("touch '{}'\necho '[{{\"name\": \"cmdb-sw1\", \"host\": \"10.0.0.1\"}}]'", ran.display()),
+        )
+        .expect("write source script");
+    workspace
+        .write(
+            &inventory_path,
+            r#"
+include:
+  - source:
+      name: cmdb
+      command: ./cmdb.sh
+inventory:
+  - name: alpha
+    protocol: ssh
+    host: alpha.example
+"#,
+        )
+        .expect("write inventory");
+    let mut app = AppState::new_for_tests();
+
+    app.reload_inventory_tree_from_path(&inventory_path).expect("reload inventory");
+
+    assert!(!ran.exists(), "a reload on the UI thread ran the source command");
+    assert!(app.hosts.iter().any(|host| host.name == "alpha"));
+    assert!(!app.hosts.iter().any(|host| host.name == "cmdb-sw1"));
+    assert!(app.inventory_source_refresh_due.is_some());
+}
+
+#[test]
+fn source_refresh_started_before_a_reload_is_dropped_for_the_reloaded_tree() {
+    let workspace = TestWorkspace::new("tui", "inventory_stale_refresh").expect("temp workspace");
+    let inventory_path = workspace.join("cossh-inventory.yaml");
+    let release = workspace.join("release");
+    workspace
+        .write_script(
+            "cmdb.sh",
+            &format!(
+                "while [ ! -f '{}' ]; do sleep 0.05; done\necho '[{{\"name\": \"cmdb-sw1\", \"host\": \"10.0.0.1\"}}]'",
+                release.display()
+            ),
+        )
+        .expect("write source script");
+    let inventory = r#"
+include:
+  - source:
+      name: cmdb
+      command: ./cmdb.sh
+inventory:
+  - name: alpha
+    protocol: ssh
+    host: alpha.example
+"#;
+    workspace.write(&inventory_path, inventory).expect("write inventory");
+    let mut app = AppState::new_for_tests();
+    app.reload_inventory_tree_from_path(&inventory_path).expect("load inventory");
+
+    app.refresh_inventory_sources();
+    workspace
+        .write(
+            &inventory_path,
+            &format!("{inventory}  - name: beta\n    protocol: ssh\n    host: beta.example\n"),
+        )
+        .expect("edit inventory while the source runs");
+    app.reload_inventory_tree_from_path(&inventory_path).expect("reload edited inventory");
+    assert!(app.hosts.iter().any(|host| host.name == "beta"));
+
+    std::fs::write(&release, "").expect("release source command");
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while !app.hosts.iter().any(|host| host.name == "cmdb-sw1") {
+        assert!(std::time::Instant::now() < deadline, "refreshed source output was never applied");
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        app.apply_inventory_reload_notifications();
+    }
+    assert!(app.hosts.iter().any(|host| host.name == "beta"), "a stale refresh replaced the reloaded tree");
+}
@@ -0,0 +1,114 @@
+use super::*;
+use crate::auth::secret::ExposeSecret;
+
+fn summary(name: &str, ssh_key: bool) -> VaultEntrySummary {
+    VaultEntrySummary {
+        name: name.to_string(),
+        updated_at: "2026-01-02T03:04:05+00:00".to_string(),
+        ssh_key,
+        user: None,
+    }
+}
+
+fn host(name: &str, vault_pass: Option<&str>, vault_key: Option<&str>) -> InventoryHost {
+    let mut host = InventoryHost::new(name.to_string());
+    host.vault_pass = vault_pass.map(str::to_string);
+    host.ssh.vault_key = vault_key.map(str::to_string);
+    host
+}
+
+#[test]
+fn reload_indexes_references_and_warns_about_missing_vault_pass_entries() {
+    let hosts = vec![
+        host("core-sw", Some("network"), None),
+        host("edge-sw", Some("network"), Some("deploy")),
+        host("web01", Some("op:Infra/web01/password"), None),
+        host("db01", Some("retired"), Some("gone-key")),
+    ];
+
+    let state = VaultManagerState::new(vec![summary("deploy", true), summary("network", false), summary("unused", false)], &hosts);
+
+    assert_eq!(state.entries[0].referenced_by, ["edge-sw"]);
+    assert_eq!(state.entries[1].referenced_by, ["core-sw", "edge-sw"]);
+    assert!(state.entries[2].referenced_by.is_empty());
+    assert_eq!(
+        state.missing_references,
+        [VaultMissingReference {
+            host_name: "db01".to_string(),
+            entry_name: "retired".to_string(),
+        }],
+        "external provider references and vault_key values are not reported as missing vault_pass entries"
+    );
+}
+
+#[test]
+fn reload_keeps_the_selected_entry_when_the_list_changes() {
+    let mut state = VaultManagerState::new(vec![summary("alpha", false), summary("beta", false), summary("gamma", false)], &[]);
+    state.select_entry_named("beta");
+
+    state.reload(vec![summary("aaa", false), summary("beta", false)], &[]);
+    assert_eq!(state.selected_entry().map(|entry| entry.name.as_str()), Some("beta"));
+
+    state.reload(vec![summary("aaa", false)], &[]);
+    assert_eq!(state.selected, 0, "selection is clamped when the selected entry disappears");
+}
+
+#[test]
+fn form_fields_depend_on_kind_and_edit_the_focused_buffer() {
+    let remove = VaultManagerForm::new(VaultManagerFormKind::Remove, Some("shared".to_string()));
+    assert_eq!(remove.fields(), [VaultManagerField::MasterPassword]);
+    assert!(remove.is_last_field_focused());
+
+    let mut rename = VaultManagerForm::new(VaultManagerFormKind::Rename, Some("shared".to_string()));
+    assert_eq!(rename.focused, VaultManagerField::Name);
+    assert_eq!(rename.name, "shared");
+    rename.insert_char('2');
+    assert_eq!(rename.name, "shared2");
+
+    let mut add = VaultManagerForm::new(VaultManagerFormKind::Add, None);
+    add.focus_next();
+    assert_eq!(add.focused, VaultManagerField::Secret);
+    for ch in "s3cret".chars() {
+        add.insert_char(ch);
+    }
+    add.move_cursor_home();
+    add.delete();
+    add.focus_next();
+    add.insert_char('m');
+    add.focus_next();
+    assert_eq!(add.focused, VaultManagerField::Name, "focus wraps to the first field");
+    add.focus_prev();
+    assert_eq!(add.focused, VaultManagerField::MasterPassword);
+
+    assert!(add.name.is_empty());
+    assert_eq!(add.secret.as_str().expect("secret text"), "3cret");
+    assert_eq!(add.secret.masked(), "*****");
+    let master_password = std::mem::take(&mut add.master_password).into_sensitive_string().expect("master password");
+    assert_eq!(master_password.expose_secret(), "m");
+}
+
+#[test]
+fn pending_change_reports_the_worker_result_once_it_finishes() {
+    let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+    let pending = PendingVaultManagerChange::spawn(
+        move || {
+            let _ = release_rx.recv();
+            Err("Invalid master password. Try again.".to_string())
+        },
+        Some("shared".to_string()),
+        "Added vault entry 'shared'.".to_string(),
+    )
+    .expect("spawn change");
+    assert_eq!(pending.take_result(), None, "the worker is still waiting");
+
+    release_tx.send(()).expect("release worker");
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    let result = loop {
+        if let Some(result) = pending.take_result() {
+            break result;
+        }
+        assert!(std::time::Instant::now() < deadline, "worker result never arrived");
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    };
+    assert_eq!(result, Err("Invalid master password. Try again.".to_string()));
+}
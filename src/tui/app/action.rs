@@ -17,7 +17,7 @@ pub(crate) fn resolve_action(app: &AppState) -> AppAction {
     if app.rdp_credentials.is_some() {
         return AppAction::Tab;
     }
-    if app.vault_unlock.is_some() || app.vault_status_modal.is_some() {
+    if app.vault_unlock.is_some() || app.vault_status_modal.is_some() || app.vault_manager.is_some() {
         return AppAction::Tab;
     }
     if app.quick_connect.is_some() {
@@ -44,6 +44,7 @@ pub(crate) fn should_mark_ui_dirty_for_key(app: &AppState, key: &KeyEvent) -> bo
         && app.rdp_credentials.is_none()
         && app.vault_unlock.is_none()
         && app.vault_status_modal.is_none()
+        && app.vault_manager.is_none()
         && !app.search_mode;
 
     if direct_terminal_input && key.modifiers.is_empty() && matches!(key.code, KeyCode::Char(_) | KeyCode::Enter | KeyCode::Tab) {
@@ -13,7 +13,9 @@ pub(crate) fn run_app(terminal: &mut DefaultTerminal, app: &mut AppState) -> io:
 
     loop {
         app.apply_vault_status_notifications();
+        app.apply_finished_vault_manager_change();
         app.apply_inventory_reload_notifications();
+        app.refresh_stale_inventory_sources();
         app.apply_config_reload_notifications();
         app.expire_reload_notice_toast();
         app.refresh_vault_status_if_stale(VAULT_STATUS_MODAL_POLL_INTERVAL);
@@ -1,6 +1,7 @@
 //! TUI bootstrap and teardown lifecycle.
 
 use super::event_loop::run_app;
+use crate::auth::agent::AgentClient;
 use crate::tui::AppState;
 use crate::{config, inventory::ConnectionProtocol, log_debug, log_error, platform};
 use crossterm::{
@@ -43,6 +44,20 @@ impl Drop for TerminalModeGuard {
     }
 }
 
+/// Deregisters the session manager from the vault agent when dropped.
+///
+/// Held until after the selected-host relaunch so the `tui_exit` lock trigger
+/// does not lock the vault before that connection has read its secret.
+struct VaultAgentDetachGuard;
+
+impl Drop for VaultAgentDetachGuard {
+    fn drop(&mut self) {
+        if let Err(err) = AgentClient::new().and_then(|client| client.detach_tui()) {
+            log_debug!("Failed to deregister session manager from password vault agent: {}", err);
+        }
+    }
+}
+
 /// Run the interactive session manager.
 pub fn run_session_manager(runtime_profile: Option<String>) -> io::Result<()> {
     log_debug!("Starting interactive session manager");
@@ -53,6 +68,7 @@ pub fn run_session_manager(runtime_profile: Option<String>) -> io::Result<()> {
     let mut terminal = Terminal::new(backend)?;
     let _watcher = config::config_watcher_with_scope(runtime_profile, config::ReloadNoticeTarget::Queue, config::ConfigWatchScope::AllProfiles);
 
+    let _vault_agent_guard = VaultAgentDetachGuard;
     let mut app = AppState::new()?;
     let result = run_app(&mut terminal, &mut app);
     let selected_request = app.selected_host_to_connect.take();
@@ -28,6 +28,9 @@ impl AppState {
     }
 
     pub(crate) fn open_folder_picker_for_move_host(&mut self, host_idx: usize) {
+        if self.reject_dynamic_source_host(host_idx) {
+            return;
+        }
         let Some(host) = self.hosts.get(host_idx).cloned() else {
             return;
         };
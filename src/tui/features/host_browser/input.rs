@@ -201,6 +201,12 @@ impl AppState {
                     self.open_manual_vault_unlock();
                 }
             }
+            KeyCode::Char('V') if self.focus_on_manager && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                self.open_vault_manager();
+            }
+            KeyCode::Char('R') if self.focus_on_manager && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                self.refresh_inventory_sources();
+            }
             KeyCode::Char('i') if self.focus_on_manager && key.modifiers.is_empty() => {
                 self.host_info_visible = !self.host_info_visible;
                 self.is_dragging_host_info_divider = false;
@@ -1,5 +1,6 @@
 //! Host browser rendering.
 
+use crate::inventory::InventoryHost;
 use crate::tui::ui::theme;
 use crate::tui::{AppState, HostTreeRowKind};
 use ratatui::{
@@ -10,6 +11,17 @@ use ratatui::{
     widgets::{List, ListItem, ListState, Paragraph, Wrap},
 };
 
+/// Append a dim marker when `field` came from a folder `defaults:` block.
+fn with_inherited_marker<'a>(host: &InventoryHost, field: &str, mut spans: Vec<Span<'a>>) -> Vec<Span<'a>> {
+    if host.is_inherited(field) {
+        spans.push(Span::styled(
+            " (inherited)",
+            Style::default().fg(theme::ansi_bright_black()).add_modifier(Modifier::ITALIC),
+        ));
+    }
+    spans
+}
+
 fn inventory_load_error_lines(error: &str) -> Vec<Line<'static>> {
     vec![
         Line::from(""),
@@ -170,72 +182,119 @@ impl AppState {
                 Span::styled(host.protocol.display_name(), Style::default().fg(theme::ansi_cyan())),
             ]));
 
-            lines.push(Line::from(vec![
-                Span::styled("Host: ", Style::default().fg(theme::ansi_bright_black())),
-                Span::styled(&host.host, Style::default().fg(theme::ansi_bright_white())),
-            ]));
-
-            if let Some(user) = &host.user {
+            if let Some(template) = &host.template {
                 lines.push(Line::from(vec![
-                    Span::styled("User: ", Style::default().fg(theme::ansi_bright_black())),
-                    Span::styled(user, Style::default().fg(theme::ansi_bright_white())),
+                    Span::styled("Template: ", Style::default().fg(theme::ansi_bright_black())),
+                    Span::styled(template, Style::default().fg(theme::ansi_cyan())),
                 ]));
             }
 
+            lines.push(Line::from(with_inherited_marker(
+                host,
+                "host",
+                vec![
+                    Span::styled("Host: ", Style::default().fg(theme::ansi_bright_black())),
+                    Span::styled(&host.host, Style::default().fg(theme::ansi_bright_white())),
+                ],
+            )));
+
+            if let Some(user) = &host.user {
+                lines.push(Line::from(with_inherited_marker(
+                    host,
+                    "user",
+                    vec![
+                        Span::styled("User: ", Style::default().fg(theme::ansi_bright_black())),
+                        Span::styled(user, Style::default().fg(theme::ansi_bright_white())),
+                    ],
+                )));
+            }
+
             if let Some(port) = &host.port {
-                lines.push(Line::from(vec![
-                    Span::styled("Port: ", Style::default().fg(theme::ansi_bright_black())),
-                    Span::styled(port.to_string(), Style::default().fg(theme::ansi_bright_white())),
-                ]));
+                lines.push(Line::from(with_inherited_marker(
+                    host,
+                    "port",
+                    vec![
+                        Span::styled("Port: ", Style::default().fg(theme::ansi_bright_black())),
+                        Span::styled(port.to_string(), Style::default().fg(theme::ansi_bright_white())),
+                    ],
+                )));
             }
 
             if let Some(identity) = host.ssh.identity_files.first() {
                 let display = identity.rsplit('/').next().unwrap_or(identity);
-                lines.push(Line::from(vec![
-                    Span::styled("Key:  ", Style::default().fg(theme::ansi_bright_black())),
-                    Span::styled(display, Style::default().fg(theme::ansi_bright_black())),
-                ]));
+                lines.push(Line::from(with_inherited_marker(
+                    host,
+                    "identity_file",
+                    vec![
+                        Span::styled("Key:  ", Style::default().fg(theme::ansi_bright_black())),
+                        Span::styled(display, Style::default().fg(theme::ansi_bright_black())),
+                    ],
+                )));
             }
 
             if let Some(proxy) = &host.ssh.proxy_jump {
-                lines.push(Line::from(vec![
-                    Span::styled("Jump: ", Style::default().fg(theme::ansi_bright_black())),
-                    Span::styled(proxy, Style::default().fg(theme::ansi_bright_white())),
-                ]));
+                lines.push(Line::from(with_inherited_marker(
+                    host,
+                    "proxy_jump",
+                    vec![
+                        Span::styled("Jump: ", Style::default().fg(theme::ansi_bright_black())),
+                        Span::styled(proxy, Style::default().fg(theme::ansi_bright_white())),
+                    ],
+                )));
             }
 
             for fwd in &host.ssh.local_forward {
-                lines.push(Line::from(vec![
-                    Span::styled("LFwd: ", Style::default().fg(theme::ansi_bright_black())),
-                    Span::styled(fwd, Style::default().fg(theme::ansi_bright_white())),
-                ]));
+                lines.push(Line::from(with_inherited_marker(
+                    host,
+                    "local_forward",
+                    vec![
+                        Span::styled("LFwd: ", Style::default().fg(theme::ansi_bright_black())),
+                        Span::styled(fwd, Style::default().fg(theme::ansi_bright_white())),
+                    ],
+                )));
             }
             for fwd in &host.ssh.remote_forward {
-                lines.push(Line::from(vec![
-                    Span::styled("RFwd: ", Style::default().fg(theme::ansi_bright_black())),
-                    Span::styled(fwd, Style::default().fg(theme::ansi_bright_white())),
-                ]));
+                lines.push(Line::from(with_inherited_marker(
+                    host,
+                    "remote_forward",
+                    vec![
+                        Span::styled("RFwd: ", Style::default().fg(theme::ansi_bright_black())),
+                        Span::styled(fwd, Style::default().fg(theme::ansi_bright_white())),
+                    ],
+                )));
             }
 
             if let Some(profile) = &host.profile {
-                lines.push(Line::from(vec![
-                    Span::styled("Prof: ", Style::default().fg(theme::ansi_bright_black())),
-                    Span::styled(profile, Style::default().fg(theme::ansi_magenta())),
-                ]));
+                lines.push(Line::from(with_inherited_marker(
+                    host,
+                    "profile",
+                    vec![
+                        Span::styled("Prof: ", Style::default().fg(theme::ansi_bright_black())),
+                        Span::styled(profile, Style::default().fg(theme::ansi_magenta())),
+                    ],
+                )));
             }
 
             if !host.rdp.args.is_empty() {
-                lines.push(Line::from(vec![
-                    Span::styled("RDP:  ", Style::default().fg(theme::ansi_bright_black())),
-                    Span::styled(host.rdp.args.join(" "), Style::default().fg(theme::ansi_bright_white())),
-                ]));
+                lines.push(Line::from(with_inherited_marker(
+                    host,
+                    "rdp_args",
+                    vec![
+                        Span::styled("RDP:  ", Style::default().fg(theme::ansi_bright_black())),
+                        Span::styled(host.rdp.args.join(" "), Style::default().fg(theme::ansi_bright_white())),
+                    ],
+                )));
             }
 
             if host.vault_pass.is_some() {
-                lines.push(Line::from(vec![
-                    Span::styled("Pass: ", Style::default().fg(theme::ansi_bright_black())),
-                    Span::styled("enabled", Style::default().fg(theme::ansi_yellow())),
-                ]));
+                lines.push(Line::from(with_inherited_marker(
+                    host,
+                    "vault_pass",
+                    vec![
+                        Span::styled("Pass: ", Style::default().fg(theme::ansi_bright_black())),
+                        Span::styled("enabled", Style::default().fg(theme::ansi_yellow())),
+                    ],
+                )));
             }
 
             let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
@@ -1,6 +1,6 @@
 //! Fuzzy search and host filtering logic
 
-use crate::inventory::{FolderId, InventoryTreeModel, TreeFolder, build_inventory_tree};
+use crate::inventory::{FolderId, InventoryTreeModel, TreeFolder, build_cached_inventory_tree};
 use crate::tui::state::HostSearchEntry;
 use crate::tui::{AppState, HostTreeRow, HostTreeRowKind};
 use std::collections::{HashMap, HashSet};
@@ -19,6 +19,15 @@ enum HostRowKey {
     Host(String),
 }
 
+/// Build the inventory at `inventory_path` with `build`, or an empty tree when it does not exist.
+pub(crate) fn load_inventory_tree_with(inventory_path: &Path, build: fn(&Path) -> io::Result<InventoryTreeModel>) -> io::Result<InventoryTreeModel> {
+    if inventory_path.exists() {
+        build(inventory_path)
+    } else {
+        Ok(empty_inventory_tree_model(inventory_path))
+    }
+}
+
 fn empty_inventory_tree_model(inventory_path: &Path) -> InventoryTreeModel {
     let root_name = inventory_path
         .file_name()
@@ -35,6 +44,7 @@ fn empty_inventory_tree_model(inventory_path: &Path) -> InventoryTreeModel {
             host_indices: Vec::new(),
         },
         hosts: Vec::new(),
+        source_refresh_due: None,
     }
 }
 
@@ -421,26 +431,36 @@ impl AppState {
         self.update_filtered_hosts_with_preferred(previous);
     }
 
+    /// Rebuild the tree from `inventory_path` using cached `source:` output
+    /// only; stale sources are refreshed in the background afterwards.
     pub(crate) fn reload_inventory_tree_from_path(&mut self, inventory_path: &Path) -> io::Result<()> {
+        self.reload_inventory_tree_with(inventory_path, build_cached_inventory_tree)
+    }
+
+    pub(crate) fn reload_inventory_tree_with(&mut self, inventory_path: &Path, build: fn(&Path) -> io::Result<InventoryTreeModel>) -> io::Result<()> {
+        self.inventory_reloads += 1;
+        self.apply_inventory_tree(load_inventory_tree_with(inventory_path, build))
+    }
+
+    /// Replace the host tree with a built inventory, keeping selection, scroll and
+    /// collapsed folders. A failed build keeps the current tree.
+    pub(crate) fn apply_inventory_tree(&mut self, tree_model: io::Result<InventoryTreeModel>) -> io::Result<()> {
         let previous_selection = self.selected_row_key();
         let collapsed_folder_keys = self.collapsed_folder_keys();
         let previous_scroll_offset = self.host_scroll_offset;
-        let tree_model = if inventory_path.exists() {
-            match build_inventory_tree(inventory_path) {
-                Ok(tree_model) => tree_model,
-                Err(err) => {
-                    self.inventory_load_error = Some(err.to_string());
-                    self.mark_ui_dirty();
-                    return Err(err);
-                }
+        let tree_model = match tree_model {
+            Ok(tree_model) => tree_model,
+            Err(err) => {
+                self.inventory_load_error = Some(err.to_string());
+                self.mark_ui_dirty();
+                return Err(err);
             }
-        } else {
-            empty_inventory_tree_model(inventory_path)
         };
 
         self.hosts = tree_model.hosts;
         self.host_search_index = Self::build_host_search_index(&self.hosts);
         self.host_tree_root = tree_model.root;
+        self.inventory_source_refresh_due = tree_model.source_refresh_due;
         self.inventory_load_error = None;
         self.restore_collapsed_folders(&collapsed_folder_keys);
         self.update_filtered_hosts_with_preferred(previous_selection);
@@ -113,7 +113,26 @@ impl AppState {
         self.open_host_editor_for_duplicate_host_idx(host_idx);
     }
 
+    /// Hosts loaded from an inventory `source:` belong to that source, so
+    /// they are not edited, moved or deleted in place.
+    pub(crate) fn reject_dynamic_source_host(&mut self, host_idx: usize) -> bool {
+        let Some(host) = self.hosts.get(host_idx) else {
+            return false;
+        };
+        let Some(source) = host.dynamic_source.as_deref() else {
+            return false;
+        };
+
+        let notice = format!("'{}' comes from inventory source '{}'; change it there.", host.name, source);
+        self.reload_notice_toast = Some(ReloadNoticeToast::new(format_reload_notice(&notice)));
+        self.mark_ui_dirty();
+        true
+    }
+
     fn open_host_editor_for_host_idx(&mut self, host_idx: usize) {
+        if self.reject_dynamic_source_host(host_idx) {
+            return;
+        }
         let Some(host) = self.hosts.get(host_idx).cloned() else {
             return;
         };
@@ -194,6 +213,9 @@ impl AppState {
     }
 
     fn open_host_delete_confirmation_for_host_idx(&mut self, host_idx: usize) {
+        if self.reject_dynamic_source_host(host_idx) {
+            return;
+        }
         let Some(host) = self.hosts.get(host_idx) else {
             return;
         };
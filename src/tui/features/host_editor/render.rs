@@ -25,6 +25,15 @@ fn section_indicator(form: &HostEditorState, section: HostEditorSection) -> &'st
     if form.section_collapsed(section) { "▸" } else { "▾" }
 }
 
+fn inherited_hint_span(form: &HostEditorState, field: HostEditorField) -> Option<Span<'static>> {
+    form.inherited_hint(field).map(|value| {
+        Span::styled(
+            format!("  (inherited: {value})"),
+            Style::default().fg(theme::ansi_bright_black()).add_modifier(Modifier::ITALIC),
+        )
+    })
+}
+
 impl AppState {
     pub(crate) fn render_host_context_menu(&self, frame: &mut Frame) {
         let Some(menu) = self.host_context_menu.as_ref() else {
@@ -155,14 +164,17 @@ impl AppState {
                     HostEditorField::IdentitiesOnly => {
                         let label_style = if form.is_selected_field(field) { selected_label } else { normal_label };
                         let value_style = if form.is_selected_field(field) { selected_value } else { normal_value };
-                        body_lines.push(Line::from(vec![
+                        let mut spans = vec![
                             Span::styled(format!("{}: ", field.label()), label_style),
                             Span::styled(form.identities_only_display(), value_style),
-                        ]));
+                        ];
+                        spans.extend(inherited_hint_span(form, field));
+                        body_lines.push(Line::from(spans));
                     }
                     _ => {
                         let label_style = if form.is_selected_field(field) { selected_label } else { normal_label };
-                        let placeholder = form.field_example(field);
+                        // An inherited value replaces the example as the hint for an empty field.
+                        let placeholder = form.field_example(field).filter(|_| form.inherited_hint(field).is_none());
                         let value_column_width = body_width.saturating_sub(field.label().chars().count() as u16).saturating_sub(2);
 
                         let value_spans = if let Some(text) = form.text_field(field) {
@@ -211,6 +223,7 @@ impl AppState {
 
                         let mut spans = vec![Span::styled(format!("{}: ", field.label()), label_style)];
                         spans.extend(value_spans);
+                        spans.extend(inherited_hint_span(form, field));
                         body_lines.push(Line::from(spans));
                     }
                 },
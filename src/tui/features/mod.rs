@@ -10,3 +10,4 @@ pub(crate) mod selection;
 pub(crate) mod terminal_search;
 pub(crate) mod terminal_session;
 pub(crate) mod terminal_tabs;
+pub(crate) mod vault_manager;
@@ -18,7 +18,7 @@ impl AppState {
             VaultUnlockAction::OpenHostTab { auth_settings, .. } | VaultUnlockAction::ReconnectTab { auth_settings, .. } => auth_settings.clone(),
         };
 
-        UnlockPolicy::new(auth_settings.idle_timeout_seconds, auth_settings.session_timeout_seconds)
+        UnlockPolicy::from_auth_settings(&auth_settings)
     }
 
     fn launch_protocol_for_vault_action<'a>(&'a self, action: &'a VaultUnlockAction) -> Option<&'a ConnectionProtocol> {
@@ -1,4 +1,5 @@
 use crate::auth::agent;
+use crate::auth::provider::is_external_secret_reference;
 use crate::auth::secret::{ExposeSecret, SensitiveString};
 use crate::inventory::{ConnectionProtocol, InventoryHost};
 use crate::log::SessionSshLogger;
@@ -404,6 +405,27 @@ impl AppState {
             });
         };
 
+        if is_external_secret_reference(pass_key) {
+            // External providers are resolved by the agent at spawn time and
+            // never require the vault unlock modal.
+            return Some(HostPassResolution {
+                pass_entry_override: Some(pass_key.to_string()),
+                pass_fallback_notice: None,
+                disable_vault_autologin: false,
+                manual_rdp_password: None,
+            });
+        }
+
+        if matches!(host.protocol, ConnectionProtocol::Ssh) && process::forwarded_vault_socket_for_entry(pass_key).is_some() {
+            // Answered by the forwarding machine's vault, which confirms each release itself.
+            return Some(HostPassResolution {
+                pass_entry_override: Some(pass_key.to_string()),
+                pass_fallback_notice: None,
+                disable_vault_autologin: false,
+                manual_rdp_password: None,
+            });
+        }
+
         let client = match agent::AgentClient::new() {
             Ok(client) => client,
             Err(err) => {
@@ -742,6 +764,7 @@ impl AppState {
             initial_rows,
             initial_cols,
         )
+        .map(|session| session.with_vault_forward(command_spec.vault_forward.take()))
     }
 
     fn spawn_rdp_session(
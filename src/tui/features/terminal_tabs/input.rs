@@ -156,6 +156,11 @@ impl AppState {
             return Ok(());
         }
 
+        if self.vault_manager.is_some() {
+            self.handle_vault_manager_key(key);
+            return Ok(());
+        }
+
         if self.quick_connect.is_some() {
             self.handle_quick_connect_key(key);
             return Ok(());
@@ -201,6 +206,11 @@ impl AppState {
             return Ok(());
         }
 
+        if self.vault_manager.is_some() {
+            self.handle_vault_manager_paste(&pasted);
+            return Ok(());
+        }
+
         if self.quick_connect.is_some() {
             self.handle_quick_connect_paste(&pasted);
             return Ok(());
@@ -39,6 +39,11 @@ impl AppState {
             return Ok(());
         }
 
+        if self.vault_manager.is_some() {
+            self.handle_vault_manager_mouse(mouse);
+            return Ok(());
+        }
+
         if self.quick_connect.is_some() {
             self.handle_quick_connect_mouse(mouse);
             return Ok(());
@@ -0,0 +1,344 @@
+//! Vault manager keyboard handling and entry mutations.
+
+use crate::args::validate_vault_entry_name;
+use crate::auth::agent::{AgentClient, AgentEntryChange, AgentError};
+use crate::auth::audit::{self, AuditEvent, AuditEventKind, AuditProcess};
+use crate::auth::secret::{ExposeSecret, SensitiveString};
+use crate::auth::vault::{self, UnlockedVault, VaultEntrySummary, VaultError};
+use crate::log_debug;
+use crate::tui::{AppState, PendingVaultManagerChange, VaultManagerForm, VaultManagerFormKind, VaultManagerState};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+const VAULT_MANAGER_NOT_INITIALIZED_NOTICE: &str = "Password vault is not initialized. Run `cossh vault init` first.";
+
+fn load_vault_entry_summaries() -> Result<Vec<VaultEntrySummary>, String> {
+    match vault::list_entry_summaries() {
+        Ok(summaries) => Ok(summaries),
+        Err(VaultError::VaultNotInitialized) => Err(VAULT_MANAGER_NOT_INITIALIZED_NOTICE.to_string()),
+        Err(err) => Err(format!("Failed to list password vault entries: {err}")),
+    }
+}
+
+/// Unlock the vault for one manager action, recording the attempt like `cossh vault` does.
+fn unlock_vault_for_manager(master_password: &str) -> Result<UnlockedVault, VaultError> {
+    let result = vault::unlock_with_password(master_password);
+    let audit_kind = match &result {
+        Ok(_) => Some(AuditEventKind::Unlocked),
+        Err(VaultError::InvalidMasterPassword) => Some(AuditEventKind::UnlockFailed),
+        Err(_) => None,
+    };
+    if let (Some(kind), Ok(paths)) = (audit_kind, vault::VaultPaths::resolve_default()) {
+        audit::record_event(&paths, AuditEvent::new(kind).with_process(Some(AuditProcess::current())).with_detail("tui"));
+    }
+    result
+}
+
+/// Whether the vault agent is unlocked and can change entries without the master password.
+fn vault_agent_unlocked() -> bool {
+    AgentClient::new().and_then(|client| client.status()).is_ok_and(|status| status.unlocked)
+}
+
+impl AppState {
+    pub(crate) fn open_vault_manager(&mut self) {
+        log_debug!("Opening TUI vault manager");
+        self.quick_connect = None;
+        self.vault_status_modal = None;
+        let mut manager = VaultManagerState::new(Vec::new(), &self.hosts);
+        match load_vault_entry_summaries() {
+            Ok(summaries) => manager.reload(summaries, &self.hosts),
+            Err(err) => manager.load_error = Some(err),
+        }
+        self.vault_manager = Some(manager);
+        self.mark_ui_dirty();
+    }
+
+    pub(crate) fn reload_vault_manager(&mut self) {
+        let hosts = &self.hosts;
+        let Some(manager) = self.vault_manager.as_mut() else {
+            return;
+        };
+        match load_vault_entry_summaries() {
+            Ok(summaries) => {
+                manager.reload(summaries, hosts);
+                manager.load_error = None;
+            }
+            Err(err) => {
+                manager.reload(Vec::new(), hosts);
+                manager.load_error = Some(err);
+            }
+        }
+        self.mark_ui_dirty();
+    }
+
+    fn open_vault_manager_form(&mut self, kind: VaultManagerFormKind) {
+        let Some(manager) = self.vault_manager.as_mut() else {
+            return;
+        };
+
+        let source_name = match kind {
+            VaultManagerFormKind::Add => None,
+            _ => {
+                let Some(entry) = manager.selected_entry() else {
+                    manager.set_message("Select a vault entry first.".to_string(), true);
+                    self.mark_ui_dirty();
+                    return;
+                };
+                if kind == VaultManagerFormKind::Edit && entry.ssh_key {
+                    manager.set_message("SSH key entries are replaced with `cossh vault add-key`.".to_string(), true);
+                    self.mark_ui_dirty();
+                    return;
+                }
+                Some(entry.name.clone())
+            }
+        };
+
+        manager.message = None;
+        let mut form = VaultManagerForm::new(kind, source_name);
+        form.vault_unlocked = vault_agent_unlocked();
+        manager.form = Some(form);
+        self.mark_ui_dirty();
+    }
+
+    pub(crate) fn handle_vault_manager_key(&mut self, key: KeyEvent) {
+        let Some(manager) = self.vault_manager.as_mut() else {
+            return;
+        };
+
+        if manager.form.is_some() {
+            self.handle_vault_manager_form_key(key);
+            return;
+        }
+
+        let plain = !key.modifiers.contains(KeyModifiers::CONTROL) && !key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') if plain => {
+                self.vault_manager = None;
+            }
+            KeyCode::Up | KeyCode::Char('k') if plain => manager.select_prev(),
+            KeyCode::Down | KeyCode::Char('j') if plain => manager.select_next(),
+            KeyCode::Home => manager.selected = 0,
+            KeyCode::End => manager.selected = manager.entries.len().saturating_sub(1),
+            KeyCode::Char('a') if plain => self.open_vault_manager_form(VaultManagerFormKind::Add),
+            KeyCode::Enter | KeyCode::Char('e') if plain => self.open_vault_manager_form(VaultManagerFormKind::Edit),
+            KeyCode::Char('r') if plain => self.open_vault_manager_form(VaultManagerFormKind::Rename),
+            KeyCode::Delete | KeyCode::Char('d') if plain => self.open_vault_manager_form(VaultManagerFormKind::Remove),
+            KeyCode::Char('R') if plain => self.reload_vault_manager(),
+            _ => return,
+        }
+        self.mark_ui_dirty();
+    }
+
+    fn handle_vault_manager_form_key(&mut self, key: KeyEvent) {
+        let Some(form) = self.vault_manager.as_mut().and_then(|manager| manager.form.as_mut()) else {
+            return;
+        };
+        if form.pending.is_some() {
+            return;
+        }
+
+        match key.code {
+            KeyCode::Esc => {
+                if let Some(manager) = self.vault_manager.as_mut() {
+                    manager.form = None;
+                }
+            }
+            KeyCode::Enter if form.is_last_field_focused() => self.submit_vault_manager_form(),
+            KeyCode::Enter | KeyCode::Tab | KeyCode::Down => form.focus_next(),
+            KeyCode::BackTab | KeyCode::Up => form.focus_prev(),
+            KeyCode::Left => form.move_cursor_left(),
+            KeyCode::Right => form.move_cursor_right(),
+            KeyCode::Home => form.move_cursor_home(),
+            KeyCode::End => form.move_cursor_end(),
+            KeyCode::Backspace => form.backspace(),
+            KeyCode::Delete => form.delete(),
+            KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => form.move_cursor_home(),
+            KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => form.move_cursor_end(),
+            KeyCode::Char(ch) if !key.modifiers.contains(KeyModifiers::CONTROL) && !key.modifiers.contains(KeyModifiers::ALT) => form.insert_char(ch),
+            _ => return,
+        }
+        self.mark_ui_dirty();
+    }
+
+    pub(crate) fn handle_vault_manager_paste(&mut self, pasted: &str) {
+        let Some(form) = self.vault_manager.as_mut().and_then(|manager| manager.form.as_mut()) else {
+            return;
+        };
+        if form.pending.is_some() {
+            return;
+        }
+
+        for ch in pasted.chars().filter(|ch| !ch.is_control()) {
+            form.insert_char(ch);
+        }
+        self.mark_ui_dirty();
+    }
+
+    pub(crate) fn submit_vault_manager_form(&mut self) {
+        let Some(mut form) = self.vault_manager.as_mut().and_then(|manager| manager.form.take()) else {
+            return;
+        };
+        if form.kind == VaultManagerFormKind::Rename && form.source_name.as_deref() == Some(form.name.trim()) {
+            if let Some(manager) = self.vault_manager.as_mut() {
+                manager.set_message("Vault entry name unchanged.".to_string(), false);
+            }
+            self.mark_ui_dirty();
+            return;
+        }
+
+        match Self::start_vault_manager_change(&mut form) {
+            Ok(pending) => {
+                log_debug!("TUI vault manager {:?} started", form.kind);
+                form.error = None;
+                form.pending = Some(pending);
+            }
+            Err(err) => {
+                log_debug!("TUI vault manager {:?} failed: {}", form.kind, err);
+                form.error = Some(err);
+            }
+        }
+        if let Some(manager) = self.vault_manager.as_mut() {
+            manager.form = Some(form);
+        }
+        self.mark_ui_dirty();
+    }
+
+    /// Apply the outcome of a submitted form once its worker has finished.
+    pub(crate) fn apply_finished_vault_manager_change(&mut self) {
+        let Some(form) = self.vault_manager.as_mut().and_then(|manager| manager.form.as_mut()) else {
+            return;
+        };
+        let Some(result) = form.pending.as_ref().and_then(PendingVaultManagerChange::take_result) else {
+            return;
+        };
+        let Some(pending) = form.pending.take() else {
+            return;
+        };
+
+        match result {
+            Ok(()) => {
+                log_debug!("TUI vault manager {:?} succeeded", form.kind);
+                if let Some(manager) = self.vault_manager.as_mut() {
+                    manager.form = None;
+                }
+                self.reload_vault_manager();
+                if let Some(manager) = self.vault_manager.as_mut() {
+                    if let Some(name) = pending.selected_name {
+                        manager.select_entry_named(&name);
+                    }
+                    manager.set_message(pending.message, false);
+                }
+            }
+            Err(err) => {
+                log_debug!("TUI vault manager {:?} failed: {}", form.kind, err);
+                form.error = Some(err);
+                form.vault_unlocked = vault_agent_unlocked();
+            }
+        }
+        self.mark_ui_dirty();
+    }
+
+    /// Validate one form and start its change on a worker thread.
+    fn start_vault_manager_change(form: &mut VaultManagerForm) -> Result<PendingVaultManagerChange, String> {
+        let new_name = form.name.trim().to_string();
+        if matches!(form.kind, VaultManagerFormKind::Add | VaultManagerFormKind::Rename) && !validate_vault_entry_name(&new_name) {
+            return Err(VaultError::InvalidEntryName.to_string());
+        }
+        if matches!(form.kind, VaultManagerFormKind::Add | VaultManagerFormKind::Edit) && form.secret.is_empty() {
+            return Err("Secret value cannot be empty.".to_string());
+        }
+        if form.master_password.is_empty() && !form.vault_unlocked {
+            return Err("Master password is required.".to_string());
+        }
+        if form.kind == VaultManagerFormKind::Add && vault::entry_exists(&new_name).unwrap_or(false) {
+            return Err(format!(
+                "Vault entry '{new_name}' already exists; select it and press [e] to replace its value."
+            ));
+        }
+
+        let source_name = form.source_name.clone().unwrap_or_default();
+        let (change, selected_name, message) = match form.kind {
+            VaultManagerFormKind::Add | VaultManagerFormKind::Edit => {
+                let secret = std::mem::take(&mut form.secret)
+                    .into_sensitive_string()
+                    .map_err(|err| format!("Password vault input could not be processed ({err})."))?;
+                form.secret_cursor = 0;
+                if form.kind == VaultManagerFormKind::Add {
+                    let message = format!("Added vault entry '{new_name}'.");
+                    (
+                        AgentEntryChange::Store {
+                            name: new_name.clone(),
+                            secret,
+                        },
+                        Some(new_name),
+                        message,
+                    )
+                } else {
+                    let message = format!("Replaced value of '{source_name}'; the previous value is kept in history.");
+                    (
+                        AgentEntryChange::Replace {
+                            name: source_name.clone(),
+                            secret,
+                        },
+                        Some(source_name),
+                        message,
+                    )
+                }
+            }
+            VaultManagerFormKind::Rename => {
+                let message = format!("Renamed vault entry '{source_name}' to '{new_name}'.");
+                (
+                    AgentEntryChange::Rename {
+                        name: source_name,
+                        new_name: new_name.clone(),
+                    },
+                    Some(new_name),
+                    message,
+                )
+            }
+            VaultManagerFormKind::Remove => {
+                let message = format!("Removed vault entry '{source_name}'.");
+                (AgentEntryChange::Remove { name: source_name }, None, message)
+            }
+        };
+
+        let master_password = std::mem::take(&mut form.master_password)
+            .into_sensitive_string()
+            .map_err(|err| format!("Password vault input could not be processed ({err})."))?;
+        form.master_password_cursor = 0;
+        PendingVaultManagerChange::spawn(move || apply_vault_manager_change(change, master_password), selected_name, message)
+            .map_err(|err| format!("Vault entry change could not be started ({err})."))
+    }
+}
+
+/// Make one change through the unlocked agent, or unlock the vault with the
+/// master password when the agent is locked. Runs on a worker thread.
+fn apply_vault_manager_change(change: AgentEntryChange, master_password: SensitiveString) -> Result<(), String> {
+    let failure = match &change {
+        AgentEntryChange::Store { .. } => "Failed to store vault entry",
+        AgentEntryChange::Replace { .. } => "Failed to replace vault entry",
+        AgentEntryChange::Rename { .. } => "Failed to rename vault entry",
+        AgentEntryChange::Remove { .. } => "Failed to remove vault entry",
+    };
+    match AgentClient::new().and_then(|client| client.change_entry(change.clone())) {
+        Ok(_) => return Ok(()),
+        Err(AgentError::Locked) => {}
+        Err(err) => return Err(format!("{failure}: {err}")),
+    }
+
+    if master_password.expose_secret().is_empty() {
+        return Err("Password vault is locked; enter the master password.".to_string());
+    }
+    let unlocked = match unlock_vault_for_manager(master_password.expose_secret()) {
+        Ok(unlocked) => unlocked,
+        Err(VaultError::InvalidMasterPassword) => return Err("Invalid master password. Try again.".to_string()),
+        Err(VaultError::VaultNotInitialized) => return Err(VAULT_MANAGER_NOT_INITIALIZED_NOTICE.to_string()),
+        Err(err) => return Err(format!("Password vault could not be unlocked ({err}).")),
+    };
+    match &change {
+        AgentEntryChange::Store { name, secret } => unlocked.store_secret(name, secret.expose_secret()),
+        AgentEntryChange::Replace { name, secret } => unlocked.rotate_secret(name, secret.expose_secret()),
+        AgentEntryChange::Rename { name, new_name } => unlocked.rename_entry(name, new_name),
+        AgentEntryChange::Remove { name } => unlocked.remove_entry(name),
+    }
+    .map_err(|err| format!("{failure}: {err}"))
+}
@@ -0,0 +1,8 @@
+//! Vault manager modal: list, add, replace, rename and remove vault entries.
+
+/// Missing-reference warnings listed before the rest are summarized in one line.
+const VAULT_MANAGER_MAX_WARNING_LINES: usize = 3;
+
+pub(crate) mod input;
+pub(crate) mod mouse;
+pub(crate) mod render;
@@ -0,0 +1,75 @@
+//! Vault manager modal layout and mouse handling.
+
+use crate::tui::AppState;
+use crossterm::event::{self, MouseButton, MouseEventKind};
+use ratatui::layout::Rect;
+
+impl AppState {
+    pub(crate) fn handle_vault_manager_mouse(&mut self, mouse: event::MouseEvent) {
+        let Some((_, inner)) = self.vault_manager_modal_layout() else {
+            return;
+        };
+        let Some(manager) = self.vault_manager.as_mut() else {
+            return;
+        };
+        if manager.form.is_some() {
+            return;
+        }
+
+        match mouse.kind {
+            MouseEventKind::ScrollUp => manager.select_prev(),
+            MouseEventKind::ScrollDown => manager.select_next(),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let list_height = Self::vault_manager_list_height(manager, inner);
+                let list_area = Rect::new(inner.x, inner.y.saturating_add(1), inner.width, list_height as u16);
+                if !Self::folder_picker_point_in_rect(list_area, mouse.column, mouse.row) {
+                    return;
+                }
+                let scroll_offset = Self::folder_picker_scroll_offset(manager.selected, manager.entries.len(), list_height);
+                let row_idx = scroll_offset + mouse.row.saturating_sub(list_area.y) as usize;
+                if row_idx >= manager.entries.len() {
+                    return;
+                }
+                manager.selected = row_idx;
+            }
+            _ => return,
+        }
+        self.mark_ui_dirty();
+    }
+
+    pub(crate) fn vault_manager_modal_layout(&self) -> Option<(Rect, Rect)> {
+        self.vault_manager.as_ref()?;
+        let full_area = Rect::new(0, 0, self.last_terminal_size.0, self.last_terminal_size.1);
+        if full_area.width == 0 || full_area.height == 0 {
+            return None;
+        }
+
+        let width = full_area.width.clamp(60, 100);
+        let height = full_area.height.saturating_sub(4).clamp(12, 24).min(full_area.height);
+        let area = Self::centered_rect(width, height, full_area);
+        Some((area, Self::vault_manager_inner_rect(area)))
+    }
+
+    pub(crate) fn vault_manager_form_layout(&self) -> Option<(Rect, Rect)> {
+        let form = self.vault_manager.as_ref()?.form.as_ref()?;
+        let full_area = Rect::new(0, 0, self.last_terminal_size.0, self.last_terminal_size.1);
+        if full_area.width == 0 || full_area.height == 0 {
+            return None;
+        }
+
+        let width = full_area.width.clamp(48, 76);
+        // Entry line, one line per field, error line and hint line inside the border.
+        let height = (form.fields().len() as u16).saturating_add(5).min(full_area.height);
+        let area = Self::centered_rect(width, height, full_area);
+        Some((area, Self::vault_manager_inner_rect(area)))
+    }
+
+    fn vault_manager_inner_rect(area: Rect) -> Rect {
+        Rect::new(
+            area.x.saturating_add(1),
+            area.y.saturating_add(1),
+            area.width.saturating_sub(2),
+            area.height.saturating_sub(2),
+        )
+    }
+}
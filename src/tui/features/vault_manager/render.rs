@@ -0,0 +1,247 @@
+//! Vault manager modal rendering.
+
+use super::VAULT_MANAGER_MAX_WARNING_LINES;
+use crate::tui::text_edit::build_edit_value_spans;
+use crate::tui::ui::theme;
+use crate::tui::{AppState, VaultManagerField, VaultManagerForm, VaultManagerFormKind, VaultManagerState};
+use chrono::{DateTime, Local};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+const ACTION_SEPARATOR: &str = "  |  ";
+const NAME_COLUMN_WIDTH: usize = 28;
+const UPDATED_COLUMN_WIDTH: usize = 18;
+
+fn format_entry_updated_at(updated_at: &str) -> String {
+    DateTime::parse_from_rfc3339(updated_at)
+        .map(|datetime| datetime.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+fn vault_manager_hint_text() -> String {
+    ["[a] Add", "[e] Replace", "[r] Rename", "[d] Remove", "[R] Reload", "[Esc] Close"].join(ACTION_SEPARATOR)
+}
+
+fn pad_to_width(text: &str, width: usize) -> String {
+    let truncated = theme::truncate_to_display_width(text, width.saturating_sub(1));
+    let padding = width.saturating_sub(theme::display_width(&truncated));
+    format!("{truncated}{}", " ".repeat(padding))
+}
+
+fn field_label(field: VaultManagerField) -> &'static str {
+    match field {
+        VaultManagerField::Name => "Name: ",
+        VaultManagerField::Secret => "Secret: ",
+        VaultManagerField::MasterPassword => "Master Password: ",
+    }
+}
+
+impl AppState {
+    pub(crate) fn render_vault_manager_modal(&self, frame: &mut Frame, _full_area: Rect) {
+        let Some(manager) = self.vault_manager.as_ref() else {
+            return;
+        };
+        let Some((area, inner)) = self.vault_manager_modal_layout() else {
+            return;
+        };
+
+        frame.render_widget(Clear, area);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme::ansi_cyan()))
+            .title(" Password Vault Entries ");
+        frame.render_widget(block, area);
+
+        let label_style = Style::default().fg(theme::ansi_bright_black());
+        let value_style = Style::default().fg(theme::ansi_bright_white());
+        let warning_style = Style::default().fg(theme::ansi_yellow()).add_modifier(Modifier::BOLD);
+
+        let mut lines = vec![Line::from(vec![Span::styled(
+            format!(
+                "{}{}{}",
+                pad_to_width("Entry", NAME_COLUMN_WIDTH),
+                pad_to_width("Updated", UPDATED_COLUMN_WIDTH),
+                "Used by"
+            ),
+            label_style.add_modifier(Modifier::BOLD),
+        )])];
+
+        let list_height = Self::vault_manager_list_height(manager, inner);
+        if let Some(error) = manager.load_error.as_ref() {
+            lines.push(Line::from(vec![Span::styled(error.clone(), warning_style)]));
+            lines.extend((1..list_height).map(|_| Line::from("")));
+        } else if manager.entries.is_empty() {
+            lines.push(Line::from(vec![Span::styled("No password vault entries. Press [a] to add one.", label_style)]));
+            lines.extend((1..list_height).map(|_| Line::from("")));
+        } else {
+            let scroll_offset = Self::folder_picker_scroll_offset(manager.selected, manager.entries.len(), list_height);
+            for row_idx in scroll_offset..scroll_offset + list_height {
+                let Some(entry) = manager.entries.get(row_idx) else {
+                    lines.push(Line::from(""));
+                    continue;
+                };
+                let row_style = if row_idx == manager.selected {
+                    Style::default()
+                        .fg(theme::ansi_yellow())
+                        .bg(theme::ansi_bright_black())
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    value_style
+                };
+                let name = if entry.ssh_key {
+                    format!("{} (ssh key)", entry.name)
+                } else {
+                    entry.name.clone()
+                };
+                let used_by = match entry.referenced_by.len() {
+                    0 => "-".to_string(),
+                    1 => "1 host".to_string(),
+                    count => format!("{count} hosts"),
+                };
+                lines.push(Line::from(vec![Span::styled(
+                    format!(
+                        "{}{}{}",
+                        pad_to_width(&name, NAME_COLUMN_WIDTH),
+                        pad_to_width(&format_entry_updated_at(&entry.updated_at), UPDATED_COLUMN_WIDTH),
+                        used_by
+                    ),
+                    row_style,
+                )]));
+            }
+        }
+
+        let referenced_by = manager
+            .selected_entry()
+            .map(|entry| {
+                if entry.referenced_by.is_empty() {
+                    "no inventory hosts".to_string()
+                } else {
+                    entry.referenced_by.join(", ")
+                }
+            })
+            .unwrap_or_default();
+        lines.push(Line::from(vec![
+            Span::styled("Referenced by: ", label_style),
+            Span::styled(
+                theme::truncate_to_display_width(&referenced_by, inner.width.saturating_sub(15) as usize),
+                value_style,
+            ),
+        ]));
+
+        for missing in manager.missing_references.iter().take(VAULT_MANAGER_MAX_WARNING_LINES) {
+            lines.push(Line::from(vec![Span::styled(
+                format!("Warning: host '{}' uses missing vault_pass entry '{}'", missing.host_name, missing.entry_name),
+                warning_style,
+            )]));
+        }
+        if manager.missing_references.len() > VAULT_MANAGER_MAX_WARNING_LINES {
+            lines.push(Line::from(vec![Span::styled(
+                format!(
+                    "Warning: {} more hosts use missing vault_pass entries",
+                    manager.missing_references.len() - VAULT_MANAGER_MAX_WARNING_LINES
+                ),
+                warning_style,
+            )]));
+        }
+
+        let message_style = if manager.message_is_error {
+            Style::default().fg(theme::ansi_red()).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme::ansi_green()).add_modifier(Modifier::BOLD)
+        };
+        lines.push(
+            manager
+                .message
+                .as_ref()
+                .map_or_else(|| Line::from(""), |message| Line::from(vec![Span::styled(message.clone(), message_style)])),
+        );
+        lines.push(Line::from(vec![Span::styled(vault_manager_hint_text(), label_style)]));
+
+        frame.render_widget(Paragraph::new(lines), inner);
+
+        if let Some(form) = manager.form.as_ref() {
+            self.render_vault_manager_form(frame, form);
+        }
+    }
+
+    fn render_vault_manager_form(&self, frame: &mut Frame, form: &VaultManagerForm) {
+        let Some((area, inner)) = self.vault_manager_form_layout() else {
+            return;
+        };
+
+        frame.render_widget(Clear, area);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme::ansi_cyan()))
+            .title(form.title());
+        frame.render_widget(block, area);
+
+        let label_style = Style::default().fg(theme::ansi_bright_black());
+        let focused_label_style = Style::default().fg(theme::ansi_yellow()).add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(theme::ansi_bright_white()).add_modifier(Modifier::BOLD);
+        let cursor_style = Style::default().fg(theme::ansi_black()).bg(theme::ansi_cyan()).add_modifier(Modifier::BOLD);
+
+        let target = match form.kind {
+            VaultManagerFormKind::Add => "new entry".to_string(),
+            VaultManagerFormKind::Remove => format!("{} (this cannot be undone)", form.source_name.as_deref().unwrap_or_default()),
+            _ => form.source_name.clone().unwrap_or_default(),
+        };
+        let mut lines = vec![Line::from(vec![Span::styled("Entry: ", label_style), Span::styled(target, value_style)])];
+
+        for field in form.fields() {
+            let focused = *field == form.focused;
+            let (value, cursor) = match field {
+                VaultManagerField::Name => (form.name.clone(), form.name_cursor),
+                VaultManagerField::Secret => (form.secret.masked(), form.secret_cursor),
+                VaultManagerField::MasterPassword => (form.master_password.masked(), form.master_password_cursor),
+            };
+            let mut spans = vec![Span::styled(field_label(*field), if focused { focused_label_style } else { label_style })];
+            if focused {
+                spans.extend(
+                    build_edit_value_spans(&value, cursor, None, value_style, cursor_style, value_style)
+                        .into_iter()
+                        .map(|span| Span::styled(span.content.into_owned(), span.style)),
+                );
+            } else {
+                spans.push(Span::styled(value, value_style));
+            }
+            lines.push(Line::from(spans));
+        }
+
+        lines.push(if form.pending.is_some() {
+            Line::from(vec![Span::styled("Saving...", label_style)])
+        } else if let Some(error) = form.error.as_ref() {
+            Line::from(vec![Span::styled(
+                error.clone(),
+                Style::default().fg(theme::ansi_red()).add_modifier(Modifier::BOLD),
+            )])
+        } else if form.vault_unlocked {
+            Line::from(vec![Span::styled("Vault unlocked; the master password may be left empty.", label_style)])
+        } else {
+            Line::from("")
+        });
+        let next_hint = if form.is_last_field_focused() {
+            form.submit_hint()
+        } else {
+            "[Enter/Tab] Next"
+        };
+        lines.push(Line::from(vec![Span::styled(
+            format!("{next_hint}{ACTION_SEPARATOR}[Esc] Cancel"),
+            label_style,
+        )]));
+
+        frame.render_widget(Paragraph::new(lines), inner);
+    }
+
+    /// Rows available to the entry list below the header and above the detail/warning/message/hint lines.
+    pub(crate) fn vault_manager_list_height(manager: &VaultManagerState, inner: Rect) -> usize {
+        let warning_lines = manager.missing_references.len().min(VAULT_MANAGER_MAX_WARNING_LINES + 1);
+        let footer_lines = 3 + warning_lines;
+        (inner.height as usize).saturating_sub(1 + footer_lines).max(1)
+    }
+}
@@ -12,10 +12,10 @@ mod ui;
 pub(crate) use state::{
     AppState, ConnectRequest, EditorTabId, EditorTabState, FolderCreateState, FolderDeleteConfirmState, FolderPickerMode, FolderPickerRow, FolderPickerState,
     FolderRenameState, HostContextMenuAction, HostContextMenuState, HostContextMenuTarget, HostDeleteConfirmState, HostEditorField, HostEditorMode,
-    HostEditorSection, HostEditorState, HostEditorVisibleItem, HostTab, HostTreeRow, HostTreeRowKind, QuickConnectField, QuickConnectRow, QuickConnectState,
+    HostEditorSection, HostEditorState, HostEditorVisibleItem, HostTab, HostTreeRow, HostTreeRowKind, PendingVaultManagerChange, QuickConnectField, QuickConnectRow, QuickConnectState,
     QuickConnectSubmission, QuickConnectValidationError, RdpCredentialLaunchContext, RdpCredentialSubmission, RdpCredentialValidationError,
-    RdpCredentialsAction, RdpCredentialsField, RdpCredentialsState, TerminalSearchState, TerminalTabState, VaultStatusModalState, VaultUnlockAction,
-    VaultUnlockState,
+    RdpCredentialsAction, RdpCredentialsField, RdpCredentialsState, TerminalSearchState, TerminalTabState, VaultManagerField, VaultManagerForm,
+    VaultManagerFormKind, VaultManagerState, VaultStatusModalState, VaultUnlockAction, VaultUnlockState,
 };
 
 /// Run the interactive session manager.
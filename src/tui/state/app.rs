@@ -9,18 +9,22 @@ use super::quick_connect::QuickConnectState;
 use super::rdp_prompt::RdpCredentialsState;
 use super::tabs::{EditorTabState, HostTab, TerminalSearchState, TerminalTabState};
 use super::vault::{VaultStatusModalState, VaultUnlockState};
+use super::vault_manager::VaultManagerState;
 use crate::auth::ipc::{self, VaultStatus, VaultStatusEvent, VaultStatusEventKind};
 use crate::config;
-use crate::inventory::{ConnectionProtocol, FolderId, InventoryHost, TreeFolder};
+use crate::inventory::{ConnectionProtocol, FolderId, InventoryHost, TreeFolder, build_inventory_tree, refresh_inventory_tree};
 use crate::log_debug;
 use crate::runtime::{ReloadNoticeToast, format_reload_notice};
 use crate::terminal::{TerminalGridPoint, TerminalSelection};
 use ratatui::layout::Rect;
 use std::collections::{HashMap, HashSet};
 use std::io;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
-use self::init::{AppStateInit, InventoryEventWatcher, VaultStatusEventWatcher, load_app_state_init, load_vault_status};
+use self::init::{
+    AppStateInit, InventoryEventWatcher, InventorySourceRefresh, SshConfigSync, VaultStatusEventWatcher, attach_to_vault_agent, load_app_state_init,
+    load_vault_status,
+};
 
 pub(crate) const HOST_PANEL_MIN_WIDTH: u16 = 15;
 pub(crate) const HOST_PANEL_MAX_WIDTH: u16 = 80;
@@ -40,6 +44,11 @@ pub(crate) struct AppState {
     pub(crate) host_search_index: Vec<HostSearchEntry>,
     pub(crate) host_tree_root: TreeFolder,
     pub(crate) inventory_load_error: Option<String>,
+    /// When cached output of a command inventory `source:` goes stale.
+    pub(crate) inventory_source_refresh_due: Option<SystemTime>,
+    /// Inventory rebuilds on the UI thread so far; a background source
+    /// refresh started before the latest one is out of date.
+    pub(crate) inventory_reloads: u64,
     pub(crate) visible_host_rows: Vec<HostTreeRow>,
     pub(crate) selected_host_row: usize,
     pub(crate) host_match_scores: HashMap<usize, i32>,
@@ -87,8 +96,11 @@ pub(crate) struct AppState {
     pub(crate) rdp_credentials: Option<RdpCredentialsState>,
     pub(crate) vault_unlock: Option<VaultUnlockState>,
     pub(crate) vault_status_modal: Option<VaultStatusModalState>,
+    pub(crate) vault_manager: Option<VaultManagerState>,
     pub(crate) vault_status: VaultStatus,
     pub(crate) quick_connect_default_ssh_logging: bool,
+    /// Sync `~/.ssh/config` whenever the inventory watcher reloads the inventory.
+    pub(crate) inventory_ssh_sync: bool,
     pub(crate) last_terminal_size: (u16, u16),
     pub(crate) reload_notice_toast: Option<ReloadNoticeToast>,
     pub(crate) ui_dirty: bool,
@@ -98,6 +110,8 @@ pub(crate) struct AppState {
     pub(crate) last_vault_status_refresh_at: Instant,
     vault_status_events: Option<VaultStatusEventWatcher>,
     inventory_events: Option<InventoryEventWatcher>,
+    inventory_source_refresh: Option<InventorySourceRefresh>,
+    ssh_config_sync: Option<SshConfigSync>,
 }
 
 impl AppState {
@@ -295,18 +309,28 @@ impl AppState {
     pub(crate) fn apply_vault_status_notifications(&mut self) {
         if let Some(paths) = self.vault_status_events.as_ref().and_then(VaultStatusEventWatcher::take_pending_paths) {
             match ipc::read_vault_status_event(&paths) {
-                Ok(event) => self.handle_vault_status_notification(event),
+                Ok(event) => {
+                    if event.kind == VaultStatusEventKind::Unlocked {
+                        attach_to_vault_agent();
+                    }
+                    self.handle_vault_status_notification(event)
+                }
                 Err(err) => log_debug!("Failed to read password vault status notification: {}", err),
             }
         }
     }
 
     pub(crate) fn apply_inventory_reload_notifications(&mut self) {
+        self.apply_finished_inventory_source_refresh();
+        self.apply_finished_ssh_config_sync();
         if !self.inventory_events.as_ref().is_some_and(InventoryEventWatcher::take_pending_reload) {
             return;
         }
 
         let inventory_path = self.host_tree_root.path.clone();
+        if self.inventory_ssh_sync {
+            self.start_ssh_config_sync();
+        }
         let notice = match self.reload_inventory_tree_from_path(&inventory_path) {
             Ok(()) => {
                 if let Some(watcher) = InventoryEventWatcher::new(&inventory_path) {
@@ -324,6 +348,115 @@ impl AppState {
         self.mark_ui_dirty();
     }
 
+    /// Sync `~/.ssh/config` in the background; a sync already running runs once more when done.
+    fn start_ssh_config_sync(&mut self) {
+        if let Some(sync) = self.ssh_config_sync.as_mut() {
+            sync.rerun = true;
+            return;
+        }
+        match SshConfigSync::spawn(self.host_tree_root.path.clone()) {
+            Ok(sync) => self.ssh_config_sync = Some(sync),
+            Err(err) => crate::log_error!("SSH config sync failed: {}", err),
+        }
+    }
+
+    /// Report a finished background SSH config sync. Inventory files it
+    /// changed are picked up by the inventory watcher.
+    fn apply_finished_ssh_config_sync(&mut self) {
+        let Some(result) = self.ssh_config_sync.as_ref().and_then(SshConfigSync::take_result) else {
+            return;
+        };
+        let rerun = self.ssh_config_sync.take().is_some_and(|sync| sync.rerun);
+        let notice = match result {
+            Ok(summary) if summary.deferred_removals > 0 => Some(format!(
+                "Synced with ~/.ssh/config; {} host removal(s) left for `cossh inventory sync`",
+                summary.deferred_removals
+            )),
+            Ok(summary) if summary.changed_anything() => Some("Inventory synced with ~/.ssh/config".to_string()),
+            Ok(_) => None,
+            Err(err) => {
+                crate::log_error!("SSH config sync failed: {}", err);
+                Some(format!("SSH config sync failed: {}", err))
+            }
+        };
+        if rerun {
+            self.start_ssh_config_sync();
+        }
+        if let Some(notice) = notice {
+            self.reload_notice_toast = Some(ReloadNoticeToast::new(format_reload_notice(&notice)));
+            self.mark_ui_dirty();
+        }
+    }
+
+    /// Run command inventory sources again instead of using their cached output.
+    pub(crate) fn refresh_inventory_sources(&mut self) {
+        let notice = if self.inventory_source_refresh.as_ref().is_some_and(|refresh| refresh.manual) {
+            "Inventory source refresh is already running".to_string()
+        } else {
+            match InventorySourceRefresh::spawn(self.host_tree_root.path.clone(), refresh_inventory_tree, true, self.inventory_reloads) {
+                Ok(refresh) => {
+                    self.inventory_source_refresh = Some(refresh);
+                    "Refreshing inventory sources...".to_string()
+                }
+                Err(err) => format!("Inventory source refresh failed: {}", err),
+            }
+        };
+
+        self.reload_notice_toast = Some(ReloadNoticeToast::new(format_reload_notice(&notice)));
+        self.mark_ui_dirty();
+    }
+
+    /// Reload the inventory in the background once cached command source output goes stale.
+    pub(crate) fn refresh_stale_inventory_sources(&mut self) {
+        if self.inventory_source_refresh.is_some() || self.inventory_source_refresh_due.is_none_or(|due| SystemTime::now() < due) {
+            return;
+        }
+
+        match InventorySourceRefresh::spawn(self.host_tree_root.path.clone(), build_inventory_tree, false, self.inventory_reloads) {
+            Ok(refresh) => self.inventory_source_refresh = Some(refresh),
+            Err(err) => {
+                crate::log_error!("Inventory source refresh failed: {}", err);
+                self.inventory_source_refresh_due = None;
+            }
+        }
+    }
+
+    /// Apply the tree built by a finished background source refresh.
+    fn apply_finished_inventory_source_refresh(&mut self) {
+        let Some(result) = self.inventory_source_refresh.as_ref().and_then(InventorySourceRefresh::take_result) else {
+            return;
+        };
+        let Some(refresh) = self.inventory_source_refresh.take() else {
+            return;
+        };
+        let manual = refresh.manual;
+        let applied = if refresh.started_after_reload == self.inventory_reloads {
+            self.apply_inventory_tree(result)
+        } else {
+            // The inventory was rebuilt after this refresh started, so its tree
+            // is out of date; rebuild from the source output it cached instead.
+            log_debug!("Dropping an inventory source refresh that started before the latest reload");
+            let inventory_path = self.host_tree_root.path.clone();
+            self.reload_inventory_tree_from_path(&inventory_path)
+        };
+        let notice = match applied {
+            Ok(()) => "Inventory sources refreshed".to_string(),
+            Err(err) => {
+                crate::log_error!("Inventory source refresh failed: {}", err);
+                if !manual {
+                    // Retry only on the next manual refresh or inventory change.
+                    self.inventory_source_refresh_due = None;
+                }
+                format!("Inventory source refresh failed: {}", err)
+            }
+        };
+
+        if manual {
+            self.reload_notice_toast = Some(ReloadNoticeToast::new(format_reload_notice(&notice)));
+            self.mark_ui_dirty();
+        }
+    }
+
     pub(crate) fn apply_config_reload_notifications(&mut self) {
         let mut latest_notice = config::take_reload_notices().into_iter().last();
 
@@ -388,6 +521,8 @@ impl AppState {
             host_search_index,
             host_tree_root: init.host_tree_root,
             inventory_load_error: init.inventory_load_error,
+            inventory_source_refresh_due: init.inventory_source_refresh_due,
+            inventory_reloads: 0,
             visible_host_rows: Vec::new(),
             selected_host_row: 0,
             host_match_scores: HashMap::new(),
@@ -435,8 +570,10 @@ impl AppState {
             rdp_credentials: None,
             vault_unlock: None,
             vault_status_modal: None,
+            vault_manager: None,
             vault_status: init.vault_status,
             quick_connect_default_ssh_logging: init.quick_connect_default_ssh_logging,
+            inventory_ssh_sync: init.inventory_ssh_sync,
             last_terminal_size: init.last_terminal_size,
             reload_notice_toast: None,
             ui_dirty: true,
@@ -446,6 +583,8 @@ impl AppState {
             last_vault_status_refresh_at: now,
             vault_status_events: init.vault_status_events,
             inventory_events: init.inventory_events,
+            inventory_source_refresh: None,
+            ssh_config_sync: None,
         };
 
         app.update_filtered_hosts();
@@ -455,7 +594,11 @@ impl AppState {
     /// Create a new AppState instance.
     pub(crate) fn new() -> io::Result<Self> {
         log_debug!("Initializing session manager");
-        Ok(Self::build_from_init(load_app_state_init()))
+        let app = Self::build_from_init(load_app_state_init());
+        if app.vault_status.unlocked {
+            attach_to_vault_agent();
+        }
+        Ok(app)
     }
 
     #[cfg(test)]
@@ -8,17 +8,19 @@ use crate::auth::{
 };
 use crate::config;
 use crate::inventory::{
-    FolderId, InventoryHost, InventoryTreeModel, InventoryWatchPlan, TreeFolder, build_inventory_watch_plan, get_default_inventory_path, load_inventory_tree,
-    should_reload_for_inventory_event,
+    FolderId, InventoryHost, InventorySyncSummary, InventoryTreeModel, InventoryWatchPlan, SyncSide, TreeFolder, build_inventory_watch_plan,
+    get_default_inventory_path, load_cached_inventory_tree, should_reload_for_inventory_event, sync_default_ssh_config,
 };
+use crate::tui::features::host_browser::search::load_inventory_tree_with;
 use crate::{log_debug, log_error, log_warn};
 use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
 use std::collections::HashSet;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
-use std::sync::mpsc::{self, Receiver};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 pub(super) const DEFAULT_TERMINAL_SIZE: (u16, u16) = (100, 30);
 const INVENTORY_WATCHER_DEBOUNCE: Duration = Duration::from_millis(250);
@@ -95,6 +97,74 @@ impl InventoryEventWatcher {
     }
 }
 
+/// Inventory tree rebuilt on a worker thread, so slow `source:` commands never
+/// block the event loop.
+pub(super) struct InventorySourceRefresh {
+    receiver: Receiver<io::Result<InventoryTreeModel>>,
+    /// Started from the refresh key; its outcome is reported in a toast.
+    pub(super) manual: bool,
+    /// `AppState::inventory_reloads` when the refresh started.
+    pub(super) started_after_reload: u64,
+}
+
+impl InventorySourceRefresh {
+    pub(super) fn spawn(
+        inventory_path: PathBuf,
+        build: fn(&Path) -> io::Result<InventoryTreeModel>,
+        manual: bool,
+        started_after_reload: u64,
+    ) -> io::Result<Self> {
+        let (result_tx, result_rx) = mpsc::channel();
+        thread::Builder::new().name("inventory-source-refresh".to_string()).spawn(move || {
+            let _ = result_tx.send(load_inventory_tree_with(&inventory_path, build));
+        })?;
+        Ok(Self {
+            receiver: result_rx,
+            manual,
+            started_after_reload,
+        })
+    }
+
+    /// Built tree once the worker has finished, or `None` while it is still running.
+    pub(super) fn take_result(&self) -> Option<io::Result<InventoryTreeModel>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(io::Error::other("inventory source refresh stopped unexpectedly"))),
+        }
+    }
+}
+
+/// `~/.ssh/config` sync run on a worker thread after an inventory reload.
+pub(super) struct SshConfigSync {
+    receiver: Receiver<io::Result<InventorySyncSummary>>,
+    /// Another reload arrived while this sync was running; sync once more.
+    pub(super) rerun: bool,
+}
+
+impl SshConfigSync {
+    pub(super) fn spawn(inventory_path: PathBuf) -> io::Result<Self> {
+        let (result_tx, result_rx) = mpsc::channel();
+        thread::Builder::new().name("inventory-ssh-sync".to_string()).spawn(move || {
+            // Inventory edits win over conflicting SSH config edits, since they triggered the reload.
+            let _ = result_tx.send(sync_default_ssh_config(&inventory_path, SyncSide::Inventory));
+        })?;
+        Ok(Self {
+            receiver: result_rx,
+            rerun: false,
+        })
+    }
+
+    /// Sync outcome once the worker has finished, or `None` while it is still running.
+    pub(super) fn take_result(&self) -> Option<io::Result<InventorySyncSummary>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(io::Error::other("SSH config sync stopped unexpectedly"))),
+        }
+    }
+}
+
 pub(super) struct VaultStatusEventWatcher {
     _watcher: RecommendedWatcher,
     receiver: Receiver<()>,
@@ -172,6 +242,7 @@ struct AppStateConfig {
     host_view_size_percent: u16,
     info_view_size_percent: u16,
     quick_connect_default_ssh_logging: bool,
+    inventory_ssh_sync: bool,
 }
 
 impl Default for AppStateConfig {
@@ -182,6 +253,7 @@ impl Default for AppStateConfig {
             host_view_size_percent: 25,
             info_view_size_percent: 40,
             quick_connect_default_ssh_logging: false,
+            inventory_ssh_sync: false,
         }
     }
 }
@@ -199,6 +271,7 @@ impl AppStateConfig {
                 session_config.host_info_visible = interactive.info_view;
                 session_config.host_view_size_percent = interactive.host_view_size;
                 session_config.info_view_size_percent = interactive.info_view_size;
+                session_config.inventory_ssh_sync = interactive.inventory_ssh_sync;
             }
 
             session_config
@@ -210,12 +283,14 @@ pub(super) struct AppStateInit {
     pub(super) hosts: Vec<InventoryHost>,
     pub(super) host_tree_root: TreeFolder,
     pub(super) inventory_load_error: Option<String>,
+    pub(super) inventory_source_refresh_due: Option<SystemTime>,
     pub(super) collapsed_folders: HashSet<FolderId>,
     pub(super) host_panel_width: u16,
     pub(super) host_panel_default_percent: u16,
     pub(super) host_info_height: u16,
     pub(super) host_info_visible: bool,
     pub(super) quick_connect_default_ssh_logging: bool,
+    pub(super) inventory_ssh_sync: bool,
     pub(super) last_terminal_size: (u16, u16),
     pub(super) vault_status: VaultStatus,
     pub(super) vault_status_events: Option<VaultStatusEventWatcher>,
@@ -266,8 +341,10 @@ fn fallback_host_tree_root() -> TreeFolder {
     }
 }
 
+/// Startup tree, built from cached `source:` output so the first frame never
+/// waits on a command; stale sources are refreshed in the background.
 fn load_host_tree_model() -> (InventoryTreeModel, Option<String>) {
-    match load_inventory_tree() {
+    match load_cached_inventory_tree() {
         Ok(tree_model) => (tree_model, None),
         Err(err) => {
             log_error!("Failed to load inventory hosts: {}", err);
@@ -275,6 +352,7 @@ fn load_host_tree_model() -> (InventoryTreeModel, Option<String>) {
                 InventoryTreeModel {
                     root: fallback_host_tree_root(),
                     hosts: Vec::new(),
+                    source_refresh_due: None,
                 },
                 Some(err.to_string()),
             )
@@ -308,6 +386,13 @@ pub(super) fn load_vault_status() -> VaultStatus {
         .unwrap_or_else(|_| VaultStatus::locked(fallback_vault_exists))
 }
 
+/// Register this session manager with the agent for the `tui_exit` lock trigger.
+pub(super) fn attach_to_vault_agent() {
+    if let Err(err) = agent::AgentClient::new().and_then(|client| client.attach_tui()) {
+        log_debug!("Failed to register session manager with password vault agent: {}", err);
+    }
+}
+
 pub(super) fn load_app_state_init() -> AppStateInit {
     let (tree_model, inventory_load_error) = load_host_tree_model();
     let session_config = AppStateConfig::load();
@@ -325,12 +410,14 @@ pub(super) fn load_app_state_init() -> AppStateInit {
         hosts: tree_model.hosts,
         host_tree_root,
         inventory_load_error,
+        inventory_source_refresh_due: tree_model.source_refresh_due,
         collapsed_folders,
         host_panel_width,
         host_panel_default_percent: session_config.host_view_size_percent,
         host_info_height: compute_host_info_height(term_height, session_config.info_view_size_percent),
         host_info_visible: session_config.host_info_visible,
         quick_connect_default_ssh_logging: session_config.quick_connect_default_ssh_logging,
+        inventory_ssh_sync: session_config.inventory_ssh_sync,
         last_terminal_size: (term_width, term_height),
         vault_status: load_vault_status(),
         vault_status_events: VaultStatusEventWatcher::new(),
@@ -344,6 +431,7 @@ pub(super) fn test_app_state_init() -> AppStateInit {
         hosts: Vec::new(),
         host_tree_root: fallback_host_tree_root(),
         inventory_load_error: None,
+        inventory_source_refresh_due: None,
         collapsed_folders: HashSet::new(),
         host_panel_width: 25,
         host_panel_default_percent: 25,
@@ -351,6 +439,7 @@ pub(super) fn test_app_state_init() -> AppStateInit {
         host_info_visible: true,
         vault_status: VaultStatus::locked(false),
         quick_connect_default_ssh_logging: false,
+        inventory_ssh_sync: false,
         last_terminal_size: DEFAULT_TERMINAL_SIZE,
         vault_status_events: None,
         inventory_events: None,
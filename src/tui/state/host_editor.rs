@@ -1,10 +1,10 @@
 //! Host editor state used by TUI create/edit/delete workflows.
 
-use crate::args::validate_vault_entry_name;
+use crate::args::validate_secret_reference;
 use crate::inventory::{ConnectionProtocol, EditableInventoryHost, FolderId, InventoryHost, SshOptionMap};
 use crate::tui::text_edit;
 use serde_yml::{Mapping, Value};
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -138,7 +138,7 @@ pub(crate) enum HostEditorMode {
     Edit,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub(crate) enum HostEditorField {
     Name,
     Description,
@@ -335,10 +335,31 @@ pub(crate) struct HostEditorState {
     pub(crate) profile_index: usize,
     pub(crate) vault_pass_options: Vec<String>,
     pub(crate) vault_pass_index: usize,
+    /// Values the edited host inherits from its template or folder
+    /// `defaults:`, shown beside empty fields and never written back to the host entry.
+    pub(crate) inherited: HashMap<HostEditorField, String>,
     drag_anchor: Option<(HostEditorField, usize)>,
     pub(crate) error: Option<String>,
 }
 
+/// Editor fields that can be inherited, with their canonical inventory keys.
+const INHERITABLE_FIELDS: [(HostEditorField, &str); 14] = [
+    (HostEditorField::Description, "description"),
+    (HostEditorField::Host, "host"),
+    (HostEditorField::User, "user"),
+    (HostEditorField::Port, "port"),
+    (HostEditorField::Profile, "profile"),
+    (HostEditorField::VaultPass, "vault_pass"),
+    (HostEditorField::IdentityFile, "identity_file"),
+    (HostEditorField::ProxyJump, "proxy_jump"),
+    (HostEditorField::ProxyCommand, "proxy_command"),
+    (HostEditorField::ForwardAgent, "forward_agent"),
+    (HostEditorField::LocalForward, "local_forward"),
+    (HostEditorField::RemoteForward, "remote_forward"),
+    (HostEditorField::RdpDomain, "rdp_domain"),
+    (HostEditorField::RdpArgs, "rdp_args"),
+];
+
 #[derive(Debug, Clone)]
 pub(crate) struct HostDeleteConfirmState {
     pub(crate) source_file: PathBuf,
@@ -374,7 +395,9 @@ impl HostEditorValidationError {
             Self::ProtocolRequired => "Protocol is required.".to_string(),
             Self::InvalidProtocol => "Protocol must be 'ssh' or 'rdp'.".to_string(),
             Self::InvalidPort => "Port must be a number between 1 and 65535.".to_string(),
-            Self::InvalidVaultPass => "Vault Pass is invalid: use only letters, numbers, '.', '_' or '-' .".to_string(),
+            Self::InvalidVaultPass => {
+                "Vault Pass is invalid: use a vault entry name (letters, numbers, '.', '_' or '-') or '<provider>:<locator>'.".to_string()
+            }
             Self::InvalidFolderPath => "Folder path must be '/' or use '/folder/subfolder/' format.".to_string(),
             Self::InvalidYamlField { field, detail } => {
                 format!("{field} must be valid YAML for this field ({detail}).")
@@ -420,6 +443,7 @@ impl HostEditorState {
             profile_index: 0,
             vault_pass_options,
             vault_pass_index: 0,
+            inherited: HashMap::new(),
             drag_anchor: None,
             error: None,
         };
@@ -429,6 +453,14 @@ impl HostEditorState {
     }
 
     pub(crate) fn new_edit(host: &InventoryHost, profile_options: Vec<String>, vault_pass_options: Vec<String>) -> Self {
+        let mut state = Self::from_host(host, profile_options, vault_pass_options);
+        state.restore_templated_values(host);
+        state.separate_inherited_values(host);
+        state
+    }
+
+    /// Editor prefilled with every effective value of `host`, inherited or not.
+    fn from_host(host: &InventoryHost, profile_options: Vec<String>, vault_pass_options: Vec<String>) -> Self {
         let profile_options = normalize_profile_options(profile_options);
         let vault_pass_options = normalize_cycle_options(vault_pass_options, false);
         let default_profile = initial_profile_value(host.profile.as_deref(), &profile_options);
@@ -468,6 +500,7 @@ impl HostEditorState {
             profile_index: 0,
             vault_pass_options,
             vault_pass_index: 0,
+            inherited: HashMap::new(),
             drag_anchor: None,
             error: None,
         };
@@ -476,8 +509,90 @@ impl HostEditorState {
         state
     }
 
+    /// Show the host's own `${...}` values as written rather than expanded, so
+    /// saving keeps the variables. `ssh_options` are restored while separating
+    /// inherited values.
+    fn restore_templated_values(&mut self, host: &InventoryHost) {
+        for (field, key) in [
+            (HostEditorField::Description, "description"),
+            (HostEditorField::Host, "host"),
+            (HostEditorField::User, "user"),
+            (HostEditorField::ProxyJump, "proxy_jump"),
+        ] {
+            if let Some([value]) = host.templated_values.get(key).map(Vec::as_slice)
+                && let Some(input) = self.text_field_mut(field)
+            {
+                *input = TextInput::new(value.clone());
+            }
+        }
+    }
+
+    /// Move values `host` inherits from its template or `defaults:` out of the
+    /// inputs so that saving keeps them inherited.
+    fn separate_inherited_values(&mut self, host: &InventoryHost) {
+        for (field, key) in INHERITABLE_FIELDS {
+            if !host.is_inherited(key) {
+                continue;
+            }
+            let value = match field {
+                HostEditorField::Profile => {
+                    let value = std::mem::replace(&mut self.profile, TextInput::new(initial_profile_value(None, &self.profile_options)));
+                    self.sync_profile_index_from_value();
+                    value.value
+                }
+                _ => self.text_field_mut(field).map(std::mem::take).unwrap_or_default().value,
+            };
+            self.inherited.insert(field, value);
+        }
+        if host.is_inherited("identities_only") {
+            let value = self.identities_only.take();
+            self.inherited
+                .insert(HostEditorField::IdentitiesOnly, if value == Some(true) { "yes" } else { "no" }.to_string());
+        }
+
+        let (inherited_options, mut explicit_options): (SshOptionMap, SshOptionMap) = host
+            .ssh
+            .extra_options
+            .clone()
+            .into_iter()
+            .partition(|(option, _)| host.is_inherited_ssh_option(option));
+        let mut restored = false;
+        for (option, values) in &mut explicit_options {
+            if let Some(raw_values) = host.templated_values.get(&format!("ssh_options.{option}"))
+                && raw_values.len() == values.len()
+            {
+                values.clone_from(raw_values);
+                restored = true;
+            }
+        }
+        if !inherited_options.is_empty() || restored {
+            self.ssh_options = TextInput::new(if explicit_options.is_empty() {
+                String::new()
+            } else {
+                serialize_yaml_inline(&explicit_options)
+            });
+        }
+        if !inherited_options.is_empty() {
+            self.inherited.insert(HostEditorField::SshOptions, serialize_yaml_inline(&inherited_options));
+        }
+    }
+
+    /// Inherited value to show beside `field` while the host does not override it.
+    pub(crate) fn inherited_hint(&self, field: HostEditorField) -> Option<&str> {
+        let inherited = self.inherited.get(&field)?;
+        let overridden = match field {
+            HostEditorField::IdentitiesOnly => self.identities_only.is_some(),
+            // Inherited options merge with the explicit ones, so they always apply.
+            HostEditorField::SshOptions => false,
+            HostEditorField::Profile => optional_trimmed_profile(&self.profile.value).is_some(),
+            _ => self.text_field(field).is_some_and(|input| !input.value.trim().is_empty()),
+        };
+        (!overridden).then_some(inherited.as_str())
+    }
+
     pub(crate) fn new_duplicate(host: &InventoryHost, profile_options: Vec<String>, vault_pass_options: Vec<String>) -> Self {
-        let mut state = Self::new_edit(host, profile_options, vault_pass_options);
+        // The copy may be saved into another folder, so inherited values are kept explicit.
+        let mut state = Self::from_host(host, profile_options, vault_pass_options);
         state.mode = HostEditorMode::Create;
         state.original_name = None;
         state.selected = HostEditorVisibleItem::Field(HostEditorField::Name);
@@ -1074,7 +1189,7 @@ impl HostEditorState {
         }
 
         let host = self.host.value.trim();
-        if host.is_empty() {
+        if host.is_empty() && !self.inherited.contains_key(&HostEditorField::Host) {
             return Err(HostEditorValidationError::HostRequired);
         }
 
@@ -1098,7 +1213,7 @@ impl HostEditorState {
 
         let vault_pass = optional_trimmed_string(&self.vault_pass.value);
         if let Some(vault_pass) = vault_pass.as_deref()
-            && !validate_vault_entry_name(vault_pass)
+            && !validate_secret_reference(vault_pass)
         {
             return Err(HostEditorValidationError::InvalidVaultPass);
         }
@@ -8,6 +8,7 @@ mod quick_connect;
 mod rdp_prompt;
 mod tabs;
 mod vault;
+mod vault_manager;
 
 pub(crate) use app::{AppState, ConnectRequest, HOST_PANEL_MAX_WIDTH, HOST_PANEL_MIN_WIDTH};
 pub(crate) use folder_picker::{FolderCreateState, FolderDeleteConfirmState, FolderPickerMode, FolderPickerRow, FolderPickerState, FolderRenameState};
@@ -22,3 +23,4 @@ pub(crate) use rdp_prompt::{
 };
 pub(crate) use tabs::{EditorTabId, EditorTabState, HostTab, TerminalSearchState, TerminalTabState};
 pub(crate) use vault::{VaultStatusModalState, VaultUnlockAction, VaultUnlockState};
+pub(crate) use vault_manager::{PendingVaultManagerChange, VaultManagerField, VaultManagerForm, VaultManagerFormKind, VaultManagerState};
@@ -0,0 +1,351 @@
+//! Vault manager modal state: entry list, inventory references, and edit forms.
+
+use crate::auth::provider::SecretReference;
+use crate::auth::secret::SensitiveBuffer;
+use crate::auth::vault::VaultEntrySummary;
+use crate::inventory::InventoryHost;
+use crate::tui::text_edit;
+use std::collections::HashSet;
+use std::io;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct VaultManagerEntry {
+    pub(crate) name: String,
+    pub(crate) updated_at: String,
+    pub(crate) ssh_key: bool,
+    /// Inventory host aliases whose `vault_pass` or `vault_key` names this entry.
+    pub(crate) referenced_by: Vec<String>,
+}
+
+/// Host whose `vault_pass` names an entry that is not in the vault.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct VaultMissingReference {
+    pub(crate) host_name: String,
+    pub(crate) entry_name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VaultManagerFormKind {
+    Add,
+    Edit,
+    Rename,
+    Remove,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VaultManagerField {
+    Name,
+    Secret,
+    MasterPassword,
+}
+
+pub(crate) struct VaultManagerForm {
+    pub(crate) kind: VaultManagerFormKind,
+    /// Entry the form acts on; `None` when adding.
+    pub(crate) source_name: Option<String>,
+    pub(crate) name: String,
+    pub(crate) name_cursor: usize,
+    pub(crate) secret: SensitiveBuffer,
+    pub(crate) secret_cursor: usize,
+    pub(crate) master_password: SensitiveBuffer,
+    pub(crate) master_password_cursor: usize,
+    pub(crate) focused: VaultManagerField,
+    pub(crate) error: Option<String>,
+    /// The vault agent was unlocked when the form opened, so the change can
+    /// be made without the master password.
+    pub(crate) vault_unlocked: bool,
+    /// Submitted change still running on its worker thread.
+    pub(crate) pending: Option<PendingVaultManagerChange>,
+}
+
+/// Entry change applied on a worker thread, so unlocking the vault with the
+/// master password never blocks the event loop.
+pub(crate) struct PendingVaultManagerChange {
+    receiver: Receiver<Result<(), String>>,
+    /// Entry to select once the change succeeds.
+    pub(crate) selected_name: Option<String>,
+    /// Status message shown once the change succeeds.
+    pub(crate) message: String,
+}
+
+impl PendingVaultManagerChange {
+    pub(crate) fn spawn(apply: impl FnOnce() -> Result<(), String> + Send + 'static, selected_name: Option<String>, message: String) -> io::Result<Self> {
+        let (result_tx, result_rx) = mpsc::channel();
+        thread::Builder::new().name("vault-manager-change".to_string()).spawn(move || {
+            let _ = result_tx.send(apply());
+        })?;
+        Ok(Self {
+            receiver: result_rx,
+            selected_name,
+            message,
+        })
+    }
+
+    /// Outcome once the worker has finished, or `None` while it is still running.
+    pub(crate) fn take_result(&self) -> Option<Result<(), String>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err("Vault entry change stopped unexpectedly.".to_string())),
+        }
+    }
+}
+
+impl VaultManagerForm {
+    pub(crate) fn new(kind: VaultManagerFormKind, source_name: Option<String>) -> Self {
+        let name = match kind {
+            VaultManagerFormKind::Rename => source_name.clone().unwrap_or_default(),
+            _ => String::new(),
+        };
+        let mut form = Self {
+            kind,
+            source_name,
+            name_cursor: text_edit::char_len(&name),
+            name,
+            secret: SensitiveBuffer::new(),
+            secret_cursor: 0,
+            master_password: SensitiveBuffer::new(),
+            master_password_cursor: 0,
+            focused: VaultManagerField::MasterPassword,
+            error: None,
+            vault_unlocked: false,
+            pending: None,
+        };
+        form.focused = form.fields()[0];
+        form
+    }
+
+    pub(crate) fn title(&self) -> &'static str {
+        match self.kind {
+            VaultManagerFormKind::Add => " Add Vault Entry ",
+            VaultManagerFormKind::Edit => " Replace Vault Entry Value ",
+            VaultManagerFormKind::Rename => " Rename Vault Entry ",
+            VaultManagerFormKind::Remove => " Remove Vault Entry ",
+        }
+    }
+
+    pub(crate) fn submit_hint(&self) -> &'static str {
+        match self.kind {
+            VaultManagerFormKind::Add | VaultManagerFormKind::Edit | VaultManagerFormKind::Rename => "[Enter] Save",
+            VaultManagerFormKind::Remove => "[Enter] Remove",
+        }
+    }
+
+    /// Fields shown for this form, in focus order.
+    pub(crate) fn fields(&self) -> &'static [VaultManagerField] {
+        match self.kind {
+            VaultManagerFormKind::Add => &[VaultManagerField::Name, VaultManagerField::Secret, VaultManagerField::MasterPassword],
+            VaultManagerFormKind::Edit => &[VaultManagerField::Secret, VaultManagerField::MasterPassword],
+            VaultManagerFormKind::Rename => &[VaultManagerField::Name, VaultManagerField::MasterPassword],
+            VaultManagerFormKind::Remove => &[VaultManagerField::MasterPassword],
+        }
+    }
+
+    pub(crate) fn focus_next(&mut self) {
+        let fields = self.fields();
+        let idx = fields.iter().position(|field| *field == self.focused).unwrap_or(0);
+        self.focused = fields[(idx + 1) % fields.len()];
+    }
+
+    pub(crate) fn focus_prev(&mut self) {
+        let fields = self.fields();
+        let idx = fields.iter().position(|field| *field == self.focused).unwrap_or(0);
+        self.focused = fields[(idx + fields.len() - 1) % fields.len()];
+    }
+
+    pub(crate) fn is_last_field_focused(&self) -> bool {
+        self.fields().last() == Some(&self.focused)
+    }
+
+    pub(crate) fn insert_char(&mut self, ch: char) {
+        match self.focused {
+            VaultManagerField::Name => {
+                let mut selection = None;
+                text_edit::insert_char(&mut self.name, &mut self.name_cursor, &mut selection, ch);
+            }
+            VaultManagerField::Secret => {
+                self.secret.insert_char(self.secret_cursor, ch);
+                self.secret_cursor += 1;
+            }
+            VaultManagerField::MasterPassword => {
+                self.master_password.insert_char(self.master_password_cursor, ch);
+                self.master_password_cursor += 1;
+            }
+        }
+        self.error = None;
+    }
+
+    pub(crate) fn backspace(&mut self) {
+        match self.focused {
+            VaultManagerField::Name => {
+                let mut selection = None;
+                text_edit::backspace(&mut self.name, &mut self.name_cursor, &mut selection);
+            }
+            VaultManagerField::Secret => self.secret_cursor = self.secret.backspace_char(self.secret_cursor),
+            VaultManagerField::MasterPassword => self.master_password_cursor = self.master_password.backspace_char(self.master_password_cursor),
+        }
+        self.error = None;
+    }
+
+    pub(crate) fn delete(&mut self) {
+        match self.focused {
+            VaultManagerField::Name => {
+                let mut selection = None;
+                text_edit::delete_char(&mut self.name, &mut self.name_cursor, &mut selection);
+            }
+            VaultManagerField::Secret => self.secret_cursor = self.secret.delete_char(self.secret_cursor),
+            VaultManagerField::MasterPassword => self.master_password_cursor = self.master_password.delete_char(self.master_password_cursor),
+        }
+        self.error = None;
+    }
+
+    pub(crate) fn move_cursor_left(&mut self) {
+        let cursor = self.focused_cursor_mut();
+        *cursor = cursor.saturating_sub(1);
+    }
+
+    pub(crate) fn move_cursor_right(&mut self) {
+        let len = self.focused_len();
+        let cursor = self.focused_cursor_mut();
+        *cursor = (*cursor + 1).min(len);
+    }
+
+    pub(crate) fn move_cursor_home(&mut self) {
+        *self.focused_cursor_mut() = 0;
+    }
+
+    pub(crate) fn move_cursor_end(&mut self) {
+        let len = self.focused_len();
+        *self.focused_cursor_mut() = len;
+    }
+
+    fn focused_len(&self) -> usize {
+        match self.focused {
+            VaultManagerField::Name => text_edit::char_len(&self.name),
+            VaultManagerField::Secret => self.secret.char_len(),
+            VaultManagerField::MasterPassword => self.master_password.char_len(),
+        }
+    }
+
+    fn focused_cursor_mut(&mut self) -> &mut usize {
+        match self.focused {
+            VaultManagerField::Name => &mut self.name_cursor,
+            VaultManagerField::Secret => &mut self.secret_cursor,
+            VaultManagerField::MasterPassword => &mut self.master_password_cursor,
+        }
+    }
+}
+
+impl Drop for VaultManagerForm {
+    fn drop(&mut self) {
+        self.secret.clear();
+        self.master_password.clear();
+    }
+}
+
+pub(crate) struct VaultManagerState {
+    pub(crate) entries: Vec<VaultManagerEntry>,
+    pub(crate) missing_references: Vec<VaultMissingReference>,
+    pub(crate) selected: usize,
+    pub(crate) form: Option<VaultManagerForm>,
+    pub(crate) load_error: Option<String>,
+    pub(crate) message: Option<String>,
+    pub(crate) message_is_error: bool,
+}
+
+impl VaultManagerState {
+    pub(crate) fn new(summaries: Vec<VaultEntrySummary>, hosts: &[InventoryHost]) -> Self {
+        let mut state = Self {
+            entries: Vec::new(),
+            missing_references: Vec::new(),
+            selected: 0,
+            form: None,
+            load_error: None,
+            message: None,
+            message_is_error: false,
+        };
+        state.reload(summaries, hosts);
+        state
+    }
+
+    /// Rebuild the entry list and reference index, keeping the selection on the same entry when possible.
+    pub(crate) fn reload(&mut self, summaries: Vec<VaultEntrySummary>, hosts: &[InventoryHost]) {
+        let selected_name = self.selected_entry().map(|entry| entry.name.clone());
+        let names: HashSet<&str> = summaries.iter().map(|summary| summary.name.as_str()).collect();
+
+        let mut missing_references = Vec::new();
+        for host in hosts {
+            if let Some(entry_name) = host.vault_pass.as_deref().and_then(vault_entry_reference)
+                && !names.contains(entry_name.as_str())
+            {
+                missing_references.push(VaultMissingReference {
+                    host_name: host.name.clone(),
+                    entry_name,
+                });
+            }
+        }
+
+        self.entries = summaries
+            .iter()
+            .map(|summary| VaultManagerEntry {
+                name: summary.name.clone(),
+                updated_at: summary.updated_at.clone(),
+                ssh_key: summary.ssh_key,
+                referenced_by: hosts
+                    .iter()
+                    .filter(|host| {
+                        [host.vault_pass.as_deref(), host.ssh.vault_key.as_deref()]
+                            .into_iter()
+                            .flatten()
+                            .any(|value| vault_entry_reference(value).as_deref() == Some(summary.name.as_str()))
+                    })
+                    .map(|host| host.name.clone())
+                    .collect(),
+            })
+            .collect();
+        self.missing_references = missing_references;
+        self.selected = selected_name
+            .and_then(|name| self.entries.iter().position(|entry| entry.name == name))
+            .unwrap_or(self.selected)
+            .min(self.entries.len().saturating_sub(1));
+    }
+
+    pub(crate) fn select_entry_named(&mut self, name: &str) {
+        if let Some(idx) = self.entries.iter().position(|entry| entry.name == name) {
+            self.selected = idx;
+        }
+    }
+
+    pub(crate) fn selected_entry(&self) -> Option<&VaultManagerEntry> {
+        self.entries.get(self.selected)
+    }
+
+    pub(crate) fn select_next(&mut self) {
+        if !self.entries.is_empty() {
+            self.selected = (self.selected + 1).min(self.entries.len() - 1);
+        }
+    }
+
+    pub(crate) fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub(crate) fn set_message(&mut self, message: String, is_error: bool) {
+        self.message = Some(message);
+        self.message_is_error = is_error;
+    }
+}
+
+/// Local vault entry named by a `vault_pass`/`vault_key` value; external provider references have none.
+fn vault_entry_reference(value: &str) -> Option<String> {
+    match SecretReference::parse(value.trim())? {
+        SecretReference::VaultEntry(name) => Some(name),
+        SecretReference::External(_) => None,
+    }
+}
+
+#[cfg(test)]
+#[path = "../../test/tui/state/vault_manager.rs"]
+mod tests;
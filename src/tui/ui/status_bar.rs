@@ -147,6 +147,14 @@ impl AppState {
                 Span::styled("^d", Style::default().fg(theme::ansi_red())),
                 Span::styled(":delete · ", Style::default().fg(theme::ansi_bright_black())),
             ]);
+        } else if let Some(host_idx) = self.selected_host_idx()
+            && self.hosts.get(host_idx).is_some_and(|host| host.dynamic_source.is_some())
+        {
+            // Hosts from an inventory source can only be copied into the YAML inventory.
+            right.extend([
+                Span::styled("^c", Style::default().fg(theme::ansi_cyan())),
+                Span::styled(":duplicate · ", Style::default().fg(theme::ansi_bright_black())),
+            ]);
         } else if self.selected_host_idx().is_some() {
             right.extend([
                 Span::styled("e", Style::default().fg(theme::ansi_yellow())),
@@ -169,6 +177,14 @@ impl AppState {
             Span::styled(":resize · ", Style::default().fg(theme::ansi_bright_black())),
             Span::styled("c", Style::default().fg(theme::ansi_cyan())),
             Span::styled(":collapse · ", Style::default().fg(theme::ansi_bright_black())),
+        ]);
+        if self.inventory_source_refresh_due.is_some() {
+            right.extend([
+                Span::styled("R", Style::default().fg(theme::ansi_cyan())),
+                Span::styled(":refresh · ", Style::default().fg(theme::ansi_bright_black())),
+            ]);
+        }
+        right.extend([
             Span::styled("i", Style::default().fg(theme::ansi_cyan())),
             Span::styled(":info · ", Style::default().fg(theme::ansi_bright_black())),
             Span::styled("/", Style::default().fg(theme::ansi_cyan())),